@@ -0,0 +1,145 @@
+//! The DataFusion execution plan which reads the data of a kernel [`Scan`].
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::TaskContext;
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, Partitioning, PlanProperties,
+    SendableRecordBatchStream,
+};
+use delta_kernel::scan::Scan;
+use delta_kernel::{Engine, Error};
+use url::Url;
+
+/// An [`ExecutionPlan`] with a single partition, which streams the data of a kernel [`Scan`]
+/// (without the rows deleted by deletion vectors), and stops once it has read `limit` rows.
+pub struct DeltaScanExec {
+    table_root: Url,
+    scan: Arc<Scan>,
+    engine: Arc<dyn Engine>,
+    schema: SchemaRef,
+    limit: Option<usize>,
+    properties: PlanProperties,
+}
+
+impl DeltaScanExec {
+    pub(crate) fn new(
+        table_root: Url,
+        scan: Arc<Scan>,
+        engine: Arc<dyn Engine>,
+        schema: SchemaRef,
+        limit: Option<usize>,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            Partitioning::UnknownPartitioning(1),
+            ExecutionMode::Bounded,
+        );
+        Self {
+            table_root,
+            scan,
+            engine,
+            schema,
+            limit,
+            properties,
+        }
+    }
+}
+
+impl fmt::Debug for DeltaScanExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeltaScanExec")
+            .field("table_root", &self.table_root)
+            .field("scan", &self.scan)
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DisplayAs for DeltaScanExec {
+    fn fmt_as(&self, _format: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DeltaScanExec: table={}", self.table_root)?;
+        if let Some(predicate) = self.scan.predicate() {
+            write!(f, ", predicate={predicate}")?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, ", limit={limit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionPlan for DeltaScanExec {
+    fn name(&self) -> &str {
+        "DeltaScanExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.is_empty() {
+            true => Ok(self),
+            false => Err(DataFusionError::Internal(
+                "DeltaScanExec has no children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "DeltaScanExec has a single partition, but partition {partition} was requested"
+            )));
+        }
+        // the kernel reads the data with blocking calls, so it runs on a blocking thread
+        let mut builder = RecordBatchReceiverStream::builder(self.schema.clone(), 2);
+        let tx = builder.tx();
+        let (scan, engine, mut remaining) = (self.scan.clone(), self.engine.clone(), self.limit);
+        builder.spawn_blocking(move || {
+            for result in scan.execute(engine).map_err(to_datafusion_error)? {
+                let batch = result
+                    .and_then(RecordBatch::try_from)
+                    .map_err(to_datafusion_error)?;
+                let batch = match remaining {
+                    Some(limit) if batch.num_rows() > limit => batch.slice(0, limit),
+                    _ => batch,
+                };
+                remaining = remaining.map(|limit| limit - batch.num_rows());
+                // stop reading once the stream is dropped, or has all the rows it needs
+                if tx.blocking_send(Ok(batch)).is_err() || remaining == Some(0) {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        Ok(builder.build())
+    }
+}
+
+pub(crate) fn to_datafusion_error(err: Error) -> DataFusionError {
+    DataFusionError::External(Box::new(err))
+}
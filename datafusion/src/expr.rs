@@ -0,0 +1,124 @@
+//! Translation of DataFusion filter expressions into kernel predicates.
+
+use datafusion::common::ScalarValue;
+use datafusion::logical_expr::{Between, BinaryExpr, Expr, Operator};
+use delta_kernel::expressions::{BinaryOperator, Expression, Scalar, UnaryOperator};
+
+/// Translate `expr` into a kernel expression, or `None` if it (or any of its children) has no
+/// kernel equivalent.
+pub fn to_kernel_expression(expr: &Expr) -> Option<Expression> {
+    let expr = match expr {
+        Expr::Column(column) => Expression::column([column.name.as_str()]),
+        Expr::Literal(value) => Expression::literal(to_kernel_scalar(value)?),
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let left = to_kernel_expression(left)?;
+            let right = to_kernel_expression(right)?;
+            match op {
+                Operator::And => left.and(right),
+                Operator::Or => left.or(right),
+                Operator::IsNotDistinctFrom => {
+                    Expression::unary(UnaryOperator::Not, left.distinct(right))
+                }
+                op => Expression::binary(to_kernel_operator(op)?, left, right),
+            }
+        }
+        Expr::Not(expr) => Expression::unary(UnaryOperator::Not, to_kernel_expression(expr)?),
+        Expr::IsNull(expr) => to_kernel_expression(expr)?.is_null(),
+        Expr::IsNotNull(expr) => to_kernel_expression(expr)?.is_not_null(),
+        Expr::Between(Between {
+            expr,
+            negated,
+            low,
+            high,
+        }) => {
+            let expr = to_kernel_expression(expr)?;
+            let between = expr
+                .clone()
+                .ge(to_kernel_expression(low)?)
+                .and(expr.le(to_kernel_expression(high)?));
+            match negated {
+                true => Expression::unary(UnaryOperator::Not, between),
+                false => between,
+            }
+        }
+        _ => return None,
+    };
+    Some(expr)
+}
+
+fn to_kernel_operator(op: &Operator) -> Option<BinaryOperator> {
+    let op = match op {
+        Operator::Eq => BinaryOperator::Equal,
+        Operator::NotEq => BinaryOperator::NotEqual,
+        Operator::Lt => BinaryOperator::LessThan,
+        Operator::LtEq => BinaryOperator::LessThanOrEqual,
+        Operator::Gt => BinaryOperator::GreaterThan,
+        Operator::GtEq => BinaryOperator::GreaterThanOrEqual,
+        Operator::IsDistinctFrom => BinaryOperator::Distinct,
+        Operator::Plus => BinaryOperator::Plus,
+        Operator::Minus => BinaryOperator::Minus,
+        Operator::Multiply => BinaryOperator::Multiply,
+        Operator::Divide => BinaryOperator::Divide,
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn to_kernel_scalar(value: &ScalarValue) -> Option<Scalar> {
+    let scalar = match value {
+        ScalarValue::Boolean(Some(v)) => Scalar::Boolean(*v),
+        ScalarValue::Int8(Some(v)) => Scalar::Byte(*v),
+        ScalarValue::Int16(Some(v)) => Scalar::Short(*v),
+        ScalarValue::Int32(Some(v)) => Scalar::Integer(*v),
+        ScalarValue::Int64(Some(v)) => Scalar::Long(*v),
+        ScalarValue::Float32(Some(v)) => Scalar::Float(*v),
+        ScalarValue::Float64(Some(v)) => Scalar::Double(*v),
+        ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)) => Scalar::String(v.clone()),
+        ScalarValue::Binary(Some(v)) | ScalarValue::LargeBinary(Some(v)) => {
+            Scalar::Binary(v.clone())
+        }
+        ScalarValue::Date32(Some(v)) => Scalar::Date(*v),
+        ScalarValue::TimestampMicrosecond(Some(v), Some(_)) => Scalar::Timestamp(*v),
+        ScalarValue::TimestampMicrosecond(Some(v), None) => Scalar::TimestampNtz(*v),
+        ScalarValue::Decimal128(Some(v), precision, scale) => {
+            Scalar::Decimal(*v, *precision, u8::try_from(*scale).ok()?)
+        }
+        // nulls compare as unknown, which can't be used to skip data
+        _ => return None,
+    };
+    Some(scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::logical_expr::{col, lit};
+    use delta_kernel::expressions::column_expr;
+
+    use super::*;
+
+    #[test]
+    fn test_to_kernel_expression() {
+        let expr = col("a")
+            .gt(lit(1i64))
+            .and(col("b").is_null().or(col("c").eq(lit("x"))));
+        let expected = column_expr!("a")
+            .gt(Scalar::Long(1))
+            .and(column_expr!("b").is_null().or(column_expr!("c").eq("x")));
+        assert_eq!(to_kernel_expression(&expr), Some(expected));
+
+        let expr = col("a").between(lit(1i32), lit(3i32));
+        let expected = column_expr!("a").ge(1).and(column_expr!("a").le(3));
+        assert_eq!(to_kernel_expression(&expr), Some(expected));
+    }
+
+    #[test]
+    fn test_untranslatable_expressions() {
+        // LIKE has no kernel equivalent, so neither has the conjunction
+        let expr = col("a").like(lit("x%")).and(col("b").eq(lit(1i32)));
+        assert_eq!(to_kernel_expression(&expr), None);
+        assert_eq!(
+            to_kernel_expression(&col("a").eq(lit(ScalarValue::Int32(None)))),
+            None
+        );
+    }
+}
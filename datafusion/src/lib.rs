@@ -0,0 +1,196 @@
+//! A [DataFusion](https://datafusion.apache.org) [`TableProvider`] for Delta tables.
+//!
+//! [`DeltaTableProvider`] reads a [`Snapshot`] of a table with delta kernel. The columns selected
+//! by a query are pushed down into the kernel [`Scan`](delta_kernel::scan::Scan), as are the
+//! filters which can be translated into kernel expressions (see [`to_kernel_expression`]), to skip
+//! the files which can't contain matching rows. Skipping is inexact, so DataFusion still applies
+//! the filters to the rows which are read.
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{Schema as ArrowSchema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use delta_kernel::expressions::Expression;
+use delta_kernel::snapshot::Snapshot;
+use delta_kernel::{DeltaResult, Engine};
+
+mod exec;
+mod expr;
+
+pub use exec::DeltaScanExec;
+pub use expr::to_kernel_expression;
+
+use exec::to_datafusion_error;
+
+/// A [`TableProvider`] which scans a [`Snapshot`] of a Delta table with the given engine.
+pub struct DeltaTableProvider {
+    snapshot: Arc<Snapshot>,
+    engine: Arc<dyn Engine>,
+    schema: SchemaRef,
+}
+
+impl DeltaTableProvider {
+    /// Create a provider for the snapshot, which fails if the schema of the table has no arrow
+    /// equivalent.
+    pub fn try_new(snapshot: Arc<Snapshot>, engine: Arc<dyn Engine>) -> DeltaResult<Self> {
+        let schema = ArrowSchema::try_from(snapshot.schema())?;
+        Ok(Self {
+            snapshot,
+            engine,
+            schema: Arc::new(schema),
+        })
+    }
+
+    /// The snapshot of the table which is scanned.
+    pub fn snapshot(&self) -> &Arc<Snapshot> {
+        &self.snapshot
+    }
+}
+
+impl fmt::Debug for DeltaTableProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeltaTableProvider")
+            .field("table_root", self.snapshot.table_root())
+            .field("version", &self.snapshot.version())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl TableProvider for DeltaTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        let pushdown = |filter: &&Expr| match to_kernel_expression(filter) {
+            Some(_) => TableProviderFilterPushDown::Inexact,
+            None => TableProviderFilterPushDown::Unsupported,
+        };
+        Ok(filters.iter().map(pushdown).collect())
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let (kernel_schema, schema) = match projection {
+            Some(indices) => {
+                let names: Vec<_> = indices
+                    .iter()
+                    .map(|i| self.schema.field(*i).name())
+                    .collect();
+                let kernel_schema = self
+                    .snapshot
+                    .schema()
+                    .project(&names)
+                    .map_err(to_datafusion_error)?;
+                (Some(kernel_schema), Arc::new(self.schema.project(indices)?))
+            }
+            None => (None, self.schema.clone()),
+        };
+        let predicates: Vec<_> = filters.iter().filter_map(to_kernel_expression).collect();
+        let predicate = match predicates.len() {
+            0 => None,
+            1 => predicates.into_iter().next(),
+            _ => Some(Expression::and_from(predicates)),
+        };
+        let scan = self
+            .snapshot
+            .clone()
+            .scan_builder()
+            .with_schema_opt(kernel_schema)
+            .with_predicate(predicate.map(Arc::new))
+            .build()
+            .map_err(to_datafusion_error)?;
+        Ok(Arc::new(DeltaScanExec::new(
+            self.snapshot.table_root().clone(),
+            Arc::new(scan),
+            self.engine.clone(),
+            schema,
+            limit,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use datafusion::arrow::array::{Array, Int32Array};
+    use datafusion::prelude::SessionContext;
+    use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use delta_kernel::engine::default::DefaultEngine;
+    use delta_kernel::Table;
+
+    use super::*;
+
+    fn provider() -> DeltaTableProvider {
+        let path = PathBuf::from("../kernel/tests/data/table-with-dv-small/");
+        let table =
+            Table::try_from_uri(std::fs::canonicalize(path).unwrap().to_str().unwrap()).unwrap();
+        let engine = DefaultEngine::try_new(
+            table.location(),
+            std::iter::empty::<(&str, &str)>(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+        .unwrap();
+        let engine: Arc<dyn Engine> = Arc::new(engine);
+        let snapshot = Arc::new(table.snapshot(engine.as_ref(), None).unwrap());
+        DeltaTableProvider::try_new(snapshot, engine).unwrap()
+    }
+
+    async fn query(sql: &str) -> Vec<i32> {
+        let ctx = SessionContext::new();
+        ctx.register_table("t", Arc::new(provider())).unwrap();
+        let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let values = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap();
+                (0..values.len())
+                    .map(|i| values.value(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan() {
+        // rows 0 and 9 are deleted by the deletion vector
+        assert_eq!(
+            query("SELECT value FROM t ORDER BY value").await,
+            (1..=8).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            query("SELECT value FROM t WHERE value > 5 ORDER BY value").await,
+            [6, 7, 8]
+        );
+        assert_eq!(query("SELECT value FROM t LIMIT 3").await.len(), 3);
+    }
+}
@@ -0,0 +1,221 @@
+//! `delta-inspect`: inspect the log and the snapshots of a Delta table with the kernel, e.g. to
+//! debug issues with a table, or to check what the kernel makes of it.
+//!
+//! ```text
+//! delta-inspect <TABLE> [--version <VERSION>] metadata
+//! delta-inspect <TABLE> [--version <VERSION>] files
+//! delta-inspect <TABLE> commit <VERSION>
+//! delta-inspect <TABLE> [--version <VERSION>] skipping "<PREDICATE>"
+//! delta-inspect <TABLE> verify
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand};
+use delta_kernel::expressions::sql::parse_sql_expression;
+use delta_kernel::scan::state::ScanFile;
+use delta_kernel::scan::ScanBuilder;
+use delta_kernel::snapshot::Snapshot;
+use delta_kernel::verify::VerificationReport;
+use delta_kernel::{DeltaResult, Engine, Error, ExpressionRef, Table, Version};
+
+#[derive(Parser)]
+// no `--version` flag of the tool, since that's the version of the table
+#[command(author, about, long_about = None)]
+struct Cli {
+    /// Path or URL of the table to inspect
+    table: String,
+
+    /// The version of the table to inspect (the latest version by default)
+    #[arg(short, long, global = true)]
+    version: Option<Version>,
+
+    /// Options for the object store of the table, e.g. `-o aws_region=us-east-1`
+    #[arg(short, long = "option", value_parser = parse_option, global = true)]
+    options: Vec<(String, String)>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the version, protocol, metadata and schema of the table
+    Metadata,
+    /// List the live files of the table, with their sizes, row counts and deletion vectors
+    Files,
+    /// Pretty-print the actions of the commit of a version of the table
+    Commit {
+        /// The version of the commit
+        commit_version: Version,
+    },
+    /// Show which files a predicate (in Spark SQL, e.g. `id > 100L AND date = '2024-01-01'`)
+    /// would skip by their statistics and partition values
+    Skipping {
+        /// The predicate of the scan
+        predicate: String,
+    },
+    /// Check that the files of the latest version of the table exist, and that its checkpoint
+    /// matches its commits
+    Verify,
+}
+
+fn parse_option(option: &str) -> Result<(String, String), String> {
+    match option.split_once('=') {
+        Some((key, value)) => Ok((key.to_string(), value.to_string())),
+        None => Err(format!("expected `key=value`, found `{option}`")),
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    match try_main() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn try_main() -> DeltaResult<()> {
+    let cli = Cli::parse();
+    let location = Table::try_from_uri(&cli.table)?.location().clone();
+    let (table, engine) = Table::builder(location)
+        .with_storage_options(cli.options)
+        .build()?;
+    let engine = engine.as_ref();
+
+    match cli.command {
+        Command::Metadata => print_metadata(&table.snapshot(engine, cli.version)?),
+        Command::Files => {
+            let snapshot = Arc::new(table.snapshot(engine, cli.version)?);
+            print_files(&scan_files(snapshot, engine, None)?);
+        }
+        Command::Commit { commit_version } => print_commit(&table, engine, commit_version)?,
+        Command::Skipping { predicate } => {
+            let predicate = Arc::new(parse_sql_expression(&predicate)?);
+            let snapshot = Arc::new(table.snapshot(engine, cli.version)?);
+            print_skipping(snapshot, engine, predicate)?;
+        }
+        Command::Verify => print_verification(&table.verify(engine)?)?,
+    }
+    Ok(())
+}
+
+fn print_metadata(snapshot: &Snapshot) {
+    println!("Table:    {}", snapshot.table_root());
+    println!("Version:  {}", snapshot.version());
+    println!("\n{:#?}", snapshot.protocol());
+    println!("\n{:#?}", snapshot.metadata());
+    println!("\n{:#?}", snapshot.schema());
+}
+
+fn scan_files(
+    snapshot: Arc<Snapshot>,
+    engine: &dyn Engine,
+    predicate: Option<ExpressionRef>,
+) -> DeltaResult<Vec<ScanFile>> {
+    let scan = ScanBuilder::new(snapshot)
+        .with_predicate(predicate)
+        .build()?;
+    let mut files: Vec<_> = scan.scan_files(engine)?.collect::<DeltaResult<_>>()?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn print_files(files: &[ScanFile]) {
+    println!("path\tsize\tmodification_time\tnum_records\tdeleted_rows\tpartition_values");
+    for file in files {
+        let num_records = file.stats.as_ref().map(|stats| stats.num_records);
+        let deleted_rows = file
+            .dv_info
+            .deletion_vector_descriptor()
+            .map(|dv| dv.cardinality);
+        // sorted, so that the output is stable
+        let partition_values: BTreeMap<_, _> = file.partition_values.iter().collect();
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{partition_values:?}",
+            file.path,
+            file.size,
+            or_unknown(file.modification_time),
+            or_unknown(num_records),
+            or_unknown(deleted_rows),
+        );
+    }
+    let bytes: i64 = files.iter().map(|file| file.size).sum();
+    let num_records: Option<u64> = files.iter().map(ScanFile::exact_num_records).sum();
+    println!(
+        "\n{} files, {bytes} bytes, {} rows",
+        files.len(),
+        or_unknown(num_records)
+    );
+}
+
+fn or_unknown(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+fn print_commit(table: &Table, engine: &dyn Engine, version: Version) -> DeltaResult<()> {
+    let commit = table
+        .location()
+        .join(&format!("_delta_log/{version:020}.json"))?;
+    let mut contents = engine
+        .get_file_system_client()
+        .read_files(vec![(commit, None)])?;
+    let contents = contents
+        .next()
+        .ok_or_else(|| Error::generic(format!("No commit for version {version}")))??;
+    let contents = std::str::from_utf8(&contents)
+        .map_err(|e| Error::generic(format!("Commit {version} is not valid UTF-8: {e}")))?;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let action: serde_json::Value = serde_json::from_str(line)?;
+        println!("{}", serde_json::to_string_pretty(&action)?);
+    }
+    Ok(())
+}
+
+fn print_skipping(
+    snapshot: Arc<Snapshot>,
+    engine: &dyn Engine,
+    predicate: ExpressionRef,
+) -> DeltaResult<()> {
+    let all_files = scan_files(snapshot.clone(), engine, None)?;
+    let kept_files = scan_files(snapshot, engine, Some(predicate.clone()))?;
+    let kept: HashSet<_> = kept_files.iter().map(|file| &file.path).collect();
+    println!("Predicate: {predicate}\n");
+    for file in &all_files {
+        let status = match kept.contains(&file.path) {
+            true => "kept",
+            false => "skipped",
+        };
+        println!("{status}\t{}", file.path);
+    }
+    println!(
+        "\n{} of {} files skipped",
+        all_files.len() - kept_files.len(),
+        all_files.len()
+    );
+    Ok(())
+}
+
+fn print_verification(report: &VerificationReport) -> DeltaResult<()> {
+    println!("Version:  {}", report.version);
+    println!("Files:    {}", report.files_checked);
+    for file in &report.missing_files {
+        println!("missing file\t{file}");
+    }
+    for dv in &report.missing_deletion_vectors {
+        println!("missing deletion vector\t{dv}");
+    }
+    if report.invalid_last_checkpoint {
+        println!("invalid _last_checkpoint file");
+    }
+    println!("Checkpoint: {:?}", report.checkpoint);
+    match report.is_consistent() {
+        true => Ok(()),
+        false => Err(Error::generic("The table is not consistent")),
+    }
+}
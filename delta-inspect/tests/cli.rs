@@ -0,0 +1,81 @@
+//! Smoke tests of the subcommands of `delta-inspect`, against the test tables of the kernel.
+
+use std::process::{Command, Output};
+
+fn inspect(table: &str, args: &[&str]) -> Output {
+    let table = format!(
+        "{}/../kernel/tests/data/{table}/",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    Command::new(env!("CARGO_BIN_EXE_delta-inspect"))
+        .arg(table)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// The output of a successful run of `delta-inspect`
+fn inspect_ok(table: &str, args: &[&str]) -> String {
+    let output = inspect(table, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{args:?} failed: {stderr}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn metadata() {
+    let output = inspect_ok("table-with-dv-small", &["metadata"]);
+    assert!(output.contains("Version:  1"), "{output}");
+    assert!(output.contains("deletionVectors"), "{output}");
+
+    let output = inspect_ok("basic_partitioned", &["--version", "0", "metadata"]);
+    assert!(output.contains("Version:  0"), "{output}");
+}
+
+#[test]
+fn files() {
+    let output = inspect_ok("table-with-dv-small", &["files"]);
+    let file = "part-00000-fae5310a-a37d-4e51-827b-c3d5516560ca-c000.snappy.parquet";
+    // the file has 10 rows, 2 of which are deleted, so the row count of the table isn't known
+    assert!(
+        output.contains(&format!("{file}\t635\t1677811178336\t10\t2\t{{}}")),
+        "{output}"
+    );
+    assert!(
+        output.ends_with("\n1 files, 635 bytes, - rows\n"),
+        "{output}"
+    );
+}
+
+#[test]
+fn commit() {
+    let output = inspect_ok("table-with-dv-small", &["commit", "0"]);
+    assert!(output.contains("\"minReaderVersion\": 3"), "{output}");
+
+    let output = inspect("table-with-dv-small", &["commit", "7"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn skipping() {
+    let output = inspect_ok("basic_partitioned", &["skipping", "letter = 'b'"]);
+    assert!(output.contains("\nkept\tletter=b/"), "{output}");
+    assert!(output.contains("\nskipped\tletter=a/"), "{output}");
+    // the file of the null partition is kept
+    assert!(output.ends_with("\n4 of 6 files skipped\n"), "{output}");
+
+    let output = inspect("basic_partitioned", &["skipping", "letter ="]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn verify() {
+    let output = inspect_ok("table-with-dv-small", &["verify"]);
+    assert!(output.contains("Files:    1"), "{output}");
+
+    // the data file of this table is missing
+    let output = inspect("with_checkpoint_no_last_checkpoint", &["verify"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("missing file\t"), "{stdout}");
+}
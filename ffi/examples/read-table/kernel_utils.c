@@ -20,7 +20,8 @@ void print_diag(char* fmt, ...)
 void print_error(const char* msg, Error* err)
 {
   printf("[ERROR] %s\n", msg);
-  printf("  Kernel Code: %i\n", err->etype.etype);
+  KernelStringSlice description = kernel_error_description(err->etype.etype);
+  printf("  Kernel Code: %i (%.*s)\n", err->etype.etype, (int)description.len, description.ptr);
   printf("  Kernel Msg: %s\n", err->msg);
 }
 
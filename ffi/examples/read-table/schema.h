@@ -114,6 +114,18 @@ void visit_struct(
   SchemaItem* struct_item = add_to_list(&builder->lists[sibling_list_id], name_ptr, "struct");
   struct_item->children = child_list_id;
 }
+void visit_variant(
+  void* data,
+  uintptr_t sibling_list_id,
+  struct KernelStringSlice name,
+  uintptr_t child_list_id)
+{
+  SchemaBuilder* builder = data;
+  char* name_ptr = allocate_string(name);
+  PRINT_CHILD_VISIT("variant", name_ptr, sibling_list_id, "Children", child_list_id);
+  SchemaItem* variant_item = add_to_list(&builder->lists[sibling_list_id], name_ptr, "variant");
+  variant_item->children = child_list_id;
+}
 void visit_array(
   void* data,
   uintptr_t sibling_list_id,
@@ -237,6 +249,7 @@ void print_schema(SharedSnapshot* snapshot)
     .visit_date = visit_date,
     .visit_timestamp = visit_timestamp,
     .visit_timestamp_ntz = visit_timestamp_ntz,
+    .visit_variant = visit_variant,
   };
   uintptr_t schema_list_id = visit_schema(snapshot, &visitor);
 #ifdef VERBOSE
@@ -36,6 +36,7 @@ enum OpType {
   Distinct,
   In,
   NotIn,
+  NullIf,
 };
 enum LitType {
   Integer,
@@ -59,6 +60,7 @@ enum ExpressionType { BinOp, Variadic, Literal, Unary, Column };
 enum VariadicType {
   And,
   Or,
+  Coalesce,
   StructExpression,
 };
 enum UnaryType { Not, IsNull };
@@ -168,6 +170,7 @@ DEFINE_BINOP(visit_expr_ne, NotEqual)
 DEFINE_BINOP(visit_expr_distinct, Distinct)
 DEFINE_BINOP(visit_expr_in, In)
 DEFINE_BINOP(visit_expr_not_in, NotIn)
+DEFINE_BINOP(visit_expr_null_if, NullIf)
 #undef DEFINE_BINOP
 
 /*************************************************************
@@ -263,6 +266,7 @@ void visit_expr_variadic(void* data,
 }
 DEFINE_VARIADIC(visit_expr_and, And)
 DEFINE_VARIADIC(visit_expr_or, Or)
+DEFINE_VARIADIC(visit_expr_coalesce, Coalesce)
 DEFINE_VARIADIC(visit_expr_struct_expr, StructExpression)
 #undef DEFINE_VARIADIC
 
@@ -357,6 +361,8 @@ ExpressionItemList construct_predicate(SharedExpression* predicate) {
     .visit_divide = visit_expr_divide,
     .visit_column = visit_expr_column,
     .visit_struct_expr = visit_expr_struct_expr,
+    .visit_coalesce = visit_expr_coalesce,
+    .visit_null_if = visit_expr_null_if,
   };
   uintptr_t top_level_id = visit_expression(&predicate, &visitor);
   ExpressionItemList top_level_expr = data.lists[top_level_id];
@@ -75,6 +75,9 @@ void print_tree_helper(ExpressionItem ref, int depth) {
         case Distinct:
           printf("Distinct\n");
           break;
+        case NullIf:
+          printf("NullIf\n");
+          break;
       }
       print_expression_item_list(op->exprs, depth + 1);
       break;
@@ -89,6 +92,9 @@ void print_tree_helper(ExpressionItem ref, int depth) {
         case Or:
           printf("Or\n");
           break;
+        case Coalesce:
+          printf("Coalesce\n");
+          break;
         case StructExpression:
           printf("StructExpression\n");
           break;
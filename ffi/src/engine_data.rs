@@ -71,6 +71,15 @@ pub unsafe extern "C" fn get_raw_arrow_data(
 // TODO: This method leaks the returned pointer memory. How will the engine free it?
 #[cfg(feature = "default-engine")]
 fn get_raw_arrow_data_impl(data: Box<dyn EngineData>) -> DeltaResult<*mut ArrowFFIData> {
+    let ret_data = Box::new(to_arrow_ffi_data(data)?);
+    Ok(Box::leak(ret_data))
+}
+
+// Export `data`, which must be [`ArrowEngineData`], through the arrow C Data Interface
+//
+// [`ArrowEngineData`]: delta_kernel::engine::arrow_data::ArrowEngineData
+#[cfg(feature = "default-engine")]
+pub(crate) fn to_arrow_ffi_data(data: Box<dyn EngineData>) -> DeltaResult<ArrowFFIData> {
     let record_batch: arrow_array::RecordBatch = data
         .into_any()
         .downcast::<delta_kernel::engine::arrow_data::ArrowEngineData>()
@@ -81,6 +90,5 @@ fn get_raw_arrow_data_impl(data: Box<dyn EngineData>) -> DeltaResult<*mut ArrowF
     // these call `clone`. is there a way to not copy anything and what exactly are they cloning?
     let array = arrow_data::ffi::FFI_ArrowArray::new(&array_data);
     let schema = arrow_schema::ffi::FFI_ArrowSchema::try_from(array_data.data_type())?;
-    let ret_data = Box::new(ArrowFFIData { array, schema });
-    Ok(Box::leak(ret_data))
+    Ok(ArrowFFIData { array, schema })
 }
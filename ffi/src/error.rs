@@ -2,55 +2,59 @@ use delta_kernel::{DeltaResult, Error};
 
 use crate::{kernel_string_slice, ExternEngine, KernelStringSlice};
 
+/// The kind of an error returned by kernel. The values of the codes are stable: codes are never
+/// renumbered or reused (even for errors which can't occur with the enabled features), and new
+/// codes are only ever added at the end.
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KernelError {
-    UnknownError, // catch-all for unrecognized kernel Error types
-    FFIError,     // errors encountered in the code layer that supports FFI
-    #[cfg(any(feature = "default-engine", feature = "sync-engine"))]
-    ArrowError,
-    EngineDataTypeError,
-    ExtractError,
-    GenericError,
-    IOErrorError,
-    #[cfg(any(feature = "default-engine", feature = "sync-engine"))]
-    ParquetError,
-    #[cfg(feature = "default-engine")]
-    ObjectStoreError,
-    #[cfg(feature = "default-engine")]
-    ObjectStorePathError,
-    #[cfg(feature = "default-engine")]
-    ReqwestError,
-    FileNotFoundError,
-    MissingColumnError,
-    UnexpectedColumnTypeError,
-    MissingDataError,
-    MissingVersionError,
-    DeletionVectorError,
-    InvalidUrlError,
-    MalformedJsonError,
-    MissingMetadataError,
-    MissingProtocolError,
-    InvalidProtocolError,
-    MissingMetadataAndProtocolError,
-    ParseError,
-    JoinFailureError,
-    Utf8Error,
-    ParseIntError,
-    InvalidColumnMappingModeError,
-    InvalidTableLocationError,
-    InvalidDecimalError,
-    InvalidStructDataError,
-    InternalError,
-    InvalidExpression,
-    InvalidLogPath,
-    InvalidCommitInfo,
-    FileAlreadyExists,
-    MissingCommitInfo,
-    UnsupportedError,
-    ParseIntervalError,
-    ChangeDataFeedUnsupported,
-    ChangeDataFeedIncompatibleSchema,
+    UnknownError = 0, // catch-all for unrecognized kernel Error types
+    FFIError = 1,     // errors encountered in the code layer that supports FFI
+    ArrowError = 2,
+    EngineDataTypeError = 3,
+    ExtractError = 4,
+    GenericError = 5,
+    IOErrorError = 6,
+    ParquetError = 7,
+    ObjectStoreError = 8,
+    ObjectStorePathError = 9,
+    ReqwestError = 10,
+    FileNotFoundError = 11,
+    MissingColumnError = 12,
+    UnexpectedColumnTypeError = 13,
+    MissingDataError = 14,
+    MissingVersionError = 15,
+    DeletionVectorError = 16,
+    InvalidUrlError = 17,
+    MalformedJsonError = 18,
+    MissingMetadataError = 19,
+    MissingProtocolError = 20,
+    InvalidProtocolError = 21,
+    MissingMetadataAndProtocolError = 22,
+    ParseError = 23,
+    JoinFailureError = 24,
+    Utf8Error = 25,
+    ParseIntError = 26,
+    InvalidColumnMappingModeError = 27,
+    InvalidTableLocationError = 28,
+    InvalidDecimalError = 29,
+    InvalidStructDataError = 30,
+    InternalError = 31,
+    InvalidExpression = 32,
+    InvalidLogPath = 33,
+    InvalidCommitInfo = 34,
+    FileAlreadyExists = 35,
+    MissingCommitInfo = 36,
+    UnsupportedError = 37,
+    ParseIntervalError = 38,
+    ChangeDataFeedUnsupported = 39,
+    ChangeDataFeedIncompatibleSchema = 40,
+    MemoryLimitExceeded = 41,
+    UnsupportedTableFeatureError = 42,
+    InvalidHandleError = 43, // an invalid (or released) registered handle id, see `registry`
+    VersionBeyondLatestError = 44,
+    VersionTruncatedError = 45,
+    InvalidCheckpointError = 46,
 }
 
 impl From<Error> for KernelError {
@@ -97,6 +101,7 @@ impl From<Error> for KernelError {
                 source,
                 backtrace: _,
             } => Self::from(*source),
+            Error::WithContext { source, .. } => Self::from(*source),
             Error::InvalidExpressionEvaluation(_) => KernelError::InvalidExpression,
             Error::InvalidLogPath(_) => KernelError::InvalidLogPath,
             Error::InvalidCommitInfo(_) => KernelError::InvalidCommitInfo,
@@ -108,10 +113,81 @@ impl From<Error> for KernelError {
             Error::ChangeDataFeedIncompatibleSchema(_, _) => {
                 KernelError::ChangeDataFeedIncompatibleSchema
             }
+            Error::MemoryLimitExceeded(_) => KernelError::MemoryLimitExceeded,
+            Error::UnsupportedTableFeature(_) => KernelError::UnsupportedTableFeatureError,
+            Error::VersionBeyondLatest { .. } => KernelError::VersionBeyondLatestError,
+            Error::VersionTruncated { .. } => KernelError::VersionTruncatedError,
+            Error::InvalidCheckpoint(_) => KernelError::InvalidCheckpointError,
         }
     }
 }
 
+impl KernelError {
+    /// A short, static description of the kind of error (the message of each error describes the
+    /// error itself).
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::UnknownError => "An unrecognized kernel error",
+            Self::FFIError => "An error in the code layer that supports FFI",
+            Self::ArrowError => "An error from arrow",
+            Self::EngineDataTypeError => "Engine data of an unexpected type",
+            Self::ExtractError => "Data could not be extracted from engine data",
+            Self::GenericError => "A generic error",
+            Self::IOErrorError => "An I/O error",
+            Self::ParquetError => "An error reading or writing parquet",
+            Self::ObjectStoreError => "An error from the object store",
+            Self::ObjectStorePathError => "An invalid object store path",
+            Self::ReqwestError => "An HTTP error",
+            Self::FileNotFoundError => "A file was not found",
+            Self::MissingColumnError => "A column is missing",
+            Self::UnexpectedColumnTypeError => "A column has an unexpected type",
+            Self::MissingDataError => "Data is missing",
+            Self::MissingVersionError => "The table version is missing",
+            Self::DeletionVectorError => "An error reading a deletion vector",
+            Self::InvalidUrlError => "An invalid URL",
+            Self::MalformedJsonError => "Malformed JSON",
+            Self::MissingMetadataError => "The table metadata is missing",
+            Self::MissingProtocolError => "The table protocol is missing",
+            Self::InvalidProtocolError => "The table protocol is invalid",
+            Self::MissingMetadataAndProtocolError => "The table metadata and protocol are missing",
+            Self::ParseError => "A value could not be parsed",
+            Self::JoinFailureError => "A thread could not be joined",
+            Self::Utf8Error => "Invalid UTF-8",
+            Self::ParseIntError => "An integer could not be parsed",
+            Self::InvalidColumnMappingModeError => "An invalid column mapping mode",
+            Self::InvalidTableLocationError => "An invalid table location",
+            Self::InvalidDecimalError => "An invalid decimal",
+            Self::InvalidStructDataError => "Invalid struct data",
+            Self::InternalError => "An internal kernel error",
+            Self::InvalidExpression => "An invalid expression",
+            Self::InvalidLogPath => "An invalid log path",
+            Self::InvalidCommitInfo => "Invalid commit info",
+            Self::FileAlreadyExists => "The file already exists",
+            Self::MissingCommitInfo => "The commit info is missing",
+            Self::UnsupportedError => "Unsupported functionality",
+            Self::ParseIntervalError => "An interval could not be parsed",
+            Self::ChangeDataFeedUnsupported => "Change data feed is unsupported for the table",
+            Self::ChangeDataFeedIncompatibleSchema => {
+                "Change data feed encountered an incompatible schema"
+            }
+            Self::MemoryLimitExceeded => "The memory limit was exceeded",
+            Self::UnsupportedTableFeatureError => "The table uses an unsupported table feature",
+            Self::InvalidHandleError => "An invalid or released handle",
+            Self::VersionBeyondLatestError => "The version is beyond the latest table version",
+            Self::VersionTruncatedError => "The version is before the earliest table version",
+            Self::InvalidCheckpointError => "A checkpoint of the table is invalid",
+        }
+    }
+}
+
+/// Get a static description of the kind of error with the given code, e.g. to display next to
+/// the message of an error. The returned slice is valid for the lifetime of the program.
+#[no_mangle]
+pub extern "C" fn kernel_error_description(etype: KernelError) -> KernelStringSlice {
+    let description = etype.description();
+    kernel_string_slice!(description)
+}
+
 /// An error that can be returned to the engine. Engines that wish to associate additional
 /// information can define and use any type that is [pointer
 /// interconvertible](https://en.cppreference.com/w/cpp/language/static_cast#pointer-interconvertible)
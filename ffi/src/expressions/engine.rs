@@ -7,7 +7,8 @@ use crate::{
     ReferenceSet, TryFromStringSlice,
 };
 use delta_kernel::{
-    expressions::{BinaryOperator, ColumnName, Expression, UnaryOperator},
+    expressions::{BinaryOperator, ColumnName, Expression, Scalar, UnaryOperator},
+    schema::PrimitiveType,
     DeltaResult,
 };
 
@@ -88,6 +89,22 @@ pub extern "C" fn visit_expression_and(
     wrap_expression(state, result)
 }
 
+// The EngineIterator is not thread safe, not reentrant, not owned by callee, not freed by callee.
+#[no_mangle]
+pub extern "C" fn visit_expression_or(
+    state: &mut KernelExpressionVisitorState,
+    children: &mut EngineIterator,
+) -> usize {
+    let children: Vec<_> = children
+        .map(|child| unwrap_kernel_expression(state, child as usize))
+        .collect();
+    // dropping an invalid child would make the OR stricter, and skip files that may match it
+    match children.into_iter().collect::<Option<Vec<_>>>() {
+        Some(children) => wrap_expression(state, Expression::or_from(children)),
+        None => 0, // invalid child => invalid node
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn visit_expression_lt(
     state: &mut KernelExpressionVisitorState,
@@ -133,6 +150,26 @@ pub extern "C" fn visit_expression_eq(
     visit_expression_binary(state, BinaryOperator::Equal, a, b)
 }
 
+#[no_mangle]
+pub extern "C" fn visit_expression_ne(
+    state: &mut KernelExpressionVisitorState,
+    a: usize,
+    b: usize,
+) -> usize {
+    visit_expression_binary(state, BinaryOperator::NotEqual, a, b)
+}
+
+/// Visit `a IS DISTINCT FROM b`, i.e. `a != b` where NULL is distinct from any non-NULL value (and
+/// not from NULL).
+#[no_mangle]
+pub extern "C" fn visit_expression_distinct(
+    state: &mut KernelExpressionVisitorState,
+    a: usize,
+    b: usize,
+) -> usize {
+    visit_expression_binary(state, BinaryOperator::Distinct, a, b)
+}
+
 /// # Safety
 /// The string slice must be valid
 #[no_mangle]
@@ -169,6 +206,15 @@ pub extern "C" fn visit_expression_is_null(
     visit_expression_unary(state, UnaryOperator::IsNull, inner_expr)
 }
 
+#[no_mangle]
+pub extern "C" fn visit_expression_is_not_null(
+    state: &mut KernelExpressionVisitorState,
+    inner_expr: usize,
+) -> usize {
+    unwrap_kernel_expression(state, inner_expr)
+        .map_or(0, |expr| wrap_expression(state, expr.is_not_null()))
+}
+
 /// # Safety
 /// The string slice must be valid
 #[no_mangle]
@@ -244,3 +290,199 @@ pub extern "C" fn visit_expression_literal_bool(
 ) -> usize {
     wrap_expression(state, value)
 }
+
+/// Visit a timestamp literal, in microseconds since the unix epoch (UTC).
+#[no_mangle]
+pub extern "C" fn visit_expression_literal_timestamp(
+    state: &mut KernelExpressionVisitorState,
+    value: i64,
+) -> usize {
+    wrap_expression(state, Scalar::Timestamp(value))
+}
+
+/// Visit a timestamp literal without a timezone, in microseconds since the unix epoch.
+#[no_mangle]
+pub extern "C" fn visit_expression_literal_timestamp_ntz(
+    state: &mut KernelExpressionVisitorState,
+    value: i64,
+) -> usize {
+    wrap_expression(state, Scalar::TimestampNtz(value))
+}
+
+/// Visit a date literal, in days since the unix epoch.
+#[no_mangle]
+pub extern "C" fn visit_expression_literal_date(
+    state: &mut KernelExpressionVisitorState,
+    value: i32,
+) -> usize {
+    wrap_expression(state, Scalar::Date(value))
+}
+
+/// Visit a binary literal, which is copied from the `len` bytes at `buf`.
+///
+/// # Safety
+/// The caller must pass a valid pointer to at least `len` bytes (which may be null if `len` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn visit_expression_literal_binary(
+    state: &mut KernelExpressionVisitorState,
+    buf: *const u8,
+    len: usize,
+) -> usize {
+    let value = match len {
+        0 => vec![],
+        _ => unsafe { std::slice::from_raw_parts(buf, len) }.to_vec(),
+    };
+    wrap_expression(state, Scalar::Binary(value))
+}
+
+/// Visit a decimal literal with the given `precision` and `scale`, whose 128-bit unscaled value has
+/// the most significant 64 bits `value_ms` and the least significant 64 bits `value_ls` (as in
+/// `visit_literal_decimal` of [`EngineExpressionVisitor`]).
+///
+/// [`EngineExpressionVisitor`]: crate::expressions::kernel::EngineExpressionVisitor
+///
+/// # Safety
+/// The caller must pass a valid `allocate_error` function.
+#[no_mangle]
+pub unsafe extern "C" fn visit_expression_literal_decimal(
+    state: &mut KernelExpressionVisitorState,
+    value_ms: u64,
+    value_ls: u64,
+    precision: u8,
+    scale: u8,
+    allocate_error: AllocateErrorFn,
+) -> ExternResult<usize> {
+    visit_expression_literal_decimal_impl(state, value_ms, value_ls, precision, scale)
+        .into_extern_result(&allocate_error)
+}
+fn visit_expression_literal_decimal_impl(
+    state: &mut KernelExpressionVisitorState,
+    value_ms: u64,
+    value_ls: u64,
+    precision: u8,
+    scale: u8,
+) -> DeltaResult<usize> {
+    PrimitiveType::check_decimal(precision, scale)?;
+    let value = ((value_ms as i128) << 64) | value_ls as i128;
+    Ok(wrap_expression(
+        state,
+        Scalar::Decimal(value, precision, scale),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::NonNull;
+
+    use delta_kernel::expressions::column_expr;
+
+    use super::*;
+    use crate::error::{EngineError, KernelError};
+    use crate::kernel_string_slice;
+
+    extern "C" fn next_child(data: NonNull<c_void>) -> *const c_void {
+        let children = unsafe { data.cast::<std::vec::IntoIter<usize>>().as_mut() };
+        children.next().unwrap_or(0) as *const c_void
+    }
+
+    fn visit_children(
+        state: &mut KernelExpressionVisitorState,
+        children: Vec<usize>,
+        visit: extern "C" fn(&mut KernelExpressionVisitorState, &mut EngineIterator) -> usize,
+    ) -> usize {
+        let mut children = children.into_iter();
+        let mut iter = EngineIterator {
+            data: NonNull::from(&mut children).cast(),
+            get_next: next_child,
+        };
+        visit(state, &mut iter)
+    }
+
+    fn ok(result: ExternResult<usize>) -> usize {
+        match result {
+            ExternResult::Ok(id) => id,
+            ExternResult::Err(_) => panic!("visiting the expression failed"),
+        }
+    }
+
+    extern "C" fn allocate_err(_etype: KernelError, _msg: KernelStringSlice) -> *mut EngineError {
+        Box::leak(Box::new(EngineError {
+            etype: KernelError::GenericError,
+        }))
+    }
+
+    // Visits `x < 10 OR (y.z IS NOT NULL AND d = 123.45 AND b != 'ab' AND t >= <timestamp>)`
+    extern "C" fn visit_predicate(
+        _predicate: *mut c_void,
+        state: &mut KernelExpressionVisitorState,
+    ) -> usize {
+        let (x, yz, d, b, t) = ("x", "y.z", "d", "b", "t");
+        let column =
+            |state: &mut _, name| ok(unsafe { visit_expression_column(state, name, allocate_err) });
+        let lt = {
+            let x = column(state, kernel_string_slice!(x));
+            let ten = visit_expression_literal_int(state, 10);
+            visit_expression_lt(state, x, ten)
+        };
+        let not_null = {
+            let yz = column(state, kernel_string_slice!(yz));
+            visit_expression_is_not_null(state, yz)
+        };
+        let eq = {
+            let d = column(state, kernel_string_slice!(d));
+            let value =
+                unsafe { visit_expression_literal_decimal(state, 0, 12345, 5, 2, allocate_err) };
+            let value = ok(value);
+            visit_expression_eq(state, d, value)
+        };
+        let ne = {
+            let b = column(state, kernel_string_slice!(b));
+            let value = unsafe { visit_expression_literal_binary(state, b"ab".as_ptr(), 2) };
+            visit_expression_ne(state, b, value)
+        };
+        let ge = {
+            let t = column(state, kernel_string_slice!(t));
+            let value = visit_expression_literal_timestamp(state, 1_000_000);
+            visit_expression_ge(state, t, value)
+        };
+        let and = visit_children(state, vec![not_null, eq, ne, ge], visit_expression_and);
+        visit_children(state, vec![lt, and], visit_expression_or)
+    }
+
+    #[test]
+    fn test_visit_engine_predicate() {
+        let predicate = EnginePredicate {
+            predicate: std::ptr::null_mut(),
+            visitor: visit_predicate,
+        };
+        let mut state = KernelExpressionVisitorState::new();
+        let id = (predicate.visitor)(predicate.predicate, &mut state);
+        let expected = Expression::or(
+            column_expr!("x").lt(10),
+            Expression::and_from([
+                column_expr!("y.z").is_not_null(),
+                column_expr!("d").eq(Scalar::Decimal(12345, 5, 2)),
+                column_expr!("b").ne(Scalar::Binary(b"ab".to_vec())),
+                column_expr!("t").gt_eq(Scalar::Timestamp(1_000_000)),
+            ]),
+        );
+        assert_eq!(unwrap_kernel_expression(&mut state, id), Some(expected));
+    }
+
+    #[test]
+    fn test_visit_or_with_invalid_child() {
+        let mut state = KernelExpressionVisitorState::new();
+        let x = visit_expression_literal_int(&mut state, 1);
+        let invalid = x + 100;
+        let or = visit_children(&mut state, vec![x, invalid], visit_expression_or);
+        assert_eq!(or, 0);
+    }
+
+    #[test]
+    fn test_visit_invalid_decimal() {
+        let mut state = KernelExpressionVisitorState::new();
+        let result =
+            unsafe { visit_expression_literal_decimal(&mut state, 0, 1, 40, 2, allocate_err) };
+        assert!(matches!(result, ExternResult::Err(_)));
+    }
+}
@@ -5,8 +5,8 @@ use std::ffi::c_void;
 
 use crate::{handle::Handle, kernel_string_slice, KernelStringSlice};
 use delta_kernel::expressions::{
-    ArrayData, BinaryExpression, BinaryOperator, Expression, Scalar, StructData, UnaryExpression,
-    UnaryOperator, VariadicExpression, VariadicOperator,
+    ArrayData, BinaryExpression, BinaryOperator, Expression, ExpressionRef, Scalar, StructData,
+    UnaryExpression, UnaryOperator, VariadicExpression, VariadicOperator,
 };
 
 /// Free the memory the passed SharedExpression
@@ -174,6 +174,12 @@ pub struct EngineExpressionVisitor {
     /// The sub-expressions of the `StructExpression` are in a list identified by `child_list_id`
     pub visit_struct_expr:
         extern "C" fn(data: *mut c_void, sibling_list_id: usize, child_list_id: usize),
+    /// Visits a `coalesce` expression belonging to the list identified by `sibling_list_id`.
+    /// The sub-expressions of the array are in a list identified by `child_list_id`
+    pub visit_coalesce: VisitVariadicFn,
+    /// Visits the `NullIf` binary operator belonging to the list identified by `sibling_list_id`.
+    /// The operands will be in a _two_ item list identified by `child_list_id`
+    pub visit_null_if: VisitBinaryOpFn,
 }
 
 /// Visit the expression of the passed [`SharedExpression`] Handle using the provided `visitor`.
@@ -247,7 +253,7 @@ pub unsafe extern "C" fn visit_expression(
     fn visit_expression_variadic(
         visitor: &mut EngineExpressionVisitor,
         op: &VariadicOperator,
-        exprs: &[Expression],
+        exprs: &[ExpressionRef],
         sibling_list_id: usize,
     ) {
         let child_list_id = call!(visitor, make_field_list, exprs.len());
@@ -258,6 +264,7 @@ pub unsafe extern "C" fn visit_expression(
         let visit_fn = match op {
             VariadicOperator::And => &visitor.visit_and,
             VariadicOperator::Or => &visitor.visit_or,
+            VariadicOperator::Coalesce => &visitor.visit_coalesce,
         };
         visit_fn(visitor.data, sibling_list_id, child_list_id);
     }
@@ -347,6 +354,7 @@ pub unsafe extern "C" fn visit_expression(
                     BinaryOperator::Equal => visitor.visit_eq,
                     BinaryOperator::NotEqual => visitor.visit_ne,
                     BinaryOperator::Distinct => visitor.visit_distinct,
+                    BinaryOperator::NullIf => visitor.visit_null_if,
                     BinaryOperator::In => visitor.visit_in,
                     BinaryOperator::NotIn => visitor.visit_not_in,
                 };
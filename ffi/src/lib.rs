@@ -6,8 +6,9 @@
 use std::collections::HashMap;
 use std::default::Default;
 use std::os::raw::{c_char, c_void};
+use std::panic::AssertUnwindSafe;
 use std::ptr::NonNull;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use tracing::debug;
 use url::Url;
 
@@ -37,13 +38,84 @@ use error::{AllocateError, AllocateErrorFn, ExternResult, IntoExternResult};
 pub mod expressions;
 #[cfg(feature = "tracing")]
 pub mod ffi_tracing;
+pub mod registry;
 pub mod scan;
 pub mod schema;
 #[cfg(feature = "test-ffi")]
 pub mod test_ffi;
+pub mod transaction;
 
 pub(crate) type NullableCvoid = Option<NonNull<c_void>>;
 
+/// An engine context which is handed to a kernel-owned thread, to pass back to the engine's
+/// callbacks. The engine is responsible for the context being usable from that thread.
+#[derive(Clone, Copy)]
+pub(crate) struct EngineContext(NullableCvoid);
+
+// SAFETY: Kernel never dereferences the context, and the engine promised that it can be used from
+// other threads.
+unsafe impl Send for EngineContext {}
+
+impl EngineContext {
+    // NOTE: Closures capture the fields they use, so they would capture the (non-`Send`) context
+    // itself rather than this wrapper if they accessed the field directly.
+    pub(crate) fn into_inner(self) -> NullableCvoid {
+        self.0
+    }
+}
+
+/// The number of kernel-owned threads which run the async calls of the engine, e.g.
+/// [`snapshot_async`]. Further calls wait for one of the threads to be free.
+const ASYNC_CALL_THREADS: usize = 4;
+
+type AsyncCall = Box<dyn FnOnce() + Send>;
+
+/// Run `call` on one of the kernel-owned threads of the async calls (which are started on first
+/// use). The result of `call` is an error if it panics, so that it can always be reported to the
+/// engine.
+pub(crate) fn spawn_async_call<T: Send + 'static>(
+    call: impl FnOnce() -> DeltaResult<T> + Send + 'static,
+    completion: impl FnOnce(DeltaResult<T>) + Send + 'static,
+) {
+    static CALLS: OnceLock<mpsc::Sender<AsyncCall>> = OnceLock::new();
+    let calls = CALLS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<AsyncCall>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..ASYNC_CALL_THREADS {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("delta-kernel-async-{i}"))
+                .spawn(move || loop {
+                    // the lock is released before the call runs
+                    let call = receiver.lock().map(|receiver| receiver.recv());
+                    match call {
+                        Ok(Ok(call)) => call(),
+                        _ => return,
+                    }
+                })
+                .expect("failed to start a thread for async calls");
+        }
+        sender
+    });
+    let call = Box::new(move || completion(catch_panic(call)));
+    // the threads never exit, so there's always a receiver
+    let _ = calls.send(call);
+}
+
+// The result of `call`, or an error if it panics
+fn catch_panic<T>(call: impl FnOnce() -> DeltaResult<T>) -> DeltaResult<T> {
+    std::panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        Err(delta_kernel::Error::generic(format!(
+            "Async call panicked: {message}"
+        )))
+    })
+}
+
 /// Model iterators. This allows an engine to specify iteration however it likes, and we simply wrap
 /// the engine functions. The engine retains ownership of the iterator.
 #[repr(C)]
@@ -578,6 +650,41 @@ pub unsafe extern "C" fn snapshot(
     snapshot_impl(url, engine).into_extern_result(&engine)
 }
 
+/// Called by kernel with the result of [`snapshot_async`], and the `engine_context` which was
+/// passed to it.
+pub type SnapshotCompletionFn =
+    extern "C" fn(engine_context: NullableCvoid, snapshot: ExternResult<Handle<SharedSnapshot>>);
+
+/// Get the latest snapshot from the specified table without blocking the calling thread: the
+/// snapshot is loaded on one of a few kernel-owned threads, which calls `completion` (exactly once)
+/// with the result, or with an error if loading the snapshot panicked. This allows engines with their own event loop to not dedicate a thread to waiting on
+/// the reads of the log. The snapshot must be freed with [`free_snapshot`], as for [`snapshot`].
+///
+/// # Safety
+///
+/// Caller is responsible for passing valid handles and path pointer. `engine_context` must remain
+/// valid until `completion` is called. The engine's error allocator and `completion` are called
+/// from the kernel-owned thread, so they (and `engine_context`) must be usable from any thread.
+#[no_mangle]
+pub unsafe extern "C" fn snapshot_async(
+    path: KernelStringSlice,
+    engine: Handle<SharedExternEngine>,
+    engine_context: NullableCvoid,
+    completion: SnapshotCompletionFn,
+) {
+    let url = unsafe { unwrap_and_parse_path_as_url(path) };
+    let engine = unsafe { engine.clone_as_arc() };
+    let engine_context = EngineContext(engine_context);
+    let call_engine = engine.clone();
+    spawn_async_call(
+        move || snapshot_impl(url, call_engine.as_ref()),
+        move |result| {
+            let result = unsafe { result.into_extern_result(&engine.as_ref()) };
+            completion(engine_context.into_inner(), result);
+        },
+    );
+}
+
 fn snapshot_impl(
     url: DeltaResult<Url>,
     extern_engine: &dyn ExternEngine,
@@ -721,7 +828,7 @@ mod tests {
     use test_utils::{actions_to_string, add_commit, TestAction};
 
     use super::*;
-    use crate::error::{EngineError, KernelError};
+    use crate::error::{kernel_error_description, EngineError, KernelError};
 
     #[no_mangle]
     extern "C" fn allocate_err(etype: KernelError, _: KernelStringSlice) -> *mut EngineError {
@@ -815,6 +922,312 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_snapshot_error_code() -> Result<(), Box<dyn std::error::Error>> {
+        let storage = Arc::new(InMemory::new());
+        let protocol = r#"{"protocol":{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":["idk"],"writerFeatures":["idk"]}}"#;
+        let metadata = actions_to_string(vec![TestAction::Metadata]).replace(
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#,
+            protocol,
+        );
+        add_commit(storage.as_ref(), 0, metadata).await?;
+        let engine = DefaultEngine::new(
+            storage.clone(),
+            Path::from("/"),
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+        let engine = engine_to_handle(Arc::new(engine), allocate_err);
+        let path = "memory:///";
+
+        match unsafe { snapshot(kernel_string_slice!(path), engine.shallow_copy()) } {
+            ExternResult::Ok(_) => panic!("Expected an unsupported table feature error"),
+            ExternResult::Err(e) => {
+                let error = unsafe { Box::from_raw(e) };
+                assert_eq!(error.etype, KernelError::UnsupportedTableFeatureError);
+                assert_eq!(error.etype as u32, 42);
+                let description = kernel_error_description(error.etype);
+                let description = unsafe { String::try_from_slice(&description) }?;
+                assert_eq!(description, "The table uses an unsupported table feature");
+            }
+        }
+
+        unsafe { free_engine(engine) }
+        Ok(())
+    }
+
+    // The engine context of the async calls is a (boxed) sender, which their completion callbacks
+    // take back ownership of to send the result over
+    fn sender_context<T>(sender: std::sync::mpsc::Sender<T>) -> NullableCvoid {
+        NonNull::new(Box::into_raw(Box::new(sender)).cast())
+    }
+
+    fn send<T>(engine_context: NullableCvoid, value: T) {
+        let sender = engine_context
+            .unwrap()
+            .as_ptr()
+            .cast::<std::sync::mpsc::Sender<T>>();
+        unsafe { Box::from_raw(sender) }.send(value).unwrap();
+    }
+
+    extern "C" fn snapshot_done(
+        engine_context: NullableCvoid,
+        snapshot: ExternResult<Handle<SharedSnapshot>>,
+    ) {
+        let snapshot = ok_or_panic(snapshot);
+        let version = unsafe { version(snapshot.shallow_copy()) };
+        unsafe { free_snapshot(snapshot) };
+        send(engine_context, version);
+    }
+
+    extern "C" fn scan_data_done(engine_context: NullableCvoid, has_next: ExternResult<bool>) {
+        send(engine_context, ok_or_panic(has_next));
+    }
+
+    extern "C" fn visit_scan_data(
+        _: NullableCvoid,
+        _: Handle<ExclusiveEngineData>,
+        _: KernelBoolSlice,
+    ) {
+        panic!("The table has no files to scan");
+    }
+
+    #[tokio::test]
+    async fn test_async_calls() -> Result<(), Box<dyn std::error::Error>> {
+        let storage = Arc::new(InMemory::new());
+        add_commit(
+            storage.as_ref(),
+            0,
+            actions_to_string(vec![TestAction::Metadata]),
+        )
+        .await?;
+        let engine = DefaultEngine::new(
+            storage.clone(),
+            Path::from("/"),
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+        let engine = engine_to_handle(Arc::new(engine), allocate_err);
+        let path = "memory:///";
+
+        let (sender, receiver) = std::sync::mpsc::channel::<u64>();
+        let context = sender_context(sender);
+        unsafe {
+            snapshot_async(
+                kernel_string_slice!(path),
+                engine.shallow_copy(),
+                context,
+                snapshot_done,
+            )
+        };
+        assert_eq!(receiver.recv()?, 0);
+
+        let snapshot =
+            unsafe { ok_or_panic(snapshot(kernel_string_slice!(path), engine.shallow_copy())) };
+        let scan = unsafe { ok_or_panic(scan::scan(snapshot, engine.shallow_copy(), None)) };
+        let data = unsafe {
+            ok_or_panic(scan::kernel_scan_data_init(
+                engine.shallow_copy(),
+                scan.shallow_copy(),
+            ))
+        };
+        let (sender, receiver) = std::sync::mpsc::channel::<bool>();
+        let context = sender_context(sender);
+        unsafe {
+            scan::kernel_scan_data_next_async(
+                data.shallow_copy(),
+                context,
+                visit_scan_data,
+                scan_data_done,
+            )
+        };
+        // the kernel-owned thread has its own reference to the iterator
+        unsafe { scan::free_kernel_scan_data(data) };
+        assert!(!receiver.recv()?);
+
+        unsafe { scan::free_scan(scan) }
+        unsafe { free_engine(engine) }
+        Ok(())
+    }
+
+    #[test]
+    fn test_async_call_panics() {
+        // the completion is called with an error, and the thread is still there for other calls
+        for _ in 0..=ASYNC_CALL_THREADS {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            spawn_async_call(
+                || -> DeltaResult<()> { panic!("kernel bug") },
+                move |result| sender.send(result).unwrap(),
+            );
+            let err = receiver.recv().unwrap().unwrap_err();
+            assert!(err.to_string().contains("kernel bug"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit() -> Result<(), Box<dyn std::error::Error>> {
+        use object_store::ObjectStore;
+
+        let storage = Arc::new(InMemory::new());
+        // kernel only writes to tables with reader/writer version 3/7
+        let protocol = r#"{"protocol":{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":[],"writerFeatures":[]}}"#;
+        let metadata = actions_to_string(vec![TestAction::Metadata]).replace(
+            r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#,
+            protocol,
+        );
+        add_commit(storage.as_ref(), 0, metadata).await?;
+        let engine = DefaultEngine::new(
+            storage.clone(),
+            Path::from("/"),
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+        let engine = engine_to_handle(Arc::new(engine), allocate_err);
+        let path = "memory:///";
+
+        let txn = unsafe {
+            ok_or_panic(transaction::transaction(
+                kernel_string_slice!(path),
+                engine.shallow_copy(),
+            ))
+        };
+        let engine_info = "test-engine";
+        let txn = unsafe {
+            ok_or_panic(transaction::with_engine_info(
+                txn,
+                kernel_string_slice!(engine_info),
+                engine.shallow_copy(),
+            ))
+        };
+        let (key, value) = ("engine", "test");
+        let txn = unsafe {
+            ok_or_panic(transaction::with_engine_commit_info(
+                txn,
+                &kernel_string_slice!(key),
+                &kernel_string_slice!(value),
+                1,
+                engine.shallow_copy(),
+            ))
+        };
+        let (file_path, stats) = ("part-1.parquet", r#"{"numRecords":3}"#);
+        let file = engine_funcs::FileMeta {
+            path: kernel_string_slice!(file_path),
+            last_modified: 1000,
+            size: 123,
+        };
+        let added = unsafe {
+            ok_or_panic(transaction::add_file(
+                txn.shallow_copy(),
+                &file,
+                std::ptr::null(),
+                std::ptr::null(),
+                0,
+                Some(&kernel_string_slice!(stats)),
+                true,
+                engine.shallow_copy(),
+            ))
+        };
+        assert!(added);
+        let version = unsafe { ok_or_panic(transaction::commit(txn, engine.shallow_copy())) };
+        assert_eq!(version, 1);
+
+        let commit = storage
+            .get(&Path::from("_delta_log/00000000000000000001.json"))
+            .await?
+            .bytes()
+            .await?;
+        let actions: Vec<serde_json::Value> = commit
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_slice)
+            .collect::<Result<_, _>>()?;
+        let commit_info = &actions[0]["commitInfo"];
+        assert_eq!(commit_info["engineInfo"], "test-engine");
+        assert_eq!(commit_info["engineCommitInfo"]["engine"], "test");
+        let add = &actions[1]["add"];
+        assert_eq!(add["path"], "part-1.parquet");
+        assert_eq!(add["size"], 123);
+        assert_eq!(add["stats"], stats);
+
+        unsafe { free_engine(engine) }
+        Ok(())
+    }
+
+    #[cfg(feature = "default-engine")]
+    extern "C" fn collect_arrow_batch(
+        engine_context: NullableCvoid,
+        arrow_data: &mut engine_data::ArrowFFIData,
+        selection_vector: KernelBoolSlice,
+    ) {
+        use arrow_data::ffi::FFI_ArrowArray;
+        let batches = engine_context
+            .unwrap()
+            .cast::<Vec<(arrow_array::StructArray, Vec<bool>)>>();
+        // move the array out, as an engine keeping it would
+        let array = std::mem::replace(&mut arrow_data.array, FFI_ArrowArray::empty());
+        let array = unsafe { arrow_array::ffi::from_ffi(array, &arrow_data.schema) }.unwrap();
+        let selection_vector = unsafe { selection_vector.into_vec() };
+        unsafe { &mut *batches.as_ptr() }.push((array.into(), selection_vector));
+    }
+
+    #[test]
+    #[cfg(feature = "default-engine")]
+    fn scan_to_arrow() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::Int32Type;
+
+        let path = std::fs::canonicalize("../kernel/tests/data/table-with-dv-small/").unwrap();
+        let path = Url::from_directory_path(path).unwrap().to_string();
+        let path = path.as_str();
+        let builder =
+            unsafe { ok_or_panic(get_engine_builder(kernel_string_slice!(path), allocate_err)) };
+        let engine = unsafe { ok_or_panic(builder_build(builder)) };
+        let snapshot =
+            unsafe { ok_or_panic(snapshot(kernel_string_slice!(path), engine.shallow_copy())) };
+        let scan = unsafe {
+            ok_or_panic(scan::scan(
+                snapshot.shallow_copy(),
+                engine.shallow_copy(),
+                None,
+            ))
+        };
+        let results = unsafe {
+            ok_or_panic(scan::kernel_scan_execute(
+                engine.shallow_copy(),
+                scan.shallow_copy(),
+            ))
+        };
+
+        let mut batches: Vec<(arrow_array::StructArray, Vec<bool>)> = vec![];
+        let context = NonNull::new(&mut batches as *mut _ as *mut c_void);
+        while unsafe {
+            ok_or_panic(scan::kernel_scan_result_next_arrow(
+                results.shallow_copy(),
+                context,
+                collect_arrow_batch,
+            ))
+        } {}
+        let values: Vec<i32> = batches
+            .iter()
+            .flat_map(|(array, selection_vector)| {
+                let values = array
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec();
+                values
+                    .into_iter()
+                    .zip(selection_vector.iter().chain(std::iter::repeat(&true)))
+                    .filter_map(|(value, selected)| selected.then_some(value))
+            })
+            .collect();
+        assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        unsafe {
+            scan::free_kernel_scan_result(results);
+            scan::free_scan(scan);
+            free_snapshot(snapshot);
+            free_engine(engine);
+        }
+    }
+
     #[test]
     #[cfg(feature = "sync-engine")]
     fn sync_engine() {
@@ -0,0 +1,392 @@
+//! Registry-backed handles, and negotiation of the version of the FFI ABI.
+//!
+//! A [`Handle`] is a pointer, so using it after it was freed (or passing a handle of the wrong
+//! type) is undefined behavior. As an alternative, engines can register engines, snapshots and
+//! scans with the kernel and refer to them by [`KernelHandleId`]: an opaque `u64` which is resolved
+//! through a registry. Each slot of the registry has a generation counter, which is bumped when the object in
+//! it is released, and ids also record the type of object they refer to. Using an id after it was
+//! released, or as an id of the wrong type, is thus reported as an
+//! [`InvalidHandleError`][KernelError::InvalidHandleError] rather than being UB.
+//!
+//! Other bindings of the kernel (e.g. for the JVM) can use a [`Registry`] for their own objects,
+//! with tags above [`MAX_FFI_REGISTRY_TAG`] so that their ids never collide with those of the FFI.
+//!
+//! Engines which may load a different version of the kernel shared library than they were built
+//! against should call [`negotiate_abi_version`] before anything else, to check that the library
+//! still supports (a version of) the ABI they were built for.
+
+use std::sync::{Arc, Mutex};
+
+use delta_kernel::scan::Scan;
+use delta_kernel::snapshot::Snapshot;
+use delta_kernel::{DeltaResult, Error};
+
+use crate::error::{AllocateError, AllocateErrorFn, ExternResult, IntoExternResult, KernelError};
+use crate::expressions::engine::EnginePredicate;
+use crate::handle::Handle;
+use crate::scan::scan_impl;
+use crate::{
+    kernel_string_slice, unwrap_and_parse_path_as_url, ExternEngine, KernelStringSlice,
+    SharedExternEngine,
+};
+
+/// The version of the ABI of the FFI. It is bumped whenever a function or type of the FFI changes
+/// in a backward incompatible way.
+pub const KERNEL_ABI_VERSION: u32 = 1;
+
+/// The oldest version of the ABI which is still supported by this library.
+pub const KERNEL_MIN_ABI_VERSION: u32 = 1;
+
+/// Get the version of the ABI of this kernel library.
+#[no_mangle]
+pub extern "C" fn kernel_abi_version() -> u32 {
+    KERNEL_ABI_VERSION
+}
+
+/// Negotiate the version of the ABI with the kernel library: given the range of versions of the
+/// ABI the engine was built for (inclusive), returns the newest version that both the engine and
+/// the library support, or 0 if there is none, in which case the engine must not use the library.
+#[no_mangle]
+pub extern "C" fn negotiate_abi_version(engine_min_version: u32, engine_max_version: u32) -> u32 {
+    let version = engine_max_version.min(KERNEL_ABI_VERSION);
+    if version < engine_min_version.max(KERNEL_MIN_ABI_VERSION) {
+        return 0;
+    }
+    version
+}
+
+/// An opaque id of an object registered with the kernel. The id 0 is never valid.
+pub type KernelHandleId = u64;
+
+// An id is made of the tag of the type of its object (8 bits), the generation of its slot (24
+// bits), and the index of its slot (32 bits). Generations start at 1, so that 0 is never valid.
+const GENERATION_BITS: u32 = 24;
+const GENERATION_MASK: u32 = (1 << GENERATION_BITS) - 1;
+
+struct Slot<T: ?Sized> {
+    generation: u32,
+    value: Option<Arc<T>>,
+}
+
+struct Slots<T: ?Sized> {
+    slots: Vec<Slot<T>>,
+    // Indexes of the slots without a value, to reuse
+    free: Vec<u32>,
+}
+
+/// A registry of objects of one type, which are referred to by [`KernelHandleId`].
+pub struct Registry<T: ?Sized> {
+    tag: u8,
+    type_name: &'static str,
+    slots: Mutex<Slots<T>>,
+}
+
+impl<T: ?Sized> Registry<T> {
+    /// Create a registry for objects of the named type. The tag must be unique across registries.
+    pub const fn new(tag: u8, type_name: &'static str) -> Self {
+        let slots = Slots {
+            slots: Vec::new(),
+            free: Vec::new(),
+        };
+        Self {
+            tag,
+            type_name,
+            slots: Mutex::new(slots),
+        }
+    }
+
+    fn id(&self, generation: u32, index: u32) -> KernelHandleId {
+        ((self.tag as u64) << 56) | ((generation as u64) << 32) | index as u64
+    }
+
+    fn lock(&self) -> DeltaResult<std::sync::MutexGuard<'_, Slots<T>>> {
+        self.slots
+            .lock()
+            .map_err(|_| Error::generic("poisoned mutex"))
+    }
+
+    /// Register an object, and return its id.
+    pub fn insert(&self, value: Arc<T>) -> DeltaResult<KernelHandleId> {
+        let mut slots = self.lock()?;
+        let index = match slots.free.pop() {
+            Some(index) => index,
+            None => {
+                let index = u32::try_from(slots.slots.len())
+                    .map_err(|_| Error::generic(format!("Too many {}s", self.type_name)))?;
+                slots.slots.push(Slot {
+                    generation: 1,
+                    value: None,
+                });
+                index
+            }
+        };
+        let slot = &mut slots.slots[index as usize];
+        slot.value = Some(value);
+        Ok(self.id(slot.generation, index))
+    }
+
+    /// Resolve an id into its object, or `None` if the id isn't a live id of this registry.
+    pub fn get(&self, id: KernelHandleId) -> Option<Arc<T>> {
+        let slots = self.lock().ok()?;
+        let slot = self.slot(&slots, id)?;
+        slot.value.clone()
+    }
+
+    /// Release the object of an id, which is then no longer valid. Returns `false` if the id isn't
+    /// a live id of this registry.
+    pub fn remove(&self, id: KernelHandleId) -> bool {
+        let Ok(mut slots) = self.lock() else {
+            return false;
+        };
+        if !matches!(self.slot(&slots, id), Some(Slot { value: Some(_), .. })) {
+            return false;
+        }
+        let index = id as u32;
+        let slot = &mut slots.slots[index as usize];
+        slot.value = None;
+        // Wrap around (skipping 0) after 2^24 generations of the slot
+        slot.generation = (slot.generation % GENERATION_MASK) + 1;
+        slots.free.push(index);
+        true
+    }
+
+    fn slot<'a>(&self, slots: &'a Slots<T>, id: KernelHandleId) -> Option<&'a Slot<T>> {
+        let tag = (id >> 56) as u8;
+        let generation = (id >> 32) as u32 & GENERATION_MASK;
+        let slot = slots.slots.get(id as u32 as usize)?;
+        (tag == self.tag && generation == slot.generation).then_some(slot)
+    }
+
+    /// Resolve an id into its object, with an error message if the id isn't valid.
+    pub fn resolve(&self, id: KernelHandleId) -> Result<Arc<T>, String> {
+        self.get(id)
+            .ok_or_else(|| format!("Invalid (or released) {} handle {id:#x}", self.type_name))
+    }
+}
+
+/// The largest tag of the registries of the FFI itself.
+pub const MAX_FFI_REGISTRY_TAG: u8 = 3;
+
+static ENGINES: Registry<dyn ExternEngine> = Registry::new(1, "engine");
+static SNAPSHOTS: Registry<Snapshot> = Registry::new(2, "snapshot");
+static SCANS: Registry<Scan> = Registry::new(3, "scan");
+
+/// Report an invalid id as an [`InvalidHandleError`][KernelError::InvalidHandleError], allocated
+/// with the given allocator.
+fn invalid_handle<T>(allocate_error: impl AllocateError, msg: String) -> ExternResult<T> {
+    let err = unsafe {
+        allocate_error.allocate_error(KernelError::InvalidHandleError, kernel_string_slice!(msg))
+    };
+    ExternResult::Err(err)
+}
+
+/// Register an engine, and get its id. This consumes the engine handle: the engine is released
+/// with [`release_engine`].
+///
+/// # Safety
+///
+/// Caller is responsible for passing a valid engine handle.
+#[no_mangle]
+pub unsafe extern "C" fn register_engine(
+    engine: Handle<SharedExternEngine>,
+) -> ExternResult<KernelHandleId> {
+    let engine = unsafe { engine.into_inner() };
+    let id = ENGINES.insert(engine.clone());
+    unsafe { id.into_extern_result(&engine.as_ref()) }
+}
+
+/// Release a registered engine. Returns false if the id isn't the id of a registered engine (for
+/// example because it was already released).
+#[no_mangle]
+pub extern "C" fn release_engine(engine: KernelHandleId) -> bool {
+    ENGINES.remove(engine)
+}
+
+/// Get the latest snapshot of the table at `path` with a registered engine, and register it. The
+/// snapshot is released with [`release_snapshot`].
+///
+/// Errors are allocated with `allocate_error` rather than the error allocator of the engine, so
+/// that an invalid engine id can be reported as well.
+///
+/// # Safety
+///
+/// Caller is responsible for passing a valid path.
+#[no_mangle]
+pub unsafe extern "C" fn registered_snapshot(
+    engine: KernelHandleId,
+    path: KernelStringSlice,
+    allocate_error: AllocateErrorFn,
+) -> ExternResult<KernelHandleId> {
+    let engine = match ENGINES.resolve(engine) {
+        Ok(engine) => engine,
+        Err(msg) => return invalid_handle(allocate_error, msg),
+    };
+    let url = unsafe { unwrap_and_parse_path_as_url(path) };
+    let snapshot = url.and_then(|url| Snapshot::try_new(url, engine.engine().as_ref(), None));
+    let id = snapshot.and_then(|snapshot| SNAPSHOTS.insert(Arc::new(snapshot)));
+    unsafe { id.into_extern_result(&allocate_error) }
+}
+
+/// Get the version of a registered snapshot. Errors are allocated with `allocate_error`.
+#[no_mangle]
+pub extern "C" fn registered_snapshot_version(
+    snapshot: KernelHandleId,
+    allocate_error: AllocateErrorFn,
+) -> ExternResult<u64> {
+    match SNAPSHOTS.resolve(snapshot) {
+        Ok(snapshot) => ExternResult::Ok(snapshot.version()),
+        Err(msg) => invalid_handle(allocate_error, msg),
+    }
+}
+
+/// Release a registered snapshot. Returns false if the id isn't the id of a registered snapshot
+/// (for example because it was already released).
+#[no_mangle]
+pub extern "C" fn release_snapshot(snapshot: KernelHandleId) -> bool {
+    SNAPSHOTS.remove(snapshot)
+}
+
+/// Build a scan of a registered snapshot, optionally with a predicate, and register it. The scan
+/// is released with [`release_scan`]. Errors are allocated with `allocate_error`.
+///
+/// # Safety
+///
+/// Caller is responsible for passing a valid predicate, if any.
+#[no_mangle]
+pub unsafe extern "C" fn registered_scan(
+    snapshot: KernelHandleId,
+    predicate: Option<&mut EnginePredicate>,
+    allocate_error: AllocateErrorFn,
+) -> ExternResult<KernelHandleId> {
+    let snapshot = match SNAPSHOTS.resolve(snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(msg) => return invalid_handle(allocate_error, msg),
+    };
+    let id = scan_impl(snapshot, predicate).and_then(|scan| SCANS.insert(Arc::new(scan)));
+    unsafe { id.into_extern_result(&allocate_error) }
+}
+
+/// Release a registered scan. Returns false if the id isn't the id of a registered scan (for
+/// example because it was already released).
+#[no_mangle]
+pub extern "C" fn release_scan(scan: KernelHandleId) -> bool {
+    SCANS.remove(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use delta_kernel::engine::default::DefaultEngine;
+    use object_store::memory::InMemory;
+    use object_store::path::Path;
+    use test_utils::{actions_to_string, add_commit, TestAction};
+
+    use super::*;
+    use crate::engine_to_handle;
+    use crate::error::EngineError;
+
+    extern "C" fn allocate_err(etype: KernelError, _: KernelStringSlice) -> *mut EngineError {
+        Box::leak(Box::new(EngineError { etype }))
+    }
+
+    #[test]
+    fn test_negotiate_abi_version() {
+        assert_eq!(negotiate_abi_version(1, 1), 1);
+        assert_eq!(negotiate_abi_version(1, 5), KERNEL_ABI_VERSION);
+        assert_eq!(negotiate_abi_version(2, 5), 0);
+        assert_eq!(negotiate_abi_version(0, 0), 0);
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry: Registry<str> = Registry::new(7, "string");
+        let other: Registry<str> = Registry::new(8, "other");
+        assert!(registry.get(0).is_none());
+
+        let a = registry.insert(Arc::from("a")).unwrap();
+        let b = registry.insert(Arc::from("b")).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(registry.get(a).as_deref(), Some("a"));
+        assert_eq!(registry.get(b).as_deref(), Some("b"));
+
+        // ids of another registry are invalid, even for the same slot
+        let other_a = other.insert(Arc::from("other")).unwrap();
+        assert!(registry.get(other_a).is_none());
+        assert!(!registry.remove(other_a));
+
+        // released ids are invalid, also after their slot is reused
+        assert!(registry.remove(a));
+        assert!(registry.get(a).is_none());
+        assert!(!registry.remove(a));
+        let c = registry.insert(Arc::from("c")).unwrap();
+        assert_eq!(c as u32, a as u32);
+        assert_ne!(c, a);
+        assert!(registry.get(a).is_none());
+        assert_eq!(registry.get(c).as_deref(), Some("c"));
+        assert!(registry.resolve(a).unwrap_err().starts_with("Invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_registered_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+        let storage = Arc::new(InMemory::new());
+        add_commit(
+            storage.as_ref(),
+            0,
+            actions_to_string(vec![TestAction::Metadata]),
+        )
+        .await?;
+        let engine = DefaultEngine::new(
+            storage.clone(),
+            Path::from("/"),
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+        let engine = engine_to_handle(Arc::new(engine), allocate_err);
+        let ExternResult::Ok(engine) = (unsafe { register_engine(engine) }) else {
+            panic!("Failed to register the engine");
+        };
+        let path = "memory:///";
+
+        let ExternResult::Ok(snapshot) =
+            (unsafe { registered_snapshot(engine, kernel_string_slice!(path), allocate_err) })
+        else {
+            panic!("Failed to get a snapshot");
+        };
+        assert!(matches!(
+            registered_snapshot_version(snapshot, allocate_err),
+            ExternResult::Ok(0)
+        ));
+        let ExternResult::Ok(scan) = (unsafe { registered_scan(snapshot, None, allocate_err) })
+        else {
+            panic!("Failed to build a scan");
+        };
+        assert!(release_scan(scan));
+        assert!(!release_scan(scan));
+
+        // a released snapshot (or one used as an engine) is reported as an invalid handle
+        assert!(release_snapshot(snapshot));
+        assert!(!release_snapshot(snapshot));
+        let ExternResult::Err(err) = registered_snapshot_version(snapshot, allocate_err) else {
+            panic!("Expected an invalid handle error");
+        };
+        let err = unsafe { Box::from_raw(err) };
+        assert_eq!(err.etype, KernelError::InvalidHandleError);
+        let ExternResult::Err(err) = (unsafe { registered_scan(snapshot, None, allocate_err) })
+        else {
+            panic!("Expected an invalid handle error");
+        };
+        let err = unsafe { Box::from_raw(err) };
+        assert_eq!(err.etype, KernelError::InvalidHandleError);
+        assert!(!release_engine(snapshot));
+
+        // so is a released engine
+        assert!(release_engine(engine));
+        let ExternResult::Err(err) =
+            (unsafe { registered_snapshot(engine, kernel_string_slice!(path), allocate_err) })
+        else {
+            panic!("Expected an invalid handle error");
+        };
+        let err = unsafe { Box::from_raw(err) };
+        assert_eq!(err.etype, KernelError::InvalidHandleError);
+        Ok(())
+    }
+}
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use delta_kernel::scan::state::{visit_scan_files, DvInfo, GlobalScanState};
+#[cfg(feature = "default-engine")]
+use delta_kernel::scan::ScanResult;
 use delta_kernel::scan::{Scan, ScanData};
 use delta_kernel::schema::Schema;
 use delta_kernel::snapshot::Snapshot;
@@ -16,12 +18,15 @@ use crate::expressions::engine::{
     unwrap_kernel_expression, EnginePredicate, KernelExpressionVisitorState,
 };
 use crate::{
-    kernel_string_slice, AllocateStringFn, ExclusiveEngineData, ExternEngine, ExternResult,
-    IntoExternResult, KernelBoolSlice, KernelRowIndexArray, KernelStringSlice, NullableCvoid,
-    SharedExternEngine, SharedSnapshot, StringIter, StringSliceIterator, TryFromStringSlice,
+    kernel_string_slice, spawn_async_call, AllocateStringFn, EngineContext, ExclusiveEngineData,
+    ExternEngine, ExternResult, IntoExternResult, KernelBoolSlice, KernelRowIndexArray,
+    KernelStringSlice, NullableCvoid, SharedExternEngine, SharedSnapshot, StringIter,
+    StringSliceIterator, TryFromStringSlice,
 };
 
 use super::handle::Handle;
+#[cfg(feature = "default-engine")]
+use crate::engine_data::{to_arrow_ffi_data, ArrowFFIData};
 
 // TODO: Why do we even need to expose a scan, when the only thing an engine can do with it is
 // handit back to the kernel by calling `kernel_scan_data_init`? There isn't even an FFI method to
@@ -50,13 +55,15 @@ pub unsafe extern "C" fn scan(
     predicate: Option<&mut EnginePredicate>,
 ) -> ExternResult<Handle<SharedScan>> {
     let snapshot = unsafe { snapshot.clone_as_arc() };
-    scan_impl(snapshot, predicate).into_extern_result(&engine.as_ref())
+    scan_impl(snapshot, predicate)
+        .map(|scan| Arc::new(scan).into())
+        .into_extern_result(&engine.as_ref())
 }
 
-fn scan_impl(
+pub(crate) fn scan_impl(
     snapshot: Arc<Snapshot>,
     predicate: Option<&mut EnginePredicate>,
-) -> DeltaResult<Handle<SharedScan>> {
+) -> DeltaResult<Scan> {
     let mut scan_builder = snapshot.scan_builder();
     if let Some(predicate) = predicate {
         let mut visitor_state = KernelExpressionVisitorState::new();
@@ -65,7 +72,7 @@ fn scan_impl(
         debug!("Got predicate: {:#?}", predicate);
         scan_builder = scan_builder.with_predicate(predicate.map(Arc::new));
     }
-    Ok(Arc::new(scan_builder.build()?).into())
+    scan_builder.build()
 }
 
 #[handle_descriptor(target=GlobalScanState, mutable=false, sized=true)]
@@ -217,6 +224,48 @@ pub unsafe extern "C" fn kernel_scan_data_next(
     kernel_scan_data_next_impl(data, engine_context, engine_visitor)
         .into_extern_result(&data.engine.as_ref())
 }
+/// Called by kernel with the result of [`kernel_scan_data_next_async`] (whether there was a next
+/// scan data item, as for [`kernel_scan_data_next`]), and the `engine_context` which was passed to
+/// it.
+pub type ScanDataCompletionFn =
+    extern "C" fn(engine_context: NullableCvoid, has_next: ExternResult<bool>);
+
+/// Like [`kernel_scan_data_next`], but without blocking the calling thread: the next scan data item
+/// is read on one of a few kernel-owned threads, which calls `engine_visitor` with it (if any), and
+/// then `completion` (exactly once) with the result, or with an error if reading it panicked. The
+/// kernel-owned thread keeps its own reference to the iterator, so the engine may free the iterator
+/// before `completion` is called.
+///
+/// # Safety
+///
+/// The iterator must be valid (returned by [kernel_scan_data_init]) and not yet freed by
+/// [`free_kernel_scan_data`] when this is called. `engine_context` must remain valid until
+/// `completion` is called. The engine's error allocator, `engine_visitor` and `completion` are
+/// called from the kernel-owned thread, so they (and `engine_context`) must be usable from any
+/// thread.
+#[no_mangle]
+pub unsafe extern "C" fn kernel_scan_data_next_async(
+    data: Handle<SharedScanDataIterator>,
+    engine_context: NullableCvoid,
+    engine_visitor: extern "C" fn(
+        engine_context: NullableCvoid,
+        engine_data: Handle<ExclusiveEngineData>,
+        selection_vector: KernelBoolSlice,
+    ),
+    completion: ScanDataCompletionFn,
+) {
+    let data = unsafe { data.clone_as_arc() };
+    let engine_context = EngineContext(engine_context);
+    let call_data = data.clone();
+    spawn_async_call(
+        move || kernel_scan_data_next_impl(&call_data, engine_context.into_inner(), engine_visitor),
+        move |result| {
+            let result = unsafe { result.into_extern_result(&data.engine.as_ref()) };
+            completion(engine_context.into_inner(), result);
+        },
+    );
+}
+
 fn kernel_scan_data_next_impl(
     data: &KernelScanDataIterator,
     engine_context: NullableCvoid,
@@ -250,6 +299,125 @@ pub unsafe extern "C" fn free_kernel_scan_data(data: Handle<SharedScanDataIterat
     data.drop_handle();
 }
 
+// Intentionally opaque to the engine.
+#[cfg(feature = "default-engine")]
+pub struct KernelScanResultIterator {
+    // Mutex -> Allow the iterator to be accessed safely by multiple threads.
+    // Box -> Wrap its unsized content this struct is fixed-size with thin pointers.
+    data: Mutex<Box<dyn Iterator<Item = DeltaResult<ScanResult>> + Send>>,
+
+    // Also keep a reference to the external engine for its error allocator, and to keep its tokio
+    // reactor alive while the iterator reads data files.
+    engine: Arc<dyn ExternEngine>,
+}
+
+#[cfg(feature = "default-engine")]
+#[handle_descriptor(target=KernelScanResultIterator, mutable=false, sized=true)]
+pub struct SharedScanResultIterator;
+
+/// Execute the scan, reading (and transforming) the data of the table with the engine, which must
+/// be a default engine. The batches of the data are returned by
+/// [`kernel_scan_result_next_arrow`], as arrow [C Data
+/// Interface](https://arrow.apache.org/docs/format/CDataInterface.html) structs. It is the
+/// responsibility of the _engine_ to free the iterator by calling [`free_kernel_scan_result`].
+///
+/// # Safety
+///
+/// Engine is responsible for passing a valid [`SharedExternEngine`] and [`SharedScan`]
+#[cfg(feature = "default-engine")]
+#[no_mangle]
+pub unsafe extern "C" fn kernel_scan_execute(
+    engine: Handle<SharedExternEngine>,
+    scan: Handle<SharedScan>,
+) -> ExternResult<Handle<SharedScanResultIterator>> {
+    let engine = unsafe { engine.clone_as_arc() };
+    let scan = unsafe { scan.as_ref() };
+    kernel_scan_execute_impl(&engine, scan).into_extern_result(&engine.as_ref())
+}
+
+#[cfg(feature = "default-engine")]
+fn kernel_scan_execute_impl(
+    engine: &Arc<dyn ExternEngine>,
+    scan: &Scan,
+) -> DeltaResult<Handle<SharedScanResultIterator>> {
+    let results = scan.execute(engine.engine())?;
+    let data = KernelScanResultIterator {
+        data: Mutex::new(Box::new(results)),
+        engine: engine.clone(),
+    };
+    Ok(Arc::new(data).into())
+}
+
+/// Call the provided `engine_visitor` on the next batch of the data of the scan, as an arrow array
+/// and its schema. The array is a struct array, whose fields are the columns of the scan.
+///
+/// Following the C Data Interface, the array and schema are only valid for the duration of the
+/// call: an engine which keeps them must _move_ them out of the [`ArrowFFIData`], by copying the
+/// structs and setting the `release` callback of the originals to null. The kernel releases
+/// whatever the engine doesn't move, so no data is copied nor leaked either way.
+///
+/// The selection vector selects the rows of the batch which are not deleted by a deletion vector.
+/// It is empty if all rows are selected, and otherwise has one entry per row of the batch. It is
+/// the responsibility of the _engine_ to free it by calling [`free_bool_slice`].
+///
+/// Returns `false` once the data of the scan is exhausted.
+///
+/// # Safety
+///
+/// The iterator must be valid (returned by [`kernel_scan_execute`]) and not yet freed by
+/// [`free_kernel_scan_result`]. The visitor function pointer must be non-null.
+///
+/// [`free_bool_slice`]: crate::free_bool_slice
+#[cfg(feature = "default-engine")]
+#[no_mangle]
+pub unsafe extern "C" fn kernel_scan_result_next_arrow(
+    data: Handle<SharedScanResultIterator>,
+    engine_context: NullableCvoid,
+    engine_visitor: extern "C" fn(
+        engine_context: NullableCvoid,
+        arrow_data: &mut ArrowFFIData,
+        selection_vector: KernelBoolSlice,
+    ),
+) -> ExternResult<bool> {
+    let data = unsafe { data.as_ref() };
+    kernel_scan_result_next_arrow_impl(data, engine_context, engine_visitor)
+        .into_extern_result(&data.engine.as_ref())
+}
+
+#[cfg(feature = "default-engine")]
+fn kernel_scan_result_next_arrow_impl(
+    data: &KernelScanResultIterator,
+    engine_context: NullableCvoid,
+    engine_visitor: extern "C" fn(
+        engine_context: NullableCvoid,
+        arrow_data: &mut ArrowFFIData,
+        selection_vector: KernelBoolSlice,
+    ),
+) -> DeltaResult<bool> {
+    let mut data = data
+        .data
+        .lock()
+        .map_err(|_| Error::generic("poisoned mutex"))?;
+    let Some(result) = data.next().transpose()? else {
+        return Ok(false);
+    };
+    let selection_vector = result.full_mask().unwrap_or_default();
+    let mut arrow_data = to_arrow_ffi_data(result.raw_data?)?;
+    (engine_visitor)(engine_context, &mut arrow_data, selection_vector.into());
+    // drops (i.e. releases) the array and schema, unless the engine moved them out
+    Ok(true)
+}
+
+/// # Safety
+///
+/// Caller is responsible for (at most once) passing a valid pointer returned by a call to
+/// [`kernel_scan_execute`].
+#[cfg(feature = "default-engine")]
+#[no_mangle]
+pub unsafe extern "C" fn free_kernel_scan_result(data: Handle<SharedScanResultIterator>) {
+    data.drop_handle();
+}
+
 /// Give engines an easy way to consume stats
 #[repr(C)]
 pub struct Stats {
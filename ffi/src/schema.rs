@@ -11,8 +11,8 @@ use delta_kernel::schema::{ArrayType, DataType, MapType, PrimitiveType, StructTy
 /// future.
 ///
 /// Every schema element the kernel visits belongs to some list of "sibling" elements. The schema
-/// itself is a list of schema elements, and every complex type (struct, map, array) contains a list
-/// of "child" elements.
+/// itself is a list of schema elements, and every complex type (struct, map, array, variant)
+/// contains a list of "child" elements.
 ///  1. Before visiting schema or any complex type, the kernel asks the engine to allocate a list to
 ///     hold its children
 ///  2. When visiting any schema element, the kernel passes its parent's "child list" as the
@@ -24,6 +24,7 @@ use delta_kernel::schema::{ArrayType, DataType, MapType, PrimitiveType, StructTy
 ///        type, and value nullability (keys are never nullable)
 ///      - For a list, visit the element, passing its special name ("array_element"), type, and
 ///        nullability
+///      - For a variant, visit the fields of its physical struct representation, like a struct
 ///  3. When visiting a complex schema element, the kernel also passes the "child list" containing
 ///     that element's (already-visited) children.
 ///  4. The [`visit_schema`] method returns the id of the list of top-level columns
@@ -123,6 +124,16 @@ pub struct EngineSchemaVisitor {
     /// Visit a `timestamp` with no timezone belonging to the list identified by `sibling_list_id`.
     pub visit_timestamp_ntz:
         extern "C" fn(data: *mut c_void, sibling_list_id: usize, name: KernelStringSlice),
+
+    /// Indicate that the schema contains a `Variant` type. The fields of the variant's physical
+    /// `Struct` representation (binary `metadata` and `value`) are in the list identified by
+    /// `child_list_id`.
+    pub visit_variant: extern "C" fn(
+        data: *mut c_void,
+        sibling_list_id: usize,
+        name: KernelStringSlice,
+        child_list_id: usize,
+    ),
 }
 
 /// Visit the schema of the passed `SnapshotHandle`, using the provided `visitor`. See the
@@ -180,6 +191,7 @@ pub unsafe extern "C" fn visit_schema(
         }
         match data_type {
             DataType::Struct(st) => call!(visit_struct, visit_struct_fields(visitor, st)),
+            DataType::Variant(st) => call!(visit_variant, visit_struct_fields(visitor, st)),
             DataType::Map(mt) => {
                 call!(
                     visit_map,
@@ -70,6 +70,7 @@ pub unsafe extern "C" fn get_testing_kernel_expression() -> Handle<SharedExpress
             Scalar::Long(20).into(),
         ])]),
         Expr::not(Expr::is_null(column_expr!("col"))),
+        Expr::coalesce([column_expr!("col"), Scalar::Integer(0).into()]),
     ];
     sub_exprs.extend(
         [
@@ -86,6 +87,7 @@ pub unsafe extern "C" fn get_testing_kernel_expression() -> Handle<SharedExpress
             BinaryOperator::GreaterThan,
             BinaryOperator::GreaterThanOrEqual,
             BinaryOperator::Distinct,
+            BinaryOperator::NullIf,
         ]
         .iter()
         .map(|op| Expr::binary(*op, Scalar::Integer(0), Scalar::Long(0))),
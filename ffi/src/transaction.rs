@@ -0,0 +1,232 @@
+//! Transaction related ffi code: writing to a table by committing the files written by the engine
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use delta_kernel::schema::{DataType, MapType, SchemaRef, StructField, StructType};
+use delta_kernel::transaction::{get_write_metadata_schema, CommitResult, Transaction};
+use delta_kernel::{DeltaResult, Engine, EngineData, Error, Table, Version};
+use delta_kernel_ffi_macros::handle_descriptor;
+use url::Url;
+
+use crate::engine_funcs::FileMeta;
+use crate::handle::Handle;
+use crate::{
+    unwrap_and_parse_path_as_url, ExternEngine, ExternResult, IntoExternResult, KernelStringSlice,
+    SharedExternEngine, TryFromStringSlice,
+};
+
+#[handle_descriptor(target=Transaction, mutable=true, sized=true)]
+pub struct ExclusiveTransaction;
+
+/// Start a transaction on the latest snapshot of the table at `path`. It is the responsibility of
+/// the _engine_ to either commit the transaction with [`commit`] or free it with
+/// [`free_transaction`].
+///
+/// # Safety
+///
+/// Caller is responsible for passing valid handles and path pointer.
+#[no_mangle]
+pub unsafe extern "C" fn transaction(
+    path: KernelStringSlice,
+    engine: Handle<SharedExternEngine>,
+) -> ExternResult<Handle<ExclusiveTransaction>> {
+    let url = unsafe { unwrap_and_parse_path_as_url(path) };
+    let engine = unsafe { engine.as_ref() };
+    transaction_impl(url, engine).into_extern_result(&engine)
+}
+
+fn transaction_impl(
+    url: DeltaResult<Url>,
+    extern_engine: &dyn ExternEngine,
+) -> DeltaResult<Handle<ExclusiveTransaction>> {
+    let transaction = Table::new(url?).new_transaction(extern_engine.engine().as_ref())?;
+    Ok(Box::new(transaction).into())
+}
+
+/// Abandon (and free) a transaction, without committing it.
+///
+/// # Safety
+///
+/// Caller is responsible for passing a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn free_transaction(txn: Handle<ExclusiveTransaction>) {
+    txn.drop_handle();
+}
+
+/// Set the string identifying the engine which performs the transaction, which is persisted as the
+/// `engineInfo` of the commit. This consumes the transaction and returns it, or frees it on error.
+///
+/// # Safety
+///
+/// Caller is responsible for passing valid handles and string slice.
+#[no_mangle]
+pub unsafe extern "C" fn with_engine_info(
+    txn: Handle<ExclusiveTransaction>,
+    engine_info: KernelStringSlice,
+    engine: Handle<SharedExternEngine>,
+) -> ExternResult<Handle<ExclusiveTransaction>> {
+    let txn = unsafe { txn.into_inner() };
+    let engine_info = unsafe { String::try_from_slice(&engine_info) };
+    let engine = unsafe { engine.as_ref() };
+    engine_info
+        .map(|engine_info| Box::new(txn.with_engine_info(engine_info)).into())
+        .into_extern_result(&engine)
+}
+
+/// Set the commit info of the engine, which is persisted as the `engineCommitInfo` of the commit,
+/// to the `len` entries with the given keys and values. A transaction must have commit info
+/// (possibly with no entries) to be committed. This consumes the transaction and returns it, or
+/// frees it on error.
+///
+/// # Safety
+///
+/// Caller is responsible for passing valid handles, and for `keys` and `values` to point to `len`
+/// valid string slices each (or to be null if `len` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn with_engine_commit_info(
+    txn: Handle<ExclusiveTransaction>,
+    keys: *const KernelStringSlice,
+    values: *const KernelStringSlice,
+    len: usize,
+    engine: Handle<SharedExternEngine>,
+) -> ExternResult<Handle<ExclusiveTransaction>> {
+    let txn = unsafe { txn.into_inner() };
+    let commit_info = unsafe { string_map(keys, values, len) };
+    let engine = unsafe { engine.as_ref() };
+    with_engine_commit_info_impl(*txn, commit_info, engine.engine().as_ref())
+        .into_extern_result(&engine)
+}
+
+fn with_engine_commit_info_impl(
+    txn: Transaction,
+    commit_info: DeltaResult<HashMap<String, String>>,
+    engine: &dyn Engine,
+) -> DeltaResult<Handle<ExclusiveTransaction>> {
+    let map_type = MapType::new(DataType::STRING, DataType::STRING, false);
+    let schema = StructType::new([StructField::new("engineCommitInfo", map_type, true)]);
+    let json = serde_json::json!({ "engineCommitInfo": commit_info? });
+    let commit_info = parse_json_row(engine, json, Arc::new(schema))?;
+    Ok(Box::new(txn.with_commit_info(commit_info)).into())
+}
+
+/// Add a data file written by the engine to the transaction, to be committed as an `add` action.
+/// The file has the `len` partition values with the given (physical) partition column names and
+/// (serialized) values, and the given statistics (a JSON string, as described in the Delta
+/// protocol), if any.
+///
+/// # Safety
+///
+/// Caller is responsible for passing valid handles, file and string slices, and for
+/// `partition_columns` and `partition_values` to point to `len` valid string slices each (or to be
+/// null if `len` is 0).
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn add_file(
+    mut txn: Handle<ExclusiveTransaction>,
+    file: &FileMeta,
+    partition_columns: *const KernelStringSlice,
+    partition_values: *const KernelStringSlice,
+    len: usize,
+    stats: Option<&KernelStringSlice>,
+    data_change: bool,
+    engine: Handle<SharedExternEngine>,
+) -> ExternResult<bool> {
+    let txn = unsafe { txn.as_mut() };
+    let path = unsafe { String::try_from_slice(&file.path) };
+    let partition_values = unsafe { string_map(partition_columns, partition_values, len) };
+    let stats = stats.map(|stats| unsafe { String::try_from_slice(stats) });
+    let engine = unsafe { engine.as_ref() };
+    add_file_impl(
+        txn,
+        engine.engine().as_ref(),
+        path,
+        file,
+        partition_values,
+        stats.transpose(),
+        data_change,
+    )
+    .into_extern_result(&engine)
+}
+
+fn add_file_impl(
+    txn: &mut Transaction,
+    engine: &dyn Engine,
+    path: DeltaResult<String>,
+    file: &FileMeta,
+    partition_values: DeltaResult<HashMap<String, String>>,
+    stats: DeltaResult<Option<String>>,
+    data_change: bool,
+) -> DeltaResult<bool> {
+    let json = serde_json::json!({
+        "path": path?,
+        "partitionValues": partition_values?,
+        "size": file.size,
+        "modificationTime": file.last_modified,
+        "dataChange": data_change,
+        "stats": stats?,
+    });
+    let write_metadata = parse_json_row(engine, json, get_write_metadata_schema().clone())?;
+    txn.add_write_metadata(write_metadata);
+    Ok(true)
+}
+
+/// Commit the transaction, returning the version it committed. This consumes (and frees) the
+/// transaction, whether or not the commit succeeds. If another writer committed a version which
+/// conflicts with the transaction, the commit fails and the engine may retry the transaction from
+/// the start.
+///
+/// # Safety
+///
+/// Caller is responsible for passing valid handles.
+#[no_mangle]
+pub unsafe extern "C" fn commit(
+    txn: Handle<ExclusiveTransaction>,
+    engine: Handle<SharedExternEngine>,
+) -> ExternResult<Version> {
+    let txn = unsafe { txn.into_inner() };
+    let engine = unsafe { engine.as_ref() };
+    commit_impl(*txn, engine.engine().as_ref()).into_extern_result(&engine)
+}
+
+fn commit_impl(txn: Transaction, engine: &dyn Engine) -> DeltaResult<Version> {
+    match txn.commit(engine)? {
+        CommitResult::Committed(version) => Ok(version),
+        CommitResult::Conflict(txn, version) => Err(Error::generic(format!(
+            "The transaction conflicts with the commit of version {version}: {:?}",
+            txn.conflict()
+        ))),
+    }
+}
+
+// The map of the `len` keys to the `len` values
+unsafe fn string_map(
+    keys: *const KernelStringSlice,
+    values: *const KernelStringSlice,
+    len: usize,
+) -> DeltaResult<HashMap<String, String>> {
+    if len == 0 {
+        return Ok(HashMap::new());
+    }
+    let keys = unsafe { std::slice::from_raw_parts(keys, len) };
+    let values = unsafe { std::slice::from_raw_parts(values, len) };
+    keys.iter()
+        .zip(values)
+        .map(|(key, value)| unsafe {
+            Ok((String::try_from_slice(key)?, String::try_from_slice(value)?))
+        })
+        .collect()
+}
+
+// Convert `json` into a single row of engine data with the given schema
+fn parse_json_row(
+    engine: &dyn Engine,
+    json: serde_json::Value,
+    schema: SchemaRef,
+) -> DeltaResult<Box<dyn EngineData>> {
+    let json_schema = StructType::new([StructField::new("json", DataType::STRING, false)]);
+    let json = engine
+        .get_expression_handler()
+        .create_one(Arc::new(json_schema), &[json.to_string().into()])?;
+    engine.get_json_handler().parse_json(json, schema)
+}
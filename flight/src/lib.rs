@@ -0,0 +1,292 @@
+//! Serve the scan of a Delta table as an [Arrow Flight] endpoint.
+//!
+//! A [`DeltaFlightService`] plans the files of a kernel [`Scan`] into splits of (at most)
+//! [`DeltaFlightService::with_files_per_split`] files each. `GetFlightInfo` returns the schema of
+//! the scan, and an endpoint for each split, whose ticket the client redeems with `DoGet` to read
+//! the data of the split (without the rows deleted by deletion vectors). Remote clients can thus
+//! read Delta tables (in parallel, one split at a time) with any Arrow Flight client.
+//!
+//! ```ignore
+//! let service = DeltaFlightService::try_new(scan, engine)?.with_files_per_split(4)?;
+//! Server::builder()
+//!     .add_service(FlightServiceServer::new(service))
+//!     .serve(addr)
+//!     .await?;
+//! ```
+//!
+//! [Arrow Flight]: https://arrow.apache.org/docs/format/Flight.html
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use arrow::datatypes::{Schema as ArrowSchema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::FlightService;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use delta_kernel::scan::Scan;
+use delta_kernel::{DeltaResult, Engine, Error};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+
+mod split;
+
+use split::{num_records, read_files, scan_files, split_index, ticket, ScanState};
+
+type BoxedStream<T> = Pin<Box<dyn futures::Stream<Item = Result<T, Status>> + Send + 'static>>;
+
+/// A [`FlightService`] which serves the data of a [`Scan`], split into groups of files.
+pub struct DeltaFlightService {
+    state: Arc<ScanState>,
+    files_per_split: usize,
+    schema: SchemaRef,
+}
+
+impl DeltaFlightService {
+    /// Create a service for the scan, with a split for each file of the scan. This plans the
+    /// files of the scan (by replaying the log of the table) with `engine`, which the service
+    /// then reads the files with.
+    pub fn try_new(scan: Scan, engine: Arc<dyn Engine>) -> DeltaResult<Self> {
+        let schema = Arc::new(ArrowSchema::try_from(scan.schema().as_ref())?);
+        let files = scan_files(&scan, engine.as_ref())?;
+        let global_state = scan.global_scan_state();
+        Ok(Self {
+            state: Arc::new(ScanState {
+                scan,
+                global_state,
+                engine,
+                files,
+            }),
+            files_per_split: 1,
+            schema,
+        })
+    }
+
+    /// Group the files of the scan into splits of (at most) `files_per_split` files each, which
+    /// must be at least 1.
+    pub fn with_files_per_split(mut self, files_per_split: usize) -> DeltaResult<Self> {
+        if files_per_split == 0 {
+            return Err(Error::generic("A split must have at least one file"));
+        }
+        self.files_per_split = files_per_split;
+        Ok(self)
+    }
+
+    /// The number of splits (and so of endpoints) of the scan.
+    pub fn num_splits(&self) -> usize {
+        self.state.files.chunks(self.files_per_split).len()
+    }
+
+    fn flight_info(&self, descriptor: FlightDescriptor) -> Result<FlightInfo, Status> {
+        let mut info = FlightInfo::new()
+            .try_with_schema(&self.schema)
+            .map_err(|err| Status::internal(err.to_string()))?
+            .with_descriptor(descriptor);
+        for index in 0..self.num_splits() {
+            info =
+                info.with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(ticket(index))));
+        }
+        match num_records(&self.state.files) {
+            Some(total) => Ok(info.with_total_records(total as i64)),
+            None => Ok(info),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl FlightService for DeltaFlightService {
+    type HandshakeStream = BoxedStream<HandshakeResponse>;
+    type ListFlightsStream = BoxedStream<FlightInfo>;
+    type DoGetStream = BoxedStream<FlightData>;
+    type DoPutStream = BoxedStream<PutResult>;
+    type DoActionStream = BoxedStream<arrow_flight::Result>;
+    type ListActionsStream = BoxedStream<ActionType>;
+    type DoExchangeStream = BoxedStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("Handshakes are not supported"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let info = self.flight_info(FlightDescriptor::new_path(vec![]))?;
+        Ok(Response::new(stream::iter([Ok(info)]).boxed()))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Ok(Response::new(self.flight_info(request.into_inner())?))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented(
+            "The scan is planned upfront, use GetFlightInfo",
+        ))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        let options = IpcWriteOptions::default();
+        let schema = SchemaAsIpc::new(&self.schema, &options)
+            .try_into()
+            .map_err(|err: ArrowError| Status::internal(err.to_string()))?;
+        Ok(Response::new(schema))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let ticket = request.into_inner().ticket;
+        let index = split_index(&ticket)
+            .filter(|index| *index < self.num_splits())
+            .ok_or_else(|| Status::invalid_argument("Not a ticket of a split of the scan"))?;
+
+        // the kernel reads the files with blocking calls, so they are read on a blocking thread
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let state = self.state.clone();
+        let files_per_split = self.files_per_split;
+        tokio::task::spawn_blocking(move || {
+            let start = index * files_per_split;
+            let end = state.files.len().min(start + files_per_split);
+            let result = read_files(
+                &state.files[start..end],
+                &state.scan,
+                &state.global_state,
+                state.engine.as_ref(),
+                |batch| tx.blocking_send(Ok(batch)).is_ok(),
+            );
+            if let Err(err) = result {
+                let _ = tx.blocking_send(Err(FlightError::ExternalError(Box::new(err))));
+            }
+        });
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(self.schema.clone())
+            .build(ReceiverStream::new(rx))
+            .map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("Writing is not supported"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("No actions are supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        let actions: BoxStream<'static, _> = stream::empty().boxed();
+        Ok(Response::new(actions))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("Exchanging data is not supported"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use arrow::array::{Array, Int32Array};
+    use arrow_flight::decode::FlightRecordBatchStream;
+    use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use delta_kernel::engine::default::DefaultEngine;
+    use delta_kernel::Table;
+
+    use super::*;
+
+    fn service(path: &str) -> DeltaFlightService {
+        let path = std::fs::canonicalize(PathBuf::from(path)).unwrap();
+        let table = Table::try_from_uri(path.to_string_lossy()).unwrap();
+        let engine: Arc<dyn Engine> = Arc::new(
+            DefaultEngine::try_new(
+                table.location(),
+                std::iter::empty::<(&str, &str)>(),
+                Arc::new(TokioBackgroundExecutor::new()),
+            )
+            .unwrap(),
+        );
+        let snapshot = Arc::new(table.snapshot(engine.as_ref(), None).unwrap());
+        let scan = snapshot.scan_builder().build().unwrap();
+        DeltaFlightService::try_new(scan, engine).unwrap()
+    }
+
+    async fn read_values(service: &DeltaFlightService) -> Vec<i32> {
+        let descriptor = FlightDescriptor::new_path(vec!["table".to_string()]);
+        let info = service
+            .get_flight_info(Request::new(descriptor))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(info.endpoint.len(), service.num_splits());
+        let mut values = vec![];
+        for endpoint in info.endpoint {
+            let ticket = endpoint.ticket.unwrap();
+            let data = service.do_get(Request::new(ticket)).await.unwrap();
+            let batches: Vec<_> = FlightRecordBatchStream::new_from_flight_data(
+                data.into_inner().map_err(FlightError::Tonic),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+            for batch in batches {
+                let column = batch.column(0).as_any().downcast_ref::<Int32Array>();
+                values.extend(column.unwrap().values().iter().copied());
+            }
+        }
+        values.sort();
+        values
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_do_get() {
+        // rows 0 and 9 are deleted by the deletion vector
+        let service = service("../kernel/tests/data/table-with-dv-small/");
+        assert_eq!(read_values(&service).await, (1..=8).collect::<Vec<_>>());
+
+        let service = service.with_files_per_split(2).unwrap();
+        assert_eq!(read_values(&service).await, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_invalid_ticket() {
+        let service = service("../kernel/tests/data/table-with-dv-small/");
+        let ticket = Ticket::new(ticket(service.num_splits()));
+        let err = service.do_get(Request::new(ticket)).await.err().unwrap();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(service.with_files_per_split(0).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! The files of a scan, which are grouped into splits that are each read by one `DoGet` request.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::record_batch::RecordBatch;
+use delta_kernel::actions::deletion_vector::split_vector;
+use delta_kernel::engine::arrow_data::ArrowEngineData;
+use delta_kernel::scan::state::{visit_scan_files, DvInfo, GlobalScanState, Stats};
+use delta_kernel::scan::{transform_to_logical, Scan};
+use delta_kernel::{DeltaResult, Engine, FileMeta};
+use url::Url;
+
+/// A file to read for a scan, as visited by [`visit_scan_files`].
+#[derive(Debug, Clone)]
+pub(crate) struct ScanFile {
+    path: String,
+    size: i64,
+    num_records: Option<u64>,
+    dv_info: DvInfo,
+    partition_values: HashMap<String, String>,
+}
+
+fn scan_file_callback(
+    files: &mut Vec<ScanFile>,
+    path: &str,
+    size: i64,
+    stats: Option<Stats>,
+    dv_info: DvInfo,
+    partition_values: HashMap<String, String>,
+) {
+    files.push(ScanFile {
+        path: path.to_string(),
+        size,
+        num_records: stats.map(|stats| stats.num_records),
+        dv_info,
+        partition_values,
+    });
+}
+
+/// The exact number of rows of the files, if all of them have statistics and none of them has a
+/// deletion vector (since the statistics don't reflect the deleted rows).
+pub(crate) fn num_records(files: &[ScanFile]) -> Option<u64> {
+    files
+        .iter()
+        .map(|file| file.num_records.filter(|_| !file.dv_info.has_vector()))
+        .sum()
+}
+
+/// The files to read for `scan`.
+pub(crate) fn scan_files(scan: &Scan, engine: &dyn Engine) -> DeltaResult<Vec<ScanFile>> {
+    let mut files = vec![];
+    for scan_data in scan.scan_data(engine)? {
+        let (data, selection_vector) = scan_data?;
+        files = visit_scan_files(data.as_ref(), &selection_vector, files, scan_file_callback)?;
+    }
+    Ok(files)
+}
+
+/// Read the files of a split (without the rows deleted by deletion vectors), and send each record
+/// batch to `send`, until it returns `false`.
+pub(crate) fn read_files(
+    files: &[ScanFile],
+    scan: &Scan,
+    global_state: &GlobalScanState,
+    engine: &dyn Engine,
+    mut send: impl FnMut(RecordBatch) -> bool,
+) -> DeltaResult<()> {
+    let table_root = Url::parse(&global_state.table_root)?;
+    for file in files {
+        let mut selection_vector = file.dv_info.get_selection_vector(engine, &table_root)?;
+        let meta = FileMeta {
+            last_modified: 0,
+            size: file.size as usize,
+            location: scan.file_url(&file.path)?,
+        };
+        // skipping rows would shift the rows selected by the deletion vector
+        let predicate = match file.dv_info.has_vector() {
+            true => None,
+            false => scan.parquet_predicate(),
+        };
+        let read_results = engine.get_parquet_handler().read_parquet_files(
+            &[meta],
+            global_state.read_schema.clone(),
+            predicate,
+        )?;
+        for data in read_results {
+            let data = data?;
+            let len = data.len();
+            let logical = transform_to_logical(engine, data, global_state, &file.partition_values)?;
+            // what's left in the selection vector covers the following batches
+            let rest = split_vector(selection_vector.as_mut(), len, Some(true));
+            let batch = ArrowEngineData::try_into_selected_record_batch(logical, selection_vector)?;
+            selection_vector = rest;
+            if !send(batch) {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The ticket of the split at `index`.
+pub(crate) fn ticket(index: usize) -> Vec<u8> {
+    index.to_string().into_bytes()
+}
+
+/// The index of the split of the ticket, if it's a valid ticket.
+pub(crate) fn split_index(ticket: &[u8]) -> Option<usize> {
+    std::str::from_utf8(ticket).ok()?.parse().ok()
+}
+
+/// The scan, its files and the engine which reads them, shared by the requests of a service.
+pub(crate) struct ScanState {
+    pub(crate) scan: Scan,
+    pub(crate) global_state: GlobalScanState,
+    pub(crate) engine: Arc<dyn Engine>,
+    pub(crate) files: Vec<ScanFile>,
+}
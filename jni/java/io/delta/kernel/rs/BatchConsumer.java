@@ -0,0 +1,13 @@
+package io.delta.kernel.rs;
+
+/** Receives the record batches of a scan, each one as an Arrow IPC stream. */
+public interface BatchConsumer {
+  /** Called once for each batch of the scan, in order. */
+  void accept(byte[] batch);
+
+  /**
+   * Called once after the last batch of an asynchronous read, with the message of the error which
+   * stopped the read, or null if it succeeded. Synchronous reads throw their errors instead.
+   */
+  default void done(String error) {}
+}
@@ -0,0 +1,8 @@
+package io.delta.kernel.rs;
+
+/** An error of the native kernel, e.g. reading a table which doesn't exist. */
+public class DeltaKernelException extends RuntimeException {
+  public DeltaKernelException(String message) {
+    super(message);
+  }
+}
@@ -0,0 +1,49 @@
+package io.delta.kernel.rs;
+
+/**
+ * The native methods of the delta_kernel_jni library.
+ *
+ * <p>Tables, snapshots and scans are opaque handles, which must be closed once they're not needed
+ * anymore. Using a closed handle (or a handle of another kind) throws, and closing a handle again
+ * does nothing. Schemas and batches are Arrow IPC streams, which can be read with
+ * {@code org.apache.arrow.vector.ipc.ArrowStreamReader}. Failures throw a {@link
+ * DeltaKernelException}.
+ */
+public final class NativeKernel {
+  static {
+    System.loadLibrary("delta_kernel_jni");
+  }
+
+  private NativeKernel() {}
+
+  /** Opens the table at {@code location}, with the storage options of its object store. */
+  public static native long openTable(String location, String[] optionKeys, String[] optionValues);
+
+  public static native void closeTable(long table);
+
+  /** A snapshot of the table at {@code version}, or at its latest version if it's negative. */
+  public static native long snapshot(long table, long version);
+
+  public static native long snapshotVersion(long snapshot);
+
+  public static native byte[] snapshotSchema(long snapshot);
+
+  public static native void closeSnapshot(long snapshot);
+
+  /** A scan of the given columns of the snapshot, or of all of them if {@code columns} is null. */
+  public static native long scan(long snapshot, String[] columns);
+
+  public static native byte[] scanSchema(long scan);
+
+  /** Reads the batches of the scan on the calling thread. */
+  public static native void readBatches(long scan, BatchConsumer consumer);
+
+  /**
+   * Reads the batches of the scan on a native thread, and returns once the thread is attached to
+   * the JVM. {@link BatchConsumer#done} is called when the read is over, unless this throws.
+   * Closing the scan doesn't stop the read.
+   */
+  public static native void readBatchesAsync(long scan, BatchConsumer consumer);
+
+  public static native void closeScan(long scan);
+}
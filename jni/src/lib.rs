@@ -0,0 +1,492 @@
+//! JNI bindings for delta kernel, for JVM query engines.
+//!
+//! The native methods of `io.delta.kernel.rs.NativeKernel` (see the `java` directory) open a
+//! [`Table`], get a [`Snapshot`] of it, and scan the snapshot with the default engine. Kernel
+//! objects are handed to the JVM as opaque `long` ids of a [`Registry`] (the registry type of the C
+//! FFI, with tags of its own), so using a handle which was closed, or a handle of the wrong kind,
+//! throws rather than crashing the JVM. Closing a handle which is already closed does nothing. Schemas and record
+//! batches are exchanged as byte arrays in the [Arrow IPC stream
+//! format](https://arrow.apache.org/docs/format/Columnar.html#ipc-streaming-format), which the JVM
+//! reads with `org.apache.arrow.vector.ipc.ArrowStreamReader` -- so the JVM and the kernel don't
+//! have to agree on the version of arrow, or on the layout of any native structure.
+//!
+//! Batches are passed to a `BatchConsumer`. [`readBatchesAsync`] reads them on a kernel-owned
+//! thread, which attaches itself to the JVM for the duration of the read (and detaches when done),
+//! so that the engine doesn't have to bridge threads itself.
+//!
+//! Failures throw a `io.delta.kernel.rs.DeltaKernelException`.
+//!
+//! [`Table`]: delta_kernel::Table
+//! [`readBatchesAsync`]: Java_io_delta_kernel_rs_NativeKernel_readBatchesAsync
+
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc};
+
+use arrow::datatypes::Schema as ArrowSchema;
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
+use delta_kernel::engine::default::DefaultEngine;
+use delta_kernel::scan::Scan;
+use delta_kernel::schema::Schema;
+use delta_kernel::snapshot::Snapshot;
+use delta_kernel::{Error, Table};
+use delta_kernel_ffi::registry::{KernelHandleId, Registry, MAX_FFI_REGISTRY_TAG};
+use jni::objects::{GlobalRef, JClass, JObject, JObjectArray, JString, JValue};
+use jni::sys::{jbyteArray, jlong};
+use jni::JNIEnv;
+
+const EXCEPTION_CLASS: &str = "io/delta/kernel/rs/DeltaKernelException";
+
+type JniEngine = Arc<DefaultEngine<TokioBackgroundExecutor>>;
+
+/// The errors of a native method: of the kernel, or of the JVM (e.g. a pending Java exception
+/// thrown by a callback).
+#[derive(Debug)]
+enum JniError {
+    Kernel(Error),
+    Jni(jni::errors::Error),
+}
+
+impl From<Error> for JniError {
+    fn from(err: Error) -> Self {
+        Self::Kernel(err)
+    }
+}
+
+impl From<jni::errors::Error> for JniError {
+    fn from(err: jni::errors::Error) -> Self {
+        Self::Jni(err)
+    }
+}
+
+impl From<arrow::error::ArrowError> for JniError {
+    fn from(err: arrow::error::ArrowError) -> Self {
+        Self::Kernel(err.into())
+    }
+}
+
+type JniResult<T> = Result<T, JniError>;
+
+/// Return the value of a native method, or throw a `DeltaKernelException` (unless a Java
+/// exception is already pending) and return the default value, which the JVM ignores.
+fn or_throw<T: Default>(env: &mut JNIEnv<'_>, result: JniResult<T>) -> T {
+    let msg = match result {
+        Ok(value) => return value,
+        Err(JniError::Kernel(err)) => err.to_string(),
+        Err(JniError::Jni(err)) => err.to_string(),
+    };
+    if !env.exception_check().unwrap_or(false) {
+        // If even throwing fails there is nothing left to report the error with
+        let _ = env.throw_new(EXCEPTION_CLASS, msg);
+    }
+    T::default()
+}
+
+static TABLES: Registry<JniTable> = Registry::new(MAX_FFI_REGISTRY_TAG + 1, "table");
+static SNAPSHOTS: Registry<JniSnapshot> = Registry::new(MAX_FFI_REGISTRY_TAG + 2, "snapshot");
+static SCANS: Registry<JniScan> = Registry::new(MAX_FFI_REGISTRY_TAG + 3, "scan");
+
+fn into_handle<T>(registry: &Registry<T>, value: T) -> JniResult<jlong> {
+    Ok(registry.insert(Arc::new(value))? as jlong)
+}
+
+/// Resolve a handle of the JVM, which is an error if it was closed or is of another registry.
+fn resolve<T>(registry: &Registry<T>, handle: jlong) -> JniResult<Arc<T>> {
+    Ok(registry
+        .resolve(handle as KernelHandleId)
+        .map_err(Error::generic)?)
+}
+
+/// Close a handle of the JVM. The object is dropped once no read of it is in progress anymore.
+fn close<T>(registry: &Registry<T>, handle: jlong) {
+    registry.remove(handle as KernelHandleId);
+}
+
+fn strings(env: &mut JNIEnv<'_>, array: &JObjectArray<'_>) -> JniResult<Vec<String>> {
+    if array.is_null() {
+        return Ok(vec![]);
+    }
+    let len = env.get_array_length(array)?;
+    (0..len)
+        .map(|index| {
+            let string = JString::from(env.get_object_array_element(array, index)?);
+            Ok(env.get_string(&string)?.into())
+        })
+        .collect()
+}
+
+/// Encode the arrow schema of a kernel schema as an Arrow IPC stream without batches.
+fn schema_to_ipc(schema: &Schema) -> JniResult<Vec<u8>> {
+    let schema = ArrowSchema::try_from(schema).map_err(Error::from)?;
+    let mut writer = StreamWriter::try_new(vec![], &schema)?;
+    writer.finish()?;
+    Ok(writer.into_inner()?)
+}
+
+fn batch_to_ipc(batch: &RecordBatch) -> JniResult<Vec<u8>> {
+    let mut writer = StreamWriter::try_new(vec![], &batch.schema())?;
+    writer.write(batch)?;
+    writer.finish()?;
+    Ok(writer.into_inner()?)
+}
+
+fn byte_array(env: &mut JNIEnv<'_>, bytes: &[u8]) -> JniResult<jbyteArray> {
+    Ok(env.byte_array_from_slice(bytes)?.into_raw())
+}
+
+/// A table, with the engine which reads it.
+struct JniTable {
+    table: Table,
+    engine: JniEngine,
+}
+
+struct JniSnapshot {
+    snapshot: Arc<Snapshot>,
+    engine: JniEngine,
+}
+
+struct JniScan {
+    scan: Scan,
+    engine: JniEngine,
+}
+
+impl JniScan {
+    /// Read the batches of the scan (without the rows deleted by deletion vectors), and pass each
+    /// one to `f`.
+    fn read_batches(&self, mut f: impl FnMut(RecordBatch) -> JniResult<()>) -> JniResult<()> {
+        for result in self.scan.execute(self.engine.clone())? {
+            f(RecordBatch::try_from(result?)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn open_table(location: &str, options: HashMap<String, String>) -> JniResult<jlong> {
+    let table = Table::try_from_uri(location)?;
+    let engine = DefaultEngine::try_new(
+        table.location(),
+        options,
+        Arc::new(TokioBackgroundExecutor::new()),
+    )?;
+    let engine = Arc::new(engine);
+    into_handle(&TABLES, JniTable { table, engine })
+}
+
+fn snapshot(table: jlong, version: jlong) -> JniResult<jlong> {
+    let table = resolve(&TABLES, table)?;
+    let version = u64::try_from(version).ok();
+    let snapshot = table.table.snapshot(table.engine.as_ref(), version)?;
+    let snapshot = JniSnapshot {
+        snapshot: Arc::new(snapshot),
+        engine: table.engine.clone(),
+    };
+    into_handle(&SNAPSHOTS, snapshot)
+}
+
+fn scan(snapshot: jlong, columns: Option<Vec<String>>) -> JniResult<jlong> {
+    let snapshot = resolve(&SNAPSHOTS, snapshot)?;
+    let schema = columns
+        .map(|columns| snapshot.snapshot.schema().project(&columns))
+        .transpose()?;
+    let scan = snapshot
+        .snapshot
+        .clone()
+        .scan_builder()
+        .with_schema_opt(schema)
+        .build()?;
+    let engine = snapshot.engine.clone();
+    into_handle(&SCANS, JniScan { scan, engine })
+}
+
+/// Pass a batch to the `accept(byte[])` method of a `BatchConsumer`.
+fn accept_batch(
+    env: &mut JNIEnv<'_>,
+    consumer: &JObject<'_>,
+    batch: &RecordBatch,
+) -> JniResult<()> {
+    let bytes = batch_to_ipc(batch)?;
+    let array = env.byte_array_from_slice(&bytes)?;
+    env.call_method(consumer, "accept", "([B)V", &[JValue::Object(&array)])?;
+    // Batches can be many, so don't wait for the native method to return to free the array
+    env.delete_local_ref(array)?;
+    Ok(())
+}
+
+/// Open the table at `location`. The storage options (e.g. credentials) are passed to the object
+/// store of the engine, as parallel arrays of keys and values.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_openTable<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    location: JString<'local>,
+    option_keys: JObjectArray<'local>,
+    option_values: JObjectArray<'local>,
+) -> jlong {
+    let result = (|| -> JniResult<jlong> {
+        let location: String = env.get_string(&location)?.into();
+        let keys = strings(&mut env, &option_keys)?;
+        let values = strings(&mut env, &option_values)?;
+        open_table(&location, keys.into_iter().zip(values).collect())
+    })();
+    or_throw(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_closeTable<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    table: jlong,
+) {
+    close(&TABLES, table)
+}
+
+/// Get a snapshot of the table at `version`, or at its latest version if `version` is negative.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_snapshot<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    table: jlong,
+    version: jlong,
+) -> jlong {
+    let result = snapshot(table, version);
+    or_throw(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_closeSnapshot<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    snapshot: jlong,
+) {
+    close(&SNAPSHOTS, snapshot)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_snapshotVersion<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    snapshot: jlong,
+) -> jlong {
+    let result = resolve(&SNAPSHOTS, snapshot).map(|snapshot| snapshot.snapshot.version() as jlong);
+    or_throw(&mut env, result)
+}
+
+/// The schema of the table, as an Arrow IPC stream.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_snapshotSchema<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    snapshot: jlong,
+) -> jbyteArray {
+    let result = resolve(&SNAPSHOTS, snapshot)
+        .and_then(|snapshot| schema_to_ipc(snapshot.snapshot.schema()))
+        .and_then(|bytes| byte_array(&mut env, &bytes));
+    or_throw(&mut env, result)
+}
+
+/// A scan of the given columns of the snapshot, or of all of its columns if `columns` is null.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_scan<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    snapshot: jlong,
+    columns: JObjectArray<'local>,
+) -> jlong {
+    let result = (|| -> JniResult<jlong> {
+        let columns = match columns.is_null() {
+            true => None,
+            false => Some(strings(&mut env, &columns)?),
+        };
+        scan(snapshot, columns)
+    })();
+    or_throw(&mut env, result)
+}
+
+/// Close a scan. Reads of the scan which are in progress keep going.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_closeScan<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    scan: jlong,
+) {
+    close(&SCANS, scan)
+}
+
+/// The schema of the data of the scan, as an Arrow IPC stream.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_scanSchema<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    scan: jlong,
+) -> jbyteArray {
+    let result = resolve(&SCANS, scan)
+        .and_then(|scan| schema_to_ipc(scan.scan.schema()))
+        .and_then(|bytes| byte_array(&mut env, &bytes));
+    or_throw(&mut env, result)
+}
+
+/// Read the data of the scan on the calling thread, passing each batch (as an Arrow IPC stream)
+/// to `consumer.accept`. An exception thrown by the consumer stops the read and is rethrown.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_readBatches<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    scan: jlong,
+    consumer: JObject<'local>,
+) {
+    let result = resolve(&SCANS, scan)
+        .and_then(|scan| scan.read_batches(|batch| accept_batch(&mut env, &consumer, &batch)));
+    or_throw(&mut env, result)
+}
+
+/// Read the data of the scan on a kernel-owned thread (attached to the JVM while it reads),
+/// passing each batch to `consumer.accept`, and then calling `consumer.done` with null, or with
+/// the message of the error which stopped the read. Returns once the thread is attached to the
+/// JVM; if it can't attach, `consumer.done` is never called and the failure is thrown instead.
+#[no_mangle]
+pub extern "system" fn Java_io_delta_kernel_rs_NativeKernel_readBatchesAsync<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    scan: jlong,
+    consumer: JObject<'local>,
+) {
+    let result = (|| -> JniResult<()> {
+        let scan = resolve(&SCANS, scan)?;
+        let vm = env.get_java_vm()?;
+        // A global reference keeps the consumer alive (and usable from other threads) after this
+        // method returns
+        let consumer: GlobalRef = env.new_global_ref(consumer)?;
+        let (attached_tx, attached_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            // The guard detaches the thread from the JVM when it's dropped
+            let mut env = match vm.attach_current_thread() {
+                Ok(env) => env,
+                Err(err) => {
+                    let _ = attached_tx.send(Some(err.to_string()));
+                    return;
+                }
+            };
+            let _ = attached_tx.send(None);
+            let result =
+                scan.read_batches(|batch| accept_batch(&mut env, consumer.as_obj(), &batch));
+            let error = match result {
+                Ok(()) => None,
+                Err(JniError::Kernel(err)) => Some(err.to_string()),
+                Err(JniError::Jni(err)) => {
+                    // Report (and clear) an exception thrown by the consumer, rather than leaving
+                    // it pending on a thread which is about to be detached
+                    let _ = env.exception_describe();
+                    let _ = env.exception_clear();
+                    Some(err.to_string())
+                }
+            };
+            let done = || -> JniResult<()> {
+                let error = match error {
+                    Some(msg) => JObject::from(env.new_string(msg)?),
+                    None => JObject::null(),
+                };
+                let args = [JValue::Object(&error)];
+                env.call_method(consumer.as_obj(), "done", "(Ljava/lang/String;)V", &args)?;
+                Ok(())
+            };
+            if done().is_err() {
+                let _ = env.exception_clear();
+            }
+        });
+        // Without the JVM the thread can't call `consumer.done`, so wait for it to attach and
+        // throw here if it couldn't, rather than leaving the caller waiting for `done` forever
+        match attached_rx.recv() {
+            Ok(None) => Ok(()),
+            Ok(Some(msg)) => Err(Error::generic(format!(
+                "Failed to attach the read thread to the JVM: {msg}"
+            ))
+            .into()),
+            Err(_) => {
+                Err(Error::generic("The read thread stopped before attaching to the JVM").into())
+            }
+        }
+    })();
+    or_throw(&mut env, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int32Type;
+    use arrow::ipc::reader::StreamReader;
+
+    use super::*;
+
+    fn table_path(name: &str) -> String {
+        format!(
+            "{}/../kernel/tests/data/{name}/",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    }
+
+    fn read_ipc(bytes: Vec<u8>) -> (Arc<ArrowSchema>, Vec<RecordBatch>) {
+        let reader = StreamReader::try_new(Cursor::new(bytes), None).unwrap();
+        let schema = reader.schema();
+        (schema, reader.map(Result::unwrap).collect())
+    }
+
+    #[test]
+    fn test_read_table() {
+        let table = open_table(&table_path("table-with-dv-small"), HashMap::new()).unwrap();
+        let snapshot = snapshot(table, -1).unwrap();
+        assert_eq!(resolve(&SNAPSHOTS, snapshot).unwrap().snapshot.version(), 1);
+        let scan = scan(snapshot, Some(vec!["value".to_string()])).unwrap();
+
+        // the scan outlives the snapshot and the table it was made from
+        close(&SNAPSHOTS, snapshot);
+        close(&TABLES, table);
+        let scan = resolve(&SCANS, scan).unwrap();
+        let (schema, batches) = read_ipc(schema_to_ipc(scan.scan.schema()).unwrap());
+        assert_eq!(schema.fields().len(), 1);
+        assert_eq!(schema.field(0).name(), "value");
+        assert!(batches.is_empty());
+
+        let mut values = vec![];
+        scan.read_batches(|batch| {
+            let (_, batches) = read_ipc(batch_to_ipc(&batch)?);
+            assert_eq!(batches, [batch]);
+            let column = batches[0].column(0).as_primitive::<Int32Type>();
+            values.extend(column.values().iter().copied());
+            Ok(())
+        })
+        .unwrap();
+        // the deletion vector deletes the first and last rows
+        assert_eq!(values, (1..9).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_invalid_handles() {
+        let table = open_table(&table_path("basic_partitioned"), HashMap::new()).unwrap();
+        let snapshot = snapshot(table, 0).unwrap();
+
+        // a handle of another kind is invalid
+        assert!(matches!(
+            scan(table, None),
+            Err(JniError::Kernel(Error::Generic(msg))) if msg.contains("snapshot handle")
+        ));
+        assert!(resolve(&SCANS, snapshot).is_err());
+
+        // a closed handle is invalid, and closing it again does nothing
+        close(&SNAPSHOTS, snapshot);
+        assert!(resolve(&SNAPSHOTS, snapshot).is_err());
+        close(&SNAPSHOTS, snapshot);
+        close(&TABLES, table);
+        assert!(matches!(
+            super::snapshot(table, 0),
+            Err(JniError::Kernel(Error::Generic(msg))) if msg.contains("table handle")
+        ));
+
+        assert!(matches!(
+            open_table(&table_path("no-such-table"), HashMap::new()),
+            Err(JniError::Kernel(Error::InvalidTableLocation(_)))
+        ));
+    }
+}
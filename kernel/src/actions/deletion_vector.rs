@@ -4,7 +4,8 @@ use std::io::{Cursor, Read};
 use std::sync::Arc;
 
 use bytes::Bytes;
-use roaring::RoaringTreemap;
+use itertools::Itertools;
+use roaring::{RoaringBitmap, RoaringTreemap};
 use url::Url;
 
 use delta_kernel_derive::Schema;
@@ -12,8 +13,43 @@ use delta_kernel_derive::Schema;
 use crate::utils::require;
 use crate::{DeltaResult, Error, FileSystemClient};
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
-#[cfg_attr(test, derive(serde::Serialize), serde(rename_all = "camelCase"))]
+/// Magic number of a `RoaringBitmapArray` in the portable (i.e. 64-bit `RoaringTreemap`
+/// compatible) serialization format.
+const PORTABLE_BITMAP_ARRAY_MAGIC: u32 = 1681511377;
+/// Magic number of a `RoaringBitmapArray` in the native serialization format, which stores the
+/// number of bitmaps followed by each 32-bit bitmap, without their (implicit) high bits.
+const NATIVE_BITMAP_ARRAY_MAGIC: u32 = 1681511376;
+/// Version of the deletion vector file format written by the kernel.
+const DV_FILE_FORMAT_VERSION: u8 = 1;
+
+/// How a deletion vector is stored, as given by [`DeletionVectorDescriptor::storage_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletionVectorStorageType {
+    /// `'u'`: stored in a file relative to the table root, whose name is derived from a UUID
+    PersistedRelative,
+    /// `'i'`: stored inline in the log, as z85 encoded bytes
+    Inline,
+    /// `'p'`: stored in a file with an absolute path
+    PersistedAbsolute,
+}
+
+impl TryFrom<&str> for DeletionVectorStorageType {
+    type Error = Error;
+
+    fn try_from(storage_type: &str) -> DeltaResult<Self> {
+        match storage_type {
+            "u" => Ok(Self::PersistedRelative),
+            "i" => Ok(Self::Inline),
+            "p" => Ok(Self::PersistedAbsolute),
+            other => Err(Error::DeletionVector(format!(
+                "Unknown storage format: '{other}'."
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Schema, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeletionVectorDescriptor {
     /// A single character to indicate how to access the DV. Legal options are: ['u', 'i', 'p'].
     pub storage_type: String,
@@ -60,9 +96,16 @@ impl DeletionVectorDescriptor {
         }
     }
 
-    pub fn absolute_path(&self, parent: &Url) -> DeltaResult<Option<Url>> {
-        match self.storage_type.as_str() {
-            "u" => {
+    /// Parse the [`storage_type`](Self::storage_type) of this deletion vector.
+    pub fn parsed_storage_type(&self) -> DeltaResult<DeletionVectorStorageType> {
+        self.storage_type.as_str().try_into()
+    }
+
+    /// Get the path of the file the deletion vector is stored in, as recorded in the log: relative
+    /// to the table root, or an absolute URL. `None` if the deletion vector is stored inline.
+    pub fn path(&self) -> DeltaResult<Option<String>> {
+        match self.parsed_storage_type()? {
+            DeletionVectorStorageType::PersistedRelative => {
                 let path_len = self.path_or_inline_dv.len();
                 require!(
                     path_len >= 20,
@@ -81,21 +124,30 @@ impl DeletionVectorDescriptor {
                 } else {
                     format!("deletion_vector_{uuid}.bin")
                 };
-                let dv_path = parent
-                    .join(&dv_suffix)
-                    .map_err(|_| Error::DeletionVector(format!("invalid path: {dv_suffix}")))?;
-                Ok(Some(dv_path))
+                Ok(Some(dv_suffix))
             }
-            "p" => Ok(Some(Url::parse(&self.path_or_inline_dv).map_err(|_| {
-                Error::DeletionVector(format!("invalid path: {}", self.path_or_inline_dv))
-            })?)),
-            "i" => Ok(None),
-            other => Err(Error::DeletionVector(format!(
-                "Unknown storage format: '{other}'."
-            ))),
+            DeletionVectorStorageType::PersistedAbsolute => {
+                Ok(Some(self.path_or_inline_dv.clone()))
+            }
+            DeletionVectorStorageType::Inline => Ok(None),
         }
     }
 
+    /// Get the location of the file the deletion vector is stored in, or `None` if it is stored
+    /// inline. Relative locations are resolved against `parent`, which is the table root.
+    pub fn absolute_path(&self, parent: &Url) -> DeltaResult<Option<Url>> {
+        let Some(path) = self.path()? else {
+            return Ok(None);
+        };
+        let dv_path = match self.parsed_storage_type()? {
+            DeletionVectorStorageType::PersistedRelative => parent.join(&path),
+            _ => Url::parse(&path),
+        };
+        let dv_path =
+            dv_path.map_err(|_| Error::DeletionVector(format!("invalid path: {path}")))?;
+        Ok(Some(dv_path))
+    }
+
     /// Read a dv in stored form into a [`RoaringTreemap`]
     // A few notes:
     //  - dvs write integers in BOTH big and little endian format. The magic and dv itself are
@@ -107,23 +159,33 @@ impl DeletionVectorDescriptor {
         fs_client: Arc<dyn FileSystemClient>,
         parent: &Url,
     ) -> DeltaResult<RoaringTreemap> {
-        match self.absolute_path(parent)? {
+        self.read_from(fs_client, self.absolute_path(parent)?)
+    }
+
+    // Read the dv from the file at `location`, or from the log if it's `None` (i.e. it's inline)
+    fn read_from(
+        &self,
+        fs_client: Arc<dyn FileSystemClient>,
+        location: Option<Url>,
+    ) -> DeltaResult<RoaringTreemap> {
+        let size_in_bytes = usize::try_from(self.size_in_bytes).map_err(|_| {
+            Error::DeletionVector(format!("Invalid size in bytes: {}", self.size_in_bytes))
+        })?;
+        match location {
             None => {
                 let byte_slice = z85::decode(&self.path_or_inline_dv)
                     .map_err(|_| Error::deletion_vector("Failed to decode DV"))?;
-                let magic = slice_to_u32(&byte_slice[0..4], Endian::Little)?;
-                match magic {
-                    1681511377 => RoaringTreemap::deserialize_from(&byte_slice[4..])
-                        .map_err(|err| Error::DeletionVector(err.to_string())),
-                    1681511376 => {
-                        todo!("Don't support native serialization in inline bitmaps yet");
-                    }
-                    _ => Err(Error::DeletionVector(format!("Invalid magic {magic}"))),
-                }
+                // the encoded bytes may be padded, so only the first size_in_bytes are the DV
+                let dv_bytes = byte_slice.get(..size_in_bytes).ok_or_else(|| {
+                    Error::DeletionVector(format!(
+                        "DV size mismatch. Log indicates {size_in_bytes}, inline DV has {}",
+                        byte_slice.len()
+                    ))
+                })?;
+                deserialize_bitmap_array(dv_bytes)
             }
             Some(path) => {
                 let offset = self.offset;
-                let size_in_bytes = self.size_in_bytes;
 
                 let dv_data = fs_client
                     .read_files(vec![(path, None)])?
@@ -146,34 +208,104 @@ impl DeletionVectorDescriptor {
                 }
                 let dv_size = read_u32(&mut cursor, Endian::Big)?;
                 require!(
-                    dv_size == size_in_bytes as u32,
+                    dv_size as usize == size_in_bytes,
                     Error::DeletionVector(format!(
                         "DV size mismatch. Log indicates {size_in_bytes}, file says: {dv_size}"
                     ))
                 );
-                let magic = read_u32(&mut cursor, Endian::Little)?;
-                require!(
-                    magic == 1681511377,
-                    Error::DeletionVector(format!("Invalid magic: {magic}"))
-                );
 
-                // get the Bytes back out and limit it to dv_size
-                let position = cursor.position();
-                let mut bytes = cursor.into_inner();
-                let truncate_pos = position + dv_size as u64;
-                assert!(
-                    truncate_pos <= usize::MAX as u64,
-                    "Can't truncate as truncate_pos is > usize::MAX"
-                );
-                bytes.truncate(truncate_pos as usize);
-                let mut cursor = Cursor::new(bytes);
-                cursor.set_position(position);
-                RoaringTreemap::deserialize_from(cursor)
-                    .map_err(|err| Error::DeletionVector(err.to_string()))
+                // the DV data is followed by a checksum, which we don't need
+                let start = cursor.position() as usize;
+                let bytes = cursor.into_inner();
+                let dv_bytes = bytes.get(start..start + size_in_bytes).ok_or_else(|| {
+                    Error::DeletionVector(format!(
+                        "DV file too short: expected {size_in_bytes} bytes at {start}"
+                    ))
+                })?;
+                deserialize_bitmap_array(dv_bytes)
             }
         }
     }
 
+    /// Load this deletion vector, from the log if it is inline or from storage otherwise.
+    /// Relative paths are resolved against `table_root`. Fails if the number of deleted rows does
+    /// not match the [`cardinality`](Self::cardinality) recorded in the log.
+    pub fn load(
+        &self,
+        fs_client: Arc<dyn FileSystemClient>,
+        table_root: &Url,
+    ) -> DeltaResult<DeletionVector> {
+        self.load_from(fs_client, self.absolute_path(table_root)?)
+    }
+
+    /// Load this deletion vector like [`Self::load`], but from the file at `location` (e.g. a
+    /// pre-signed URL of its [`path`](Self::path)) rather than from the table root.
+    pub(crate) fn load_from(
+        &self,
+        fs_client: Arc<dyn FileSystemClient>,
+        location: Option<Url>,
+    ) -> DeltaResult<DeletionVector> {
+        let treemap = self.read_from(fs_client, location)?;
+        require!(
+            i64::try_from(treemap.len()).is_ok_and(|len| len == self.cardinality),
+            Error::DeletionVector(format!(
+                "DV cardinality mismatch. Log indicates {}, DV has {}",
+                self.cardinality,
+                treemap.len()
+            ))
+        );
+        Ok(DeletionVector { treemap })
+    }
+
+    /// Create a descriptor which stores `dv` inline in the log, z85 encoded.
+    pub fn inline(dv: &DeletionVector) -> DeltaResult<Self> {
+        let mut bytes = dv.serialize()?;
+        let size_in_bytes = bytes.len();
+        // z85 can only encode whole 4 byte chunks, so pad with zeros. The padding is ignored when
+        // reading since only the first `size_in_bytes` bytes are deserialized.
+        bytes.resize(size_in_bytes.div_ceil(4) * 4, 0);
+        Ok(Self {
+            storage_type: "i".to_string(),
+            path_or_inline_dv: z85::encode(bytes),
+            offset: None,
+            size_in_bytes: to_i32(size_in_bytes)?,
+            cardinality: to_i64(dv.cardinality())?,
+        })
+    }
+
+    /// Write `dv` to a new deletion vector file in `table_root`, and create a descriptor which
+    /// references it. The file name is derived from a random UUID, and the file is never
+    /// overwritten.
+    // The file holds a single DV: the format version, followed (at offset 1) by the big endian
+    // size of the DV, the DV itself, and its big endian CRC-32 checksum.
+    pub fn write(
+        dv: &DeletionVector,
+        fs_client: &dyn FileSystemClient,
+        table_root: &Url,
+    ) -> DeltaResult<Self> {
+        let data = dv.serialize()?;
+        let size_in_bytes = to_i32(data.len())?;
+        let mut file = Vec::with_capacity(data.len() + 9);
+        file.push(DV_FILE_FORMAT_VERSION);
+        file.extend_from_slice(&(size_in_bytes as u32).to_be_bytes());
+        file.extend_from_slice(&data);
+        file.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+
+        let uuid = uuid::Uuid::new_v4();
+        let descriptor = Self {
+            storage_type: "u".to_string(),
+            path_or_inline_dv: z85::encode(uuid.as_bytes()),
+            offset: Some(1),
+            size_in_bytes,
+            cardinality: to_i64(dv.cardinality())?,
+        };
+        let path = descriptor
+            .absolute_path(table_root)?
+            .ok_or_else(|| Error::internal_error("relative DV must have a path"))?;
+        fs_client.write_file(&path, file.into(), false)?;
+        Ok(descriptor)
+    }
+
     /// Materialize the row indexes of the deletion vector as a `Vec<u64>` in which each element
     /// represents a row index that is deleted from the table.
     pub fn row_indexes(
@@ -185,6 +317,116 @@ impl DeletionVectorDescriptor {
     }
 }
 
+/// A loaded deletion vector: the set of row indexes that are deleted from a data file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeletionVector {
+    treemap: RoaringTreemap,
+}
+
+impl DeletionVector {
+    /// The number of deleted rows.
+    pub fn cardinality(&self) -> u64 {
+        self.treemap.len()
+    }
+
+    /// True if no rows are deleted.
+    pub fn is_empty(&self) -> bool {
+        self.treemap.is_empty()
+    }
+
+    /// Check whether the row at `row_index` of the data file is deleted.
+    pub fn is_deleted(&self, row_index: u64) -> bool {
+        self.treemap.contains(row_index)
+    }
+
+    /// The indexes of the deleted rows, in ascending order.
+    pub fn row_indexes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.treemap.iter()
+    }
+
+    /// Convert into a selection vector, in which index `i` is `false` if row `i` is deleted. The
+    /// vector ends at the last deleted row, so rows past its end are selected (see
+    /// [`split_vector`] for how to extend it).
+    pub fn into_selection_vector(self) -> Vec<bool> {
+        treemap_to_bools(self.treemap)
+    }
+
+    /// Get the underlying bitmap of deleted row indexes.
+    pub fn into_treemap(self) -> RoaringTreemap {
+        self.treemap
+    }
+
+    /// Mark all rows deleted in `other` as deleted in this deletion vector too.
+    pub fn union_with(&mut self, other: &DeletionVector) {
+        self.treemap |= &other.treemap;
+    }
+
+    /// Serialize into a `RoaringBitmapArray` in the portable format, as stored in deletion vector
+    /// files and inline in the log.
+    pub(crate) fn serialize(&self) -> DeltaResult<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(4 + self.treemap.serialized_size());
+        bytes.extend_from_slice(&PORTABLE_BITMAP_ARRAY_MAGIC.to_le_bytes());
+        self.treemap
+            .serialize_into(&mut bytes)
+            .map_err(|err| Error::DeletionVector(err.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+impl FromIterator<u64> for DeletionVector {
+    fn from_iter<I: IntoIterator<Item = u64>>(row_indexes: I) -> Self {
+        Self {
+            treemap: row_indexes.into_iter().collect(),
+        }
+    }
+}
+
+impl From<RoaringTreemap> for DeletionVector {
+    fn from(treemap: RoaringTreemap) -> Self {
+        Self { treemap }
+    }
+}
+
+/// Deserialize a `RoaringBitmapArray`, in either the portable or the native format. See
+/// [Deletion Vector Format](https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Deletion-Vector-Format)
+fn deserialize_bitmap_array(bytes: &[u8]) -> DeltaResult<RoaringTreemap> {
+    require!(
+        bytes.len() >= 4,
+        Error::deletion_vector("DV too short to contain a magic number")
+    );
+    let (magic, bitmaps) = bytes.split_at(4);
+    match slice_to_u32(magic, Endian::Little)? {
+        PORTABLE_BITMAP_ARRAY_MAGIC => RoaringTreemap::deserialize_from(bitmaps)
+            .map_err(|err| Error::DeletionVector(err.to_string())),
+        NATIVE_BITMAP_ARRAY_MAGIC => {
+            require!(
+                bitmaps.len() >= 4,
+                Error::deletion_vector("DV too short to contain the number of bitmaps")
+            );
+            let (count, mut bitmaps) = bitmaps.split_at(4);
+            let count = slice_to_u32(count, Endian::Little)?;
+            let bitmaps: Vec<_> = (0..count)
+                .map(|high_bits| {
+                    let bitmap = RoaringBitmap::deserialize_from(&mut bitmaps)
+                        .map_err(|err| Error::DeletionVector(err.to_string()))?;
+                    Ok::<_, Error>((high_bits, bitmap))
+                })
+                .try_collect()?;
+            Ok(RoaringTreemap::from_bitmaps(bitmaps))
+        }
+        magic => Err(Error::DeletionVector(format!("Invalid magic {magic}"))),
+    }
+}
+
+fn to_i32(size: usize) -> DeltaResult<i32> {
+    i32::try_from(size).map_err(|_| Error::DeletionVector(format!("DV too large: {size} bytes")))
+}
+
+fn to_i64(cardinality: u64) -> DeltaResult<i64> {
+    i64::try_from(cardinality)
+        .map_err(|_| Error::DeletionVector(format!("Invalid cardinality: {cardinality}")))
+}
+
 enum Endian {
     Big,
     Little,
@@ -327,6 +569,10 @@ mod tests {
             Url::parse("s3://mytable/ab/deletion_vector_d2c639aa-8816-431a-aaf6-d3fe2512ff61.bin")
                 .unwrap();
         assert_eq!(expected, relative.absolute_path(&parent).unwrap().unwrap());
+        assert_eq!(
+            relative.path().unwrap().as_deref(),
+            Some("ab/deletion_vector_d2c639aa-8816-431a-aaf6-d3fe2512ff61.bin")
+        );
 
         let absolute = dv_absolute();
         let expected =
@@ -336,6 +582,7 @@ mod tests {
 
         let inline = dv_inline();
         assert_eq!(None, inline.absolute_path(&parent).unwrap());
+        assert_eq!(None, inline.path().unwrap());
 
         let path =
             std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
@@ -402,6 +649,160 @@ mod tests {
         assert_eq!(bools, expected);
     }
 
+    #[test]
+    fn test_storage_type() {
+        use DeletionVectorStorageType::*;
+        assert_eq!(
+            dv_relative().parsed_storage_type().unwrap(),
+            PersistedRelative
+        );
+        assert_eq!(
+            dv_absolute().parsed_storage_type().unwrap(),
+            PersistedAbsolute
+        );
+        assert_eq!(dv_inline().parsed_storage_type().unwrap(), Inline);
+        let unknown = DeletionVectorDescriptor {
+            storage_type: "x".to_string(),
+            ..dv_inline()
+        };
+        assert!(unknown.parsed_storage_type().is_err());
+        assert!(unknown
+            .absolute_path(&Url::parse("s3://mytable/").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let sync_engine = SyncEngine::new();
+        let parent = Url::parse("http://not.used").unwrap();
+        let dv = dv_inline()
+            .load(sync_engine.get_file_system_client(), &parent)
+            .unwrap();
+        assert_eq!(dv.cardinality(), 6);
+        assert!(dv.is_deleted(3));
+        assert!(!dv.is_deleted(5));
+        assert_eq!(dv.row_indexes().collect_vec(), [3, 4, 7, 11, 18, 29]);
+        let selection_vector = dv.into_selection_vector();
+        assert_eq!(selection_vector.len(), 30);
+        assert!(!selection_vector[29]);
+
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let parent = url::Url::from_directory_path(path).unwrap();
+        let dv = dv_example()
+            .load(sync_engine.get_file_system_client(), &parent)
+            .unwrap();
+        assert_eq!(dv.row_indexes().collect_vec(), [0, 9]);
+
+        // the cardinality in the log must match the DV
+        let wrong_cardinality = DeletionVectorDescriptor {
+            cardinality: 3,
+            ..dv_example()
+        };
+        let err = wrong_cardinality
+            .load(sync_engine.get_file_system_client(), &parent)
+            .unwrap_err();
+        assert!(err.to_string().contains("cardinality mismatch"));
+    }
+
+    #[test]
+    fn test_inline_size_mismatch() {
+        let sync_engine = SyncEngine::new();
+        let parent = Url::parse("http://not.used").unwrap();
+        let too_large = DeletionVectorDescriptor {
+            size_in_bytes: 48,
+            ..dv_inline()
+        };
+        let err = too_large
+            .read(sync_engine.get_file_system_client(), &parent)
+            .unwrap_err();
+        assert!(err.to_string().contains("DV size mismatch"));
+    }
+
+    #[test]
+    fn test_native_bitmap_array() {
+        let mut bytes = NATIVE_BITMAP_ARRAY_MAGIC.to_le_bytes().to_vec();
+        bytes.extend(2u32.to_le_bytes());
+        for bitmap in [
+            RoaringBitmap::from_iter([1, 5]),
+            RoaringBitmap::from_iter([3]),
+        ] {
+            bitmap.serialize_into(&mut bytes).unwrap();
+        }
+        let treemap = deserialize_bitmap_array(&bytes).unwrap();
+        assert_eq!(treemap.iter().collect_vec(), [1, 5, (1 << 32) + 3]);
+
+        // the portable format is what RoaringTreemap serializes to
+        let mut bytes = PORTABLE_BITMAP_ARRAY_MAGIC.to_le_bytes().to_vec();
+        treemap.serialize_into(&mut bytes).unwrap();
+        assert_eq!(deserialize_bitmap_array(&bytes).unwrap(), treemap);
+
+        assert!(deserialize_bitmap_array(&[1, 2]).is_err());
+        assert!(deserialize_bitmap_array(&[1, 2, 3, 4]).is_err());
+    }
+
+    #[test]
+    fn test_write_inline() {
+        let dv: DeletionVector = [3, 4, 7, 11, 18, 29, (1 << 32) + 1].into_iter().collect();
+        let descriptor = DeletionVectorDescriptor::inline(&dv).unwrap();
+        assert_eq!(
+            descriptor.parsed_storage_type().unwrap(),
+            DeletionVectorStorageType::Inline
+        );
+        assert_eq!(descriptor.offset, None);
+        assert_eq!(descriptor.cardinality, 7);
+        assert_eq!(
+            descriptor.size_in_bytes as usize,
+            dv.serialize().unwrap().len()
+        );
+
+        let sync_engine = SyncEngine::new();
+        let parent = Url::parse("http://not.used").unwrap();
+        let loaded = descriptor
+            .load(sync_engine.get_file_system_client(), &parent)
+            .unwrap();
+        assert_eq!(loaded, dv);
+    }
+
+    #[test]
+    fn test_write_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = Url::from_directory_path(dir.path()).unwrap();
+        let sync_engine = SyncEngine::new();
+        let fs_client = sync_engine.get_file_system_client();
+
+        let dv: DeletionVector = (0..1000).step_by(3).collect();
+        let descriptor =
+            DeletionVectorDescriptor::write(&dv, fs_client.as_ref(), &table_root).unwrap();
+        assert_eq!(
+            descriptor.parsed_storage_type().unwrap(),
+            DeletionVectorStorageType::PersistedRelative
+        );
+        assert_eq!(descriptor.offset, Some(1));
+        assert_eq!(descriptor.cardinality, 334);
+        let loaded = descriptor.load(fs_client.clone(), &table_root).unwrap();
+        assert_eq!(loaded, dv);
+
+        // the file ends with the checksum of the DV
+        let path = descriptor.absolute_path(&table_root).unwrap().unwrap();
+        let file = std::fs::read(path.to_file_path().unwrap()).unwrap();
+        let (data, checksum) = file[5..].split_at(descriptor.size_in_bytes as usize);
+        assert_eq!(checksum, crc32fast::hash(data).to_be_bytes());
+    }
+
+    #[test]
+    fn test_checksum_matches_existing_file() {
+        // the DV files written by other writers use the same checksum
+        let path = std::fs::canonicalize(PathBuf::from(
+            "./tests/data/table-with-dv-small/deletion_vector_61d16c75-6994-46b7-a15b-8b538852e50e.bin",
+        ))
+        .unwrap();
+        let file = std::fs::read(path).unwrap();
+        let size = dv_example().size_in_bytes as usize;
+        let (data, checksum) = file[5..].split_at(size);
+        assert_eq!(checksum[..4], crc32fast::hash(data).to_be_bytes());
+    }
+
     #[test]
     fn test_dv_row_indexes() {
         let example = dv_inline();
@@ -0,0 +1,130 @@
+//! Log replay for [`DomainMetadata`] actions, which record the configuration of named metadata
+//! domains. The latest action for a domain wins, and a `removed` action logically deletes it.
+use std::sync::{Arc, LazyLock};
+
+use crate::actions::visitors::{DomainMetadataMap, DomainMetadataVisitor};
+use crate::actions::{get_log_schema, DomainMetadata, DOMAIN_METADATA_NAME};
+use crate::log_segment::LogSegment;
+use crate::{DeltaResult, Engine, Expression as Expr, ExpressionRef, RowVisitor as _};
+
+/// Scan the log for the latest domain metadata action of each domain, but terminate early if a
+/// specific domain is requested. The result includes tombstones of removed domains.
+pub(crate) fn scan_domain_metadatas(
+    log_segment: &LogSegment,
+    domain: Option<&str>,
+    engine: &dyn Engine,
+) -> DeltaResult<DomainMetadataMap> {
+    static META_PREDICATE: LazyLock<Option<ExpressionRef>> = LazyLock::new(|| {
+        Some(Arc::new(
+            Expr::column([DOMAIN_METADATA_NAME, "domain"]).is_not_null(),
+        ))
+    });
+    let schema = get_log_schema().project(&[DOMAIN_METADATA_NAME])?;
+    let mut visitor = DomainMetadataVisitor::new(domain.map(|s| s.to_owned()));
+    for maybe_data in log_segment.replay(engine, schema.clone(), schema, META_PREDICATE.clone())? {
+        let (domain_metadatas, _) = maybe_data?;
+        visitor.visit_rows_of(domain_metadatas.as_ref())?;
+        // if a specific domain is requested and its latest action was found, then return
+        if domain.is_some() && !visitor.domain_metadatas.is_empty() {
+            break;
+        }
+    }
+    Ok(visitor.domain_metadatas)
+}
+
+/// Get the configuration of `domain`, or `None` if the domain does not exist or was removed.
+pub(crate) fn domain_metadata_configuration(
+    log_segment: &LogSegment,
+    domain: &str,
+    engine: &dyn Engine,
+) -> DeltaResult<Option<String>> {
+    let mut domain_metadatas = scan_domain_metadatas(log_segment, Some(domain), engine)?;
+    Ok(domain_metadatas
+        .remove(domain)
+        .filter(|domain_metadata| !domain_metadata.removed)
+        .map(|DomainMetadata { configuration, .. }| configuration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{Metadata, Protocol};
+    use crate::engine::sync::SyncEngine;
+    use crate::schema::{DataType, StructField, StructType};
+    use crate::table_features::WriterFeatures;
+    use crate::utils::test_utils::{Action, LocalMockTable};
+    use crate::Table;
+
+    fn domain_metadata(domain: &str, configuration: &str, removed: bool) -> Action {
+        Action::DomainMetadata(DomainMetadata {
+            domain: domain.to_string(),
+            configuration: configuration.to_string(),
+            removed,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_domain_metadata() {
+        let schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+        let mut mock_table = LocalMockTable::new();
+        mock_table
+            .commit([
+                Action::Protocol(
+                    Protocol::try_new(
+                        1,
+                        7,
+                        None::<Vec<String>>,
+                        Some([WriterFeatures::DomainMetadata]),
+                    )
+                    .unwrap(),
+                ),
+                Action::Metadata(Metadata {
+                    schema_string: serde_json::to_string(&schema).unwrap(),
+                    ..Default::default()
+                }),
+                domain_metadata("app1", "{\"a\": 1}", false),
+                domain_metadata("app2", "{}", false),
+            ])
+            .await;
+        mock_table
+            .commit([
+                domain_metadata("app1", "{\"a\": 2}", false),
+                domain_metadata("app2", "{}", true),
+            ])
+            .await;
+
+        let engine = SyncEngine::new();
+        let table = Table::new(url::Url::from_directory_path(mock_table.table_root()).unwrap());
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        assert_eq!(
+            snapshot
+                .domain_metadata("app1", &engine)
+                .unwrap()
+                .as_deref(),
+            Some("{\"a\": 2}")
+        );
+        assert_eq!(snapshot.domain_metadata("app2", &engine).unwrap(), None);
+        assert_eq!(snapshot.domain_metadata("app3", &engine).unwrap(), None);
+
+        let snapshot = table.snapshot(&engine, Some(0)).unwrap();
+        assert_eq!(
+            snapshot
+                .domain_metadata("app1", &engine)
+                .unwrap()
+                .as_deref(),
+            Some("{\"a\": 1}")
+        );
+        assert_eq!(
+            snapshot
+                .domain_metadata("app2", &engine)
+                .unwrap()
+                .as_deref(),
+            Some("{}")
+        );
+
+        let latest = table.snapshot(&engine, None).unwrap();
+        let all = scan_domain_metadatas(&latest.log_segment, None, &engine).unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all["app2"].removed);
+    }
+}
@@ -1,29 +1,28 @@
 //! Provides parsing and manipulation of the various actions defined in the [Delta
 //! specification](https://github.com/delta-io/delta/blob/master/PROTOCOL.md)
 
-use std::any::type_name;
 use std::collections::{HashMap, HashSet};
-use std::fmt::{Debug, Display};
-use std::hash::Hash;
-use std::str::FromStr;
 use std::sync::LazyLock;
 
 use self::deletion_vector::DeletionVectorDescriptor;
 use crate::actions::schemas::GetStructField;
 use crate::schema::{SchemaRef, StructType};
 use crate::table_features::{
-    ReaderFeatures, WriterFeatures, SUPPORTED_READER_FEATURES, SUPPORTED_WRITER_FEATURES,
+    ReaderFeatures, TableFeature, WriterFeatures, SUPPORTED_READER_FEATURES,
+    SUPPORTED_WRITER_FEATURES,
 };
 use crate::table_properties::TableProperties;
 use crate::utils::require;
-use crate::{DeltaResult, EngineData, Error, RowVisitor as _};
+use crate::{DeltaResult, EngineData, Error, RowVisitor as _, UnsupportedFeatures};
 use visitors::{MetadataVisitor, ProtocolVisitor};
 
 use delta_kernel_derive::Schema;
 use serde::{Deserialize, Serialize};
 
 pub mod deletion_vector;
+pub(crate) mod domain_metadata;
 pub mod set_transaction;
+pub mod tombstones;
 
 pub(crate) mod schemas;
 #[cfg(feature = "developer-visibility")]
@@ -45,6 +44,8 @@ pub(crate) const SET_TRANSACTION_NAME: &str = "txn";
 pub(crate) const COMMIT_INFO_NAME: &str = "commitInfo";
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) const CDC_NAME: &str = "cdc";
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+pub(crate) const DOMAIN_METADATA_NAME: &str = "domainMetadata";
 
 static LOG_ADD_SCHEMA: LazyLock<SchemaRef> =
     LazyLock::new(|| StructType::new([Option::<Add>::get_struct_field(ADD_NAME)]).into());
@@ -58,8 +59,7 @@ static LOG_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
         Option::<SetTransaction>::get_struct_field(SET_TRANSACTION_NAME),
         Option::<CommitInfo>::get_struct_field(COMMIT_INFO_NAME),
         Option::<Cdc>::get_struct_field(CDC_NAME),
-        // We don't support the following actions yet
-        //Option::<DomainMetadata>::get_struct_field(DOMAIN_METADATA_NAME),
+        Option::<DomainMetadata>::get_struct_field(DOMAIN_METADATA_NAME),
     ])
     .into()
 });
@@ -84,8 +84,8 @@ pub(crate) fn get_log_commit_info_schema() -> &'static SchemaRef {
     &LOG_COMMIT_INFO_SCHEMA
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
-#[cfg_attr(test, derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Format {
     /// Name of the encoding for files in this table
     pub provider: String,
@@ -102,8 +102,8 @@ impl Default for Format {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Schema)]
-#[cfg_attr(test, derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Metadata {
     /// Unique identifier for this table
     pub id: String,
@@ -238,97 +238,137 @@ impl Protocol {
             .is_some_and(|features| features.iter().any(|f| f == feature.as_ref()))
     }
 
-    /// Check if reading a table with this protocol is supported. That is: does the kernel support
-    /// the specified protocol reader version and all enabled reader features? If yes, returns unit
-    /// type, otherwise will return an error.
-    pub fn ensure_read_supported(&self) -> DeltaResult<()> {
-        match &self.reader_features {
-            // if min_reader_version = 3 and all reader features are subset of supported => OK
-            Some(reader_features) if self.min_reader_version == 3 => {
+    /// The reader features (or versions) of this protocol which keep the kernel from reading the
+    /// table, or None if reading it is supported. Engines can use this to check whether they can
+    /// read a table before reading it.
+    pub fn unsupported_read_features(&self) -> Option<UnsupportedFeatures> {
+        match (&self.reader_features, self.min_reader_version) {
+            // reader features are present exactly when min_reader_version = 3, see `try_new`
+            (Some(reader_features), 3) => {
                 ensure_supported_features(reader_features, &SUPPORTED_READER_FEATURES)
+                    .err()
+                    .map(|features| self.with_versions(features))
             }
-            // if min_reader_version = 3 and no reader features => ERROR
-            // NOTE this is caught by the protocol parsing.
-            None if self.min_reader_version == 3 => Err(Error::internal_error(
-                "Reader features must be present when minimum reader version = 3",
-            )),
-            // if min_reader_version = 1,2 and there are no reader features => OK
-            None if self.min_reader_version == 1 || self.min_reader_version == 2 => Ok(()),
-            // if min_reader_version = 1,2 and there are reader features => ERROR
-            // NOTE this is caught by the protocol parsing.
-            Some(_) if self.min_reader_version == 1 || self.min_reader_version == 2 => {
-                Err(Error::internal_error(
-                    "Reader features must not be present when minimum reader version = 1 or 2",
-                ))
-            }
+            (None, 1 | 2) => None,
             // any other min_reader_version is not supported
-            _ => Err(Error::Unsupported(format!(
-                "Unsupported minimum reader version {}",
-                self.min_reader_version
-            ))),
+            _ => Some(self.with_versions(UnsupportedFeatures::default())),
         }
     }
 
-    /// Check if writing to a table with this protocol is supported. That is: does the kernel
-    /// support the specified protocol writer version and all enabled writer features?
-    pub fn ensure_write_supported(&self) -> DeltaResult<()> {
+    /// The writer features (or versions) of this protocol which keep the kernel from writing to
+    /// the table, or None if writing to it is supported. Only tables with min reader version 3
+    /// and min writer version 7 are supported for writes.
+    pub fn unsupported_write_features(&self) -> Option<UnsupportedFeatures> {
         match &self.writer_features {
-            // if min_reader_version = 3 and min_writer_version = 7 and all writer features are
-            // supported => OK
             Some(writer_features)
                 if self.min_reader_version == 3 && self.min_writer_version == 7 =>
             {
                 ensure_supported_features(writer_features, &SUPPORTED_WRITER_FEATURES)
+                    .err()
+                    .map(|features| self.with_versions(features))
             }
-            // otherwise not supported
-            _ => Err(Error::unsupported(
-                "Only tables with min reader version 3 and min writer version 7 with no table features are supported."
-            )),
+            _ => Some(self.with_versions(UnsupportedFeatures {
+                write: true,
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// True if the kernel supports reading a table with this protocol, see
+    /// [`Protocol::unsupported_read_features`].
+    pub fn is_read_supported(&self) -> bool {
+        self.unsupported_read_features().is_none()
+    }
+
+    /// True if the kernel supports writing to a table with this protocol, see
+    /// [`Protocol::unsupported_write_features`].
+    pub fn is_write_supported(&self) -> bool {
+        self.unsupported_write_features().is_none()
+    }
+
+    /// Check if reading a table with this protocol is supported. That is: does the kernel support
+    /// the specified protocol reader version and all enabled reader features? If not, fails with
+    /// an [`Error::UnsupportedTableFeature`] listing the unsupported features.
+    pub fn ensure_read_supported(&self) -> DeltaResult<()> {
+        match self.unsupported_read_features() {
+            Some(features) => Err(Error::unsupported_table_features(features)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if writing to a table with this protocol is supported. That is: does the kernel
+    /// support the specified protocol writer version and all enabled writer features? If not,
+    /// fails with an [`Error::UnsupportedTableFeature`] listing the unsupported features.
+    pub fn ensure_write_supported(&self) -> DeltaResult<()> {
+        match self.unsupported_write_features() {
+            Some(features) => Err(Error::unsupported_table_features(features)),
+            None => Ok(()),
+        }
+    }
+
+    // the unsupported `features`, with the versions of this protocol
+    pub(crate) fn with_versions(&self, features: UnsupportedFeatures) -> UnsupportedFeatures {
+        UnsupportedFeatures {
+            min_reader_version: self.min_reader_version,
+            min_writer_version: self.min_writer_version,
+            ..features
         }
     }
 }
 
-// given unparsed `table_features`, parse and check if they are subset of `supported_features`
+// given unparsed `table_features`, parse and check if they are subset of `supported_features`.
+// The error lists every feature of the table which is unknown or not supported by the kernel,
+// along with the supported ones; the caller fills in the versions of the protocol.
 pub(crate) fn ensure_supported_features<T>(
     table_features: &[String],
     supported_features: &HashSet<T>,
-) -> DeltaResult<()>
+) -> Result<(), UnsupportedFeatures>
 where
-    <T as FromStr>::Err: Display,
-    T: Debug + FromStr + Hash + Eq,
+    T: TableFeature,
 {
-    let error = |unsupported, unsupported_or_unknown| {
-        let supported = supported_features.iter().collect::<Vec<_>>();
-        let features_type = type_name::<T>()
-            .rsplit("::")
-            .next()
-            .unwrap_or("table features");
-        Error::Unsupported(format!(
-            "{} {} {:?}. Supported {} are {:?}",
-            unsupported_or_unknown, features_type, unsupported, features_type, supported
-        ))
-    };
-    let parsed_features: HashSet<T> = table_features
+    let mut unsupported = vec![];
+    let mut unknown = vec![];
+    for feature in table_features {
+        match T::from_str(feature) {
+            Ok(parsed) if supported_features.contains(&parsed) => continue,
+            Ok(_) => {}
+            Err(_) => unknown.push(feature.clone()),
+        }
+        unsupported.push(feature.clone());
+    }
+    if unsupported.is_empty() {
+        return Ok(());
+    }
+
+    let mut supported: Vec<_> = supported_features
         .iter()
-        .map(|s| T::from_str(s).map_err(|_| error(vec![s.to_string()], "Unknown")))
-        .collect::<Result<_, Error>>()?;
-    parsed_features
-        .is_subset(supported_features)
-        .then_some(())
-        .ok_or_else(|| {
-            let unsupported = parsed_features
-                .difference(supported_features)
-                .map(|f| format!("{:?}", f))
-                .collect::<Vec<_>>();
-            error(unsupported, "Unsupported")
-        })
+        .map(|f| format!("{f:?}"))
+        .collect();
+    supported.sort();
+    let (reader_features, writer_features) = match T::WRITER {
+        true => (vec![], unsupported),
+        false => (unsupported, vec![]),
+    };
+    Err(UnsupportedFeatures {
+        write: T::WRITER,
+        reader_features,
+        writer_features,
+        unknown_features: unknown,
+        supported_features: supported,
+        ..Default::default()
+    })
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 #[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
-#[cfg_attr(test, derive(Serialize, Default), serde(rename_all = "camelCase"))]
+#[cfg_attr(test, derive(Default))]
 struct CommitInfo {
+    /// The in-commit timestamp of this commit, as milliseconds since the epoch. Only present (and
+    /// then required) when in-commit timestamps are enabled, in which case it is strictly greater
+    /// than the timestamp of the previous commit.
+    pub(crate) in_commit_timestamp: Option<i64>,
     /// The time this logical file was created, as milliseconds since the epoch.
     /// Read: optional, write: required (that is, kernel always writes).
     /// If in-commit timestamps are enabled, this is always required.
@@ -337,19 +377,23 @@ struct CommitInfo {
     /// specified by the engine. Read: optional, write: required (that is, kernel alwarys writes).
     pub(crate) operation: Option<String>,
     /// Map of arbitrary string key-value pairs that provide additional information about the
-    /// operation. This is specified by the engine. For now this is always empty on write.
+    /// operation. This is specified by the engine. Kernel always writes this (possibly empty) map.
     pub(crate) operation_parameters: Option<HashMap<String, String>>,
     /// The version of the delta_kernel crate used to write this commit. The kernel will always
     /// write this field, but it is optional since many tables will not have this field (i.e. any
     /// tables not written by kernel).
     pub(crate) kernel_version: Option<String>,
+    /// An arbitrary string that identifies the engine which wrote this commit, e.g.
+    /// `Apache-Spark/3.5.0 Delta-Lake/3.2.0`. This is specified by the engine.
+    pub(crate) engine_info: Option<String>,
     /// A place for the engine to store additional metadata associated with this commit encoded as
     /// a map of strings.
     pub(crate) engine_commit_info: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
-#[cfg_attr(test, derive(Serialize, Default), serde(rename_all = "camelCase"))]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, derive(Default))]
 pub struct Add {
     /// A relative path to a data file from the root of the table or an absolute path to a file
     /// that should be added to the table. The path is a URI as specified by
@@ -378,29 +422,29 @@ pub struct Add {
     /// Contains [statistics] (e.g., count, min/max values for columns) about the data in this logical file.
     ///
     /// [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<String>,
 
     /// Map containing metadata about this logical file.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashMap<String, String>>,
 
     /// Information about deletion vector (DV) associated with this add action
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub deletion_vector: Option<DeletionVectorDescriptor>,
 
     /// Default generated Row ID of the first row in the file. The default generated Row IDs
     /// of the other rows in the file can be reconstructed by adding the physical index of the
     /// row within the file to the base Row ID
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub base_row_id: Option<i64>,
 
     /// First commit version in which an add action with the same path was committed to the table.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_row_commit_version: Option<i64>,
 
     /// The name of the clustering implementation
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub clustering_provider: Option<String>,
 }
 
@@ -410,10 +454,11 @@ impl Add {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 #[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
-#[cfg_attr(test, derive(Serialize, Default), serde(rename_all = "camelCase"))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, derive(Default))]
 struct Remove {
     /// A relative path to a data file from the root of the table or an absolute path to a file
     /// that should be added to the table. The path is a URI as specified by
@@ -423,7 +468,7 @@ struct Remove {
     pub(crate) path: String,
 
     /// The time this logical file was created, as milliseconds since the epoch.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) deletion_timestamp: Option<i64>,
 
     /// When `false` the logical file must already be present in the table or the records
@@ -431,33 +476,33 @@ struct Remove {
     pub(crate) data_change: bool,
 
     /// When true the fields `partition_values`, `size`, and `tags` are present
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) extended_file_metadata: Option<bool>,
 
     /// A map from partition column to value for this logical file.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) partition_values: Option<HashMap<String, String>>,
 
     /// The size of this data file in bytes
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) size: Option<i64>,
 
     /// Map containing metadata about this logical file.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) tags: Option<HashMap<String, String>>,
 
     /// Information about deletion vector (DV) associated with this add action
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) deletion_vector: Option<DeletionVectorDescriptor>,
 
     /// Default generated Row ID of the first row in the file. The default generated Row IDs
     /// of the other rows in the file can be reconstructed by adding the physical index of the
     /// row within the file to the base Row ID
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) base_row_id: Option<i64>,
 
     /// First commit version in which an add action with the same path was committed to the table.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) default_row_commit_version: Option<i64>,
 }
 
@@ -494,7 +539,8 @@ struct Cdc {
     pub tags: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SetTransaction {
     /// A unique identifier for the application performing the transaction.
     pub app_id: String,
@@ -506,6 +552,21 @@ pub struct SetTransaction {
     pub last_updated: Option<i64>,
 }
 
+/// Arbitrary configuration of a metadata domain, used by table features (system-controlled
+/// domains, whose names start with `delta.`) and by applications (user-controlled domains).
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainMetadata {
+    /// The name of the metadata domain
+    pub domain: String,
+
+    /// The configuration of the domain, typically a JSON string
+    pub configuration: String,
+
+    /// When `true`, the action is a tombstone that logically removes the domain
+    pub removed: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -684,6 +745,24 @@ mod tests {
         assert_eq!(schema, expected);
     }
 
+    #[test]
+    fn test_domain_metadata_schema() {
+        let schema = get_log_schema()
+            .project(&[DOMAIN_METADATA_NAME])
+            .expect("Couldn't get domainMetadata field");
+
+        let expected = Arc::new(StructType::new([StructField::new(
+            "domainMetadata",
+            StructType::new([
+                StructField::new("domain", DataType::STRING, false),
+                StructField::new("configuration", DataType::STRING, false),
+                StructField::new("removed", DataType::BOOLEAN, false),
+            ]),
+            true,
+        )]));
+        assert_eq!(schema, expected);
+    }
+
     #[test]
     fn test_commit_info_schema() {
         let schema = get_log_schema()
@@ -693,6 +772,7 @@ mod tests {
         let expected = Arc::new(StructType::new(vec![StructField::new(
             "commitInfo",
             StructType::new(vec![
+                StructField::new("inCommitTimestamp", DataType::LONG, true),
                 StructField::new("timestamp", DataType::LONG, true),
                 StructField::new("operation", DataType::STRING, true),
                 StructField::new(
@@ -701,6 +781,7 @@ mod tests {
                     true,
                 ),
                 StructField::new("kernelVersion", DataType::STRING, true),
+                StructField::new("engineInfo", DataType::STRING, true),
                 StructField::new(
                     "engineCommitInfo",
                     MapType::new(DataType::STRING, DataType::STRING, false),
@@ -846,6 +927,15 @@ mod tests {
             Some([WriterFeatures::DeletionVectors]),
         )
         .unwrap();
+        assert!(protocol.ensure_write_supported().is_ok());
+
+        let protocol = Protocol::try_new(
+            3,
+            7,
+            Some(Vec::<String>::new()),
+            Some([WriterFeatures::ChangeDataFeed]),
+        )
+        .unwrap();
         assert!(protocol.ensure_write_supported().is_err());
     }
 
@@ -863,14 +953,84 @@ mod tests {
         // test unknown features
         let table_features = vec![ReaderFeatures::ColumnMapping.to_string(), "idk".to_string()];
         let error = ensure_supported_features(&table_features, &supported_features).unwrap_err();
-        match error {
-            Error::Unsupported(e) if e ==
+        match error.to_string() {
+            e if e ==
                 "Unknown ReaderFeatures [\"idk\"]. Supported ReaderFeatures are [ColumnMapping, DeletionVectors]"
             => {},
-            Error::Unsupported(e) if e ==
-                "Unknown ReaderFeatures [\"idk\"]. Supported ReaderFeatures are [DeletionVectors, ColumnMapping]"
+            _ => panic!("Expected unsupported table feature error"),
+        }
+
+        // test that all unsupported and unknown features are listed
+        let table_features = vec![
+            ReaderFeatures::V2Checkpoint.to_string(),
+            "idk".to_string(),
+            ReaderFeatures::ColumnMapping.to_string(),
+            ReaderFeatures::TypeWidening.to_string(),
+            "idk2".to_string(),
+        ];
+        let error = ensure_supported_features(&table_features, &supported_features).unwrap_err();
+        match error.to_string() {
+            e if e ==
+                "Unsupported ReaderFeatures [\"v2Checkpoint\", \"typeWidening\"]. Unknown ReaderFeatures [\"idk\", \"idk2\"]. Supported ReaderFeatures are [ColumnMapping, DeletionVectors]"
             => {},
-            _ => panic!("Expected unsupported error"),
+            _ => panic!("Expected unsupported table feature error"),
         }
+        assert_eq!(
+            error.reader_features,
+            ["v2Checkpoint", "idk", "typeWidening", "idk2"]
+        );
+        assert_eq!(error.unknown_features, ["idk", "idk2"]);
+    }
+
+    #[test]
+    fn test_unsupported_features() {
+        let protocol = Protocol::try_new(
+            3,
+            7,
+            Some([ReaderFeatures::V2Checkpoint.to_string(), "idk".to_string()]),
+            Some([WriterFeatures::V2Checkpoint.to_string(), "idk".to_string()]),
+        )
+        .unwrap();
+        assert!(!protocol.is_read_supported());
+        assert!(!protocol.is_write_supported());
+        let mut supported_features: Vec<_> = SUPPORTED_READER_FEATURES
+            .iter()
+            .map(|f| format!("{f:?}"))
+            .collect();
+        supported_features.sort();
+        let expected = UnsupportedFeatures {
+            min_reader_version: 3,
+            min_writer_version: 7,
+            write: false,
+            reader_features: vec!["v2Checkpoint".to_string(), "idk".to_string()],
+            writer_features: vec![],
+            unknown_features: vec!["idk".to_string()],
+            supported_features,
+        };
+        assert_eq!(protocol.unsupported_read_features(), Some(expected.clone()));
+        let err = protocol.ensure_read_supported().unwrap_err();
+        assert_eq!(err.unsupported_features(), Some(&expected));
+        assert_eq!(
+            protocol
+                .unsupported_write_features()
+                .unwrap()
+                .writer_features,
+            ["v2Checkpoint", "idk"]
+        );
+
+        // unsupported versions are reported without features
+        let protocol = Protocol::try_new(4, 7, Some([""; 0]), Some([""; 0])).unwrap();
+        let features = protocol.unsupported_read_features().unwrap();
+        assert_eq!(features.min_reader_version, 4);
+        assert!(features.reader_features.is_empty());
+        assert_eq!(features.to_string(), "Unsupported minimum reader version 4");
+        let protocol = Protocol::try_new(1, 2, None::<Vec<String>>, None::<Vec<String>>).unwrap();
+        assert!(protocol.is_read_supported());
+        assert!(!protocol.is_write_supported());
+        assert!(protocol.unsupported_write_features().unwrap().write);
+
+        assert!(ReaderFeatures::DeletionVectors.is_supported());
+        assert!(!ReaderFeatures::V2Checkpoint.is_supported());
+        assert!(WriterFeatures::AppendOnly.is_supported());
     }
 }
@@ -1,8 +1,10 @@
 use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime};
 
 use crate::actions::visitors::SetTransactionVisitor;
 use crate::actions::{get_log_schema, SetTransaction, SET_TRANSACTION_NAME};
 use crate::snapshot::Snapshot;
+use crate::utils::{current_time, retention_cutoff_millis};
 use crate::{
     DeltaResult, Engine, EngineData, Expression as Expr, ExpressionRef, RowVisitor as _, SchemaRef,
 };
@@ -17,11 +19,22 @@ impl SetTransactionScanner {
         SetTransactionScanner { snapshot }
     }
 
-    /// Scan the entire log for all application ids but terminate early if a specific application id is provided
+    /// The retention duration for application transactions, from the
+    /// `delta.setTransactionRetentionDuration` table property. Transactions are retained forever if
+    /// it is unset.
+    pub fn set_transaction_retention_duration(&self) -> Option<Duration> {
+        self.snapshot
+            .table_properties()
+            .set_transaction_retention_duration
+    }
+
+    /// Scan the entire log for all application ids but terminate early if a specific application id is provided.
+    /// Transactions which have expired at `now` are dropped.
     fn scan_application_transactions(
         &self,
         engine: &dyn Engine,
         application_id: Option<&str>,
+        now: SystemTime,
     ) -> DeltaResult<SetTransactionMap> {
         let schema = Self::get_txn_schema()?;
         let mut visitor = SetTransactionVisitor::new(application_id.map(|s| s.to_owned()));
@@ -36,7 +49,15 @@ impl SetTransactionScanner {
             }
         }
 
-        Ok(visitor.set_transactions)
+        // only the latest transaction of each application was kept, so an expired transaction
+        // cannot be replaced by an older one
+        let mut set_transactions = visitor.set_transactions;
+        if let Some(retention) = self.set_transaction_retention_duration() {
+            let cutoff = retention_cutoff_millis(now, retention)?;
+            set_transactions
+                .retain(|_, txn| !is_expired_transaction(txn.last_updated, Some(cutoff)));
+        }
+        Ok(set_transactions)
     }
 
     // Factored out to facilitate testing
@@ -64,20 +85,41 @@ impl SetTransactionScanner {
             .replay(engine, schema.clone(), schema, META_PREDICATE.clone())
     }
 
-    /// Scan the Delta Log for the latest transaction entry of an application
+    /// Scan the Delta Log for the latest transaction entry of an application. Returns `None` if
+    /// the latest transaction of the application has expired (see
+    /// [`Self::set_transaction_retention_duration`]).
     pub fn application_transaction(
         &self,
         engine: &dyn Engine,
         application_id: &str,
     ) -> DeltaResult<Option<SetTransaction>> {
-        let mut transactions = self.scan_application_transactions(engine, Some(application_id))?;
+        let mut transactions =
+            self.scan_application_transactions(engine, Some(application_id), current_time())?;
         Ok(transactions.remove(application_id))
     }
 
-    /// Scan the Delta Log to obtain the latest transaction for all applications
+    /// Scan the Delta Log to obtain the latest transaction for all applications, without the
+    /// transactions which have expired (see [`Self::set_transaction_retention_duration`]).
     pub fn application_transactions(&self, engine: &dyn Engine) -> DeltaResult<SetTransactionMap> {
-        self.scan_application_transactions(engine, None)
+        self.application_transactions_at(engine, current_time())
     }
+
+    /// Like [`Self::application_transactions`], but with the transactions retained at the given
+    /// time.
+    pub fn application_transactions_at(
+        &self,
+        engine: &dyn Engine,
+        now: SystemTime,
+    ) -> DeltaResult<SetTransactionMap> {
+        self.scan_application_transactions(engine, None, now)
+    }
+}
+
+/// Whether a transaction last updated at `last_updated` has expired, given the time (in
+/// milliseconds since the epoch) at or before which transactions expire, if any. Following Delta
+/// Spark, transactions without a `lastUpdated` time never expire.
+pub(crate) fn is_expired_transaction(last_updated: Option<i64>, cutoff: Option<i64>) -> bool {
+    matches!((last_updated, cutoff), (Some(last_updated), Some(cutoff)) if last_updated <= cutoff)
 }
 
 #[cfg(all(test, feature = "default-engine"))]
@@ -85,7 +127,10 @@ mod tests {
     use std::path::PathBuf;
 
     use super::*;
+    use crate::actions::{Metadata, Protocol};
     use crate::engine::sync::SyncEngine;
+    use crate::schema::{DataType, StructField, StructType};
+    use crate::utils::test_utils::{Action, LocalMockTable};
     use crate::Table;
     use itertools::Itertools;
 
@@ -161,4 +206,74 @@ mod tests {
             .unwrap();
         assert_eq!(data.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_expired_transactions() {
+        let now = SystemTime::now();
+        let millis_ago = |duration: Duration| retention_cutoff_millis(now, duration).unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let txn = |app_id: &str, last_updated| {
+            Action::SetTransaction(SetTransaction {
+                app_id: app_id.to_string(),
+                version: 1,
+                last_updated,
+            })
+        };
+        let schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+        let mut mock_table = LocalMockTable::new();
+        mock_table
+            .commit([
+                Action::Protocol(
+                    Protocol::try_new(1, 2, None::<Vec<String>>, None::<Vec<String>>).unwrap(),
+                ),
+                Action::Metadata(Metadata {
+                    schema_string: serde_json::to_string(&schema).unwrap(),
+                    configuration: [(
+                        "delta.setTransactionRetentionDuration".to_string(),
+                        "interval 2 days".to_string(),
+                    )]
+                    .into(),
+                    ..Default::default()
+                }),
+                txn("expired", Some(millis_ago(3 * day))),
+                txn("retained", Some(millis_ago(day))),
+                txn("no-timestamp", None),
+            ])
+            .await;
+
+        let engine = SyncEngine::new();
+        let table = Table::new(url::Url::from_directory_path(mock_table.table_root()).unwrap());
+        let snapshot = Arc::new(table.snapshot(&engine, None).unwrap());
+        let scanner = SetTransactionScanner::new(snapshot.clone());
+        assert_eq!(scanner.set_transaction_retention_duration(), Some(2 * day));
+        let txns = scanner.application_transactions(&engine).unwrap();
+        assert_eq!(
+            txns.keys().sorted().collect_vec(),
+            ["no-timestamp", "retained"]
+        );
+        assert!(scanner
+            .application_transaction(&engine, "expired")
+            .unwrap()
+            .is_none());
+        assert!(scanner
+            .application_transaction(&engine, "retained")
+            .unwrap()
+            .is_some());
+        let txns = scanner
+            .application_transactions_at(&engine, now - 2 * day)
+            .unwrap();
+        assert_eq!(txns.len(), 3);
+
+        // checkpoints drop expired transactions
+        snapshot.checkpoint(&engine).unwrap();
+        let snapshot = Arc::new(table.snapshot(&engine, None).unwrap());
+        assert!(snapshot.log_segment.ascending_commit_files.is_empty());
+        let txns = SetTransactionScanner::new(snapshot)
+            .application_transactions_at(&engine, now - 2 * day)
+            .unwrap();
+        assert_eq!(
+            txns.keys().sorted().collect_vec(),
+            ["no-timestamp", "retained"]
+        );
+    }
 }
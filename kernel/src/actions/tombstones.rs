@@ -0,0 +1,287 @@
+//! Support for finding the files that have been removed from a table (i.e. "tombstones"), as needed
+//! by engines that implement VACUUM.
+
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime};
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::visitors::visit_deletion_vector_at;
+use crate::actions::{get_log_schema, ADD_NAME, REMOVE_NAME};
+use crate::engine_data::{GetData, TypedGetData as _};
+use crate::expressions::{column_name, ColumnName, Expression as Expr, ExpressionRef};
+use crate::log_segment::LogSegment;
+use crate::scan::log_replay::SeenFileActions;
+use crate::schema::{ColumnNamesAndTypes, DataType};
+use crate::snapshot::Snapshot;
+use crate::utils::{require, retention_cutoff_millis};
+use crate::{DeltaResult, Engine, Error, RowVisitor};
+
+/// A data file that has been logically removed from the table by a `remove` action, and which
+/// is not referenced by any `add` action of the snapshot. The file may still physically exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tombstone {
+    /// A relative path to the removed data file from the root of the table, or an absolute path.
+    /// The path is a URI, which needs to be decoded to get the data file path.
+    pub path: String,
+    /// The time the file was removed, as milliseconds since the epoch, if recorded.
+    pub deletion_timestamp: Option<i64>,
+    /// The size of the removed data file in bytes, if recorded.
+    pub size: Option<i64>,
+    /// The deletion vector that was associated with the removed file, if any.
+    pub deletion_vector: Option<DeletionVectorDescriptor>,
+}
+
+/// Finds the [`Tombstone`]s of a snapshot, and which of them have expired. Engines implementing
+/// VACUUM can use [`Snapshot::vacuum_plan`] instead, which also finds the files in storage that
+/// the table doesn't reference.
+///
+/// [`Snapshot::vacuum_plan`]: crate::snapshot::Snapshot::vacuum_plan
+pub struct TombstoneScanner {
+    snapshot: Arc<Snapshot>,
+}
+
+impl TombstoneScanner {
+    /// Create a scanner for the tombstones of `snapshot`.
+    pub fn new(snapshot: Arc<Snapshot>) -> Self {
+        TombstoneScanner { snapshot }
+    }
+
+    /// Replay the log of the snapshot to find all tombstones, i.e. `remove` actions that were not
+    /// superseded by a more recent `add` of the same file, and whose data file is not referenced
+    /// by any `add` of the snapshot (e.g. because only its deletion vector was replaced).
+    pub fn tombstones(&self, engine: &dyn Engine) -> DeltaResult<Vec<Tombstone>> {
+        let TombstoneVisitor {
+            tombstones,
+            live_data_files,
+            ..
+        } = visit_file_actions(&self.snapshot, engine)?;
+        Ok(tombstones
+            .into_iter()
+            .filter(|tombstone| {
+                !live_data_files.contains(&live_data_files.key(&tombstone.path, None))
+            })
+            .collect())
+    }
+
+    /// The retention duration for tombstones, from the `delta.deletedFileRetentionDuration` table
+    /// property, or one week if unset.
+    pub fn deleted_file_retention_duration(&self) -> Duration {
+        self.snapshot
+            .table_properties()
+            .deleted_file_retention_duration_or_default()
+    }
+
+    /// Get the tombstones which are older than the table's deleted file retention duration (see
+    /// [`Self::deleted_file_retention_duration`]) at the given time. The data files of expired
+    /// tombstones are no longer needed by readers of any retained version of the table, and are
+    /// thus safe to delete. Tombstones without a deletion timestamp are always expired.
+    pub fn expired_tombstones(
+        &self,
+        engine: &dyn Engine,
+        now: SystemTime,
+    ) -> DeltaResult<Vec<Tombstone>> {
+        let cutoff_millis = retention_cutoff_millis(now, self.deleted_file_retention_duration())?;
+        let mut tombstones = self.tombstones(engine)?;
+        tombstones.retain(|tombstone| tombstone.deletion_timestamp.unwrap_or(0) < cutoff_millis);
+        Ok(tombstones)
+    }
+}
+
+/// Replay the add and remove actions of the snapshot, to find its live files and tombstones.
+pub(crate) fn visit_file_actions(
+    snapshot: &Snapshot,
+    engine: &dyn Engine,
+) -> DeltaResult<TombstoneVisitor> {
+    visit_log_segment_file_actions(&snapshot.log_segment, engine)
+}
+
+/// Replay the add and remove actions of a log segment, see [`visit_file_actions`].
+pub(crate) fn visit_log_segment_file_actions(
+    log_segment: &LogSegment,
+    engine: &dyn Engine,
+) -> DeltaResult<TombstoneVisitor> {
+    static META_PREDICATE: LazyLock<Option<ExpressionRef>> = LazyLock::new(|| {
+        Some(Arc::new(Expr::or(
+            Expr::column([ADD_NAME, "path"]).is_not_null(),
+            Expr::column([REMOVE_NAME, "path"]).is_not_null(),
+        )))
+    });
+    let schema = get_log_schema().project(&[ADD_NAME, REMOVE_NAME])?;
+    let actions = log_segment.replay(engine, schema.clone(), schema, META_PREDICATE.clone())?;
+    let mut visitor = TombstoneVisitor::default();
+    for maybe_data in actions {
+        let (actions, is_log_batch) = maybe_data?;
+        visitor.is_log_batch = is_log_batch;
+        visitor.visit_rows_of(actions.as_ref())?;
+    }
+    Ok(visitor)
+}
+
+/// Replays add and remove actions newest-first. The first action seen for a given (path, dvId)
+/// pair determines whether that logical file is live (add) or a tombstone (remove).
+#[derive(Default)]
+pub(crate) struct TombstoneVisitor {
+    seen: SeenFileActions,
+    /// The data files referenced by a live add, keyed by path only
+    live_data_files: SeenFileActions,
+    /// The path and deletion vector of each live add
+    pub(crate) live_files: Vec<(String, Option<DeletionVectorDescriptor>)>,
+    /// All tombstones, including those whose data file is still referenced by a live add (with
+    /// another deletion vector)
+    pub(crate) tombstones: Vec<Tombstone>,
+    is_log_batch: bool,
+}
+
+impl RowVisitor for TombstoneVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            const STRING: DataType = DataType::STRING;
+            const INTEGER: DataType = DataType::INTEGER;
+            const LONG: DataType = DataType::LONG;
+            let types_and_names = vec![
+                (STRING, column_name!("add.path")),
+                (STRING, column_name!("add.deletionVector.storageType")),
+                (STRING, column_name!("add.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("add.deletionVector.offset")),
+                (INTEGER, column_name!("add.deletionVector.sizeInBytes")),
+                (LONG, column_name!("add.deletionVector.cardinality")),
+                (STRING, column_name!("remove.path")),
+                (LONG, column_name!("remove.deletionTimestamp")),
+                (LONG, column_name!("remove.size")),
+                (STRING, column_name!("remove.deletionVector.storageType")),
+                (STRING, column_name!("remove.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("remove.deletionVector.offset")),
+                (INTEGER, column_name!("remove.deletionVector.sizeInBytes")),
+                (LONG, column_name!("remove.deletionVector.cardinality")),
+            ];
+            let (types, names) = types_and_names.into_iter().unzip();
+            (names, types).into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 14,
+            Error::InternalError(format!(
+                "Wrong number of TombstoneVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            if let Some(path) = getters[0].get_str(i, "add.path")? {
+                let deletion_vector = visit_deletion_vector_at(i, &getters[1..6])?;
+                let dv_unique_id = deletion_vector.as_ref().map(|dv| dv.unique_id());
+                if self.record_seen(path, dv_unique_id.as_deref()) {
+                    let key = self.live_data_files.key(path, None);
+                    self.live_data_files.insert(key);
+                    self.live_files.push((path.to_string(), deletion_vector));
+                }
+            } else if let Some(path) = getters[6].get_str(i, "remove.path")? {
+                let deletion_vector = visit_deletion_vector_at(i, &getters[9..])?;
+                let dv_unique_id = deletion_vector.as_ref().map(|dv| dv.unique_id());
+                if self.record_seen(path, dv_unique_id.as_deref()) {
+                    self.tombstones.push(Tombstone {
+                        path: path.to_string(),
+                        deletion_timestamp: getters[7].get_opt(i, "remove.deletionTimestamp")?,
+                        size: getters[8].get_opt(i, "remove.size")?,
+                        deletion_vector,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TombstoneVisitor {
+    /// Returns true if this is the first (i.e. most recent) action for the given logical file.
+    fn record_seen(&mut self, path: &str, dv_unique_id: Option<&str>) -> bool {
+        let key = self.seen.key(path, dv_unique_id);
+        if self.seen.contains(&key) {
+            return false;
+        }
+        // Checkpoint actions are already reconciled and never replace each other, so there is no
+        // need to remember them.
+        if self.is_log_batch {
+            self.seen.insert(key);
+        }
+        true
+    }
+}
+
+#[cfg(all(test, feature = "default-engine"))]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::table_properties::DEFAULT_DELETED_FILE_RETENTION_DURATION;
+    use crate::Table;
+
+    fn tombstone_scanner(path: &str, engine: &dyn Engine) -> TombstoneScanner {
+        let path = std::fs::canonicalize(PathBuf::from(path)).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let snapshot = Table::new(url).snapshot(engine, None).unwrap();
+        TombstoneScanner::new(snapshot.into())
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let engine = SyncEngine::new();
+
+        // one file removed by a commit, and one remembered by the checkpoint
+        let scanner =
+            tombstone_scanner("./tests/data/with_checkpoint_no_last_checkpoint/", &engine);
+        let mut tombstones = scanner.tombstones(&engine).unwrap();
+        tombstones.sort_by(|a, b| a.path.cmp(&b.path));
+        let paths_and_timestamps: Vec<_> = tombstones
+            .iter()
+            .map(|t| (t.path.as_str(), t.deletion_timestamp))
+            .collect();
+        assert_eq!(
+            paths_and_timestamps,
+            [
+                (
+                    "part-00000-a190be9e-e3df-439e-b366-06a863f51e99-c000.snappy.parquet",
+                    Some(1674611461982)
+                ),
+                (
+                    "part-00000-ad1a4bb7-07e8-4f40-b50b-49910d209e0c-c000.snappy.parquet",
+                    Some(1674611459307)
+                ),
+            ]
+        );
+
+        // the most recent remove of a file wins
+        let scanner = tombstone_scanner("./tests/data/table-with-cdf/", &engine);
+        let tombstones = scanner.tombstones(&engine).unwrap();
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].path, "fake/path/1");
+        assert_eq!(tombstones[0].deletion_timestamp, Some(1704392846603));
+
+        // a file that was removed and re-added with a deletion vector is still live
+        let scanner = tombstone_scanner("./tests/data/table-with-dv-small/", &engine);
+        assert!(scanner.tombstones(&engine).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expired_tombstones() {
+        let engine = SyncEngine::new();
+        let scanner = tombstone_scanner("./tests/data/table-with-cdf/", &engine);
+        assert_eq!(
+            scanner.deleted_file_retention_duration(),
+            DEFAULT_DELETED_FILE_RETENTION_DURATION
+        );
+
+        let deleted_at = UNIX_EPOCH + Duration::from_millis(1704392846603);
+        let retention = DEFAULT_DELETED_FILE_RETENTION_DURATION;
+        let expired = |now| scanner.expired_tombstones(&engine, now).unwrap();
+        assert!(expired(deleted_at).is_empty());
+        assert!(expired(deleted_at + retention).is_empty());
+        let tombstones = expired(deleted_at + retention + Duration::from_millis(1));
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].path, "fake/path/1");
+    }
+}
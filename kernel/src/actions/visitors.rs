@@ -12,8 +12,9 @@ use crate::{DeltaResult, Error};
 use super::deletion_vector::DeletionVectorDescriptor;
 use super::schemas::ToSchema as _;
 use super::{
-    Add, Cdc, Format, Metadata, Protocol, Remove, SetTransaction, ADD_NAME, CDC_NAME,
-    METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME, SET_TRANSACTION_NAME,
+    Add, Cdc, DomainMetadata, Format, Metadata, Protocol, Remove, SetTransaction, ADD_NAME,
+    CDC_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME,
 };
 
 #[derive(Default)]
@@ -444,6 +445,76 @@ impl RowVisitor for SetTransactionVisitor {
     }
 }
 
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+#[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
+type DomainMetadataMap = HashMap<String, DomainMetadata>;
+
+/// Extract domain metadata actions from the log into a map, keyed by domain.
+///
+/// Like [`SetTransactionVisitor`], this visitor keeps the first action it encounters for each
+/// domain, which is the latest one when batches are visited in reverse log order. Tombstones
+/// (`removed` actions) are kept too, so that they shadow older actions of the same domain. When a
+/// specific `domain` is requested, bookkeeping is only done for that domain.
+#[derive(Default, Debug)]
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+#[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
+struct DomainMetadataVisitor {
+    pub(crate) domain_metadatas: DomainMetadataMap,
+    pub(crate) domain: Option<String>,
+}
+
+impl DomainMetadataVisitor {
+    /// Create a new visitor. When domain is set then bookkeeping is only for that domain
+    pub(crate) fn new(domain: Option<String>) -> Self {
+        DomainMetadataVisitor {
+            domain_metadatas: HashMap::default(),
+            domain,
+        }
+    }
+}
+
+impl RowVisitor for DomainMetadataVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| DomainMetadata::to_schema().leaves(DOMAIN_METADATA_NAME));
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 3,
+            Error::InternalError(format!(
+                "Wrong number of DomainMetadataVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        // Assumes batches are visited in reverse order relative to the log
+        for i in 0..row_count {
+            let domain: Option<String> = getters[0].get_opt(i, "domainMetadata.domain")?;
+            let Some(domain) = domain else {
+                continue;
+            };
+            if self
+                .domain
+                .as_ref()
+                .is_some_and(|requested| *requested != domain)
+                || self.domain_metadatas.contains_key(&domain)
+            {
+                continue;
+            }
+            let configuration = getters[1].get(i, "domainMetadata.configuration")?;
+            let removed = getters[2].get(i, "domainMetadata.removed")?;
+            let domain_metadata = DomainMetadata {
+                domain: domain.clone(),
+                configuration,
+                removed,
+            };
+            self.domain_metadatas.insert(domain, domain_metadata);
+        }
+        Ok(())
+    }
+}
+
 /// Get a DV out of some engine data. The caller is responsible for slicing the `getters` slice such
 /// that the first element contains the `storageType` element of the deletion vector.
 pub(crate) fn visit_deletion_vector_at<'a>(
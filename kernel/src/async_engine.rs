@@ -0,0 +1,166 @@
+//! Async variants of the [`Engine`] traits, for engines whose IO is natively async.
+//!
+//! An [`AsyncEngine`] provides an [`AsyncFileSystemClient`], an [`AsyncJsonHandler`] and an
+//! [`AsyncParquetHandler`], whose reads return streams and whose writes return futures. Since the
+//! kernel itself is synchronous, an [`AsyncEngine`] is used through an [`Engine`] which drives
+//! these futures and streams, like the `AsyncEngineAdapter` of the default engine.
+//!
+//! [`Engine`]: crate::Engine
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use url::Url;
+
+use crate::schema::SchemaRef;
+use crate::{
+    AsAny, DeltaResult, EngineData, Error, ExpressionHandler, ExpressionRef, FileMeta, FileSlice,
+    FilteredEngineData, ListOptions,
+};
+
+/// The stream of data read from a list of files, see [`FileDataReadResultIterator`].
+///
+/// [`FileDataReadResultIterator`]: crate::FileDataReadResultIterator
+pub type FileDataReadResultStream = BoxStream<'static, DeltaResult<Box<dyn EngineData>>>;
+
+/// The async variant of [`FileSystemClient`].
+///
+/// [`FileSystemClient`]: crate::FileSystemClient
+pub trait AsyncFileSystemClient: AsAny {
+    /// List the paths in the same directory that are lexicographically greater or equal to
+    /// (UTF-8 sorting) the given `path`. The result should also be sorted by the file name.
+    fn list_from(&self, path: &Url) -> DeltaResult<BoxStream<'static, DeltaResult<FileMeta>>>;
+
+    /// List the paths in the directory of `path` at or after it, narrowed down by the given
+    /// options, see [`FileSystemClient::list_with_options`].
+    ///
+    /// The default implementation filters the results of [`Self::list_from`], and ignores
+    /// [`ListOptions::delimited`].
+    ///
+    /// [`FileSystemClient::list_with_options`]: crate::FileSystemClient::list_with_options
+    fn list_with_options(
+        &self,
+        path: &Url,
+        options: &ListOptions,
+    ) -> DeltaResult<BoxStream<'static, DeltaResult<FileMeta>>> {
+        let files = self.list_from(path)?;
+        let options = options.clone();
+        let max_results = options.max_results.unwrap_or(usize::MAX);
+        Ok(files
+            .try_filter(move |file| futures::future::ready(options.matches(file)))
+            .take(max_results)
+            .boxed())
+    }
+
+    /// Get the metadata of the file at `path`, see [`FileSystemClient::head`].
+    ///
+    /// The default implementation lists the directory of the file from its path.
+    ///
+    /// [`FileSystemClient::head`]: crate::FileSystemClient::head
+    fn head(&self, path: &Url) -> BoxFuture<'_, DeltaResult<FileMeta>> {
+        let path = path.clone();
+        Box::pin(async move {
+            match self.list_from(&path)?.try_next().await? {
+                Some(file) if file.location == path => Ok(file),
+                _ => Err(Error::file_not_found(path)),
+            }
+        })
+    }
+
+    /// Read data specified by the start and end offset from the files, in order.
+    fn read_files(
+        &self,
+        files: Vec<FileSlice>,
+    ) -> DeltaResult<BoxStream<'static, DeltaResult<Bytes>>>;
+
+    /// Atomically (!) write `data` to the file at `path`, see [`FileSystemClient::write_file`].
+    ///
+    /// [`FileSystemClient::write_file`]: crate::FileSystemClient::write_file
+    fn write_file(
+        &self,
+        path: &Url,
+        data: Bytes,
+        overwrite: bool,
+    ) -> BoxFuture<'_, DeltaResult<()>>;
+}
+
+/// The async variant of [`JsonHandler`].
+///
+/// [`JsonHandler`]: crate::JsonHandler
+pub trait AsyncJsonHandler: AsAny {
+    /// Parse the given json strings, see [`JsonHandler::parse_json`]. Parsing does no IO, so this
+    /// is synchronous.
+    ///
+    /// [`JsonHandler::parse_json`]: crate::JsonHandler::parse_json
+    fn parse_json(
+        &self,
+        json_strings: Box<dyn EngineData>,
+        output_schema: SchemaRef,
+    ) -> DeltaResult<Box<dyn EngineData>>;
+
+    /// Read and parse the JSON format files, see [`JsonHandler::read_json_files`].
+    ///
+    /// [`JsonHandler::read_json_files`]: crate::JsonHandler::read_json_files
+    fn read_json_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultStream>;
+
+    /// Atomically (!) write a single JSON file, see [`JsonHandler::write_json_file`].
+    ///
+    /// [`JsonHandler::write_json_file`]: crate::JsonHandler::write_json_file
+    fn write_json_file(
+        &self,
+        path: &Url,
+        data: Vec<Box<dyn EngineData>>,
+        overwrite: bool,
+    ) -> BoxFuture<'_, DeltaResult<()>>;
+}
+
+/// The async variant of [`ParquetHandler`].
+///
+/// [`ParquetHandler`]: crate::ParquetHandler
+pub trait AsyncParquetHandler: AsAny {
+    /// Read and parse the Parquet files, see [`ParquetHandler::read_parquet_files`].
+    ///
+    /// [`ParquetHandler::read_parquet_files`]: crate::ParquetHandler::read_parquet_files
+    fn read_parquet_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultStream>;
+
+    /// Write a single Parquet file, see [`ParquetHandler::write_parquet`].
+    ///
+    /// [`ParquetHandler::write_parquet`]: crate::ParquetHandler::write_parquet
+    fn write_parquet(
+        &self,
+        location: &Url,
+        schema: SchemaRef,
+        data: Vec<FilteredEngineData>,
+    ) -> BoxFuture<'_, DeltaResult<FileMeta>>;
+}
+
+/// The async variant of [`Engine`]. Expression evaluation does no IO, so the
+/// [`ExpressionHandler`] is shared with synchronous engines.
+///
+/// [`Engine`]: crate::Engine
+pub trait AsyncEngine: AsAny {
+    /// Get the connector provided [`ExpressionHandler`].
+    fn get_expression_handler(&self) -> Arc<dyn ExpressionHandler>;
+
+    /// Get the connector provided [`AsyncFileSystemClient`].
+    fn get_file_system_client(&self) -> Arc<dyn AsyncFileSystemClient>;
+
+    /// Get the connector provided [`AsyncJsonHandler`].
+    fn get_json_handler(&self) -> Arc<dyn AsyncJsonHandler>;
+
+    /// Get the connector provided [`AsyncParquetHandler`].
+    fn get_parquet_handler(&self) -> Arc<dyn AsyncParquetHandler>;
+}
@@ -0,0 +1,395 @@
+//! Writing of checkpoints, which reconcile the log of a snapshot into a single parquet file that
+//! contains the snapshot's live actions, so that readers don't need to replay every commit.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock};
+
+use tracing::debug;
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::set_transaction::is_expired_transaction;
+use crate::actions::{
+    get_log_schema, ADD_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME,
+};
+use crate::engine_data::{GetData, TypedGetData as _};
+use crate::expressions::{column_name, ColumnName};
+use crate::path::ParsedLogPath;
+use crate::scan::log_replay::SeenFileActions;
+use crate::schema::{
+    ColumnNamesAndTypes, DataType, SchemaRef, SchemaTransform, StructField, StructType,
+};
+use crate::snapshot::Snapshot;
+use crate::table_features::WriterFeatures;
+use crate::utils::{current_time, require, retention_cutoff_millis};
+use crate::{DeltaResult, Engine, Error, FileMeta, FilteredEngineData, RowVisitor};
+
+/// The result of writing a checkpoint, as needed for the `_last_checkpoint` hint file.
+#[derive(Debug)]
+pub(crate) struct CheckpointWriteResult {
+    /// The written checkpoint file
+    pub(crate) file: FileMeta,
+    /// The number of actions in the checkpoint
+    pub(crate) num_actions: i64,
+    /// The number of add actions in the checkpoint
+    pub(crate) num_add_files: i64,
+}
+
+/// Write a single-part classic checkpoint for the version of `snapshot`. The checkpoint contains
+/// the snapshot's protocol and metadata, its live add actions, the remove actions that have not yet
+/// expired (per `delta.deletedFileRetentionDuration`), the latest transaction of each application
+/// (unless it has expired per `delta.setTransactionRetentionDuration`) and the live domain
+/// metadata.
+pub(crate) fn write_checkpoint(
+    engine: &dyn Engine,
+    snapshot: &Snapshot,
+) -> DeltaResult<CheckpointWriteResult> {
+    require!(
+        !snapshot
+            .protocol()
+            .has_writer_feature(&WriterFeatures::V2Checkpoint),
+        Error::unsupported("Writing checkpoints of tables with v2 checkpoints is not supported")
+    );
+    let now = current_time();
+    let table_properties = snapshot.table_properties();
+    let tombstone_cutoff = retention_cutoff_millis(
+        now,
+        table_properties.deleted_file_retention_duration_or_default(),
+    )?;
+    let txn_cutoff = table_properties
+        .set_transaction_retention_duration
+        .map(|retention| retention_cutoff_millis(now, retention))
+        .transpose()?;
+
+    let read_schema = get_log_schema().project(&[
+        ADD_NAME,
+        REMOVE_NAME,
+        METADATA_NAME,
+        PROTOCOL_NAME,
+        SET_TRANSACTION_NAME,
+        DOMAIN_METADATA_NAME,
+    ])?;
+    let actions =
+        snapshot
+            .log_segment
+            .replay(engine, read_schema.clone(), read_schema.clone(), None)?;
+    let mut visitor = CheckpointVisitor::new(tombstone_cutoff, txn_cutoff);
+    let data = actions.map(|actions| -> DeltaResult<_> {
+        let (data, is_log_batch) = actions?;
+        visitor.is_log_batch = is_log_batch;
+        visitor.selection_vector = vec![false; data.len()];
+        visitor.visit_rows_of(data.as_ref())?;
+        Ok(FilteredEngineData {
+            data,
+            selection_vector: std::mem::take(&mut visitor.selection_vector),
+        })
+    });
+
+    let path =
+        ParsedLogPath::new_single_part_checkpoint(snapshot.table_root(), snapshot.version())?;
+    let file = engine.get_parquet_handler().write_parquet(
+        &path.location,
+        checkpoint_schema(&read_schema),
+        Box::new(data),
+    )?;
+    require!(
+        visitor.seen_protocol && visitor.seen_metadata,
+        Error::internal_error("Checkpoint must contain a protocol and metadata")
+    );
+    debug!(
+        "Wrote checkpoint {} with {} actions",
+        file.location, visitor.num_actions
+    );
+    Ok(CheckpointWriteResult {
+        file,
+        num_actions: visitor.num_actions,
+        num_add_files: visitor.num_add_files,
+    })
+}
+
+// The schema of the checkpoint file. Like Delta Spark, every field is nullable: each row holds a
+// single action, and the (required) fields of the other actions of the row are null.
+fn checkpoint_schema(read_schema: &StructType) -> SchemaRef {
+    struct MakeNullable;
+    impl<'a> SchemaTransform<'a> for MakeNullable {
+        fn transform_struct_field(
+            &mut self,
+            field: &'a StructField,
+        ) -> Option<Cow<'a, StructField>> {
+            let field = self.recurse_into_struct_field(field)?;
+            Some(Cow::Owned(StructField {
+                nullable: true,
+                ..field.into_owned()
+            }))
+        }
+    }
+    // NOTE: unwrap is safe because the transformer is incapable of returning None
+    Arc::new(
+        MakeNullable
+            .transform_struct(read_schema)
+            .unwrap()
+            .into_owned(),
+    )
+}
+
+/// Replays the actions of a snapshot newest-first, selecting the actions that belong in its
+/// checkpoint: the first action for each file (path, dvId) pair (unless it is an expired remove),
+/// the newest protocol and metadata, the newest transaction of each application (unless it has
+/// expired), and the newest action for each domain (unless the domain was removed).
+struct CheckpointVisitor {
+    seen_files: SeenFileActions,
+    seen_protocol: bool,
+    seen_metadata: bool,
+    seen_app_ids: HashSet<String>,
+    seen_domains: HashSet<String>,
+    /// Removes deleted before this time (in milliseconds since the epoch) have expired
+    tombstone_cutoff: i64,
+    /// Transactions last updated at or before this time (in milliseconds since the epoch) have
+    /// expired, if the table sets a transaction retention duration
+    txn_cutoff: Option<i64>,
+    selection_vector: Vec<bool>,
+    is_log_batch: bool,
+    num_actions: i64,
+    num_add_files: i64,
+}
+
+impl CheckpointVisitor {
+    fn new(tombstone_cutoff: i64, txn_cutoff: Option<i64>) -> Self {
+        CheckpointVisitor {
+            seen_files: SeenFileActions::default(),
+            seen_protocol: false,
+            seen_metadata: false,
+            seen_app_ids: HashSet::new(),
+            seen_domains: HashSet::new(),
+            tombstone_cutoff,
+            txn_cutoff,
+            selection_vector: vec![],
+            is_log_batch: false,
+            num_actions: 0,
+            num_add_files: 0,
+        }
+    }
+
+    // Returns true if this is the first action seen for the file. Like scan log replay, only files
+    // of commit batches are remembered, since checkpoint batches never contain duplicates.
+    fn is_first_file_action<'a>(
+        &mut self,
+        i: usize,
+        path: &str,
+        dv_getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<bool> {
+        let dv_unique_id = match dv_getters[0].get_opt(i, "deletionVector.storageType")? {
+            Some(storage_type) => Some(DeletionVectorDescriptor::unique_id_from_parts(
+                storage_type,
+                dv_getters[1].get(i, "deletionVector.pathOrInlineDv")?,
+                dv_getters[2].get_opt(i, "deletionVector.offset")?,
+            )),
+            None => None,
+        };
+        let key = self.seen_files.key(path, dv_unique_id.as_deref());
+        if self.seen_files.contains(&key) {
+            return Ok(false);
+        }
+        if self.is_log_batch {
+            self.seen_files.insert(key);
+        }
+        Ok(true)
+    }
+
+    fn is_checkpoint_action<'a>(
+        &mut self,
+        i: usize,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<bool> {
+        if let Some(path) = getters[0].get_opt(i, "add.path")? {
+            let selected = self.is_first_file_action(i, path, &getters[1..4])?;
+            if selected {
+                self.num_add_files += 1;
+            }
+            Ok(selected)
+        } else if let Some(path) = getters[4].get_opt(i, "remove.path")? {
+            let deletion_timestamp: Option<i64> =
+                getters[5].get_opt(i, "remove.deletionTimestamp")?;
+            Ok(self.is_first_file_action(i, path, &getters[6..9])?
+                && deletion_timestamp.unwrap_or(0) >= self.tombstone_cutoff)
+        } else if getters[9].get_str(i, "metaData.id")?.is_some() {
+            Ok(!std::mem::replace(&mut self.seen_metadata, true))
+        } else if getters[10]
+            .get_int(i, "protocol.minReaderVersion")?
+            .is_some()
+        {
+            Ok(!std::mem::replace(&mut self.seen_protocol, true))
+        } else if let Some(app_id) = getters[11].get_opt(i, "txn.appId")? {
+            let last_updated: Option<i64> = getters[12].get_opt(i, "txn.lastUpdated")?;
+            Ok(self.seen_app_ids.insert(app_id)
+                && !is_expired_transaction(last_updated, self.txn_cutoff))
+        } else if let Some(domain) = getters[13].get_opt(i, "domainMetadata.domain")? {
+            let removed: bool = getters[14].get(i, "domainMetadata.removed")?;
+            Ok(self.seen_domains.insert(domain) && !removed)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl RowVisitor for CheckpointVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            const STRING: DataType = DataType::STRING;
+            const INTEGER: DataType = DataType::INTEGER;
+            const LONG: DataType = DataType::LONG;
+            const BOOLEAN: DataType = DataType::BOOLEAN;
+            let types_and_names = vec![
+                (STRING, column_name!("add.path")),
+                (STRING, column_name!("add.deletionVector.storageType")),
+                (STRING, column_name!("add.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("add.deletionVector.offset")),
+                (STRING, column_name!("remove.path")),
+                (LONG, column_name!("remove.deletionTimestamp")),
+                (STRING, column_name!("remove.deletionVector.storageType")),
+                (STRING, column_name!("remove.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("remove.deletionVector.offset")),
+                (STRING, column_name!("metaData.id")),
+                (INTEGER, column_name!("protocol.minReaderVersion")),
+                (STRING, column_name!("txn.appId")),
+                (LONG, column_name!("txn.lastUpdated")),
+                (STRING, column_name!("domainMetadata.domain")),
+                (BOOLEAN, column_name!("domainMetadata.removed")),
+            ];
+            let (types, names) = types_and_names.into_iter().unzip();
+            (names, types).into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 15,
+            Error::InternalError(format!(
+                "Wrong number of CheckpointVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            let selected = self.is_checkpoint_action(i, getters)?;
+            if selected {
+                self.num_actions += 1;
+            }
+            self.selection_vector[i] = selected;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sync-engine"))]
+mod tests {
+    use std::path::Path;
+
+    use url::Url;
+
+    use super::*;
+    use crate::actions::set_transaction::SetTransactionScanner;
+    use crate::engine::sync::SyncEngine;
+    use crate::Table;
+
+    // Copy the test table `name` to a temporary directory, so that checkpoints can be written to it
+    fn copy_test_table(name: &str) -> (tempfile::TempDir, Url) {
+        fn copy_dir(from: &Path, to: &Path) {
+            std::fs::create_dir_all(to).unwrap();
+            for entry in std::fs::read_dir(from).unwrap() {
+                let entry = entry.unwrap();
+                let target = to.join(entry.file_name());
+                if entry.file_type().unwrap().is_dir() {
+                    copy_dir(&entry.path(), &target);
+                } else {
+                    std::fs::copy(entry.path(), target).unwrap();
+                }
+            }
+        }
+        let dir = tempfile::tempdir().unwrap();
+        copy_dir(&Path::new("./tests/data").join(name), dir.path());
+        let url = Url::from_directory_path(dir.path()).unwrap();
+        (dir, url)
+    }
+
+    #[test]
+    fn test_checkpoint_deletion_vectors() {
+        let engine = Arc::new(SyncEngine::new());
+        let (_dir, url) = copy_test_table("table-with-dv-small");
+        let table = Table::new(url);
+        let snapshot = table.snapshot(engine.as_ref(), None).unwrap();
+
+        // the expired remove of the file is dropped, and only its add with a DV is kept
+        let result = write_checkpoint(engine.as_ref(), &snapshot).unwrap();
+        assert_eq!(result.num_actions, 3);
+        assert_eq!(result.num_add_files, 1);
+        assert!(result
+            .file
+            .location
+            .path()
+            .ends_with("_delta_log/00000000000000000001.checkpoint.parquet"));
+        snapshot
+            .write_last_checkpoint(engine.as_ref(), &[result.file], 3, Some(1))
+            .unwrap();
+
+        let snapshot = table.snapshot(engine.as_ref(), None).unwrap();
+        assert_eq!(snapshot.version(), 1);
+        assert!(snapshot.log_segment.ascending_commit_files.is_empty());
+        assert_eq!(snapshot.log_segment.checkpoint_parts.len(), 1);
+        assert_eq!(snapshot.metadata().id, "testId");
+        assert_eq!(snapshot.protocol().min_reader_version(), 3);
+
+        // the deletion vector still applies when reading from the checkpoint
+        let scan = snapshot.into_scan_builder().build().unwrap();
+        let num_rows: usize = scan
+            .execute(engine)
+            .unwrap()
+            .map(|result| {
+                let result = result.unwrap();
+                let mask = result.full_mask();
+                let data = result.raw_data.unwrap();
+                match mask {
+                    Some(mask) => mask.iter().filter(|selected| **selected).count(),
+                    None => data.len(),
+                }
+            })
+            .sum();
+        assert_eq!(num_rows, 8);
+    }
+
+    #[test]
+    fn test_checkpoint_app_transactions() {
+        let engine = SyncEngine::new();
+        let (_dir, url) = copy_test_table("app-txn-no-checkpoint");
+        let table = Table::new(url);
+        table
+            .snapshot(&engine, None)
+            .unwrap()
+            .checkpoint(&engine)
+            .unwrap();
+
+        let snapshot = Arc::new(table.snapshot(&engine, None).unwrap());
+        assert!(snapshot.log_segment.ascending_commit_files.is_empty());
+        let txns = SetTransactionScanner::new(snapshot.clone())
+            .application_transactions(&engine)
+            .unwrap();
+        assert_eq!(txns.len(), 2);
+        assert_eq!(txns["my-app"].version, 3);
+        assert_eq!(txns["my-app2"].version, 2);
+
+        let scan = snapshot.scan_builder().build().unwrap();
+        let num_files: usize = scan
+            .scan_data(&engine)
+            .unwrap()
+            .map(|data| {
+                let (_, selection_vector) = data.unwrap();
+                selection_vector
+                    .into_iter()
+                    .filter(|selected| *selected)
+                    .count()
+            })
+            .sum();
+        assert_eq!(num_files, 4);
+    }
+}
@@ -126,7 +126,8 @@ impl TryFrom<&DataType> for ArrowDataType {
                     }
                 }
             }
-            DataType::Struct(s) => Ok(ArrowDataType::Struct(
+            // Variants are represented by their physical struct of binary fields
+            DataType::Struct(s) | DataType::Variant(s) => Ok(ArrowDataType::Struct(
                 s.fields()
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<ArrowField>, ArrowError>>()?
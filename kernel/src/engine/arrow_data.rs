@@ -1,4 +1,6 @@
+use crate::engine::memory::MemoryReservation;
 use crate::engine_data::{EngineData, EngineList, EngineMap, GetData, RowVisitor};
+use crate::scan::ScanResult;
 use crate::schema::{ColumnName, DataType};
 use crate::{DeltaResult, Error};
 
@@ -6,6 +8,7 @@ use arrow_array::cast::AsArray;
 use arrow_array::types::{Int32Type, Int64Type};
 use arrow_array::{Array, ArrayRef, GenericListArray, MapArray, OffsetSizeTrait, RecordBatch, StructArray};
 use arrow_schema::{FieldRef, DataType as ArrowDataType};
+use arrow_select::filter::filter_record_batch;
 use tracing::{debug};
 
 use std::collections::{HashMap, HashSet};
@@ -13,12 +16,23 @@ use std::collections::{HashMap, HashSet};
 /// ArrowEngineData holds an Arrow RecordBatch, implements `EngineData` so the kernel can extract from it.
 pub struct ArrowEngineData {
     data: RecordBatch,
+    /// The reservation of the memory of the data, if the engine limits its memory
+    reservation: Option<MemoryReservation>,
 }
 
 impl ArrowEngineData {
     /// Create a new `ArrowEngineData` from a `RecordBatch`
     pub fn new(data: RecordBatch) -> Self {
-        ArrowEngineData { data }
+        ArrowEngineData {
+            data,
+            reservation: None,
+        }
+    }
+
+    /// Hold `reservation` for as long as this `ArrowEngineData` exists
+    pub(crate) fn with_reservation(mut self, reservation: MemoryReservation) -> Self {
+        self.reservation = Some(reservation);
+        self
     }
 
     /// Utility constructor to get a `Box<ArrowEngineData>` out of a `Box<dyn EngineData>`
@@ -33,6 +47,28 @@ impl ArrowEngineData {
     pub fn record_batch(&self) -> &RecordBatch {
         &self.data
     }
+
+    /// Take apart this `ArrowEngineData` into its `RecordBatch` and the reservation of its memory
+    /// (if the engine limits its memory), which should be held for as long as the batch is.
+    pub fn into_parts(self) -> (RecordBatch, Option<MemoryReservation>) {
+        (self.data, self.reservation)
+    }
+
+    /// Get the `RecordBatch` of the rows of `engine_data` (which must be `ArrowEngineData`) that
+    /// are selected by `selection_vector`, e.g. the rows of a file which are not deleted by its
+    /// deletion vector. Rows past the end of the selection vector are selected, and all rows are
+    /// selected if there is none.
+    pub fn try_into_selected_record_batch(
+        engine_data: Box<dyn EngineData>,
+        selection_vector: Option<Vec<bool>>,
+    ) -> DeltaResult<RecordBatch> {
+        let batch: RecordBatch = Self::try_from_engine_data(engine_data)?.into();
+        let Some(mut selection_vector) = selection_vector else {
+            return Ok(batch);
+        };
+        selection_vector.resize(batch.num_rows(), true);
+        Ok(filter_record_batch(&batch, &selection_vector.into())?)
+    }
 }
 
 impl From<RecordBatch> for ArrowEngineData {
@@ -41,18 +77,41 @@ impl From<RecordBatch> for ArrowEngineData {
     }
 }
 
+/// Note that converting releases the reservation of the memory of the data (if any), see
+/// [`ArrowEngineData::into_parts`] to keep it.
 impl From<ArrowEngineData> for RecordBatch {
     fn from(value: ArrowEngineData) -> Self {
         value.data
     }
 }
 
+/// Note that converting releases the reservation of the memory of the data (if any), see
+/// [`ArrowEngineData::into_parts`] to keep it.
 impl From<Box<ArrowEngineData>> for RecordBatch {
     fn from(value: Box<ArrowEngineData>) -> Self {
         value.data
     }
 }
 
+/// Converts the rows of the result which are not deleted (see [`ScanResult::full_mask`]), for
+/// engines whose data is `ArrowEngineData`.
+impl TryFrom<ScanResult> for RecordBatch {
+    type Error = Error;
+
+    fn try_from(result: ScanResult) -> DeltaResult<Self> {
+        let mask = result.full_mask();
+        ArrowEngineData::try_into_selected_record_batch(result.raw_data?, mask)
+    }
+}
+
+// The string at `index` of `array`, which is a string or string view array
+fn string_value(array: &dyn Array, index: usize) -> &str {
+    match array.as_string_view_opt() {
+        Some(array) => array.value(index),
+        None => array.as_string::<i32>().value(index),
+    }
+}
+
 impl<OffsetSize> EngineList for GenericListArray<OffsetSize>
 where
     OffsetSize: OffsetSizeTrait,
@@ -62,9 +121,7 @@ where
     }
 
     fn get(&self, row_index: usize, index: usize) -> String {
-        let arry = self.value(row_index);
-        let sarry = arry.as_string::<i32>();
-        sarry.value(index).to_string()
+        string_value(&self.value(row_index), index).to_string()
     }
 
     fn materialize(&self, row_index: usize) -> Vec<String> {
@@ -81,14 +138,11 @@ impl EngineMap for MapArray {
         let offsets = self.offsets();
         let start_offset = offsets[row_index] as usize;
         let count = offsets[row_index + 1] as usize - start_offset;
-        let keys = self.keys().as_string::<i32>();
-        for (idx, map_key) in keys.iter().enumerate().skip(start_offset).take(count) {
-            if let Some(map_key) = map_key {
-                if key == map_key {
-                    // found the item
-                    let vals = self.values().as_string::<i32>();
-                    return Some(vals.value(idx));
-                }
+        let keys = self.keys();
+        for idx in start_offset..start_offset + count {
+            if keys.is_valid(idx) && key == string_value(keys, idx) {
+                // found the item
+                return Some(string_value(self.values(), idx));
             }
         }
         None
@@ -97,11 +151,10 @@ impl EngineMap for MapArray {
     fn materialize(&self, row_index: usize) -> HashMap<String, String> {
         let mut ret = HashMap::new();
         let map_val = self.value(row_index);
-        let keys = map_val.column(0).as_string::<i32>();
-        let values = map_val.column(1).as_string::<i32>();
-        for (key, value) in keys.iter().zip(values.iter()) {
-            if let (Some(key), Some(value)) = (key, value) {
-                ret.insert(key.into(), value.into());
+        let (keys, values) = (map_val.column(0), map_val.column(1));
+        for idx in 0..map_val.len() {
+            if keys.is_valid(idx) && values.is_valid(idx) {
+                ret.insert(string_value(keys, idx).into(), string_value(values, idx).into());
             }
         }
         ret
@@ -214,16 +267,17 @@ impl ArrowEngineData {
         data_type: &DataType,
         col: &'a dyn Array,
     ) -> DeltaResult<&'a dyn GetData<'a>> {
-        use ArrowDataType::Utf8;
+        use ArrowDataType::{Utf8, Utf8View};
+        let is_string = |data_type: &ArrowDataType| matches!(data_type, Utf8 | Utf8View);
         let col_as_list = || if let Some(array) = col.as_list_opt::<i32>() {
-            (array.value_type() == Utf8).then_some(array as _)
+            is_string(&array.value_type()).then_some(array as _)
         } else if let Some(array) = col.as_list_opt::<i64>() {
-            (array.value_type() == Utf8).then_some(array as _)
+            is_string(&array.value_type()).then_some(array as _)
         } else {
             None
         };
         let col_as_map = || col.as_map_opt().and_then(|array| {
-            (array.key_type() == &Utf8 && array.value_type() == &Utf8).then_some(array as _)
+            (is_string(array.key_type()) && is_string(array.value_type())).then_some(array as _)
         });
         let result: Result<&'a dyn GetData<'a>, _> = match data_type {
             &DataType::BOOLEAN => {
@@ -232,7 +286,10 @@ impl ArrowEngineData {
             }
             &DataType::STRING => {
                 debug!("Pushing string array for {}", ColumnName::new(path));
-                col.as_string_opt().map(|a| a as _).ok_or("string")
+                col.as_string_opt()
+                    .map(|a| a as _)
+                    .or_else(|| col.as_string_view_opt().map(|a| a as _))
+                    .ok_or("string")
             }
             &DataType::INTEGER => {
                 debug!("Pushing int32 array for {}", ColumnName::new(path));
@@ -269,6 +326,7 @@ impl ArrowEngineData {
 mod tests {
     use std::sync::Arc;
 
+    use arrow_array::cast::AsArray;
     use arrow_array::{RecordBatch, StringArray};
     use arrow_schema::{DataType, Field, Schema as ArrowSchema};
 
@@ -329,4 +387,21 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_selected_record_batch() -> DeltaResult<()> {
+        let data = || string_array_to_engine_data(vec!["a", "b", "c"].into());
+        let selected = |selection_vector| {
+            let batch = ArrowEngineData::try_into_selected_record_batch(data(), selection_vector)?;
+            Ok::<_, crate::Error>(batch.column(0).as_string::<i32>().clone())
+        };
+        // rows past the end of the selection vector are selected
+        assert_eq!(selected(Some(vec![false]))?, vec!["b", "c"].into());
+        assert_eq!(
+            selected(Some(vec![true, false, true]))?,
+            vec!["a", "c"].into()
+        );
+        assert_eq!(selected(None)?, vec!["a", "b", "c"].into());
+        Ok(())
+    }
 }
@@ -1,18 +1,22 @@
 //! Expression handling based on arrow-rs compute kernels.
 use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 
-use arrow_arith::boolean::{and_kleene, is_null, not, or_kleene};
+use arrow_arith::boolean::{and_kleene, is_not_null, is_null, not, or_kleene};
 use arrow_arith::numeric::{add, div, mul, sub};
 use arrow_array::cast::AsArray;
-use arrow_array::{types::*, MapArray};
 use arrow_array::{
-    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Datum, Decimal128Array, Float32Array,
-    Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, ListArray, RecordBatch,
-    StringArray, StructArray, TimestampMicrosecondArray,
+    new_null_array, Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Datum,
+    Decimal128Array, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+    ListArray, RecordBatch, StringArray, StructArray, TimestampMicrosecondArray,
 };
+use arrow_array::{types::*, MapArray};
 use arrow_buffer::OffsetBuffer;
+use arrow_cast::{cast, cast_with_options, CastOptions};
 use arrow_ord::cmp::{distinct, eq, gt, gt_eq, lt, lt_eq, neq};
 use arrow_ord::comparison::in_list_utf8;
 use arrow_schema::{
@@ -20,10 +24,13 @@ use arrow_schema::{
     Schema as ArrowSchema, TimeUnit,
 };
 use arrow_select::concat::concat;
+use arrow_select::nullif::nullif;
+use arrow_select::zip::zip;
+use indexmap::IndexMap;
 use itertools::Itertools;
 
 use super::arrow_conversion::LIST_ARRAY_ROOT;
-use super::arrow_utils::make_arrow_error;
+use super::arrow_utils::{make_arrow_error, to_view_schema, to_view_type};
 use crate::engine::arrow_data::ArrowEngineData;
 use crate::engine::arrow_utils::prim_array_cmp;
 use crate::engine::ensure_data_types::ensure_data_types;
@@ -117,7 +124,7 @@ impl Scalar {
                             .with_precision_and_scale(*precision, *scale as i8)?,
                     ),
                 },
-                DataType::Struct(t) => {
+                DataType::Struct(t) | DataType::Variant(t) => {
                     let fields: Fields = t.fields().map(ArrowField::try_from).try_collect()?;
                     Arc::new(StructArray::new_null(fields, num_rows))
                 }
@@ -126,7 +133,7 @@ impl Scalar {
                         ArrowField::new(LIST_ARRAY_ROOT, t.element_type().try_into()?, true);
                     Arc::new(ListArray::new_null(Arc::new(field), num_rows))
                 }
-                DataType::Map { .. } => unimplemented!(),
+                DataType::Map { .. } => new_null_array(&data_type.try_into()?, num_rows),
             },
         };
         Ok(arr)
@@ -188,7 +195,8 @@ fn extract_column(mut parent: &dyn ProvidesColumnByName, col: &[String]) -> Delt
     }
 }
 
-fn evaluate_expression(
+/// Evaluate `expression` over `batch`, with the `result_type` if given (e.g. to construct structs).
+pub(crate) fn evaluate_expression(
     expression: &Expression,
     batch: &RecordBatch,
     result_type: Option<&DataType>,
@@ -199,6 +207,19 @@ fn evaluate_expression(
         (Literal(scalar), _) => Ok(scalar.to_array(batch.num_rows())?),
         (Column(name), _) => extract_column(batch, name),
         (Struct(fields), Some(DataType::Struct(output_schema))) => {
+            let num_fields = output_schema.fields().count();
+            if fields.len() > num_fields {
+                return Err(Error::invalid_expression(format!(
+                    "Struct expression has {} fields, but its data type has only {num_fields}",
+                    fields.len(),
+                )));
+            }
+            if fields.is_empty() {
+                return Ok(Arc::new(StructArray::new_empty_fields(
+                    batch.num_rows(),
+                    None,
+                )));
+            }
             let columns = fields
                 .iter()
                 .zip(output_schema.fields())
@@ -285,6 +306,20 @@ fn evaluate_expression(
                 let exists = ad.array_elements().contains(lit);
                 Ok(Arc::new(BooleanArray::from(vec![exists])))
             }
+            (_, Literal(Scalar::Array(ad))) => {
+                // x IN (a, b, ...) is equivalent to x = a OR x = b OR ..., including for nulls
+                let left_arr = evaluate_expression(left.as_ref(), batch, None)?;
+                #[allow(deprecated)]
+                let elements = ad.array_elements();
+                let mut result = BooleanArray::from(vec![false; batch.num_rows()]);
+                for element in elements {
+                    let mut arrays = [left_arr.clone(), element.to_array(batch.num_rows())?];
+                    coerce_numeric(&mut arrays)?;
+                    coerce_views(&mut arrays)?;
+                    result = or_kleene(&result, &eq(&arrays[0], &arrays[1])?)?;
+                }
+                Ok(Arc::new(result))
+            }
             (l, r) => Err(Error::invalid_expression(format!(
                 "Invalid right value for (NOT) IN comparison, left is: {l} right is: {r}"
             ))),
@@ -297,15 +332,25 @@ fn evaluate_expression(
             }),
             _,
         ) => {
-            let reverse_op = Expression::binary(In, *left.clone(), *right.clone());
+            // The operands are shared with (not copied from) the NOT IN expression
+            let reverse_op = Expression::Binary(BinaryExpression {
+                op: In,
+                left: left.clone(),
+                right: right.clone(),
+            });
             let reverse_expr = evaluate_expression(&reverse_op, batch, None)?;
             not(reverse_expr.as_boolean())
                 .map(wrap_comparison_result)
                 .map_err(Error::generic_err)
         }
         (Binary(BinaryExpression { op, left, right }), _) => {
-            let left_arr = evaluate_expression(left.as_ref(), batch, None)?;
-            let right_arr = evaluate_expression(right.as_ref(), batch, None)?;
+            let mut arrays = [
+                evaluate_expression(left.as_ref(), batch, None)?,
+                evaluate_expression(right.as_ref(), batch, None)?,
+            ];
+            coerce_numeric(&mut arrays)?;
+            coerce_views(&mut arrays)?;
+            let [left_arr, right_arr] = arrays;
 
             type Operation = fn(&dyn Datum, &dyn Datum) -> Result<ArrayRef, ArrowError>;
             let eval: Operation = match op {
@@ -320,17 +365,43 @@ fn evaluate_expression(
                 Equal => |l, r| eq(l, r).map(wrap_comparison_result),
                 NotEqual => |l, r| neq(l, r).map(wrap_comparison_result),
                 Distinct => |l, r| distinct(l, r).map(wrap_comparison_result),
+                NullIf => |l, r| nullif(l.get().0, &eq(l, r)?),
                 // NOTE: [Not]In was already covered above
                 In | NotIn => return Err(Error::generic("Invalid expression given")),
             };
 
             eval(&left_arr, &right_arr).map_err(Error::generic_err)
         }
+        (
+            Variadic(VariadicExpression {
+                op: VariadicOperator::Coalesce,
+                exprs,
+            }),
+            _,
+        ) => {
+            let mut arrays: Vec<_> = exprs
+                .iter()
+                .map(|expr| evaluate_expression(expr, batch, result_type))
+                .try_collect()?;
+            coerce_numeric(&mut arrays)?;
+            coerce_views(&mut arrays)?;
+            let mut arrays = arrays.into_iter();
+            let first = arrays.next().ok_or_else(|| {
+                Error::invalid_expression("COALESCE requires at least one argument")
+            })?;
+            arrays.try_fold(first, |result, array| -> DeltaResult<_> {
+                Ok(zip(&is_not_null(&result)?, &result, &array)?)
+            })
+        }
         (Variadic(VariadicExpression { op, exprs }), None | Some(&DataType::BOOLEAN)) => {
             type Operation = fn(&BooleanArray, &BooleanArray) -> Result<BooleanArray, ArrowError>;
             let (reducer, default): (Operation, _) = match op {
                 VariadicOperator::And => (and_kleene, true),
                 VariadicOperator::Or => (or_kleene, false),
+                // NOTE: Coalesce was already covered above
+                VariadicOperator::Coalesce => {
+                    return Err(Error::generic("Invalid expression given"))
+                }
             };
             exprs
                 .iter()
@@ -345,13 +416,99 @@ fn evaluate_expression(
         }
         (Variadic(_), _) => {
             // NOTE: Update this error message if we add support for variadic operations on other types
-            Err(Error::Generic(format!(
+            Err(Error::unsupported(format!(
                 "Variadic {expression:?} is expected to return boolean results, got {result_type:?}"
             )))
         }
     }
 }
 
+// The numeric type to which operands of types `left` and `right` are widened before they are
+// combined, or `None` if they are not both numeric
+fn common_numeric_type(left: &ArrowDataType, right: &ArrowDataType) -> Option<ArrowDataType> {
+    use ArrowDataType::*;
+    // The width of an integer type, in bytes
+    fn int_width(data_type: &ArrowDataType) -> Option<u8> {
+        match data_type {
+            Int8 => Some(1),
+            Int16 => Some(2),
+            Int32 => Some(4),
+            Int64 => Some(8),
+            _ => None,
+        }
+    }
+    // The precision and scale of the narrowest decimal which holds every value of the type
+    fn as_decimal(data_type: &ArrowDataType) -> Option<(u8, i8)> {
+        match data_type {
+            Int8 => Some((3, 0)),
+            Int16 => Some((5, 0)),
+            Int32 => Some((10, 0)),
+            Int64 => Some((20, 0)),
+            Decimal128(precision, scale) => Some((*precision, *scale)),
+            _ => None,
+        }
+    }
+    let is_numeric = |t: &ArrowDataType| as_decimal(t).is_some() || matches!(t, Float32 | Float64);
+    match (left, right) {
+        _ if left == right => Some(left.clone()),
+        _ if !is_numeric(left) || !is_numeric(right) => None,
+        (l, r) if int_width(l).is_some() && int_width(r).is_some() => {
+            Some(std::cmp::max_by_key(l, r, |t| int_width(t)).clone())
+        }
+        (Float32, Int8 | Int16) | (Int8 | Int16, Float32) => Some(Float32),
+        (Float32 | Float64, _) | (_, Float32 | Float64) => Some(Float64),
+        (l, r) => {
+            let (left_precision, left_scale) = as_decimal(l)?;
+            let (right_precision, right_scale) = as_decimal(r)?;
+            let scale = left_scale.max(right_scale);
+            let int_digits = (left_precision as i16 - left_scale as i16)
+                .max(right_precision as i16 - right_scale as i16);
+            let precision = (int_digits + scale as i16).min(38) as u8;
+            Some(Decimal128(precision, scale))
+        }
+    }
+}
+
+// Cast numeric arrays of different types to their common type (if any), so that they can be
+// combined by arrow kernels, which require operands of the same type. Values which don't fit the
+// common type (e.g. large integers cast to a decimal whose precision is capped) are errors.
+fn coerce_numeric(arrays: &mut [ArrayRef]) -> DeltaResult<()> {
+    let common_type = arrays
+        .iter()
+        .map(|array| Some(array.data_type().clone()))
+        .reduce(|l, r| common_numeric_type(&l?, &r?));
+    if let Some(Some(common_type)) = common_type {
+        let options = CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+        for array in arrays.iter_mut() {
+            if *array.data_type() != common_type {
+                *array = cast_with_options(array, &common_type, &options)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Cast the strings and binaries among `arrays` to views if any of them is a view, so that they can
+// be combined by arrow kernels (e.g. to compare a column read as views with a string literal)
+fn coerce_views(arrays: &mut [ArrayRef]) -> DeltaResult<()> {
+    for (data_type, view_type) in [
+        (ArrowDataType::Utf8, ArrowDataType::Utf8View),
+        (ArrowDataType::Binary, ArrowDataType::BinaryView),
+    ] {
+        if arrays.iter().any(|array| *array.data_type() == view_type) {
+            for array in arrays.iter_mut() {
+                if *array.data_type() == data_type {
+                    *array = cast(array, &view_type)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 // Apply a schema to an array. The array _must_ be a `StructArray`. Returns a `RecordBatch where the
 // names of fields, nullable, and metadata in the struct have been transformed to match those in
 // schema specified by `schema`
@@ -510,8 +667,40 @@ fn apply_schema_to(array: &ArrayRef, schema: &DataType) -> DeltaResult<ArrayRef>
     Ok(array)
 }
 
-#[derive(Debug)]
-pub struct ArrowExpressionHandler;
+/// The maximum number of evaluators cached by an [`ArrowExpressionHandler`]
+const EVALUATOR_CACHE_CAPACITY: usize = 64;
+
+#[derive(Debug, Default)]
+pub struct ArrowExpressionHandler {
+    view_types: bool,
+    evaluators: EvaluatorCache,
+}
+
+impl ArrowExpressionHandler {
+    /// Create a handler which produces `Utf8` and `Binary` arrays, like [`Default::default`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Produce the strings and binaries of the results of the evaluators (and of
+    /// [`ExpressionHandler::create_one`]) as `Utf8View` and `BinaryView` arrays, rather than
+    /// `Utf8` and `Binary` arrays. Evaluators consume both regardless.
+    ///
+    /// Defaults to false.
+    pub fn with_view_types(mut self, view_types: bool) -> Self {
+        self.view_types = view_types;
+        self
+    }
+}
+
+// Represent the strings and binaries of `array` (at any depth) as views
+fn cast_to_views(array: ArrayRef) -> DeltaResult<ArrayRef> {
+    let view_type = to_view_type(array.data_type());
+    if *array.data_type() == view_type {
+        return Ok(array);
+    }
+    Ok(cast(&array, &view_type)?)
+}
 
 impl ExpressionHandler for ArrowExpressionHandler {
     fn get_evaluator(
@@ -520,12 +709,77 @@ impl ExpressionHandler for ArrowExpressionHandler {
         expression: Expression,
         output_type: DataType,
     ) -> Arc<dyn ExpressionEvaluator> {
-        Arc::new(DefaultExpressionEvaluator {
+        self.evaluators.get_or_insert(DefaultExpressionEvaluator {
             input_schema: schema,
             expression: Box::new(expression),
             output_type,
+            view_types: self.view_types,
         })
     }
+
+    fn create_one(&self, schema: SchemaRef, values: &[Scalar]) -> DeltaResult<Box<dyn EngineData>> {
+        if schema.fields.len() != values.len() {
+            return Err(Error::generic(format!(
+                "Expected {} values for schema but got {}",
+                schema.fields.len(),
+                values.len()
+            )));
+        }
+        let mut arrays: Vec<ArrayRef> =
+            values.iter().map(|value| value.to_array(1)).try_collect()?;
+        let mut arrow_schema: ArrowSchema = schema.as_ref().try_into()?;
+        if self.view_types {
+            arrays = arrays.into_iter().map(cast_to_views).try_collect()?;
+            arrow_schema = to_view_schema(&arrow_schema);
+        }
+        let batch = RecordBatch::try_new(Arc::new(arrow_schema), arrays)?;
+        Ok(Box::new(ArrowEngineData::new(batch)))
+    }
+}
+
+/// The evaluators most recently created by an [`ArrowExpressionHandler`], which are shared by all
+/// the callers asking for the same evaluator (e.g. the scans of a table, or each data file of a
+/// partition), up to [`EVALUATOR_CACHE_CAPACITY`] evaluators. Evaluators hold no state between
+/// batches, so a shared evaluator can be used concurrently and for any number of batches.
+#[derive(Debug, Default)]
+struct EvaluatorCache {
+    /// The cached evaluators by their [keys][DefaultExpressionEvaluator::key], from the least to
+    /// the most recently used
+    evaluators: Mutex<IndexMap<u64, Arc<DefaultExpressionEvaluator>>>,
+}
+
+impl EvaluatorCache {
+    /// The cached evaluator equal to `evaluator`, or else `evaluator` (which is then cached)
+    fn get_or_insert(
+        &self,
+        evaluator: DefaultExpressionEvaluator,
+    ) -> Arc<DefaultExpressionEvaluator> {
+        // A thread panicked while holding the lock, so the cache may be inconsistent: stop caching
+        let Ok(mut evaluators) = self.evaluators.lock() else {
+            return Arc::new(evaluator);
+        };
+        let key = evaluator.key();
+        // An evaluator with the same key but which isn't equal is replaced
+        let evaluator = match evaluators.shift_remove(&key) {
+            Some(cached) if cached.same_as(&evaluator) => cached,
+            _ => Arc::new(evaluator),
+        };
+        if evaluators.len() >= EVALUATOR_CACHE_CAPACITY {
+            evaluators.shift_remove_index(0);
+        }
+        evaluators.insert(key, evaluator.clone());
+        evaluator
+    }
+}
+
+/// Feeds what is written to it to a [`Hasher`]
+struct HashWriter<'a, H>(&'a mut H);
+
+impl<H: Hasher> std::fmt::Write for HashWriter<'_, H> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -533,6 +787,32 @@ pub struct DefaultExpressionEvaluator {
     input_schema: SchemaRef,
     expression: Box<Expression>,
     output_type: DataType,
+    view_types: bool,
+}
+
+impl DefaultExpressionEvaluator {
+    // Whether this evaluator evaluates the same expression as `other`, over the same input schema
+    fn same_as(&self, other: &Self) -> bool {
+        (Arc::ptr_eq(&self.input_schema, &other.input_schema)
+            || self.input_schema == other.input_schema)
+            && self.expression == other.expression
+            && self.output_type == other.output_type
+            && self.view_types == other.view_types
+    }
+
+    // The key of this evaluator in an [`EvaluatorCache`], which is the same for evaluators which
+    // are the [same][Self::same_as]. Expressions (with their floating point literals) and schemas
+    // (with their metadata maps) aren't `Hash`, so this hashes their display forms instead.
+    fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let mut writer = HashWriter(&mut hasher);
+        for field in self.input_schema.fields() {
+            let _ = write!(writer, "{}: {},", field.name, field.data_type);
+        }
+        let _ = write!(writer, "{} -> {}", self.expression, self.output_type);
+        self.view_types.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
 impl ExpressionEvaluator for DefaultExpressionEvaluator {
@@ -542,21 +822,17 @@ impl ExpressionEvaluator for DefaultExpressionEvaluator {
             .downcast_ref::<ArrowEngineData>()
             .ok_or_else(|| Error::engine_data_type("ArrowEngineData"))?
             .record_batch();
-        let _input_schema: ArrowSchema = self.input_schema.as_ref().try_into()?;
-        // TODO: make sure we have matching schemas for validation
-        // if batch.schema().as_ref() != &input_schema {
-        //     return Err(Error::Generic(format!(
-        //         "input schema does not match batch schema: {:?} != {:?}",
-        //         input_schema,
-        //         batch.schema()
-        //     )));
-        // };
-        let array_ref = evaluate_expression(&self.expression, batch, Some(&self.output_type))?;
+        // TODO: make sure the batch matches the input schema, without converting the input schema
+        // for each batch
+        let mut array_ref = evaluate_expression(&self.expression, batch, Some(&self.output_type))?;
+        if self.view_types {
+            array_ref = cast_to_views(array_ref)?;
+        }
         let batch: RecordBatch = if let DataType::Struct(_) = self.output_type {
             apply_schema(&array_ref, &self.output_type)?
         } else {
             let array_ref = apply_schema_to(&array_ref, &self.output_type)?;
-            let arrow_type: ArrowDataType = ArrowDataType::try_from(&self.output_type)?;
+            let arrow_type = array_ref.data_type().clone();
             let schema = ArrowSchema::new(vec![ArrowField::new("output", arrow_type, true)]);
             RecordBatch::try_new(Arc::new(schema), vec![array_ref])?
         };
@@ -568,7 +844,7 @@ impl ExpressionEvaluator for DefaultExpressionEvaluator {
 mod tests {
     use std::ops::{Add, Div, Mul, Sub};
 
-    use arrow_array::{GenericStringArray, Int32Array};
+    use arrow_array::{GenericStringArray, Int32Array, StringViewArray};
     use arrow_buffer::ScalarBuffer;
     use arrow_schema::{DataType, Field, Fields, Schema};
 
@@ -867,4 +1143,324 @@ mod tests {
         let expected = Arc::new(BooleanArray::from(vec![true, false]));
         assert_eq!(results.as_ref(), expected.as_ref());
     }
+
+    #[test]
+    fn test_binary_op_mixed_types() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("c", DataType::Float32, true),
+            Field::new("d", DataType::Decimal128(5, 2), true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), None])),
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+                Arc::new(Float32Array::from(vec![0.5, 1.5, 2.5])),
+                Arc::new(
+                    Decimal128Array::from(vec![150, 250, 350])
+                        .with_precision_and_scale(5, 2)
+                        .unwrap(),
+                ),
+            ],
+        )
+        .unwrap();
+
+        // integers are widened to the widest integer type
+        let expression = column_expr!("a").add(column_expr!("b"));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Int64Array::from(vec![Some(11), Some(22), None]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        // integers and floats are widened to doubles
+        let expression = column_expr!("b").mul(column_expr!("c"));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Float64Array::from(vec![5.0, 30.0, 75.0]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        // integers are widened to decimals
+        let expression = column_expr!("d").sub(column_expr!("a"));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        assert_eq!(results.as_primitive::<Decimal128Type>().value(0), 50);
+        assert_eq!(results.as_primitive::<Decimal128Type>().value(1), 50);
+        assert!(results.is_null(2));
+
+        // and so are the operands of comparisons
+        let expression = column_expr!("a").lt(Expression::literal(2i64));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = BooleanArray::from(vec![Some(true), Some(false), None]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        // non-numeric types are not coerced
+        let expression = column_expr!("a").add(Expression::literal("1"));
+        assert!(evaluate_expression(&expression, &batch, None).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_overflow() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int64, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![i32::MAX])),
+                Arc::new(Int64Array::from(vec![i64::MAX])),
+            ],
+        )
+        .unwrap();
+
+        // overflows are errors rather than nulls
+        let expression = column_expr!("a").add(Expression::literal(1));
+        assert!(evaluate_expression(&expression, &batch, None).is_err());
+
+        // and so are values which don't fit the common type of the operands
+        let expression = column_expr!("b").add(Scalar::Decimal(1, 38, 20));
+        assert!(evaluate_expression(&expression, &batch, None).is_err());
+    }
+
+    #[test]
+    fn test_null_if_and_coalesce() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int64, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), None, None])),
+                Arc::new(Int64Array::from(vec![Some(1), None, Some(3), None])),
+            ],
+        )
+        .unwrap();
+
+        let expression = column_expr!("a").null_if(Expression::literal(2));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Int32Array::from(vec![Some(1), None, None, None]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        let expression = Expression::coalesce([column_expr!("a"), column_expr!("b")]);
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Int64Array::from(vec![Some(1), Some(2), Some(3), None]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        let expression = Expression::coalesce([
+            column_expr!("b"),
+            column_expr!("a"),
+            Expression::literal(0i64),
+        ]);
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = Int64Array::from(vec![1, 2, 3, 0]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        let expression = Expression::coalesce([]);
+        assert!(evaluate_expression(&expression, &batch, None).is_err());
+    }
+
+    #[test]
+    fn test_in_list_of_column() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let values = Int64Array::from(vec![Some(1), Some(2), Some(3), None]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
+        let array = |values: Vec<Scalar>| {
+            let array_type = ArrayType::new(DeltaDataTypes::INTEGER, true);
+            Scalar::Array(ArrayData::new(array_type, values))
+        };
+
+        let expression = Expression::binary(
+            BinaryOperator::In,
+            column_expr!("a"),
+            array(vec![1.into(), 3.into()]),
+        );
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = BooleanArray::from(vec![Some(true), Some(false), Some(true), None]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+
+        // the comparison with a null element is null unless another element matches
+        let elements = vec![1.into(), Scalar::Null(DeltaDataTypes::INTEGER)];
+        let expression =
+            Expression::binary(BinaryOperator::NotIn, column_expr!("a"), array(elements));
+        let results = evaluate_expression(&expression, &batch, None).unwrap();
+        let expected = BooleanArray::from(vec![Some(false), None, None, None]);
+        assert_eq!(results.as_ref(), &expected as &dyn Array);
+    }
+
+    #[test]
+    fn test_nested_struct() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let values = Int32Array::from(vec![1, 2]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
+        let inner = crate::schema::StructType::new([
+            StructField::new("x", DeltaDataTypes::INTEGER, false),
+            StructField::new("y", DeltaDataTypes::STRING, true),
+        ]);
+        let outer = crate::schema::StructType::new([
+            StructField::new("inner", inner, false),
+            StructField::new("z", DeltaDataTypes::LONG, false),
+        ]);
+        let expression = Expression::struct_from([
+            Expression::struct_from([
+                column_expr!("a"),
+                Expression::null_literal(DeltaDataTypes::STRING),
+            ]),
+            column_expr!("a").add(Expression::literal(1i64)),
+        ]);
+        let results =
+            evaluate_expression(&expression, &batch, Some(&outer.clone().into())).unwrap();
+        let results = results.as_struct();
+        let inner_results = results.column_by_name("inner").unwrap().as_struct();
+        let expected = Int32Array::from(vec![1, 2]);
+        assert_eq!(inner_results.column(0).as_ref(), &expected as &dyn Array);
+        assert_eq!(inner_results.column(1).null_count(), 2);
+        let expected = Int64Array::from(vec![2, 3]);
+        assert_eq!(
+            results.column_by_name("z").unwrap().as_ref(),
+            &expected as &dyn Array
+        );
+
+        // there must be a field in the data type for every expression
+        let expression =
+            Expression::struct_from([column_expr!("a"), column_expr!("a"), column_expr!("a")]);
+        let result = evaluate_expression(&expression, &batch, Some(&outer.into()));
+        assert!(matches!(result, Err(Error::InvalidExpressionEvaluation(_))));
+    }
+
+    #[test]
+    fn test_unsupported_expressions() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Boolean, false)]);
+        let values = BooleanArray::from(vec![true, false]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
+        let expression = Expression::and(column_expr!("a"), column_expr!("a"));
+        let result = evaluate_expression(&expression, &batch, Some(&DeltaDataTypes::LONG));
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_create_one() {
+        let schema = Arc::new(crate::schema::StructType::new([
+            StructField::new("a", DeltaDataTypes::LONG, false),
+            StructField::new("b", DeltaDataTypes::STRING, true),
+            StructField::new("c", DeltaDataTypes::STRING, true),
+        ]));
+        let values = [
+            Scalar::Long(1),
+            Scalar::from("hello"),
+            Scalar::Null(DeltaDataTypes::STRING),
+        ];
+        let batch: RecordBatch = ArrowExpressionHandler::default()
+            .create_one(schema.clone(), &values)
+            .unwrap()
+            .into_any()
+            .downcast::<ArrowEngineData>()
+            .unwrap()
+            .into();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 1);
+        assert_eq!(batch.column(1).as_string::<i32>().value(0), "hello");
+        assert!(batch.column(2).is_null(0));
+
+        // wrong number of values
+        assert!(ArrowExpressionHandler::default()
+            .create_one(schema.clone(), &values[..2])
+            .is_err());
+        // null in a non-nullable field
+        let values = [
+            Scalar::Null(DeltaDataTypes::LONG),
+            Scalar::from("hello"),
+            Scalar::Null(DeltaDataTypes::STRING),
+        ];
+        assert!(ArrowExpressionHandler::default()
+            .create_one(schema, &values)
+            .is_err());
+    }
+
+    #[test]
+    fn test_evaluator_cache() {
+        let handler = ArrowExpressionHandler::default();
+        let schema = Arc::new(crate::schema::StructType::new([
+            crate::schema::StructField::new("a", DeltaDataTypes::LONG, true),
+        ]));
+        let evaluator = |schema: SchemaRef, value: i64| {
+            let expression = column_expr!("a").gt(Expression::literal(value));
+            handler.get_evaluator(schema, expression, DeltaDataTypes::BOOLEAN)
+        };
+        let same = |a: &Arc<dyn ExpressionEvaluator>, b: &Arc<dyn ExpressionEvaluator>| {
+            std::ptr::addr_eq(Arc::as_ptr(a), Arc::as_ptr(b))
+        };
+
+        // equal requests share an evaluator, even with distinct (but equal) schemas
+        let first = evaluator(schema.clone(), 0);
+        assert!(same(&evaluator(schema.clone(), 0), &first));
+        assert!(same(
+            &evaluator(Arc::new(schema.as_ref().clone()), 0),
+            &first
+        ));
+        let second = evaluator(schema.clone(), 1);
+        assert!(!same(&second, &first));
+        let non_nullable = Arc::new(crate::schema::StructType::new([
+            crate::schema::StructField::new("a", DeltaDataTypes::LONG, false),
+        ]));
+        assert!(!same(&evaluator(non_nullable, 1), &second));
+        let second = evaluator(schema.clone(), 1);
+
+        // the least recently used evaluators are evicted once the cache is full
+        for value in 2..EVALUATOR_CACHE_CAPACITY as i64 {
+            evaluator(schema.clone(), value);
+        }
+        assert!(same(&evaluator(schema.clone(), 0), &first));
+        evaluator(schema.clone(), -1);
+        assert!(!same(&evaluator(schema.clone(), 1), &second));
+        assert!(same(&evaluator(schema.clone(), 0), &first));
+        assert_eq!(
+            handler.evaluators.evaluators.lock().unwrap().len(),
+            EVALUATOR_CACHE_CAPACITY
+        );
+    }
+
+    #[test]
+    fn test_view_types() {
+        let schema = Schema::new(vec![Field::new("s", DataType::Utf8View, true)]);
+        let values = StringViewArray::from(vec![Some("a"), Some("b"), None]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
+        let data = ArrowEngineData::new(batch);
+        let input_schema = Arc::new(crate::schema::StructType::new([
+            crate::schema::StructField::new("s", DeltaDataTypes::STRING, true),
+        ]));
+
+        // views are compared with string literals
+        let handler = ArrowExpressionHandler::default();
+        let evaluator = handler.get_evaluator(
+            input_schema.clone(),
+            column_expr!("s").eq(Expression::literal("a")),
+            DeltaDataTypes::BOOLEAN,
+        );
+        let result: RecordBatch =
+            ArrowEngineData::try_from_engine_data(evaluator.evaluate(&data).unwrap())
+                .unwrap()
+                .into();
+        let expected = BooleanArray::from(vec![Some(true), Some(false), None]);
+        assert_eq!(result.column(0).as_boolean(), &expected);
+
+        // string results (including literals) are produced as views
+        let handler = ArrowExpressionHandler::default().with_view_types(true);
+        let output_type = DeltaDataTypes::struct_type([
+            crate::schema::StructField::new("s", DeltaDataTypes::STRING, true),
+            crate::schema::StructField::new("lit", DeltaDataTypes::STRING, true),
+        ]);
+        let evaluator = handler.get_evaluator(
+            input_schema,
+            Expression::struct_from([column_expr!("s"), Expression::literal("x")]),
+            output_type,
+        );
+        let result: RecordBatch =
+            ArrowEngineData::try_from_engine_data(evaluator.evaluate(&data).unwrap())
+                .unwrap()
+                .into();
+        for column in result.columns() {
+            assert_eq!(column.data_type(), &DataType::Utf8View);
+        }
+        assert_eq!(result.column(1).as_string_view().value(2), "x");
+    }
 }
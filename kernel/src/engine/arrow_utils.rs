@@ -1,29 +1,38 @@
 //! Some utilities for working with arrow data types
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::{BufRead, BufReader};
 use std::sync::Arc;
 
 use crate::engine::ensure_data_types::DataTypeCompat;
 use crate::{
     engine::arrow_data::ArrowEngineData,
-    schema::{DataType, Schema, SchemaRef, StructField, StructType},
+    schema::{
+        ColumnMetadataKey, DataType, MetadataValue, Schema, SchemaRef, StructField, StructType,
+    },
     utils::require,
-    DeltaResult, EngineData, Error,
+    DeltaResult, EngineData, Error, FilteredEngineData,
 };
 
 use arrow_array::{
-    cast::AsArray, new_null_array, Array as ArrowArray, GenericListArray, OffsetSizeTrait,
-    RecordBatch, StringArray, StructArray,
+    cast::AsArray, make_array, new_null_array, Array as ArrowArray, ArrayRef as ArrowArrayRef,
+    BooleanArray, GenericListArray, MapArray, OffsetSizeTrait, RecordBatch, StringArray,
+    StructArray,
 };
 use arrow_json::{LineDelimitedWriter, ReaderBuilder};
+use arrow_schema::Schema as ArrowSchema;
 use arrow_schema::{
     DataType as ArrowDataType, Field as ArrowField, FieldRef as ArrowFieldRef, Fields,
     SchemaRef as ArrowSchemaRef,
 };
 use arrow_select::concat::concat_batches;
+use arrow_select::filter::filter_record_batch;
 use itertools::Itertools;
-use parquet::{arrow::ProjectionMask, schema::types::SchemaDescriptor};
+use parquet::{
+    arrow::{ArrowWriter, ProjectionMask, PARQUET_FIELD_ID_META_KEY},
+    file::properties::WriterProperties,
+    schema::types::SchemaDescriptor,
+};
 use tracing::debug;
 
 macro_rules! prim_array_cmp {
@@ -165,11 +174,13 @@ pub(crate) fn make_arrow_error(s: impl Into<String>) -> Error {
 /// position represents a column that will be in the read parquet data at that level and
 /// position. The `index` of the element is the position that the column should appear in the final
 /// output. The `transform` indicates what, if any, transforms are needed. See the docs for
-/// [`ReorderIndexTransform`] for the meaning.
+/// [`ReorderIndexTransform`] for the meaning. If `rename` is set, the column is renamed to the
+/// contained name, which happens when a column was matched by field id rather than by name.
 #[derive(Debug, PartialEq)]
 pub(crate) struct ReorderIndex {
     pub(crate) index: usize,
     transform: ReorderIndexTransform,
+    rename: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -186,7 +197,11 @@ pub(crate) enum ReorderIndexTransform {
 
 impl ReorderIndex {
     fn new(index: usize, transform: ReorderIndexTransform) -> Self {
-        ReorderIndex { index, transform }
+        ReorderIndex {
+            index,
+            transform,
+            rename: None,
+        }
     }
 
     fn cast(index: usize, target: ArrowDataType) -> Self {
@@ -208,6 +223,9 @@ impl ReorderIndex {
     /// Check if this reordering requires a transformation anywhere. See comment below on
     /// [`ordering_needs_transform`] to understand why this is needed.
     fn needs_transform(&self) -> bool {
+        if self.rename.is_some() {
+            return true;
+        }
         match self.transform {
             // if we're casting or inserting null, we need to transform
             ReorderIndexTransform::Cast(_) | ReorderIndexTransform::Missing(_) => true,
@@ -219,6 +237,25 @@ impl ReorderIndex {
     }
 }
 
+/// Get the parquet field id of an arrow field read from parquet, if it has one.
+fn parquet_field_id(field: &ArrowField) -> Option<i32> {
+    field
+        .metadata()
+        .get(PARQUET_FIELD_ID_META_KEY)?
+        .parse()
+        .ok()
+}
+
+/// Get the parquet field id a requested field should be resolved by, if it has one. Physical
+/// schemas of tables in column mapping `id` mode carry these.
+fn requested_field_id(field: &StructField) -> Option<i32> {
+    match field.get_config_value(&ColumnMetadataKey::ParquetFieldId)? {
+        MetadataValue::Number(id) => Some(*id),
+        MetadataValue::String(id) => id.parse().ok(),
+        _ => None,
+    }
+}
+
 // count the number of physical columns, including nested ones in an `ArrowField`
 fn count_cols(field: &ArrowField) -> usize {
     _count_cols(field.data_type())
@@ -250,11 +287,22 @@ fn get_indices(
     let mut found_fields = HashSet::with_capacity(requested_schema.fields.len());
     let mut reorder_indices = Vec::with_capacity(requested_schema.fields.len());
     let mut parquet_offset = start_parquet_offset;
+    // If the requested fields have field ids, parquet fields which also have a field id are
+    // resolved by id rather than by name (e.g. for column mapping `id` mode)
+    let requested_ids: HashMap<i32, &str> = requested_schema
+        .fields()
+        .filter_map(|field| Some((requested_field_id(field)?, field.name().as_str())))
+        .collect();
     // for each field, get its position in the parquet (via enumerate), a reference to the arrow
     // field, and info about where it appears in the requested_schema, or None if the field is not
     // requested
     let all_field_info = fields.iter().enumerate().map(|(parquet_index, field)| {
-        let field_info = requested_schema.fields.get_full(field.name());
+        let field_info = match parquet_field_id(field) {
+            Some(id) if !requested_ids.is_empty() => requested_ids
+                .get(&id)
+                .and_then(|name| requested_schema.fields.get_full(*name)),
+            _ => requested_schema.fields.get_full(field.name()),
+        };
         (parquet_index, field, field_info)
     });
     for (parquet_index, field, field_info) in all_field_info {
@@ -265,7 +313,21 @@ fn get_indices(
         if let Some((index, _, requested_field)) = field_info {
             match field.data_type() {
                 ArrowDataType::Struct(fields) => {
-                    if let DataType::Struct(ref requested_schema) = requested_field.data_type {
+                    if let DataType::Variant(ref requested_schema) = requested_field.data_type {
+                        // A shredded variant stores (parts of) its values in a `typed_value`
+                        // column, which we can't reassemble into the unshredded representation
+                        if fields.find("typed_value").is_some()
+                            && requested_schema.field("typed_value").is_none()
+                        {
+                            return Err(Error::unsupported(format!(
+                                "Reading shredded variant column {} is not supported",
+                                field.name()
+                            )));
+                        }
+                    }
+                    if let DataType::Struct(ref requested_schema)
+                    | DataType::Variant(ref requested_schema) = requested_field.data_type
+                    {
                         let (parquet_advance, children) = get_indices(
                             parquet_index + parquet_offset,
                             requested_schema.as_ref(),
@@ -314,12 +376,28 @@ fn get_indices(
                         // the index is wrong, as it's the index from the inner schema. Adjust
                         // it to be our index
                         children.index = index;
+                        // a cast of the element (e.g. after type widening) becomes a cast of the
+                        // whole list, since we can't cast the child array in place
+                        if let ReorderIndexTransform::Cast(element_type) = children.transform {
+                            let element_field =
+                                Arc::new(list_field.as_ref().clone().with_data_type(element_type));
+                            let list_type = match field.data_type() {
+                                ArrowDataType::LargeList(_) => {
+                                    ArrowDataType::LargeList(element_field)
+                                }
+                                ArrowDataType::ListView(_) => {
+                                    ArrowDataType::ListView(element_field)
+                                }
+                                _ => ArrowDataType::List(element_field),
+                            };
+                            children.transform = ReorderIndexTransform::Cast(list_type);
+                        }
                         reorder_indices.push(children);
                     } else {
                         return Err(Error::unexpected_column_type(list_field.name()));
                     }
                 }
-                ArrowDataType::Map(key_val_field, _) => {
+                ArrowDataType::Map(key_val_field, sorted) => {
                     match (key_val_field.data_type(), requested_field.data_type()) {
                         (ArrowDataType::Struct(inner_fields), DataType::Map(map_type)) => {
                             let mut key_val_names =
@@ -334,7 +412,7 @@ fn get_indices(
                                 return Err(Error::generic("map fields had more than 2 members"));
                             }
                             let inner_schema = map_type.as_struct_schema(key_name, val_name);
-                            let (parquet_advance, _children) = get_indices(
+                            let (parquet_advance, children) = get_indices(
                                 parquet_index + parquet_offset,
                                 &inner_schema,
                                 inner_fields,
@@ -346,8 +424,32 @@ fn get_indices(
                             parquet_offset += parquet_advance - 1;
                             // note that we found this field
                             found_fields.insert(requested_field.name());
-                            // push the child reorder on, currently no reordering for maps
-                            reorder_indices.push(ReorderIndex::identity(index));
+                            // currently no reordering for maps, but casts of the key or value
+                            // (e.g. after type widening) become a cast of the whole map
+                            let needs_cast = children.iter().any(|child| {
+                                matches!(child.transform, ReorderIndexTransform::Cast(_))
+                            });
+                            if needs_cast {
+                                // children are in parquet order, just like `inner_fields`
+                                let entries: Fields = inner_fields
+                                    .iter()
+                                    .zip(children)
+                                    .map(|(inner_field, child)| match child.transform {
+                                        ReorderIndexTransform::Cast(target) => Arc::new(
+                                            inner_field.as_ref().clone().with_data_type(target),
+                                        ),
+                                        _ => inner_field.clone(),
+                                    })
+                                    .collect();
+                                let entries_field = key_val_field
+                                    .as_ref()
+                                    .clone()
+                                    .with_data_type(ArrowDataType::Struct(entries));
+                                let map_type = ArrowDataType::Map(Arc::new(entries_field), *sorted);
+                                reorder_indices.push(ReorderIndex::cast(index, map_type));
+                            } else {
+                                reorder_indices.push(ReorderIndex::identity(index));
+                            }
                         }
                         _ => {
                             return Err(Error::unexpected_column_type(field.name()));
@@ -380,6 +482,13 @@ fn get_indices(
                     mask_indices.push(parquet_offset + parquet_index);
                 }
             }
+            // each of the arms above pushed exactly one reorder index for this field. If it was
+            // matched by id under a different name, it must take on the requested name
+            if field.name() != requested_field.name() {
+                if let Some(reorder_index) = reorder_indices.last_mut() {
+                    reorder_index.rename = Some(requested_field.name().clone());
+                }
+            }
         } else {
             // We're NOT selecting this field, but we still need to track how many leaf columns we
             // skipped over
@@ -415,6 +524,46 @@ fn get_indices(
     ))
 }
 
+/// The `data_type` with its strings and binaries represented as views (i.e. `Utf8View` and
+/// `BinaryView`), at any depth. Engines whose own arrays are views (e.g. DataFusion and DuckDB)
+/// consume these without copying the bytes of each value.
+pub(crate) fn to_view_type(data_type: &ArrowDataType) -> ArrowDataType {
+    let to_view_field = |field: &ArrowFieldRef| -> ArrowFieldRef {
+        Arc::new(
+            field
+                .as_ref()
+                .clone()
+                .with_data_type(to_view_type(field.data_type())),
+        )
+    };
+    match data_type {
+        ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => ArrowDataType::Utf8View,
+        ArrowDataType::Binary | ArrowDataType::LargeBinary => ArrowDataType::BinaryView,
+        ArrowDataType::Struct(fields) => {
+            ArrowDataType::Struct(fields.iter().map(to_view_field).collect())
+        }
+        ArrowDataType::List(field) => ArrowDataType::List(to_view_field(field)),
+        ArrowDataType::LargeList(field) => ArrowDataType::LargeList(to_view_field(field)),
+        ArrowDataType::Map(field, sorted) => ArrowDataType::Map(to_view_field(field), *sorted),
+        data_type => data_type.clone(),
+    }
+}
+
+/// The `schema` with its strings and binaries represented as views. See [`to_view_type`].
+pub(crate) fn to_view_schema(schema: &ArrowSchema) -> ArrowSchema {
+    let fields: Fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            field
+                .as_ref()
+                .clone()
+                .with_data_type(to_view_type(field.data_type()))
+        })
+        .collect();
+    ArrowSchema::new_with_metadata(fields, schema.metadata().clone())
+}
+
 /// Get the indices in `parquet_schema` of the specified columns in `requested_schema`. This returns
 /// a tuple of (mask_indices: Vec<parquet_schema_index>, reorder_indices:
 /// Vec<requested_index>). `mask_indices` is used for generating the mask for reading from the
@@ -500,7 +649,10 @@ pub(crate) fn reorder_struct_array(
             match &reorder_index.transform {
                 ReorderIndexTransform::Cast(target) => {
                     let col = input_cols[parquet_position].as_ref();
-                    let col = Arc::new(arrow_cast::cast::cast(col, target)?);
+                    let col = match col.as_map_opt() {
+                        Some(map) => cast_map(map, target)?,
+                        None => Arc::new(arrow_cast::cast::cast(col, target)?),
+                    };
                     let new_field = Arc::new(
                         input_fields[parquet_position]
                             .as_ref()
@@ -560,6 +712,11 @@ pub(crate) fn reorder_struct_array(
                     final_fields_cols[reorder_index.index] = Some((field, null_array));
                 }
             }
+            if let Some(name) = &reorder_index.rename {
+                if let Some((field, _)) = final_fields_cols[reorder_index.index].as_mut() {
+                    *field = Arc::new(field.as_ref().clone().with_name(name));
+                }
+            }
         }
         let num_cols = final_fields_cols.len();
         let (field_vec, reordered_columns): (Vec<Arc<ArrowField>>, _) =
@@ -576,6 +733,44 @@ pub(crate) fn reorder_struct_array(
     }
 }
 
+/// Cast the keys and/or values of a map to the entry types of the `target` map type. Unlike
+/// `arrow_cast::cast`, this drops the (all-valid) null buffers casting produces, which the
+/// non-nullable keys (and possibly values) of a map can't have.
+fn cast_map(map: &MapArray, target: &ArrowDataType) -> DeltaResult<ArrowArrayRef> {
+    let ArrowDataType::Map(entries_field, sorted) = target else {
+        return Err(Error::internal_error("Map can only be cast to a map"));
+    };
+    let ArrowDataType::Struct(entry_fields) = entries_field.data_type() else {
+        return Err(Error::internal_error("Map entries must be a struct"));
+    };
+    let entry_columns: Vec<_> = map
+        .entries()
+        .columns()
+        .iter()
+        .zip(entry_fields.iter())
+        .map(|(column, field)| -> DeltaResult<ArrowArrayRef> {
+            let column = arrow_cast::cast::cast(column, field.data_type())?;
+            if column.null_count() > 0 {
+                return Ok(column);
+            }
+            let data = column.into_data().into_builder().nulls(None).build()?;
+            Ok(make_array(data))
+        })
+        .try_collect()?;
+    let entries = StructArray::try_new(
+        entry_fields.clone(),
+        entry_columns,
+        map.entries().nulls().cloned(),
+    )?;
+    Ok(Arc::new(MapArray::try_new(
+        entries_field.clone(),
+        map.offsets().clone(),
+        entries,
+        map.nulls().cloned(),
+        *sorted,
+    )?))
+}
+
 fn reorder_list<O: OffsetSizeTrait>(
     list_array: GenericListArray<O>,
     input_field_name: &str,
@@ -614,17 +809,40 @@ fn reorder_list<O: OffsetSizeTrait>(
 pub(crate) fn parse_json(
     json_strings: Box<dyn EngineData>,
     schema: SchemaRef,
+) -> DeltaResult<Box<dyn EngineData>> {
+    parse_selected_json(json_strings, schema, &[])
+}
+
+/// Like [`parse_json`], but only the strings of the rows selected by `selection_vector` (whose
+/// missing entries count as selected) are parsed. The other rows of the result are all-null.
+pub(crate) fn parse_selected_json(
+    json_strings: Box<dyn EngineData>,
+    schema: SchemaRef,
+    selection_vector: &[bool],
 ) -> DeltaResult<Box<dyn EngineData>> {
     let json_strings: RecordBatch = ArrowEngineData::try_from_engine_data(json_strings)?.into();
+    let mut json_strings = json_strings.column(0).clone();
+    if json_strings.data_type() == &ArrowDataType::Utf8View {
+        json_strings = arrow_cast::cast(&json_strings, &ArrowDataType::Utf8)?;
+    }
     let json_strings = json_strings
-        .column(0)
         .as_any()
         .downcast_ref::<StringArray>()
         .ok_or_else(|| {
             Error::generic("Expected json_strings to be a StringArray, found something else")
         })?;
     let schema: ArrowSchemaRef = Arc::new(schema.as_ref().try_into()?);
-    let result = parse_json_impl(json_strings, schema)?;
+    let result = if selection_vector.contains(&false) {
+        let is_selected = |i| selection_vector.get(i).copied().unwrap_or(true);
+        let selected_strings: StringArray = json_strings
+            .iter()
+            .enumerate()
+            .map(|(i, json_string)| json_string.filter(|_| is_selected(i)))
+            .collect();
+        parse_json_impl(&selected_strings, schema)?
+    } else {
+        parse_json_impl(json_strings, schema)?
+    };
     Ok(Box::new(ArrowEngineData::new(result)))
 }
 
@@ -675,6 +893,38 @@ pub(crate) fn to_json_bytes(
     Ok(writer.into_inner())
 }
 
+/// Serialize the selected rows of each batch of `data` as a single parquet file with the given
+/// `schema` and writer `properties` (or the defaults of the parquet writer). Batches are cast to
+/// the schema, so that batches whose (nested) field names differ only because they were read from
+/// different file formats can be written to the same file.
+pub(crate) fn to_parquet_bytes(
+    schema: &StructType,
+    data: impl Iterator<Item = DeltaResult<FilteredEngineData>> + Send,
+    properties: Option<WriterProperties>,
+) -> DeltaResult<Vec<u8>> {
+    let arrow_schema: ArrowSchemaRef = Arc::new(ArrowSchema::try_from(schema)?);
+    let target_type = ArrowDataType::Struct(arrow_schema.fields().clone());
+    let mut writer = ArrowWriter::try_new(Vec::new(), arrow_schema, properties)?;
+    for chunk in data {
+        let FilteredEngineData {
+            data,
+            mut selection_vector,
+        } = chunk?;
+        let arrow_data = ArrowEngineData::try_from_engine_data(data)?;
+        let record_batch = arrow_data.record_batch();
+        // rows beyond the end of the selection vector are selected
+        selection_vector.resize(record_batch.num_rows(), true);
+        let filtered = filter_record_batch(record_batch, &BooleanArray::from(selection_vector))?;
+        if filtered.num_rows() == 0 {
+            continue;
+        }
+        let array: ArrowArrayRef = Arc::new(StructArray::from(filtered));
+        let array = arrow_cast::cast(&array, &target_type)?;
+        writer.write(&array.as_struct().into())?;
+    }
+    Ok(writer.into_inner()?)
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -684,7 +934,8 @@ mod tests {
         buffer::{OffsetBuffer, ScalarBuffer},
     };
     use arrow_array::{
-        Array, ArrayRef as ArrowArrayRef, BooleanArray, GenericListArray, Int32Array, StructArray,
+        types::Int64Type, Array, ArrayRef as ArrowArrayRef, BooleanArray, GenericListArray,
+        Int32Array, MapArray, StructArray,
     };
     use arrow_schema::{
         DataType as ArrowDataType, Field as ArrowField, Fields, Schema as ArrowSchema,
@@ -909,6 +1160,89 @@ mod tests {
         assert_eq!(reorder_indices, expect_reorder);
     }
 
+    #[test]
+    fn field_id_indices() {
+        let with_id = |field: ArrowField, id: &str| {
+            field.with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+        let parquet_schema = Arc::new(ArrowSchema::new(vec![
+            with_id(ArrowField::new("a", ArrowDataType::Int32, false), "1"),
+            with_id(
+                ArrowField::new(
+                    "s",
+                    ArrowDataType::Struct(
+                        vec![
+                            with_id(ArrowField::new("x", ArrowDataType::Int32, false), "3"),
+                            with_id(ArrowField::new("y", ArrowDataType::Utf8, false), "4"),
+                        ]
+                        .into(),
+                    ),
+                    false,
+                ),
+                "2",
+            ),
+            with_id(ArrowField::new("b", ArrowDataType::Int32, false), "5"),
+        ]));
+        let field_id = |id: i32| [(ColumnMetadataKey::ParquetFieldId.as_ref(), id)];
+        // columns are matched by id, so the requested `a` with an unknown id is not found
+        let requested_schema = Arc::new(StructType::new([
+            StructField::new("col_b", DataType::INTEGER, false).with_metadata(field_id(5)),
+            StructField::new(
+                "col_s",
+                StructType::new([
+                    StructField::new("col_y", DataType::STRING, false).with_metadata(field_id(4))
+                ]),
+                false,
+            )
+            .with_metadata(field_id(2)),
+            StructField::new("a", DataType::INTEGER, true).with_metadata(field_id(9)),
+        ]));
+        let (mask_indices, reorder_indices) =
+            get_requested_indices(&requested_schema, &parquet_schema).unwrap();
+        let renamed = |reorder_index, name: &str| ReorderIndex {
+            rename: Some(name.to_string()),
+            ..reorder_index
+        };
+        let expect_reorder = vec![
+            renamed(
+                ReorderIndex::nested(1, vec![renamed(ReorderIndex::identity(0), "col_y")]),
+                "col_s",
+            ),
+            renamed(ReorderIndex::identity(0), "col_b"),
+            ReorderIndex::missing(
+                2,
+                Arc::new(with_id(
+                    ArrowField::new("a", ArrowDataType::Int32, true),
+                    "9",
+                )),
+            ),
+        ];
+        assert_eq!(mask_indices, vec![2, 3]);
+        assert_eq!(reorder_indices, expect_reorder);
+
+        // the columns read from parquet take on the requested names
+        let inner = StructArray::from(vec![(
+            Arc::new(ArrowField::new("y", ArrowDataType::Utf8, false)),
+            Arc::new(StringArray::from(vec!["v"])) as ArrowArrayRef,
+        )]);
+        let data = StructArray::from(vec![
+            (
+                Arc::new(ArrowField::new("s", inner.data_type().clone(), false)),
+                Arc::new(inner) as ArrowArrayRef,
+            ),
+            (
+                Arc::new(ArrowField::new("b", ArrowDataType::Int32, false)),
+                Arc::new(Int32Array::from(vec![1])) as ArrowArrayRef,
+            ),
+        ]);
+        let ordered = reorder_struct_array(data, &reorder_indices).unwrap();
+        assert_eq!(ordered.column_names(), vec!["col_b", "col_s", "a"]);
+        assert_eq!(ordered.column(1).as_struct().column_names(), vec!["col_y"]);
+    }
+
     #[test]
     fn nested_indices_reorder() {
         let requested_schema = Arc::new(StructType::new([
@@ -1383,6 +1717,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn widened_list_and_map_elements_cast() {
+        // files written before a type change still have the old (narrower) element types
+        let requested_schema = Arc::new(StructType::new([
+            StructField::new("list", ArrayType::new(DataType::LONG, false), false),
+            StructField::new(
+                "map",
+                MapType::new(DataType::INTEGER, DataType::LONG, false),
+                false,
+            ),
+        ]));
+        let list_field = ArrowField::new(
+            "list",
+            ArrowDataType::new_list(ArrowDataType::Int32, false),
+            false,
+        );
+        let map_field = ArrowField::new_map(
+            "map",
+            "entries",
+            ArrowField::new("k", ArrowDataType::Int32, false),
+            ArrowField::new("v", ArrowDataType::Int32, false),
+            false,
+            false,
+        );
+        let parquet_schema = Arc::new(ArrowSchema::new(vec![
+            list_field.clone(),
+            map_field.clone(),
+        ]));
+        let (mask_indices, reorder_indices) =
+            get_requested_indices(&requested_schema, &parquet_schema).unwrap();
+        let expect_list_type = ArrowDataType::new_list(ArrowDataType::Int64, false);
+        let expect_map_type = ArrowField::new_map(
+            "map",
+            "entries",
+            ArrowField::new("k", ArrowDataType::Int32, false),
+            ArrowField::new("v", ArrowDataType::Int64, false),
+            false,
+            false,
+        )
+        .data_type()
+        .clone();
+        let expect_reorder = vec![
+            ReorderIndex::cast(0, expect_list_type.clone()),
+            ReorderIndex::cast(1, expect_map_type.clone()),
+        ];
+        assert_eq!(mask_indices, vec![0, 1, 2]);
+        assert_eq!(reorder_indices, expect_reorder);
+
+        let offsets = OffsetBuffer::new(ScalarBuffer::from(vec![0, 2, 3]));
+        let list = GenericListArray::<i32>::new(
+            Arc::new(ArrowField::new_list_field(ArrowDataType::Int32, false)),
+            offsets.clone(),
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            None,
+        );
+        let ArrowDataType::Map(entries_field, _) = map_field.data_type() else {
+            panic!("expected a map");
+        };
+        let ArrowDataType::Struct(entries_fields) = entries_field.data_type() else {
+            panic!("expected map entries");
+        };
+        let entries = StructArray::new(
+            entries_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(Int32Array::from(vec![10, 20, 30])),
+            ],
+            None,
+        );
+        let map = MapArray::new(entries_field.clone(), offsets, entries, None, false);
+        let struct_array = StructArray::from(vec![
+            (Arc::new(list_field), Arc::new(list) as ArrowArrayRef),
+            (Arc::new(map_field), Arc::new(map) as ArrowArrayRef),
+        ]);
+        let ordered = reorder_struct_array(struct_array, &reorder_indices).unwrap();
+        assert_eq!(ordered.column(0).data_type(), &expect_list_type);
+        assert_eq!(ordered.column(1).data_type(), &expect_map_type);
+        let values = ordered.column(1).as_map().values().clone();
+        assert_eq!(
+            values.as_primitive::<Int64Type>().values(),
+            &[10i64, 20, 30]
+        );
+    }
+
     #[test]
     fn no_matches() {
         let requested_schema = Arc::new(StructType::new([
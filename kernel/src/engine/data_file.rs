@@ -0,0 +1,218 @@
+//! Writing the data files of a table: the encoding of (logical) data as parquet files along with
+//! their statistics, and the write metadata of the written files which engines hand to a
+//! [`Transaction`].
+//!
+//! [`Transaction`]: crate::transaction::Transaction
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::builder::{MapBuilder, MapFieldNames, StringBuilder};
+use arrow_array::{BooleanArray, Int64Array, RecordBatch, StringArray};
+use parquet::arrow::arrow_writer::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use uuid::Uuid;
+
+use crate::engine::arrow_data::ArrowEngineData;
+use crate::engine::stats::StatsCollector;
+use crate::schema::Schema;
+use crate::transaction::WriteContext;
+use crate::{DeltaResult, EngineData, Error, ExpressionHandler, FileMeta};
+
+/// Metadata of a data file (typically a parquet file): its file metadata and, if collected, its
+/// statistics.
+#[derive(Debug)]
+// only public through the default engine
+#[cfg_attr(not(feature = "default-engine-base"), allow(unreachable_pub))]
+pub struct DataFileMetadata {
+    pub(crate) file_meta: FileMeta,
+    pub(crate) stats: Option<String>,
+}
+
+#[cfg_attr(not(feature = "default-engine-base"), allow(unreachable_pub))]
+impl DataFileMetadata {
+    pub fn new(file_meta: FileMeta) -> Self {
+        Self {
+            file_meta,
+            stats: None,
+        }
+    }
+
+    /// Set the statistics of the file, as serialized by a [`StatsCollector`].
+    pub fn with_stats(mut self, stats: String) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    // convert DataFileMetadata into a record batch which matches the 'write_metadata' schema
+    pub(crate) fn as_record_batch(
+        &self,
+        partition_values: &HashMap<String, String>,
+        data_change: bool,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let DataFileMetadata {
+            file_meta:
+                FileMeta {
+                    location,
+                    last_modified,
+                    size,
+                },
+            stats,
+        } = self;
+        let write_metadata_schema = crate::transaction::get_write_metadata_schema();
+
+        // create the record batch of the write metadata
+        let path = Arc::new(StringArray::from(vec![location.to_string()]));
+        let key_builder = StringBuilder::new();
+        let val_builder = StringBuilder::new();
+        let names = MapFieldNames {
+            entry: "key_value".to_string(),
+            key: "key".to_string(),
+            value: "value".to_string(),
+        };
+        let mut builder = MapBuilder::new(Some(names), key_builder, val_builder);
+        for (k, v) in partition_values {
+            builder.keys().append_value(k);
+            builder.values().append_value(v);
+        }
+        builder.append(true).unwrap();
+        let partitions = Arc::new(builder.finish());
+        // this means max size we can write is i64::MAX (~8EB)
+        let size: i64 = (*size)
+            .try_into()
+            .map_err(|_| Error::generic("Failed to convert parquet metadata 'size' to i64"))?;
+        let size = Arc::new(Int64Array::from(vec![size]));
+        let data_change = Arc::new(BooleanArray::from(vec![data_change]));
+        let modification_time = Arc::new(Int64Array::from(vec![*last_modified]));
+        let stats = Arc::new(StringArray::from(vec![stats.clone()]));
+        Ok(Box::new(ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(write_metadata_schema.as_ref().try_into()?),
+            vec![
+                path,
+                partitions,
+                size,
+                modification_time,
+                data_change,
+                stats,
+            ],
+        )?)))
+    }
+}
+
+/// The name of a new data file: `<uuid>.parquet`, where `<uuid>` is a generated UUIDv4.
+pub(crate) fn new_data_file_name() -> String {
+    format!("{}.parquet", Uuid::new_v4())
+}
+
+/// Transform the logical `data` of a table into the physical data of its files, see
+/// [`WriteContext::logical_to_physical`].
+pub(crate) fn logical_to_physical(
+    expression_handler: &dyn ExpressionHandler,
+    data: &ArrowEngineData,
+    write_context: &WriteContext,
+) -> DeltaResult<Box<dyn EngineData>> {
+    let input_schema: Schema = data.record_batch().schema().try_into()?;
+    let evaluator = expression_handler.get_evaluator(
+        input_schema.into(),
+        write_context.logical_to_physical().clone(),
+        write_context.physical_schema().clone().into(),
+    );
+    evaluator.evaluate(data)
+}
+
+/// Map the partition values of (logical) partition columns to their physical names, by which the
+/// log refers to them.
+pub(crate) fn physical_partition_values(
+    write_context: &WriteContext,
+    partition_values: HashMap<String, String>,
+) -> DeltaResult<HashMap<String, String>> {
+    partition_values
+        .into_iter()
+        .map(|(column, value)| {
+            let field = write_context
+                .schema()
+                .field(&column)
+                .ok_or_else(|| Error::missing_column(&column))?;
+            Ok((field.physical_name().to_string(), value))
+        })
+        .collect()
+}
+
+/// Encode the (physical) `data` as a parquet file with the given writer `properties` (or the
+/// defaults of the parquet writer), returning its bytes and the serialized statistics of its
+/// `stats_columns`.
+pub(crate) fn encode_parquet(
+    data: Box<dyn EngineData>,
+    stats_columns: &[crate::expressions::ColumnName],
+    properties: Option<WriterProperties>,
+) -> DeltaResult<(Vec<u8>, String)> {
+    let batch: Box<_> = ArrowEngineData::try_from_engine_data(data)?;
+    let record_batch = batch.record_batch();
+    let mut stats = StatsCollector::new(stats_columns);
+    stats.update(record_batch)?;
+
+    let mut buffer = vec![];
+    let mut writer = ArrowWriter::try_new(&mut buffer, record_batch.schema(), properties)?;
+    writer.write(record_batch)?;
+    writer.close()?; // writer must be closed to write footer
+    Ok((buffer, stats.finish()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+
+    #[test]
+    fn test_as_record_batch() {
+        let location = Url::parse("file:///test_url").unwrap();
+        let size = 1_000_000;
+        let last_modified = 10000000000;
+        let file_metadata = FileMeta::new(location.clone(), last_modified, size as usize);
+        let stats = r#"{"numRecords":10}"#.to_string();
+        let data_file_metadata = DataFileMetadata::new(file_metadata).with_stats(stats.clone());
+        let partition_values = HashMap::from([("partition1".to_string(), "a".to_string())]);
+        let data_change = true;
+        let actual = data_file_metadata
+            .as_record_batch(&partition_values, data_change)
+            .unwrap();
+        let actual = ArrowEngineData::try_from_engine_data(actual).unwrap();
+
+        let schema = Arc::new(
+            crate::transaction::get_write_metadata_schema()
+                .as_ref()
+                .try_into()
+                .unwrap(),
+        );
+        let key_builder = StringBuilder::new();
+        let val_builder = StringBuilder::new();
+        let mut partition_values_builder = MapBuilder::new(
+            Some(MapFieldNames {
+                entry: "key_value".to_string(),
+                key: "key".to_string(),
+                value: "value".to_string(),
+            }),
+            key_builder,
+            val_builder,
+        );
+        partition_values_builder.keys().append_value("partition1");
+        partition_values_builder.values().append_value("a");
+        partition_values_builder.append(true).unwrap();
+        let partition_values = partition_values_builder.finish();
+        let expected = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec![location.to_string()])),
+                Arc::new(partition_values),
+                Arc::new(Int64Array::from(vec![size])),
+                Arc::new(Int64Array::from(vec![last_modified])),
+                Arc::new(BooleanArray::from(vec![data_change])),
+                Arc::new(StringArray::from(vec![stats])),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(actual.record_batch(), &expected);
+    }
+}
@@ -0,0 +1,435 @@
+//! An adapter driving an [`AsyncEngine`] for the kernel, and the [`AsyncEngine`] implementation of
+//! the [`DefaultEngine`].
+//!
+//! Since the kernel itself is synchronous, an [`AsyncEngineAdapter`] turns an [`AsyncEngine`] into
+//! an [`Engine`] by driving its futures and streams on a [`TaskExecutor`]. The adapter also
+//! provides async entry points to construct snapshots ([`AsyncEngineAdapter::snapshot`]) and
+//! execute scans ([`AsyncEngineAdapter::execute_scan`]), which run the kernel on the blocking
+//! threads of the executor instead of blocking the caller.
+//!
+//! The handlers of the [`DefaultEngine`] implement the async traits as well, so the
+//! [`DefaultEngine`] is also an [`AsyncEngine`].
+//!
+//! [`DefaultEngine`]: super::DefaultEngine
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::channel::{mpsc, oneshot};
+use futures::stream::BoxStream;
+use futures::{SinkExt, StreamExt};
+use url::Url;
+
+use super::executor::TaskExecutor;
+use super::DefaultEngine;
+use crate::async_engine::{
+    AsyncEngine, AsyncFileSystemClient, AsyncJsonHandler, AsyncParquetHandler,
+};
+use crate::scan::{Scan, ScanResult};
+use crate::schema::SchemaRef;
+use crate::snapshot::Snapshot;
+use crate::{
+    DeltaResult, Engine, EngineData, Error, ExpressionHandler, ExpressionRef,
+    FileDataReadResultIterator, FileMeta, FileSlice, FileSystemClient, FilteredEngineData,
+    JsonHandler, ListOptions, ParquetHandler, Table, Version,
+};
+
+impl<E: TaskExecutor> AsyncEngine for DefaultEngine<E> {
+    fn get_expression_handler(&self) -> Arc<dyn ExpressionHandler> {
+        self.expression_handler()
+    }
+
+    fn get_file_system_client(&self) -> Arc<dyn AsyncFileSystemClient> {
+        self.file_system.clone()
+    }
+
+    fn get_json_handler(&self) -> Arc<dyn AsyncJsonHandler> {
+        self.json.clone()
+    }
+
+    fn get_parquet_handler(&self) -> Arc<dyn AsyncParquetHandler> {
+        self.parquet.clone()
+    }
+}
+
+/// An [`Engine`] backed by an [`AsyncEngine`], whose futures and streams are driven by a
+/// [`TaskExecutor`].
+#[allow(missing_debug_implementations)]
+pub struct AsyncEngineAdapter<E: TaskExecutor> {
+    engine: Arc<dyn AsyncEngine>,
+    task_executor: Arc<E>,
+    readahead: usize,
+}
+
+impl<E: TaskExecutor> Clone for AsyncEngineAdapter<E> {
+    fn clone(&self) -> Self {
+        Self {
+            engine: self.engine.clone(),
+            task_executor: self.task_executor.clone(),
+            readahead: self.readahead,
+        }
+    }
+}
+
+impl<E: TaskExecutor> AsyncEngineAdapter<E> {
+    /// Create an adapter which drives the futures and streams of `engine` on `task_executor`.
+    pub fn new(engine: Arc<dyn AsyncEngine>, task_executor: Arc<E>) -> Self {
+        Self {
+            engine,
+            task_executor,
+            readahead: 10,
+        }
+    }
+
+    /// Set the maximum number of items (e.g. batches of data) of a stream to read ahead.
+    ///
+    /// Defaults to 10.
+    pub fn with_readahead(mut self, readahead: usize) -> Self {
+        self.readahead = readahead;
+        self
+    }
+
+    /// The [`AsyncEngine`] of this adapter.
+    pub fn async_engine(&self) -> &Arc<dyn AsyncEngine> {
+        &self.engine
+    }
+
+    /// Get the [`Snapshot`] of `table` at `version` (or the latest version), see
+    /// [`Table::snapshot`].
+    pub async fn snapshot(&self, table: &Table, version: Option<Version>) -> DeltaResult<Snapshot> {
+        let engine = self.clone();
+        let table = table.clone();
+        self.run_blocking(move || table.snapshot(&engine, version))
+            .await?
+    }
+
+    /// Execute `scan`, returning a stream of the data read from the table, see
+    /// [`Scan::execute`].
+    pub fn execute_scan(&self, scan: Scan) -> BoxStream<'static, DeltaResult<ScanResult>> {
+        let (sender, receiver) = mpsc::channel(self.readahead);
+        let engine: Arc<dyn Engine> = Arc::new(self.clone());
+        let executor = self.task_executor.clone();
+        self.task_executor.spawn(async move {
+            let mut error_sender = sender.clone();
+            let scan_task = executor.spawn_blocking(move || {
+                let mut sender = sender;
+                let results = match scan.execute(engine) {
+                    Ok(results) => results,
+                    Err(e) => {
+                        futures::executor::block_on(sender.send(Err(e))).ok();
+                        return;
+                    }
+                };
+                for result in results {
+                    // stop scanning once the stream has been dropped
+                    if futures::executor::block_on(sender.send(result)).is_err() {
+                        break;
+                    }
+                }
+            });
+            if let Err(e) = scan_task.await {
+                error_sender.send(Err(e)).await.ok();
+            }
+        });
+        receiver.boxed()
+    }
+
+    // Run `task` on a blocking thread of the executor, without blocking the caller
+    async fn run_blocking<R: Send + 'static>(
+        &self,
+        task: impl FnOnce() -> R + Send + 'static,
+    ) -> DeltaResult<R> {
+        let (sender, receiver) = oneshot::channel();
+        let executor = self.task_executor.clone();
+        self.task_executor.spawn(async move {
+            sender.send(executor.spawn_blocking(task).await).ok();
+        });
+        receiver
+            .await
+            .map_err(|_| Error::join_failure("Blocking task was cancelled"))?
+    }
+}
+
+impl<E: TaskExecutor> Engine for AsyncEngineAdapter<E> {
+    fn get_expression_handler(&self) -> Arc<dyn ExpressionHandler> {
+        self.engine.get_expression_handler()
+    }
+
+    fn get_file_system_client(&self) -> Arc<dyn FileSystemClient> {
+        Arc::new(BlockingFileSystemClient {
+            inner: self.engine.get_file_system_client(),
+            task_executor: self.task_executor.clone(),
+            readahead: self.readahead,
+        })
+    }
+
+    fn get_json_handler(&self) -> Arc<dyn JsonHandler> {
+        Arc::new(BlockingJsonHandler {
+            inner: self.engine.get_json_handler(),
+            task_executor: self.task_executor.clone(),
+            readahead: self.readahead,
+        })
+    }
+
+    fn get_parquet_handler(&self) -> Arc<dyn ParquetHandler> {
+        Arc::new(BlockingParquetHandler {
+            inner: self.engine.get_parquet_handler(),
+            task_executor: self.task_executor.clone(),
+            readahead: self.readahead,
+        })
+    }
+}
+
+// Drive `stream` on `task_executor`, buffering up to `readahead` of its items for the returned
+// iterator. The stream stops once the iterator is dropped.
+fn into_iter<E: TaskExecutor, T: Send + 'static>(
+    task_executor: &Arc<E>,
+    mut stream: BoxStream<'static, T>,
+    readahead: usize,
+) -> std::sync::mpsc::IntoIter<T> {
+    let (sender, receiver) = std::sync::mpsc::sync_channel(readahead);
+    let executor = task_executor.clone();
+    task_executor.spawn(async move {
+        while let Some(item) = stream.next().await {
+            let sender = sender.clone();
+            // send from a blocking thread, so that a full channel doesn't block the executor
+            match executor.spawn_blocking(move || sender.send(item)).await {
+                Ok(Ok(())) => continue,
+                Ok(Err(_)) => break,
+                Err(je) => {
+                    panic!("Couldn't join spawned task, runtime is likely in bad state: {je}")
+                }
+            }
+        }
+    });
+    receiver.into_iter()
+}
+
+struct BlockingFileSystemClient<E: TaskExecutor> {
+    inner: Arc<dyn AsyncFileSystemClient>,
+    task_executor: Arc<E>,
+    readahead: usize,
+}
+
+impl<E: TaskExecutor> FileSystemClient for BlockingFileSystemClient<E> {
+    fn list_from(
+        &self,
+        path: &Url,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        let stream = self.inner.list_from(path)?;
+        Ok(Box::new(into_iter(
+            &self.task_executor,
+            stream,
+            self.readahead,
+        )))
+    }
+
+    fn list_with_options(
+        &self,
+        path: &Url,
+        options: &ListOptions,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        let stream = self.inner.list_with_options(path, options)?;
+        Ok(Box::new(into_iter(
+            &self.task_executor,
+            stream,
+            self.readahead,
+        )))
+    }
+
+    fn head(&self, path: &Url) -> DeltaResult<FileMeta> {
+        let inner = self.inner.clone();
+        let path = path.clone();
+        self.task_executor
+            .block_on(async move { inner.head(&path).await })
+    }
+
+    fn read_files(
+        &self,
+        files: Vec<FileSlice>,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>> {
+        let stream = self.inner.read_files(files)?;
+        Ok(Box::new(into_iter(
+            &self.task_executor,
+            stream,
+            self.readahead,
+        )))
+    }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        let inner = self.inner.clone();
+        let path = path.clone();
+        self.task_executor
+            .block_on(async move { inner.write_file(&path, data, overwrite).await })
+    }
+}
+
+struct BlockingJsonHandler<E: TaskExecutor> {
+    inner: Arc<dyn AsyncJsonHandler>,
+    task_executor: Arc<E>,
+    readahead: usize,
+}
+
+impl<E: TaskExecutor> JsonHandler for BlockingJsonHandler<E> {
+    fn parse_json(
+        &self,
+        json_strings: Box<dyn EngineData>,
+        output_schema: SchemaRef,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        self.inner.parse_json(json_strings, output_schema)
+    }
+
+    fn read_json_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultIterator> {
+        let stream = self
+            .inner
+            .read_json_files(files, physical_schema, predicate)?;
+        Ok(Box::new(into_iter(
+            &self.task_executor,
+            stream,
+            self.readahead,
+        )))
+    }
+
+    fn write_json_file(
+        &self,
+        path: &Url,
+        data: Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + '_>,
+        overwrite: bool,
+    ) -> DeltaResult<()> {
+        let data = data.collect::<DeltaResult<Vec<_>>>()?;
+        let inner = self.inner.clone();
+        let path = path.clone();
+        self.task_executor
+            .block_on(async move { inner.write_json_file(&path, data, overwrite).await })
+    }
+}
+
+struct BlockingParquetHandler<E: TaskExecutor> {
+    inner: Arc<dyn AsyncParquetHandler>,
+    task_executor: Arc<E>,
+    readahead: usize,
+}
+
+impl<E: TaskExecutor> ParquetHandler for BlockingParquetHandler<E> {
+    fn read_parquet_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultIterator> {
+        let stream = self
+            .inner
+            .read_parquet_files(files, physical_schema, predicate)?;
+        Ok(Box::new(into_iter(
+            &self.task_executor,
+            stream,
+            self.readahead,
+        )))
+    }
+
+    fn write_parquet(
+        &self,
+        location: &Url,
+        schema: SchemaRef,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+    ) -> DeltaResult<FileMeta> {
+        let data = data.collect::<DeltaResult<Vec<_>>>()?;
+        let inner = self.inner.clone();
+        let location = location.clone();
+        self.task_executor
+            .block_on(async move { inner.write_parquet(&location, schema, data).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use futures::TryStreamExt;
+    use object_store::local::LocalFileSystem;
+    use object_store::path::Path;
+
+    use super::*;
+    use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+
+    fn test_table() -> (Table, AsyncEngineAdapter<TokioBackgroundExecutor>) {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-without-dv-small/")).unwrap();
+        let url = Url::from_directory_path(path).unwrap();
+        let executor = Arc::new(TokioBackgroundExecutor::new());
+        let engine =
+            DefaultEngine::try_new(&url, std::iter::empty::<(&str, &str)>(), executor.clone())
+                .unwrap();
+        (
+            Table::new(url),
+            AsyncEngineAdapter::new(Arc::new(engine), executor),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_async_snapshot_and_scan() {
+        let (table, engine) = test_table();
+        let snapshot = engine.snapshot(&table, None).await.unwrap();
+        assert_eq!(snapshot.version(), 0);
+
+        let scan = snapshot.into_scan_builder().build().unwrap();
+        let results: Vec<ScanResult> = engine.execute_scan(scan).try_collect().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].raw_data.as_ref().unwrap().len(), 10);
+
+        // snapshots of versions which don't exist fail
+        assert!(engine.snapshot(&table, Some(1)).await.is_err());
+    }
+
+    #[test]
+    fn test_adapter_is_engine() {
+        let (table, engine) = test_table();
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        let scan = snapshot.into_scan_builder().build().unwrap();
+        let rows: usize = scan
+            .execute(Arc::new(engine))
+            .unwrap()
+            .map(|result| result.unwrap().raw_data.unwrap().len())
+            .sum();
+        assert_eq!(rows, 10);
+    }
+
+    #[test]
+    fn test_blocking_file_system_client() {
+        let tmp = tempfile::tempdir().unwrap();
+        let url = Url::from_directory_path(tmp.path()).unwrap();
+        let store = Arc::new(LocalFileSystem::new());
+        let prefix = Path::from_url_path(url.path()).unwrap();
+        let executor = Arc::new(TokioBackgroundExecutor::new());
+        let default_engine = DefaultEngine::new(store, prefix, executor.clone());
+        let engine = AsyncEngineAdapter::new(Arc::new(default_engine), executor);
+        let client = engine.get_file_system_client();
+
+        let log_file = |version: u64| url.join(&format!("_delta_log/{version:020}.json")).unwrap();
+        for version in [1, 0, 2] {
+            let data = Bytes::from(format!("version {version}"));
+            client.write_file(&log_file(version), data, false).unwrap();
+        }
+        let result = client.write_file(&log_file(0), Bytes::new(), false);
+        assert!(matches!(result, Err(Error::FileAlreadyExists(_))));
+
+        let listed: Vec<_> = client
+            .list_from(&log_file(0))
+            .unwrap()
+            .map(|meta| meta.unwrap().location)
+            .collect();
+        assert_eq!(listed, [log_file(1), log_file(2)]);
+
+        let read: Vec<_> = client
+            .read_files(vec![(log_file(2), None), (log_file(0), Some(0..7))])
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect();
+        assert_eq!(read, [Bytes::from("version 2"), Bytes::from("version")]);
+    }
+}
@@ -0,0 +1,343 @@
+//! Caching of the metadata of tables in the default engine.
+//!
+//! Constructing snapshots and scanning them reads the same small objects from storage over and
+//! over: the commit files and checkpoint parts of the log, and the footers of parquet files. Since
+//! these are immutable once written, a [`MetadataCache`] can keep them in memory across snapshots
+//! and scans. The cache is bounded by the total (estimated) size of its entries, evicting the
+//! least recently used entries first, and entries expire after a time to live.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use object_store::path::Path;
+use object_store::{DynObjectStore, ObjectMeta};
+use parquet::arrow::arrow_reader::ArrowReaderMetadata;
+use url::Url;
+use web_time::Instant;
+
+use crate::path::ParsedLogPath;
+use crate::{DeltaResult, FileMeta};
+
+/// The bounds of a [`MetadataCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// The maximum total size of the cached entries in bytes. Defaults to 64 MiB.
+    pub max_size: usize,
+    /// The maximum size of the cached commit files and checkpoint parts in bytes. Larger files
+    /// are not cached (though the footers of checkpoint parts still are). Defaults to 8 MiB.
+    pub max_object_size: usize,
+    /// How long entries are cached. Defaults to 10 minutes.
+    pub ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 64 * 1024 * 1024,
+            max_object_size: 8 * 1024 * 1024,
+            ttl: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+// Entries are keyed by the URLs of files rather than their paths, since the paths of files in
+// different stores (e.g. the store of a table and an external store) may be the same
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Footer(Url),
+    Object(Url),
+}
+
+#[derive(Debug, Clone)]
+enum CacheValue {
+    Footer(ObjectMeta, ArrowReaderMetadata),
+    Object(Bytes),
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    value: CacheValue,
+    size: usize,
+    inserted_at: Instant,
+    // the key of the entry in `CacheState::lru`
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    // The keys of the entries by the tick at which they were last used, least recent first
+    lru: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    size: usize,
+}
+
+impl CacheState {
+    fn get(&mut self, key: &CacheKey, ttl: Duration) -> Option<CacheValue> {
+        let entry = self.entries.get_mut(key)?;
+        if entry.inserted_at.elapsed() > ttl {
+            self.remove(key);
+            return None;
+        }
+        self.tick += 1;
+        self.lru.remove(&entry.last_used);
+        self.lru.insert(self.tick, key.clone());
+        entry.last_used = self.tick;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, value: CacheValue, size: usize, max_size: usize) {
+        if size > max_size {
+            return;
+        }
+        self.remove(&key);
+        while self.size + size > max_size {
+            let Some((_, lru_key)) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&lru_key) {
+                self.size -= entry.size;
+            }
+        }
+        self.tick += 1;
+        self.lru.insert(self.tick, key.clone());
+        self.size += size;
+        let entry = CacheEntry {
+            value,
+            size,
+            inserted_at: Instant::now(),
+            last_used: self.tick,
+        };
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.last_used);
+            self.size -= entry.size;
+        }
+    }
+}
+
+/// A cache of commit files, checkpoint parts and parquet footers, shared by the handlers of the
+/// default engine. Clones of a cache share its entries. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    config: CacheConfig,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl MetadataCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            state: Default::default(),
+        }
+    }
+
+    /// The bounds of the cache
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// The total (estimated) size of the cached entries in bytes
+    pub fn size(&self) -> usize {
+        self.lock().size
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&self) {
+        *self.lock() = CacheState::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // the state is consistent after every operation, so it's safe to ignore poisoning
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<CacheValue> {
+        self.lock().get(key, self.config.ttl)
+    }
+
+    fn insert(&self, key: CacheKey, value: CacheValue, size: usize) {
+        self.lock().insert(key, value, size, self.config.max_size)
+    }
+
+    /// Whether the contents of `file` are cached, i.e. if it's a (small enough) commit file or
+    /// checkpoint part.
+    pub(crate) fn caches_contents(&self, file: &FileMeta) -> bool {
+        file.size <= self.config.max_object_size
+            && matches!(
+                ParsedLogPath::try_from(file.location.clone()),
+                Ok(Some(log_path)) if log_path.is_commit() || log_path.is_checkpoint()
+            )
+    }
+
+    /// The contents of the file at `location`, read from `store` unless cached.
+    pub(crate) async fn get_contents(
+        &self,
+        store: &DynObjectStore,
+        location: &Url,
+    ) -> DeltaResult<Bytes> {
+        let key = CacheKey::Object(location.clone());
+        if let Some(CacheValue::Object(bytes)) = self.get(&key) {
+            return Ok(bytes);
+        }
+        let path = Path::from_url_path(location.path())?;
+        let bytes = store.get(&path).await?.bytes().await?;
+        if bytes.len() <= self.config.max_object_size {
+            let size = bytes.len();
+            self.insert(key, CacheValue::Object(bytes.clone()), size);
+        }
+        Ok(bytes)
+    }
+
+    /// The cached metadata and footer of the parquet file at `location`, if any.
+    pub(crate) fn footer(&self, location: &Url) -> Option<(ObjectMeta, ArrowReaderMetadata)> {
+        match self.get(&CacheKey::Footer(location.clone()))? {
+            CacheValue::Footer(meta, metadata) => Some((meta, metadata)),
+            CacheValue::Object(_) => None,
+        }
+    }
+
+    /// Cache the metadata and footer of the parquet file at `location`.
+    pub(crate) fn insert_footer(
+        &self,
+        location: &Url,
+        meta: ObjectMeta,
+        metadata: ArrowReaderMetadata,
+    ) {
+        let size = metadata.metadata().memory_size();
+        let value = CacheValue::Footer(meta, metadata);
+        self.insert(CacheKey::Footer(location.clone()), value, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+    use object_store::ObjectStore;
+
+    use super::*;
+
+    fn cache(max_size: usize, ttl: Duration) -> MetadataCache {
+        MetadataCache::new(CacheConfig {
+            max_size,
+            max_object_size: max_size,
+            ttl,
+        })
+    }
+
+    async fn put(store: &InMemory, path: &str, size: usize) -> Url {
+        store
+            .put(&Path::from(path), vec![0; size].into())
+            .await
+            .unwrap();
+        Url::parse("memory:///").unwrap().join(path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_contents() {
+        let store = InMemory::new();
+        let path = put(&store, "_delta_log/00000000000000000000.json", 10).await;
+        let cache = cache(100, Duration::from_secs(60));
+
+        assert_eq!(cache.get_contents(&store, &path).await.unwrap().len(), 10);
+        assert_eq!(cache.size(), 10);
+
+        // the cached contents are returned even after the object is gone
+        store.delete(&Path::from(path.path())).await.unwrap();
+        assert_eq!(cache.get_contents(&store, &path).await.unwrap().len(), 10);
+
+        cache.clear();
+        assert_eq!(cache.size(), 0);
+        cache.get_contents(&store, &path).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_same_path_in_different_stores() {
+        let path = "_delta_log/00000000000000000000.json";
+        let (table_store, external_store) = (InMemory::new(), InMemory::new());
+        table_store
+            .put(&Path::from(path), vec![0; 10].into())
+            .await
+            .unwrap();
+        external_store
+            .put(&Path::from(path), vec![0; 20].into())
+            .await
+            .unwrap();
+        let table_file = Url::parse("s3://table-bucket/")
+            .unwrap()
+            .join(path)
+            .unwrap();
+        let external_file = Url::parse("s3://other-bucket/")
+            .unwrap()
+            .join(path)
+            .unwrap();
+        let cache = cache(100, Duration::from_secs(60));
+
+        let contents = cache.get_contents(&table_store, &table_file).await.unwrap();
+        assert_eq!(contents.len(), 10);
+        let contents = cache.get_contents(&external_store, &external_file).await;
+        assert_eq!(contents.unwrap().len(), 20);
+        assert_eq!(cache.size(), 30);
+    }
+
+    #[tokio::test]
+    async fn test_evict_least_recently_used() {
+        let store = InMemory::new();
+        let a = put(&store, "a", 40).await;
+        let b = put(&store, "b", 40).await;
+        let c = put(&store, "c", 40).await;
+        let too_large = put(&store, "too_large", 101).await;
+        let cache = cache(100, Duration::from_secs(60));
+
+        cache.get_contents(&store, &a).await.unwrap();
+        cache.get_contents(&store, &b).await.unwrap();
+        // use `a`, so that `b` is evicted to make room for `c`
+        cache.get_contents(&store, &a).await.unwrap();
+        cache.get_contents(&store, &c).await.unwrap();
+        assert_eq!(cache.size(), 80);
+
+        store.delete(&Path::from("a")).await.unwrap();
+        store.delete(&Path::from("b")).await.unwrap();
+        cache.get_contents(&store, &a).await.unwrap();
+        cache.get_contents(&store, &b).await.unwrap_err();
+
+        cache.get_contents(&store, &too_large).await.unwrap();
+        assert_eq!(cache.size(), 80);
+    }
+
+    #[tokio::test]
+    async fn test_expire_entries() {
+        let store = InMemory::new();
+        let path = put(&store, "a", 10).await;
+        let cache = cache(100, Duration::ZERO);
+
+        cache.get_contents(&store, &path).await.unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+        store.delete(&Path::from(path.path())).await.unwrap();
+        cache.get_contents(&store, &path).await.unwrap_err();
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn test_caches_contents() {
+        let cache = cache(100, Duration::from_secs(60));
+        let file = |path: &str, size| FileMeta {
+            location: Url::parse("memory:///").unwrap().join(path).unwrap(),
+            last_modified: 0,
+            size,
+        };
+        assert!(cache.caches_contents(&file("_delta_log/00000000000000000001.json", 10)));
+        assert!(cache.caches_contents(&file(
+            "_delta_log/00000000000000000001.checkpoint.parquet",
+            10
+        )));
+        assert!(!cache.caches_contents(&file("_delta_log/00000000000000000001.json", 101)));
+        assert!(!cache.caches_contents(&file("_delta_log/_last_checkpoint", 10)));
+        assert!(!cache.caches_contents(&file("part-00000.parquet", 10)));
+    }
+}
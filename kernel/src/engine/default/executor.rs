@@ -5,7 +5,17 @@
 //! A generic trait [TaskExecutor] can be implemented with your preferred async
 //! runtime. Behind the `tokio` feature flag, we provide a both a single-threaded
 //! and multi-threaded executor based on Tokio.
-use futures::{future::BoxFuture, Future};
+//!
+//! Applications which already run a multi-threaded Tokio runtime should share it with the
+//! engine, by creating a [`TokioMultiThreadExecutor`] from a handle to the runtime, rather than
+//! having a [`TokioBackgroundExecutor`] run a second runtime on its own thread. All of the tasks
+//! of the engine, including its blocking tasks, then run on the runtime of the application.
+//!
+//! [`TokioMultiThreadExecutor`]: tokio::TokioMultiThreadExecutor
+//! [`TokioBackgroundExecutor`]: tokio::TokioBackgroundExecutor
+use std::time::Duration;
+
+use futures::{future::BoxFuture, Future, FutureExt};
 
 use crate::DeltaResult;
 
@@ -30,10 +40,26 @@ pub trait TaskExecutor: Send + Sync + 'static {
     where
         F: Future<Output = ()> + Send + 'static;
 
+    /// Run the blocking function on a thread where blocking is acceptable, returning a future of
+    /// its output.
+    ///
+    /// Like [`Self::spawn`], this should NOT panic if called outside of an async context.
     fn spawn_blocking<T, R>(&self, task: T) -> BoxFuture<'_, DeltaResult<R>>
     where
         T: FnOnce() -> R + Send + 'static,
         R: Send + 'static;
+
+    /// Return a future which completes after `duration`, e.g. to back off before retrying a
+    /// failed request.
+    ///
+    /// The default implementation sleeps on a blocking thread (see [`Self::spawn_blocking`]).
+    /// Executors whose runtime has a timer should use it instead, and executors which can't block
+    /// threads (e.g. on wasm32-unknown-unknown) must.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'_, ()> {
+        self.spawn_blocking(move || std::thread::sleep(duration))
+            .map(|_| ())
+            .boxed()
+    }
 }
 
 #[cfg(any(feature = "tokio", test))]
@@ -42,15 +68,17 @@ pub mod tokio {
     use futures::TryFutureExt;
     use futures::{future::BoxFuture, Future};
     use std::sync::mpsc::channel;
-    use tokio::runtime::RuntimeFlavor;
+    use std::time::Duration;
+    use tokio::runtime::{Handle, RuntimeFlavor};
 
-    use crate::DeltaResult;
+    use crate::{DeltaResult, Error};
 
     /// A [`TaskExecutor`] that uses the tokio single-threaded runtime in a
     /// background thread to service tasks.
     #[derive(Debug)]
     pub struct TokioBackgroundExecutor {
         sender: tokio::sync::mpsc::Sender<BoxFuture<'static, ()>>,
+        handle: Handle,
         _thread: std::thread::JoinHandle<()>,
     }
 
@@ -63,19 +91,25 @@ pub mod tokio {
     impl TokioBackgroundExecutor {
         pub fn new() -> Self {
             let (sender, mut receiver) = tokio::sync::mpsc::channel::<BoxFuture<'_, ()>>(50);
+            let (handle_sender, handle_receiver) = channel();
             let thread = std::thread::spawn(move || {
                 let rt = tokio::runtime::Builder::new_current_thread()
                     .enable_all()
                     .build()
                     .unwrap();
+                handle_sender.send(rt.handle().clone()).unwrap();
                 rt.block_on(async move {
                     while let Some(task) = receiver.recv().await {
                         tokio::task::spawn(task);
                     }
                 });
             });
+            let handle = handle_receiver
+                .recv()
+                .expect("TokioBackgroundExecutor failed to start");
             Self {
                 sender,
+                handle,
                 _thread: thread,
             }
         }
@@ -140,7 +174,17 @@ pub mod tokio {
             T: FnOnce() -> R + Send + 'static,
             R: Send + 'static,
         {
-            Box::pin(tokio::task::spawn_blocking(task).map_err(crate::Error::join_failure))
+            Box::pin(
+                self.handle
+                    .spawn_blocking(task)
+                    .map_err(Error::join_failure),
+            )
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'_, ()> {
+            // the timer of the runtime is found when the future is created
+            let _guard = self.handle.enter();
+            Box::pin(tokio::time::sleep(duration))
         }
     }
 
@@ -149,17 +193,45 @@ pub mod tokio {
     /// the runtime with other parts of your application.
     #[derive(Debug)]
     pub struct TokioMultiThreadExecutor {
-        handle: tokio::runtime::Handle,
+        handle: Handle,
     }
 
     impl TokioMultiThreadExecutor {
-        pub fn new(handle: tokio::runtime::Handle) -> Self {
-            assert_eq!(
-                handle.runtime_flavor(),
-                RuntimeFlavor::MultiThread,
-                "TokioExecutor must be created with a multi-threaded runtime"
-            );
-            Self { handle }
+        /// Create an executor which runs its tasks on the runtime of `handle`.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the runtime is not a multi-threaded runtime, see [`Self::try_new`].
+        pub fn new(handle: Handle) -> Self {
+            Self::try_new(handle)
+                .expect("TokioExecutor must be created with a multi-threaded runtime")
+        }
+
+        /// Create an executor which runs its tasks on the runtime of `handle`, which must be a
+        /// multi-threaded runtime.
+        pub fn try_new(handle: Handle) -> DeltaResult<Self> {
+            match handle.runtime_flavor() {
+                RuntimeFlavor::MultiThread => Ok(Self { handle }),
+                flavor => Err(Error::generic(format!(
+                    "TokioMultiThreadExecutor requires a multi-threaded runtime, got {flavor:?}"
+                ))),
+            }
+        }
+
+        /// Create an executor which runs its tasks on the runtime of the current async context,
+        /// which must be a multi-threaded runtime. See [`Handle::try_current`].
+        pub fn try_current() -> DeltaResult<Self> {
+            let handle = Handle::try_current().map_err(|e| {
+                Error::generic(format!(
+                    "Cannot create an executor outside of a runtime: {e}"
+                ))
+            })?;
+            Self::try_new(handle)
+        }
+
+        /// The handle of the runtime of the executor
+        pub fn handle(&self) -> &Handle {
+            &self.handle
         }
     }
 
@@ -203,7 +275,17 @@ pub mod tokio {
             T: FnOnce() -> R + Send + 'static,
             R: Send + 'static,
         {
-            Box::pin(tokio::task::spawn_blocking(task).map_err(crate::Error::join_failure))
+            Box::pin(
+                self.handle
+                    .spawn_blocking(task)
+                    .map_err(Error::join_failure),
+            )
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'_, ()> {
+            // the timer of the runtime is found when the future is created
+            let _guard = self.handle.enter();
+            Box::pin(tokio::time::sleep(duration))
         }
     }
 
@@ -238,8 +320,35 @@ pub mod tokio {
 
         #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
         async fn test_tokio_multi_thread_executor() {
-            let executor = TokioMultiThreadExecutor::new(tokio::runtime::Handle::current());
+            let executor = TokioMultiThreadExecutor::new(Handle::current());
             test_executor(executor).await;
         }
+
+        #[test]
+        fn test_executors_outside_of_runtime() {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .build()
+                .unwrap();
+            let executor = TokioMultiThreadExecutor::try_new(runtime.handle().clone()).unwrap();
+            let background_executor = TokioBackgroundExecutor::new();
+
+            // blocking tasks run on the runtime of the executor, not of the caller
+            let task = executor.spawn_blocking(|| 2 + 2);
+            assert_eq!(futures::executor::block_on(task).unwrap(), 4);
+            let task = background_executor.spawn_blocking(|| 2 + 2);
+            assert_eq!(futures::executor::block_on(task).unwrap(), 4);
+        }
+
+        #[test]
+        fn test_try_new_multi_thread_executor() {
+            assert!(TokioMultiThreadExecutor::try_current().is_err());
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap();
+            assert!(TokioMultiThreadExecutor::try_new(runtime.handle().clone()).is_err());
+            let executor = runtime.block_on(async { TokioMultiThreadExecutor::try_current() });
+            assert!(executor.is_err());
+        }
     }
 }
@@ -1,5 +1,4 @@
 use std::collections::VecDeque;
-use std::mem;
 use std::ops::Range;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -8,12 +7,13 @@ use std::task::{ready, Context, Poll};
 use arrow_array::RecordBatch;
 use arrow_schema::SchemaRef as ArrowSchemaRef;
 use futures::future::BoxFuture;
-use futures::stream::{BoxStream, Stream, StreamExt};
-use futures::FutureExt;
+use futures::stream::{BoxStream, Stream, StreamExt, TryStreamExt};
+use futures::{FutureExt, SinkExt};
 
 use super::executor::TaskExecutor;
-use crate::engine::arrow_data::ArrowEngineData;
-use crate::{DeltaResult, FileDataReadResultIterator, FileMeta};
+use crate::async_engine::FileDataReadResultStream;
+use crate::engine::memory::{track_batches, MemoryPool};
+use crate::{DeltaResult, Error, ErrorContext, FileDataReadResultIterator, FileMeta};
 
 /// A fallible future that resolves to a stream of [`RecordBatch`]
 /// cbindgen:ignore
@@ -30,6 +30,52 @@ pub trait FileOpener: Send + Unpin {
     fn open(&self, file_meta: FileMeta, range: Option<Range<i64>>) -> DeltaResult<FileOpenFuture>;
 }
 
+/// A [`FileOpener`] which opens and decodes each file in its own task on a [`TaskExecutor`].
+///
+/// A [`FileStream`] polls all the files it reads from within a single task, so decoding them is
+/// serialized even when several files are open. Wrapping its opener in a `SpawnedFileOpener`
+/// instead decodes the (up to `max_concurrent_opens`) open files in parallel on the executor, which
+/// lets CPU-bound decoding (e.g. parsing the JSON of a long tail of commit files) use all cores
+/// and overlap with IO. Each task buffers at most a couple of decoded batches ahead of the stream,
+/// and the stream still produces the batches in the order of the input files.
+#[allow(missing_debug_implementations)]
+pub struct SpawnedFileOpener<E: TaskExecutor> {
+    inner: Box<dyn FileOpener>,
+    task_executor: Arc<E>,
+}
+
+impl<E: TaskExecutor> SpawnedFileOpener<E> {
+    pub fn new(inner: Box<dyn FileOpener>, task_executor: Arc<E>) -> Self {
+        Self {
+            inner,
+            task_executor,
+        }
+    }
+}
+
+impl<E: TaskExecutor> FileOpener for SpawnedFileOpener<E> {
+    fn open(&self, file_meta: FileMeta, range: Option<Range<i64>>) -> DeltaResult<FileOpenFuture> {
+        let open = self.inner.open(file_meta, range)?;
+        let (mut sender, receiver) = futures::channel::mpsc::channel(1);
+        self.task_executor.spawn(async move {
+            let mut reader = match open.await {
+                Ok(reader) => reader,
+                Err(e) => {
+                    let _ = sender.send(Err(e)).await;
+                    return;
+                }
+            };
+            while let Some(res) = reader.next().await {
+                // stop decoding once the stream no longer wants the batches of the file
+                if sender.send(res).await.is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Box::pin(futures::future::ready(Ok(receiver.boxed()))))
+    }
+}
+
 /// Describes the behavior of the `FileStream` if file opening or scanning fails
 #[allow(missing_debug_implementations)]
 pub enum OnError {
@@ -45,39 +91,61 @@ impl Default for OnError {
     }
 }
 
-/// Represents the state of the next `FileOpenFuture`. Since we need to poll
-/// this future while scanning the current file, we need to store the result if it
+/// Represents the state of a pending `FileOpenFuture`. Since we need to keep polling
+/// these futures while scanning the current file, we need to store the result if it
 /// is ready
 enum NextOpen {
     Pending(FileOpenFuture),
-    Ready(DeltaResult<BoxStream<'static, DeltaResult<RecordBatch>>>),
+    Ready(DeltaResult<ReadAhead>),
 }
 
-enum FileStreamState {
-    /// The idle state, no file is currently being read
-    Idle,
-    /// Currently performing asynchronous IO to obtain a stream of RecordBatch
-    /// for a given parquet file
-    Open {
-        /// A [`FileOpenFuture`] returned by [`FileOpener::open`]
-        future: FileOpenFuture,
-    },
-    /// Scanning the [`BoxStream`] returned by the completion of a [`FileOpenFuture`]
-    /// returned by [`FileOpener::open`]
-    Scan {
-        /// The reader instance
-        reader: BoxStream<'static, DeltaResult<RecordBatch>>,
-        /// A [`FileOpenFuture`] for the next file to be processed,
-        /// and its corresponding partition column values, if any.
-        /// This allows the next file to be opened in parallel while the
-        /// current file is read.
-        next: Option<NextOpen>,
-    },
-    /// Encountered an error
-    Error,
+/// An opened file which is not scanned yet, and the batches read ahead from it
+struct ReadAhead {
+    /// The stream of the remaining batches of the file, or `None` once it's exhausted
+    reader: Option<BoxStream<'static, DeltaResult<RecordBatch>>>,
+    batches: VecDeque<DeltaResult<RecordBatch>>,
+}
+
+impl ReadAhead {
+    fn new(reader: BoxStream<'static, DeltaResult<RecordBatch>>) -> Self {
+        Self {
+            reader: Some(reader),
+            batches: VecDeque::new(),
+        }
+    }
+
+    /// Read batches until `max_batches` are buffered or no more are ready
+    fn poll_read_ahead(&mut self, cx: &mut Context<'_>, max_batches: usize) {
+        while self.batches.len() < max_batches {
+            let Some(reader) = &mut self.reader else {
+                return;
+            };
+            match reader.poll_next_unpin(cx) {
+                Poll::Ready(Some(batch)) => self.batches.push_back(batch),
+                Poll::Ready(None) => self.reader = None,
+                Poll::Pending => return,
+            }
+        }
+    }
+
+    /// The batches of the file, starting with those read ahead
+    fn into_stream(self) -> BoxStream<'static, DeltaResult<RecordBatch>> {
+        let batches = futures::stream::iter(self.batches);
+        match self.reader {
+            Some(reader) => batches.chain(reader).boxed(),
+            None => batches.boxed(),
+        }
+    }
 }
 
 /// A stream that iterates record batch by record batch, file over file.
+///
+/// Up to `max_concurrent_opens` files are opened concurrently ahead of the file currently being
+/// scanned, which hides per-file IO latency when reading many small files (e.g. the commit files
+/// of a log segment). With a `file_readahead`, up to that many batches are also read from each
+/// opened file before its turn, so that large files are read concurrently as well. Regardless of
+/// the order in which files finish opening, batches are always produced in the order of the input
+/// files.
 #[allow(missing_debug_implementations)]
 pub struct FileStream {
     /// An iterator over input files.
@@ -91,8 +159,18 @@ pub struct FileStream {
     /// is not capable of limiting the number of records in the last batch, the file
     /// stream will take care of truncating it.
     file_opener: Box<dyn FileOpener>,
-    /// The stream state
-    state: FileStreamState,
+    /// The reader for the file currently being scanned, if any
+    reader: Option<BoxStream<'static, DeltaResult<RecordBatch>>>,
+    /// [`FileOpenFuture`]s of the files to be processed after the current one, in file order
+    pending_opens: VecDeque<NextOpen>,
+    /// The maximum number of files to open concurrently
+    max_concurrent_opens: usize,
+    /// The maximum number of batches to read ahead from each opened file
+    file_readahead: usize,
+    /// The pool in which the batches produced by the stream reserve their memory, if any
+    memory_pool: Option<MemoryPool>,
+    /// Set once the stream has encountered an error and stopped
+    failed: bool,
     /// Describes the behavior of the `FileStream` if file opening or scanning fails
     on_error: OnError,
 }
@@ -101,23 +179,40 @@ impl FileStream {
     /// Creates a new `FileStream` from a given schema, `FileOpener`, and files list; the files are
     /// processed asynchronously by the provided `TaskExecutor`. Returns an `Iterator` that consumes
     /// the results.
+    ///
+    /// Up to `max_concurrent_opens` files are opened concurrently, and up to `readahead` batches
+    /// are buffered for the consumer.
     pub fn new_async_read_iterator<E: TaskExecutor>(
         task_executor: Arc<E>,
         schema: ArrowSchemaRef,
         file_opener: Box<dyn FileOpener>,
         files: &[FileMeta],
         readahead: usize,
+        max_concurrent_opens: usize,
     ) -> DeltaResult<FileDataReadResultIterator> {
-        let mut stream = FileStream::new(files.to_vec(), schema, file_opener)?;
+        Ok(FileStream::new(files.to_vec(), schema, file_opener)?
+            .with_max_concurrent_opens(max_concurrent_opens)
+            .into_async_read_iterator(task_executor, readahead))
+    }
 
+    /// Process the files of this stream asynchronously on the provided `TaskExecutor`. Returns an
+    /// `Iterator` that consumes the results, buffering up to `readahead` batches.
+    pub fn into_async_read_iterator<E: TaskExecutor>(
+        mut self,
+        task_executor: Arc<E>,
+        readahead: usize,
+    ) -> FileDataReadResultIterator {
         // This channel will become the output iterator
         // The stream will execute in the background, and we allow up to `readahead`
         // batches to be buffered in the channel.
         let (sender, receiver) = std::sync::mpsc::sync_channel(readahead);
 
         let executor_for_block = task_executor.clone();
+        // reserve the memory of buffered batches too
+        let memory_pool = self.memory_pool.take();
+        let mut batches = track_batches(memory_pool, self);
         task_executor.spawn(async move {
-            while let Some(res) = stream.next().await {
+            while let Some(res) = batches.next().await {
                 let sender = sender.clone();
                 let join_res = executor_for_block
                     .spawn_blocking(move || sender.send(res))
@@ -134,9 +229,20 @@ impl FileStream {
             }
         });
 
-        Ok(Box::new(receiver.into_iter().map(|rbr| {
-            rbr.map(|rb| Box::new(ArrowEngineData::new(rb)) as _)
-        })))
+        Box::new(
+            receiver
+                .into_iter()
+                .map(|res| res.map(|data| Box::new(data) as _)),
+        )
+    }
+
+    /// Convert this stream into a stream of engine data, which reserves the memory of the batches
+    /// in the memory pool of the stream (if any).
+    pub fn into_engine_data_stream(mut self) -> FileDataReadResultStream {
+        let memory_pool = self.memory_pool.take();
+        track_batches(memory_pool, self)
+            .map_ok(|data| Box::new(data) as _)
+            .boxed()
     }
 
     /// Create a new `FileStream` using the given `FileOpener` to scan underlying files
@@ -149,7 +255,12 @@ impl FileStream {
             file_iter: files.into_iter().collect(),
             projected_schema: schema,
             file_opener,
-            state: FileStreamState::Idle,
+            reader: None,
+            pending_opens: VecDeque::new(),
+            max_concurrent_opens: 1,
+            file_readahead: 0,
+            memory_pool: None,
+            failed: false,
             on_error: OnError::Fail,
         })
     }
@@ -163,106 +274,131 @@ impl FileStream {
         self
     }
 
+    /// Specify the maximum number of files to open concurrently, ahead of the file currently
+    /// being scanned. Values smaller than 1 are treated as 1.
+    ///
+    /// Defaults to 1, i.e. the next file is opened while the current file is scanned.
+    pub fn with_max_concurrent_opens(mut self, max_concurrent_opens: usize) -> Self {
+        self.max_concurrent_opens = max_concurrent_opens.max(1);
+        self
+    }
+
+    /// Specify the maximum number of batches to read ahead from each file opened ahead of the
+    /// file currently being scanned, so that up to `max_concurrent_opens` files are read
+    /// concurrently.
+    ///
+    /// Defaults to 0, i.e. files are only opened ahead of their turn.
+    pub fn with_file_readahead(mut self, file_readahead: usize) -> Self {
+        self.file_readahead = file_readahead;
+        self
+    }
+
+    /// Reserve the memory of the batches of the stream in `memory_pool`, failing the stream once
+    /// it's exhausted. Only applies to the [`Self::into_async_read_iterator`] and
+    /// [`Self::into_engine_data_stream`] conversions of the stream.
+    pub fn with_memory_pool(mut self, memory_pool: MemoryPool) -> Self {
+        self.memory_pool = Some(memory_pool);
+        self
+    }
+
     /// Begin opening the next file in parallel while decoding the current file in FileStream.
     ///
     /// Since file opening is mostly IO (and may involve a
     /// bunch of sequential IO), it can be parallelized with decoding.
     fn start_next_file(&mut self) -> Option<DeltaResult<FileOpenFuture>> {
         let file_meta = self.file_iter.pop_front()?;
-        Some(self.file_opener.open(file_meta, None))
+        let context = ErrorContext::new().with_file(file_meta.location.clone());
+        // annotate the errors opening or scanning the file with its location
+        let open = match self.file_opener.open(file_meta, None) {
+            Ok(open) => open,
+            Err(err) => return Some(Err(err.with_context(context))),
+        };
+        Some(Ok(Box::pin(async move {
+            match open.await {
+                Ok(stream) => Ok(stream
+                    .map_err(move |err| err.with_context(context.clone()))
+                    .boxed()),
+                Err(err) => Err(err.with_context(context)),
+            }
+        })))
+    }
+
+    /// Handle an error opening or scanning a file. Returns the error if the stream should fail.
+    fn handle_error(&mut self, err: Error) -> Option<Error> {
+        match self.on_error {
+            OnError::Skip => None,
+            OnError::Fail => {
+                self.failed = true;
+                self.reader = None;
+                self.pending_opens.clear();
+                Some(err)
+            }
+        }
     }
 
     fn poll_inner(&mut self, cx: &mut Context<'_>) -> Poll<Option<DeltaResult<RecordBatch>>> {
         loop {
-            match &mut self.state {
-                FileStreamState::Idle => match self.start_next_file().transpose() {
-                    Ok(Some(future)) => self.state = FileStreamState::Open { future },
-                    Ok(None) => return Poll::Ready(None),
-                    Err(e) => {
-                        self.state = FileStreamState::Error;
-                        return Poll::Ready(Some(Err(e)));
-                    }
-                },
-                FileStreamState::Open { future } => match ready!(future.poll_unpin(cx)) {
-                    Ok(reader) => {
-                        // include time needed to start opening in `start_next_file`
-                        let next = self.start_next_file().transpose();
-
-                        match next {
-                            Ok(Some(next_future)) => {
-                                self.state = FileStreamState::Scan {
-                                    reader,
-                                    next: Some(NextOpen::Pending(next_future)),
-                                };
-                            }
-                            Ok(None) => {
-                                self.state = FileStreamState::Scan { reader, next: None };
-                            }
-                            Err(e) => {
-                                self.state = FileStreamState::Error;
-                                return Poll::Ready(Some(Err(e)));
-                            }
-                        }
-                    }
-                    Err(e) => match self.on_error {
-                        OnError::Skip => self.state = FileStreamState::Idle,
-                        OnError::Fail => {
-                            self.state = FileStreamState::Error;
+            if self.failed {
+                return Poll::Ready(None);
+            }
+
+            // Keep up to `max_concurrent_opens` files opening ahead of the current file
+            while self.pending_opens.len() < self.max_concurrent_opens {
+                match self.start_next_file() {
+                    Some(Ok(future)) => self.pending_opens.push_back(NextOpen::Pending(future)),
+                    Some(Err(e)) => {
+                        if let Some(e) = self.handle_error(e) {
                             return Poll::Ready(Some(Err(e)));
                         }
-                    },
-                },
-                FileStreamState::Scan { reader, next } => {
-                    // We need to poll the next `FileOpenFuture` here to drive it forward
-                    if let Some(next_open_future) = next {
-                        if let NextOpen::Pending(f) = next_open_future {
-                            if let Poll::Ready(reader) = f.as_mut().poll(cx) {
-                                *next_open_future = NextOpen::Ready(reader);
-                            }
-                        }
                     }
-                    match ready!(reader.poll_next_unpin(cx)) {
-                        Some(Ok(batch)) => {
-                            return Poll::Ready(Some(Ok(batch)));
+                    None => break,
+                }
+            }
+
+            // We need to poll the pending `FileOpenFuture`s here to drive them forward, and read
+            // ahead from the opened files
+            let file_readahead = self.file_readahead;
+            for next_open in self.pending_opens.iter_mut() {
+                if let NextOpen::Pending(f) = next_open {
+                    if let Poll::Ready(reader) = f.poll_unpin(cx) {
+                        *next_open = NextOpen::Ready(reader.map(ReadAhead::new));
+                    }
+                }
+                if let NextOpen::Ready(Ok(read_ahead)) = next_open {
+                    read_ahead.poll_read_ahead(cx, file_readahead);
+                }
+            }
+
+            if let Some(reader) = &mut self.reader {
+                match ready!(reader.poll_next_unpin(cx)) {
+                    Some(Ok(batch)) => return Poll::Ready(Some(Ok(batch))),
+                    // If `OnError::Skip` we skip the file as soon as we hit the first error
+                    Some(Err(err)) => {
+                        self.reader = None;
+                        if let Some(err) = self.handle_error(err) {
+                            return Poll::Ready(Some(Err(err)));
                         }
-                        Some(Err(err)) => {
-                            match self.on_error {
-                                // If `OnError::Skip` we skip the file as soon as we hit the first error
-                                OnError::Skip => match mem::take(next) {
-                                    Some(future) => match future {
-                                        NextOpen::Pending(future) => {
-                                            self.state = FileStreamState::Open { future }
-                                        }
-                                        NextOpen::Ready(reader) => {
-                                            self.state = FileStreamState::Open {
-                                                future: Box::pin(std::future::ready(reader)),
-                                            }
-                                        }
-                                    },
-                                    None => return Poll::Ready(None),
-                                },
-                                OnError::Fail => {
-                                    self.state = FileStreamState::Error;
-                                    return Poll::Ready(Some(Err(err)));
-                                }
-                            }
+                    }
+                    None => self.reader = None,
+                }
+                continue;
+            }
+
+            // No current file: move on to the next file once it has been opened
+            match self.pending_opens.front() {
+                None => return Poll::Ready(None),
+                Some(NextOpen::Pending(_)) => return Poll::Pending,
+                Some(NextOpen::Ready(_)) => {}
+            }
+            if let Some(NextOpen::Ready(reader)) = self.pending_opens.pop_front() {
+                match reader {
+                    Ok(read_ahead) => self.reader = Some(read_ahead.into_stream()),
+                    Err(err) => {
+                        if let Some(err) = self.handle_error(err) {
+                            return Poll::Ready(Some(Err(err)));
                         }
-                        None => match mem::take(next) {
-                            Some(future) => match future {
-                                NextOpen::Pending(future) => {
-                                    self.state = FileStreamState::Open { future }
-                                }
-                                NextOpen::Ready(reader) => {
-                                    self.state = FileStreamState::Open {
-                                        future: Box::pin(std::future::ready(reader)),
-                                    }
-                                }
-                            },
-                            None => return Poll::Ready(None),
-                        },
                     }
                 }
-                FileStreamState::Error => return Poll::Ready(None),
             }
         }
     }
@@ -275,3 +411,199 @@ impl Stream for FileStream {
         self.poll_inner(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use arrow_array::{Array, Int64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use futures::task::noop_waker_ref;
+    use url::Url;
+
+    use super::*;
+    use crate::engine::arrow_data::ArrowEngineData;
+    use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+
+    /// Opens "files" whose opening only completes once the test releases them, and whose contents
+    /// are a single batch holding the index of the file.
+    struct ControlledOpener {
+        /// For each file: whether opening has started, and whether the test released it
+        files: Arc<Mutex<Vec<(bool, bool)>>>,
+        schema: ArrowSchemaRef,
+    }
+
+    impl FileOpener for ControlledOpener {
+        fn open(&self, file_meta: FileMeta, _: Option<Range<i64>>) -> DeltaResult<FileOpenFuture> {
+            let index: usize = file_meta.location.path()[1..].parse().unwrap();
+            let files = self.files.clone();
+            let schema = self.schema.clone();
+            Ok(Box::pin(std::future::poll_fn(move |_| {
+                let mut files = files.lock().unwrap();
+                files[index].0 = true;
+                if !files[index].1 {
+                    return Poll::Pending;
+                }
+                let column = Arc::new(Int64Array::from(vec![index as i64]));
+                let batch = RecordBatch::try_new(schema.clone(), vec![column]);
+                Poll::Ready(Ok(
+                    futures::stream::iter([batch.map_err(Into::into)]).boxed()
+                ))
+            })))
+        }
+    }
+
+    #[test]
+    fn test_concurrent_opens_preserve_order() {
+        let num_files = 5;
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int64, false)]));
+        let files = Arc::new(Mutex::new(vec![(false, false); num_files]));
+        let opener = ControlledOpener {
+            files: files.clone(),
+            schema: schema.clone(),
+        };
+        let file_metas = (0..num_files)
+            .map(|i| FileMeta::new(Url::parse(&format!("memory:///{i}")).unwrap(), 0, 0));
+        let mut stream = FileStream::new(file_metas, schema, Box::new(opener))
+            .unwrap()
+            .with_max_concurrent_opens(3);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut poll_index = |stream: &mut FileStream| match stream.poll_next_unpin(&mut cx) {
+            Poll::Ready(Some(batch)) => {
+                let batch = batch.unwrap();
+                let column = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                Poll::Ready(Some(column.unwrap().value(0)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        };
+        let started = |files: &Mutex<Vec<(bool, bool)>>| -> Vec<bool> {
+            files.lock().unwrap().iter().map(|(s, _)| *s).collect()
+        };
+        let release = |i: usize| files.lock().unwrap()[i].1 = true;
+
+        // the first three files start opening concurrently
+        assert!(poll_index(&mut stream).is_pending());
+        assert_eq!(started(&files), [true, true, true, false, false]);
+
+        // files that finish opening out of order are buffered until their turn
+        release(2);
+        release(1);
+        assert!(poll_index(&mut stream).is_pending());
+        assert_eq!(started(&files), [true, true, true, false, false]);
+
+        release(0);
+        assert_eq!(poll_index(&mut stream), Poll::Ready(Some(0)));
+        assert_eq!(poll_index(&mut stream), Poll::Ready(Some(1)));
+        assert_eq!(started(&files), [true, true, true, true, true]);
+        assert_eq!(poll_index(&mut stream), Poll::Ready(Some(2)));
+        assert!(poll_index(&mut stream).is_pending());
+
+        release(4);
+        release(3);
+        assert_eq!(poll_index(&mut stream), Poll::Ready(Some(3)));
+        assert_eq!(poll_index(&mut stream), Poll::Ready(Some(4)));
+        assert_eq!(poll_index(&mut stream), Poll::Ready(None));
+    }
+
+    /// Opens "files" of two batches each, holding `10 * index + i` for the `i`th batch, and
+    /// counts the batches read from each file.
+    struct CountingOpener {
+        batches_read: Arc<Mutex<Vec<usize>>>,
+        schema: ArrowSchemaRef,
+    }
+
+    impl FileOpener for CountingOpener {
+        fn open(&self, file_meta: FileMeta, _: Option<Range<i64>>) -> DeltaResult<FileOpenFuture> {
+            let index: usize = file_meta.location.path()[1..].parse().unwrap();
+            let batches_read = self.batches_read.clone();
+            let schema = self.schema.clone();
+            let batches = futures::stream::iter(0..2).map(move |i| {
+                batches_read.lock().unwrap()[index] += 1;
+                let column = Arc::new(Int64Array::from(vec![(10 * index + i) as i64]));
+                Ok(RecordBatch::try_new(schema.clone(), vec![column])?)
+            });
+            Ok(Box::pin(futures::future::ready(Ok(batches.boxed()))))
+        }
+    }
+
+    #[test]
+    fn test_file_readahead() {
+        let num_files = 4;
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int64, false)]));
+        let batches_read = Arc::new(Mutex::new(vec![0; num_files]));
+        let opener = CountingOpener {
+            batches_read: batches_read.clone(),
+            schema: schema.clone(),
+        };
+        let file_metas = (0..num_files)
+            .map(|i| FileMeta::new(Url::parse(&format!("memory:///{i}")).unwrap(), 0, 0));
+        let mut stream = FileStream::new(file_metas, schema, Box::new(opener))
+            .unwrap()
+            .with_max_concurrent_opens(2)
+            .with_file_readahead(1);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut next_value = || match stream.poll_next_unpin(&mut cx) {
+            Poll::Ready(Some(batch)) => {
+                let batch = batch.unwrap();
+                let column = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                Some(column.unwrap().value(0))
+            }
+            Poll::Ready(None) => None,
+            Poll::Pending => panic!("the batches of all files are ready"),
+        };
+        let batches_read = || batches_read.lock().unwrap().clone();
+
+        // while the first file is scanned, one batch is read ahead from each of the next two
+        assert_eq!(next_value(), Some(0));
+        assert_eq!(batches_read(), [1, 1, 1, 0]);
+        assert_eq!(next_value(), Some(1));
+        assert_eq!(batches_read(), [2, 1, 1, 0]);
+        assert_eq!(next_value(), Some(10));
+        assert_eq!(batches_read(), [2, 1, 1, 1]);
+
+        let values: Vec<_> = std::iter::from_fn(next_value).collect();
+        assert_eq!(values, [11, 20, 21, 30, 31]);
+        assert_eq!(batches_read(), [2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_spawned_file_opener() {
+        let num_files = 4;
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int64, false)]));
+        let batches_read = Arc::new(Mutex::new(vec![0; num_files]));
+        let opener = CountingOpener {
+            batches_read: batches_read.clone(),
+            schema: schema.clone(),
+        };
+        let executor = Arc::new(TokioBackgroundExecutor::new());
+        let opener = SpawnedFileOpener::new(Box::new(opener), executor.clone());
+        let file_metas = (0..num_files)
+            .map(|i| FileMeta::new(Url::parse(&format!("memory:///{i}")).unwrap(), 0, 0));
+        let mut batches = FileStream::new(file_metas, schema, Box::new(opener))
+            .unwrap()
+            .with_max_concurrent_opens(num_files)
+            .into_async_read_iterator(executor, 1)
+            .map(|batch| {
+                let batch = RecordBatch::from(
+                    ArrowEngineData::try_from_engine_data(batch.unwrap()).unwrap(),
+                );
+                let column = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                column.unwrap().value(0)
+            });
+        assert_eq!(batches.next(), Some(0));
+
+        // the files behind the first one are decoded by their own tasks, ahead of the consumer
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while batches_read.lock().unwrap().iter().any(|read| *read < 2) {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "files weren't decoded ahead"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let values: Vec<_> = batches.collect();
+        assert_eq!(values, [1, 10, 11, 20, 21, 30, 31]);
+    }
+}
@@ -1,22 +1,34 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
-use futures::stream::StreamExt;
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use futures::{FutureExt, SinkExt, TryFutureExt};
 use itertools::Itertools;
 use object_store::path::Path;
-use object_store::{DynObjectStore, ObjectStore};
+use object_store::{DynObjectStore, ObjectMeta, ObjectStore};
 use url::Url;
 
+use crate::async_engine;
 use crate::engine::default::executor::TaskExecutor;
-use crate::{DeltaResult, Error, FileMeta, FileSlice, FileSystemClient};
+#[cfg(feature = "reqwest")]
+use crate::engine::default::presigned;
+use crate::engine::default::storage::{put_error, ExternalStores};
+use crate::{DeltaResult, Error, FileMeta, FileSlice, FileSystemClient, ListOptions};
 
 #[derive(Debug)]
 pub struct ObjectStoreFileSystemClient<E: TaskExecutor> {
     inner: Arc<DynObjectStore>,
+    external_stores: ExternalStores,
     has_ordered_listing: bool,
     table_root: Path,
     task_executor: Arc<E>,
     readahead: usize,
+    #[cfg(feature = "mmap")]
+    mmap: bool,
+    // reads HTTP(S) URLs, e.g. pre-signed URLs
+    #[cfg(feature = "reqwest")]
+    client: reqwest::Client,
 }
 
 impl<E: TaskExecutor> ObjectStoreFileSystemClient<E> {
@@ -28,10 +40,15 @@ impl<E: TaskExecutor> ObjectStoreFileSystemClient<E> {
     ) -> Self {
         Self {
             inner: store,
+            external_stores: ExternalStores::default(),
             has_ordered_listing,
             table_root,
             task_executor,
             readahead: 10,
+            #[cfg(feature = "mmap")]
+            mmap: false,
+            #[cfg(feature = "reqwest")]
+            client: reqwest::Client::new(),
         }
     }
 
@@ -40,56 +57,309 @@ impl<E: TaskExecutor> ObjectStoreFileSystemClient<E> {
         self.readahead = readahead;
         self
     }
+
+    // Read and head the files in external storage with its store
+    pub(crate) fn with_external_stores(mut self, external_stores: ExternalStores) -> Self {
+        self.external_stores = external_stores;
+        self
+    }
+
+    /// Memory-map local (`file://`) files rather than reading them through the object store in
+    /// [FileSystemClient::read_files()]. See [mmap].
+    ///
+    /// Defaults to false.
+    ///
+    /// [mmap]: super::mmap
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
 }
 
-impl<E: TaskExecutor> FileSystemClient for ObjectStoreFileSystemClient<E> {
-    fn list_from(
-        &self,
-        path: &Url,
-    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
-        let url = path.clone();
-        let offset = Path::from(path.path());
+impl<E: TaskExecutor> ObjectStoreFileSystemClient<E> {
+    // A stream of the log files at or after `path`, in the order returned by the object store
+    fn list_stream(&self, path: &Url) -> BoxStream<'static, DeltaResult<FileMeta>> {
         // TODO properly handle table prefix
         let prefix = self.table_root.child("_delta_log");
+        self.list_stream_with_prefix(path, prefix)
+    }
 
+    // A stream of the files under `prefix` at or after `path`, in the order returned by the
+    // object store
+    fn list_stream_with_prefix(
+        &self,
+        path: &Url,
+        prefix: Path,
+    ) -> BoxStream<'static, DeltaResult<FileMeta>> {
+        let url = path.clone();
+        let offset = match Path::from_url_path(path.path()) {
+            Ok(offset) => offset,
+            Err(err) => return futures::stream::once(async { Err(err.into()) }).boxed(),
+        };
         let store = self.inner.clone();
+        // the listing borrows the store, so it sends its results through a channel, and is driven
+        // along with the (owned) stream of its results
+        let (mut sender, receiver) = futures::channel::mpsc::channel(100);
+        let list = async move {
+            let mut stream = store.list_with_offset(Some(&prefix), &offset);
+            while let Some(meta) = stream.next().await {
+                // stop listing once the results are dropped
+                if sender.send(meta).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let list = list
+            .into_stream()
+            .filter_map(|()| futures::future::ready(None::<object_store::Result<ObjectMeta>>));
+        futures::stream::select(list, receiver)
+            .map(move |meta| Ok(file_meta(&url, meta?)))
+            .boxed()
+    }
 
+    // A stream of the files (and, if delimited, directories) in the directory of `path` at or
+    // after it, sorted, and narrowed down by `options`
+    fn list_with_options_stream(
+        &self,
+        path: &Url,
+        options: &ListOptions,
+    ) -> BoxStream<'static, DeltaResult<FileMeta>> {
+        let dir = path.path().rsplit_once('/').map_or("", |(dir, _)| dir);
+        let (prefix, offset) = match (Path::from_url_path(dir), Path::from_url_path(path.path())) {
+            (Ok(prefix), Ok(offset)) => (prefix, offset),
+            (Err(err), _) | (_, Err(err)) => {
+                return futures::stream::once(async { Err(err.into()) }).boxed()
+            }
+        };
+        let stream = if options.delimited {
+            // the object store lists the direct children of the prefix in one go
+            let url = path.clone();
+            let store = self.inner.clone();
+            let list = async move {
+                let result = store.list_with_delimiter(Some(&prefix)).await?;
+                let directories = result.common_prefixes.into_iter().map(|dir| {
+                    let mut location = object_url(&url, &dir);
+                    location.set_path(&format!("{}/", location.path()));
+                    (dir, FileMeta::new(location, 0, 0))
+                });
+                let files = result
+                    .objects
+                    .into_iter()
+                    .map(|meta| (meta.location.clone(), file_meta(&url, meta)));
+                let mut files: Vec<_> = directories
+                    .chain(files)
+                    .filter(|(location, _)| *location > offset)
+                    .map(|(_, file)| file)
+                    .collect();
+                files.sort_unstable();
+                Ok::<_, Error>(futures::stream::iter(files.into_iter().map(Ok)))
+            };
+            list.try_flatten_stream().boxed()
+        } else {
+            self.sorted(self.list_stream_with_prefix(path, prefix))
+        };
+        let options = options.clone();
+        let max_results = options.max_results.unwrap_or(usize::MAX);
+        stream
+            .try_filter(move |file| futures::future::ready(options.matches(file)))
+            .take(max_results)
+            .boxed()
+    }
+
+    // Sort the files of `stream`, unless the object store already lists them in order
+    fn sorted(
+        &self,
+        stream: BoxStream<'static, DeltaResult<FileMeta>>,
+    ) -> BoxStream<'static, DeltaResult<FileMeta>> {
+        if self.has_ordered_listing {
+            return stream;
+        }
+        // This FS doesn't return things in the order we require
+        let sorted = async move {
+            let mut fms: Vec<FileMeta> = stream.try_collect().await?;
+            fms.sort_unstable();
+            Ok::<_, Error>(futures::stream::iter(fms.into_iter().map(Ok)))
+        };
+        sorted.try_flatten_stream().boxed()
+    }
+
+    // Drive `stream` on the executor, returning an iterator of its results
+    fn blocking_iter<T: Send + 'static>(
+        &self,
+        mut stream: BoxStream<'static, T>,
+    ) -> Box<dyn Iterator<Item = T>> {
         // This channel will become the iterator
         let (sender, receiver) = std::sync::mpsc::sync_channel(4_000);
 
         self.task_executor.spawn(async move {
-            let mut stream = store.list_with_offset(Some(&prefix), &offset);
-
             while let Some(meta) = stream.next().await {
-                match meta {
-                    Ok(meta) => {
-                        let mut location = url.clone();
-                        location.set_path(&format!("/{}", meta.location.as_ref()));
-                        sender
-                            .send(Ok(FileMeta {
-                                location,
-                                last_modified: meta.last_modified.timestamp(),
-                                size: meta.size,
-                            }))
-                            .ok();
-                    }
-                    Err(e) => {
-                        sender.send(Err(e.into())).ok();
-                    }
+                // stop listing once the receiver is gone, e.g. because the kernel has already
+                // found all the log files it needs
+                if sender.send(meta).is_err() {
+                    break;
                 }
             }
         });
+        Box::new(receiver.into_iter())
+    }
+
+    // The store of the file at `url`, which is in the storage of the table unless it's external
+    fn store_for(&self, url: &Url) -> DeltaResult<Arc<DynObjectStore>> {
+        self.external_stores.store_for(url, &self.inner)
+    }
+
+    fn head_future(&self, path: &Url) -> BoxFuture<'static, DeltaResult<FileMeta>> {
+        let store = self.store_for(path);
+        let url = path.clone();
+        Box::pin(async move {
+            let meta = store?.head(&object_store_path(&url)?).await?;
+            Ok(file_meta(&url, meta))
+        })
+    }
 
+    // A stream of the contents of `files`, in order, reading up to `readahead` files concurrently
+    #[cfg_attr(
+        not(any(feature = "mmap", feature = "reqwest")),
+        allow(unused_variables)
+    )]
+    fn read_stream(&self, files: Vec<FileSlice>) -> BoxStream<'static, DeltaResult<Bytes>> {
+        #[cfg(feature = "reqwest")]
+        let client = self.client.clone(); // uses Arc internally according to reqwest docs
+        #[cfg(feature = "mmap")]
+        let mmap = self.mmap;
+        // pick the stores up front, since the stream outlives the client. Files in external
+        // storage with a store are read with it, rather than mapped or fetched directly
+        let files: Vec<_> = files
+            .into_iter()
+            .map(|(url, range)| {
+                let external = self.external_stores.get(&url).is_some();
+                let store = self.store_for(&url);
+                (url, range, external, store)
+            })
+            .collect();
+        futures::stream::iter(files)
+            .map(move |(url, range, external, store)| {
+                #[cfg(feature = "reqwest")]
+                let client = client.clone();
+                async move {
+                    match url.scheme() {
+                        #[cfg(feature = "mmap")]
+                        "file" if mmap && !external => super::mmap::read_mapped(&url, range),
+                        #[cfg(feature = "reqwest")]
+                        "http" | "https" if !external => presigned::get(client, url, range).await,
+                        _ => {
+                            let store = store?;
+                            let path = object_store_path(&url)?;
+                            if let Some(rng) = range {
+                                Ok(store.get_range(&path, rng).await?)
+                            } else {
+                                let result = store.get(&path).await?;
+                                Ok(result.bytes().await?)
+                            }
+                        }
+                    }
+                }
+            })
+            // We allow executing up to `readahead` futures concurrently and
+            // buffer the results.
+            .buffered(self.readahead)
+            .boxed()
+    }
+
+    fn write_future(
+        &self,
+        path: &Url,
+        data: Bytes,
+        overwrite: bool,
+    ) -> BoxFuture<'static, DeltaResult<()>> {
+        // Put if absent, unless the caller asked to overwrite
+        let put_mode = if overwrite {
+            object_store::PutMode::Overwrite
+        } else {
+            object_store::PutMode::Create
+        };
+        let store = self.inner.clone(); // cheap Arc
+        let path = object_store_path(path);
+        Box::pin(async move {
+            let path = path?;
+            let path_str = path.to_string();
+            store
+                .put_opts(&path, data.into(), put_mode.into())
+                .await
+                .map_err(|e| put_error(e, path_str))?;
+            Ok(())
+        })
+    }
+}
+
+// The path of the object at `url` in the object store. The path of the URL is percent-encoded,
+// e.g. a space in the name of a partition directory is `%20`, which the object path doesn't encode.
+fn object_store_path(url: &Url) -> DeltaResult<Path> {
+    // Wasn't checking the scheme before calling to_file_path causing the url path to be eaten in
+    // a strange way. Now, if not a file scheme, just blindly convert to a path.
+    // https://docs.rs/url/latest/url/struct.Url.html#method.to_file_path has more details about
+    // why this check is necessary
+    if url.scheme() == "file" {
+        let file_path = url
+            .to_file_path()
+            .map_err(|()| Error::generic(format!("Not a valid file path: {url}")))?;
+        Ok(Path::from_absolute_path(file_path)?)
+    } else {
+        Ok(Path::from_url_path(url.path())?)
+    }
+}
+
+// The URL of the object at `path` in the object store of `url`. Object paths aren't encoded (e.g.
+// a Hive-escaped partition directory has a `%` in its name), so the parts of the path are
+// percent-encoded, such that `object_store_path` returns `path` again.
+fn object_url(url: &Url, path: &Path) -> Url {
+    let mut location = url.clone();
+    if let Ok(mut segments) = location.path_segments_mut() {
+        segments.clear().extend(path.parts());
+    }
+    location
+}
+
+// The metadata of the file at `url` (of the object store) described by `meta`
+fn file_meta(url: &Url, meta: ObjectMeta) -> FileMeta {
+    FileMeta {
+        location: object_url(url, &meta.location),
+        last_modified: meta.last_modified.timestamp_millis(),
+        size: meta.size,
+    }
+}
+
+impl<E: TaskExecutor> FileSystemClient for ObjectStoreFileSystemClient<E> {
+    fn list_from(
+        &self,
+        path: &Url,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        let files = self.blocking_iter(self.list_stream(path));
         if !self.has_ordered_listing {
             // This FS doesn't return things in the order we require
-            let mut fms: Vec<FileMeta> = receiver.into_iter().try_collect()?;
+            let mut fms: Vec<FileMeta> = files.try_collect()?;
             fms.sort_unstable();
             Ok(Box::new(fms.into_iter().map(Ok)))
         } else {
-            Ok(Box::new(receiver.into_iter()))
+            Ok(files)
         }
     }
 
+    /// List the files in the directory of `path` at or after it, pushing a delimiter down to the
+    /// object store, see [`FileSystemClient::list_with_options`].
+    fn list_with_options(
+        &self,
+        path: &Url,
+        options: &ListOptions,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        Ok(self.blocking_iter(self.list_with_options_stream(path, options)))
+    }
+
+    fn head(&self, path: &Url) -> DeltaResult<FileMeta> {
+        self.task_executor.block_on(self.head_future(path))
+    }
+
     /// Read data specified by the start and end offset from the file.
     ///
     /// This will return the data in the same order as the provided file slices.
@@ -100,56 +370,59 @@ impl<E: TaskExecutor> FileSystemClient for ObjectStoreFileSystemClient<E> {
         &self,
         files: Vec<FileSlice>,
     ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>> {
-        let store = self.inner.clone();
-
         // This channel will become the output iterator.
         // Because there will already be buffering in the stream, we set the
         // buffer size to 0.
         let (sender, receiver) = std::sync::mpsc::sync_channel(0);
 
-        self.task_executor.spawn(
-            futures::stream::iter(files)
-                .map(move |(url, range)| {
-                    // Wasn't checking the scheme before calling to_file_path causing the url path to
-                    // be eaten in a strange way. Now, if not a file scheme, just blindly convert to a path.
-                    // https://docs.rs/url/latest/url/struct.Url.html#method.to_file_path has more
-                    // details about why this check is necessary
-                    let path = if url.scheme() == "file" {
-                        let file_path = url.to_file_path().expect("Not a valid file path");
-                        Path::from_absolute_path(file_path).expect("Not able to be made into Path")
-                    } else {
-                        Path::from(url.path())
-                    };
-                    let store = store.clone();
-                    async move {
-                        match url.scheme() {
-                            "http" | "https" => {
-                                // have to annotate type here or rustc can't figure it out
-                                Ok::<bytes::Bytes, Error>(reqwest::get(url).await?.bytes().await?)
-                            }
-                            _ => {
-                                if let Some(rng) = range {
-                                    Ok(store.get_range(&path, rng).await?)
-                                } else {
-                                    let result = store.get(&path).await?;
-                                    Ok(result.bytes().await?)
-                                }
-                            }
-                        }
-                    }
-                })
-                // We allow executing up to `readahead` futures concurrently and
-                // buffer the results. This allows us to achieve async concurrency
-                // within a synchronous method.
-                .buffered(self.readahead)
-                .for_each(move |res| {
-                    sender.send(res).ok();
-                    futures::future::ready(())
-                }),
-        );
+        // This allows us to achieve async concurrency within a synchronous method.
+        self.task_executor
+            .spawn(self.read_stream(files).for_each(move |res| {
+                sender.send(res).ok();
+                futures::future::ready(())
+            }));
 
         Ok(Box::new(receiver.into_iter()))
     }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        self.task_executor
+            .block_on(self.write_future(path, data, overwrite))
+    }
+}
+
+impl<E: TaskExecutor> async_engine::AsyncFileSystemClient for ObjectStoreFileSystemClient<E> {
+    fn list_from(&self, path: &Url) -> DeltaResult<BoxStream<'static, DeltaResult<FileMeta>>> {
+        Ok(self.sorted(self.list_stream(path)))
+    }
+
+    fn list_with_options(
+        &self,
+        path: &Url,
+        options: &ListOptions,
+    ) -> DeltaResult<BoxStream<'static, DeltaResult<FileMeta>>> {
+        Ok(self.list_with_options_stream(path, options))
+    }
+
+    fn head(&self, path: &Url) -> BoxFuture<'_, DeltaResult<FileMeta>> {
+        self.head_future(path)
+    }
+
+    fn read_files(
+        &self,
+        files: Vec<FileSlice>,
+    ) -> DeltaResult<BoxStream<'static, DeltaResult<Bytes>>> {
+        Ok(self.read_stream(files))
+    }
+
+    fn write_file(
+        &self,
+        path: &Url,
+        data: Bytes,
+        overwrite: bool,
+    ) -> BoxFuture<'_, DeltaResult<()>> {
+        self.write_future(path, data, overwrite)
+    }
 }
 
 #[cfg(test)]
@@ -216,6 +489,65 @@ mod tests {
         assert_eq!(data[2], Bytes::from("el-da"));
     }
 
+    #[tokio::test]
+    async fn test_list_with_options_and_head() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tmp_store = LocalFileSystem::new_with_prefix(tmp.path()).unwrap();
+        for (name, size) in [("data/a", 1), ("data/b", 3), ("data/sub/c", 5), ("d", 7)] {
+            let data = Bytes::from("x".repeat(size));
+            tmp_store.put(&Path::from(name), data.into()).await.unwrap();
+        }
+
+        let url = Url::from_directory_path(tmp.path()).unwrap();
+        let store = Arc::new(LocalFileSystem::new());
+        let prefix = Path::from_url_path(url.path()).expect("Couldn't get path");
+        let client = ObjectStoreFileSystemClient::new(
+            store,
+            false, // don't have ordered listing
+            prefix,
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+        let list = |path: &str, options: ListOptions| -> Vec<String> {
+            let path = url.join(path).unwrap();
+            client
+                .list_with_options(&path, &options)
+                .unwrap()
+                .map_ok(|file| {
+                    file.location
+                        .path()
+                        .strip_prefix(url.path())
+                        .unwrap()
+                        .to_string()
+                })
+                .try_collect()
+                .unwrap()
+        };
+
+        let options = ListOptions::default();
+        assert_eq!(list("data/", options), ["data/a", "data/b", "data/sub/c"]);
+        let options = ListOptions::default().with_delimiter();
+        assert_eq!(list("data/", options), ["data/a", "data/b", "data/sub/"]);
+        let options = ListOptions::default().with_delimiter();
+        assert_eq!(list("data/a", options), ["data/b", "data/sub/"]);
+        let options = ListOptions::default().with_size_range(Some(2), None);
+        assert_eq!(list("data/", options), ["data/b", "data/sub/c"]);
+        let options = ListOptions::default().with_max_results(1);
+        assert_eq!(list("data/", options), ["data/a"]);
+
+        let file = client.head(&url.join("data/b").unwrap()).unwrap();
+        assert_eq!(file.size, 3);
+        // modification times are in milliseconds since the epoch
+        let modified = std::fs::metadata(tmp.path().join("data/b"))
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(file.last_modified, modified.as_millis() as i64);
+        let missing = client.head(&url.join("data/e").unwrap());
+        assert!(matches!(missing, Err(Error::FileNotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_default_engine_listing() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1,28 +1,38 @@
 //! Default Json handler implementation
 
-use std::io::BufReader;
 use std::ops::Range;
 use std::sync::Arc;
 use std::task::{ready, Poll};
 
+use arrow_array::RecordBatch;
+use arrow_json::reader::Decoder;
 use arrow_json::ReaderBuilder;
 use arrow_schema::SchemaRef as ArrowSchemaRef;
 use bytes::{Buf, Bytes};
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
 use futures::{StreamExt, TryStreamExt};
 use object_store::path::Path;
-use object_store::{DynObjectStore, GetResultPayload};
+use object_store::DynObjectStore;
 use url::Url;
 
+use super::cache::MetadataCache;
 use super::executor::TaskExecutor;
-use super::file_stream::{FileOpenFuture, FileOpener, FileStream};
+use super::file_stream::{FileOpenFuture, FileOpener, FileStream, SpawnedFileOpener};
+use super::storage::put_error;
+use crate::async_engine::{self, FileDataReadResultStream};
 use crate::engine::arrow_utils::parse_json as arrow_parse_json;
+use crate::engine::arrow_utils::parse_selected_json as arrow_parse_selected_json;
 use crate::engine::arrow_utils::to_json_bytes;
+use crate::engine::memory::{memory_size, track_produced, MemoryPool};
 use crate::schema::SchemaRef;
 use crate::{
     DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
     JsonHandler,
 };
 
+const DEFAULT_MAX_BATCH_BYTES: usize = 16 * 1024 * 1024;
+
 #[derive(Debug)]
 pub struct DefaultJsonHandler<E: TaskExecutor> {
     /// The object store to read files from
@@ -31,8 +41,16 @@ pub struct DefaultJsonHandler<E: TaskExecutor> {
     task_executor: Arc<E>,
     /// The maximun number of batches to read ahead
     readahead: usize,
+    /// The maximum number of files to read concurrently
+    max_concurrent_reads: usize,
     /// The number of rows to read per batch
     batch_size: usize,
+    /// The maximum number of bytes of JSON to read per batch
+    max_batch_bytes: usize,
+    /// The cache of commit files, if any
+    metadata_cache: Option<MetadataCache>,
+    /// The pool in which the read and parsed data reserves its memory, if any
+    memory_pool: Option<MemoryPool>,
 }
 
 impl<E: TaskExecutor> DefaultJsonHandler<E> {
@@ -41,7 +59,11 @@ impl<E: TaskExecutor> DefaultJsonHandler<E> {
             store,
             task_executor,
             readahead: 10,
+            max_concurrent_reads: 10,
             batch_size: 1024,
+            max_batch_bytes: DEFAULT_MAX_BATCH_BYTES,
+            metadata_cache: None,
+            memory_pool: None,
         }
     }
 
@@ -53,6 +75,16 @@ impl<E: TaskExecutor> DefaultJsonHandler<E> {
         self
     }
 
+    /// Set the maximum number of files to fetch and parse concurrently during
+    /// [Self::read_json_files()]. Each file is parsed in its own task on the executor, and batches
+    /// are still returned in the order of the requested files.
+    ///
+    /// Defaults to 10.
+    pub fn with_max_concurrent_reads(mut self, max_concurrent_reads: usize) -> Self {
+        self.max_concurrent_reads = max_concurrent_reads;
+        self
+    }
+
     /// Set the number of rows to read per batch during [Self::parse_json()].
     ///
     /// Defaults to 1024.
@@ -60,6 +92,34 @@ impl<E: TaskExecutor> DefaultJsonHandler<E> {
         self.batch_size = batch_size;
         self
     }
+
+    /// Set the maximum number of bytes of JSON to read per batch during
+    /// [Self::read_json_files()]. A batch ends with the line which brings it over this size, or
+    /// once it holds the batch size number of rows, whichever comes first.
+    ///
+    /// Defaults to 16 MiB.
+    pub fn with_max_batch_bytes(mut self, max_batch_bytes: usize) -> Self {
+        self.max_batch_bytes = max_batch_bytes;
+        self
+    }
+
+    /// Cache the contents of the commit files read by [Self::read_json_files()] in
+    /// `metadata_cache`.
+    ///
+    /// Defaults to no caching.
+    pub fn with_metadata_cache(mut self, metadata_cache: MetadataCache) -> Self {
+        self.metadata_cache = Some(metadata_cache);
+        self
+    }
+
+    /// Reserve the memory of the data read by [Self::read_json_files()] and parsed by
+    /// [Self::parse_json()] in `memory_pool`, failing once it's exhausted.
+    ///
+    /// Defaults to no limit.
+    pub fn with_memory_pool(mut self, memory_pool: MemoryPool) -> Self {
+        self.memory_pool = Some(memory_pool);
+        self
+    }
 }
 
 impl<E: TaskExecutor> JsonHandler for DefaultJsonHandler<E> {
@@ -68,7 +128,23 @@ impl<E: TaskExecutor> JsonHandler for DefaultJsonHandler<E> {
         json_strings: Box<dyn EngineData>,
         output_schema: SchemaRef,
     ) -> DeltaResult<Box<dyn EngineData>> {
-        arrow_parse_json(json_strings, output_schema)
+        // the parsed data is usually no larger than the JSON strings
+        let estimate = memory_size(json_strings.as_ref());
+        track_produced(self.memory_pool.as_ref(), estimate, || {
+            arrow_parse_json(json_strings, output_schema)
+        })
+    }
+
+    fn parse_json_selected(
+        &self,
+        json_strings: Box<dyn EngineData>,
+        output_schema: SchemaRef,
+        selection_vector: &[bool],
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let estimate = memory_size(json_strings.as_ref());
+        track_produced(self.memory_pool.as_ref(), estimate, || {
+            arrow_parse_selected_json(json_strings, output_schema, selection_vector)
+        })
     }
 
     fn read_json_files(
@@ -80,16 +156,8 @@ impl<E: TaskExecutor> JsonHandler for DefaultJsonHandler<E> {
         if files.is_empty() {
             return Ok(Box::new(std::iter::empty()));
         }
-
-        let schema: ArrowSchemaRef = Arc::new(physical_schema.as_ref().try_into()?);
-        let file_opener = JsonOpener::new(self.batch_size, schema.clone(), self.store.clone());
-        FileStream::new_async_read_iterator(
-            self.task_executor.clone(),
-            schema,
-            Box::new(file_opener),
-            files,
-            self.readahead,
-        )
+        let stream = self.file_stream(files, &physical_schema)?;
+        Ok(stream.into_async_read_iterator(self.task_executor.clone(), self.readahead))
     }
 
     // note: for now we just buffer all the data and write it out all at once
@@ -97,24 +165,100 @@ impl<E: TaskExecutor> JsonHandler for DefaultJsonHandler<E> {
         &self,
         path: &Url,
         data: Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + '_>,
-        _overwrite: bool,
+        overwrite: bool,
     ) -> DeltaResult<()> {
         let buffer = to_json_bytes(data)?;
-        // Put if absent
-        let store = self.store.clone(); // cheap Arc
-        let path = Path::from(path.path());
-        let path_str = path.to_string();
         self.task_executor
-            .block_on(async move {
-                store
-                    .put_opts(&path, buffer.into(), object_store::PutMode::Create.into())
-                    .await
-            })
-            .map_err(|e| match e {
-                object_store::Error::AlreadyExists { .. } => Error::FileAlreadyExists(path_str),
-                e => e.into(),
-            })?;
-        Ok(())
+            .block_on(self.put_json(path, buffer, overwrite))
+    }
+}
+
+impl<E: TaskExecutor> DefaultJsonHandler<E> {
+    fn file_stream(
+        &self,
+        files: &[FileMeta],
+        physical_schema: &SchemaRef,
+    ) -> DeltaResult<FileStream> {
+        let file_opener = self.file_opener(physical_schema)?;
+        let schema = file_opener.projected_schema.clone();
+        // parse the files in parallel on the executor, rather than in the task polling the stream
+        let file_opener = SpawnedFileOpener::new(Box::new(file_opener), self.task_executor.clone());
+        let mut stream = FileStream::new(files.to_vec(), schema, Box::new(file_opener))?
+            .with_max_concurrent_opens(self.max_concurrent_reads);
+        if let Some(memory_pool) = &self.memory_pool {
+            stream = stream.with_memory_pool(memory_pool.clone());
+        }
+        Ok(stream)
+    }
+
+    fn file_opener(&self, physical_schema: &SchemaRef) -> DeltaResult<JsonOpener> {
+        let schema: ArrowSchemaRef = Arc::new(physical_schema.as_ref().try_into()?);
+        Ok(JsonOpener {
+            max_batch_bytes: self.max_batch_bytes,
+            metadata_cache: self.metadata_cache.clone(),
+            ..JsonOpener::new(self.batch_size, schema, self.store.clone())
+        })
+    }
+
+    fn put_json(
+        &self,
+        path: &Url,
+        buffer: Vec<u8>,
+        overwrite: bool,
+    ) -> BoxFuture<'static, DeltaResult<()>> {
+        // Put if absent, unless the caller asked to overwrite
+        let put_mode = if overwrite {
+            object_store::PutMode::Overwrite
+        } else {
+            object_store::PutMode::Create
+        };
+        let store = self.store.clone(); // cheap Arc
+        let path = Path::from_url_path(path.path());
+        Box::pin(async move {
+            let path = path?;
+            let path_str = path.to_string();
+            store
+                .put_opts(&path, buffer.into(), put_mode.into())
+                .await
+                .map_err(|e| put_error(e, path_str))?;
+            Ok(())
+        })
+    }
+}
+
+impl<E: TaskExecutor> async_engine::AsyncJsonHandler for DefaultJsonHandler<E> {
+    fn parse_json(
+        &self,
+        json_strings: Box<dyn EngineData>,
+        output_schema: SchemaRef,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        // the parsed data is usually no larger than the JSON strings
+        let estimate = memory_size(json_strings.as_ref());
+        track_produced(self.memory_pool.as_ref(), estimate, || {
+            arrow_parse_json(json_strings, output_schema)
+        })
+    }
+
+    fn read_json_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        _predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultStream> {
+        let stream = self.file_stream(files, &physical_schema)?;
+        Ok(stream.into_engine_data_stream())
+    }
+
+    fn write_json_file(
+        &self,
+        path: &Url,
+        data: Vec<Box<dyn EngineData>>,
+        overwrite: bool,
+    ) -> BoxFuture<'_, DeltaResult<()>> {
+        match to_json_bytes(data.into_iter().map(Ok)) {
+            Ok(buffer) => self.put_json(path, buffer, overwrite),
+            Err(e) => Box::pin(futures::future::ready(Err(e))),
+        }
     }
 }
 
@@ -122,8 +266,10 @@ impl<E: TaskExecutor> JsonHandler for DefaultJsonHandler<E> {
 #[allow(missing_debug_implementations)]
 pub struct JsonOpener {
     batch_size: usize,
+    max_batch_bytes: usize,
     projected_schema: ArrowSchemaRef,
     object_store: Arc<DynObjectStore>,
+    metadata_cache: Option<MetadataCache>,
 }
 
 impl JsonOpener {
@@ -136,9 +282,11 @@ impl JsonOpener {
     ) -> Self {
         Self {
             batch_size,
+            max_batch_bytes: DEFAULT_MAX_BATCH_BYTES,
             projected_schema,
             // file_compression_type,
             object_store,
+            metadata_cache: None,
         }
     }
 }
@@ -148,68 +296,104 @@ impl FileOpener for JsonOpener {
         let store = self.object_store.clone();
         let schema = self.projected_schema.clone();
         let batch_size = self.batch_size;
+        let max_batch_bytes = self.max_batch_bytes;
+        let metadata_cache = self
+            .metadata_cache
+            .clone()
+            .filter(|cache| cache.caches_contents(&file_meta));
 
         Ok(Box::pin(async move {
-            let path = Path::from_url_path(file_meta.location.path())?;
-            match store.get(&path).await?.payload {
-                GetResultPayload::File(file, _) => {
-                    let reader = ReaderBuilder::new(schema)
-                        .with_batch_size(batch_size)
-                        .build(BufReader::new(file))?;
-                    Ok(futures::stream::iter(reader).map_err(Error::from).boxed())
+            let input = match metadata_cache {
+                Some(cache) => {
+                    let contents = cache.get_contents(&store, &file_meta.location).await?;
+                    futures::stream::once(futures::future::ready(Ok(contents))).boxed()
                 }
-                GetResultPayload::Stream(s) => {
-                    let mut decoder = ReaderBuilder::new(schema)
-                        .with_batch_size(batch_size)
-                        .build_decoder()?;
-
-                    let mut input = s.map_err(Error::from);
-                    let mut buffered = Bytes::new();
-
-                    let s = futures::stream::poll_fn(move |cx| {
-                        loop {
-                            if buffered.is_empty() {
-                                buffered = match ready!(input.poll_next_unpin(cx)) {
-                                    Some(Ok(b)) => b,
-                                    Some(Err(e)) => return Poll::Ready(Some(Err(e))),
-                                    None => break,
-                                };
-                            }
-                            let read = buffered.len();
-
-                            let decoded = match decoder.decode(buffered.as_ref()) {
-                                Ok(decoded) => decoded,
-                                Err(e) => return Poll::Ready(Some(Err(e.into()))),
-                            };
-
-                            buffered.advance(decoded);
-                            if decoded != read {
-                                break;
-                            }
-                        }
-
-                        Poll::Ready(decoder.flush().map_err(Error::from).transpose())
-                    });
-                    Ok(s.map_err(Error::from).boxed())
+                None => {
+                    let path = Path::from_url_path(file_meta.location.path())?;
+                    store.get(&path).await?.into_stream().boxed()
                 }
-            }
+            };
+            let decoder = ReaderBuilder::new(schema)
+                .with_batch_size(batch_size)
+                .build_decoder()?;
+            Ok(decode_json_stream(
+                decoder,
+                input.map_err(Error::from).boxed(),
+                max_batch_bytes,
+            ))
         }))
     }
 }
 
+/// Decode the newline-delimited JSON of `input` incrementally, into batches of at most the batch
+/// size of the `decoder`. Batches are also flushed at the end of the first line which brings them
+/// over `max_batch_bytes` of JSON, so that the memory used by a batch stays bounded even if the
+/// lines are large (e.g. `add` actions with large statistics).
+fn decode_json_stream(
+    mut decoder: Decoder,
+    input: BoxStream<'static, DeltaResult<Bytes>>,
+    max_batch_bytes: usize,
+) -> BoxStream<'static, DeltaResult<RecordBatch>> {
+    let mut input = input.fuse();
+    let mut buffered = Bytes::new();
+    let mut batch_bytes = 0;
+    futures::stream::poll_fn(move |cx| loop {
+        // feed the decoder until its batch is full or the input is exhausted
+        let exhausted = loop {
+            if buffered.is_empty() {
+                buffered = match ready!(input.poll_next_unpin(cx)) {
+                    Some(Ok(b)) => b,
+                    Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                    None => break true,
+                };
+            }
+            // only feed the decoder up to the end of the line that exceeds the budget of the
+            // batch, so the batch can be flushed at the end of a record
+            let budget = max_batch_bytes.saturating_sub(batch_bytes);
+            let to_decode = match buffered.get(budget..) {
+                Some(rest) => rest
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map_or(buffered.len(), |i| budget + i + 1),
+                None => buffered.len(),
+            };
+            let decoded = match decoder.decode(&buffered[..to_decode]) {
+                Ok(decoded) => decoded,
+                Err(e) => return Poll::Ready(Some(Err(e.into()))),
+            };
+            let at_line_end = decoded > 0 && buffered[decoded - 1] == b'\n';
+            buffered.advance(decoded);
+            batch_bytes += decoded;
+            // the decoder stops early once it holds a full batch
+            if decoded < to_decode || (batch_bytes >= max_batch_bytes && at_line_end) {
+                break false;
+            }
+        };
+        batch_bytes = 0;
+        match decoder.flush() {
+            Ok(Some(batch)) => return Poll::Ready(Some(Ok(batch))),
+            Ok(None) if !exhausted => continue,
+            Ok(None) => return Poll::Ready(None),
+            Err(e) => return Poll::Ready(Some(Err(e.into()))),
+        }
+    })
+    .boxed()
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
 
-    use arrow::array::{AsArray, RecordBatch, StringArray};
+    use arrow::array::{AsArray, Int32Array, RecordBatch, StringArray};
+    use arrow::datatypes::Int32Type;
     use arrow_schema::{DataType, Field, Schema as ArrowSchema};
     use itertools::Itertools;
-    use object_store::{local::LocalFileSystem, ObjectStore};
+    use object_store::{local::LocalFileSystem, memory::InMemory, ObjectStore};
 
     use super::*;
     use crate::{
         actions::get_log_schema, engine::arrow_data::ArrowEngineData,
-        engine::default::executor::tokio::TokioBackgroundExecutor,
+        engine::default::executor::tokio::TokioBackgroundExecutor, schema,
     };
 
     fn string_array_to_engine_data(string_array: StringArray) -> Box<dyn EngineData> {
@@ -239,6 +423,38 @@ mod tests {
         assert_eq!(batch.len(), 4);
     }
 
+    #[test]
+    fn test_parse_json_selected() {
+        let store = Arc::new(LocalFileSystem::new());
+        let handler = DefaultJsonHandler::new(store, Arc::new(TokioBackgroundExecutor::new()));
+        let json_strings = || {
+            string_array_to_engine_data(StringArray::from(vec![
+                r#"{"a": 1}"#,
+                "not json",
+                r#"{"a": 3}"#,
+            ]))
+        };
+        let output_schema = Arc::new(schema::StructType::new([schema::StructField::new(
+            "a",
+            schema::DataType::INTEGER,
+            true,
+        )]));
+
+        assert!(handler
+            .parse_json(json_strings(), output_schema.clone())
+            .is_err());
+        // unselected strings aren't parsed, and rows missing from the selection vector are selected
+        let batch: RecordBatch = handler
+            .parse_json_selected(json_strings(), output_schema, &[true, false])
+            .unwrap()
+            .into_any()
+            .downcast::<ArrowEngineData>()
+            .map(|sd| sd.into())
+            .unwrap();
+        let a = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(a, &Int32Array::from(vec![Some(1), None, Some(3)]));
+    }
+
     #[test]
     fn test_parse_json_drop_field() {
         let store = Arc::new(LocalFileSystem::new());
@@ -303,4 +519,84 @@ mod tests {
         assert_eq!(data.len(), 1);
         assert_eq!(data[0].num_rows(), 4);
     }
+
+    #[tokio::test]
+    async fn test_decode_json_stream() {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "a",
+            DataType::Utf8,
+            true,
+        )]));
+        // ten lines of 10 bytes each, with one large line in the middle
+        let mut json = String::new();
+        for i in 0..10 {
+            match i {
+                5 => json.push_str(&format!(r#"{{"a":"{}"}}"#, "x".repeat(93))),
+                _ => json.push_str(&format!(r#"{{"a":"{i}"}}"#)),
+            }
+            json.push('\n');
+        }
+        let decode = |batch_size, max_batch_bytes, chunk_size| {
+            let chunks: Vec<_> = json
+                .as_bytes()
+                .chunks(chunk_size)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect();
+            let decoder = ReaderBuilder::new(schema.clone())
+                .with_batch_size(batch_size)
+                .build_decoder()
+                .unwrap();
+            decode_json_stream(
+                decoder,
+                futures::stream::iter(chunks).boxed(),
+                max_batch_bytes,
+            )
+            .map_ok(|batch| batch.num_rows())
+            .try_collect::<Vec<_>>()
+        };
+
+        // batches are bounded by rows, regardless of how the input is chunked
+        for chunk_size in [1, 7, 1000] {
+            assert_eq!(decode(4, 1000, chunk_size).await.unwrap(), [4, 4, 2]);
+        }
+        // batches end with the line which brings them over the byte budget
+        for chunk_size in [1, 7, 1000] {
+            assert_eq!(decode(1024, 25, chunk_size).await.unwrap(), [3, 3, 3, 1]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_json_files_with_metadata_cache() {
+        let store = Arc::new(InMemory::new());
+        let commit =
+            std::fs::read("./tests/data/table-with-dv-small/_delta_log/00000000000000000000.json")
+                .unwrap();
+        let path = Path::from("_delta_log/00000000000000000000.json");
+        let files = &[FileMeta {
+            location: Url::parse(&format!("memory:///{path}")).unwrap(),
+            last_modified: 0,
+            size: commit.len(),
+        }];
+        store.put(&path, commit.into()).await.unwrap();
+
+        let cache = MetadataCache::new(Default::default());
+        let handler =
+            DefaultJsonHandler::new(store.clone(), Arc::new(TokioBackgroundExecutor::new()))
+                .with_metadata_cache(cache.clone());
+        let read = || -> Vec<_> {
+            handler
+                .read_json_files(files, get_log_schema().clone(), None)
+                .unwrap()
+                .map_ok(|data| data.len())
+                .try_collect()
+                .unwrap()
+        };
+
+        assert_eq!(read(), [4]);
+        assert_eq!(cache.size(), files[0].size);
+
+        // the commit is read from the cache
+        store.delete(&path).await.unwrap();
+        assert_eq!(read(), [4]);
+    }
 }
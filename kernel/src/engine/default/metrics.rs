@@ -0,0 +1,398 @@
+//! Metrics and tracing of the storage operations of the default engine.
+//!
+//! The handlers of the [`DefaultEngine`] access storage through an [`InstrumentedObjectStore`],
+//! which runs every storage request in a tracing span (named `storage`, at debug level), and
+//! reports the requests, their latencies and the bytes they read and write to an
+//! [`EngineMetrics`], if one is configured. The retries of failed requests (see [retry]) are
+//! reported as well. Each attempt of a retried operation counts as a request of its own.
+//!
+//! [`StorageMetrics`] is an implementation of [`EngineMetrics`] which aggregates the metrics in
+//! memory, e.g. to be exported periodically. Operators who already have a metrics library can
+//! instead report into it by implementing [`EngineMetrics`] themselves.
+//!
+//! [`DefaultEngine`]: super::DefaultEngine
+//! [retry]: super::retry
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use tracing::{debug_span, Instrument};
+use web_time::Instant;
+
+use super::retry::StorageOperation;
+
+/// Receives the metrics of the storage operations of the default engine. All methods do nothing by
+/// default, so implementations only need to implement the metrics they are interested in.
+///
+/// The methods are called from the tasks of the engine, so they should be cheap and must not
+/// block.
+pub trait EngineMetrics: Debug + Send + Sync {
+    /// A request to storage completed (successfully or not) after `latency`.
+    fn request_completed(&self, operation: StorageOperation, latency: Duration, succeeded: bool) {
+        let _ = (operation, latency, succeeded);
+    }
+
+    /// A request of the `operation` read `bytes` bytes from storage.
+    fn bytes_read(&self, operation: StorageOperation, bytes: usize) {
+        let _ = (operation, bytes);
+    }
+
+    /// A request wrote `bytes` bytes to storage.
+    fn bytes_written(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    /// A failed request of the `operation` is retried.
+    fn request_retried(&self, operation: StorageOperation) {
+        let _ = operation;
+    }
+}
+
+/// The upper bounds of the buckets of the latency histograms of [`StorageMetrics`]. The last
+/// bucket (of latencies above the last bound) has no upper bound.
+pub const LATENCY_BUCKETS: [Duration; 6] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+];
+
+/// The metrics of the requests of one kind of storage operation, see [`StorageMetrics`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperationMetrics {
+    /// The number of requests issued, including the failed ones
+    pub requests: u64,
+    /// The number of requests which failed
+    pub failed_requests: u64,
+    /// The number of retries of failed requests
+    pub retries: u64,
+    /// The number of bytes read by the requests
+    pub bytes_read: u64,
+    /// The total latency of the requests
+    pub total_latency: Duration,
+    /// The number of requests whose latency falls into each of the [`LATENCY_BUCKETS`], plus
+    /// (at the end) the number of requests which took longer than all of them.
+    pub latency_histogram: [u64; LATENCY_BUCKETS.len() + 1],
+}
+
+/// An [`EngineMetrics`] which aggregates the metrics of the engine in memory.
+#[derive(Debug, Default)]
+pub struct StorageMetrics {
+    operations: Mutex<HashMap<StorageOperation, OperationMetrics>>,
+    bytes_written: Mutex<u64>,
+}
+
+impl StorageMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The metrics of the requests of the `operation`
+    pub fn operation(&self, operation: StorageOperation) -> OperationMetrics {
+        let operations = self.operations.lock().unwrap();
+        operations.get(&operation).cloned().unwrap_or_default()
+    }
+
+    /// The total number of bytes read from storage
+    pub fn bytes_read(&self) -> u64 {
+        let operations = self.operations.lock().unwrap();
+        operations.values().map(|metrics| metrics.bytes_read).sum()
+    }
+
+    /// The total number of bytes written to storage
+    pub fn bytes_written(&self) -> u64 {
+        *self.bytes_written.lock().unwrap()
+    }
+
+    fn update(&self, operation: StorageOperation, update: impl FnOnce(&mut OperationMetrics)) {
+        let mut operations = self.operations.lock().unwrap();
+        update(operations.entry(operation).or_default());
+    }
+}
+
+impl EngineMetrics for StorageMetrics {
+    fn request_completed(&self, operation: StorageOperation, latency: Duration, succeeded: bool) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.update(operation, |metrics| {
+            metrics.requests += 1;
+            metrics.failed_requests += u64::from(!succeeded);
+            metrics.total_latency += latency;
+            metrics.latency_histogram[bucket] += 1;
+        });
+    }
+
+    fn bytes_read(&self, operation: StorageOperation, bytes: usize) {
+        self.update(operation, |metrics| metrics.bytes_read += bytes as u64);
+    }
+
+    fn bytes_written(&self, bytes: usize) {
+        *self.bytes_written.lock().unwrap() += bytes as u64;
+    }
+
+    fn request_retried(&self, operation: StorageOperation) {
+        self.update(operation, |metrics| metrics.retries += 1);
+    }
+}
+
+/// An [`ObjectStore`] which traces the requests to another store, and reports their metrics to an
+/// [`EngineMetrics`] (if any).
+pub struct InstrumentedObjectStore {
+    inner: Arc<DynObjectStore>,
+    metrics: Option<Arc<dyn EngineMetrics>>,
+}
+
+impl InstrumentedObjectStore {
+    /// Trace the requests to `inner`, without reporting their metrics.
+    pub fn new(inner: Arc<DynObjectStore>) -> Self {
+        Self {
+            inner,
+            metrics: None,
+        }
+    }
+
+    /// Report the metrics of the requests to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn EngineMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// The store whose requests are instrumented
+    pub fn inner(&self) -> &Arc<DynObjectStore> {
+        &self.inner
+    }
+
+    // Run the request in a span, and report its latency, and the bytes it read
+    async fn instrument<T>(
+        &self,
+        operation: StorageOperation,
+        location: &Path,
+        request: impl Future<Output = Result<T>>,
+        bytes_read: impl FnOnce(&T) -> usize,
+    ) -> Result<T> {
+        let span = debug_span!("storage", operation = ?operation, location = %location);
+        let start = Instant::now();
+        let result = request.instrument(span).await;
+        if let Some(metrics) = &self.metrics {
+            metrics.request_completed(operation, start.elapsed(), result.is_ok());
+            if let Ok(result) = &result {
+                let bytes = bytes_read(result);
+                if bytes > 0 {
+                    metrics.bytes_read(operation, bytes);
+                }
+            }
+        }
+        result
+    }
+
+    // A listing is a single request, which completes once all of its files are listed (or it
+    // fails)
+    fn instrument_list<'a>(
+        &'a self,
+        prefix: Option<&Path>,
+        list: BoxStream<'a, Result<ObjectMeta>>,
+    ) -> BoxStream<'a, Result<ObjectMeta>> {
+        let prefix = prefix.map(ToString::to_string).unwrap_or_default();
+        let span = debug_span!("storage", operation = ?StorageOperation::List, location = %prefix);
+        let state = Some((list, Instant::now(), span));
+        futures::stream::unfold(state, move |state| async move {
+            let (mut list, start, span) = state?;
+            let next = list.next().instrument(span.clone()).await;
+            let (done, succeeded) = match &next {
+                None => (true, true),
+                Some(Err(_)) => (true, false),
+                Some(Ok(_)) => (false, true),
+            };
+            if let (true, Some(metrics)) = (done, &self.metrics) {
+                metrics.request_completed(StorageOperation::List, start.elapsed(), succeeded);
+            }
+            let state = (!done).then_some((list, start, span));
+            next.map(|next| (next, state))
+        })
+        .boxed()
+    }
+}
+
+impl Debug for InstrumentedObjectStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstrumentedObjectStore")
+            .field("inner", &self.inner)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+// Displays as the inner store, which identifies the kind of store (e.g. a `LocalFileSystem`)
+impl Display for InstrumentedObjectStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InstrumentedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let bytes = payload.content_length();
+        let put = self.inner.put_opts(location, payload, opts);
+        let result = self
+            .instrument(StorageOperation::Put, location, put, |_| 0)
+            .await;
+        if let (Ok(_), Some(metrics)) = (&result, &self.metrics) {
+            metrics.bytes_written(bytes);
+        }
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let put = self.inner.put_multipart_opts(location, opts);
+        self.instrument(StorageOperation::Put, location, put, |_| 0)
+            .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let get = self.inner.get_opts(location, options);
+        self.instrument(StorageOperation::Get, location, get, |result| {
+            result.range.len()
+        })
+        .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let get = self.inner.get_range(location, range);
+        self.instrument(StorageOperation::Get, location, get, Bytes::len)
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let get = self.inner.get_ranges(location, ranges);
+        self.instrument(StorageOperation::Get, location, get, |ranges| {
+            ranges.iter().map(Bytes::len).sum()
+        })
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let head = self.inner.head(location);
+        self.instrument(StorageOperation::Head, location, head, |_| 0)
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let delete = self.inner.delete(location);
+        self.instrument(StorageOperation::Delete, location, delete, |_| 0)
+            .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.instrument_list(prefix, self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.instrument_list(prefix, self.inner.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let list = self.inner.list_with_delimiter(prefix);
+        let location = prefix.cloned().unwrap_or_default();
+        self.instrument(StorageOperation::List, &location, list, |_| 0)
+            .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let copy = self.inner.copy(from, to);
+        self.instrument(StorageOperation::Copy, from, copy, |_| 0)
+            .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let rename = self.inner.rename(from, to);
+        self.instrument(StorageOperation::Copy, from, rename, |_| 0)
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let copy = self.inner.copy_if_not_exists(from, to);
+        self.instrument(StorageOperation::Copy, from, copy, |_| 0)
+            .await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let rename = self.inner.rename_if_not_exists(from, to);
+        self.instrument(StorageOperation::Copy, from, rename, |_| 0)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_storage_metrics() {
+        let metrics = Arc::new(StorageMetrics::new());
+        let store =
+            InstrumentedObjectStore::new(Arc::new(InMemory::new())).with_metrics(metrics.clone());
+
+        let path = Path::from("a/b");
+        store.put(&path, "kernel-data".into()).await.unwrap();
+        store.get_range(&path, 0..6).await.unwrap();
+        store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert!(store.head(&Path::from("a/c")).await.is_err());
+        let files: Vec<_> = store
+            .list(Some(&Path::from("a")))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+
+        assert_eq!(metrics.bytes_written(), 11);
+        assert_eq!(metrics.bytes_read(), 17);
+        let get = metrics.operation(StorageOperation::Get);
+        assert_eq!(
+            (get.requests, get.failed_requests, get.bytes_read),
+            (2, 0, 17)
+        );
+        assert_eq!(get.latency_histogram.iter().sum::<u64>(), 2);
+        let head = metrics.operation(StorageOperation::Head);
+        assert_eq!((head.requests, head.failed_requests), (1, 1));
+        assert_eq!(metrics.operation(StorageOperation::List).requests, 1);
+        assert_eq!(metrics.operation(StorageOperation::Put).requests, 1);
+        assert_eq!(
+            metrics.operation(StorageOperation::Delete),
+            Default::default()
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Memory-mapped reads of local files.
+//!
+//! The default engine reads files through its object store, which copies them into buffers of its
+//! own. For tables with `file://` URLs, it can instead map the parquet files (data files and
+//! checkpoints) and the files read by [`FileSystemClient::read_files`] into memory, so that their
+//! pages are shared with the page cache of the OS rather than buffered twice. This mostly helps
+//! engines which embed the kernel to analyze large local tables. See
+//! [`DefaultEngineOptions::with_mmap`].
+//!
+//! A mapped file must not be modified while it's read. The data and log files of a Delta table are
+//! never rewritten in place, but they may be deleted (e.g. by `VACUUM`), which leaves the mapping
+//! valid on unix.
+//!
+//! [`FileSystemClient::read_files`]: crate::FileSystemClient::read_files
+//! [`DefaultEngineOptions::with_mmap`]: super::DefaultEngineOptions::with_mmap
+
+use std::fs::File;
+use std::ops::Range;
+
+use bytes::Bytes;
+use memmap2::Mmap;
+use url::Url;
+
+use crate::{DeltaResult, Error};
+
+/// Map the local file at `url` into memory, and return its `range` (or all of it). The bytes are
+/// read from the file as they're accessed, and the file stays mapped until they're all dropped.
+pub(crate) fn read_mapped(url: &Url, range: Option<Range<usize>>) -> DeltaResult<Bytes> {
+    let path = url
+        .to_file_path()
+        .map_err(|()| Error::generic(format!("Not a local file: {url}")))?;
+    let file = File::open(&path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => Error::file_not_found(path.display()),
+        _ => err.into(),
+    })?;
+    // SAFETY: the files of a Delta table are never modified in place (see the module docs), so the
+    // mapped bytes don't change while they're read
+    let mmap = unsafe { Mmap::map(&file)? };
+    let bytes = Bytes::from_owner(mmap);
+    match range {
+        Some(range) if range.end > bytes.len() => Err(Error::generic(format!(
+            "Range {range:?} is out of bounds of {url}, which has {} bytes",
+            bytes.len()
+        ))),
+        Some(range) => Ok(bytes.slice(range)),
+        None => Ok(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"hello world").unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        assert_eq!(read_mapped(&url, None).unwrap(), "hello world");
+        assert_eq!(read_mapped(&url, Some(6..11)).unwrap(), "world");
+        assert!(read_mapped(&url, Some(6..12)).is_err());
+
+        let missing = Url::from_file_path(dir.path().join("missing")).unwrap();
+        assert!(matches!(
+            read_mapped(&missing, None),
+            Err(Error::FileNotFound(_))
+        ));
+    }
+}
@@ -5,41 +5,206 @@
 //! The underlying implementations use asynchronous IO. Async tasks are run on
 //! a separate thread pool, provided by the [`TaskExecutor`] trait. Read more in
 //! the [executor] module.
+//!
+//! With the `default-engine-base` feature (rather than `default-engine`), the engine is built
+//! without tokio and reqwest, so that it can be built for wasm32-unknown-unknown. The application
+//! then provides the [`TaskExecutor`] (e.g. one running its futures on the JS event loop) and the
+//! object store (e.g. one making requests with `fetch`), and pre-signed HTTP(S) URLs are read
+//! through the object store.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use self::storage::parse_url_opts;
+use self::storage::{parse_url_opts, ExternalStores};
+use ::parquet::file::properties::WriterProperties;
 use object_store::{path::Path, DynObjectStore};
 use url::Url;
 
+use self::cache::{CacheConfig, MetadataCache};
 use self::executor::TaskExecutor;
 use self::filesystem::ObjectStoreFileSystemClient;
 use self::json::DefaultJsonHandler;
+use self::metrics::{EngineMetrics, InstrumentedObjectStore};
 use self::parquet::DefaultParquetHandler;
+use self::retry::{Retrier, RetryConfig, RetryingObjectStore};
+use self::upload::MultipartUploadConfig;
 use super::arrow_data::ArrowEngineData;
 use super::arrow_expression::ArrowExpressionHandler;
-use crate::schema::Schema;
+use super::data_file::{logical_to_physical, physical_partition_values};
+use super::memory::{MemoryLimitedExpressionHandler, MemoryPool};
+use super::partitioned_write::split_by_partition;
 use crate::transaction::WriteContext;
 use crate::{
     DeltaResult, Engine, EngineData, ExpressionHandler, FileSystemClient, JsonHandler,
     ParquetHandler,
 };
 
+pub mod async_engine;
+pub mod cache;
 pub mod executor;
 pub mod file_stream;
 pub mod filesystem;
 pub mod json;
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod parquet;
+#[cfg(feature = "reqwest")]
+pub mod presigned;
+pub mod retry;
 pub mod storage;
+pub mod upload;
+
+/// Options of a [`DefaultEngine`], see [`DefaultEngine::new_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct DefaultEngineOptions {
+    retry_config: RetryConfig,
+    metadata_cache: Option<CacheConfig>,
+    max_concurrent_parquet_reads: Option<usize>,
+    max_concurrent_parquet_requests: Option<usize>,
+    memory_pool: Option<MemoryPool>,
+    parquet_writer_properties: Option<WriterProperties>,
+    metrics: Option<Arc<dyn EngineMetrics>>,
+    multipart_upload: Option<MultipartUploadConfig>,
+    view_types: bool,
+    external_stores: ExternalStores,
+    #[cfg(feature = "mmap")]
+    mmap: bool,
+}
+
+impl DefaultEngineOptions {
+    /// Set which failed storage operations are retried, and how. See [retry].
+    ///
+    /// Defaults to [`RetryConfig::default`]. Use [`RetryConfig::never`] to disable retries.
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Cache commit files, checkpoint parts and parquet footers in memory, within the bounds of
+    /// `config`. See [cache].
+    ///
+    /// Defaults to no caching.
+    pub fn with_metadata_cache(mut self, config: CacheConfig) -> Self {
+        self.metadata_cache = Some(config);
+        self
+    }
+
+    /// Set the maximum number of parquet files read concurrently by a scan. See
+    /// [`DefaultParquetHandler::with_max_concurrent_reads`].
+    ///
+    /// Defaults to 10.
+    pub fn with_max_concurrent_parquet_reads(mut self, max_concurrent_reads: usize) -> Self {
+        self.max_concurrent_parquet_reads = Some(max_concurrent_reads);
+        self
+    }
+
+    /// Limit the number of concurrent requests to storage for reading and writing parquet files.
+    /// See [`DefaultParquetHandler::with_max_concurrent_requests`].
+    ///
+    /// Defaults to no limit.
+    pub fn with_max_concurrent_parquet_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_parquet_requests = Some(max_concurrent_requests);
+        self
+    }
+
+    /// Reserve the memory of the data read, parsed and computed by the engine in `memory_pool`,
+    /// failing with [`Error::MemoryLimitExceeded`] once it's exhausted. See [memory].
+    ///
+    /// Defaults to no limit.
+    ///
+    /// [`Error::MemoryLimitExceeded`]: crate::Error::MemoryLimitExceeded
+    /// [memory]: crate::engine::memory
+    pub fn with_memory_pool(mut self, memory_pool: MemoryPool) -> Self {
+        self.memory_pool = Some(memory_pool);
+        self
+    }
+
+    /// Write parquet files (e.g. the data files of the table) with the given writer `properties`.
+    /// See [`DefaultParquetHandler::with_writer_properties`].
+    ///
+    /// Defaults to the default properties of the parquet writer.
+    pub fn with_parquet_writer_properties(mut self, properties: WriterProperties) -> Self {
+        self.parquet_writer_properties = Some(properties);
+        self
+    }
+
+    /// Report the requests to storage, their latencies, retries and the bytes they read and write
+    /// to `metrics`, and trace each request in a span. See [metrics].
+    ///
+    /// Defaults to no metrics (the requests are still traced).
+    pub fn with_metrics(mut self, metrics: Arc<dyn EngineMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Upload large parquet files (e.g. data files and checkpoints) in parts, as configured by
+    /// `config`. See [upload].
+    ///
+    /// Defaults to [`MultipartUploadConfig::default`].
+    pub fn with_multipart_upload(mut self, config: MultipartUploadConfig) -> Self {
+        self.multipart_upload = Some(config);
+        self
+    }
+
+    /// Read and compute strings and binaries as `Utf8View` and `BinaryView` arrays, for engines
+    /// whose arrays are views (e.g. DataFusion and DuckDB). See
+    /// [`DefaultParquetHandler::with_view_types`] and [`ArrowExpressionHandler::with_view_types`].
+    ///
+    /// Defaults to false.
+    pub fn with_view_types(mut self, view_types: bool) -> Self {
+        self.view_types = view_types;
+        self
+    }
+
+    /// Access the files of the table stored in the storage of `url` (e.g. `s3://source-bucket/`),
+    /// outside of the storage of the table, with `store`. Shallow clones reference the files of
+    /// their source table, which may be in another bucket (with other credentials) or file system.
+    /// The requests to the store are retried and instrumented like those to the store of the
+    /// table. See [`Scan::external_storage`].
+    ///
+    /// Defaults to accessing all files with the store of the table.
+    ///
+    /// [`Scan::external_storage`]: crate::scan::Scan::external_storage
+    pub fn with_external_store(mut self, url: &Url, store: Arc<DynObjectStore>) -> Self {
+        self.external_stores.insert(url, store);
+        self
+    }
+
+    /// The URL of the table (e.g. `s3://bucket/table/`), which tells the files in the storage of
+    /// the table apart from those in external storage (see [`Self::with_external_store`]).
+    /// Accessing a file in external storage without a store then fails, rather than accessing
+    /// the file at the same path in the storage of the table. [`DefaultEngine::try_new`] sets it.
+    ///
+    /// Defaults to accessing the files in external storage without a store with the store of the
+    /// table.
+    pub fn with_table_root(mut self, table_root: &Url) -> Self {
+        self.external_stores.set_table_root(table_root);
+        self
+    }
+
+    /// Memory-map the parquet files and the files read by the [`FileSystemClient`] of tables with
+    /// `file://` URLs, rather than reading them through the object store (so those reads aren't
+    /// retried or instrumented). See [mmap], [`DefaultParquetHandler::with_mmap`] and
+    /// [`ObjectStoreFileSystemClient::with_mmap`].
+    ///
+    /// Defaults to false.
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
+}
 
 #[derive(Debug)]
 pub struct DefaultEngine<E: TaskExecutor> {
     store: Arc<DynObjectStore>,
+    external_stores: ExternalStores,
     file_system: Arc<ObjectStoreFileSystemClient<E>>,
     json: Arc<DefaultJsonHandler<E>>,
     parquet: Arc<DefaultParquetHandler<E>>,
     expression: Arc<ArrowExpressionHandler>,
+    memory_pool: Option<MemoryPool>,
 }
 
 impl<E: TaskExecutor> DefaultEngine<E> {
@@ -60,11 +225,17 @@ impl<E: TaskExecutor> DefaultEngine<E> {
         V: Into<String>,
     {
         // table root is the path of the table in the ObjectStore
+        let engine_options = DefaultEngineOptions::default().with_table_root(table_root);
         let (store, table_root) = parse_url_opts(table_root, options)?;
-        Ok(Self::new(Arc::new(store), table_root, task_executor))
+        Ok(Self::new_with_options(
+            Arc::new(store),
+            table_root,
+            task_executor,
+            engine_options,
+        ))
     }
 
-    /// Create a new [`DefaultEngine`] instance
+    /// Create a new [`DefaultEngine`] instance with the default [`DefaultEngineOptions`].
     ///
     /// # Parameters
     ///
@@ -72,6 +243,43 @@ impl<E: TaskExecutor> DefaultEngine<E> {
     /// - `table_root_path`: The root path of the table within storage.
     /// - `task_executor`: Used to spawn async IO tasks. See [executor::TaskExecutor].
     pub fn new(store: Arc<DynObjectStore>, table_root: Path, task_executor: Arc<E>) -> Self {
+        Self::new_with_options(store, table_root, task_executor, Default::default())
+    }
+
+    /// Create a new [`DefaultEngine`] instance, which retries failed storage operations according
+    /// to `retry_config`. Use [`RetryConfig::never`] to disable retries.
+    ///
+    /// # Parameters
+    ///
+    /// - `store`: The object store to use.
+    /// - `table_root_path`: The root path of the table within storage.
+    /// - `task_executor`: Used to spawn async IO tasks. See [executor::TaskExecutor].
+    /// - `retry_config`: Which failed storage operations are retried, and how. See [retry].
+    pub fn new_with_retry_config(
+        store: Arc<DynObjectStore>,
+        table_root: Path,
+        task_executor: Arc<E>,
+        retry_config: RetryConfig,
+    ) -> Self {
+        let options = DefaultEngineOptions::default().with_retry_config(retry_config);
+        Self::new_with_options(store, table_root, task_executor, options)
+    }
+
+    /// Create a new [`DefaultEngine`] instance
+    ///
+    /// # Parameters
+    ///
+    /// - `store`: The object store to use.
+    /// - `table_root_path`: The root path of the table within storage.
+    /// - `task_executor`: Used to spawn async IO tasks. See [executor::TaskExecutor].
+    /// - `options`: How storage operations are retried, cached and parallelized, and how much
+    ///   memory the engine may use. See [`DefaultEngineOptions`].
+    pub fn new_with_options(
+        store: Arc<DynObjectStore>,
+        table_root: Path,
+        task_executor: Arc<E>,
+        options: DefaultEngineOptions,
+    ) -> Self {
         // HACK to check if we're using a LocalFileSystem from ObjectStore. We need this because
         // local filesystem doesn't return a sorted list by default. Although the `object_store`
         // crate explicitly says it _does not_ return a sorted listing, in practice all the cloud
@@ -93,25 +301,86 @@ impl<E: TaskExecutor> DefaultEngine<E> {
         // `filesystem.rs`
         let store_str = format!("{}", store);
         let is_local = store_str.starts_with("LocalFileSystem");
+        let mut retrier = Retrier::new(options.retry_config.clone(), task_executor.clone());
+        if let Some(metrics) = &options.metrics {
+            retrier = retrier.with_metrics(metrics.clone());
+        }
+        // instrument and retry the requests to a store
+        let wrap = |store: Arc<DynObjectStore>| -> Arc<DynObjectStore> {
+            let mut store = InstrumentedObjectStore::new(store);
+            if let Some(metrics) = &options.metrics {
+                store = store.with_metrics(metrics.clone());
+            }
+            Arc::new(RetryingObjectStore::with_retrier(
+                Arc::new(store),
+                retrier.clone(),
+            ))
+        };
+        let store = wrap(store);
+        let external_stores = options.external_stores.clone().map(wrap);
+        let mut json = DefaultJsonHandler::new(store.clone(), task_executor.clone());
+        let mut parquet = DefaultParquetHandler::new(store.clone(), task_executor.clone())
+            .with_external_stores(external_stores.clone())
+            .with_retrier(retrier);
+        if let Some(config) = options.metadata_cache {
+            let cache = MetadataCache::new(config);
+            json = json.with_metadata_cache(cache.clone());
+            parquet = parquet.with_metadata_cache(cache);
+        }
+        if let Some(max_concurrent_reads) = options.max_concurrent_parquet_reads {
+            parquet = parquet.with_max_concurrent_reads(max_concurrent_reads);
+        }
+        if let Some(max_concurrent_requests) = options.max_concurrent_parquet_requests {
+            parquet = parquet.with_max_concurrent_requests(max_concurrent_requests);
+        }
+        if let Some(memory_pool) = &options.memory_pool {
+            json = json.with_memory_pool(memory_pool.clone());
+            parquet = parquet.with_memory_pool(memory_pool.clone());
+        }
+        if let Some(properties) = options.parquet_writer_properties {
+            parquet = parquet.with_writer_properties(properties);
+        }
+        if let Some(config) = options.multipart_upload {
+            parquet = parquet.with_multipart_upload(config);
+        }
+        parquet = parquet.with_view_types(options.view_types);
+        let file_system =
+            ObjectStoreFileSystemClient::new(store.clone(), !is_local, table_root, task_executor)
+                .with_external_stores(external_stores.clone());
+        #[cfg(feature = "mmap")]
+        let (parquet, file_system) = (
+            parquet.with_mmap(options.mmap),
+            file_system.with_mmap(options.mmap),
+        );
         Self {
-            file_system: Arc::new(ObjectStoreFileSystemClient::new(
-                store.clone(),
-                !is_local,
-                table_root,
-                task_executor.clone(),
-            )),
-            json: Arc::new(DefaultJsonHandler::new(
-                store.clone(),
-                task_executor.clone(),
-            )),
-            parquet: Arc::new(DefaultParquetHandler::new(store.clone(), task_executor)),
+            file_system: Arc::new(file_system),
+            json: Arc::new(json),
+            parquet: Arc::new(parquet),
             store,
-            expression: Arc::new(ArrowExpressionHandler {}),
+            external_stores,
+            expression: Arc::new(ArrowExpressionHandler::new().with_view_types(options.view_types)),
+            memory_pool: options.memory_pool,
+        }
+    }
+
+    // The expression handler, which reserves the memory of its results in the memory pool of the
+    // engine (if any)
+    pub(crate) fn expression_handler(&self) -> Arc<dyn ExpressionHandler> {
+        match &self.memory_pool {
+            Some(memory_pool) => Arc::new(MemoryLimitedExpressionHandler::new(
+                self.expression.clone(),
+                memory_pool.clone(),
+            )),
+            None => self.expression.clone(),
         }
     }
 
-    pub fn get_object_store_for_url(&self, _url: &Url) -> Option<Arc<DynObjectStore>> {
-        Some(self.store.clone())
+    /// The object store of the file at `url`: the store of its external storage (see
+    /// [`DefaultEngineOptions::with_external_store`]), or else the store of the table. None if
+    /// the file is in external storage without a store (see
+    /// [`DefaultEngineOptions::with_table_root`]).
+    pub fn get_object_store_for_url(&self, url: &Url) -> Option<Arc<DynObjectStore>> {
+        self.external_stores.store_for(url, &self.store).ok()
     }
 
     pub async fn write_parquet(
@@ -121,19 +390,64 @@ impl<E: TaskExecutor> DefaultEngine<E> {
         partition_values: HashMap<String, String>,
         data_change: bool,
     ) -> DeltaResult<Box<dyn EngineData>> {
-        let transform = write_context.logical_to_physical();
-        let input_schema: Schema = data.record_batch().schema().try_into()?;
-        let output_schema = write_context.schema();
-        let logical_to_physical_expr = self.get_expression_handler().get_evaluator(
-            input_schema.into(),
-            transform.clone(),
-            output_schema.clone().into(),
-        );
-        let physical_data = logical_to_physical_expr.evaluate(data)?;
+        self.write_parquet_to(
+            data,
+            write_context,
+            write_context.target_dir(),
+            partition_values,
+            data_change,
+        )
+        .await
+    }
+
+    /// Write `data`, which includes the partition columns of the table, as one parquet file per
+    /// partition in the partition's directory (e.g. `date=2024-01-01/`). Returns the write
+    /// metadata of each file, with its serialized partition values. See
+    /// [`split_by_partition`] for how the data is split.
+    pub async fn write_partitioned_parquet(
+        &self,
+        data: &ArrowEngineData,
+        write_context: &WriteContext,
+        data_change: bool,
+    ) -> DeltaResult<Vec<Box<dyn EngineData>>> {
+        let partitions =
+            split_by_partition(data.record_batch(), write_context.partition_columns())?;
+        let mut write_metadata = Vec::with_capacity(partitions.len());
+        for partition in partitions {
+            let target_dir = partition.target_dir(write_context.target_dir())?;
+            let partition_values =
+                physical_partition_values(write_context, partition.partition_values)?;
+            let data = ArrowEngineData::new(partition.data);
+            write_metadata.push(
+                self.write_parquet_to(
+                    &data,
+                    write_context,
+                    &target_dir,
+                    partition_values,
+                    data_change,
+                )
+                .await?,
+            );
+        }
+        Ok(write_metadata)
+    }
+
+    // Write the (logical) `data` as a parquet file in `target_dir`
+    async fn write_parquet_to(
+        &self,
+        data: &ArrowEngineData,
+        write_context: &WriteContext,
+        target_dir: &Url,
+        partition_values: HashMap<String, String>,
+        data_change: bool,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let physical_data =
+            logical_to_physical(self.expression_handler().as_ref(), data, write_context)?;
         self.parquet
-            .write_parquet_file(
-                write_context.target_dir(),
+            .write_parquet_file_with_stats(
+                target_dir,
                 physical_data,
+                write_context.stats_columns(),
                 partition_values,
                 data_change,
             )
@@ -143,7 +457,7 @@ impl<E: TaskExecutor> DefaultEngine<E> {
 
 impl<E: TaskExecutor> Engine for DefaultEngine<E> {
     fn get_expression_handler(&self) -> Arc<dyn ExpressionHandler> {
-        self.expression.clone()
+        self.expression_handler()
     }
 
     fn get_file_system_client(&self) -> Arc<dyn FileSystemClient> {
@@ -1,103 +1,85 @@
 //! Default Parquet handler implementation
 
 use std::collections::HashMap;
+use std::io::Cursor;
 use std::ops::Range;
 use std::sync::Arc;
 
-use arrow_array::builder::{MapBuilder, MapFieldNames, StringBuilder};
-use arrow_array::{BooleanArray, Int64Array, RecordBatch, StringArray};
+#[cfg(any(feature = "reqwest", feature = "mmap"))]
+use arrow_array::RecordBatch;
+use futures::future::BoxFuture;
+#[cfg(any(feature = "reqwest", feature = "mmap"))]
+use futures::stream::BoxStream;
 use futures::StreamExt;
+use object_store::limit::LimitStore;
 use object_store::path::Path;
 use object_store::DynObjectStore;
-use parquet::arrow::arrow_reader::{
-    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder,
+#[cfg(any(feature = "reqwest", feature = "mmap"))]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::arrow::async_reader::{
+    AsyncFileReader, ParquetObjectReader, ParquetRecordBatchStreamBuilder,
 };
-use parquet::arrow::arrow_writer::ArrowWriter;
-use parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStreamBuilder};
-use uuid::Uuid;
+use parquet::file::properties::WriterProperties;
+use url::Url;
 
+use super::cache::MetadataCache;
 use super::file_stream::{FileOpenFuture, FileOpener, FileStream};
-use crate::engine::arrow_data::ArrowEngineData;
-use crate::engine::arrow_utils::{generate_mask, get_requested_indices, reorder_struct_array};
+use super::retry::Retrier;
+use super::storage::ExternalStores;
+use super::upload::MultipartUploadConfig;
+use crate::async_engine::{self, FileDataReadResultStream};
+use crate::engine::arrow_utils::{
+    generate_mask, get_requested_indices, reorder_struct_array, to_parquet_bytes, to_view_schema,
+};
+pub use crate::engine::data_file::DataFileMetadata;
+use crate::engine::data_file::{encode_parquet, new_data_file_name};
 use crate::engine::default::executor::TaskExecutor;
+use crate::engine::memory::MemoryPool;
+use crate::engine::parquet_row_filter::ParquetRowFilter;
 use crate::engine::parquet_row_group_skipping::ParquetRowGroupSkipping;
+use crate::expressions::ColumnName;
 use crate::schema::SchemaRef;
 use crate::{
     DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
-    ParquetHandler,
+    FilteredEngineData, ParquetHandler,
 };
 
 #[derive(Debug)]
 pub struct DefaultParquetHandler<E: TaskExecutor> {
     store: Arc<DynObjectStore>,
+    external_stores: ExternalStores,
     task_executor: Arc<E>,
     readahead: usize,
-}
-
-/// Metadata of a data file (typically a parquet file), currently just includes the file metadata
-/// but will expand to include file statistics and other metadata in the future.
-#[derive(Debug)]
-pub struct DataFileMetadata {
-    file_meta: FileMeta,
-}
-
-impl DataFileMetadata {
-    pub fn new(file_meta: FileMeta) -> Self {
-        Self { file_meta }
-    }
-
-    // convert DataFileMetadata into a record batch which matches the 'write_metadata' schema
-    fn as_record_batch(
-        &self,
-        partition_values: &HashMap<String, String>,
-        data_change: bool,
-    ) -> DeltaResult<Box<dyn EngineData>> {
-        let DataFileMetadata {
-            file_meta:
-                FileMeta {
-                    location,
-                    last_modified,
-                    size,
-                },
-        } = self;
-        let write_metadata_schema = crate::transaction::get_write_metadata_schema();
-
-        // create the record batch of the write metadata
-        let path = Arc::new(StringArray::from(vec![location.to_string()]));
-        let key_builder = StringBuilder::new();
-        let val_builder = StringBuilder::new();
-        let names = MapFieldNames {
-            entry: "key_value".to_string(),
-            key: "key".to_string(),
-            value: "value".to_string(),
-        };
-        let mut builder = MapBuilder::new(Some(names), key_builder, val_builder);
-        for (k, v) in partition_values {
-            builder.keys().append_value(k);
-            builder.values().append_value(v);
-        }
-        builder.append(true).unwrap();
-        let partitions = Arc::new(builder.finish());
-        // this means max size we can write is i64::MAX (~8EB)
-        let size: i64 = (*size)
-            .try_into()
-            .map_err(|_| Error::generic("Failed to convert parquet metadata 'size' to i64"))?;
-        let size = Arc::new(Int64Array::from(vec![size]));
-        let data_change = Arc::new(BooleanArray::from(vec![data_change]));
-        let modification_time = Arc::new(Int64Array::from(vec![*last_modified]));
-        Ok(Box::new(ArrowEngineData::new(RecordBatch::try_new(
-            Arc::new(write_metadata_schema.as_ref().try_into()?),
-            vec![path, partitions, size, modification_time, data_change],
-        )?)))
-    }
+    max_concurrent_reads: usize,
+    file_readahead: usize,
+    metadata_cache: Option<MetadataCache>,
+    memory_pool: Option<MemoryPool>,
+    writer_properties: Option<WriterProperties>,
+    multipart_upload: MultipartUploadConfig,
+    retrier: Option<Retrier>,
+    view_types: bool,
+    #[cfg(feature = "mmap")]
+    mmap: bool,
 }
 
 impl<E: TaskExecutor> DefaultParquetHandler<E> {
     pub fn new(store: Arc<DynObjectStore>, task_executor: Arc<E>) -> Self {
         Self {
             store,
+            external_stores: ExternalStores::default(),
             task_executor,
             readahead: 10,
+            max_concurrent_reads: 10,
+            file_readahead: 2,
+            metadata_cache: None,
+            memory_pool: None,
+            writer_properties: None,
+            multipart_upload: MultipartUploadConfig::default(),
+            retrier: None,
+            view_types: false,
+            #[cfg(feature = "mmap")]
+            mmap: false,
         }
     }
 
@@ -109,26 +91,121 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         self
     }
 
+    /// Max number of files to read concurrently while executing [Self::read_parquet_files()].
+    /// Batches are still returned in the order of the requested files. See also
+    /// [Self::with_file_readahead()].
+    ///
+    /// Defaults to 10.
+    pub fn with_max_concurrent_reads(mut self, max_concurrent_reads: usize) -> Self {
+        self.max_concurrent_reads = max_concurrent_reads;
+        self
+    }
+
+    /// Max number of batches to read ahead from each of the files read concurrently with the
+    /// file whose batches are currently returned by [Self::read_parquet_files()]. If 0, these
+    /// files are only opened (i.e. their footers are read) concurrently.
+    ///
+    /// Defaults to 2.
+    pub fn with_file_readahead(mut self, file_readahead: usize) -> Self {
+        self.file_readahead = file_readahead;
+        self
+    }
+
+    // Read the files in external storage with its store
+    pub(crate) fn with_external_stores(mut self, external_stores: ExternalStores) -> Self {
+        self.external_stores = external_stores;
+        self
+    }
+
+    // Retry failed multipart uploads (the requests to the store are retried by the store itself)
+    pub(crate) fn with_retrier(mut self, retrier: Retrier) -> Self {
+        self.retrier = Some(retrier);
+        self
+    }
+
+    /// Max number of concurrent requests to the object store by this handler, across all reads
+    /// and writes. Values smaller than 1 are treated as 1.
+    ///
+    /// Defaults to no limit.
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        let max_concurrent_requests = max_concurrent_requests.max(1);
+        self.store = Arc::new(LimitStore::new(self.store, max_concurrent_requests));
+        self
+    }
+
+    /// Cache the footers of the parquet files and the contents of the checkpoint parts read by
+    /// [Self::read_parquet_files()] in `metadata_cache`.
+    ///
+    /// Defaults to no caching.
+    pub fn with_metadata_cache(mut self, metadata_cache: MetadataCache) -> Self {
+        self.metadata_cache = Some(metadata_cache);
+        self
+    }
+
+    /// Reserve the memory of the data read by [Self::read_parquet_files()] in `memory_pool`,
+    /// failing once it's exhausted.
+    ///
+    /// Defaults to no limit.
+    pub fn with_memory_pool(mut self, memory_pool: MemoryPool) -> Self {
+        self.memory_pool = Some(memory_pool);
+        self
+    }
+
+    /// Write parquet files with the given writer `properties`, e.g. to choose their compression
+    /// codec, dictionary encoding, row group size and the level of their column statistics.
+    ///
+    /// Defaults to the default properties of the parquet writer.
+    pub fn with_writer_properties(mut self, writer_properties: WriterProperties) -> Self {
+        self.writer_properties = Some(writer_properties);
+        self
+    }
+
+    /// Upload parquet files larger than the part size of `config` in parts. See [upload].
+    ///
+    /// Defaults to [`MultipartUploadConfig::default`].
+    ///
+    /// [upload]: super::upload
+    pub fn with_multipart_upload(mut self, config: MultipartUploadConfig) -> Self {
+        self.multipart_upload = config;
+        self
+    }
+
+    /// Read the strings and binaries of the parquet files as `Utf8View` and `BinaryView` arrays
+    /// (rather than `Utf8` and `Binary` arrays) in [Self::read_parquet_files()], which spares
+    /// engines whose arrays are views (e.g. DataFusion and DuckDB) from copying them.
+    ///
+    /// Defaults to false.
+    pub fn with_view_types(mut self, view_types: bool) -> Self {
+        self.view_types = view_types;
+        self
+    }
+
+    /// Memory-map local (`file://`) parquet files rather than reading them through the object
+    /// store in [Self::read_parquet_files()]. See [mmap].
+    ///
+    /// Defaults to false.
+    ///
+    /// [mmap]: super::mmap
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
+
     // Write `data` to `{path}/<uuid>.parquet` as parquet using ArrowWriter and return the parquet
-    // metadata (where `<uuid>` is a generated UUIDv4).
+    // metadata, with the statistics of the `stats_columns` (where `<uuid>` is a generated UUIDv4).
     //
     // Note: after encoding the data as parquet, this issues a PUT followed by a HEAD to storage in
     // order to obtain metadata about the object just written.
-    async fn write_parquet(
+    async fn write_data_file(
         &self,
         path: &url::Url,
         data: Box<dyn EngineData>,
+        stats_columns: &[ColumnName],
     ) -> DeltaResult<DataFileMetadata> {
-        let batch: Box<_> = ArrowEngineData::try_from_engine_data(data)?;
-        let record_batch = batch.record_batch();
-
-        let mut buffer = vec![];
-        let mut writer = ArrowWriter::try_new(&mut buffer, record_batch.schema(), None)?;
-        writer.write(record_batch)?;
-        writer.close()?; // writer must be closed to write footer
-
+        let (buffer, stats) = encode_parquet(data, stats_columns, self.writer_properties.clone())?;
         let size = buffer.len();
-        let name: String = format!("{}.parquet", Uuid::new_v4());
+        let name = new_data_file_name();
         // fail if path does not end with a trailing slash
         if !path.path().ends_with('/') {
             return Err(Error::generic(format!(
@@ -137,12 +214,19 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
             )));
         }
         let path = path.join(&name)?;
+        // decode the URL path (e.g. of escaped partition directories) as readers do
+        let store_path = Path::from_url_path(path.path())?;
 
-        self.store
-            .put(&Path::from(path.path()), buffer.into())
+        self.multipart_upload
+            .put(
+                self.store.as_ref(),
+                &store_path,
+                buffer.into(),
+                self.retrier.as_ref(),
+            )
             .await?;
 
-        let metadata = self.store.head(&Path::from(path.path())).await?;
+        let metadata = self.store.head(&store_path).await?;
         let modification_time = metadata.last_modified.timestamp_millis();
         if size != metadata.size {
             return Err(Error::generic(format!(
@@ -152,12 +236,13 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         }
 
         let file_meta = FileMeta::new(path, modification_time, size);
-        Ok(DataFileMetadata::new(file_meta))
+        Ok(DataFileMetadata::new(file_meta).with_stats(stats))
     }
 
     /// Write `data` to `{path}/<uuid>.parquet` as parquet using ArrowWriter and return the parquet
     /// metadata as an EngineData batch which matches the [write metadata] schema (where `<uuid>` is
-    /// a generated UUIDv4).
+    /// a generated UUIDv4). The `stats` of the metadata only hold the number of records, see
+    /// [`Self::write_parquet_file_with_stats`] to also collect column statistics.
     ///
     /// [write metadata]: crate::transaction::get_write_metadata_schema
     pub async fn write_parquet_file(
@@ -167,53 +252,186 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         partition_values: HashMap<String, String>,
         data_change: bool,
     ) -> DeltaResult<Box<dyn EngineData>> {
-        let parquet_metadata = self.write_parquet(path, data).await?;
+        self.write_parquet_file_with_stats(path, data, &[], partition_values, data_change)
+            .await
+    }
+
+    /// Like [`Self::write_parquet_file`], but the `stats` of the metadata also hold the statistics
+    /// of the (physical) `stats_columns` of the data, see [`WriteContext::stats_columns`].
+    ///
+    /// [`WriteContext::stats_columns`]: crate::transaction::WriteContext::stats_columns
+    pub async fn write_parquet_file_with_stats(
+        &self,
+        path: &url::Url,
+        data: Box<dyn EngineData>,
+        stats_columns: &[ColumnName],
+        partition_values: HashMap<String, String>,
+        data_change: bool,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let parquet_metadata = self.write_data_file(path, data, stats_columns).await?;
         parquet_metadata.as_record_batch(&partition_values, data_change)
     }
 }
 
-impl<E: TaskExecutor> ParquetHandler for DefaultParquetHandler<E> {
-    fn read_parquet_files(
+impl<E: TaskExecutor> DefaultParquetHandler<E> {
+    // The stream of the batches of `files`, which reads up to `max_concurrent_reads` of them
+    // concurrently
+    fn file_stream(
         &self,
         files: &[FileMeta],
-        physical_schema: SchemaRef,
+        physical_schema: &SchemaRef,
         predicate: Option<ExpressionRef>,
-    ) -> DeltaResult<FileDataReadResultIterator> {
-        if files.is_empty() {
-            return Ok(Box::new(std::iter::empty()));
+    ) -> DeltaResult<FileStream> {
+        let file_opener = self.file_opener(files, physical_schema, predicate);
+        let mut stream = FileStream::new(
+            files.to_vec(),
+            Arc::new(physical_schema.as_ref().try_into()?),
+            file_opener,
+        )?
+        .with_max_concurrent_opens(self.max_concurrent_reads)
+        .with_file_readahead(self.file_readahead);
+        if let Some(memory_pool) = &self.memory_pool {
+            stream = stream.with_memory_pool(memory_pool.clone());
         }
+        Ok(stream)
+    }
 
-        // get the first FileMeta to decide how to fetch the file.
+    // The opener for `files`, which decides how to fetch them
+    fn file_opener(
+        &self,
+        files: &[FileMeta],
+        physical_schema: &SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> Box<dyn FileOpener> {
         // NB: This means that every file in `FileMeta` _must_ have the same scheme or things will break
         // s3://    -> aws   (ParquetOpener)
         // nothing  -> local (ParquetOpener)
         // https:// -> assume presigned URL (and fetch without object_store)
         //   -> reqwest to get data
         //   -> parse to parquet
-        // SAFETY: we did is_empty check above, this is ok.
-        let file_opener: Box<dyn FileOpener> = match files[0].location.scheme() {
-            "http" | "https" => Box::new(PresignedUrlOpener::new(
-                1024,
-                physical_schema.clone(),
+        // files in external storage with a store are read with it
+        #[cfg(any(feature = "mmap", feature = "reqwest"))]
+        let external = files
+            .iter()
+            .any(|file| self.external_stores.get(&file.location).is_some());
+        match files.first().map(|file| file.location.scheme()) {
+            #[cfg(feature = "mmap")]
+            Some("file") if self.mmap && !external => Box::new(MmapOpener {
+                batch_size: 1024,
+                table_schema: physical_schema.clone(),
                 predicate,
-            )),
-            _ => Box::new(ParquetOpener::new(
-                1024,
-                physical_schema.clone(),
-                predicate,
-                self.store.clone(),
-            )),
-        };
-        FileStream::new_async_read_iterator(
-            self.task_executor.clone(),
-            Arc::new(physical_schema.as_ref().try_into()?),
-            file_opener,
-            files,
-            self.readahead,
-        )
+                view_types: self.view_types,
+            }),
+            #[cfg(feature = "reqwest")]
+            Some("http" | "https") if !external => Box::new(PresignedUrlOpener {
+                view_types: self.view_types,
+                ..PresignedUrlOpener::new(1024, physical_schema.clone(), predicate)
+            }),
+            _ => Box::new(ParquetOpener {
+                external_stores: self.external_stores.clone(),
+                metadata_cache: self.metadata_cache.clone(),
+                view_types: self.view_types,
+                ..ParquetOpener::new(1024, physical_schema.clone(), predicate, self.store.clone())
+            }),
+        }
+    }
+
+    fn put_parquet(
+        &self,
+        location: &Url,
+        buffer: Vec<u8>,
+    ) -> BoxFuture<'static, DeltaResult<FileMeta>> {
+        let size = buffer.len();
+        let store = self.store.clone(); // cheap Arc
+        let multipart_upload = self.multipart_upload.clone();
+        let retrier = self.retrier.clone();
+        let location = location.clone();
+        Box::pin(async move {
+            let path = Path::from_url_path(location.path())?;
+            multipart_upload
+                .put(store.as_ref(), &path, buffer.into(), retrier.as_ref())
+                .await?;
+            let metadata = store.head(&path).await?;
+            if size != metadata.size {
+                return Err(Error::generic(format!(
+                    "Size mismatch after writing parquet file: expected {}, got {}",
+                    size, metadata.size
+                )));
+            }
+            Ok(FileMeta::new(
+                location,
+                metadata.last_modified.timestamp_millis(),
+                size,
+            ))
+        })
     }
 }
 
+impl<E: TaskExecutor> ParquetHandler for DefaultParquetHandler<E> {
+    fn read_parquet_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultIterator> {
+        if files.is_empty() {
+            return Ok(Box::new(std::iter::empty()));
+        }
+        let stream = self.file_stream(files, &physical_schema, predicate)?;
+        Ok(stream.into_async_read_iterator(self.task_executor.clone(), self.readahead))
+    }
+
+    fn write_parquet(
+        &self,
+        location: &Url,
+        schema: SchemaRef,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+    ) -> DeltaResult<FileMeta> {
+        let buffer = to_parquet_bytes(&schema, data, self.writer_properties.clone())?;
+        self.task_executor
+            .block_on(self.put_parquet(location, buffer))
+    }
+}
+
+impl<E: TaskExecutor> async_engine::AsyncParquetHandler for DefaultParquetHandler<E> {
+    fn read_parquet_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultStream> {
+        let stream = self.file_stream(files, &physical_schema, predicate)?;
+        Ok(stream.into_engine_data_stream())
+    }
+
+    fn write_parquet(
+        &self,
+        location: &Url,
+        schema: SchemaRef,
+        data: Vec<FilteredEngineData>,
+    ) -> BoxFuture<'_, DeltaResult<FileMeta>> {
+        match to_parquet_bytes(
+            &schema,
+            data.into_iter().map(Ok),
+            self.writer_properties.clone(),
+        ) {
+            Ok(buffer) => self.put_parquet(location, buffer),
+            Err(e) => Box::pin(futures::future::ready(Err(e))),
+        }
+    }
+}
+
+// The `metadata` with the strings and binaries of its schema represented as views, so that the
+// file is read into view arrays
+fn with_view_types(metadata: ArrowReaderMetadata) -> DeltaResult<ArrowReaderMetadata> {
+    let schema = Arc::new(to_view_schema(metadata.schema()));
+    let options = ArrowReaderOptions::new().with_schema(schema);
+    Ok(ArrowReaderMetadata::try_new(
+        metadata.metadata().clone(),
+        options,
+    )?)
+}
+
 /// Implements [`FileOpener`] for a parquet file
 struct ParquetOpener {
     // projection: Arc<[usize]>,
@@ -222,6 +440,9 @@ struct ParquetOpener {
     predicate: Option<ExpressionRef>,
     limit: Option<usize>,
     store: Arc<DynObjectStore>,
+    external_stores: ExternalStores,
+    metadata_cache: Option<MetadataCache>,
+    view_types: bool,
 }
 
 impl ParquetOpener {
@@ -237,6 +458,9 @@ impl ParquetOpener {
             predicate,
             limit: None,
             store,
+            external_stores: ExternalStores::default(),
+            metadata_cache: None,
+            view_types: false,
         }
     }
 }
@@ -244,28 +468,62 @@ impl ParquetOpener {
 impl FileOpener for ParquetOpener {
     fn open(&self, file_meta: FileMeta, _range: Option<Range<i64>>) -> DeltaResult<FileOpenFuture> {
         let path = Path::from_url_path(file_meta.location.path())?;
-        let store = self.store.clone();
+        let store = self
+            .external_stores
+            .store_for(&file_meta.location, &self.store)?;
+        let metadata_cache = self.metadata_cache.clone();
+        let cache_contents = metadata_cache
+            .as_ref()
+            .is_some_and(|cache| cache.caches_contents(&file_meta));
 
         let batch_size = self.batch_size;
-        // let projection = self.projection.clone();
         let table_schema = self.table_schema.clone();
         let predicate = self.predicate.clone();
         let limit = self.limit;
+        let view_types = self.view_types;
+        let location = file_meta.location.clone();
 
         Ok(Box::pin(async move {
-            // TODO avoid IO by converting passed file meta to ObjectMeta
-            let meta = store.head(&path).await?;
-            let mut reader = ParquetObjectReader::new(store, meta);
-            let metadata = ArrowReaderMetadata::load_async(&mut reader, Default::default()).await?;
-            let parquet_schema = metadata.schema();
+            let (reader, metadata): (Box<dyn AsyncFileReader>, _) = match metadata_cache {
+                // read (small) checkpoint parts from memory
+                Some(cache) if cache_contents => {
+                    let contents = cache.get_contents(&store, &location).await?;
+                    let metadata = ArrowReaderMetadata::load(&contents, Default::default())?;
+                    (Box::new(Cursor::new(contents)), metadata)
+                }
+                cache => {
+                    let cached_footer = cache.as_ref().and_then(|cache| cache.footer(&location));
+                    match cached_footer {
+                        Some((meta, metadata)) => {
+                            (Box::new(ParquetObjectReader::new(store, meta)), metadata)
+                        }
+                        None => {
+                            // TODO avoid IO by converting passed file meta to ObjectMeta
+                            let meta = store.head(&path).await?;
+                            let mut reader = ParquetObjectReader::new(store, meta.clone());
+                            let metadata =
+                                ArrowReaderMetadata::load_async(&mut reader, Default::default())
+                                    .await?;
+                            if let Some(cache) = cache {
+                                cache.insert_footer(&location, meta, metadata.clone());
+                            }
+                            (Box::new(reader), metadata)
+                        }
+                    }
+                }
+            };
+            let metadata = if view_types {
+                with_view_types(metadata)?
+            } else {
+                metadata
+            };
+            let parquet_schema = metadata.schema().clone();
             let (indicies, requested_ordering) =
-                get_requested_indices(&table_schema, parquet_schema)?;
-            let options = ArrowReaderOptions::new(); //.with_page_index(enable_page_index);
-            let mut builder =
-                ParquetRecordBatchStreamBuilder::new_with_options(reader, options).await?;
+                get_requested_indices(&table_schema, &parquet_schema)?;
+            let mut builder = ParquetRecordBatchStreamBuilder::new_with_metadata(reader, metadata);
             if let Some(mask) = generate_mask(
                 &table_schema,
-                parquet_schema,
+                &parquet_schema,
                 builder.parquet_schema(),
                 &indicies,
             ) {
@@ -273,7 +531,9 @@ impl FileOpener for ParquetOpener {
             }
 
             if let Some(ref predicate) = predicate {
-                builder = builder.with_row_group_filter(predicate);
+                builder = builder
+                    .with_row_group_filter(predicate)
+                    .with_predicate_row_filter(predicate);
             }
             if let Some(limit) = limit {
                 builder = builder.with_limit(limit)
@@ -293,14 +553,17 @@ impl FileOpener for ParquetOpener {
 }
 
 /// Implements [`FileOpener`] for a opening a parquet file from a presigned URL
+#[cfg(feature = "reqwest")]
 struct PresignedUrlOpener {
     batch_size: usize,
     predicate: Option<ExpressionRef>,
     limit: Option<usize>,
     table_schema: SchemaRef,
     client: reqwest::Client,
+    view_types: bool,
 }
 
+#[cfg(feature = "reqwest")]
 impl PresignedUrlOpener {
     pub(crate) fn new(
         batch_size: usize,
@@ -313,10 +576,12 @@ impl PresignedUrlOpener {
             predicate,
             limit: None,
             client: reqwest::Client::new(),
+            view_types: false,
         }
     }
 }
 
+#[cfg(feature = "reqwest")]
 impl FileOpener for PresignedUrlOpener {
     fn open(&self, file_meta: FileMeta, _range: Option<Range<i64>>) -> DeltaResult<FileOpenFuture> {
         let batch_size = self.batch_size;
@@ -324,46 +589,102 @@ impl FileOpener for PresignedUrlOpener {
         let predicate = self.predicate.clone();
         let limit = self.limit;
         let client = self.client.clone(); // uses Arc internally according to reqwest docs
+        let view_types = self.view_types;
 
         Ok(Box::pin(async move {
             // fetch the file from the interweb
             let reader = client.get(file_meta.location).send().await?.bytes().await?;
-            let metadata = ArrowReaderMetadata::load(&reader, Default::default())?;
-            let parquet_schema = metadata.schema();
-            let (indicies, requested_ordering) =
-                get_requested_indices(&table_schema, parquet_schema)?;
+            read_parquet_bytes(
+                reader,
+                &table_schema,
+                predicate,
+                limit,
+                batch_size,
+                view_types,
+            )
+        }))
+    }
+}
 
-            let options = ArrowReaderOptions::new();
-            let mut builder =
-                ParquetRecordBatchReaderBuilder::try_new_with_options(reader, options)?;
-            if let Some(mask) = generate_mask(
+/// Implements [`FileOpener`] for a local parquet file, which is memory-mapped rather than read
+/// through the object store
+#[cfg(feature = "mmap")]
+struct MmapOpener {
+    batch_size: usize,
+    table_schema: SchemaRef,
+    predicate: Option<ExpressionRef>,
+    view_types: bool,
+}
+
+#[cfg(feature = "mmap")]
+impl FileOpener for MmapOpener {
+    fn open(&self, file_meta: FileMeta, _range: Option<Range<i64>>) -> DeltaResult<FileOpenFuture> {
+        let batch_size = self.batch_size;
+        let table_schema = self.table_schema.clone();
+        let predicate = self.predicate.clone();
+        let view_types = self.view_types;
+        Ok(Box::pin(async move {
+            let mapped = super::mmap::read_mapped(&file_meta.location, None)?;
+            read_parquet_bytes(
+                mapped,
                 &table_schema,
-                parquet_schema,
-                builder.parquet_schema(),
-                &indicies,
-            ) {
-                builder = builder.with_projection(mask)
-            }
+                predicate,
+                None,
+                batch_size,
+                view_types,
+            )
+        }))
+    }
+}
 
-            if let Some(ref predicate) = predicate {
-                builder = builder.with_row_group_filter(predicate);
-            }
-            if let Some(limit) = limit {
-                builder = builder.with_limit(limit)
-            }
+/// Read the batches of the parquet file held in `bytes` (which are decoded as the stream is
+/// polled)
+#[cfg(any(feature = "reqwest", feature = "mmap"))]
+fn read_parquet_bytes(
+    bytes: bytes::Bytes,
+    table_schema: &SchemaRef,
+    predicate: Option<ExpressionRef>,
+    limit: Option<usize>,
+    batch_size: usize,
+    view_types: bool,
+) -> DeltaResult<BoxStream<'static, DeltaResult<RecordBatch>>> {
+    let metadata = ArrowReaderMetadata::load(&bytes, Default::default())?;
+    let metadata = if view_types {
+        with_view_types(metadata)?
+    } else {
+        metadata
+    };
+    let parquet_schema = metadata.schema().clone();
+    let (indicies, requested_ordering) = get_requested_indices(table_schema, &parquet_schema)?;
 
-            let reader = builder.with_batch_size(batch_size).build()?;
+    let mut builder = ParquetRecordBatchReaderBuilder::new_with_metadata(bytes, metadata);
+    if let Some(mask) = generate_mask(
+        table_schema,
+        &parquet_schema,
+        builder.parquet_schema(),
+        &indicies,
+    ) {
+        builder = builder.with_projection(mask)
+    }
 
-            let stream = futures::stream::iter(reader);
-            let stream = stream.map(move |rbr| {
-                // re-order each batch if needed
-                rbr.map_err(Error::Arrow).and_then(|rb| {
-                    reorder_struct_array(rb.into(), &requested_ordering).map(Into::into)
-                })
-            });
-            Ok(stream.boxed())
-        }))
+    if let Some(ref predicate) = predicate {
+        builder = builder
+            .with_row_group_filter(predicate)
+            .with_predicate_row_filter(predicate);
     }
+    if let Some(limit) = limit {
+        builder = builder.with_limit(limit)
+    }
+
+    let reader = builder.with_batch_size(batch_size).build()?;
+
+    let stream = futures::stream::iter(reader);
+    let stream = stream.map(move |rbr| {
+        // re-order each batch if needed
+        rbr.map_err(Error::Arrow)
+            .and_then(|rb| reorder_struct_array(rb.into(), &requested_ordering).map(Into::into))
+    });
+    Ok(stream.boxed())
 }
 
 #[cfg(test)]
@@ -372,13 +693,18 @@ mod tests {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     use arrow_array::array::Array;
+    use arrow_array::Int64Array;
     use arrow_array::RecordBatch;
     use object_store::{local::LocalFileSystem, memory::InMemory, ObjectStore};
+    use parquet::basic::Compression;
+    use parquet::file::properties::EnabledStatistics;
     use url::Url;
 
     use crate::engine::arrow_data::ArrowEngineData;
     use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
-    use crate::EngineData;
+    use crate::expressions::column_name;
+    use crate::schema::{DataType, StructField, StructType};
+    use crate::{EngineData, ErrorKind};
 
     use itertools::Itertools;
 
@@ -428,54 +754,102 @@ mod tests {
         assert_eq!(data[0].num_rows(), 10);
     }
 
-    #[test]
-    fn test_as_record_batch() {
-        let location = Url::parse("file:///test_url").unwrap();
-        let size = 1_000_000;
-        let last_modified = 10000000000;
-        let file_metadata = FileMeta::new(location.clone(), last_modified, size as usize);
-        let data_file_metadata = DataFileMetadata::new(file_metadata);
-        let partition_values = HashMap::from([("partition1".to_string(), "a".to_string())]);
-        let data_change = true;
-        let actual = data_file_metadata
-            .as_record_batch(&partition_values, data_change)
+    #[cfg(feature = "mmap")]
+    #[tokio::test]
+    async fn test_read_parquet_files_with_mmap() {
+        // the store is empty, so the file can only be read by mapping it
+        let store = Arc::new(InMemory::new());
+        let path = std::fs::canonicalize(PathBuf::from(
+            "./tests/data/table-with-dv-small/part-00000-fae5310a-a37d-4e51-827b-c3d5516560ca-c000.snappy.parquet"
+        )).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len() as usize;
+        let files = &[FileMeta {
+            location: url::Url::from_file_path(path).unwrap(),
+            last_modified: 0,
+            size,
+        }];
+        let physical_schema = Arc::new(StructType::new([StructField::new(
+            "value",
+            DataType::INTEGER,
+            true,
+        )]));
+
+        let handler = DefaultParquetHandler::new(store, Arc::new(TokioBackgroundExecutor::new()))
+            .with_mmap(true);
+        let data: Vec<RecordBatch> = handler
+            .read_parquet_files(files, physical_schema, None)
+            .unwrap()
+            .map(into_record_batch)
+            .try_collect()
             .unwrap();
-        let actual = ArrowEngineData::try_from_engine_data(actual).unwrap();
 
-        let schema = Arc::new(
-            crate::transaction::get_write_metadata_schema()
-                .as_ref()
-                .try_into()
-                .unwrap(),
-        );
-        let key_builder = StringBuilder::new();
-        let val_builder = StringBuilder::new();
-        let mut partition_values_builder = MapBuilder::new(
-            Some(MapFieldNames {
-                entry: "key_value".to_string(),
-                key: "key".to_string(),
-                value: "value".to_string(),
-            }),
-            key_builder,
-            val_builder,
-        );
-        partition_values_builder.keys().append_value("partition1");
-        partition_values_builder.values().append_value("a");
-        partition_values_builder.append(true).unwrap();
-        let partition_values = partition_values_builder.finish();
-        let expected = RecordBatch::try_new(
-            schema,
-            vec![
-                Arc::new(StringArray::from(vec![location.to_string()])),
-                Arc::new(partition_values),
-                Arc::new(Int64Array::from(vec![size])),
-                Arc::new(Int64Array::from(vec![last_modified])),
-                Arc::new(BooleanArray::from(vec![data_change])),
-            ],
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].num_rows(), 10);
+        assert_eq!(data[0].num_columns(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_parquet_files_with_metadata_cache() {
+        let store = Arc::new(InMemory::new());
+        let checkpoint = std::fs::read(
+            "./tests/data/with_checkpoint_no_last_checkpoint/_delta_log/00000000000000000002.checkpoint.parquet",
+        )
+        .unwrap();
+        let data_file = std::fs::read(
+            "./tests/data/table-with-dv-small/part-00000-fae5310a-a37d-4e51-827b-c3d5516560ca-c000.snappy.parquet",
         )
         .unwrap();
+        let checkpoint_path = Path::from("_delta_log/00000000000000000002.checkpoint.parquet");
+        let data_file_path = Path::from("part-00000.parquet");
+        let file_meta = |path: &Path, size| FileMeta {
+            location: Url::parse(&format!("memory:///{path}")).unwrap(),
+            last_modified: 0,
+            size,
+        };
+        let checkpoint_meta = file_meta(&checkpoint_path, checkpoint.len());
+        let data_file_meta = file_meta(&data_file_path, data_file.len());
+        store
+            .put(&checkpoint_path, checkpoint.into())
+            .await
+            .unwrap();
+        store.put(&data_file_path, data_file.into()).await.unwrap();
 
-        assert_eq!(actual.record_batch(), &expected);
+        let cache = MetadataCache::new(Default::default());
+        let handler =
+            DefaultParquetHandler::new(store.clone(), Arc::new(TokioBackgroundExecutor::new()))
+                .with_metadata_cache(cache.clone());
+        let read = |file: &FileMeta| -> Vec<RecordBatch> {
+            let physical_schema = crate::actions::get_log_add_schema().clone();
+            handler
+                .read_parquet_files(std::slice::from_ref(file), physical_schema, None)
+                .unwrap()
+                .map(into_record_batch)
+                .try_collect()
+                .unwrap()
+        };
+
+        let checkpoint_data = read(&checkpoint_meta);
+        let size = cache.size();
+        assert_eq!(size, checkpoint_meta.size);
+        // the checkpoint part is read from the cache
+        store.delete(&checkpoint_path).await.unwrap();
+        assert_eq!(read(&checkpoint_meta), checkpoint_data);
+
+        // data files are not cached, but their footers are
+        let physical_schema = Arc::new(StructType::new([StructField::new(
+            "value",
+            DataType::INTEGER,
+            true,
+        )]));
+        let data: Vec<RecordBatch> = handler
+            .read_parquet_files(std::slice::from_ref(&data_file_meta), physical_schema, None)
+            .unwrap()
+            .map(into_record_batch)
+            .try_collect()
+            .unwrap();
+        assert_eq!(data[0].num_rows(), 10);
+        assert!(cache.size() > size);
+        assert!(cache.footer(&data_file_meta.location).is_some());
     }
 
     #[tokio::test]
@@ -493,7 +867,11 @@ mod tests {
         ));
 
         let write_metadata = parquet_handler
-            .write_parquet(&Url::parse("memory:///data/").unwrap(), data)
+            .write_data_file(
+                &Url::parse("memory:///data/").unwrap(),
+                data,
+                &[column_name!("a")],
+            )
             .await
             .unwrap();
 
@@ -504,6 +882,7 @@ mod tests {
                     last_modified,
                     size,
                 },
+            ref stats,
         } = write_metadata;
         let expected_location = Url::parse("memory:///data/").unwrap();
 
@@ -523,6 +902,10 @@ mod tests {
         assert_eq!(&expected_location.join(filename).unwrap(), location);
         assert_eq!(expected_size, size);
         assert!(now - last_modified < 10_000);
+        assert_eq!(
+            stats.as_deref(),
+            Some(r#"{"numRecords":3,"minValues":{"a":1},"maxValues":{"a":3},"nullCount":{"a":0}}"#)
+        );
 
         // check we can read back
         let path = Path::from(location.path());
@@ -549,6 +932,79 @@ mod tests {
         assert_eq!(data[0].num_rows(), 3);
     }
 
+    #[tokio::test]
+    async fn test_write_parquet_with_writer_properties() {
+        let store = Arc::new(InMemory::new());
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_dictionary_enabled(false)
+            .set_max_row_group_size(2)
+            .set_statistics_enabled(EnabledStatistics::None)
+            .build();
+        let parquet_handler =
+            DefaultParquetHandler::new(store.clone(), Arc::new(TokioBackgroundExecutor::new()))
+                .with_writer_properties(properties);
+
+        let data = Box::new(ArrowEngineData::new(
+            RecordBatch::try_from_iter(vec![(
+                "a",
+                Arc::new(Int64Array::from(vec![1, 2, 3])) as Arc<dyn Array>,
+            )])
+            .unwrap(),
+        ));
+        let write_metadata = parquet_handler
+            .write_data_file(&Url::parse("memory:///data/").unwrap(), data, &[])
+            .await
+            .unwrap();
+
+        let path = Path::from(write_metadata.file_meta.location.path());
+        let meta = store.head(&path).await.unwrap();
+        let reader = ParquetObjectReader::new(store.clone(), meta);
+        let builder = ParquetRecordBatchStreamBuilder::new(reader).await.unwrap();
+        let row_groups = builder.metadata().row_groups();
+        assert_eq!(row_groups.len(), 2);
+        for row_group in row_groups {
+            let column = row_group.column(0);
+            assert_eq!(column.compression(), Compression::SNAPPY);
+            assert!(column.dictionary_page_offset().is_none());
+            assert!(column.statistics().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_corrupt_footer() {
+        let store = Arc::new(InMemory::new());
+        let handler =
+            DefaultParquetHandler::new(store.clone(), Arc::new(TokioBackgroundExecutor::new()));
+        let schema = Arc::new(StructType::new([StructField::new(
+            "a",
+            DataType::LONG,
+            true,
+        )]));
+
+        // a file whose (empty) footer is followed by unknown magic bytes
+        let contents = [
+            b"PAR1".as_slice(),
+            b"\x00\x00\x00\x00\x04\x00\x00\x00",
+            b"PAR2",
+        ]
+        .concat();
+        let path = Path::from("file.parquet");
+        let size = contents.len();
+        store.put(&path, contents.into()).await.unwrap();
+        let location = Url::parse("memory:///file.parquet").unwrap();
+        let result: DeltaResult<Vec<_>> = handler
+            .read_parquet_files(&[FileMeta::new(location.clone(), 0, size)], schema, None)
+            .unwrap()
+            .try_collect();
+        let Err(err) = result else {
+            panic!("expected an error reading a corrupt file");
+        };
+        assert_eq!(err.kind(), ErrorKind::Parquet);
+        // the error is annotated with the file it occurred in
+        assert_eq!(err.context().unwrap().file(), Some(&location));
+    }
+
     #[tokio::test]
     async fn test_disallow_non_trailing_slash() {
         let store = Arc::new(InMemory::new());
@@ -564,7 +1020,7 @@ mod tests {
         ));
 
         assert!(parquet_handler
-            .write_parquet(&Url::parse("memory:///data").unwrap(), data)
+            .write_data_file(&Url::parse("memory:///data").unwrap(), data, &[])
             .await
             .is_err());
     }
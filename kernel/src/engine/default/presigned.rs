@@ -0,0 +1,196 @@
+//! Reading files from pre-signed (or otherwise publicly readable) HTTP(S) URLs.
+//!
+//! Delta Sharing servers, and the owners of restricted buckets, hand out a pre-signed URL for each
+//! file rather than credentials for the storage of the table. A [`PresignedUrlFileSystemClient`]
+//! reads such files over plain HTTP, and a scan reads its data files from the URLs given by a
+//! [`FileUrlResolver`].
+//!
+//! [`FileUrlResolver`]: crate::scan::FileUrlResolver
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::header::{CONTENT_LENGTH, LAST_MODIFIED, RANGE};
+use reqwest::{Client, Response, StatusCode};
+use url::Url;
+
+use crate::engine::default::executor::TaskExecutor;
+use crate::{DeltaResult, Error, FileMeta, FileSlice, FileSystemClient};
+
+/// A [`FileSystemClient`] which reads files directly from their (pre-signed) HTTP(S) URLs. The URLs
+/// cannot be listed or written to, so only [`FileSystemClient::read_files`] and
+/// [`FileSystemClient::head`] are supported.
+#[derive(Debug)]
+pub struct PresignedUrlFileSystemClient<E: TaskExecutor> {
+    client: Client,
+    task_executor: Arc<E>,
+    readahead: usize,
+}
+
+impl<E: TaskExecutor> PresignedUrlFileSystemClient<E> {
+    /// Create a new client, which runs its requests on `task_executor`.
+    pub fn new(task_executor: Arc<E>) -> Self {
+        Self {
+            client: Client::new(),
+            task_executor,
+            readahead: 10,
+        }
+    }
+
+    /// Send the requests with the given [`Client`], e.g. to configure its timeouts or proxies.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set the maximum number of files to read in parallel.
+    pub fn with_readahead(mut self, readahead: usize) -> Self {
+        self.readahead = readahead;
+        self
+    }
+
+    // A stream of the contents of `files`, in order, reading up to `readahead` files concurrently
+    fn read_stream(&self, files: Vec<FileSlice>) -> BoxStream<'static, DeltaResult<Bytes>> {
+        let client = self.client.clone(); // uses Arc internally according to reqwest docs
+        stream::iter(files)
+            .map(move |(url, range)| get(client.clone(), url, range))
+            .buffered(self.readahead)
+            .boxed()
+    }
+}
+
+impl<E: TaskExecutor> FileSystemClient for PresignedUrlFileSystemClient<E> {
+    fn list_from(
+        &self,
+        path: &Url,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        Err(Error::unsupported(format!(
+            "Cannot list pre-signed URLs, such as {path}"
+        )))
+    }
+
+    fn read_files(
+        &self,
+        files: Vec<FileSlice>,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>> {
+        // This channel will become the output iterator. Because there will already be buffering
+        // in the stream, we set the buffer size to 0.
+        let (sender, receiver) = std::sync::mpsc::sync_channel(0);
+        self.task_executor
+            .spawn(self.read_stream(files).for_each(move |res| {
+                sender.send(res).ok();
+                futures::future::ready(())
+            }));
+        Ok(Box::new(receiver.into_iter()))
+    }
+
+    fn head(&self, path: &Url) -> DeltaResult<FileMeta> {
+        self.task_executor
+            .block_on(head(self.client.clone(), path.clone()))
+    }
+
+    fn write_file(&self, path: &Url, _data: Bytes, _overwrite: bool) -> DeltaResult<()> {
+        Err(Error::unsupported(format!(
+            "Cannot write to pre-signed URLs, such as {path}"
+        )))
+    }
+}
+
+/// Fetch the bytes of `range` (or all of the bytes, if `None`) of the file at `url`.
+pub(crate) async fn get(
+    client: Client,
+    url: Url,
+    range: Option<Range<usize>>,
+) -> DeltaResult<Bytes> {
+    let mut request = client.get(url.clone());
+    if let Some(range) = &range {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+        request = request.header(RANGE, format!("bytes={}-{}", range.start, range.end - 1));
+    }
+    let response = check_status(&url, request.send().await?)?;
+    let partial = response.status() == StatusCode::PARTIAL_CONTENT;
+    let data = response.bytes().await?;
+    match range {
+        // the server ignored the range, and returned the whole file
+        Some(range) if !partial => {
+            if range.end > data.len() {
+                return Err(Error::generic(format!(
+                    "Requested bytes {range:?} of {url}, which has only {} bytes",
+                    data.len()
+                )));
+            }
+            Ok(data.slice(range))
+        }
+        _ => Ok(data),
+    }
+}
+
+/// Fetch the metadata of the file at `url`, from the headers of a `HEAD` request.
+pub(crate) async fn head(client: Client, url: Url) -> DeltaResult<FileMeta> {
+    let response = check_status(&url, client.head(url.clone()).send().await?)?;
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    let size = header(CONTENT_LENGTH)
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(|| Error::generic(format!("Missing the size of {url}")))?;
+    // the last modified time is only informative, so it's not an error if it's missing
+    let last_modified = header(LAST_MODIFIED)
+        .and_then(|time| chrono::DateTime::parse_from_rfc2822(time).ok())
+        .map_or(0, |time| time.timestamp_millis());
+    Ok(FileMeta::new(url, last_modified, size))
+}
+
+// Fail if the request for `url` was unsuccessful, with [`Error::FileNotFound`] if there's no file
+fn check_status(url: &Url, response: Response) -> DeltaResult<Response> {
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(Error::file_not_found(url));
+    }
+    Ok(response.error_for_status()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+
+    #[test]
+    fn test_read_presigned_urls() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("a"), "kernel-data").unwrap();
+        let base_url = Url::parse(&test_utils::serve_files(tmp.path().to_path_buf())).unwrap();
+        let client = PresignedUrlFileSystemClient::new(Arc::new(TokioBackgroundExecutor::new()));
+
+        let url = base_url.join("a").unwrap();
+        let files = vec![
+            (url.clone(), None),
+            (url.clone(), Some(7..11)),
+            (url.clone(), Some(3..3)),
+        ];
+        let data: Vec<_> = client.read_files(files).unwrap().try_collect().unwrap();
+        assert_eq!(data, ["kernel-data", "data", ""]);
+
+        let meta = client.head(&url).unwrap();
+        assert_eq!(meta.size, 11);
+        assert_eq!(meta.last_modified, 1445412480000);
+
+        let missing = base_url.join("b").unwrap();
+        assert!(matches!(client.head(&missing), Err(Error::FileNotFound(_))));
+        let mut read = client.read_files(vec![(missing, None)]).unwrap();
+        assert!(matches!(read.next(), Some(Err(Error::FileNotFound(_)))));
+
+        assert!(matches!(
+            client.list_from(&base_url),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
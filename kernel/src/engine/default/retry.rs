@@ -0,0 +1,675 @@
+//! Retries of the storage operations of the default engine.
+//!
+//! The handlers of the [`DefaultEngine`] access storage through a [`RetryingObjectStore`], which
+//! retries operations that fail with transient errors (e.g. timeouts or throttling of cloud
+//! stores) according to a [`RetryConfig`], so that such errors don't fail whole scans and commits.
+//! Note that the clients of cloud stores also retry failed HTTP requests themselves, so these
+//! retries apply on top of those.
+//!
+//! [`DefaultEngine`]: super::DefaultEngine
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMode, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use tracing::debug;
+
+use super::executor::TaskExecutor;
+use super::metrics::EngineMetrics;
+
+/// The kinds of storage operations, whose retries may be configured separately (see
+/// [`RetryConfig::with_operation_policy`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageOperation {
+    /// Reading (a range of) a file
+    Get,
+    /// Reading the metadata of a file
+    Head,
+    /// Writing a file. Only unconditional writes (which overwrite existing files) are retried,
+    /// since retrying a conditional write whose response was lost would report a conflict.
+    Put,
+    /// Listing files
+    List,
+    /// Deleting a file
+    Delete,
+    /// Copying or renaming a file
+    Copy,
+}
+
+/// How often and after which delays a failed operation is retried. The `n`th retry happens
+/// `initial_backoff * backoff_multiplier^(n - 1)` (at most `max_backoff`) after the failure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts of an operation, including the first one. An operation is
+    /// not retried if this is 1 (or 0).
+    pub max_attempts: usize,
+    /// The delay before the first retry
+    pub initial_backoff: Duration,
+    /// The maximum delay between two attempts
+    pub max_backoff: Duration,
+    /// The factor by which the delay grows from one retry to the next
+    pub backoff_multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            backoff_multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which never retries operations.
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    // The delay before retrying after the failed `attempt` (starting at 1)
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let backoff = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+        Duration::try_from_secs_f64(backoff)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+type RetryableErrorClassifier = Arc<dyn Fn(&object_store::Error) -> bool + Send + Sync>;
+
+/// The retries of the storage operations of the default engine: a [`RetryPolicy`] for all
+/// operations, which may be overridden for specific kinds of operations, and which errors are
+/// retried.
+///
+/// By default, operations are attempted up to 3 times, and errors are retried unless they are
+/// known to be permanent (e.g. files which don't exist or already exist, or missing permissions).
+#[derive(Clone)]
+pub struct RetryConfig {
+    policy: RetryPolicy,
+    operation_policies: HashMap<StorageOperation, RetryPolicy>,
+    is_retryable: RetryableErrorClassifier,
+}
+
+impl Debug for RetryConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryConfig")
+            .field("policy", &self.policy)
+            .field("operation_policies", &self.operation_policies)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            policy: RetryPolicy::default(),
+            operation_policies: HashMap::new(),
+            is_retryable: Arc::new(is_transient),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// A config which never retries operations.
+    pub fn never() -> Self {
+        Self::default().with_policy(RetryPolicy::never())
+    }
+
+    /// Set the policy of all operations without a policy of their own.
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the policy of the `operation`s, overriding the policy of all operations.
+    pub fn with_operation_policy(
+        mut self,
+        operation: StorageOperation,
+        policy: RetryPolicy,
+    ) -> Self {
+        self.operation_policies.insert(operation, policy);
+        self
+    }
+
+    /// Set which errors are retried, instead of all errors which are not known to be permanent.
+    pub fn with_retryable_errors(
+        mut self,
+        is_retryable: impl Fn(&object_store::Error) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.is_retryable = Arc::new(is_retryable);
+        self
+    }
+
+    /// The policy of the `operation`s
+    pub fn policy(&self, operation: StorageOperation) -> &RetryPolicy {
+        self.operation_policies
+            .get(&operation)
+            .unwrap_or(&self.policy)
+    }
+
+    /// Whether operations which failed with `error` are retried
+    pub fn is_retryable(&self, error: &object_store::Error) -> bool {
+        (self.is_retryable)(error)
+    }
+}
+
+// Errors which will not go away by retrying are permanent; all others may be transient
+fn is_transient(error: &object_store::Error) -> bool {
+    use object_store::Error::*;
+    !matches!(
+        error,
+        NotFound { .. }
+            | InvalidPath { .. }
+            | NotSupported { .. }
+            | AlreadyExists { .. }
+            | Precondition { .. }
+            | NotModified { .. }
+            | NotImplemented
+            | PermissionDenied { .. }
+            | Unauthenticated { .. }
+            | UnknownConfigurationKey { .. }
+    )
+}
+
+type SleepFn = Arc<dyn Fn(Duration) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Retries failed operations according to a [`RetryConfig`], backing off on the timer of a
+/// [`TaskExecutor`]. This retries the requests of a [`RetryingObjectStore`], as well as operations
+/// made of several requests, such as multipart uploads (see [upload]).
+///
+/// [upload]: super::upload
+#[derive(Clone)]
+pub(crate) struct Retrier {
+    config: RetryConfig,
+    metrics: Option<Arc<dyn EngineMetrics>>,
+    sleep: SleepFn,
+}
+
+impl Retrier {
+    pub(crate) fn new<E: TaskExecutor>(config: RetryConfig, task_executor: Arc<E>) -> Self {
+        let sleep: SleepFn = Arc::new(move |duration| {
+            let task_executor = task_executor.clone();
+            async move { task_executor.sleep(duration).await }.boxed()
+        });
+        Self {
+            config,
+            metrics: None,
+            sleep,
+        }
+    }
+
+    // Report the retries to `metrics`
+    pub(crate) fn with_metrics(mut self, metrics: Arc<dyn EngineMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    // Back off before the retry of the failed `attempt` of the `operation`
+    async fn back_off(&self, operation: StorageOperation, attempt: usize, error: &impl Display) {
+        let backoff = self.config.policy(operation).backoff(attempt);
+        debug!("Retrying {operation:?} in {backoff:?} after attempt {attempt} failed: {error}");
+        if let Some(metrics) = &self.metrics {
+            metrics.request_retried(operation);
+        }
+        (self.sleep)(backoff).await;
+    }
+
+    /// Run `attempt_operation` until it succeeds, fails with an error which isn't retryable, or
+    /// the attempts of the policy of the `operation` are exhausted.
+    pub(crate) async fn retry<T, F, Fut>(
+        &self,
+        operation: StorageOperation,
+        attempt_operation: F,
+    ) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let policy = self.config.policy(operation);
+        let mut attempt = 1;
+        loop {
+            match attempt_operation().await {
+                Err(e) if attempt < policy.max_attempts && self.config.is_retryable(&e) => {
+                    self.back_off(operation, attempt, &e).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Debug for Retrier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Retrier")
+            .field("config", &self.config)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An [`ObjectStore`] which retries the failed operations of another store according to a
+/// [`RetryConfig`]. Reads of the contents of files (see [`GetResult::bytes`]) are not retried
+/// once their response has started, except for reads of ranges of files.
+pub struct RetryingObjectStore {
+    inner: Arc<DynObjectStore>,
+    retrier: Retrier,
+}
+
+impl RetryingObjectStore {
+    /// Create a store which retries the failed operations of `inner`, backing off before each
+    /// retry on the timer of `task_executor` (see [`TaskExecutor::sleep`]).
+    pub fn new<E: TaskExecutor>(
+        inner: Arc<DynObjectStore>,
+        config: RetryConfig,
+        task_executor: Arc<E>,
+    ) -> Self {
+        Self::with_retrier(inner, Retrier::new(config, task_executor))
+    }
+
+    // Create a store which retries the failed operations of `inner` with `retrier`
+    pub(crate) fn with_retrier(inner: Arc<DynObjectStore>, retrier: Retrier) -> Self {
+        Self { inner, retrier }
+    }
+
+    /// Report the retries of failed operations to `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<dyn EngineMetrics>) -> Self {
+        self.retrier = self.retrier.with_metrics(metrics);
+        self
+    }
+
+    /// The store whose operations are retried
+    pub fn inner(&self) -> &Arc<DynObjectStore> {
+        &self.inner
+    }
+
+    async fn retry<T, F, Fut>(&self, operation: StorageOperation, attempt_operation: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.retrier.retry(operation, attempt_operation).await
+    }
+
+    // Since listings may be unordered, they are only restarted if they fail before returning any
+    // files
+    fn retry_list<'a>(
+        &'a self,
+        list: impl Fn() -> BoxStream<'a, Result<ObjectMeta>> + Send + 'a,
+    ) -> BoxStream<'a, Result<ObjectMeta>> {
+        let retrier = &self.retrier;
+        let policy = retrier.config.policy(StorageOperation::List);
+        let state = (list(), list, 1, false);
+        futures::stream::unfold(
+            state,
+            move |(mut stream, list, mut attempt, started)| async move {
+                loop {
+                    match stream.next().await {
+                        Some(Err(e))
+                            if !started
+                                && attempt < policy.max_attempts
+                                && retrier.config.is_retryable(&e) =>
+                        {
+                            retrier.back_off(StorageOperation::List, attempt, &e).await;
+                            stream = list();
+                            attempt += 1;
+                        }
+                        Some(result) => {
+                            let started = started || result.is_ok();
+                            return Some((result, (stream, list, attempt, started)));
+                        }
+                        None => return None,
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+}
+
+impl Debug for RetryingObjectStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryingObjectStore")
+            .field("inner", &self.inner)
+            .field("config", &self.retrier.config)
+            .field("metrics", &self.retrier.metrics)
+            .finish()
+    }
+}
+
+// Displays as the inner store, which identifies the kind of store (e.g. a `LocalFileSystem`)
+impl Display for RetryingObjectStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RetryingObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        if !matches!(opts.mode, PutMode::Overwrite) {
+            return self.inner.put_opts(location, payload, opts).await;
+        }
+        self.retry(StorageOperation::Put, || {
+            self.inner.put_opts(location, payload.clone(), opts.clone())
+        })
+        .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.retry(StorageOperation::Get, || {
+            self.inner.get_opts(location, options.clone())
+        })
+        .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.retry(StorageOperation::Get, || {
+            self.inner.get_range(location, range.clone())
+        })
+        .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.retry(StorageOperation::Get, || {
+            self.inner.get_ranges(location, ranges)
+        })
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.retry(StorageOperation::Head, || self.inner.head(location))
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.retry(StorageOperation::Delete, || self.inner.delete(location))
+            .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        self.retry_list(move || self.inner.list(prefix.as_ref()))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        let offset = offset.clone();
+        self.retry_list(move || self.inner.list_with_offset(prefix.as_ref(), &offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.retry(StorageOperation::List, || {
+            self.inner.list_with_delimiter(prefix)
+        })
+        .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.retry(StorageOperation::Copy, || self.inner.copy(from, to))
+            .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.retry(StorageOperation::Copy, || self.inner.rename(from, to))
+            .await
+    }
+
+    // like conditional puts, conditional copies are not retried
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+
+    use super::*;
+    use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use crate::engine::default::metrics::{InstrumentedObjectStore, StorageMetrics};
+
+    // A store whose first `failures` operations fail with `error`
+    #[derive(Debug)]
+    struct FlakyStore {
+        inner: InMemory,
+        failures: AtomicUsize,
+        error: fn() -> object_store::Error,
+    }
+
+    impl FlakyStore {
+        fn new(failures: usize, error: fn() -> object_store::Error) -> Self {
+            Self {
+                inner: InMemory::new(),
+                failures: AtomicUsize::new(failures),
+                error,
+            }
+        }
+
+        fn fail(&self) -> Result<()> {
+            match self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            {
+                Ok(_) => Err((self.error)()),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    impl Display for FlakyStore {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "FlakyStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for FlakyStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            opts: PutOptions,
+        ) -> Result<PutResult> {
+            self.fail()?;
+            self.inner.put_opts(location, payload, opts).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            opts: PutMultipartOpts,
+        ) -> Result<Box<dyn MultipartUpload>> {
+            self.inner.put_multipart_opts(location, opts).await
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+            self.fail()?;
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> Result<()> {
+            self.fail()?;
+            self.inner.delete(location).await
+        }
+
+        fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+            match self.fail() {
+                Ok(()) => self.inner.list(prefix),
+                Err(e) => futures::stream::once(async { Err(e) }).boxed(),
+            }
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn transient_error() -> object_store::Error {
+        object_store::Error::Generic {
+            store: "FlakyStore",
+            source: "connection reset".into(),
+        }
+    }
+
+    fn permanent_error() -> object_store::Error {
+        object_store::Error::NotImplemented
+    }
+
+    fn retrying_store(flaky: &Arc<FlakyStore>, max_attempts: usize) -> RetryingObjectStore {
+        let policy = RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        RetryingObjectStore::new(
+            flaky.clone(),
+            RetryConfig::default().with_policy(policy),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            backoff_multiplier: 2.0,
+        };
+        let backoffs: Vec<_> = (1..6).map(|attempt| policy.backoff(attempt)).collect();
+        let expected = [100, 200, 400, 500, 500].map(Duration::from_millis);
+        assert_eq!(backoffs, expected);
+    }
+
+    #[test]
+    fn test_operation_policy() {
+        let config = RetryConfig::default()
+            .with_operation_policy(StorageOperation::Put, RetryPolicy::never());
+        assert_eq!(config.policy(StorageOperation::Put).max_attempts, 1);
+        assert_eq!(config.policy(StorageOperation::Get).max_attempts, 3);
+        assert!(config.is_retryable(&transient_error()));
+        assert!(!config.is_retryable(&permanent_error()));
+
+        let config = config.with_retryable_errors(|_| false);
+        assert!(!config.is_retryable(&transient_error()));
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        let flaky = Arc::new(FlakyStore::new(2, transient_error));
+        let store = retrying_store(&flaky, 3);
+        let path = Path::from("a");
+        store.put(&path, "data".into()).await.unwrap();
+
+        flaky.failures.store(2, Ordering::SeqCst);
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data, "data");
+
+        flaky.failures.store(2, Ordering::SeqCst);
+        let files: Vec<_> = store.list(None).try_collect().await.unwrap();
+        assert_eq!(files.len(), 1);
+
+        flaky.failures.store(2, Ordering::SeqCst);
+        store.delete(&path).await.unwrap();
+        assert_eq!(flaky.failures.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_give_up_after_max_attempts() {
+        let flaky = Arc::new(FlakyStore::new(3, transient_error));
+        let store = retrying_store(&flaky, 3);
+        let err = store.get(&Path::from("a")).await.unwrap_err();
+        assert!(matches!(err, object_store::Error::Generic { .. }));
+        assert_eq!(flaky.failures.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry() {
+        // permanent errors are not retried
+        let flaky = Arc::new(FlakyStore::new(2, permanent_error));
+        let store = retrying_store(&flaky, 3);
+        let err = store.get(&Path::from("a")).await.unwrap_err();
+        assert!(matches!(err, object_store::Error::NotImplemented));
+        assert_eq!(flaky.failures.load(Ordering::SeqCst), 1);
+
+        // conditional puts are not retried
+        let flaky = Arc::new(FlakyStore::new(1, transient_error));
+        let store = retrying_store(&flaky, 3);
+        let opts = PutOptions::from(PutMode::Create);
+        let path = Path::from("a");
+        let err = store.put_opts(&path, "data".into(), opts.clone()).await;
+        assert!(matches!(err, Err(object_store::Error::Generic { .. })));
+        store.put_opts(&path, "data".into(), opts).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_metrics() {
+        let flaky = Arc::new(FlakyStore::new(2, transient_error));
+        let metrics = Arc::new(StorageMetrics::new());
+        let instrumented =
+            InstrumentedObjectStore::new(flaky.clone()).with_metrics(metrics.clone());
+        let store = retrying_store(&flaky, 3);
+        let executor = Arc::new(TokioBackgroundExecutor::new());
+        let store =
+            RetryingObjectStore::new(Arc::new(instrumented), store.retrier.config, executor)
+                .with_metrics(metrics.clone());
+        store.put(&Path::from("a"), "data".into()).await.unwrap();
+
+        // every attempt is a request of its own
+        let put = metrics.operation(StorageOperation::Put);
+        assert_eq!((put.requests, put.failed_requests, put.retries), (3, 2, 2));
+        assert_eq!(metrics.bytes_written(), 4);
+    }
+}
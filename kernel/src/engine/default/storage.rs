@@ -1,10 +1,26 @@
-#[cfg(feature = "cloud")]
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "hdfs")]
 use hdfs_native_object_store::HdfsObjectStore;
+#[cfg(feature = "cloud")]
+use object_store::aws::{
+    AmazonS3Builder, AmazonS3ConfigKey, AwsCredentialProvider, S3ConditionalPut,
+};
+#[cfg(feature = "cloud")]
+use object_store::azure::{AzureConfigKey, AzureCredentialProvider, MicrosoftAzureBuilder};
+#[cfg(feature = "cloud")]
+use object_store::gcp::{GcpCredentialProvider, GoogleCloudStorageBuilder, GoogleConfigKey};
 use object_store::parse_url_opts as parse_url_opts_object_store;
 use object_store::path::Path;
-use object_store::{Error, ObjectStore};
+#[cfg(feature = "cloud")]
+use object_store::ClientConfigKey;
+use object_store::{DynObjectStore, Error, ObjectStore};
 use url::Url;
 
+use crate::path::storage_root;
+use crate::DeltaResult;
+
 pub fn parse_url_opts<I, K, V>(url: &Url, options: I) -> Result<(Box<dyn ObjectStore>, Path), Error>
 where
     I: IntoIterator<Item = (K, V)>,
@@ -12,13 +28,133 @@ where
     V: Into<String>,
 {
     match url.scheme() {
-        #[cfg(feature = "cloud")]
+        #[cfg(feature = "hdfs")]
         "hdfs" | "viewfs" => parse_url_opts_hdfs_native(url, options),
+        #[cfg(feature = "cloud")]
+        "s3" | "s3a" => parse_url_opts_s3(url, options, None),
+        #[cfg(feature = "cloud")]
+        "az" | "adl" | "azure" | "abfs" | "abfss" => parse_url_opts_azure(url, options, None),
+        #[cfg(feature = "cloud")]
+        "gs" => parse_url_opts_gcs(url, options, None),
         _ => parse_url_opts_object_store(url, options),
     }
 }
 
-#[cfg(feature = "cloud")]
+/// Check that the storage options with the given `keys` configure the store of `url`, as they do
+/// when passed to [`parse_url_opts`]. The stores for cloud URLs (with the `cloud` feature) and
+/// HTTP URLs silently ignore unknown options, so a misspelled option would otherwise go unnoticed
+/// until the store fails to authenticate, and local and in-memory stores take no options. The
+/// options of other stores (e.g. the Hadoop configuration of HDFS stores) are not checked.
+pub fn validate_storage_options<'a>(
+    url: &Url,
+    keys: impl IntoIterator<Item = &'a str>,
+) -> crate::DeltaResult<()> {
+    #[cfg_attr(not(feature = "cloud"), allow(unused_variables))]
+    let is_known = |key: &str| match url.scheme() {
+        #[cfg(feature = "cloud")]
+        "s3" | "s3a" => key.parse::<AmazonS3ConfigKey>().is_ok(),
+        #[cfg(feature = "cloud")]
+        "az" | "adl" | "azure" | "abfs" | "abfss" => key.parse::<AzureConfigKey>().is_ok(),
+        #[cfg(feature = "cloud")]
+        "gs" => key.parse::<GoogleConfigKey>().is_ok(),
+        #[cfg(feature = "cloud")]
+        "http" | "https" => key.parse::<ClientConfigKey>().is_ok(),
+        "file" | "memory" => false,
+        _ => true,
+    };
+    match keys.into_iter().find(|key| !is_known(key)) {
+        Some(key) => Err(crate::Error::unsupported(format!(
+            "Unknown storage option '{key}' for {} URLs",
+            url.scheme()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The object stores of the storage outside of a table that files of the table are stored in,
+/// e.g. the bucket of the source table of a shallow clone, by the root URL of the storage (see
+/// [`DefaultEngineOptions::with_external_store`]). Accessing a file in other external storage
+/// fails, unless the storage of the table isn't known (see
+/// [`DefaultEngineOptions::with_table_root`]), in which case all other files are accessed with
+/// the store of the table.
+///
+/// [`DefaultEngineOptions::with_external_store`]: super::DefaultEngineOptions::with_external_store
+/// [`DefaultEngineOptions::with_table_root`]: super::DefaultEngineOptions::with_table_root
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExternalStores {
+    table_storage: Option<Url>,
+    stores: HashMap<Url, Arc<DynObjectStore>>,
+}
+
+impl ExternalStores {
+    pub(crate) fn set_table_root(&mut self, table_root: &Url) {
+        self.table_storage = Some(storage_root(table_root));
+    }
+
+    pub(crate) fn insert(&mut self, url: &Url, store: Arc<DynObjectStore>) {
+        self.stores.insert(storage_root(url), store);
+    }
+
+    /// Wrap each of the stores with `f`, e.g. to retry its requests.
+    pub(crate) fn map(self, f: impl Fn(Arc<DynObjectStore>) -> Arc<DynObjectStore>) -> Self {
+        let stores = self
+            .stores
+            .into_iter()
+            .map(|(root, store)| (root, f(store)))
+            .collect();
+        Self { stores, ..self }
+    }
+
+    /// The store of the external storage the file at `url` is in, if any.
+    pub(crate) fn get(&self, url: &Url) -> Option<&Arc<DynObjectStore>> {
+        match self.stores.is_empty() {
+            true => None,
+            false => self.stores.get(&storage_root(url)),
+        }
+    }
+
+    /// The store to access the file at `url` with: the store of the external storage it's in, or
+    /// else `table_store`. Fails if the file is in external storage without a store, rather than
+    /// accessing the file at the same path in the storage of the table.
+    pub(crate) fn store_for(
+        &self,
+        url: &Url,
+        table_store: &Arc<DynObjectStore>,
+    ) -> DeltaResult<Arc<DynObjectStore>> {
+        if let Some(store) = self.get(url) {
+            return Ok(store.clone());
+        }
+        match &self.table_storage {
+            Some(table_storage) if *table_storage != storage_root(url) => {
+                Err(crate::Error::generic(format!(
+                    "No store is configured for the storage {} of {url}, outside of the storage \
+                     {table_storage} of the table",
+                    storage_root(url)
+                )))
+            }
+            _ => Ok(table_store.clone()),
+        }
+    }
+}
+
+/// Convert the `error` of writing the file at `path` to a kernel error. Stores report conditional
+/// puts of files which already exist either as [`Error::AlreadyExists`] or (e.g. for Azure's
+/// `If-None-Match` header) as [`Error::Precondition`].
+pub(crate) fn put_error(error: Error, path: String) -> crate::Error {
+    match error {
+        Error::AlreadyExists { .. } | Error::Precondition { .. } => {
+            crate::Error::FileAlreadyExists(path)
+        }
+        e => e.into(),
+    }
+}
+
+/// Create an HDFS (or ViewFS) store for `url` from the storage `options`, which are passed to the
+/// HDFS client as Hadoop configuration (e.g. `dfs.ha.namenodes.<nameservice>` for highly available
+/// name nodes), on top of the configuration found in `$HADOOP_CONF_DIR`. The store writes commits
+/// by creating files without overwriting them, so concurrent writers cannot overwrite each others'
+/// commits.
+#[cfg(feature = "hdfs")]
 pub fn parse_url_opts_hdfs_native<I, K, V>(
     url: &Url,
     options: I,
@@ -36,3 +172,325 @@ where
     let path = Path::parse(url.path())?;
     Ok((Box::new(store), path))
 }
+
+/// Create an S3 (or S3-compatible, e.g. MinIO) store for `url` from the storage `options` (see
+/// [`AmazonS3ConfigKey`] for the supported keys, e.g. `aws_region`, `aws_endpoint` or
+/// `aws_access_key_id`; other keys are ignored). Unlike [`object_store::parse_url_opts`]:
+///
+/// - The credentials of the store may be supplied by a custom `credentials` provider, e.g. one
+///   which assumes a role, instead of being configured by the options and the environment.
+/// - Commits are written with conditional puts (`If-None-Match`), which S3 and most S3-compatible
+///   stores support, so that concurrent writers cannot overwrite each others' commits. Stores
+///   without conditional puts may instead coordinate commits with a DynamoDB lock table, by
+///   setting the `aws_conditional_put` option to `dynamo:<table name>`.
+/// - Plain HTTP is allowed if the `aws_endpoint` is an `http://` URL (e.g. of a local MinIO),
+///   unless `aws_allow_http` is set.
+#[cfg(feature = "cloud")]
+pub fn parse_url_opts_s3<I, K, V>(
+    url: &Url,
+    options: I,
+    credentials: Option<AwsCredentialProvider>,
+) -> Result<(Box<dyn ObjectStore>, Path), Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let mut builder = s3_builder(url, options);
+    if let Some(credentials) = credentials {
+        builder = builder.with_credentials(credentials);
+    }
+    let path = Path::parse(url.path())?;
+    Ok((Box::new(builder.build()?), path))
+}
+
+#[cfg(feature = "cloud")]
+fn s3_builder<I, K, V>(url: &Url, options: I) -> AmazonS3Builder
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let allow_http = AmazonS3ConfigKey::Client(ClientConfigKey::AllowHttp);
+    let mut sets_allow_http = false;
+    let mut builder = AmazonS3Builder::new().with_url(url.as_str());
+    for (key, value) in options {
+        if let Ok(key) = key.as_ref().parse() {
+            sets_allow_http |= key == allow_http;
+            builder = builder.with_config(key, value);
+        }
+    }
+    if builder
+        .get_config_value(&AmazonS3ConfigKey::ConditionalPut)
+        .is_none()
+    {
+        builder = builder.with_conditional_put(S3ConditionalPut::ETagMatch);
+    }
+    let is_http_endpoint = builder
+        .get_config_value(&AmazonS3ConfigKey::Endpoint)
+        .is_some_and(|endpoint| endpoint.starts_with("http://"));
+    if is_http_endpoint && !sets_allow_http {
+        builder = builder.with_allow_http(true);
+    }
+    builder
+}
+
+/// Create an Azure Blob Storage (or ADLS Gen2) store for `url` from the storage `options` (see
+/// [`AzureConfigKey`] for the supported keys; other keys are ignored). Clients may authenticate
+/// with an account key (`azure_storage_account_key`), a SAS token (`azure_storage_sas_key`), AAD
+/// client credentials (`azure_client_id`, `azure_client_secret` and `azure_tenant_id`), or a
+/// managed identity (the default, see e.g. `azure_msi_endpoint` and `azure_msi_resource_id`) --
+/// or with the credentials of a custom `credentials` provider.
+///
+/// Commits are written with conditional puts (`If-None-Match`), so that concurrent writers cannot
+/// overwrite each others' commits.
+///
+/// [`AzureConfigKey`]: object_store::azure::AzureConfigKey
+#[cfg(feature = "cloud")]
+pub fn parse_url_opts_azure<I, K, V>(
+    url: &Url,
+    options: I,
+    credentials: Option<AzureCredentialProvider>,
+) -> Result<(Box<dyn ObjectStore>, Path), Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let mut builder = options.into_iter().fold(
+        MicrosoftAzureBuilder::new().with_url(url.as_str()),
+        |builder, (key, value)| match key.as_ref().parse() {
+            Ok(key) => builder.with_config(key, value),
+            Err(_) => builder,
+        },
+    );
+    if let Some(credentials) = credentials {
+        builder = builder.with_credentials(credentials);
+    }
+    let path = Path::parse(url.path())?;
+    Ok((Box::new(builder.build()?), path))
+}
+
+/// Create a Google Cloud Storage store for `url` from the storage `options` (see
+/// [`GoogleConfigKey`] for the supported keys; other keys are ignored). Clients authenticate with a
+/// service account (a JSON key in `google_service_account_key`, or a key file in
+/// `google_service_account`), with the credentials of a custom `credentials` provider, or with the
+/// application default credentials: the file in `google_application_credentials` or the
+/// `GOOGLE_APPLICATION_CREDENTIALS` environment variable, gcloud's well-known credentials file, or
+/// the metadata server of the instance.
+///
+/// Commits are created with a precondition (`ifGenerationMatch=0`), so that concurrent writers
+/// cannot overwrite each others' commits.
+#[cfg(feature = "cloud")]
+pub fn parse_url_opts_gcs<I, K, V>(
+    url: &Url,
+    options: I,
+    credentials: Option<GcpCredentialProvider>,
+) -> Result<(Box<dyn ObjectStore>, Path), Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let application_credentials = std::env::var("GOOGLE_APPLICATION_CREDENTIALS").ok();
+    let mut builder = gcs_builder(url, options, application_credentials);
+    if let Some(credentials) = credentials {
+        builder = builder.with_credentials(credentials);
+    }
+    let path = Path::parse(url.path())?;
+    Ok((Box::new(builder.build()?), path))
+}
+
+// Use the `application_credentials` file unless the options configure other credentials
+#[cfg(feature = "cloud")]
+fn gcs_builder<I, K, V>(
+    url: &Url,
+    options: I,
+    application_credentials: Option<String>,
+) -> GoogleCloudStorageBuilder
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let builder = options.into_iter().fold(
+        GoogleCloudStorageBuilder::new().with_url(url.as_str()),
+        |builder, (key, value)| match key.as_ref().parse() {
+            Ok(key) => builder.with_config(key, value),
+            Err(_) => builder,
+        },
+    );
+    let has_credentials = [
+        GoogleConfigKey::ServiceAccount,
+        GoogleConfigKey::ServiceAccountKey,
+        GoogleConfigKey::ApplicationCredentials,
+    ]
+    .iter()
+    .any(|key| builder.get_config_value(key).is_some());
+    match application_credentials {
+        Some(path) if !has_credentials => builder.with_application_credentials(path),
+        _ => builder,
+    }
+}
+
+#[cfg(all(test, feature = "cloud"))]
+mod tests {
+    use super::*;
+
+    fn config(builder: &AmazonS3Builder, key: AmazonS3ConfigKey) -> Option<String> {
+        builder.get_config_value(&key)
+    }
+
+    #[test]
+    fn test_s3_builder() {
+        let url = Url::parse("s3://bucket/path/to/table").unwrap();
+        let builder = s3_builder(&url, [("aws_region", "eu-west-1"), ("unknown", "ignored")]);
+        assert_eq!(
+            config(&builder, AmazonS3ConfigKey::Region).as_deref(),
+            Some("eu-west-1")
+        );
+        assert_eq!(
+            config(&builder, AmazonS3ConfigKey::ConditionalPut).as_deref(),
+            Some("etag")
+        );
+        let allow_http = AmazonS3ConfigKey::Client(ClientConfigKey::AllowHttp);
+        assert_eq!(config(&builder, allow_http).as_deref(), Some("false"));
+
+        // S3-compatible stores with explicit put coordination
+        let builder = s3_builder(
+            &url,
+            [
+                ("aws_endpoint", "http://localhost:9000"),
+                ("aws_conditional_put", "dynamo:commits"),
+            ],
+        );
+        assert_eq!(
+            config(&builder, AmazonS3ConfigKey::ConditionalPut).as_deref(),
+            Some("dynamo:commits")
+        );
+        let allow_http = AmazonS3ConfigKey::Client(ClientConfigKey::AllowHttp);
+        assert_eq!(config(&builder, allow_http).as_deref(), Some("true"));
+
+        // an explicit setting takes precedence
+        let builder = s3_builder(
+            &url,
+            [
+                ("aws_endpoint", "http://localhost:9000"),
+                ("aws_allow_http", "false"),
+            ],
+        );
+        let allow_http = AmazonS3ConfigKey::Client(ClientConfigKey::AllowHttp);
+        assert_eq!(config(&builder, allow_http).as_deref(), Some("false"));
+    }
+
+    #[test]
+    fn test_parse_url_opts_s3() {
+        let url = Url::parse("s3a://bucket/path/to/table").unwrap();
+        let options = [
+            ("aws_region", "us-east-1"),
+            ("aws_access_key_id", "key"),
+            ("aws_secret_access_key", "secret"),
+        ];
+        let (store, path) = parse_url_opts(&url, options).unwrap();
+        assert_eq!(path.as_ref(), "path/to/table");
+        assert!(store.to_string().contains("bucket"), "{store}");
+    }
+
+    #[test]
+    fn test_parse_url_opts_azure() {
+        let options = [("azure_storage_account_key", "a2V5")];
+        let url =
+            Url::parse("abfss://container@account.dfs.core.windows.net/path/to/table").unwrap();
+        let (store, path) = parse_url_opts(&url, options).unwrap();
+        assert_eq!(path.as_ref(), "path/to/table");
+        assert!(store.to_string().contains("container"), "{store}");
+
+        let options = [
+            ("azure_storage_account_name", "account"),
+            ("sas_token", "sig=abc"),
+        ];
+        let url = Url::parse("az://container/table").unwrap();
+        let (store, path) = parse_url_opts(&url, options).unwrap();
+        assert_eq!(path.as_ref(), "table");
+        assert!(store.to_string().contains("container"), "{store}");
+    }
+
+    #[test]
+    fn test_gcs_builder() {
+        let url = Url::parse("gs://bucket/path/to/table").unwrap();
+        let adc = Some("/path/to/adc.json".to_string());
+        let builder = gcs_builder(&url, std::iter::empty::<(&str, &str)>(), adc.clone());
+        assert_eq!(
+            builder.get_config_value(&GoogleConfigKey::ApplicationCredentials),
+            adc
+        );
+
+        // explicitly configured credentials take precedence
+        let options = [("google_service_account_key", "{}")];
+        let builder = gcs_builder(&url, options, adc);
+        assert_eq!(
+            builder.get_config_value(&GoogleConfigKey::ApplicationCredentials),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_url_opts_gcs() {
+        let mut adc = tempfile::NamedTempFile::new().unwrap();
+        let credentials = r#"{"type":"authorized_user","client_id":"id","client_secret":"secret","refresh_token":"token"}"#;
+        std::io::Write::write_all(&mut adc, credentials.as_bytes()).unwrap();
+        let options = [(
+            "google_application_credentials",
+            adc.path().to_str().unwrap(),
+        )];
+        let url = Url::parse("gs://bucket/path/to/table").unwrap();
+        let (store, path) = parse_url_opts(&url, options).unwrap();
+        assert_eq!(path.as_ref(), "path/to/table");
+        assert!(store.to_string().contains("bucket"), "{store}");
+    }
+
+    #[test]
+    fn test_put_error() {
+        let source = || "exists".into();
+        let path = "_delta_log/00000000000000000001.json".to_string();
+        let error = Error::Precondition {
+            path: path.clone(),
+            source: source(),
+        };
+        assert!(matches!(
+            put_error(error, path.clone()),
+            crate::Error::FileAlreadyExists(p) if p == path
+        ));
+        let error = Error::NotImplemented;
+        assert!(matches!(
+            put_error(error, path),
+            crate::Error::ObjectStore(Error::NotImplemented)
+        ));
+    }
+
+    #[test]
+    fn test_external_stores() {
+        let table_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::new());
+        let source_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::new());
+        let mut stores = ExternalStores::default();
+        stores.insert(
+            &Url::parse("s3://source/table/").unwrap(),
+            source_store.clone(),
+        );
+        let file = Url::parse("s3://other/table/part.parquet").unwrap();
+        // the storage of the table isn't known, so the file may be in it
+        let store = stores.store_for(&file, &table_store).unwrap();
+        assert!(Arc::ptr_eq(&store, &table_store));
+
+        stores.set_table_root(&Url::parse("s3://bucket/table/").unwrap());
+        let file = Url::parse("s3://bucket/table/part.parquet").unwrap();
+        let store = stores.store_for(&file, &table_store).unwrap();
+        assert!(Arc::ptr_eq(&store, &table_store));
+        let file = Url::parse("s3://source/table/part.parquet").unwrap();
+        let store = stores.store_for(&file, &table_store).unwrap();
+        assert!(Arc::ptr_eq(&store, &source_store));
+        let file = Url::parse("s3://other/table/part.parquet").unwrap();
+        let err = stores.store_for(&file, &table_store).unwrap_err();
+        assert!(err.to_string().contains("storage s3://other/ of"), "{err}");
+    }
+}
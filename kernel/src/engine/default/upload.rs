@@ -0,0 +1,333 @@
+//! Multipart uploads of large files by the default engine.
+//!
+//! Writing a large file (e.g. a data file or a checkpoint) with a single request is slow, and fails
+//! as a whole on any error. Instead, the [`DefaultEngine`] uploads files larger than the part size
+//! of its [`MultipartUploadConfig`] in parts, using the multipart upload API of the store (S3
+//! multipart uploads, Azure block blobs and GCS XML multipart uploads). The parts are uploaded
+//! concurrently, and the upload is aborted if any of them fails, so that the store discards the
+//! parts already uploaded rather than keeping (and billing for) them.
+//!
+//! A part which fails with a transient error is retried according to the `Put` policy of the
+//! [`RetryConfig`] of the engine. Since the parts of an object store multipart upload are numbered
+//! in the order they are put, a single part can't be put again, so the part is retried by starting
+//! the upload over.
+//!
+//! [`DefaultEngine`]: super::DefaultEngine
+//! [`RetryConfig`]: super::retry::RetryConfig
+
+use bytes::Bytes;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{DynObjectStore, MultipartUpload};
+use tracing::warn;
+
+use super::retry::{Retrier, StorageOperation};
+use crate::DeltaResult;
+
+/// The smallest part size accepted by S3, which rejects smaller parts (other than the last one).
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// How the default engine uploads large files in parts. See [upload](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultipartUploadConfig {
+    part_size: usize,
+    max_concurrent_parts: usize,
+}
+
+impl Default for MultipartUploadConfig {
+    fn default() -> Self {
+        Self {
+            part_size: 10 * 1024 * 1024,
+            max_concurrent_parts: 8,
+        }
+    }
+}
+
+impl MultipartUploadConfig {
+    /// Upload files larger than `part_size` bytes in parts of `part_size` bytes (but the last).
+    /// Part sizes smaller than 5 MiB, the minimum of S3, are raised to 5 MiB.
+    ///
+    /// Defaults to 10 MiB.
+    pub fn with_part_size(mut self, part_size: usize) -> Self {
+        self.part_size = part_size.max(MIN_PART_SIZE);
+        self
+    }
+
+    /// Set the maximum number of parts of a file uploaded concurrently.
+    ///
+    /// Defaults to 8.
+    pub fn with_max_concurrent_parts(mut self, max_concurrent_parts: usize) -> Self {
+        self.max_concurrent_parts = max_concurrent_parts.max(1);
+        self
+    }
+
+    /// The size of the parts of an upload, in bytes.
+    pub fn part_size(&self) -> usize {
+        self.part_size
+    }
+
+    /// The maximum number of parts of a file uploaded concurrently.
+    pub fn max_concurrent_parts(&self) -> usize {
+        self.max_concurrent_parts
+    }
+
+    /// Write `data` to `path` in `store`, with a single request if it fits in one part and with a
+    /// multipart upload otherwise, which is retried by `retrier` (if any) if one of its parts
+    /// fails.
+    pub(crate) async fn put(
+        &self,
+        store: &DynObjectStore,
+        path: &Path,
+        data: Bytes,
+        retrier: Option<&Retrier>,
+    ) -> DeltaResult<()> {
+        if data.len() <= self.part_size {
+            store.put(path, data.into()).await?;
+            return Ok(());
+        }
+        let upload = || self.put_multipart(store, path, data.clone());
+        match retrier {
+            Some(retrier) => retrier.retry(StorageOperation::Put, upload).await?,
+            None => upload().await?,
+        }
+        Ok(())
+    }
+
+    // Write `data` to `path` with a multipart upload, which is aborted if it fails
+    async fn put_multipart(
+        &self,
+        store: &DynObjectStore,
+        path: &Path,
+        data: Bytes,
+    ) -> object_store::Result<()> {
+        let mut upload = store.put_multipart(path).await?;
+        match self.put_parts(upload.as_mut(), data).await {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                // discard the parts already uploaded, but report the error which failed the upload
+                if let Err(abort_err) = upload.abort().await {
+                    warn!("Failed to abort the multipart upload of {path}: {abort_err}");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    // Upload the parts of `data` and complete the upload
+    async fn put_parts(
+        &self,
+        upload: &mut dyn MultipartUpload,
+        data: Bytes,
+    ) -> object_store::Result<()> {
+        let parts = (0..data.len()).step_by(self.part_size).map(|start| {
+            let end = data.len().min(start + self.part_size);
+            data.slice(start..end)
+        });
+        // `put_part` must be called in order of the parts, but the parts may upload concurrently
+        stream::iter(parts)
+            .map(|part| upload.put_part(part.into()))
+            .buffer_unordered(self.max_concurrent_parts)
+            .try_collect::<()>()
+            .await?;
+        upload.complete().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use object_store::memory::InMemory;
+    use object_store::{
+        GetOptions, GetResult, ListResult, ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions,
+        PutPayload, PutResult, Result, UploadPart,
+    };
+
+    use super::*;
+    use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use crate::engine::default::retry::{RetryConfig, RetryPolicy};
+
+    const MIB: usize = 1024 * 1024;
+
+    // A store whose first `failures` multipart uploads fail on their second part, and which counts
+    // the aborted uploads
+    #[derive(Debug, Default)]
+    struct FailingMultipartStore {
+        inner: InMemory,
+        failures: AtomicUsize,
+        aborted: Arc<AtomicUsize>,
+    }
+
+    #[derive(Debug)]
+    struct FailingUpload {
+        inner: Box<dyn MultipartUpload>,
+        parts: usize,
+        aborted: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl MultipartUpload for FailingUpload {
+        fn put_part(&mut self, data: PutPayload) -> UploadPart {
+            self.parts += 1;
+            if self.parts == 2 {
+                return Box::pin(async {
+                    Err(object_store::Error::Generic {
+                        store: "test",
+                        source: "part failed".into(),
+                    })
+                });
+            }
+            self.inner.put_part(data)
+        }
+
+        async fn complete(&mut self) -> Result<PutResult> {
+            unreachable!("the upload failed")
+        }
+
+        async fn abort(&mut self) -> Result<()> {
+            self.aborted.fetch_add(1, Ordering::SeqCst);
+            self.inner.abort().await
+        }
+    }
+
+    impl std::fmt::Display for FailingMultipartStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "FailingMultipartStore")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for FailingMultipartStore {
+        async fn put_opts(
+            &self,
+            location: &Path,
+            payload: PutPayload,
+            opts: PutOptions,
+        ) -> Result<PutResult> {
+            self.inner.put_opts(location, payload, opts).await
+        }
+
+        async fn put_multipart_opts(
+            &self,
+            location: &Path,
+            opts: PutMultipartOpts,
+        ) -> Result<Box<dyn MultipartUpload>> {
+            let upload = self.inner.put_multipart_opts(location, opts).await?;
+            let fail = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if !fail {
+                return Ok(upload);
+            }
+            Ok(Box::new(FailingUpload {
+                inner: upload,
+                parts: 0,
+                aborted: self.aborted.clone(),
+            }))
+        }
+
+        async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+            self.inner.get_opts(location, options).await
+        }
+
+        async fn delete(&self, location: &Path) -> Result<()> {
+            self.inner.delete(location).await
+        }
+
+        fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> futures::stream::BoxStream<'_, Result<ObjectMeta>> {
+            self.inner.list(prefix)
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn failing_store(failures: usize) -> FailingMultipartStore {
+        FailingMultipartStore {
+            failures: AtomicUsize::new(failures),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload() {
+        let store = InMemory::new();
+        let config = MultipartUploadConfig::default().with_part_size(0);
+        assert_eq!(config.part_size(), 5 * MIB);
+
+        let data: Bytes = (0..12 * MIB).map(|i| i as u8).collect::<Vec<_>>().into();
+        let path = Path::from("large");
+        config.put(&store, &path, data.clone(), None).await.unwrap();
+        let written = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(written, data);
+
+        let path = Path::from("small");
+        config
+            .put(&store, &path, data.slice(..MIB), None)
+            .await
+            .unwrap();
+        let written = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(written, data.slice(..MIB));
+    }
+
+    #[tokio::test]
+    async fn test_failed_multipart_upload_is_aborted() {
+        let store = failing_store(1);
+        let config = MultipartUploadConfig::default()
+            .with_part_size(5 * MIB)
+            .with_max_concurrent_parts(1);
+        let data = Bytes::from(vec![0; 12 * MIB]);
+        let path = Path::from("large");
+        let result = config.put(&store, &path, data, None).await;
+        assert!(result.unwrap_err().to_string().contains("part failed"));
+        assert_eq!(store.aborted.load(Ordering::SeqCst), 1);
+        assert!(store.head(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_multipart_upload_is_retried() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let retry_config = RetryConfig::default().with_policy(policy);
+        let retrier = Retrier::new(retry_config, Arc::new(TokioBackgroundExecutor::new()));
+        let config = MultipartUploadConfig::default().with_part_size(5 * MIB);
+        let data: Bytes = (0..12 * MIB).map(|i| i as u8).collect::<Vec<_>>().into();
+        let path = Path::from("large");
+
+        let store = failing_store(2);
+        config
+            .put(&store, &path, data.clone(), Some(&retrier))
+            .await
+            .unwrap();
+        assert_eq!(store.aborted.load(Ordering::SeqCst), 2);
+        let written = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(written, data);
+
+        // the upload fails once its attempts are exhausted
+        let store = failing_store(3);
+        let result = config.put(&store, &path, data, Some(&retrier)).await;
+        assert!(result.unwrap_err().to_string().contains("part failed"));
+        assert_eq!(store.aborted.load(Ordering::SeqCst), 3);
+        assert!(store.head(&path).await.is_err());
+    }
+}
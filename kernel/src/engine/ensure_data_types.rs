@@ -59,10 +59,11 @@ impl EnsureDataTypes {
             (DataType::Primitive(_), _) if arrow_type.is_primitive() => {
                 check_cast_compat(kernel_type.try_into()?, arrow_type)
             }
-            // strings, bools, and binary  aren't primitive in arrow
+            // strings, bools, and binary  aren't primitive in arrow. Strings and binary may also
+            // be read as views (see `DefaultParquetHandler::with_view_types`)
             (&DataType::BOOLEAN, ArrowDataType::Boolean)
-                | (&DataType::STRING, ArrowDataType::Utf8)
-                | (&DataType::BINARY, ArrowDataType::Binary) => {
+                | (&DataType::STRING, ArrowDataType::Utf8 | ArrowDataType::Utf8View)
+                | (&DataType::BINARY, ArrowDataType::Binary | ArrowDataType::BinaryView) => {
                     Ok(DataTypeCompat::Identical)
                 }
             (DataType::Array(inner_type), ArrowDataType::List(arrow_list_field)) => {
@@ -98,7 +99,11 @@ impl EnsureDataTypes {
                 )?;
                 Ok(DataTypeCompat::Nested)
             }
-            (DataType::Struct(kernel_fields), ArrowDataType::Struct(arrow_fields)) => {
+            // variants are physically represented as structs
+            (
+                DataType::Struct(kernel_fields) | DataType::Variant(kernel_fields),
+                ArrowDataType::Struct(arrow_fields),
+            ) => {
                 // build a list of kernel fields that matches the order of the arrow fields
                 let mapped_fields = arrow_fields
                     .iter()
@@ -0,0 +1,360 @@
+//! Memory accounting for the arrow-based engines.
+//!
+//! A [`MemoryPool`] is a budget of bytes, shared by the handlers of an engine. Each batch of data
+//! the handlers produce (e.g. read from parquet or JSON files, or computed by evaluating an
+//! expression) reserves its (estimated) size in the pool for as long as it's held as
+//! [`ArrowEngineData`]. The memory of a batch is reserved before the batch is allocated, based on
+//! an estimate of its size (the size of the previous batch of a file, the size of the JSON
+//! strings being parsed, or the size of the input of an expression), and adjusted to the actual
+//! size of the batch once it's produced. Once a batch would exceed the budget, the operation
+//! producing it fails with [`Error::MemoryLimitExceeded`] rather than growing the memory use of
+//! the process further.
+//!
+//! Note that sizes are estimated by [`RecordBatch::get_array_memory_size`], which counts buffers
+//! shared by several batches (e.g. the columns of a batch and a projection of it) once per batch.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow_array::RecordBatch;
+#[cfg(feature = "default-engine-base")]
+use futures::stream::{BoxStream, Stream, StreamExt, TryStreamExt};
+
+use crate::engine::arrow_data::ArrowEngineData;
+use crate::schema::{DataType, SchemaRef};
+use crate::{
+    DeltaResult, EngineData, Error, Expression, ExpressionEvaluator, ExpressionHandler, Scalar,
+};
+
+#[derive(Debug)]
+struct PoolState {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+/// A budget of bytes for the data produced by an engine. Clones of a pool share its budget. See
+/// the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct MemoryPool {
+    state: Arc<PoolState>,
+}
+
+impl MemoryPool {
+    /// Create a pool of `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        let state = PoolState {
+            limit,
+            used: AtomicUsize::new(0),
+        };
+        Self {
+            state: Arc::new(state),
+        }
+    }
+
+    /// The number of bytes in the pool
+    pub fn limit(&self) -> usize {
+        self.state.limit
+    }
+
+    /// The number of bytes currently reserved
+    pub fn used(&self) -> usize {
+        self.state.used.load(Ordering::Acquire)
+    }
+
+    /// Reserve `size` bytes until the returned reservation is dropped. Fails with
+    /// [`Error::MemoryLimitExceeded`] if fewer bytes are available.
+    pub fn try_reserve(&self, size: usize) -> DeltaResult<MemoryReservation> {
+        self.grow(size)?;
+        Ok(MemoryReservation {
+            pool: self.clone(),
+            size,
+        })
+    }
+
+    // Add `size` bytes to the bytes in use, unless that exceeds the limit of the pool
+    fn grow(&self, size: usize) -> DeltaResult<()> {
+        let limit = self.state.limit;
+        self.state
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(size).filter(|total| *total <= limit)
+            })
+            .map_err(|used| {
+                Error::memory_limit_exceeded(format!(
+                    "Cannot reserve {size} bytes, {used} of {limit} bytes are in use"
+                ))
+            })?;
+        Ok(())
+    }
+
+    fn shrink(&self, size: usize) {
+        self.state.used.fetch_sub(size, Ordering::AcqRel);
+    }
+}
+
+/// Bytes reserved in a [`MemoryPool`], which are released when the reservation is dropped.
+#[derive(Debug)]
+pub struct MemoryReservation {
+    pool: MemoryPool,
+    size: usize,
+}
+
+impl MemoryReservation {
+    /// The number of bytes reserved
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Grow or shrink the reservation to `size` bytes. Fails with
+    /// [`Error::MemoryLimitExceeded`] (keeping the current size) if the additional bytes are not
+    /// available.
+    pub fn try_resize(&mut self, size: usize) -> DeltaResult<()> {
+        if size > self.size {
+            self.pool.grow(size - self.size)?;
+        } else {
+            self.pool.shrink(self.size - size);
+        }
+        self.size = size;
+        Ok(())
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.pool.shrink(self.size);
+    }
+}
+
+// Wrap `batch` into engine data which holds `reservation`, resized to the size of the batch
+fn track_reserved(
+    mut reservation: MemoryReservation,
+    batch: RecordBatch,
+) -> DeltaResult<ArrowEngineData> {
+    reservation.try_resize(batch.get_array_memory_size())?;
+    Ok(ArrowEngineData::new(batch).with_reservation(reservation))
+}
+
+/// The (estimated) size of the memory of `data`, or 0 if it's not [`ArrowEngineData`].
+pub(crate) fn memory_size(data: &dyn EngineData) -> usize {
+    data.any_ref()
+        .downcast_ref::<ArrowEngineData>()
+        .map_or(0, |data| data.record_batch().get_array_memory_size())
+}
+
+/// Wrap the batches of `batches` into engine data, which holds reservations of their sizes in
+/// `pool` (if any). The memory of each batch is reserved before the batch is read, estimated by
+/// the size of the previous batch, and the stream ends once a reservation fails.
+#[cfg(feature = "default-engine-base")]
+pub(crate) fn track_batches(
+    pool: Option<MemoryPool>,
+    batches: impl Stream<Item = DeltaResult<RecordBatch>> + Send + 'static,
+) -> BoxStream<'static, DeltaResult<ArrowEngineData>> {
+    let Some(pool) = pool else {
+        return batches.map_ok(ArrowEngineData::new).boxed();
+    };
+    let state = (Some(batches.boxed()), 0);
+    futures::stream::unfold(state, move |(batches, estimate)| {
+        let pool = pool.clone();
+        async move {
+            let mut batches = batches?;
+            let reservation = match pool.try_reserve(estimate) {
+                Ok(reservation) => reservation,
+                Err(e) => return Some((Err(e), (None, estimate))),
+            };
+            let (data, estimate) = match batches.next().await? {
+                Ok(batch) => {
+                    let size = batch.get_array_memory_size();
+                    (track_reserved(reservation, batch), size)
+                }
+                Err(e) => (Err(e), estimate),
+            };
+            Some((data, (Some(batches), estimate)))
+        }
+    })
+    .boxed()
+}
+
+/// Produce engine data with `produce`, reserving `estimate` bytes for it in `pool` (if any)
+/// beforehand. The data holds a reservation of its actual size once produced.
+pub(crate) fn track_produced(
+    pool: Option<&MemoryPool>,
+    estimate: usize,
+    produce: impl FnOnce() -> DeltaResult<Box<dyn EngineData>>,
+) -> DeltaResult<Box<dyn EngineData>> {
+    let Some(pool) = pool else {
+        return produce();
+    };
+    let reservation = pool.try_reserve(estimate)?;
+    let (batch, previous_reservation) =
+        ArrowEngineData::try_from_engine_data(produce()?)?.into_parts();
+    drop(previous_reservation);
+    Ok(Box::new(track_reserved(reservation, batch)?))
+}
+
+/// An [`ExpressionHandler`] whose evaluators reserve the size of their results in a
+/// [`MemoryPool`].
+#[derive(Debug)]
+pub struct MemoryLimitedExpressionHandler<H> {
+    inner: Arc<H>,
+    pool: MemoryPool,
+}
+
+impl<H: ExpressionHandler> MemoryLimitedExpressionHandler<H> {
+    pub fn new(inner: Arc<H>, pool: MemoryPool) -> Self {
+        Self { inner, pool }
+    }
+}
+
+impl<H: ExpressionHandler> ExpressionHandler for MemoryLimitedExpressionHandler<H> {
+    fn get_evaluator(
+        &self,
+        schema: SchemaRef,
+        expression: Expression,
+        output_type: DataType,
+    ) -> Arc<dyn ExpressionEvaluator> {
+        Arc::new(MemoryLimitedExpressionEvaluator {
+            inner: self.inner.get_evaluator(schema, expression, output_type),
+            pool: self.pool.clone(),
+        })
+    }
+
+    fn create_one(&self, schema: SchemaRef, values: &[Scalar]) -> DeltaResult<Box<dyn EngineData>> {
+        track_produced(Some(&self.pool), 0, || {
+            self.inner.create_one(schema, values)
+        })
+    }
+}
+
+struct MemoryLimitedExpressionEvaluator {
+    inner: Arc<dyn ExpressionEvaluator>,
+    pool: MemoryPool,
+}
+
+impl ExpressionEvaluator for MemoryLimitedExpressionEvaluator {
+    fn evaluate(&self, batch: &dyn EngineData) -> DeltaResult<Box<dyn EngineData>> {
+        // the result of an expression is usually no larger than its input
+        track_produced(Some(&self.pool), memory_size(batch), || {
+            self.inner.evaluate(batch)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::Int64Array;
+    use arrow_schema::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+
+    use super::*;
+    use crate::engine::arrow_expression::ArrowExpressionHandler;
+    use crate::expressions::column_expr;
+    use crate::schema::{StructField, StructType};
+
+    fn batch(len: usize) -> RecordBatch {
+        let schema = ArrowSchema::new(vec![Field::new("a", ArrowDataType::Int64, false)]);
+        let column = Int64Array::from_iter_values(0..len as i64);
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(column)]).unwrap()
+    }
+
+    fn track(pool: &MemoryPool, batch: RecordBatch) -> DeltaResult<ArrowEngineData> {
+        track_reserved(pool.try_reserve(0)?, batch)
+    }
+
+    #[test]
+    fn test_reservations() {
+        let pool = MemoryPool::new(100);
+        let reservation = pool.try_reserve(60).unwrap();
+        assert_eq!(pool.used(), 60);
+
+        let err = pool.try_reserve(50).unwrap_err();
+        assert!(matches!(err, Error::MemoryLimitExceeded(_)));
+        assert_eq!(pool.used(), 60);
+
+        drop(reservation);
+        assert_eq!(pool.used(), 0);
+        let _reservation = pool.try_reserve(100).unwrap();
+    }
+
+    #[test]
+    fn test_track_batches() {
+        let batch = batch(1000);
+        let size = batch.get_array_memory_size();
+        let pool = MemoryPool::new(2 * size);
+
+        let data = track(&pool, batch.clone()).unwrap();
+        assert_eq!(pool.used(), size);
+        let _data2 = track_produced(Some(&pool), 0, || Ok(Box::new(data))).unwrap();
+        assert_eq!(pool.used(), size);
+        let data3 = track(&pool, batch.clone()).unwrap();
+        assert_eq!(pool.used(), 2 * size);
+
+        assert!(track(&pool, batch.clone()).is_err());
+        // taking the engine data apart keeps its reservation
+        let (_batch, reservation) = data3.into_parts();
+        assert_eq!(pool.used(), 2 * size);
+        drop(reservation);
+        assert_eq!(pool.used(), size);
+    }
+
+    #[test]
+    fn test_reserve_before_producing() {
+        let size = batch(1000).get_array_memory_size();
+        let pool = MemoryPool::new(size);
+        let _reservation = pool.try_reserve(1).unwrap();
+        let result = track_produced(Some(&pool), size, || {
+            panic!("the data must not be produced")
+        });
+        assert!(matches!(result, Err(Error::MemoryLimitExceeded(_))));
+    }
+
+    #[cfg(feature = "default-engine-base")]
+    #[tokio::test]
+    async fn test_track_batches_reserves_before_reading() {
+        use std::sync::atomic::AtomicUsize;
+
+        let batch = batch(1000);
+        let size = batch.get_array_memory_size();
+        let pool = MemoryPool::new(size + size / 2);
+        let reads = Arc::new(AtomicUsize::new(0));
+        let batches = futures::stream::iter([batch.clone(), batch.clone(), batch]).map({
+            let reads = reads.clone();
+            move |batch| {
+                reads.fetch_add(1, Ordering::SeqCst);
+                Ok(batch)
+            }
+        });
+        let mut stream = track_batches(Some(pool.clone()), batches);
+
+        let _data = stream.next().await.unwrap().unwrap();
+        assert_eq!(pool.used(), size);
+        // the next batch is not read, since the memory of a batch like the previous one isn't
+        // available
+        let result = stream.next().await.unwrap();
+        assert!(matches!(result, Err(Error::MemoryLimitExceeded(_))));
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn test_memory_limited_expression_handler() {
+        let input = batch(1000);
+        let size = input.get_array_memory_size();
+        let pool = MemoryPool::new(size);
+        let handler =
+            MemoryLimitedExpressionHandler::new(Arc::new(ArrowExpressionHandler::default()), pool);
+        let schema = Arc::new(StructType::new([StructField::new(
+            "a",
+            DataType::LONG,
+            false,
+        )]));
+        let evaluator = handler.get_evaluator(schema, column_expr!("a"), DataType::LONG);
+
+        let input = ArrowEngineData::new(input);
+        let result = evaluator.evaluate(&input).unwrap();
+        assert!(matches!(
+            evaluator.evaluate(&input),
+            Err(Error::MemoryLimitExceeded(_))
+        ));
+        drop(result);
+        evaluator.evaluate(&input).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+//! An implementation of late materialization for parquet reads: the columns referenced by a
+//! predicate are decoded and the predicate evaluated first, so that the other columns are only
+//! decoded for the rows which may satisfy it.
+use arrow_array::{Array, BooleanArray, RecordBatch};
+use arrow_schema::ArrowError;
+use parquet::arrow::arrow_reader::{ArrowPredicateFn, ArrowReaderBuilder, RowFilter};
+use parquet::arrow::ProjectionMask;
+use tracing::debug;
+
+use crate::engine::arrow_expression::evaluate_expression;
+use crate::engine::parquet_row_group_skipping::compute_field_indices;
+use crate::expressions::ExpressionRef;
+
+/// An extension trait for [`ArrowReaderBuilder`] that injects row filtering capability.
+pub(crate) trait ParquetRowFilter {
+    /// Instructs the parquet reader to skip the rows for which the given `predicate` is false.
+    /// Rows for which it is null are kept, since the predicate is only a hint. If the file lacks
+    /// any of the columns of the predicate, or the predicate cannot be evaluated over a batch, no
+    /// rows are skipped.
+    fn with_predicate_row_filter(self, predicate: &ExpressionRef) -> Self;
+}
+
+impl<T> ParquetRowFilter for ArrowReaderBuilder<T> {
+    fn with_predicate_row_filter(self, predicate: &ExpressionRef) -> Self {
+        let Some(filter) = row_filter(self.parquet_schema(), predicate) else {
+            return self;
+        };
+        self.with_row_filter(filter)
+    }
+}
+
+// The row filter for `predicate`, if all of the (leaf) columns it references are in the file
+fn row_filter(
+    parquet_schema: &parquet::schema::types::SchemaDescriptor,
+    predicate: &ExpressionRef,
+) -> Option<RowFilter> {
+    let referenced = predicate.references();
+    if referenced.is_empty() {
+        return None;
+    }
+    let field_indices = compute_field_indices(parquet_schema.columns(), predicate);
+    if field_indices.len() != referenced.len() {
+        debug!("not filtering rows by {predicate:?}, which references columns not in the file");
+        return None;
+    }
+    let mask = ProjectionMask::leaves(parquet_schema, field_indices.into_values());
+    let predicate = predicate.clone();
+    let filter = ArrowPredicateFn::new(mask, move |batch: RecordBatch| {
+        Ok::<_, ArrowError>(evaluate_filter(&predicate, &batch))
+    });
+    Some(RowFilter::new(vec![Box::new(filter)]))
+}
+
+// Evaluate `predicate` over `batch`, keeping the rows for which it's not false
+fn evaluate_filter(predicate: &ExpressionRef, batch: &RecordBatch) -> BooleanArray {
+    let result = evaluate_expression(predicate, batch, None);
+    match result.as_ref().map(|array| array.as_any().downcast_ref()) {
+        Ok(Some(result)) => {
+            let result: &BooleanArray = result;
+            result
+                .iter()
+                .map(|keep| Some(keep != Some(false)))
+                .collect()
+        }
+        _ => {
+            debug!("not filtering rows by {predicate:?}, which failed to evaluate: {result:?}");
+            BooleanArray::from(vec![true; batch.num_rows()])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Int64Array, StringArray};
+    use bytes::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::arrow::ArrowWriter;
+
+    use super::*;
+    use crate::expressions::{column_expr, Expression};
+
+    // A parquet file with an int column `a` (with a null in place of 3) and a string column `b`
+    fn parquet_file() -> Bytes {
+        let a = Int64Array::from_iter((0..10).map(|i| (i != 3).then_some(i)));
+        let b = StringArray::from_iter_values((0..10).map(|i| format!("b{i}")));
+        let batch =
+            RecordBatch::try_from_iter(vec![("a", Arc::new(a) as _), ("b", Arc::new(b) as _)])
+                .unwrap();
+        let mut buffer = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        buffer.into()
+    }
+
+    fn read_column_b(predicate: Expression) -> Vec<String> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(parquet_file()).unwrap();
+        let reader = builder
+            .with_predicate_row_filter(&Arc::new(predicate))
+            .build()
+            .unwrap();
+        reader
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                let column = batch.column_by_name("b").unwrap();
+                let column = column.as_any().downcast_ref::<StringArray>().unwrap();
+                column
+                    .iter()
+                    .map(|b| b.unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        // rows for which the predicate is null are kept
+        let rows = read_column_b(column_expr!("a").gt(Expression::literal(6i64)));
+        assert_eq!(rows, ["b3", "b7", "b8", "b9"]);
+
+        let rows = read_column_b(column_expr!("a").is_not_null());
+        assert_eq!(rows.len(), 9);
+
+        let rows = read_column_b(Expression::and(
+            column_expr!("a").gt(Expression::literal(2i64)),
+            column_expr!("b").ne(Expression::literal("b4")),
+        ));
+        assert_eq!(rows, ["b3", "b5", "b6", "b7", "b8", "b9"]);
+    }
+
+    #[test]
+    fn test_keep_rows_if_predicate_cannot_be_evaluated() {
+        // a column which is not in the file
+        let rows = read_column_b(column_expr!("c").gt(Expression::literal(6i64)));
+        assert_eq!(rows.len(), 10);
+
+        // a comparison of mismatched types
+        let rows = read_column_b(column_expr!("a").gt(Expression::literal("6")));
+        assert_eq!(rows.len(), 10);
+
+        // no columns at all
+        let rows = read_column_b(Expression::literal(false));
+        assert_eq!(rows.len(), 10);
+    }
+}
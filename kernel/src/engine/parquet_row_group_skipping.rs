@@ -233,7 +233,7 @@ pub(crate) fn compute_field_indices(
             Struct(fields) => fields.iter().for_each(recurse),
             Unary(UnaryExpression { expr, .. }) => recurse(expr),
             Binary(BinaryExpression { left, right, .. }) => [left, right].iter().for_each(|e| recurse(e)),
-            Variadic(VariadicExpression { exprs, .. }) => exprs.iter().for_each(recurse),
+            Variadic(VariadicExpression { exprs, .. }) => exprs.iter().for_each(|e| recurse(e)),
         }
     }
 
@@ -1,5 +1,5 @@
 use super::*;
-use crate::expressions::{column_expr, Expression as Expr};
+use crate::expressions::{column_expr, Expression as Expr, ExpressionRef};
 use crate::predicates::PredicateEvaluator;
 use crate::DataType;
 
@@ -97,6 +97,7 @@ fn test_junctions() {
                 Some(v) => Expr::literal(v),
                 None => Expr::null_literal(DataType::BOOLEAN),
             })
+            .map(ExpressionRef::new)
             .collect();
 
         expect_eq!(
@@ -0,0 +1,364 @@
+//! Support for writing data to partitioned tables: splitting a batch into the partitions of a
+//! table, and serializing partition values and partition directories as described by the
+//! [Delta protocol](https://github.com/delta-io/delta/blob/master/PROTOCOL.md#partition-value-serialization).
+
+use std::collections::HashMap;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::TimestampMicrosecondType;
+use arrow_array::{Array, ArrayRef, RecordBatch, UInt32Array};
+use arrow_schema::{DataType as ArrowDataType, TimeUnit};
+use indexmap::IndexMap;
+use url::Url;
+
+use crate::{DeltaResult, Error};
+
+/// The directory name used for null partition values, following Hive.
+pub const NULL_PARTITION_DIRECTORY_VALUE: &str = crate::scan::HIVE_DEFAULT_PARTITION;
+
+/// The rows of a batch that belong to one partition of a table.
+#[derive(Debug)]
+pub struct PartitionData {
+    /// The rows of the partition, without the partition columns.
+    pub data: RecordBatch,
+    /// The serialized partition values of the rows, keyed by partition column, as expected in the
+    /// `partitionValues` of an `add` action. Null partition values are absent from the map.
+    pub partition_values: HashMap<String, String>,
+    /// The directory of the partition relative to the table root, e.g. `date=2024-01-01/`. Column
+    /// names and values are escaped like Hive does, but not URL encoded.
+    pub partition_path: String,
+}
+
+impl PartitionData {
+    /// The URL of the directory of the partition within `table_root` (which must be a directory
+    /// URL, i.e. end with a `/`), in which the data files of the partition are written.
+    pub fn target_dir(&self, table_root: &Url) -> DeltaResult<Url> {
+        if !table_root.path().ends_with('/') {
+            return Err(Error::generic(format!(
+                "Path must end with a trailing slash: {table_root}"
+            )));
+        }
+        let mut target_dir = table_root.clone();
+        target_dir
+            .path_segments_mut()
+            .map_err(|_| Error::generic(format!("Invalid table root: {table_root}")))?
+            .pop_if_empty()
+            .extend(self.partition_path.split('/').filter(|s| !s.is_empty()))
+            .push("");
+        Ok(target_dir)
+    }
+}
+
+/// Split `batch` into the partitions given by the values of its `partition_columns`, in the order
+/// in which each partition first occurs in the batch. The partition columns are removed from the
+/// data of each partition.
+///
+/// Fails if a partition column is missing from the batch, or its type cannot be a partition
+/// column (i.e. is not primitive).
+pub fn split_by_partition(
+    batch: &RecordBatch,
+    partition_columns: &[String],
+) -> DeltaResult<Vec<PartitionData>> {
+    let schema = batch.schema();
+    let partition_indices = partition_columns
+        .iter()
+        .map(|column| {
+            schema
+                .index_of(column)
+                .map_err(|_| Error::missing_column(column))
+        })
+        .collect::<DeltaResult<Vec<_>>>()?;
+    let values = partition_indices
+        .iter()
+        .map(|&index| serialize_partition_values(batch.column(index)))
+        .collect::<DeltaResult<Vec<_>>>()?;
+    let data_indices: Vec<_> = (0..schema.fields().len())
+        .filter(|index| !partition_indices.contains(index))
+        .collect();
+    let data = batch.project(&data_indices)?;
+    if partition_columns.is_empty() {
+        return Ok(vec![PartitionData {
+            data,
+            partition_values: HashMap::new(),
+            partition_path: String::new(),
+        }]);
+    }
+
+    let mut rows_by_partition: IndexMap<Vec<Option<&str>>, Vec<u32>> = IndexMap::new();
+    for row in 0..batch.num_rows() {
+        let key = values.iter().map(|column| column[row].as_deref()).collect();
+        let row = u32::try_from(row).map_err(|_| Error::generic("Too many rows in batch"))?;
+        rows_by_partition.entry(key).or_default().push(row);
+    }
+    rows_by_partition
+        .into_iter()
+        .map(|(key, rows)| {
+            let data = arrow_select::take::take_record_batch(&data, &UInt32Array::from(rows))?;
+            let partition_path = partition_columns
+                .iter()
+                .zip(&key)
+                .map(|(column, value)| {
+                    let value = value.map_or(NULL_PARTITION_DIRECTORY_VALUE.to_string(), |v| {
+                        escape_partition_path(v)
+                    });
+                    format!("{}={value}/", escape_partition_path(column))
+                })
+                .collect();
+            let partition_values = partition_columns
+                .iter()
+                .zip(key)
+                .filter_map(|(column, value)| Some((column.clone(), value?.to_string())))
+                .collect();
+            Ok(PartitionData {
+                data,
+                partition_values,
+                partition_path,
+            })
+        })
+        .collect()
+}
+
+/// Serialize the values of a partition column as described by the Delta protocol. Dates are
+/// serialized as `{year}-{month}-{day}`, timestamps (with or without time zone) as
+/// `{year}-{month}-{day} {hour}:{minute}:{second}.{microsecond}` in UTC, and decimals in plain
+/// notation with their scale. Nulls and empty strings are serialized as `None`, since empty
+/// partition values are read as null.
+pub fn serialize_partition_values(array: &ArrayRef) -> DeltaResult<Vec<Option<String>>> {
+    let values: Vec<Option<String>> = match array.data_type() {
+        ArrowDataType::Timestamp(TimeUnit::Microsecond, _) => array
+            .as_primitive::<TimestampMicrosecondType>()
+            .iter()
+            .map(|micros| micros.map(format_timestamp).transpose())
+            .collect::<DeltaResult<_>>()?,
+        ArrowDataType::Binary => array
+            .as_binary::<i32>()
+            .iter()
+            .map(|bytes| {
+                bytes
+                    .map(|bytes| {
+                        String::from_utf8(bytes.to_vec()).map_err(|_| {
+                            Error::generic("Binary partition values must be valid UTF-8")
+                        })
+                    })
+                    .transpose()
+            })
+            .collect::<DeltaResult<_>>()?,
+        ArrowDataType::Utf8
+        | ArrowDataType::Int8
+        | ArrowDataType::Int16
+        | ArrowDataType::Int32
+        | ArrowDataType::Int64
+        | ArrowDataType::Float32
+        | ArrowDataType::Float64
+        | ArrowDataType::Boolean
+        | ArrowDataType::Date32
+        | ArrowDataType::Decimal128(_, _) => {
+            let strings = arrow_cast::cast(array, &ArrowDataType::Utf8)?;
+            strings
+                .as_string::<i32>()
+                .iter()
+                .map(|value| value.map(str::to_string))
+                .collect()
+        }
+        data_type => {
+            return Err(Error::unsupported(format!(
+                "Unsupported partition column type: {data_type}"
+            )))
+        }
+    };
+    Ok(values
+        .into_iter()
+        .map(|value| value.filter(|value| !value.is_empty()))
+        .collect())
+}
+
+fn format_timestamp(micros: i64) -> DeltaResult<String> {
+    let timestamp = chrono::DateTime::from_timestamp_micros(micros)
+        .ok_or_else(|| Error::generic(format!("Timestamp out of range: {micros}")))?;
+    Ok(timestamp.format("%Y-%m-%d %H:%M:%S%.6f").to_string())
+}
+
+/// Escape a partition column name or value for use in a partition directory, like Hive does:
+/// characters which are special in paths (and control characters) are replaced by `%XX`.
+pub fn escape_partition_path(value: &str) -> String {
+    const SPECIAL_CHARACTERS: &[char] = &[
+        '"', '#', '%', '\'', '*', '/', ':', '=', '?', '\\', '\x7F', '{', '[', ']', '^',
+    ];
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_control() || SPECIAL_CHARACTERS.contains(&c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        Date32Array, Decimal128Array, Int32Array, StringArray, TimestampMicrosecondArray,
+    };
+    use arrow_schema::{Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_partition_values() {
+        let cases: Vec<(ArrayRef, Vec<Option<&str>>)> = vec![
+            (
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(-3)])),
+                vec![Some("1"), None, Some("-3")],
+            ),
+            (
+                Arc::new(StringArray::from(vec![Some("a b"), Some(""), None])),
+                vec![Some("a b"), None, None],
+            ),
+            (
+                Arc::new(Date32Array::from(vec![0, 19723])),
+                vec![Some("1970-01-01"), Some("2024-01-01")],
+            ),
+            (
+                Arc::new(
+                    TimestampMicrosecondArray::from(vec![1_704_110_400_123_456, 0])
+                        .with_timezone("UTC"),
+                ),
+                vec![
+                    Some("2024-01-01 12:00:00.123456"),
+                    Some("1970-01-01 00:00:00.000000"),
+                ],
+            ),
+            (
+                Arc::new(TimestampMicrosecondArray::from(vec![-1])),
+                vec![Some("1969-12-31 23:59:59.999999")],
+            ),
+            (
+                Arc::new(
+                    Decimal128Array::from(vec![12345, -5])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+                vec![Some("123.45"), Some("-0.05")],
+            ),
+        ];
+        for (array, expected) in cases {
+            let values = serialize_partition_values(&array).unwrap();
+            assert_eq!(
+                values.iter().map(Option::as_deref).collect::<Vec<_>>(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialized_values_round_trip() {
+        use crate::expressions::Scalar;
+        use crate::schema::PrimitiveType;
+
+        let timestamps: ArrayRef =
+            Arc::new(TimestampMicrosecondArray::from(vec![1_704_110_400_123_456]));
+        let value = serialize_partition_values(&timestamps).unwrap()[0]
+            .clone()
+            .unwrap();
+        assert_eq!(
+            PrimitiveType::Timestamp.parse_scalar(&value).unwrap(),
+            Scalar::Timestamp(1_704_110_400_123_456)
+        );
+        let decimals: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![12345])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let value = serialize_partition_values(&decimals).unwrap()[0]
+            .clone()
+            .unwrap();
+        assert_eq!(
+            PrimitiveType::Decimal(10, 2).parse_scalar(&value).unwrap(),
+            Scalar::Decimal(12345, 10, 2)
+        );
+    }
+
+    #[test]
+    fn test_escape_partition_path() {
+        assert_eq!(escape_partition_path("plain value"), "plain value");
+        assert_eq!(escape_partition_path("a/b=c:d%e"), "a%2Fb%3Dc%3Ad%25e");
+        assert_eq!(escape_partition_path("x\ny{}"), "x%0Ay%7B}");
+    }
+
+    #[test]
+    fn test_split_by_partition() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("number", ArrowDataType::Int32, true),
+            Field::new("region", ArrowDataType::Utf8, true),
+            Field::new("date", ArrowDataType::Date32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec![
+                    Some("US/East"),
+                    None,
+                    Some("US/East"),
+                    None,
+                ])),
+                Arc::new(Date32Array::from(vec![0, 0, 0, 1])),
+            ],
+        )
+        .unwrap();
+        let partition_columns = ["region".to_string(), "date".to_string()];
+        let partitions = split_by_partition(&batch, &partition_columns).unwrap();
+        assert_eq!(partitions.len(), 3);
+
+        let numbers = |partition: &PartitionData| {
+            assert_eq!(partition.data.num_columns(), 1);
+            partition
+                .data
+                .column(0)
+                .as_primitive::<arrow_array::types::Int32Type>()
+                .values()
+                .to_vec()
+        };
+        assert_eq!(numbers(&partitions[0]), [1, 3]);
+        assert_eq!(
+            partitions[0].partition_values,
+            HashMap::from([
+                ("region".to_string(), "US/East".to_string()),
+                ("date".to_string(), "1970-01-01".to_string()),
+            ])
+        );
+        assert_eq!(
+            partitions[0].partition_path,
+            "region=US%2FEast/date=1970-01-01/"
+        );
+        assert_eq!(numbers(&partitions[1]), [2]);
+        assert_eq!(
+            partitions[1].partition_values,
+            HashMap::from([("date".to_string(), "1970-01-01".to_string())])
+        );
+        assert_eq!(
+            partitions[1].partition_path,
+            "region=__HIVE_DEFAULT_PARTITION__/date=1970-01-01/"
+        );
+        assert_eq!(numbers(&partitions[2]), [4]);
+
+        // the escaped directory names are URL encoded in the target directory
+        let table_root = Url::parse("s3://bucket/table/").unwrap();
+        assert_eq!(
+            partitions[0].target_dir(&table_root).unwrap().as_str(),
+            "s3://bucket/table/region=US%252FEast/date=1970-01-01/"
+        );
+
+        // unpartitioned data is a single partition
+        let partitions = split_by_partition(&batch, &[]).unwrap();
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].data, batch);
+        assert_eq!(partitions[0].target_dir(&table_root).unwrap(), table_root);
+
+        let missing = split_by_partition(&batch, &["missing".to_string()]);
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! Collection of the [per-file statistics] of the data files written to a table, which readers use
+//! to skip files when scanning with a predicate.
+//!
+//! [per-file statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#per-file-statistics
+
+use std::cmp::Ordering;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float32Type, Float64Type, Int64Type};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_buffer::NullBuffer;
+use arrow_ord::ord::make_comparator;
+use arrow_schema::{DataType as ArrowDataType, SortOptions, TimeUnit};
+use chrono::DateTime;
+use indexmap::IndexMap;
+
+use crate::expressions::ColumnName;
+use crate::{DeltaResult, Error};
+
+/// String min/max values are truncated to this many characters, to keep the stats in the log small.
+pub const STRING_STATS_PREFIX_LENGTH: usize = 32;
+
+// Appended to truncated string max values, so that they remain an upper bound of the column.
+const STRING_MAX_TIE_BREAKER: char = '\u{10FFFF}';
+
+/// Collects the statistics of a data file from the batches written to it: the number of records,
+/// and the `nullCount`, `minValues` and `maxValues` of each of the stats columns (see
+/// [`WriteContext::stats_columns`]).
+///
+/// Min and max values are collected for numeric, decimal, string, date and timestamp columns. For
+/// floating point columns, they are omitted if the column contains a NaN or infinite value. String
+/// min and max values are truncated to [`STRING_STATS_PREFIX_LENGTH`] characters, and timestamps
+/// are recorded with millisecond precision, as the stats of other Delta writers are. Null counts
+/// are collected for all stats columns.
+///
+/// [`WriteContext::stats_columns`]: crate::transaction::WriteContext::stats_columns
+#[derive(Debug)]
+pub struct StatsCollector {
+    num_records: u64,
+    columns: Vec<ColumnStats>,
+}
+
+#[derive(Debug)]
+struct ColumnStats {
+    column: ColumnName,
+    null_count: u64,
+    // single-element arrays with the min and max values seen so far
+    min: Option<ArrayRef>,
+    max: Option<ArrayRef>,
+    // whether the min and max values of the column can be recorded
+    has_bounds: bool,
+}
+
+impl StatsCollector {
+    /// Create a collector for the statistics of the (physical) leaf columns `stats_columns`.
+    pub fn new(stats_columns: &[ColumnName]) -> Self {
+        let columns = stats_columns
+            .iter()
+            .map(|column| ColumnStats {
+                column: column.clone(),
+                null_count: 0,
+                min: None,
+                max: None,
+                has_bounds: true,
+            })
+            .collect();
+        Self {
+            num_records: 0,
+            columns,
+        }
+    }
+
+    /// Update the statistics with a batch of data written to the file. The batch must have the
+    /// physical schema of the data file.
+    pub fn update(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
+        self.num_records += batch.num_rows() as u64;
+        for stats in &mut self.columns {
+            let (array, nulls) = leaf_column(batch, &stats.column)?;
+            stats.null_count += nulls.as_ref().map_or(0, |n| n.null_count()) as u64;
+            if stats.has_bounds && supports_bounds(array.data_type()) {
+                stats.update_bounds(array, nulls.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the collected statistics as the JSON expected in the `stats` of an `add` action.
+    pub fn finish(self) -> DeltaResult<String> {
+        let mut min_values = JsonObject::default();
+        let mut max_values = JsonObject::default();
+        let mut null_count = JsonObject::default();
+        for stats in self.columns {
+            null_count.insert(&stats.column, stats.null_count.to_string());
+            if !stats.has_bounds {
+                continue;
+            }
+            if let Some(min) = stats.min.as_ref().map(|min| bound_json(min, false)) {
+                min_values.insert(&stats.column, min?);
+            }
+            if let Some(max) = stats.max.as_ref().map(|max| bound_json(max, true)) {
+                max_values.insert(&stats.column, max?);
+            }
+        }
+        let mut json = format!("{{\"numRecords\":{}", self.num_records);
+        for (name, values) in [
+            ("minValues", min_values),
+            ("maxValues", max_values),
+            ("nullCount", null_count),
+        ] {
+            json.push_str(&format!(",\"{name}\":"));
+            values.write_to(&mut json);
+        }
+        json.push('}');
+        Ok(json)
+    }
+}
+
+impl ColumnStats {
+    fn update_bounds(&mut self, array: &ArrayRef, nulls: Option<&NullBuffer>) -> DeltaResult<()> {
+        let cmp = make_comparator(array, array, SortOptions::default())?;
+        let mut valid = (0..array.len()).filter(|&i| nulls.map_or(true, |n| n.is_valid(i)));
+        let Some(first) = valid.next() else {
+            return Ok(());
+        };
+        let (mut min, mut max) = (first, first);
+        for i in valid {
+            if cmp(i, min) == Ordering::Less {
+                min = i;
+            }
+            if cmp(i, max) == Ordering::Greater {
+                max = i;
+            }
+        }
+        let (min, max) = (array.slice(min, 1), array.slice(max, 1));
+        // floats are compared by their total order, so NaNs and infinities end up as min or max
+        if !is_finite(&min) || !is_finite(&max) {
+            self.has_bounds = false;
+            return Ok(());
+        }
+        self.min = Some(match self.min.take() {
+            Some(old) if make_comparator(&old, &min, SortOptions::default())?(0, 0).is_le() => old,
+            _ => min,
+        });
+        self.max = Some(match self.max.take() {
+            Some(old) if make_comparator(&old, &max, SortOptions::default())?(0, 0).is_ge() => old,
+            _ => max,
+        });
+        Ok(())
+    }
+}
+
+// Find the leaf column at `path` in `batch`, along with its nulls, which include the nulls of its
+// ancestor struct columns
+pub(crate) fn leaf_column<'a>(
+    batch: &'a RecordBatch,
+    column: &ColumnName,
+) -> DeltaResult<(&'a ArrayRef, Option<NullBuffer>)> {
+    let missing = || Error::missing_column(format!("Stats column {column} not found in data"));
+    let (first, rest) = column.split_first().ok_or_else(missing)?;
+    let mut array = batch.column_by_name(first).ok_or_else(missing)?;
+    let mut nulls = array.logical_nulls();
+    for name in rest {
+        array = array
+            .as_struct_opt()
+            .and_then(|s| s.column_by_name(name))
+            .ok_or_else(missing)?;
+        nulls = NullBuffer::union(nulls.as_ref(), array.logical_nulls().as_ref());
+    }
+    Ok((array, nulls))
+}
+
+fn supports_bounds(data_type: &ArrowDataType) -> bool {
+    use ArrowDataType::*;
+    matches!(
+        data_type,
+        Int8 | Int16
+            | Int32
+            | Int64
+            | Float32
+            | Float64
+            | Decimal128(..)
+            | Utf8
+            | LargeUtf8
+            | Utf8View
+            | Date32
+            | Timestamp(..)
+    )
+}
+
+fn is_finite(value: &ArrayRef) -> bool {
+    match value.data_type() {
+        ArrowDataType::Float32 => value.as_primitive::<Float32Type>().value(0).is_finite(),
+        ArrowDataType::Float64 => value.as_primitive::<Float64Type>().value(0).is_finite(),
+        _ => true,
+    }
+}
+
+// Serialize a min (or max) value as a JSON literal
+fn bound_json(value: &ArrayRef, is_max: bool) -> DeltaResult<String> {
+    let string_json = |value: &str| {
+        let truncated = match value.char_indices().nth(STRING_STATS_PREFIX_LENGTH) {
+            Some((end, _)) if is_max => format!("{}{STRING_MAX_TIE_BREAKER}", &value[..end]),
+            Some((end, _)) => value[..end].to_string(),
+            None => value.to_string(),
+        };
+        serde_json::to_string(&truncated)
+    };
+    let json = match value.data_type() {
+        ArrowDataType::Utf8 => string_json(value.as_string::<i32>().value(0))?,
+        ArrowDataType::LargeUtf8 => string_json(value.as_string::<i64>().value(0))?,
+        ArrowDataType::Utf8View => string_json(value.as_string_view().value(0))?,
+        ArrowDataType::Timestamp(unit, tz) => {
+            let value = arrow_cast::cast(value, &ArrowDataType::Int64)?;
+            let value = value.as_primitive::<Int64Type>().value(0);
+            let millis = match unit {
+                TimeUnit::Second => value.saturating_mul(1000),
+                TimeUnit::Millisecond => value,
+                TimeUnit::Microsecond => round_millis(value, 1_000, is_max),
+                TimeUnit::Nanosecond => round_millis(value, 1_000_000, is_max),
+            };
+            let timestamp = DateTime::from_timestamp_millis(millis)
+                .ok_or_else(|| Error::generic(format!("Timestamp out of range: {millis}ms")))?;
+            let suffix = if tz.is_some() { "Z" } else { "" };
+            format!("\"{}{suffix}\"", timestamp.format("%Y-%m-%dT%H:%M:%S%.3f"))
+        }
+        ArrowDataType::Date32 => {
+            let value = arrow_cast::cast(value, &ArrowDataType::Utf8)?;
+            format!("\"{}\"", value.as_string::<i32>().value(0))
+        }
+        // integers, floats and decimals are written as exact JSON numbers
+        _ => arrow_cast::cast(value, &ArrowDataType::Utf8)?
+            .as_string::<i32>()
+            .value(0)
+            .to_string(),
+    };
+    Ok(json)
+}
+
+// Round a timestamp with `units_per_milli` units per millisecond to milliseconds, rounding down
+// min values and up max values so that they remain bounds of the column.
+fn round_millis(value: i64, units_per_milli: i64, round_up: bool) -> i64 {
+    let millis = value.div_euclid(units_per_milli);
+    if round_up && value.rem_euclid(units_per_milli) != 0 {
+        millis + 1
+    } else {
+        millis
+    }
+}
+
+// A JSON object of stats values, keyed by (nested) column name
+#[derive(Default)]
+struct JsonObject(IndexMap<String, JsonValue>);
+
+enum JsonValue {
+    Literal(String),
+    Object(JsonObject),
+}
+
+impl JsonObject {
+    fn insert(&mut self, column: &[String], value: String) {
+        match column {
+            [] => {}
+            [name] => {
+                self.0.insert(name.clone(), JsonValue::Literal(value));
+            }
+            [name, rest @ ..] => {
+                let entry = self
+                    .0
+                    .entry(name.clone())
+                    .or_insert_with(|| JsonValue::Object(JsonObject::default()));
+                if let JsonValue::Object(object) = entry {
+                    object.insert(rest, value);
+                }
+            }
+        }
+    }
+
+    fn write_to(&self, json: &mut String) {
+        json.push('{');
+        for (i, (name, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            // serializing a string cannot fail
+            json.push_str(&serde_json::to_string(name).unwrap_or_default());
+            json.push(':');
+            match value {
+                JsonValue::Literal(literal) => json.push_str(literal),
+                JsonValue::Object(object) => object.write_to(json),
+            }
+        }
+        json.push('}');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        Date32Array, Decimal128Array, Float64Array, Int32Array, StringArray, StructArray,
+        TimestampMicrosecondArray, TimestampNanosecondArray,
+    };
+    use arrow_schema::{Field, Fields, Schema};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::expressions::column_name;
+
+    fn batch(columns: Vec<(&str, ArrayRef)>) -> RecordBatch {
+        RecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    fn collect(stats_columns: &[ColumnName], batches: &[RecordBatch]) -> Value {
+        let mut collector = StatsCollector::new(stats_columns);
+        for batch in batches {
+            collector.update(batch).unwrap();
+        }
+        serde_json::from_str(&collector.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_collect_stats() {
+        let first = batch(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![Some(3), None, Some(1)])),
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![None, Some("b"), None])),
+            ),
+            (
+                "price",
+                Arc::new(
+                    Decimal128Array::from(vec![Some(12345), Some(-1), None])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+            ),
+            (
+                "day",
+                Arc::new(Date32Array::from(vec![Some(19723), Some(0), Some(1)])),
+            ),
+        ]);
+        let second = batch(vec![
+            ("id", Arc::new(Int32Array::from(vec![Some(7)]))),
+            ("name", Arc::new(StringArray::from(vec![Some("a")]))),
+            (
+                "price",
+                Arc::new(
+                    Decimal128Array::from(vec![Some(5)])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+            ),
+            ("day", Arc::new(Date32Array::from(vec![None]))),
+        ]);
+        let columns = [
+            column_name!("id"),
+            column_name!("name"),
+            column_name!("price"),
+            column_name!("day"),
+        ];
+        let stats = collect(&columns, &[first.clone(), second]);
+        assert_eq!(
+            stats,
+            json!({
+                "numRecords": 4,
+                "minValues": {"id": 1, "name": "a", "price": -0.01, "day": "1970-01-01"},
+                "maxValues": {"id": 7, "name": "b", "price": 123.45, "day": "2024-01-01"},
+                "nullCount": {"id": 1, "name": 2, "price": 1, "day": 1},
+            })
+        );
+
+        // only the requested columns are collected
+        let stats = collect(&[column_name!("id")], &[first]);
+        assert_eq!(
+            stats,
+            json!({
+                "numRecords": 3,
+                "minValues": {"id": 1},
+                "maxValues": {"id": 3},
+                "nullCount": {"id": 1},
+            })
+        );
+    }
+
+    #[test]
+    fn test_collect_nested_stats() {
+        let fields = Fields::from(vec![Field::new("x", ArrowDataType::Int32, true)]);
+        let nested = StructArray::new(
+            fields,
+            vec![Arc::new(Int32Array::from(vec![Some(1), Some(5), None]))],
+            Some(NullBuffer::from(vec![true, false, true])),
+        );
+        let stats = collect(
+            &[column_name!("s.x")],
+            &[batch(vec![("s", Arc::new(nested))])],
+        );
+        // the null struct hides the value 5 of its child
+        assert_eq!(
+            stats,
+            json!({
+                "numRecords": 3,
+                "minValues": {"s": {"x": 1}},
+                "maxValues": {"s": {"x": 1}},
+                "nullCount": {"s": {"x": 2}},
+            })
+        );
+
+        let batch = batch(vec![("s", Arc::new(Int32Array::from(vec![1])) as ArrayRef)]);
+        let mut collector = StatsCollector::new(&[column_name!("s.x")]);
+        assert!(collector.update(&batch).is_err());
+    }
+
+    #[test]
+    fn test_truncate_string_stats() {
+        let long = "a".repeat(40);
+        let data = batch(vec![(
+            "s",
+            Arc::new(StringArray::from(vec![long.as_str(), "b"])) as ArrayRef,
+        )]);
+        let stats = collect(&[column_name!("s")], &[data]);
+        assert_eq!(stats["minValues"]["s"], json!("a".repeat(32)));
+        assert_eq!(stats["maxValues"]["s"], json!("b"));
+
+        let data = batch(vec![(
+            "s",
+            Arc::new(StringArray::from(vec![long.as_str()])) as ArrayRef,
+        )]);
+        let stats = collect(&[column_name!("s")], &[data]);
+        let max = format!("{}\u{10FFFF}", "a".repeat(32));
+        assert_eq!(stats["maxValues"]["s"], json!(max));
+        assert!(max.as_str() > long.as_str());
+    }
+
+    #[test]
+    fn test_timestamp_stats() {
+        let utc = TimestampMicrosecondArray::from(vec![1_500, 2_000_001]).with_timezone("UTC");
+        let ntz = TimestampNanosecondArray::from(vec![-1, 1_000_000]);
+        let data = batch(vec![
+            ("utc", Arc::new(utc) as ArrayRef),
+            ("ntz", Arc::new(ntz) as ArrayRef),
+        ]);
+        let stats = collect(&[column_name!("utc"), column_name!("ntz")], &[data]);
+        assert_eq!(
+            stats["minValues"],
+            json!({"utc": "1970-01-01T00:00:00.001Z", "ntz": "1969-12-31T23:59:59.999"})
+        );
+        assert_eq!(
+            stats["maxValues"],
+            json!({"utc": "1970-01-01T00:00:02.001Z", "ntz": "1970-01-01T00:00:00.001"})
+        );
+    }
+
+    #[test]
+    fn test_stats_without_bounds() {
+        let floats = Float64Array::from(vec![Some(1.0), Some(f64::NAN), None]);
+        let bools = arrow_array::BooleanArray::from(vec![Some(true), None, None]);
+        let data = batch(vec![
+            ("f", Arc::new(floats) as ArrayRef),
+            ("b", Arc::new(bools) as ArrayRef),
+        ]);
+        let stats = collect(&[column_name!("f"), column_name!("b")], &[data]);
+        assert_eq!(
+            stats,
+            json!({
+                "numRecords": 3,
+                "minValues": {},
+                "maxValues": {},
+                "nullCount": {"f": 1, "b": 2},
+            })
+        );
+
+        let empty = RecordBatch::new_empty(Arc::new(Schema::new(vec![Field::new(
+            "f",
+            ArrowDataType::Float64,
+            true,
+        )])));
+        let stats = collect(&[column_name!("f")], &[empty]);
+        assert_eq!(
+            stats,
+            json!({"numRecords": 0, "minValues": {}, "maxValues": {}, "nullCount": {"f": 0}})
+        );
+    }
+}
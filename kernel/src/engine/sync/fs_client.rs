@@ -1,13 +1,78 @@
+use std::io::Write;
 use std::time::SystemTime;
 
 use bytes::Bytes;
 use itertools::Itertools;
+use tempfile::NamedTempFile;
 use url::Url;
 
 use crate::{DeltaResult, Error, FileMeta, FileSlice, FileSystemClient};
 
 pub(crate) struct SyncFilesystemClient;
 
+// For sync writer we write data to a tmp file then atomically rename it to the final path.
+// This is highly OS-dependent and for now relies on the atomicity of tempfile's
+// `persist_noclobber` (or `persist` when overwriting).
+pub(crate) fn write_local_file(path: &Url, data: &[u8], overwrite: bool) -> DeltaResult<()> {
+    let path = path
+        .to_file_path()
+        .map_err(|_| Error::generic("sync client can only write local files"))?;
+    let Some(parent) = path.parent() else {
+        return Err(Error::generic(format!("no parent found for {:?}", path)));
+    };
+
+    // like object stores, create missing directories (e.g. the `_delta_log` of a new table)
+    std::fs::create_dir_all(parent)?;
+
+    // write data to tmp file
+    let mut tmp_file = NamedTempFile::new_in(parent)?;
+    tmp_file.write_all(data)?;
+    tmp_file.flush()?;
+
+    // use 'persist_noclobber' to atomically rename tmp file to final path, or 'persist' to
+    // atomically replace any existing file when overwriting
+    let persisted = if overwrite {
+        tmp_file.persist(path.clone())
+    } else {
+        tmp_file.persist_noclobber(path.clone())
+    };
+    persisted.map_err(|e| match e {
+        tempfile::PersistError { error, .. }
+            if error.kind() == std::io::ErrorKind::AlreadyExists =>
+        {
+            Error::FileAlreadyExists(path.to_string_lossy().to_string())
+        }
+        e => Error::IOError(e.into()),
+    })?;
+    Ok(())
+}
+
+// The metadata of the local file at `path`
+fn file_meta(path: &std::path::Path, metadata: &std::fs::Metadata) -> DeltaResult<FileMeta> {
+    let last_modified: u128 = metadata
+        .modified()
+        .map(
+            |modified| match modified.duration_since(SystemTime::UNIX_EPOCH) {
+                Ok(d) => d.as_millis(),
+                Err(_) => 0,
+            },
+        )
+        .unwrap_or(0);
+    // directories are listed with a trailing `/`, see `FileMeta::is_directory`
+    let location = if metadata.is_dir() {
+        Url::from_directory_path(path)
+    } else {
+        Url::from_file_path(path)
+    };
+    location
+        .map(|location| FileMeta {
+            location,
+            last_modified: last_modified as i64,
+            size: metadata.len() as usize,
+        })
+        .map_err(|_| Error::Generic(format!("Invalid path: {:?}", path)))
+}
+
 impl FileSystemClient for SyncFilesystemClient {
     /// List the paths in the same directory that are lexicographically greater or equal to
     /// (UTF-8 sorting) the given `path`. The result is sorted by the file name.
@@ -50,24 +115,8 @@ impl FileSystemClient for SyncFilesystemClient {
                 .into_iter()
                 .sorted_by_key(|ent| ent.path())
                 .map(|ent| {
-                    ent.metadata().map_err(Error::IOError).and_then(|metadata| {
-                        let last_modified: u64 = metadata
-                            .modified()
-                            .map(
-                                |modified| match modified.duration_since(SystemTime::UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => 0,
-                                },
-                            )
-                            .unwrap_or(0);
-                        Url::from_file_path(ent.path())
-                            .map(|location| FileMeta {
-                                location,
-                                last_modified: last_modified as i64,
-                                size: metadata.len() as usize,
-                            })
-                            .map_err(|_| Error::Generic(format!("Invalid path: {:?}", ent.path())))
-                    })
+                    let metadata = ent.metadata().map_err(Error::IOError)?;
+                    file_meta(&ent.path(), &metadata)
                 });
             Ok(Box::new(it))
         } else {
@@ -93,6 +142,21 @@ impl FileSystemClient for SyncFilesystemClient {
         });
         Ok(Box::new(iter))
     }
+
+    fn head(&self, path: &Url) -> DeltaResult<FileMeta> {
+        let file_path = path
+            .to_file_path()
+            .map_err(|_| Error::generic("Can only read local filesystem"))?;
+        match std::fs::metadata(&file_path) {
+            Ok(metadata) => file_meta(&file_path, &metadata),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::file_not_found(path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        write_local_file(path, &data, overwrite)
+    }
 }
 
 #[cfg(test)]
@@ -101,10 +165,11 @@ mod tests {
     use std::io::Write;
 
     use bytes::{BufMut, BytesMut};
+    use itertools::Itertools;
     use url::Url;
 
     use super::SyncFilesystemClient;
-    use crate::FileSystemClient;
+    use crate::{Error, FileSystemClient, ListOptions};
 
     /// generate json filenames that follow the spec (numbered padded to 20 chars)
     fn get_json_filename(index: usize) -> String {
@@ -150,6 +215,75 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_list_with_options_and_head() -> Result<(), Box<dyn std::error::Error>> {
+        let client = SyncFilesystemClient;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        for i in 0..4 {
+            let path = tmp_dir.path().join(get_json_filename(i));
+            std::fs::write(path, "x".repeat(i))?;
+        }
+        let url = Url::from_directory_path(tmp_dir.path()).unwrap();
+
+        let options = ListOptions::default().with_max_results(2);
+        let list: Vec<_> = client.list_with_options(&url, &options)?.try_collect()?;
+        assert_eq!(list.len(), 2);
+
+        let options = ListOptions::default().with_size_range(Some(1), Some(2));
+        let sizes: Vec<_> = client
+            .list_with_options(&url, &options)?
+            .map_ok(|file| file.size)
+            .try_collect()?;
+        assert_eq!(sizes, [1, 2]);
+
+        let file = url.join(&get_json_filename(3))?;
+        let meta = client.head(&file)?;
+        assert_eq!(meta.location, file);
+        assert_eq!(meta.size, 3);
+
+        let missing = url.join(&get_json_filename(4))?;
+        assert!(matches!(client.head(&missing), Err(Error::FileNotFound(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_modification_times() -> Result<(), Box<dyn std::error::Error>> {
+        let client = SyncFilesystemClient;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join(get_json_filename(0));
+        std::fs::write(&path, "null")?;
+        let url = Url::from_directory_path(tmp_dir.path()).unwrap();
+
+        // modification times are in milliseconds since the epoch
+        let list: Vec<_> = client.list_from(&url)?.try_collect()?;
+        let modified = std::fs::metadata(&path)?
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)?;
+        assert_eq!(list[0].last_modified, modified.as_millis() as i64);
+        assert_eq!(
+            client.head(&list[0].location)?.last_modified,
+            list[0].last_modified
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_directories() -> Result<(), Box<dyn std::error::Error>> {
+        let client = SyncFilesystemClient;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join(get_json_filename(0)), "null")?;
+        std::fs::create_dir(tmp_dir.path().join("sub"))?;
+        let url = Url::from_directory_path(tmp_dir.path()).unwrap();
+
+        // directories are listed with a trailing slash
+        let list: Vec<_> = client.list_from(&url)?.try_collect()?;
+        assert_eq!(list.len(), 2);
+        assert!(!list[0].is_directory());
+        assert_eq!(list[1].location, url.join("sub/")?);
+        assert!(list[1].is_directory());
+        Ok(())
+    }
+
     #[test]
     fn test_read_files() -> Result<(), Box<dyn std::error::Error>> {
         let client = SyncFilesystemClient;
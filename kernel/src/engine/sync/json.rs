@@ -1,17 +1,17 @@
-use std::{fs::File, io::BufReader, io::Write};
+use std::{fs::File, io::BufReader};
 
 use arrow_schema::SchemaRef as ArrowSchemaRef;
-use tempfile::NamedTempFile;
 use url::Url;
 
+use super::fs_client::write_local_file;
 use super::read_files;
 use crate::engine::arrow_data::ArrowEngineData;
 use crate::engine::arrow_utils::parse_json as arrow_parse_json;
+use crate::engine::arrow_utils::parse_selected_json as arrow_parse_selected_json;
 use crate::engine::arrow_utils::to_json_bytes;
 use crate::schema::SchemaRef;
 use crate::{
-    DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
-    JsonHandler,
+    DeltaResult, EngineData, ExpressionRef, FileDataReadResultIterator, FileMeta, JsonHandler,
 };
 
 pub(crate) struct SyncJsonHandler;
@@ -46,49 +46,29 @@ impl JsonHandler for SyncJsonHandler {
         arrow_parse_json(json_strings, output_schema)
     }
 
-    // For sync writer we write data to a tmp file then atomically rename it to the final path.
-    // This is highly OS-dependent and for now relies on the atomicity of tempfile's
-    // `persist_noclobber`.
+    fn parse_json_selected(
+        &self,
+        json_strings: Box<dyn EngineData>,
+        output_schema: SchemaRef,
+        selection_vector: &[bool],
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        arrow_parse_selected_json(json_strings, output_schema, selection_vector)
+    }
+
     fn write_json_file(
         &self,
         path: &Url,
         data: Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + '_>,
-        _overwrite: bool,
+        overwrite: bool,
     ) -> DeltaResult<()> {
-        let path = path
-            .to_file_path()
-            .map_err(|_| crate::Error::generic("sync client can only read local files"))?;
-        let Some(parent) = path.parent() else {
-            return Err(crate::Error::generic(format!(
-                "no parent found for {:?}",
-                path
-            )));
-        };
-
-        // write data to tmp file
-        let mut tmp_file = NamedTempFile::new_in(parent)?;
-        let buf = to_json_bytes(data)?;
-        tmp_file.write_all(&buf)?;
-        tmp_file.flush()?;
-
-        // use 'persist_noclobber' to atomically rename tmp file to final path
-        tmp_file
-            .persist_noclobber(path.clone())
-            .map_err(|e| match e {
-                tempfile::PersistError { error, .. }
-                    if error.kind() == std::io::ErrorKind::AlreadyExists =>
-                {
-                    Error::FileAlreadyExists(path.to_string_lossy().to_string())
-                }
-                e => Error::IOError(e.into()),
-            })?;
-        Ok(())
+        write_local_file(path, &to_json_bytes(data)?, overwrite)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Error;
 
     use std::sync::Arc;
 
@@ -116,7 +96,7 @@ mod tests {
         )?;
         let data: Box<dyn EngineData> = Box::new(ArrowEngineData::new(data));
         let empty: Box<dyn EngineData> =
-            Box::new(ArrowEngineData::new(RecordBatch::new_empty(schema)));
+            Box::new(ArrowEngineData::new(RecordBatch::new_empty(schema.clone())));
 
         let url = Url::from_file_path(path.clone()).unwrap();
         handler
@@ -127,7 +107,7 @@ mod tests {
             Err(Error::FileAlreadyExists(_))
         ));
 
-        let file = std::fs::read_to_string(path)?;
+        let file = std::fs::read_to_string(&path)?;
         let json: Vec<_> = serde_json::Deserializer::from_str(&file)
             .into_iter::<serde_json::Value>()
             .flatten()
@@ -137,6 +117,14 @@ mod tests {
             vec![json!({"dog": "remi"}), json!({"dog": "wilson"}),]
         );
 
+        // overwriting replaces the existing file
+        let empty: Box<dyn EngineData> =
+            Box::new(ArrowEngineData::new(RecordBatch::new_empty(schema)));
+        handler
+            .write_json_file(&url, Box::new(std::iter::once(Ok(empty))), true)
+            .expect("overwrite json file");
+        assert_eq!(std::fs::read_to_string(&path)?, "");
+
         Ok(())
     }
 }
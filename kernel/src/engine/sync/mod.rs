@@ -1,24 +1,29 @@
-//! A simple, single threaded, [`Engine`] that can only read from the local filesystem
+//! A simple, single threaded, [`Engine`] that can only read from and write to the local filesystem
 
 use super::arrow_expression::ArrowExpressionHandler;
 use crate::engine::arrow_data::ArrowEngineData;
+use crate::engine::data_file::{logical_to_physical, physical_partition_values};
+use crate::engine::partitioned_write::split_by_partition;
+use crate::transaction::WriteContext;
 use crate::{
-    DeltaResult, Engine, Error, ExpressionHandler, ExpressionRef, FileDataReadResultIterator,
-    FileMeta, FileSystemClient, JsonHandler, ParquetHandler, SchemaRef,
+    DeltaResult, Engine, EngineData, Error, ErrorContext, ExpressionHandler, ExpressionRef,
+    FileDataReadResultIterator, FileMeta, FileSystemClient, JsonHandler, ParquetHandler, SchemaRef,
 };
 
 use arrow_schema::{Schema as ArrowSchema, SchemaRef as ArrowSchemaRef};
 use itertools::Itertools;
+use std::collections::HashMap;
 use std::fs::File;
 use std::sync::Arc;
 use tracing::debug;
+use url::Url;
 
 mod fs_client;
 pub(crate) mod json;
 mod parquet;
 
-/// This is a simple implementation of [`Engine`]. It only supports reading data from the local
-/// filesystem, and internally represents data using `Arrow`.
+/// This is a simple implementation of [`Engine`]. It only supports reading and writing data on the
+/// local filesystem, and internally represents data using `Arrow`.
 pub struct SyncEngine {
     fs_client: Arc<fs_client::SyncFilesystemClient>,
     json_handler: Arc<json::SyncJsonHandler>,
@@ -33,9 +38,73 @@ impl SyncEngine {
             fs_client: Arc::new(fs_client::SyncFilesystemClient {}),
             json_handler: Arc::new(json::SyncJsonHandler {}),
             parquet_handler: Arc::new(parquet::SyncParquetHandler {}),
-            expression_handler: Arc::new(ArrowExpressionHandler {}),
+            expression_handler: Arc::new(ArrowExpressionHandler::new()),
         }
     }
+
+    /// Write the (logical) `data` as a parquet file in the target directory of `write_context`,
+    /// and return its [write metadata], see [`DefaultEngine::write_parquet`].
+    ///
+    /// [write metadata]: crate::transaction::get_write_metadata_schema
+    /// [`DefaultEngine::write_parquet`]: crate::engine::default::DefaultEngine::write_parquet
+    pub fn write_parquet(
+        &self,
+        data: &ArrowEngineData,
+        write_context: &WriteContext,
+        partition_values: HashMap<String, String>,
+        data_change: bool,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        self.write_parquet_to(
+            data,
+            write_context,
+            write_context.target_dir(),
+            partition_values,
+            data_change,
+        )
+    }
+
+    /// Write `data`, which includes the partition columns of the table, as one parquet file per
+    /// partition in the partition's directory, and return the write metadata of each file. See
+    /// [`split_by_partition`] for how the data is split.
+    pub fn write_partitioned_parquet(
+        &self,
+        data: &ArrowEngineData,
+        write_context: &WriteContext,
+        data_change: bool,
+    ) -> DeltaResult<Vec<Box<dyn EngineData>>> {
+        split_by_partition(data.record_batch(), write_context.partition_columns())?
+            .into_iter()
+            .map(|partition| {
+                let target_dir = partition.target_dir(write_context.target_dir())?;
+                let partition_values =
+                    physical_partition_values(write_context, partition.partition_values)?;
+                let data = ArrowEngineData::new(partition.data);
+                self.write_parquet_to(
+                    &data,
+                    write_context,
+                    &target_dir,
+                    partition_values,
+                    data_change,
+                )
+            })
+            .collect()
+    }
+
+    // Write the (logical) `data` as a parquet file in `target_dir`
+    fn write_parquet_to(
+        &self,
+        data: &ArrowEngineData,
+        write_context: &WriteContext,
+        target_dir: &Url,
+        partition_values: HashMap<String, String>,
+        data_change: bool,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let physical_data =
+            logical_to_physical(self.expression_handler.as_ref(), data, write_context)?;
+        self.parquet_handler
+            .write_data_file(target_dir, physical_data, write_context.stats_columns())?
+            .as_record_batch(&partition_values, data_change)
+    }
 }
 
 impl Engine for SyncEngine {
@@ -78,18 +147,25 @@ where
     let result = files
         .into_iter()
         // Produces Iterator<DeltaResult<Iterator<DeltaResult<ArrowEngineData>>>>
-        .map(move |file| {
+        .map(move |file| -> DeltaResult<_> {
             let location = file.location;
             debug!("Reading {location:#?} with schema {schema:#?} and predicate {predicate:#?}");
-            let path = location
+            // annotate the errors opening or reading the file with its location
+            let context = ErrorContext::new().with_file(location.clone());
+            let reader = location
                 .to_file_path()
-                .map_err(|_| Error::generic("can only read local files"))?;
-            try_create_from_file(
-                File::open(path)?,
-                schema.clone(),
-                arrow_schema.clone(),
-                predicate.clone(),
-            )
+                .map_err(|_| Error::generic("can only read local files"))
+                .and_then(|path| Ok(File::open(path)?))
+                .and_then(|file| {
+                    try_create_from_file(
+                        file,
+                        schema.clone(),
+                        arrow_schema.clone(),
+                        predicate.clone(),
+                    )
+                })
+                .map_err(|err| err.with_context(context.clone()))?;
+            Ok(reader.map(move |data| data.map_err(|err| err.with_context(context.clone()))))
         })
         // Flatten to Iterator<DeltaResult<DeltaResult<ArrowEngineData>>>
         .flatten_ok()
@@ -2,16 +2,67 @@ use std::fs::File;
 
 use arrow_schema::SchemaRef as ArrowSchemaRef;
 use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ParquetRecordBatchReaderBuilder};
+use url::Url;
 
+use super::fs_client::write_local_file;
 use super::read_files;
 use crate::engine::arrow_data::ArrowEngineData;
-use crate::engine::arrow_utils::{generate_mask, get_requested_indices, reorder_struct_array};
+use crate::engine::arrow_utils::{
+    generate_mask, get_requested_indices, reorder_struct_array, to_parquet_bytes,
+};
+use crate::engine::data_file::{encode_parquet, new_data_file_name, DataFileMetadata};
+use crate::engine::parquet_row_filter::ParquetRowFilter;
 use crate::engine::parquet_row_group_skipping::ParquetRowGroupSkipping;
+use crate::expressions::ColumnName;
 use crate::schema::SchemaRef;
-use crate::{DeltaResult, ExpressionRef, FileDataReadResultIterator, FileMeta, ParquetHandler};
+use crate::{
+    DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
+    FilteredEngineData, ParquetHandler,
+};
 
 pub(crate) struct SyncParquetHandler;
 
+impl SyncParquetHandler {
+    // Write the (physical) `data` as a new parquet file in the directory `path` and return its
+    // metadata, with the statistics of the `stats_columns`
+    pub(crate) fn write_data_file(
+        &self,
+        path: &Url,
+        data: Box<dyn EngineData>,
+        stats_columns: &[ColumnName],
+    ) -> DeltaResult<DataFileMetadata> {
+        if !path.path().ends_with('/') {
+            return Err(Error::generic(format!(
+                "Path must end with a trailing slash: {path}"
+            )));
+        }
+        let (buffer, stats) = encode_parquet(data, stats_columns, None)?;
+        let location = path.join(&new_data_file_name())?;
+        write_local_file(&location, &buffer, false)?;
+        Ok(DataFileMetadata::new(local_file_meta(&location)?).with_stats(stats))
+    }
+}
+
+// The metadata of the local file at `location`
+fn local_file_meta(location: &Url) -> DeltaResult<FileMeta> {
+    let metadata = location
+        .to_file_path()
+        .map_err(|_| Error::generic("sync client can only write local files"))?
+        .metadata()?;
+    let last_modified = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| Error::generic("file modification time is before the unix epoch"))?
+        .as_millis()
+        .try_into()
+        .map_err(|_| Error::generic("milliseconds since unix_epoch exceeded i64 size"))?;
+    Ok(FileMeta::new(
+        location.clone(),
+        last_modified,
+        metadata.len() as usize,
+    ))
+}
+
 fn try_create_from_parquet(
     file: File,
     schema: SchemaRef,
@@ -27,7 +78,9 @@ fn try_create_from_parquet(
         builder = builder.with_projection(mask);
     }
     if let Some(predicate) = predicate {
-        builder = builder.with_row_group_filter(predicate.as_ref());
+        builder = builder
+            .with_row_group_filter(predicate.as_ref())
+            .with_predicate_row_filter(&predicate);
     }
     Ok(builder.build()?.map(move |data| {
         let reordered = reorder_struct_array(data?.into(), &requested_ordering)?;
@@ -44,4 +97,14 @@ impl ParquetHandler for SyncParquetHandler {
     ) -> DeltaResult<FileDataReadResultIterator> {
         read_files(files, schema, predicate, try_create_from_parquet)
     }
+
+    fn write_parquet(
+        &self,
+        location: &Url,
+        schema: SchemaRef,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+    ) -> DeltaResult<FileMeta> {
+        write_local_file(location, &to_parquet_bytes(&schema, data, None)?, true)?;
+        local_file_meta(location)
+    }
 }
@@ -0,0 +1,190 @@
+//! Z-order clustering of the data written to a table, e.g. for an `OPTIMIZE ZORDER BY`. Sorting
+//! rows by their [`zorder_keys`] keeps rows which are close in *all* of the clustering columns
+//! close together, so that the min/max stats of the data files written allow skipping files for
+//! predicates on any of the columns, rather than just on the first column of a plain sort.
+
+use std::cmp::Ordering;
+
+use arrow_array::{Array, FixedSizeBinaryArray, RecordBatch, UInt32Array};
+use arrow_buffer::Buffer;
+use arrow_ord::ord::make_comparator;
+use arrow_schema::SortOptions;
+use arrow_select::take::take_record_batch;
+
+use crate::engine::stats::leaf_column;
+use crate::expressions::ColumnName;
+use crate::{DeltaResult, Error};
+
+/// The number of bits of each clustering column in a Z-order key.
+const BITS_PER_COLUMN: usize = 32;
+
+/// Compute the Z-order key of each row of `batch` for the clustering `columns` (e.g. the
+/// [`Snapshot::clustering_columns`]): the bits of the row's rank in each of the columns,
+/// interleaved. Sorting rows by their keys (as unsigned bytes) sorts them along a Z-order curve.
+///
+/// Values are ranked within the batch (with nulls first), and the ranks scaled to 32 bits, so
+/// that each column contributes evenly to the keys regardless of its type and distribution. Keys
+/// of different batches are therefore not comparable: engines should compute the keys of all the
+/// data to cluster at once.
+///
+/// [`Snapshot::clustering_columns`]: crate::snapshot::Snapshot::clustering_columns
+pub fn zorder_keys(
+    batch: &RecordBatch,
+    columns: &[ColumnName],
+) -> DeltaResult<FixedSizeBinaryArray> {
+    if columns.is_empty() {
+        return Err(Error::generic(
+            "Z-order requires at least one clustering column",
+        ));
+    }
+    let ranks = columns
+        .iter()
+        .map(|column| scaled_ranks(batch, column))
+        .collect::<DeltaResult<Vec<_>>>()?;
+    let key_bits = BITS_PER_COLUMN * columns.len();
+    let key_len = key_bits / 8;
+    let mut keys = vec![0u8; batch.num_rows() * key_len];
+    for (row, key) in keys.chunks_exact_mut(key_len).enumerate() {
+        // the most significant bits of all columns first, in the order of the columns
+        for key_bit in 0..key_bits {
+            let rank = ranks[key_bit % columns.len()][row];
+            let bit = BITS_PER_COLUMN - 1 - key_bit / columns.len();
+            if (rank >> bit) & 1 == 1 {
+                key[key_bit / 8] |= 0x80 >> (key_bit % 8);
+            }
+        }
+    }
+    Ok(FixedSizeBinaryArray::new(
+        key_len as i32,
+        Buffer::from_vec(keys),
+        None,
+    ))
+}
+
+/// Sort the rows of `batch` along a Z-order curve of the clustering `columns`, see
+/// [`zorder_keys`].
+pub fn sort_by_zorder(batch: &RecordBatch, columns: &[ColumnName]) -> DeltaResult<RecordBatch> {
+    let keys = zorder_keys(batch, columns)?;
+    let mut indices: Vec<u32> = (0..batch.num_rows() as u32).collect();
+    indices.sort_by_key(|&row| keys.value(row as usize));
+    Ok(take_record_batch(batch, &UInt32Array::from(indices))?)
+}
+
+// The dense rank of the value of each row of the column (nulls first), scaled to the full range
+// of a u32, so that the smallest value has rank 0 and the largest has rank u32::MAX
+fn scaled_ranks(batch: &RecordBatch, column: &ColumnName) -> DeltaResult<Vec<u32>> {
+    let (array, nulls) = leaf_column(batch, column)?;
+    let compare_values = make_comparator(array, array, SortOptions::default())?;
+    let is_null = |row: usize| nulls.as_ref().is_some_and(|nulls| nulls.is_null(row));
+    let compare = |a: usize, b: usize| match (is_null(a), is_null(b)) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => compare_values(a, b),
+    };
+    let mut sorted: Vec<usize> = (0..array.len()).collect();
+    sorted.sort_by(|&a, &b| compare(a, b));
+
+    let mut ranks = vec![0u64; array.len()];
+    let mut rank = 0;
+    for (i, &row) in sorted.iter().enumerate() {
+        if i > 0 && compare(sorted[i - 1], row) != Ordering::Equal {
+            rank += 1;
+        }
+        ranks[row] = rank;
+    }
+    let max_rank = rank.max(1);
+    Ok(ranks
+        .into_iter()
+        .map(|rank| (rank * u64::from(u32::MAX) / max_rank) as u32)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int32Type;
+    use arrow_array::{ArrayRef, Int32Array, StringArray, StructArray};
+    use arrow_schema::{DataType as ArrowDataType, Field};
+
+    use super::*;
+
+    fn batch(columns: Vec<(&str, ArrayRef)>) -> RecordBatch {
+        RecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    fn values(batch: &RecordBatch, column: usize) -> Vec<Option<i32>> {
+        batch
+            .column(column)
+            .as_primitive::<Int32Type>()
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_by_zorder() {
+        // a 4x4 grid of (x, y), in reverse order
+        let (x, y): (Vec<i32>, Vec<i32>) = (0..16).rev().map(|i| (i / 4, i % 4)).unzip();
+        let data = batch(vec![
+            ("x", Arc::new(Int32Array::from(x))),
+            ("y", Arc::new(Int32Array::from(y))),
+        ]);
+        let columns = [ColumnName::new(["x"]), ColumnName::new(["y"])];
+        assert_eq!(zorder_keys(&data, &columns).unwrap().value_length(), 8);
+
+        let sorted = sort_by_zorder(&data, &columns).unwrap();
+        let points: Vec<_> = values(&sorted, 0)
+            .into_iter()
+            .zip(values(&sorted, 1))
+            .map(|(x, y)| (x.unwrap(), y.unwrap()))
+            .collect();
+        // each quadrant of the grid is sorted before the next one
+        #[rustfmt::skip]
+        let expected = [
+            (0, 0), (0, 1), (1, 0), (1, 1),
+            (0, 2), (0, 3), (1, 2), (1, 3),
+            (2, 0), (2, 1), (3, 0), (3, 1),
+            (2, 2), (2, 3), (3, 2), (3, 3),
+        ];
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn test_zorder_keys_ranks() {
+        // equal values have equal keys, nulls (also of parent structs) come first, and skewed
+        // values are spread evenly by their ranks
+        let value = Arc::new(Int32Array::from(vec![
+            Some(1000),
+            Some(5),
+            None,
+            Some(5),
+            Some(7),
+        ]));
+        let nested = StructArray::new(
+            vec![Field::new("v", ArrowDataType::Int32, true)].into(),
+            vec![value],
+            Some(vec![true, true, true, true, false].into()),
+        );
+        let data = batch(vec![("s", Arc::new(nested))]);
+        let keys = zorder_keys(&data, &[ColumnName::new(["s", "v"])]).unwrap();
+        let keys: Vec<_> = (0..keys.len())
+            .map(|row| u32::from_be_bytes(keys.value(row).try_into().unwrap()))
+            .collect();
+        assert_eq!(keys, [u32::MAX, u32::MAX / 2, 0, u32::MAX / 2, 0]);
+    }
+
+    #[test]
+    fn test_zorder_keys_errors() {
+        let data = batch(vec![("a", Arc::new(StringArray::from(vec!["x"])))]);
+        assert!(zorder_keys(&data, &[]).is_err());
+        assert!(zorder_keys(&data, &[ColumnName::new(["b"])]).is_err());
+        assert_eq!(
+            zorder_keys(&data, &[ColumnName::new(["a"])])
+                .unwrap()
+                .value(0),
+            [0; 4]
+        );
+    }
+}
@@ -1,4 +1,12 @@
 //! Traits that engines need to implement in order to pass data between themselves and kernel.
+//!
+//! The kernel never inspects the data of an engine directly: it hands around opaque
+//! [`EngineData`] and reads what it needs of it through a [`RowVisitor`], which the engine calls
+//! back with a [`GetData`] "getter" for each requested leaf column. Engines can thus keep their
+//! data in whatever columnar (or row) format they use natively, and the kernel APIs don't depend
+//! on any version of arrow-rs. The arrow-based [`EngineData`] of the default and sync engines
+//! (`ArrowEngineData`) is only one implementation, behind the `arrow-*` features; with the default
+//! features the kernel doesn't depend on arrow at all.
 
 use crate::schema::{ColumnName, DataType};
 use crate::{AsAny, DeltaResult, Error};
@@ -256,3 +264,106 @@ pub trait EngineData: AsAny {
         self.len() == 0
     }
 }
+
+/// A batch of [`EngineData`] together with a selection vector, which marks the rows of the batch
+/// that are selected (`true`) or filtered out (`false`). Rows beyond the end of the selection
+/// vector are selected.
+pub struct FilteredEngineData {
+    /// The underlying data
+    pub data: Box<dyn EngineData>,
+    /// The rows of `data` that are selected
+    pub selection_vector: Vec<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use super::*;
+    use crate::schema::{column_name, ColumnNamesAndTypes};
+
+    // An engine's own (row-oriented, arrow-free) data: a list of files with their sizes
+    struct Files(Vec<(String, i64)>);
+
+    struct Paths<'a>(&'a [(String, i64)]);
+    struct Sizes<'a>(&'a [(String, i64)]);
+
+    impl<'a> GetData<'a> for Paths<'a> {
+        fn get_str(&'a self, row_index: usize, _field_name: &str) -> DeltaResult<Option<&'a str>> {
+            Ok(Some(self.0[row_index].0.as_str()))
+        }
+    }
+
+    impl<'a> GetData<'a> for Sizes<'a> {
+        fn get_long(&'a self, row_index: usize, _field_name: &str) -> DeltaResult<Option<i64>> {
+            Ok(Some(self.0[row_index].1))
+        }
+    }
+
+    impl EngineData for Files {
+        fn visit_rows(
+            &self,
+            column_names: &[ColumnName],
+            visitor: &mut dyn RowVisitor,
+        ) -> DeltaResult<()> {
+            let (paths, sizes) = (Paths(&self.0), Sizes(&self.0));
+            let getters = column_names
+                .iter()
+                .map(|name| match name.to_string().as_str() {
+                    "path" => Ok(&paths as &dyn GetData<'_>),
+                    "size" => Ok(&sizes as &dyn GetData<'_>),
+                    _ => Err(Error::generic(format!("No such column {name}"))),
+                })
+                .collect::<DeltaResult<Vec<_>>>()?;
+            visitor.visit(self.len(), &getters)
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[derive(Default)]
+    struct LargeFilesVisitor(Vec<String>);
+
+    impl RowVisitor for LargeFilesVisitor {
+        fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+            static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+                let names = vec![column_name!("path"), column_name!("size")];
+                (names, vec![DataType::STRING, DataType::LONG]).into()
+            });
+            NAMES_AND_TYPES.as_ref()
+        }
+
+        fn visit<'a>(
+            &mut self,
+            row_count: usize,
+            getters: &[&'a dyn GetData<'a>],
+        ) -> DeltaResult<()> {
+            for row in 0..row_count {
+                let path: String = getters[0].get(row, "path")?;
+                let size: i64 = getters[1].get(row, "size")?;
+                if size > 100 {
+                    self.0.push(path);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_visit_engine_data_without_arrow() {
+        let files = Files(vec![
+            ("a.parquet".to_string(), 10),
+            ("b.parquet".to_string(), 1000),
+            ("c.parquet".to_string(), 500),
+        ]);
+        let mut visitor = LargeFilesVisitor::default();
+        visitor.visit_rows_of(&files).unwrap();
+        assert_eq!(visitor.0, ["b.parquet", "c.parquet"]);
+
+        let data: Box<dyn EngineData> = Box::new(files);
+        assert_eq!(data.len(), 3);
+        assert!(data.into_any().downcast::<Files>().is_ok());
+    }
+}
@@ -2,10 +2,13 @@
 
 use std::{
     backtrace::{Backtrace, BacktraceStatus},
+    fmt,
     num::ParseIntError,
     str::Utf8Error,
 };
 
+use url::Url;
+
 use crate::schema::{DataType, StructType};
 use crate::table_properties::ParseIntervalError;
 use crate::Version;
@@ -26,8 +29,16 @@ pub enum Error {
         backtrace: Box<Backtrace>,
     },
 
+    /// An error annotated with where it occurred: the table, version and file being read. See
+    /// [`Error::with_context`].
+    #[error("{source} ({context})")]
+    WithContext {
+        source: Box<Self>,
+        context: Box<ErrorContext>,
+    },
+
     /// An error performing operations on arrow data
-    #[cfg(any(feature = "default-engine", feature = "sync-engine"))]
+    #[cfg(any(feature = "default-engine-base", feature = "sync-engine"))]
     #[error(transparent)]
     Arrow(arrow_schema::ArrowError),
 
@@ -75,7 +86,7 @@ pub enum Error {
     #[error("Object store path error: {0}")]
     ObjectStorePath(#[from] object_store::path::Error),
 
-    #[cfg(feature = "default-engine")]
+    #[cfg(feature = "reqwest")]
     #[error("Reqwest Error: {0}")]
     Reqwest(#[from] reqwest::Error),
 
@@ -166,6 +177,10 @@ pub enum Error {
     #[error("Invalid log path: {0}")]
     InvalidLogPath(String),
 
+    /// A checkpoint of the table is invalid, e.g. because it lacks the protocol or metadata
+    #[error("Invalid checkpoint: {0}")]
+    InvalidCheckpoint(String),
+
     /// Invalid commit info passed to the transaction
     #[error("Invalid commit info: {0}")]
     InvalidCommitInfo(String),
@@ -182,6 +197,11 @@ pub enum Error {
     #[error("Unsupported: {0}")]
     Unsupported(String),
 
+    /// The protocol of the table requires reader or writer features (or versions) which the
+    /// kernel doesn't support (or know)
+    #[error("Unsupported table protocol: {0}")]
+    UnsupportedTableFeature(Box<UnsupportedFeatures>),
+
     /// Parsing error when attempting to deserialize an interval
     #[error(transparent)]
     ParseIntervalError(#[from] ParseIntervalError),
@@ -191,6 +211,19 @@ pub enum Error {
 
     #[error("Change data feed encountered incompatible schema. Expected {0}, got {1}")]
     ChangeDataFeedIncompatibleSchema(String, String),
+
+    /// The memory budget of the engine does not allow for more data
+    #[error("Memory limit exceeded: {0}")]
+    MemoryLimitExceeded(String),
+
+    /// A version of the table after its latest version was requested
+    #[error("Version {version} is beyond the latest version {latest} of the table")]
+    VersionBeyondLatest { version: Version, latest: Version },
+
+    /// A version of the table which can no longer be reconstructed was requested, because its log
+    /// files were cleaned up (or truncated) before the earliest checkpoint of the table
+    #[error("Version {version} is before the earliest available version {earliest} of the table")]
+    VersionTruncated { version: Version, earliest: Version },
 }
 
 // Convenience constructors for Error types that take a String argument
@@ -242,6 +275,9 @@ impl Error {
     pub(crate) fn invalid_log_path(msg: impl ToString) -> Self {
         Self::InvalidLogPath(msg.to_string())
     }
+    pub(crate) fn invalid_checkpoint(msg: impl ToString) -> Self {
+        Self::InvalidCheckpoint(msg.to_string())
+    }
 
     pub fn internal_error(msg: impl ToString) -> Self {
         Self::InternalError(msg.to_string()).with_backtrace()
@@ -251,9 +287,24 @@ impl Error {
         Self::InvalidProtocol(msg.to_string())
     }
 
+    pub fn memory_limit_exceeded(msg: impl ToString) -> Self {
+        Self::MemoryLimitExceeded(msg.to_string())
+    }
+
+    pub fn version_beyond_latest(version: Version, latest: Version) -> Self {
+        Self::VersionBeyondLatest { version, latest }
+    }
+
+    pub fn version_truncated(version: Version, earliest: Version) -> Self {
+        Self::VersionTruncated { version, earliest }
+    }
+
     pub fn unsupported(msg: impl ToString) -> Self {
         Self::Unsupported(msg.to_string())
     }
+    pub fn unsupported_table_features(features: UnsupportedFeatures) -> Self {
+        Self::UnsupportedTableFeature(Box::new(features))
+    }
     pub fn change_data_feed_unsupported(version: impl Into<Version>) -> Self {
         Self::ChangeDataFeedUnsupported(version.into())
     }
@@ -264,6 +315,109 @@ impl Error {
         Self::ChangeDataFeedIncompatibleSchema(format!("{expected:?}"), format!("{actual:?}"))
     }
 
+    /// Annotate the error with (more of) the context it occurred in. Fields already set on the
+    /// context of the error (which are set closer to where it occurred) take precedence over the
+    /// fields of `context`.
+    #[must_use]
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            _ if context.is_empty() => self,
+            // keep the backtrace outermost, so that it's displayed after the context
+            Self::Backtraced { source, backtrace } => Self::Backtraced {
+                source: Box::new(source.with_context(context)),
+                backtrace,
+            },
+            Self::WithContext {
+                source,
+                context: mut existing,
+            } => {
+                existing.merge(context);
+                Self::WithContext {
+                    source,
+                    context: existing,
+                }
+            }
+            err => Self::WithContext {
+                source: Box::new(err),
+                context: Box::new(context),
+            },
+        }
+    }
+
+    /// The context the error occurred in, if known. See [`Error::with_context`].
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Backtraced { source, .. } => source.context(),
+            Self::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The features which keep the kernel from reading or writing the table, if this error is an
+    /// [`Error::UnsupportedTableFeature`] (with any backtrace or context attached to it).
+    pub fn unsupported_features(&self) -> Option<&UnsupportedFeatures> {
+        match self {
+            Self::Backtraced { source, .. } | Self::WithContext { source, .. } => {
+                source.unsupported_features()
+            }
+            Self::UnsupportedTableFeature(features) => Some(features),
+            _ => None,
+        }
+    }
+
+    /// The kind of the error, regardless of any backtrace or context attached to it. Unlike the
+    /// variants of [`Error`], kinds are stable, so matching on them is the preferred way to
+    /// handle specific errors.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Backtraced { source, .. } | Self::WithContext { source, .. } => source.kind(),
+            #[cfg(any(feature = "default-engine-base", feature = "sync-engine"))]
+            Self::Arrow(_) => ErrorKind::Arrow,
+            Self::EngineDataType(_) | Self::Extract(..) => ErrorKind::EngineData,
+            Self::Generic(_) | Self::GenericError { .. } => ErrorKind::Generic,
+            Self::IOError(_) => ErrorKind::Io,
+            Self::InternalError(_) | Self::JoinFailure(_) => ErrorKind::Internal,
+            #[cfg(feature = "parquet")]
+            Self::Parquet(_) => ErrorKind::Parquet,
+            #[cfg(feature = "object_store")]
+            Self::ObjectStore(_) | Self::ObjectStorePath(_) => ErrorKind::Storage,
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(_) => ErrorKind::Storage,
+            Self::FileNotFound(_) => ErrorKind::FileNotFound,
+            Self::FileAlreadyExists(_) => ErrorKind::FileAlreadyExists,
+            Self::MissingColumn(_) | Self::UnexpectedColumnType(_) => ErrorKind::Schema,
+            Self::MalformedJson(_) => ErrorKind::Json,
+            Self::DeletionVector(_) => ErrorKind::DeletionVector,
+            Self::MissingData(_)
+            | Self::MissingVersion
+            | Self::MissingMetadata
+            | Self::MissingProtocol
+            | Self::MissingMetadataAndProtocol
+            | Self::InvalidProtocol(_)
+            | Self::InvalidLogPath(_)
+            | Self::InvalidColumnMappingMode(_) => ErrorKind::InvalidTable,
+            Self::InvalidCheckpoint(_) => ErrorKind::InvalidCheckpoint,
+            Self::ParseError(..)
+            | Self::Utf8Error(_)
+            | Self::ParseIntError(_)
+            | Self::ParseIntervalError(_) => ErrorKind::Parse,
+            Self::InvalidUrl(_)
+            | Self::InvalidTableLocation(_)
+            | Self::InvalidDecimal(_)
+            | Self::InvalidStructData(_)
+            | Self::InvalidExpressionEvaluation(_)
+            | Self::InvalidCommitInfo(_)
+            | Self::MissingCommitInfo => ErrorKind::InvalidArgument,
+            Self::Unsupported(_)
+            | Self::UnsupportedTableFeature(_)
+            | Self::ChangeDataFeedUnsupported(_)
+            | Self::ChangeDataFeedIncompatibleSchema(..) => ErrorKind::Unsupported,
+            Self::MemoryLimitExceeded(_) => ErrorKind::MemoryLimitExceeded,
+            Self::VersionBeyondLatest { .. } => ErrorKind::VersionBeyondLatest,
+            Self::VersionTruncated { .. } => ErrorKind::VersionTruncated,
+        }
+    }
+
     // Capture a backtrace when the error is constructed.
     #[must_use]
     pub fn with_backtrace(self) -> Self {
@@ -278,6 +432,177 @@ impl Error {
     }
 }
 
+/// The stable kind of an [`Error`], see [`Error::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// An IO error, e.g. reading a local file
+    Io,
+    /// An error interacting with storage (e.g. an object store), other than a missing file
+    Storage,
+    /// A file could not be found
+    FileNotFound,
+    /// A file already exists, prohibiting a non-overwrite write
+    FileAlreadyExists,
+    /// Malformed JSON, e.g. in a commit file
+    Json,
+    /// An error reading or writing parquet, e.g. a corrupt checkpoint or data file
+    Parquet,
+    /// An error working with arrow data, e.g. parsing JSON into it
+    Arrow,
+    /// Engine data of an unexpected type or shape
+    EngineData,
+    /// A column is missing or has an unexpected type
+    Schema,
+    /// A value could not be parsed
+    Parse,
+    /// The log or metadata of the table is missing or invalid
+    InvalidTable,
+    /// A checkpoint of the table is invalid (reading the log without it may succeed)
+    InvalidCheckpoint,
+    /// An argument (e.g. a URL, expression or commit info) is invalid
+    InvalidArgument,
+    /// An error with a deletion vector
+    DeletionVector,
+    /// The table or operation uses functionality the kernel doesn't support
+    Unsupported,
+    /// The memory budget of the engine does not allow for more data
+    MemoryLimitExceeded,
+    /// A kernel bug, or a failure of the engine running its tasks
+    Internal,
+    /// The requested version is after the latest version of the table
+    VersionBeyondLatest,
+    /// The requested version is before the earliest version of the table that can be read
+    VersionTruncated,
+    /// Any other error
+    Generic,
+}
+
+/// Where an [`Error`] occurred: the table being read, the version of the table (or of the log
+/// file being read), and the file being read. See [`Error::with_context`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    table_root: Option<Url>,
+    version: Option<Version>,
+    file: Option<Url>,
+}
+
+impl ErrorContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_table_root(mut self, table_root: Url) -> Self {
+        self.table_root = Some(table_root);
+        self
+    }
+
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn with_file(mut self, file: Url) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    pub fn table_root(&self) -> Option<&Url> {
+        self.table_root.as_ref()
+    }
+
+    pub fn version(&self) -> Option<Version> {
+        self.version
+    }
+
+    pub fn file(&self) -> Option<&Url> {
+        self.file.as_ref()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.table_root.is_none() && self.version.is_none() && self.file.is_none()
+    }
+
+    // Fill in the fields which aren't set yet from `other`
+    fn merge(&mut self, other: ErrorContext) {
+        self.table_root = self.table_root.take().or(other.table_root);
+        self.version = self.version.or(other.version);
+        self.file = self.file.take().or(other.file);
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table_root = self.table_root.as_ref().map(|root| format!("table {root}"));
+        let version = self.version.map(|version| format!("version {version}"));
+        let file = self.file.as_ref().map(|file| format!("file {file}"));
+        let parts: Vec<_> = [table_root, version, file].into_iter().flatten().collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// The reader and writer features of a table's protocol which keep the kernel from reading or
+/// writing the table, see [`Error::UnsupportedTableFeature`]. If no features are listed, the
+/// kernel doesn't support the minimum versions of the protocol for the operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsupportedFeatures {
+    /// The minimum reader version of the protocol
+    pub min_reader_version: i32,
+    /// The minimum writer version of the protocol
+    pub min_writer_version: i32,
+    /// Whether the kernel doesn't support writing to the table, rather than reading it
+    pub write: bool,
+    /// The reader features of the protocol which the kernel doesn't support, or doesn't know
+    pub reader_features: Vec<String>,
+    /// The writer features of the protocol which the kernel doesn't support, or doesn't know
+    pub writer_features: Vec<String>,
+    /// The features among the `reader_features` and `writer_features` which the kernel doesn't
+    /// know at all, e.g. because they are newer than the kernel
+    pub unknown_features: Vec<String>,
+    /// The reader (or, for writes, writer) features which the kernel supports
+    pub supported_features: Vec<String>,
+}
+
+impl fmt::Display for UnsupportedFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (features_type, features) = match self.write {
+            true => ("WriterFeatures", &self.writer_features),
+            false => ("ReaderFeatures", &self.reader_features),
+        };
+        if features.is_empty() {
+            return match self.write {
+                true => write!(
+                    f,
+                    "Unsupported minimum reader version {} and minimum writer version {}. Only \
+                     tables with min reader version 3 and min writer version 7 are supported for \
+                     writes",
+                    self.min_reader_version, self.min_writer_version
+                ),
+                false => write!(
+                    f,
+                    "Unsupported minimum reader version {}",
+                    self.min_reader_version
+                ),
+            };
+        }
+
+        let (unknown, unsupported): (Vec<_>, Vec<_>) = features
+            .iter()
+            .partition(|feature| self.unknown_features.contains(feature));
+        if !unsupported.is_empty() {
+            write!(f, "Unsupported {features_type} {unsupported:?}. ")?;
+        }
+        if !unknown.is_empty() {
+            write!(f, "Unknown {features_type} {unknown:?}. ")?;
+        }
+        write!(
+            f,
+            "Supported {features_type} are [{}]",
+            self.supported_features.join(", ")
+        )
+    }
+}
+
 macro_rules! from_with_backtrace(
     ( $(($error_type: ty, $error_variant: ident)), * ) => {
         $(
@@ -295,7 +620,7 @@ from_with_backtrace!(
     (std::io::Error, IOError)
 );
 
-#[cfg(any(feature = "default-engine", feature = "sync-engine"))]
+#[cfg(any(feature = "default-engine-base", feature = "sync-engine"))]
 impl From<arrow_schema::ArrowError> for Error {
     fn from(value: arrow_schema::ArrowError) -> Self {
         Self::Arrow(value).with_backtrace()
@@ -311,3 +636,87 @@ impl From<object_store::Error> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsupported_features() {
+        let features = UnsupportedFeatures {
+            min_reader_version: 3,
+            min_writer_version: 7,
+            write: false,
+            reader_features: vec!["v2Checkpoint".to_string(), "idk".to_string()],
+            writer_features: vec![],
+            unknown_features: vec!["idk".to_string()],
+            supported_features: vec!["ColumnMapping".to_string()],
+        };
+        let err = Error::unsupported_table_features(features.clone())
+            .with_context(ErrorContext::new().with_version(1));
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        assert_eq!(err.unsupported_features(), Some(&features));
+        assert_eq!(
+            err.to_string(),
+            "Unsupported table protocol: Unsupported ReaderFeatures [\"v2Checkpoint\"]. Unknown \
+             ReaderFeatures [\"idk\"]. Supported ReaderFeatures are [ColumnMapping] (version 1)"
+        );
+
+        // unsupported versions are reported without features
+        let features = UnsupportedFeatures {
+            min_reader_version: 1,
+            min_writer_version: 2,
+            write: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            features.to_string(),
+            "Unsupported minimum reader version 1 and minimum writer version 2. Only tables with \
+             min reader version 3 and min writer version 7 are supported for writes"
+        );
+        assert!(Error::generic("oops").unsupported_features().is_none());
+    }
+
+    #[test]
+    fn test_with_context() {
+        let table_root = Url::parse("file:///table/").unwrap();
+        let file = table_root
+            .join("_delta_log/00000000000000000001.json")
+            .unwrap();
+        let json_err = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let err = Error::MalformedJson(json_err)
+            .with_context(ErrorContext::new().with_file(file.clone()).with_version(1))
+            .with_context(
+                ErrorContext::new()
+                    .with_table_root(table_root.clone())
+                    .with_version(2),
+            );
+
+        assert_eq!(err.kind(), ErrorKind::Json);
+        let context = err.context().unwrap();
+        assert_eq!(context.table_root(), Some(&table_root));
+        assert_eq!(context.version(), Some(1));
+        assert_eq!(context.file(), Some(&file));
+        assert_eq!(
+            err.to_string(),
+            "EOF while parsing an object at line 1 column 1 (table file:///table/, version 1, \
+             file file:///table/_delta_log/00000000000000000001.json)"
+        );
+
+        // the context is attached within a backtrace
+        let err = Error::Backtraced {
+            source: Box::new(Error::file_not_found("file")),
+            backtrace: Box::new(Backtrace::disabled()),
+        }
+        .with_context(ErrorContext::new().with_version(3));
+        assert!(matches!(&err, Error::Backtraced { source, .. }
+            if matches!(**source, Error::WithContext { .. })));
+        assert_eq!(err.kind(), ErrorKind::FileNotFound);
+        assert_eq!(err.context().unwrap().version(), Some(3));
+
+        // an empty context is not attached
+        let err = Error::generic("oops").with_context(ErrorContext::new());
+        assert!(matches!(err, Error::Generic(_)));
+        assert!(err.context().is_none());
+    }
+}
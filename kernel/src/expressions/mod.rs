@@ -14,6 +14,10 @@ use crate::DataType;
 
 mod column_names;
 mod scalars;
+#[cfg(feature = "developer-visibility")]
+pub mod sql;
+#[cfg(not(feature = "developer-visibility"))]
+pub(crate) mod sql;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// A binary operator.
@@ -44,6 +48,8 @@ pub enum BinaryOperator {
     In,
     /// NOT IN
     NotIn,
+    /// NULLIF: NULL if the operands are equal, otherwise the left operand
+    NullIf,
 }
 
 impl BinaryOperator {
@@ -56,7 +62,7 @@ impl BinaryOperator {
             LessThan => Some(GreaterThan),
             LessThanOrEqual => Some(GreaterThanOrEqual),
             Equal | NotEqual | Distinct | Plus | Multiply => Some(*self),
-            In | NotIn | Minus | Divide => None, // not commutative
+            In | NotIn | Minus | Divide | NullIf => None, // not commutative
         }
     }
 }
@@ -65,6 +71,8 @@ impl BinaryOperator {
 pub enum VariadicOperator {
     And,
     Or,
+    /// The first non-NULL operand (or NULL if all of them are NULL)
+    Coalesce,
 }
 
 impl VariadicOperator {
@@ -73,6 +81,8 @@ impl VariadicOperator {
         match self {
             And => Or,
             Or => And,
+            // NOT(COALESCE(a, b)) is equivalent to COALESCE(NOT a, NOT b)
+            Coalesce => Coalesce,
         }
     }
 }
@@ -96,6 +106,7 @@ impl Display for BinaryOperator {
             Self::Distinct => write!(f, "DISTINCT"),
             Self::In => write!(f, "IN"),
             Self::NotIn => write!(f, "NOT IN"),
+            Self::NullIf => write!(f, "NULLIF"),
         }
     }
 }
@@ -116,11 +127,11 @@ pub struct UnaryExpression {
     /// The operator.
     pub op: UnaryOperator,
     /// The expression.
-    pub expr: Box<Expression>,
+    pub expr: ExpressionRef,
 }
 impl UnaryExpression {
     fn new(op: UnaryOperator, expr: impl Into<Expression>) -> Self {
-        let expr = Box::new(expr.into());
+        let expr = ExpressionRef::new(expr.into());
         Self { op, expr }
     }
 }
@@ -130,16 +141,9 @@ pub struct BinaryExpression {
     /// The operator.
     pub op: BinaryOperator,
     /// The left-hand side of the operation.
-    pub left: Box<Expression>,
+    pub left: ExpressionRef,
     /// The right-hand side of the operation.
-    pub right: Box<Expression>,
-}
-impl BinaryExpression {
-    fn new(op: BinaryOperator, left: impl Into<Expression>, right: impl Into<Expression>) -> Self {
-        let left = Box::new(left.into());
-        let right = Box::new(right.into());
-        Self { op, left, right }
-    }
+    pub right: ExpressionRef,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -147,10 +151,11 @@ pub struct VariadicExpression {
     /// The operator.
     pub op: VariadicOperator,
     /// The expressions.
-    pub exprs: Vec<Expression>,
+    pub exprs: Vec<ExpressionRef>,
 }
 impl VariadicExpression {
     fn new(op: VariadicOperator, exprs: Vec<Expression>) -> Self {
+        let exprs = exprs.into_iter().map(ExpressionRef::new).collect();
         Self { op, exprs }
     }
 }
@@ -204,6 +209,11 @@ impl Display for Expression {
                 left,
                 right,
             }) => write!(f, "DISTINCT({left}, {right})"),
+            Self::Binary(BinaryExpression {
+                op: BinaryOperator::NullIf,
+                left,
+                right,
+            }) => write!(f, "NULLIF({left}, {right})"),
             Self::Binary(BinaryExpression { op, left, right }) => write!(f, "{left} {op} {right}"),
             Self::Unary(UnaryExpression { op, expr }) => match op {
                 UnaryOperator::Not => write!(f, "NOT {expr}"),
@@ -214,6 +224,7 @@ impl Display for Expression {
                 let op = match op {
                     VariadicOperator::And => "AND",
                     VariadicOperator::Or => "OR",
+                    VariadicOperator::Coalesce => "COALESCE",
                 };
                 write!(f, "{op}({exprs})")
             }
@@ -261,7 +272,7 @@ impl Expression {
     pub fn unary(op: UnaryOperator, expr: impl Into<Expression>) -> Self {
         Self::Unary(UnaryExpression {
             op,
-            expr: Box::new(expr.into()),
+            expr: ExpressionRef::new(expr.into()),
         })
     }
 
@@ -273,15 +284,15 @@ impl Expression {
     ) -> Self {
         Self::Binary(BinaryExpression {
             op,
-            left: Box::new(lhs.into()),
-            right: Box::new(rhs.into()),
+            left: ExpressionRef::new(lhs.into()),
+            right: ExpressionRef::new(rhs.into()),
         })
     }
 
     /// Creates a new variadic expression OP(exprs...)
     pub fn variadic(op: VariadicOperator, exprs: impl IntoIterator<Item = Self>) -> Self {
         let exprs = exprs.into_iter().collect::<Vec<_>>();
-        Self::Variadic(VariadicExpression { op, exprs })
+        Self::Variadic(VariadicExpression::new(op, exprs))
     }
 
     /// Creates a new expression AND(exprs...)
@@ -294,6 +305,11 @@ impl Expression {
         Self::variadic(VariadicOperator::Or, exprs)
     }
 
+    /// Creates a new expression COALESCE(exprs...)
+    pub fn coalesce(exprs: impl IntoIterator<Item = Self>) -> Self {
+        Self::variadic(VariadicOperator::Coalesce, exprs)
+    }
+
     /// Create a new expression `self IS NULL`
     pub fn is_null(self) -> Self {
         Self::unary(UnaryOperator::IsNull, self)
@@ -359,6 +375,11 @@ impl Expression {
         Self::binary(BinaryOperator::Distinct, self, other)
     }
 
+    /// Create a new expression `NULLIF(self, other)`
+    pub fn null_if(self, other: impl Into<Self>) -> Self {
+        Self::binary(BinaryOperator::NullIf, self, other)
+    }
+
     fn walk(&self) -> impl Iterator<Item = &Self> + '_ {
         use Expression::*;
         let mut stack = vec![self];
@@ -373,7 +394,9 @@ impl Expression {
                     stack.push(left);
                     stack.push(right);
                 }
-                Variadic(VariadicExpression { exprs, .. }) => stack.extend(exprs),
+                Variadic(VariadicExpression { exprs, .. }) => {
+                    stack.extend(exprs.iter().map(AsRef::as_ref))
+                }
             }
             Some(expr)
         })
@@ -526,13 +549,18 @@ pub trait ExpressionTransform<'a> {
         use Cow::*;
         let left = self.transform(&b.left)?;
         let right = self.transform(&b.right)?;
+        // An unchanged child is shared with the new expression, rather than copied into it
+        let share = |child: Cow<'a, Expression>, original: &ExpressionRef| match child {
+            Borrowed(_) => original.clone(),
+            Owned(child) => ExpressionRef::new(child),
+        };
         let b = match (&left, &right) {
             (Borrowed(_), Borrowed(_)) => Borrowed(b),
-            _ => Owned(BinaryExpression::new(
-                b.op,
-                left.into_owned(),
-                right.into_owned(),
-            )),
+            _ => Owned(BinaryExpression {
+                op: b.op,
+                left: share(left, &b.left),
+                right: share(right, &b.right),
+            }),
         };
         Some(b)
     }
@@ -545,11 +573,27 @@ pub trait ExpressionTransform<'a> {
         v: &'a VariadicExpression,
     ) -> Option<Cow<'a, VariadicExpression>> {
         use Cow::*;
-        let v = match self.recurse_into_struct(&v.exprs)? {
-            Owned(exprs) => Owned(VariadicExpression::new(v.op, exprs)),
-            Borrowed(_) => Borrowed(v),
-        };
-        Some(v)
+        let mut num_borrowed = 0;
+        // Unchanged children are shared with the new expression, rather than copied into it
+        let exprs: Vec<_> = v
+            .exprs
+            .iter()
+            .filter_map(|child| match self.transform(child)? {
+                Borrowed(_) => {
+                    num_borrowed += 1;
+                    Some(child.clone())
+                }
+                Owned(new_child) => Some(ExpressionRef::new(new_child)),
+            })
+            .collect();
+
+        if exprs.is_empty() {
+            None // all children filtered out
+        } else if num_borrowed < v.exprs.len() {
+            Some(Owned(VariadicExpression { op: v.op, exprs }))
+        } else {
+            Some(Borrowed(v))
+        }
     }
 }
 
@@ -675,8 +719,77 @@ impl<'a> ExpressionTransform<'a> for ExpressionDepthChecker {
 
 #[cfg(test)]
 mod tests {
-    use super::{column_expr, Expression as Expr, ExpressionDepthChecker};
+    use std::borrow::Cow;
     use std::ops::Not;
+    use std::sync::Arc;
+
+    use super::{
+        column_expr, BinaryExpression, ColumnName, Expression as Expr, ExpressionDepthChecker,
+        ExpressionTransform, VariadicExpression,
+    };
+
+    #[test]
+    fn test_shared_children() {
+        let big = Expr::and_from((0..100).map(|i| column_expr!("x").ne(i)));
+        let expr = big.clone().lt(column_expr!("y"));
+        let Expr::Binary(BinaryExpression { left, .. }) = &expr else {
+            panic!("Expected a binary expression");
+        };
+
+        // cloning an expression shares its children
+        let Expr::Binary(BinaryExpression { left: cloned, .. }) = expr.clone() else {
+            panic!("Expected a binary expression");
+        };
+        assert!(Arc::ptr_eq(left, &cloned));
+
+        // and so does transforming one child of a binary expression but not the other
+        struct RenameY;
+        impl<'a> ExpressionTransform<'a> for RenameY {
+            fn transform_column(&mut self, name: &'a ColumnName) -> Option<Cow<'a, ColumnName>> {
+                if *name == ColumnName::new(["y"]) {
+                    return Some(Cow::Owned(ColumnName::new(["z"])));
+                }
+                Some(Cow::Borrowed(name))
+            }
+        }
+        let transformed = RenameY.transform(&expr).unwrap().into_owned();
+        assert_eq!(transformed, big.clone().lt(column_expr!("z")));
+        let Expr::Binary(BinaryExpression { left: renamed, .. }) = transformed else {
+            panic!("Expected a binary expression");
+        };
+        assert!(Arc::ptr_eq(left, &renamed));
+
+        // the children of a variadic expression are shared when cloning it, and when transforming
+        // some of its children but not the others
+        let Expr::Variadic(VariadicExpression { exprs, .. }) = &big else {
+            panic!("Expected a variadic expression");
+        };
+        let Expr::Variadic(VariadicExpression { exprs: cloned, .. }) = big.clone() else {
+            panic!("Expected a variadic expression");
+        };
+        assert!(exprs.iter().zip(&cloned).all(|(a, b)| Arc::ptr_eq(a, b)));
+
+        let conjuncts = Expr::and_from(
+            [column_expr!("y").eq(1)]
+                .into_iter()
+                .chain(exprs.iter().map(|expr| expr.as_ref().clone())),
+        );
+        let transformed = RenameY.transform(&conjuncts).unwrap().into_owned();
+        let Expr::Variadic(VariadicExpression { exprs: renamed, .. }) = &transformed else {
+            panic!("Expected a variadic expression");
+        };
+        let Expr::Variadic(VariadicExpression {
+            exprs: original, ..
+        }) = &conjuncts
+        else {
+            panic!("Expected a variadic expression");
+        };
+        assert_eq!(*renamed[0], column_expr!("z").eq(1));
+        assert!(renamed[1..]
+            .iter()
+            .zip(&original[1..])
+            .all(|(a, b)| Arc::ptr_eq(a, b)));
+    }
 
     #[test]
     fn test_expression_format() {
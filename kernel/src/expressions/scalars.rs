@@ -383,9 +383,17 @@ impl PrimitiveType {
             // is not adjusted to UTC, this is just so we can (de-)serialize it as a date sting.
             // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#partition-value-serialization
             Timestamp | TimestampNtz => {
-                let timestamp = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f")
-                    .map_err(|_| self.parse_error(raw))?;
-                let timestamp = Utc.from_utc_datetime(&timestamp);
+                let timestamp = match NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f")
+                    .or_else(|_| NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f"))
+                {
+                    Ok(timestamp) => Utc.from_utc_datetime(&timestamp),
+                    // Timestamps may also be written in ISO 8601 with a time zone (usually `Z`),
+                    // which timestamps without time zone can't have
+                    Err(_) if *self == Timestamp => DateTime::parse_from_rfc3339(raw)
+                        .map_err(|_| self.parse_error(raw))?
+                        .to_utc(),
+                    Err(_) => return Err(self.parse_error(raw)),
+                };
                 let micros = timestamp
                     .signed_duration_since(DateTime::UNIX_EPOCH)
                     .num_microseconds()
@@ -448,15 +456,26 @@ impl PrimitiveType {
         // we can assume this won't underflow since `frac_digits` is at minimum 0, and exp is at
         // most i128::MAX, and 0-i128::MAX doesn't underflow
         let scale = frac_digits - exp;
-        let scale: u8 = scale.try_into().map_err(|_| self.parse_error(raw))?;
-        require!(scale == expected_scale, self.parse_error(raw));
-        Self::check_decimal(precision, scale)?;
+        Self::check_decimal(precision, expected_scale)?;
 
         let int: i128 = match frac_part {
             None => int_part.parse()?,
             Some(frac_part) => format!("{}{}", int_part, frac_part).parse()?,
         };
-        Ok(Scalar::Decimal(int, precision, scale))
+        // Values with fewer fractional digits than the scale (e.g. `1.5` or `1E+2`) are padded with
+        // zeros, but digits beyond the scale can't be dropped
+        let padding: u32 = (i128::from(expected_scale) - scale)
+            .try_into()
+            .map_err(|_| self.parse_error(raw))?;
+        let int = 10i128
+            .checked_pow(padding)
+            .and_then(|factor| int.checked_mul(factor))
+            .ok_or_else(|| self.parse_error(raw))?;
+        require!(
+            int.unsigned_abs() < 10u128.pow(precision.into()),
+            self.parse_error(raw)
+        );
+        Ok(Scalar::Decimal(int, precision, expected_scale))
     }
 }
 
@@ -513,6 +532,10 @@ mod tests {
         assert_decimal("1234.5E-4", 12345, 5, 5)?;
         assert_decimal("-0", 0, 1, 0)?;
         assert_decimal("12.000000000000000000", 12000000000000000000, 38, 18)?;
+        assert_decimal("12.3", 12300, 5, 3)?;
+        assert_decimal("-12", -1200, 4, 2)?;
+        assert_decimal("1.2E+2", 12000, 5, 2)?;
+        assert_decimal("1E2", 100, 3, 0)?;
         Ok(())
     }
 
@@ -542,6 +565,9 @@ mod tests {
         expect_fail_parse("-+1.0", 1, 1);
         expect_fail_parse("++1.0", 1, 1);
         expect_fail_parse("1.0E1+", 1, 1);
+        // too many digits for the precision
+        expect_fail_parse("123.4", 3, 1);
+        expect_fail_parse("1E2", 2, 0);
         // overflow i8 for `scale`
         expect_fail_parse("0.999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999", 0, 0);
         // scale will be too small to fit in i8
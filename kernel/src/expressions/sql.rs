@@ -0,0 +1,426 @@
+//! A parser for the subset of Spark SQL that Delta tables use to store expressions in their
+//! metadata, such as generation expressions of generated columns and CHECK constraints.
+//!
+//! Supported are column references (optionally backtick-escaped and dot-separated for nested
+//! fields), numeric, string and boolean literals, arithmetic (`+`, `-`, `*`, `/`), comparisons
+//! (`=`, `==`, `!=`, `<>`, `<`, `<=`, `>`, `>=`, `<=>`), `IS [NOT] NULL`, `AND`, `OR`, `NOT` and
+//! parentheses. Anything else, e.g. function calls, `CAST` or untyped `NULL` literals, is reported
+//! as [`Error::Unsupported`], because kernel expressions cannot represent it.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::{BinaryOperator, ColumnName, Expression, Scalar};
+use crate::{DeltaResult, Error};
+
+/// Parses a Spark SQL expression into a kernel [`Expression`].
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+pub(crate) fn parse_sql_expression(sql: &str) -> DeltaResult<Expression> {
+    let tokens = tokenize(sql)?;
+    let mut parser = Parser {
+        sql,
+        tokens: tokens.into_iter().peekable(),
+    };
+    let expr = parser.parse_or()?;
+    match parser.tokens.next() {
+        None => Ok(expr),
+        Some(token) => Err(parser.error(format!("unexpected {token:?}"))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare word: a keyword or an unescaped identifier
+    Word(String),
+    /// A backtick-escaped identifier
+    QuotedIdent(String),
+    /// A numeric literal, with an optional type suffix such as `L`
+    Number(String),
+    String(String),
+    Op(&'static str),
+    LeftParen,
+    RightParen,
+    Comma,
+    Dot,
+}
+
+// Longest operators first, so that e.g. `<=>` is not tokenized as `<=` followed by `>`
+const OPERATORS: &[&str] = &[
+    "<=>", "==", "!=", "<>", "<=", ">=", "=", "<", ">", "+", "-", "*", "/",
+];
+
+fn tokenize(sql: &str) -> DeltaResult<Vec<Token>> {
+    let mut chars = sql.chars().peekable();
+    let mut tokens = vec![];
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' | ',' | '.' => {
+                chars.next();
+                match c {
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    ',' => Token::Comma,
+                    _ => Token::Dot,
+                }
+            }
+            '`' => {
+                chars.next();
+                Token::QuotedIdent(parse_quoted(&mut chars, '`', sql)?)
+            }
+            '\'' | '"' => {
+                chars.next();
+                Token::String(parse_quoted(&mut chars, c, sql)?)
+            }
+            c if c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '.') {
+                    number.push(c);
+                }
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            _ => {
+                let rest: String = chars.clone().take(3).collect();
+                let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) else {
+                    return Err(Error::generic(format!(
+                        "Failed to parse SQL expression {sql:?}: unexpected character {c:?}"
+                    )));
+                };
+                for _ in 0..op.len() {
+                    chars.next();
+                }
+                Token::Op(op)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Parses the rest of a string literal or escaped identifier, whose opening `quote` was already
+/// consumed. A doubled quote stands for the quote itself. Backslash escapes are only recognized in
+/// string literals.
+fn parse_quoted(chars: &mut Peekable<Chars<'_>>, quote: char, sql: &str) -> DeltaResult<String> {
+    let mut result = String::new();
+    loop {
+        match chars.next() {
+            Some(c) if c == quote => {
+                if chars.next_if_eq(&quote).is_none() {
+                    return Ok(result);
+                }
+                result.push(quote);
+            }
+            Some('\\') if quote != '`' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some('0') => result.push('\0'),
+                Some(c) => result.push(c),
+                None => break,
+            },
+            Some(c) => result.push(c),
+            None => break,
+        }
+    }
+    Err(Error::generic(format!(
+        "Failed to parse SQL expression {sql:?}: unterminated {quote}"
+    )))
+}
+
+struct Parser<'a> {
+    sql: &'a str,
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: impl std::fmt::Display) -> Error {
+        Error::generic(format!(
+            "Failed to parse SQL expression {:?}: {msg}",
+            self.sql
+        ))
+    }
+
+    fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|t| matches!(t, Token::Word(w) if w.eq_ignore_ascii_case(keyword)))
+            .is_some()
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> DeltaResult<()> {
+        match self.next_if_keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.error(format!("expected {keyword}"))),
+        }
+    }
+
+    fn next_if_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self
+            .tokens
+            .next_if(|t| matches!(t, Token::Op(op) if ops.contains(op)))
+        {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    // expr := and_expr (OR and_expr)*
+    fn parse_or(&mut self) -> DeltaResult<Expression> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.next_if_keyword("OR") {
+            exprs.push(self.parse_and()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expression::or_from(exprs),
+        })
+    }
+
+    // and_expr := not_expr (AND not_expr)*
+    fn parse_and(&mut self) -> DeltaResult<Expression> {
+        let mut exprs = vec![self.parse_not()?];
+        while self.next_if_keyword("AND") {
+            exprs.push(self.parse_not()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expression::and_from(exprs),
+        })
+    }
+
+    // not_expr := NOT not_expr | predicate
+    fn parse_not(&mut self) -> DeltaResult<Expression> {
+        if self.next_if_keyword("NOT") {
+            return Ok(!self.parse_not()?);
+        }
+        self.parse_predicate()
+    }
+
+    // predicate := additive [comparison additive | IS [NOT] NULL]
+    fn parse_predicate(&mut self) -> DeltaResult<Expression> {
+        let left = self.parse_additive()?;
+        if self.next_if_keyword("IS") {
+            let negated = self.next_if_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(match negated {
+                true => left.is_not_null(),
+                false => left.is_null(),
+            });
+        }
+        let comparisons = ["<=>", "==", "!=", "<>", "<=", ">=", "=", "<", ">"];
+        let Some(op) = self.next_if_op(&comparisons) else {
+            return Ok(left);
+        };
+        let right = self.parse_additive()?;
+        Ok(match op {
+            "=" | "==" => left.eq(right),
+            "!=" | "<>" => left.ne(right),
+            "<" => left.lt(right),
+            "<=" => left.le(right),
+            ">" => left.gt(right),
+            ">=" => left.ge(right),
+            _ => !left.distinct(right),
+        })
+    }
+
+    // additive := multiplicative ((+ | -) multiplicative)*
+    fn parse_additive(&mut self) -> DeltaResult<Expression> {
+        let mut expr = self.parse_multiplicative()?;
+        while let Some(op) = self.next_if_op(&["+", "-"]) {
+            let op = match op {
+                "+" => BinaryOperator::Plus,
+                _ => BinaryOperator::Minus,
+            };
+            expr = Expression::binary(op, expr, self.parse_multiplicative()?);
+        }
+        Ok(expr)
+    }
+
+    // multiplicative := unary ((* | /) unary)*
+    fn parse_multiplicative(&mut self) -> DeltaResult<Expression> {
+        let mut expr = self.parse_unary()?;
+        while let Some(op) = self.next_if_op(&["*", "/"]) {
+            let op = match op {
+                "*" => BinaryOperator::Multiply,
+                _ => BinaryOperator::Divide,
+            };
+            expr = Expression::binary(op, expr, self.parse_unary()?);
+        }
+        Ok(expr)
+    }
+
+    // unary := (+ | -) number | + unary | primary
+    fn parse_unary(&mut self) -> DeltaResult<Expression> {
+        match self.next_if_op(&["+", "-"]) {
+            Some(sign) => match self.tokens.next_if(|t| matches!(t, Token::Number(_))) {
+                Some(Token::Number(number)) => self.parse_number(sign == "-", &number),
+                _ if sign == "+" => self.parse_unary(),
+                _ => Err(Error::unsupported(format!(
+                    "Negation of non-literal values in SQL expression {:?}",
+                    self.sql
+                ))),
+            },
+            None => self.parse_primary(),
+        }
+    }
+
+    // primary := literal | column | ( expr )
+    fn parse_primary(&mut self) -> DeltaResult<Expression> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        match token {
+            Token::LeftParen => {
+                let expr = self.parse_or()?;
+                match self.tokens.next() {
+                    Some(Token::RightParen) => Ok(expr),
+                    _ => Err(self.error("expected ')'")),
+                }
+            }
+            Token::Number(number) => self.parse_number(false, &number),
+            Token::String(s) => Ok(Expression::literal(s)),
+            Token::Word(word) if word.eq_ignore_ascii_case("TRUE") => Ok(Expression::literal(true)),
+            Token::Word(word) if word.eq_ignore_ascii_case("FALSE") => {
+                Ok(Expression::literal(false))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("NULL") => Err(Error::unsupported(
+                format!("Untyped NULL literal in SQL expression {:?}", self.sql),
+            )),
+            Token::Word(word) | Token::QuotedIdent(word) => self.parse_column(word),
+            token => Err(self.error(format!("unexpected {token:?}"))),
+        }
+    }
+
+    // column := ident (. ident)*
+    fn parse_column(&mut self, first: String) -> DeltaResult<Expression> {
+        if matches!(self.tokens.peek(), Some(Token::LeftParen)) {
+            return Err(Error::unsupported(format!(
+                "Function {first} in SQL expression {:?}",
+                self.sql
+            )));
+        }
+        let mut path = vec![first];
+        while self.tokens.next_if_eq(&Token::Dot).is_some() {
+            match self.tokens.next() {
+                Some(Token::Word(name) | Token::QuotedIdent(name)) => path.push(name),
+                _ => return Err(self.error("expected field name after '.'")),
+            }
+        }
+        Ok(Expression::column(ColumnName::new(path)))
+    }
+
+    /// Parses a numeric literal following Spark's typing rules: integers are INT if they fit and
+    /// BIGINT otherwise, numbers with a fractional part are DECIMAL, and the suffixes `Y`, `S`, `L`
+    /// and `D` force TINYINT, SMALLINT, BIGINT and DOUBLE respectively.
+    fn parse_number(&self, negative: bool, number: &str) -> DeltaResult<Expression> {
+        let invalid = || self.error(format!("invalid numeric literal {number}"));
+        let (digits, suffix) = number
+            .find(|c: char| c.is_ascii_alphabetic())
+            .map_or((number, ""), |pos| number.split_at(pos));
+        let digits = match negative {
+            true => format!("-{digits}"),
+            false => digits.to_string(),
+        };
+        let scalar = match suffix.to_ascii_uppercase().as_str() {
+            "Y" => Scalar::Byte(digits.parse().map_err(|_| invalid())?),
+            "S" => Scalar::Short(digits.parse().map_err(|_| invalid())?),
+            "L" => Scalar::Long(digits.parse().map_err(|_| invalid())?),
+            "D" => Scalar::Double(digits.parse().map_err(|_| invalid())?),
+            "" if digits.contains('.') => parse_decimal(&digits).ok_or_else(invalid)?,
+            "" => match digits.parse::<i32>() {
+                Ok(i) => Scalar::Integer(i),
+                Err(_) => Scalar::Long(digits.parse().map_err(|_| invalid())?),
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(Expression::literal(scalar))
+    }
+}
+
+/// Parses a decimal literal such as `-12.50` into a DECIMAL(4, 2) scalar.
+fn parse_decimal(digits: &str) -> Option<Scalar> {
+    let (int_part, frac_part) = digits.split_once('.')?;
+    let scale = u8::try_from(frac_part.len()).ok()?;
+    let unscaled: i128 = format!("{int_part}{frac_part}").parse().ok()?;
+    let significant_digits = unscaled
+        .unsigned_abs()
+        .checked_ilog10()
+        .map_or(1, |d| d + 1);
+    let precision = u8::try_from(significant_digits).ok()?.max(scale).max(1);
+    (precision <= 38).then_some(Scalar::Decimal(unscaled, precision, scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::column_expr;
+
+    #[test]
+    fn test_parse_sql_expression() {
+        use BinaryOperator::*;
+        let cases = [
+            ("a", column_expr!("a")),
+            (" `a.b` . c ", Expression::column(["a.b", "c"])),
+            ("a.b + 1", Expression::binary(Plus, column_expr!("a.b"), 1)),
+            (
+                "a * 2 - b / 3L",
+                Expression::binary(
+                    Minus,
+                    Expression::binary(Multiply, column_expr!("a"), 2),
+                    Expression::binary(Divide, column_expr!("b"), 3i64),
+                ),
+            ),
+            (
+                "(a + -1) * 2",
+                Expression::binary(Multiply, Expression::binary(Plus, column_expr!("a"), -1), 2),
+            ),
+            ("x > 0", column_expr!("x").gt(Expression::literal(0))),
+            (
+                "x <> 'it''s'",
+                column_expr!("x").ne(Expression::literal("it's")),
+            ),
+            ("x <=> y", !column_expr!("x").distinct(column_expr!("y"))),
+            (
+                "x IS NOT NULL and not y is null OR z",
+                Expression::or_from([
+                    Expression::and_from([
+                        column_expr!("x").is_not_null(),
+                        !column_expr!("y").is_null(),
+                    ]),
+                    column_expr!("z"),
+                ]),
+            ),
+            ("TRUE", Expression::literal(true)),
+            ("12.50", Expression::literal(Scalar::Decimal(1250, 4, 2))),
+            ("0.5", Expression::literal(Scalar::Decimal(5, 1, 1))),
+            ("3000000000", Expression::literal(3000000000i64)),
+            ("1.5D", Expression::literal(1.5f64)),
+            ("\"a\\tb\"", Expression::literal("a\tb")),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(parse_sql_expression(sql).unwrap(), expected, "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_parse_unsupported_sql_expression() {
+        for sql in ["CAST(ts AS DATE)", "-a", "NULL", "year(ts)"] {
+            let err = parse_sql_expression(sql).unwrap_err();
+            assert!(matches!(err, Error::Unsupported(_)), "{sql}: {err}");
+        }
+        for sql in ["", "a +", "(a", "a b", "'abc", "a = = b", "1x", "a IS NOT"] {
+            let err = parse_sql_expression(sql).unwrap_err();
+            assert!(matches!(err, Error::Generic(_)), "{sql}: {err}");
+        }
+    }
+}
@@ -65,6 +65,7 @@ use std::{cmp::Ordering, ops::Range};
 use bytes::Bytes;
 use url::Url;
 
+use self::expressions::Scalar;
 use self::schema::{DataType, SchemaRef};
 
 pub mod actions;
@@ -73,13 +74,24 @@ pub mod error;
 pub mod expressions;
 pub mod scan;
 pub mod schema;
+pub mod schema_diff;
 pub mod snapshot;
+pub mod snapshot_cache;
 pub mod table;
 pub mod table_changes;
 pub mod table_features;
 pub mod table_properties;
 pub mod transaction;
+pub mod vacuum;
+pub mod verify;
 
+#[cfg(feature = "async-engine")]
+pub mod async_engine;
+
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+pub(crate) mod checkpoint;
 pub(crate) mod predicates;
 pub(crate) mod utils;
 
@@ -94,13 +106,13 @@ pub mod log_segment;
 pub(crate) mod log_segment;
 
 pub use delta_kernel_derive;
-pub use engine_data::{EngineData, RowVisitor};
-pub use error::{DeltaResult, Error};
+pub use engine_data::{EngineData, FilteredEngineData, RowVisitor};
+pub use error::{DeltaResult, Error, ErrorContext, ErrorKind, UnsupportedFeatures};
 pub use expressions::{Expression, ExpressionRef};
 pub use table::Table;
 
 #[cfg(any(
-    feature = "default-engine",
+    feature = "default-engine-base",
     feature = "sync-engine",
     feature = "arrow-conversion"
 ))]
@@ -151,6 +163,125 @@ impl FileMeta {
             size,
         }
     }
+
+    /// Whether this is a directory returned by a [delimited] listing, i.e. its location ends with
+    /// a `/`.
+    ///
+    /// [delimited]: ListOptions::delimited
+    pub fn is_directory(&self) -> bool {
+        self.location.path().ends_with('/')
+    }
+}
+
+/// Options which narrow down the files listed by [`FileSystemClient::list_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    /// List only the direct children of the directory, as with a `/` delimiter, rather than all
+    /// of the files under it. The subdirectories are listed as [directories].
+    ///
+    /// [directories]: FileMeta::is_directory
+    pub delimited: bool,
+    /// The maximum number of results to list.
+    pub max_results: Option<usize>,
+    /// Only list files last modified at or after this time, in milliseconds since unix epoch.
+    pub modified_after: Option<i64>,
+    /// Only list files last modified before this time, in milliseconds since unix epoch.
+    pub modified_before: Option<i64>,
+    /// Only list files with at least this size in bytes.
+    pub min_size: Option<usize>,
+    /// Only list files with at most this size in bytes.
+    pub max_size: Option<usize>,
+}
+
+impl ListOptions {
+    /// List only the direct children of the directory, see [`Self::delimited`].
+    pub fn with_delimiter(mut self) -> Self {
+        self.delimited = true;
+        self
+    }
+
+    /// List at most `max_results` results, see [`Self::max_results`].
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Only list files last modified in the given range (of milliseconds since unix epoch).
+    pub fn with_modified_range(mut self, after: Option<i64>, before: Option<i64>) -> Self {
+        self.modified_after = after;
+        self.modified_before = before;
+        self
+    }
+
+    /// Only list files with sizes in the given (inclusive) range.
+    pub fn with_size_range(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_size = min;
+        self.max_size = max;
+        self
+    }
+
+    /// Whether `file` passes the filters of these options. Directories always pass.
+    pub fn matches(&self, file: &FileMeta) -> bool {
+        if file.is_directory() {
+            return true;
+        }
+        self.modified_after
+            .map_or(true, |t| file.last_modified >= t)
+            && self
+                .modified_before
+                .map_or(true, |t| file.last_modified < t)
+            && self.min_size.map_or(true, |size| file.size >= size)
+            && self.max_size.map_or(true, |size| file.size <= size)
+    }
+
+    // Apply the delimiter, the filters and the maximum number of results of these options to a
+    // (sorted) listing from `path`
+    pub(crate) fn apply(
+        &self,
+        path: &Url,
+        files: impl Iterator<Item = DeltaResult<FileMeta>> + 'static,
+    ) -> Box<dyn Iterator<Item = DeltaResult<FileMeta>>> {
+        let files: Box<dyn Iterator<Item = _>> = match self.delimited {
+            true => Box::new(direct_children(path, files)),
+            false => Box::new(files),
+        };
+        let options = self.clone();
+        let files = files.filter(move |file| file.as_ref().map_or(true, |f| options.matches(f)));
+        Box::new(files.take(self.max_results.unwrap_or(usize::MAX)))
+    }
+}
+
+// Replace the files in subdirectories of a sorted listing from `path` by (one entry for) each
+// subdirectory. The files of a subdirectory are listed consecutively, since they share its prefix.
+fn direct_children(
+    path: &Url,
+    files: impl Iterator<Item = DeltaResult<FileMeta>>,
+) -> impl Iterator<Item = DeltaResult<FileMeta>> {
+    // the directory of `path`, which is `path` itself if it ends with a `/`
+    let directory = path.join(".").map(String::from);
+    let mut last_subdirectory = None;
+    files.filter_map(move |file| {
+        let (file, directory) = match (file, &directory) {
+            (Ok(file), Ok(directory)) => (file, directory),
+            (Err(err), _) => return Some(Err(err)),
+            (_, Err(err)) => return Some(Err(Error::from(*err))),
+        };
+        let relative_path = file.location.as_str().strip_prefix(directory.as_str());
+        let Some((name, rest)) = relative_path.and_then(|path| path.split_once('/')) else {
+            return Some(Ok(file));
+        };
+        let subdirectory = format!("{directory}{name}/");
+        if last_subdirectory.as_ref() == Some(&subdirectory) {
+            return None;
+        }
+        last_subdirectory = Some(subdirectory.clone());
+        if rest.is_empty() {
+            // the subdirectory itself
+            return Some(Ok(file));
+        }
+        let subdirectory = Url::parse(&subdirectory).map_err(Error::from);
+        Some(subdirectory.map(|location| FileMeta::new(location, 0, 0)))
+    })
 }
 
 /// Extension trait that makes it easier to work with traits objects that implement [`Any`],
@@ -306,6 +437,30 @@ pub trait ExpressionHandler: AsAny {
         expression: Expression,
         output_type: DataType,
     ) -> Arc<dyn ExpressionEvaluator>;
+
+    /// Create a single-row [`EngineData`] with the given [`Schema`], containing one value for
+    /// each top-level field of the schema (in schema order). Kernel uses this to produce small
+    /// pieces of data it needs to write, such as the `_last_checkpoint` file.
+    ///
+    /// # Parameters
+    ///
+    /// - `schema`: Schema of the output data.
+    /// - `values`: One [`Scalar`] per top-level field of `schema`. Nulls are represented by
+    ///   [`Scalar::Null`].
+    ///
+    /// The default implementation returns [`Error::Unsupported`], so kernel operations which need
+    /// it (e.g. writing checkpoints) fail for engines which don't implement it.
+    ///
+    /// [`Schema`]: crate::schema::StructType
+    fn create_one(
+        &self,
+        _schema: SchemaRef,
+        _values: &[Scalar],
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        Err(Error::unsupported(
+            "This expression handler can't create single-row data",
+        ))
+    }
 }
 
 /// Provides file system related functionalities to Delta Kernel.
@@ -319,11 +474,55 @@ pub trait FileSystemClient: AsAny {
     fn list_from(&self, path: &Url)
         -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>>;
 
+    /// List the paths in the directory of the given `path` which are lexicographically greater or
+    /// equal to it, like [`Self::list_from`], but narrowed down by the given [`ListOptions`].
+    /// Clients should push the options down to the underlying store where it supports them.
+    ///
+    /// The default implementation filters the results of [`Self::list_from`]. If they include
+    /// files in subdirectories, [delimited](ListOptions::delimited) listings replace them by
+    /// their subdirectories.
+    fn list_with_options(
+        &self,
+        path: &Url,
+        options: &ListOptions,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        Ok(options.apply(path, self.list_from(path)?))
+    }
+
+    /// Get the metadata of the file at `path`, failing with [`Error::FileNotFound`] if there is
+    /// no such file.
+    ///
+    /// The default implementation lists the directory of the file from its path.
+    fn head(&self, path: &Url) -> DeltaResult<FileMeta> {
+        match self.list_from(path)?.next().transpose()? {
+            Some(file) if file.location == *path => Ok(file),
+            _ => Err(Error::file_not_found(path)),
+        }
+    }
+
     /// Read data specified by the start and end offset from the file.
     fn read_files(
         &self,
         files: Vec<FileSlice>,
     ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>>;
+
+    /// Atomically (!) write `data` to the file at `path`, i.e. the file must either be written
+    /// completely or not at all. Kernel uses this to write files that are not data or log files,
+    /// such as deletion vectors.
+    ///
+    /// # Parameters
+    ///
+    /// - `path` - URL specifying the location to write the file
+    /// - `data` - The bytes to write
+    /// - `overwrite` - If true, overwrite the file if it exists. If false, the call must fail with
+    ///   [`Error::FileAlreadyExists`] if the file exists.
+    ///
+    /// The default implementation returns an [`Error::Unsupported`] error.
+    fn write_file(&self, path: &Url, _data: Bytes, _overwrite: bool) -> DeltaResult<()> {
+        Err(Error::unsupported(format!(
+            "Writing files is not supported by this file system client (writing {path})"
+        )))
+    }
 }
 
 /// Provides JSON handling functionality to Delta Kernel.
@@ -340,6 +539,21 @@ pub trait JsonHandler: AsAny {
         output_schema: SchemaRef,
     ) -> DeltaResult<Box<dyn EngineData>>;
 
+    /// Like [`JsonHandler::parse_json`], but only the json strings of the rows selected by
+    /// `selection_vector` are needed (entries missing from the vector count as selected). The
+    /// result must still have one row per input row, but engines may skip parsing the unselected
+    /// strings and return all-null rows for them instead. The default implementation parses every
+    /// string.
+    fn parse_json_selected(
+        &self,
+        json_strings: Box<dyn EngineData>,
+        output_schema: SchemaRef,
+        selection_vector: &[bool],
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let _ = selection_vector;
+        self.parse_json(json_strings, output_schema)
+    }
+
     /// Read and parse the JSON format file at given locations and return
     /// the data as EngineData with the columns requested by physical schema.
     ///
@@ -398,13 +612,43 @@ pub trait ParquetHandler: AsAny {
     ///
     /// - `files` - File metadata for files to be read.
     /// - `physical_schema` - Select list and order of columns to read from the Parquet file.
-    /// - `predicate` - Optional push-down predicate hint (engine is free to ignore it).
+    /// - `predicate` - Optional push-down predicate hint (engine is free to ignore it). The engine
+    ///   may skip any row groups and rows for which the predicate is provably false, e.g. by
+    ///   pruning row groups with their statistics or by evaluating the predicate over the columns
+    ///   it references before decoding the others. Kernel does not pass a predicate when it relies
+    ///   on the positions of the returned rows (e.g. to apply a deletion vector).
     fn read_parquet_files(
         &self,
         files: &[FileMeta],
         physical_schema: SchemaRef,
         predicate: Option<ExpressionRef>,
     ) -> DeltaResult<FileDataReadResultIterator>;
+
+    /// Write a single Parquet file containing the selected rows of each batch of `data`, replacing
+    /// the file if it already exists, and return the metadata of the written file. The file must
+    /// either be written completely or not at all (e.g. by writing a temporary file and renaming
+    /// it, or by a single object store PUT). Kernel uses this to write checkpoint files.
+    ///
+    /// # Parameters
+    ///
+    /// - `location` - URL specifying the location to write the Parquet file
+    /// - `schema` - The schema of the file. The columns of each batch of `data` match the fields
+    ///   of `schema` by position, but their names and nullability may differ (for example,
+    ///   between batches read from JSON commit files and batches read from Parquet checkpoints).
+    /// - `data` - Iterator of [`FilteredEngineData`] to write. Only the selected rows of each
+    ///   batch must be written, in order.
+    ///
+    /// The default implementation returns an [`Error::Unsupported`] error.
+    fn write_parquet(
+        &self,
+        location: &Url,
+        _schema: SchemaRef,
+        _data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+    ) -> DeltaResult<FileMeta> {
+        Err(Error::unsupported(format!(
+            "Writing parquet files is not supported by this parquet handler (writing {location})"
+        )))
+    }
 }
 
 /// The `Engine` trait encapsulates all the functionality an engine or connector needs to provide
@@ -425,3 +669,73 @@ pub trait Engine: AsAny {
     /// Get the connector provided [`ParquetHandler`].
     fn get_parquet_handler(&self) -> Arc<dyn ParquetHandler>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A file system client which lists all files under a directory, like object stores do
+    struct RecursiveListingClient(Vec<FileMeta>);
+
+    impl FileSystemClient for RecursiveListingClient {
+        fn list_from(
+            &self,
+            path: &Url,
+        ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+            let files: Vec<_> = self
+                .0
+                .iter()
+                .filter(|file| file.location >= *path)
+                .cloned()
+                .map(Ok)
+                .collect();
+            Ok(Box::new(files.into_iter()))
+        }
+
+        fn read_files(
+            &self,
+            _files: Vec<FileSlice>,
+        ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_default_delimited_listing() {
+        let root = Url::parse("memory:///table/").unwrap();
+        let file = |path: &str, size| FileMeta::new(root.join(path).unwrap(), 1, size);
+        let client = RecursiveListingClient(vec![
+            file("a.parquet", 1),
+            file("part=1/b.parquet", 2),
+            file("part=1/c.parquet", 3),
+            file("part=2/nested/d.parquet", 4),
+            file("z.parquet", 5),
+        ]);
+        let list = |options: ListOptions| -> Vec<String> {
+            client
+                .list_with_options(&root, &options)
+                .unwrap()
+                .map(|file| file.unwrap().location.path().to_string())
+                .collect()
+        };
+        assert_eq!(list(ListOptions::default()).len(), 5);
+        assert_eq!(
+            list(ListOptions::default().with_delimiter()),
+            [
+                "/table/a.parquet",
+                "/table/part=1/",
+                "/table/part=2/",
+                "/table/z.parquet"
+            ]
+        );
+        // directories pass all filters
+        assert_eq!(
+            list(
+                ListOptions::default()
+                    .with_delimiter()
+                    .with_size_range(Some(5), None)
+            ),
+            ["/table/part=1/", "/table/part=2/", "/table/z.parquet"]
+        );
+    }
+}
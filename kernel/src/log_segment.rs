@@ -2,15 +2,16 @@
 //! files.
 
 use crate::actions::{get_log_schema, Metadata, Protocol, METADATA_NAME, PROTOCOL_NAME};
-use crate::path::ParsedLogPath;
+use crate::path::{LogPathFileType, ParsedLogPath};
 use crate::schema::SchemaRef;
 use crate::snapshot::CheckpointMetadata;
 use crate::utils::require;
 use crate::{
-    DeltaResult, Engine, EngineData, Error, Expression, ExpressionRef, FileSystemClient, Version,
+    DeltaResult, Engine, EngineData, Error, ErrorContext, ErrorKind, Expression, ExpressionRef,
+    FileSystemClient, Version,
 };
 use itertools::Itertools;
-use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::convert::identity;
 use std::sync::{Arc, LazyLock};
 use tracing::warn;
@@ -117,16 +118,29 @@ impl LogSegment {
     ) -> DeltaResult<Self> {
         let time_travel_version = time_travel_version.into();
 
-        let (mut ascending_commit_files, checkpoint_parts) =
-            match (checkpoint_hint.into(), time_travel_version) {
-                (Some(cp), None) => {
-                    list_log_files_with_checkpoint(&cp, fs_client, &log_root, None)?
-                }
-                (Some(cp), Some(end_version)) if cp.version <= end_version => {
-                    list_log_files_with_checkpoint(&cp, fs_client, &log_root, Some(end_version))?
-                }
-                _ => list_log_files_with_version(fs_client, &log_root, None, time_travel_version)?,
-            };
+        // A stale or corrupt `_last_checkpoint` hint must never break snapshot construction, so if
+        // the hint disagrees with what we find in the log we fall back to a full listing.
+        let listed_with_hint = match (checkpoint_hint.into(), time_travel_version) {
+            (Some(cp), None) => list_log_files_with_checkpoint(&cp, fs_client, &log_root, None)?,
+            (Some(cp), Some(end_version)) if cp.version <= end_version => {
+                list_log_files_with_checkpoint(&cp, fs_client, &log_root, Some(end_version))?
+            }
+            _ => None,
+        };
+        let (mut ascending_commit_files, checkpoint_parts) = match listed_with_hint {
+            Some(listed) => listed,
+            None => list_log_files_with_version(fs_client, &log_root, None, time_travel_version)?,
+        };
+
+        if let Some(version) = time_travel_version {
+            check_time_travel_version(
+                fs_client,
+                &log_root,
+                version,
+                &ascending_commit_files,
+                &checkpoint_parts,
+            )?;
+        }
 
         // Commit file versions must be greater than the most recent checkpoint version if it exists
         if let Some(checkpoint_file) = checkpoint_parts.first() {
@@ -182,6 +196,16 @@ impl LogSegment {
         );
         LogSegment::try_new(ascending_commit_files, vec![], log_root, end_version)
     }
+
+    /// The total size of the commit and checkpoint files of this log segment, in bytes.
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.ascending_commit_files
+            .iter()
+            .chain(&self.checkpoint_parts)
+            .map(|file| file.location.size)
+            .sum()
+    }
+
     /// Read a stream of log data from this log segment.
     ///
     /// The log files will be read from most recent to oldest.
@@ -209,9 +233,11 @@ impl LogSegment {
             .rev()
             .map(|f| f.location.clone())
             .collect();
+        let table_root = self.log_root.join("../")?;
         let commit_stream = engine
             .get_json_handler()
-            .read_json_files(&commit_files, commit_read_schema, meta_predicate.clone())?
+            .read_json_files(&commit_files, commit_read_schema, meta_predicate.clone())
+            .map_err(|err| log_error(err, &table_root))?
             .map_ok(|batch| (batch, true));
 
         let checkpoint_parts: Vec<_> = self
@@ -221,10 +247,13 @@ impl LogSegment {
             .collect();
         let checkpoint_stream = engine
             .get_parquet_handler()
-            .read_parquet_files(&checkpoint_parts, checkpoint_read_schema, meta_predicate)?
+            .read_parquet_files(&checkpoint_parts, checkpoint_read_schema, meta_predicate)
+            .map_err(|err| log_error(err, &table_root))?
             .map_ok(|batch| (batch, false));
 
-        Ok(commit_stream.chain(checkpoint_stream))
+        Ok(commit_stream
+            .chain(checkpoint_stream)
+            .map(move |res| res.map_err(|err| log_error(err, &table_root))))
     }
 
     // Get the most up-to-date Protocol and Metadata actions
@@ -252,6 +281,108 @@ impl LogSegment {
         }
     }
 
+    /// Get the most up-to-date Protocol and Metadata actions like [`Self::read_metadata`], but if
+    /// that fails because the checkpoint the log segment starts at is corrupt or missing, retry
+    /// with the previous complete checkpoint, or by replaying all commits once there's none left.
+    /// Returns the log segment the actions were read from. Other errors (e.g. reading a commit
+    /// file, or a transient storage error) are returned right away.
+    pub(crate) fn read_metadata_with_fallback(
+        mut self,
+        engine: &dyn Engine,
+    ) -> DeltaResult<(Self, Metadata, Protocol)> {
+        loop {
+            let err = match self.read_metadata(engine) {
+                Ok((metadata, protocol)) => return Ok((self, metadata, protocol)),
+                Err(err) => err,
+            };
+            let Some(checkpoint) = self.checkpoint_parts.first() else {
+                return Err(err);
+            };
+            // A checkpoint has both the protocol and the metadata of the table
+            let err = match err {
+                Error::MissingMetadata
+                | Error::MissingProtocol
+                | Error::MissingMetadataAndProtocol => Error::invalid_checkpoint(format!(
+                    "{err} in the checkpoint at version {}",
+                    checkpoint.version
+                )),
+                err => err,
+            };
+            if !is_checkpoint_error(&err) {
+                return Err(err);
+            }
+            warn!(
+                "Failed to read the checkpoint at version {} of {}: {err}. Falling back to the log before it.",
+                checkpoint.version, self.log_root
+            );
+            let fs_client = engine.get_file_system_client();
+            self = match self.without_checkpoint(fs_client.as_ref()) {
+                Ok(log_segment) => log_segment,
+                Err(fallback_err) => {
+                    warn!("Failed to fall back to the log before the checkpoint: {fallback_err}");
+                    return Err(err);
+                }
+            };
+        }
+    }
+
+    // The log segment for the same version which starts at the complete checkpoint before this
+    // segment's checkpoint, or at commit 0 if there's none. Fails if the commits needed to replace
+    // the checkpoint were cleaned up.
+    fn without_checkpoint(&self, fs_client: &dyn FileSystemClient) -> DeltaResult<Self> {
+        let checkpoint_version = self
+            .checkpoint_parts
+            .first()
+            .ok_or_else(|| Error::generic("Log segment has no checkpoint"))?
+            .version;
+        let (mut commit_files, checkpoint_parts) = match checkpoint_version.checked_sub(1) {
+            Some(previous) => {
+                list_log_files_with_version(fs_client, &self.log_root, None, Some(previous))?
+            }
+            None => (vec![], vec![]),
+        };
+        commit_files.extend(list_commit_files(
+            fs_client,
+            &self.log_root,
+            checkpoint_version,
+            self.end_version,
+        )?);
+        if let Some(checkpoint_file) = checkpoint_parts.first() {
+            commit_files.retain(|log_path| checkpoint_file.version < log_path.version);
+        } else {
+            require!(
+                commit_files.first().is_some_and(|commit| commit.version == 0),
+                Error::generic(format!(
+                    "No checkpoint before version {checkpoint_version} and no commit 0 in the log at {}",
+                    self.log_root
+                ))
+            );
+        }
+        LogSegment::try_new(
+            commit_files,
+            checkpoint_parts,
+            self.log_root.clone(),
+            Some(self.end_version),
+        )
+    }
+
+    /// The log segment for the same version made of all commits from commit 0, without any
+    /// checkpoint, e.g. to validate a checkpoint against. None if commits were cleaned up.
+    pub(crate) fn commits_only(
+        &self,
+        fs_client: &dyn FileSystemClient,
+    ) -> DeltaResult<Option<Self>> {
+        let commit_files = list_commit_files(fs_client, &self.log_root, None, self.end_version)?;
+        if !commit_files
+            .first()
+            .is_some_and(|commit| commit.version == 0)
+        {
+            return Ok(None);
+        }
+        let log_root = self.log_root.clone();
+        LogSegment::try_new(commit_files, vec![], log_root, Some(self.end_version)).map(Some)
+    }
+
     // Replay the commit log, projecting rows to only contain Protocol and Metadata action columns.
     fn replay_for_metadata(
         &self,
@@ -270,12 +401,115 @@ impl LogSegment {
     }
 }
 
+/// The earliest version of the table that a snapshot can be created for: version 0 if its commit
+/// is still in the log, and otherwise the version of the earliest complete checkpoint. Older
+/// versions can't be reconstructed, because their log files were cleaned up (or the log was
+/// truncated when the table was cloned, etc).
+pub(crate) fn earliest_version(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+) -> DeltaResult<Version> {
+    // all checkpoint files seen so far for the earliest checkpoint version
+    let mut pending_checkpoint_files: Vec<ParsedLogPath> = vec![];
+    for parsed_path in list_log_files(fs_client, log_root, None, None)? {
+        let parsed_path = parsed_path?;
+        if parsed_path.is_commit() && parsed_path.version == 0 {
+            return Ok(0);
+        }
+        let new_version = pending_checkpoint_files
+            .first()
+            .is_some_and(|pending| pending.version != parsed_path.version);
+        if new_version {
+            let pending = std::mem::take(&mut pending_checkpoint_files);
+            if let Some(complete) = complete_checkpoint_parts(pending) {
+                return Ok(complete[0].version);
+            }
+        }
+        if parsed_path.is_checkpoint() {
+            pending_checkpoint_files.push(parsed_path);
+        }
+    }
+    match complete_checkpoint_parts(pending_checkpoint_files) {
+        Some(complete) => Ok(complete[0].version),
+        None => Err(Error::generic(format!(
+            "No checkpoint or initial commit found in the log at {log_root}"
+        ))),
+    }
+}
+
+// Check that the log files listed for a snapshot at `version` can reconstruct it, failing with a
+// dedicated error if the version is after the latest version of the table, or is too old for its
+// log files to still be around.
+fn check_time_travel_version(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+    version: Version,
+    ascending_commit_files: &[ParsedLogPath],
+    checkpoint_parts: &[ParsedLogPath],
+) -> DeltaResult<()> {
+    let latest_listed = ascending_commit_files
+        .iter()
+        .chain(checkpoint_parts)
+        .map(|file| file.version)
+        .max();
+    let starts_at_checkpoint_or_zero = !checkpoint_parts.is_empty()
+        || ascending_commit_files
+            .first()
+            .is_some_and(|commit| commit.version == 0);
+    match latest_listed {
+        Some(latest) if latest < version => Err(Error::version_beyond_latest(version, latest)),
+        Some(_) if starts_at_checkpoint_or_zero => Ok(()),
+        Some(_) => Err(Error::version_truncated(
+            version,
+            earliest_version(fs_client, log_root)?,
+        )),
+        // Nothing was listed up to `version`. If there are later versions, it was cleaned up.
+        None => match earliest_version(fs_client, log_root) {
+            Ok(earliest) if earliest > version => Err(Error::version_truncated(version, earliest)),
+            _ => Ok(()),
+        },
+    }
+}
+
+// True if the error occurred reading a commit file, as far as the engine attached the file to it
+fn is_commit_error(err: &Error) -> bool {
+    err.context()
+        .and_then(|context| context.file())
+        .and_then(|file| ParsedLogPath::try_from(file.clone()).ok().flatten())
+        .is_some_and(|log_path| log_path.is_commit())
+}
+
+// True if the error is one a corrupt (or missing) checkpoint causes, so that reading the log
+// without the checkpoint may succeed
+pub(crate) fn is_checkpoint_error(err: &Error) -> bool {
+    let kind = err.kind();
+    let checkpoint_kind = matches!(
+        kind,
+        ErrorKind::Parquet | ErrorKind::FileNotFound | ErrorKind::InvalidCheckpoint
+    );
+    checkpoint_kind && !is_commit_error(err)
+}
+
+// Annotate an error reading the log of the table at `table_root` with the table, and with the
+// version of the log file it occurred in (if the engine attached the file to the error)
+fn log_error(err: Error, table_root: &Url) -> Error {
+    let mut context = ErrorContext::new().with_table_root(table_root.clone());
+    let file = err.context().and_then(|context| context.file());
+    if let Some(Ok(Some(log_path))) = file.map(|file| ParsedLogPath::try_from(file.clone())) {
+        context = context.with_version(log_path.version);
+    }
+    err.with_context(context)
+}
+
 /// Returns a fallible iterator of [`ParsedLogPath`] that are between the provided `start_version` (inclusive)
 /// and `end_version` (inclusive). [`ParsedLogPath`] may be a commit or a checkpoint.  If `start_version` is
 /// not specified, the files will begin from version number 0. If `end_version` is not specified, files up to
 /// the most recent version will be included.
 ///
-/// Note: this calls [`FileSystemClient::list_from`] to get the list of log files.
+/// Note: this calls [`FileSystemClient::list_from`] to get the list of log files. Because the
+/// listing is sorted, it stops as soon as it reaches a file past `end_version`, or a file that
+/// doesn't start with a version (e.g. `_last_checkpoint` or the `_commits` directory), since all
+/// remaining files sort after it. Files in subdirectories of `log_root` are never log files.
 fn list_log_files(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
@@ -286,9 +520,18 @@ fn list_log_files(
     let end_version = end_version.into();
     let version_prefix = format!("{:020}", start_version);
     let start_from = log_root.join(&version_prefix)?;
+    let (log_root, filter_log_root) = (log_root.clone(), log_root.clone());
 
     Ok(fs_client
         .list_from(&start_from)?
+        .take_while(move |meta_res| match meta_res {
+            Ok(meta) => !is_past_log_files(&log_root, &meta.location),
+            Err(_) => true,
+        })
+        .filter(move |meta_res| match meta_res {
+            Ok(meta) => !is_in_subdirectory(&filter_log_root, &meta.location),
+            Err(_) => true,
+        })
         .map(|meta| ParsedLogPath::try_from(meta?))
         // TODO this filters out .crc files etc which start with "." - how do we want to use these kind of files?
         .filter_map_ok(identity)
@@ -297,11 +540,44 @@ fn list_log_files(
             Err(_) => true,
         }))
 }
+/// List the commit files of the log in ascending version order, from `start_version` (or the
+/// earliest available commit) up to and including `end_version` (or the latest commit).
+pub(crate) fn list_commit_files(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+    start_version: impl Into<Option<Version>>,
+    end_version: impl Into<Option<Version>>,
+) -> DeltaResult<Vec<ParsedLogPath>> {
+    list_log_files(fs_client, log_root, start_version, end_version)?
+        .filter_ok(|path| path.is_commit())
+        .try_collect()
+}
+
+// The name of a listed file relative to the log root, if the file is inside the log root
+fn name_in_log_root<'a>(log_root: &Url, location: &'a Url) -> Option<&'a str> {
+    location.path().strip_prefix(log_root.path())
+}
+
+// True if a (sorted) listing of the log root has moved past all versioned log files: every log
+// file name starts with a version number, and all other names sort after the digits.
+fn is_past_log_files(log_root: &Url, location: &Url) -> bool {
+    name_in_log_root(log_root, location)
+        .and_then(|name| name.chars().next())
+        .is_some_and(|c| c > '9')
+}
+
+// True if the file is inside a subdirectory of the log root, e.g. `_delta_log/_commits/`. Such
+// files are never commits or checkpoints of the table, even if their name starts with a version.
+fn is_in_subdirectory(log_root: &Url, location: &Url) -> bool {
+    name_in_log_root(log_root, location).is_some_and(|name| name.contains('/'))
+}
+
 /// List all commit and checkpoint files with versions above the provided `start_version` (inclusive).
 /// If successful, this returns a tuple `(ascending_commit_files, checkpoint_parts)` of type
 /// `(Vec<ParsedLogPath>, Vec<ParsedLogPath>)`. The commit files are guaranteed to be sorted in
-/// ascending order by version. The elements of `checkpoint_parts` are all the parts of the same
-/// checkpoint. Checkpoint parts share the same version.
+/// ascending order by version. The elements of `checkpoint_parts` are all the parts of the most
+/// recent _complete_ checkpoint: incomplete multi-part checkpoints (e.g. ones that are still being
+/// written, or that lost a part) are skipped in favor of an older complete checkpoint.
 fn list_log_files_with_version(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
@@ -312,44 +588,89 @@ fn list_log_files_with_version(
     // on config at some point
     let mut commit_files = Vec::with_capacity(10);
     let mut checkpoint_parts = vec![];
-    let mut max_checkpoint_version = start_version;
+    // all checkpoint files seen so far for the most recent checkpoint version
+    let mut pending_checkpoint_files: Vec<ParsedLogPath> = vec![];
 
     for parsed_path in list_log_files(fs_client, log_root, start_version, end_version)? {
         let parsed_path = parsed_path?;
         if parsed_path.is_commit() {
             commit_files.push(parsed_path);
         } else if parsed_path.is_checkpoint() {
-            let path_version = parsed_path.version;
-            match max_checkpoint_version {
-                None => {
-                    checkpoint_parts.push(parsed_path);
-                    max_checkpoint_version = Some(path_version);
+            let new_version = pending_checkpoint_files
+                .first()
+                .is_some_and(|pending| pending.version != parsed_path.version);
+            if new_version {
+                let pending = std::mem::take(&mut pending_checkpoint_files);
+                if let Some(complete) = complete_checkpoint_parts(pending) {
+                    checkpoint_parts = complete;
                 }
-                Some(checkpoint_version) => match path_version.cmp(&checkpoint_version) {
-                    Ordering::Greater => {
-                        max_checkpoint_version = Some(path_version);
-                        checkpoint_parts.clear();
-                        checkpoint_parts.push(parsed_path);
-                    }
-                    Ordering::Equal => checkpoint_parts.push(parsed_path),
-                    Ordering::Less => {}
-                },
             }
+            pending_checkpoint_files.push(parsed_path);
         }
     }
+    if let Some(complete) = complete_checkpoint_parts(pending_checkpoint_files) {
+        checkpoint_parts = complete;
+    }
 
     Ok((commit_files, checkpoint_parts))
 }
 
+/// Given all the checkpoint files of a single version, return the files that make up one complete
+/// checkpoint, or `None` if there is no complete checkpoint. A single-part checkpoint is always
+/// complete, and is preferred if present. Otherwise, a multi-part checkpoint is complete if all of
+/// its `num_parts` parts are present.
+pub(crate) fn complete_checkpoint_parts(
+    checkpoint_files: Vec<ParsedLogPath>,
+) -> Option<Vec<ParsedLogPath>> {
+    let mut multi_part_checkpoints: HashMap<u32, Vec<ParsedLogPath>> = HashMap::new();
+    for file in checkpoint_files {
+        match file.file_type {
+            LogPathFileType::SinglePartCheckpoint => return Some(vec![file]),
+            LogPathFileType::MultiPartCheckpoint { num_parts, .. } => multi_part_checkpoints
+                .entry(num_parts)
+                .or_default()
+                .push(file),
+            _ => {}
+        }
+    }
+    if multi_part_checkpoints.is_empty() {
+        return None;
+    }
+    let complete = multi_part_checkpoints
+        .into_iter()
+        .filter_map(|(num_parts, parts)| {
+            let part_nums: HashSet<u32> = parts
+                .iter()
+                .filter_map(|part| match part.file_type {
+                    LogPathFileType::MultiPartCheckpoint { part_num, .. } => Some(part_num),
+                    _ => None,
+                })
+                .collect();
+            let complete = (1..=num_parts).all(|part_num| part_nums.contains(&part_num));
+            complete.then_some((num_parts, parts))
+        })
+        // deterministically pick the checkpoint with the fewest parts if there are several
+        .min_by_key(|(num_parts, _)| *num_parts);
+    if complete.is_none() {
+        warn!("Found an incomplete multi-part checkpoint, ignoring it");
+    }
+    complete.map(|(_, parts)| parts)
+}
+
 /// List all commit and checkpoint files after the provided checkpoint. It is guaranteed that all
 /// the returned [`ParsedLogPath`]s will have a version less than or equal to the `end_version`.
 /// See [`list_log_files_with_version`] for details on the return type.
+///
+/// Returns `Ok(None)` if the checkpoint hint turns out to be unusable, i.e. no complete checkpoint
+/// exists at or after the hinted version, or the hinted checkpoint doesn't match the checkpoint
+/// found in the log (number of parts or total size in bytes). Callers should then fall back to
+/// listing the log without a hint.
 fn list_log_files_with_checkpoint(
     checkpoint_metadata: &CheckpointMetadata,
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
     end_version: Option<Version>,
-) -> DeltaResult<(Vec<ParsedLogPath>, Vec<ParsedLogPath>)> {
+) -> DeltaResult<Option<(Vec<ParsedLogPath>, Vec<ParsedLogPath>)>> {
     let (commit_files, checkpoint_parts) = list_log_files_with_version(
         fs_client,
         log_root,
@@ -358,10 +679,11 @@ fn list_log_files_with_checkpoint(
     )?;
 
     let Some(latest_checkpoint) = checkpoint_parts.last() else {
-        // TODO: We could potentially recover here
-        return Err(Error::generic(
-            "Had a _last_checkpoint hint but didn't find any checkpoints",
-        ));
+        warn!(
+            "_last_checkpoint hint points at version {}, but no complete checkpoint was found at or after it. Ignoring the hint.",
+            checkpoint_metadata.version
+        );
+        return Ok(None);
     };
     if latest_checkpoint.version != checkpoint_metadata.version {
         warn!(
@@ -369,12 +691,28 @@ fn list_log_files_with_checkpoint(
             checkpoint_metadata.version,
             latest_checkpoint.version
         );
-    } else if checkpoint_parts.len() != checkpoint_metadata.parts.unwrap_or(1) {
-        return Err(Error::Generic(format!(
-            "_last_checkpoint indicated that checkpoint should have {} parts, but it has {}",
-            checkpoint_metadata.parts.unwrap_or(1),
+        return Ok(Some((commit_files, checkpoint_parts)));
+    }
+
+    let expected_parts = checkpoint_metadata.parts.unwrap_or(1);
+    if checkpoint_parts.len() != expected_parts {
+        warn!(
+            "_last_checkpoint indicated that checkpoint should have {} parts, but it has {}. Ignoring the hint.",
+            expected_parts,
             checkpoint_parts.len()
-        )));
+        );
+        return Ok(None);
     }
-    Ok((commit_files, checkpoint_parts))
+    if let Some(expected_size) = checkpoint_metadata.size_in_bytes {
+        let actual_size: usize = checkpoint_parts.iter().map(|part| part.location.size).sum();
+        if usize::try_from(expected_size).ok() != Some(actual_size) {
+            warn!(
+                "_last_checkpoint indicated that checkpoint should have {} bytes, but it has {}. Ignoring the hint.",
+                expected_size,
+                actual_size
+            );
+            return Ok(None);
+        }
+    }
+    Ok(Some((commit_files, checkpoint_parts)))
 }
@@ -1,5 +1,8 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{path::PathBuf, sync::Arc};
 
+use bytes::Bytes;
+
 use itertools::Itertools;
 use object_store::{memory::InMemory, path::Path, ObjectStore};
 use url::Url;
@@ -7,9 +10,9 @@ use url::Url;
 use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
 use crate::engine::default::filesystem::ObjectStoreFileSystemClient;
 use crate::engine::sync::SyncEngine;
-use crate::log_segment::LogSegment;
+use crate::log_segment::{earliest_version, is_checkpoint_error, LogSegment};
 use crate::snapshot::CheckpointMetadata;
-use crate::{FileSystemClient, Table};
+use crate::{DeltaResult, Error, ErrorKind, FileMeta, FileSlice, FileSystemClient, Table};
 use test_utils::delta_path_for_version;
 
 // NOTE: In addition to testing the meta-predicate for metadata replay, this test also verifies
@@ -188,7 +191,7 @@ fn build_snapshot_with_correct_last_multipart_checkpoint() {
 }
 
 #[test]
-fn build_snapshot_with_missing_checkpoint_part_from_hint_fails() {
+fn build_snapshot_with_missing_checkpoint_part_from_hint_falls_back_to_listing() {
     let checkpoint_metadata = CheckpointMetadata {
         version: 5,
         size: 10,
@@ -218,12 +221,20 @@ fn build_snapshot_with_missing_checkpoint_part_from_hint_fails() {
         Some(&checkpoint_metadata),
     );
 
+    // the hint is ignored and the Snapshot falls back to the most recent complete checkpoint
     let log_segment =
-        LogSegment::for_snapshot(client.as_ref(), log_root, checkpoint_metadata, None);
-    assert!(log_segment.is_err())
+        LogSegment::for_snapshot(client.as_ref(), log_root, checkpoint_metadata, None).unwrap();
+    let commit_files = log_segment.ascending_commit_files;
+    let checkpoint_parts = log_segment.checkpoint_parts;
+
+    assert_eq!(checkpoint_parts.len(), 1);
+    assert_eq!(checkpoint_parts[0].version, 3);
+
+    let versions = commit_files.into_iter().map(|x| x.version).collect_vec();
+    assert_eq!(versions, vec![4, 5, 6, 7]);
 }
 #[test]
-fn build_snapshot_with_bad_checkpoint_hint_fails() {
+fn build_snapshot_with_bad_checkpoint_hint_falls_back_to_listing() {
     let checkpoint_metadata = CheckpointMetadata {
         version: 5,
         size: 10,
@@ -253,15 +264,83 @@ fn build_snapshot_with_bad_checkpoint_hint_fails() {
     );
 
     let log_segment =
-        LogSegment::for_snapshot(client.as_ref(), log_root, checkpoint_metadata, None);
-    assert!(log_segment.is_err())
+        LogSegment::for_snapshot(client.as_ref(), log_root, checkpoint_metadata, None).unwrap();
+    let commit_files = log_segment.ascending_commit_files;
+    let checkpoint_parts = log_segment.checkpoint_parts;
+
+    assert_eq!(checkpoint_parts.len(), 2);
+    assert_eq!(checkpoint_parts[0].version, 5);
+    let versions = commit_files.into_iter().map(|x| x.version).collect_vec();
+    assert_eq!(versions, vec![6, 7]);
 }
 
-#[ignore]
 #[test]
-fn build_snapshot_with_missing_checkpoint_part_no_hint() {
-    // TODO: Handle checkpoints correctly so that this test passes: https://github.com/delta-io/delta-kernel-rs/issues/497
+fn build_snapshot_with_last_checkpoint_size_in_bytes() {
+    let paths = [
+        delta_path_for_version(0, "json"),
+        delta_path_for_version(1, "json"),
+        delta_path_for_multipart_checkpoint(2, 1, 2),
+        delta_path_for_multipart_checkpoint(2, 2, 2),
+        delta_path_for_version(2, "json"),
+        delta_path_for_version(3, "json"),
+    ];
+    // each log file contains "kernel-data", which is 11 bytes
+    for size_in_bytes in [22, 1000] {
+        let checkpoint_metadata = CheckpointMetadata {
+            version: 2,
+            size: 10,
+            parts: Some(2),
+            size_in_bytes: Some(size_in_bytes),
+            num_of_add_files: None,
+            checkpoint_schema: None,
+            checksum: None,
+        };
+        let (client, log_root) =
+            build_log_with_paths_and_checkpoint(&paths, Some(&checkpoint_metadata));
+
+        // A mismatched size means the hint is ignored, but listing still finds the checkpoint
+        let log_segment =
+            LogSegment::for_snapshot(client.as_ref(), log_root, checkpoint_metadata, None).unwrap();
+        assert_eq!(log_segment.checkpoint_parts.len(), 2);
+        assert_eq!(log_segment.checkpoint_parts[0].version, 2);
+        assert_eq!(log_segment.ascending_commit_files.len(), 1);
+        assert_eq!(log_segment.ascending_commit_files[0].version, 3);
+    }
+}
+
+#[test]
+fn build_snapshot_with_last_checkpoint_past_end_of_log() {
+    // the hint points at a checkpoint that doesn't exist (e.g. the log was recreated)
+    let checkpoint_metadata = CheckpointMetadata {
+        version: 10,
+        size: 10,
+        parts: None,
+        size_in_bytes: None,
+        num_of_add_files: None,
+        checkpoint_schema: None,
+        checksum: None,
+    };
+
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_version(1, "checkpoint.parquet"),
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(2, "json"),
+        ],
+        Some(&checkpoint_metadata),
+    );
+
+    let log_segment =
+        LogSegment::for_snapshot(client.as_ref(), log_root, checkpoint_metadata, None).unwrap();
+    assert_eq!(log_segment.checkpoint_parts.len(), 1);
+    assert_eq!(log_segment.checkpoint_parts[0].version, 1);
+    assert_eq!(log_segment.ascending_commit_files.len(), 1);
+    assert_eq!(log_segment.end_version, 2);
+}
 
+#[test]
+fn build_snapshot_with_missing_checkpoint_part_no_hint() {
     // Part 2 of 3 is missing from checkpoint 5. The Snapshot should be made of checkpoint
     // number 3 and commit files 4 to 7.
     let (client, log_root) = build_log_with_paths_and_checkpoint(
@@ -416,6 +495,96 @@ fn build_snapshot_with_start_checkpoint_and_time_travel_version() {
     assert_eq!(log_segment.ascending_commit_files.len(), 1);
     assert_eq!(log_segment.ascending_commit_files[0].version, 4);
 }
+// A FileSystemClient which counts how many listed files the kernel actually consumes
+struct CountingFileSystemClient {
+    inner: Box<dyn FileSystemClient>,
+    listed: Arc<AtomicUsize>,
+}
+
+impl FileSystemClient for CountingFileSystemClient {
+    fn list_from(
+        &self,
+        path: &Url,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>> {
+        let listed = self.listed.clone();
+        let files = self.inner.list_from(path)?.inspect(move |_| {
+            listed.fetch_add(1, Ordering::Relaxed);
+        });
+        Ok(Box::new(files))
+    }
+
+    fn read_files(
+        &self,
+        files: Vec<FileSlice>,
+    ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>> {
+        self.inner.read_files(files)
+    }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        self.inner.write_file(path, data, overwrite)
+    }
+}
+
+#[test]
+fn build_snapshot_stops_listing_early() {
+    let checkpoint_metadata = CheckpointMetadata {
+        version: 5,
+        size: 10,
+        parts: None,
+        size_in_bytes: None,
+        num_of_add_files: None,
+        checkpoint_schema: None,
+        checksum: None,
+    };
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(2, "json"),
+            delta_path_for_version(3, "json"),
+            delta_path_for_version(4, "json"),
+            delta_path_for_version(5, "checkpoint.parquet"),
+            delta_path_for_version(5, "json"),
+            delta_path_for_version(6, "json"),
+            delta_path_for_version(7, "json"),
+            // unrelated files in a subdirectory of the log, which must not be listed
+            Path::from(
+                "_delta_log/_commits/00000000000000000008.3a0d65cd-4056-49b8-937b-95f9e3ee90e5.json",
+            ),
+            Path::from("_delta_log/_commits/00000000000000000009.json"),
+        ],
+        Some(&checkpoint_metadata),
+    );
+    let listed = Arc::new(AtomicUsize::new(0));
+    let client = CountingFileSystemClient {
+        inner: client,
+        listed: listed.clone(),
+    };
+
+    // listing starts at the checkpoint and stops at the `_commits` directory
+    let log_segment =
+        LogSegment::for_snapshot(&client, log_root.clone(), checkpoint_metadata, None).unwrap();
+    assert_eq!(log_segment.checkpoint_parts[0].version, 5);
+    let versions = log_segment
+        .ascending_commit_files
+        .iter()
+        .map(|x| x.version)
+        .collect_vec();
+    assert_eq!(versions, vec![6, 7]);
+    // 5.checkpoint.parquet, 5.json, 6.json, 7.json and the first file in `_commits`
+    assert_eq!(listed.swap(0, Ordering::Relaxed), 5);
+
+    // time travel stops at the first file past the requested version
+    let log_segment = LogSegment::for_snapshot(&client, log_root, None, Some(2)).unwrap();
+    let versions = log_segment
+        .ascending_commit_files
+        .iter()
+        .map(|x| x.version)
+        .collect_vec();
+    assert_eq!(versions, vec![0, 1, 2]);
+    assert_eq!(listed.load(Ordering::Relaxed), 4);
+}
+
 #[test]
 fn build_table_changes_with_commit_versions() {
     let (client, log_root) = build_log_with_paths_and_checkpoint(
@@ -511,3 +680,161 @@ fn table_changes_fails_with_larger_start_version_than_end() {
     let log_segment_res = LogSegment::for_table_changes(client.as_ref(), log_root, 1, Some(0));
     assert!(log_segment_res.is_err());
 }
+
+#[test]
+fn build_snapshot_with_time_travel_version_beyond_latest() {
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(1, "checkpoint.parquet"),
+            delta_path_for_version(2, "json"),
+        ],
+        None,
+    );
+
+    let err = LogSegment::for_snapshot(client.as_ref(), log_root, None, Some(5)).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::VersionBeyondLatest {
+            version: 5,
+            latest: 2
+        }
+    ));
+    assert_eq!(err.kind(), ErrorKind::VersionBeyondLatest);
+}
+
+#[test]
+fn build_snapshot_with_time_travel_version_before_earliest_checkpoint() {
+    // Commits 0 to 2 were cleaned up, and commits 3 and 4 remain from before the checkpoint
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(3, "json"),
+            delta_path_for_version(4, "json"),
+            delta_path_for_version(5, "json"),
+            delta_path_for_version(5, "checkpoint.parquet"),
+            delta_path_for_version(6, "json"),
+        ],
+        None,
+    );
+    assert_eq!(earliest_version(client.as_ref(), &log_root).unwrap(), 5);
+
+    for version in [1, 4] {
+        let err = LogSegment::for_snapshot(client.as_ref(), log_root.clone(), None, Some(version))
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::VersionTruncated { version: v, earliest: 5 } if v == version),
+            "{err}"
+        );
+        assert_eq!(err.kind(), ErrorKind::VersionTruncated);
+    }
+
+    let log_segment = LogSegment::for_snapshot(client.as_ref(), log_root, None, Some(5)).unwrap();
+    assert_eq!(log_segment.end_version, 5);
+}
+
+#[test]
+fn earliest_version_of_log() {
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(1, "checkpoint.parquet"),
+        ],
+        None,
+    );
+    assert_eq!(earliest_version(client.as_ref(), &log_root).unwrap(), 0);
+
+    // An incomplete multi-part checkpoint can't be read from
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_multipart_checkpoint(2, 1, 2),
+            delta_path_for_version(3, "json"),
+            delta_path_for_multipart_checkpoint(4, 1, 2),
+            delta_path_for_multipart_checkpoint(4, 2, 2),
+            delta_path_for_version(4, "json"),
+        ],
+        None,
+    );
+    assert_eq!(earliest_version(client.as_ref(), &log_root).unwrap(), 4);
+
+    let (client, log_root) =
+        build_log_with_paths_and_checkpoint(&[delta_path_for_version(3, "json")], None);
+    assert!(earliest_version(client.as_ref(), &log_root).is_err());
+}
+
+#[test]
+fn log_segment_without_checkpoint() {
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_version(1, "checkpoint.parquet"),
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(2, "json"),
+            delta_path_for_version(3, "checkpoint.parquet"),
+            delta_path_for_version(3, "json"),
+            delta_path_for_version(4, "json"),
+        ],
+        None,
+    );
+    let log_segment = LogSegment::for_snapshot(client.as_ref(), log_root, None, None).unwrap();
+    assert_eq!(log_segment.checkpoint_parts[0].version, 3);
+
+    // falls back to the previous checkpoint, and then to all commits
+    let log_segment = log_segment.without_checkpoint(client.as_ref()).unwrap();
+    assert_eq!(log_segment.end_version, 4);
+    assert_eq!(log_segment.checkpoint_parts[0].version, 1);
+    let versions: Vec<_> = log_segment
+        .ascending_commit_files
+        .iter()
+        .map(|commit| commit.version)
+        .collect();
+    assert_eq!(versions, [2, 3, 4]);
+
+    let log_segment = log_segment.without_checkpoint(client.as_ref()).unwrap();
+    assert!(log_segment.checkpoint_parts.is_empty());
+    assert_eq!(log_segment.ascending_commit_files.len(), 5);
+    assert!(log_segment.without_checkpoint(client.as_ref()).is_err());
+
+    // the commits before the checkpoint are gone
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(2, "checkpoint.parquet"),
+            delta_path_for_version(2, "json"),
+            delta_path_for_version(3, "json"),
+        ],
+        None,
+    );
+    let log_segment = LogSegment::for_snapshot(client.as_ref(), log_root, None, None).unwrap();
+    assert!(log_segment.without_checkpoint(client.as_ref()).is_err());
+}
+
+#[test]
+fn checkpoint_errors() {
+    let log_root = Url::parse("memory:///_delta_log/").unwrap();
+    let in_file = |err: Error, name: &str| {
+        let file = log_root.join(name).unwrap();
+        err.with_context(crate::ErrorContext::new().with_file(file))
+    };
+    let checkpoint = "00000000000000000002.checkpoint.parquet";
+    assert!(is_checkpoint_error(&in_file(
+        Error::file_not_found(checkpoint),
+        checkpoint
+    )));
+    assert!(is_checkpoint_error(&Error::invalid_checkpoint(
+        "no protocol"
+    )));
+
+    // an error reading a commit would happen again without the checkpoint, and so would one which
+    // isn't caused by the checkpoint
+    let commit = "00000000000000000003.json";
+    assert!(!is_checkpoint_error(&in_file(
+        Error::file_not_found(commit),
+        commit
+    )));
+    assert!(!is_checkpoint_error(&in_file(
+        Error::generic("connection reset"),
+        checkpoint
+    )));
+}
@@ -25,7 +25,6 @@ enum LogPathFileType {
     // NOTE: Delta spec doesn't actually say, but checkpoint part numbers are effectively 31-bit
     // unsigned integers: Negative values are never allowed, but Java integer types are always
     // signed. Approximate that as u32 here.
-    #[allow(unused)]
     MultiPartCheckpoint {
         part_num: u32,
         num_parts: u32,
@@ -50,6 +49,39 @@ struct ParsedLogPath<Location: AsUrl = FileMeta> {
     pub file_type: LogPathFileType,
 }
 
+/// Resolve the `path` of a file of the table at `table_root`, as recorded in the log (e.g. by an
+/// `add` or `remove` action), to its URL. The path is a URI, which is either relative to the table
+/// root, or absolute (e.g. when a shallow clone references the files of its source table, which
+/// may be on another file system). Relative paths are percent-encoded, but any `?` or `#` in them
+/// is part of a file name rather than the start of a query or fragment.
+pub(crate) fn resolve_file_path(table_root: &Url, path: &str) -> DeltaResult<Url> {
+    match Url::parse(path) {
+        // A path like `a:b/c` parses as a URL with an opaque path, but is a relative path whose
+        // first segment has a `:` in it
+        Ok(url) if !url.cannot_be_a_base() => Ok(url),
+        _ => {
+            let path = path.replace('?', "%3F").replace('#', "%23");
+            let path = match path.starts_with('/') {
+                true => path,
+                // keep a `:` in the first segment from being taken as the end of a scheme
+                false => format!("./{path}"),
+            };
+            Ok(table_root.join(&path)?)
+        }
+    }
+}
+
+/// The root of the storage `url` is in: the URL with just its scheme and authority (e.g.
+/// `s3://bucket/`). Files of a table whose storage root differs from the table's are external,
+/// e.g. the files of the source table that a shallow clone references.
+pub(crate) fn storage_root(url: &Url) -> Url {
+    let mut root = url.clone();
+    root.set_path("/");
+    root.set_query(None);
+    root.set_fragment(None);
+    root
+}
+
 // Internal helper used by TryFrom<FileMeta> below. It parses a fixed-length string into the numeric
 // type expected by the caller. A wrong length produces an error, even if the parse succeeded.
 fn parse_path_part<T: FromStr>(value: &str, expect_len: usize, location: &Url) -> DeltaResult<T> {
@@ -199,6 +231,24 @@ impl ParsedLogPath<Url> {
         }
         Ok(path)
     }
+
+    /// Create a new ParsedLogPath<Url> for a new single-part parquet checkpoint file at the
+    /// specified version
+    pub(crate) fn new_single_part_checkpoint(
+        table_root: &Url,
+        version: Version,
+    ) -> DeltaResult<ParsedLogPath<Url>> {
+        let filename = format!("{:020}.checkpoint.parquet", version);
+        let location = table_root.join("_delta_log/")?.join(&filename)?;
+        let path = Self::try_from(location)?
+            .ok_or_else(|| Error::internal_error("attempted to create invalid checkpoint path"))?;
+        if !matches!(path.file_type, LogPathFileType::SinglePartCheckpoint) {
+            return Err(Error::internal_error(
+                "ParsedLogPath::new_single_part_checkpoint created a non-checkpoint path",
+            ));
+        }
+        Ok(path)
+    }
 }
 
 #[cfg(test)]
@@ -216,6 +266,59 @@ mod tests {
         url
     }
 
+    #[test]
+    fn test_resolve_file_path() {
+        let table_root = Url::parse("s3://bucket/table/").unwrap();
+        let resolve = |path| resolve_file_path(&table_root, path).unwrap().to_string();
+        assert_eq!(
+            resolve("part-0.parquet"),
+            "s3://bucket/table/part-0.parquet"
+        );
+        assert_eq!(
+            resolve("a=1/part-0.parquet"),
+            "s3://bucket/table/a=1/part-0.parquet"
+        );
+        // percent-encoded paths stay encoded, raw spaces and non-ASCII characters are encoded
+        assert_eq!(
+            resolve("a=x%20y/p.parquet"),
+            "s3://bucket/table/a=x%20y/p.parquet"
+        );
+        assert_eq!(
+            resolve("a=x%253Ay/p.parquet"),
+            "s3://bucket/table/a=x%253Ay/p.parquet"
+        );
+        assert_eq!(
+            resolve("a=x y/p.parquet"),
+            "s3://bucket/table/a=x%20y/p.parquet"
+        );
+        assert_eq!(
+            resolve("a=ü/p.parquet"),
+            "s3://bucket/table/a=%C3%BC/p.parquet"
+        );
+        // `?`, `#` and `:` are part of the file name
+        assert_eq!(
+            resolve("p#1?.parquet"),
+            "s3://bucket/table/p%231%3F.parquet"
+        );
+        assert_eq!(resolve("a:b/p.parquet"), "s3://bucket/table/a:b/p.parquet");
+        // absolute paths
+        assert_eq!(resolve("/other/p.parquet"), "s3://bucket/other/p.parquet");
+        assert_eq!(resolve("s3://other/t/p.parquet"), "s3://other/t/p.parquet");
+        assert_eq!(resolve("file:/t/p.parquet"), "file:///t/p.parquet");
+    }
+
+    #[test]
+    fn test_storage_root() {
+        let root = |url| storage_root(&Url::parse(url).unwrap()).to_string();
+        assert_eq!(root("s3://bucket/table/p.parquet"), "s3://bucket/");
+        assert_eq!(root("s3://bucket"), "s3://bucket/");
+        assert_eq!(
+            root("abfss://container@account.dfs.core.windows.net/t/?x#y"),
+            "abfss://container@account.dfs.core.windows.net/"
+        );
+        assert_eq!(root("file:///tmp/table/"), "file:///");
+    }
+
     #[test]
     fn test_unknown_invalid_patterns() {
         let table_log_dir = table_log_dir_url();
@@ -575,4 +678,18 @@ mod tests {
         assert!(matches!(log_path.file_type, LogPathFileType::Commit));
         assert_eq!(log_path.filename, "00000000000000000010.json");
     }
+
+    #[test]
+    fn test_new_single_part_checkpoint() {
+        let table_log_dir = table_log_dir_url();
+        let log_path = ParsedLogPath::new_single_part_checkpoint(&table_log_dir, 10).unwrap();
+        assert_eq!(log_path.version, 10);
+        assert!(log_path.is_checkpoint());
+        assert_eq!(log_path.extension, "parquet");
+        assert!(matches!(
+            log_path.file_type,
+            LogPathFileType::SinglePartCheckpoint
+        ));
+        assert_eq!(log_path.filename, "00000000000000000010.checkpoint.parquet");
+    }
 }
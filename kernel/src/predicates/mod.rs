@@ -1,6 +1,6 @@
 use crate::expressions::{
-    BinaryExpression, BinaryOperator, ColumnName, Expression as Expr, Scalar, UnaryExpression,
-    UnaryOperator, VariadicExpression, VariadicOperator,
+    BinaryExpression, BinaryOperator, ColumnName, Expression as Expr, ExpressionRef, Scalar,
+    UnaryExpression, UnaryOperator, VariadicExpression, VariadicOperator,
 };
 use crate::schema::DataType;
 
@@ -187,7 +187,7 @@ pub(crate) trait PredicateEvaluator {
             }
         };
         match (op, inverted) {
-            (Plus | Minus | Multiply | Divide, _) => None, // Unsupported - not boolean output
+            (Plus | Minus | Multiply | Divide | NullIf, _) => None, // Unsupported - not boolean output
             (LessThan, false) | (GreaterThanOrEqual, true) => self.eval_lt(col, val),
             (LessThanOrEqual, false) | (GreaterThan, true) => self.eval_le(col, val),
             (GreaterThan, false) | (LessThanOrEqual, true) => self.eval_gt(col, val),
@@ -204,9 +204,13 @@ pub(crate) trait PredicateEvaluator {
     fn eval_variadic(
         &self,
         op: VariadicOperator,
-        exprs: &[Expr],
+        exprs: &[ExpressionRef],
         inverted: bool,
     ) -> Option<Self::Output> {
+        if op == VariadicOperator::Coalesce {
+            debug!("Unsupported variadic operator: {op:?}");
+            return None;
+        }
         let exprs = exprs.iter().map(|expr| self.eval_expr(expr, inverted));
         self.finish_eval_variadic(op, exprs, inverted)
     }
@@ -292,6 +296,7 @@ impl PredicateEvaluatorDefaults {
         let dominator = match op {
             VariadicOperator::And => inverted,
             VariadicOperator::Or => !inverted,
+            VariadicOperator::Coalesce => return None, // Unsupported - not a boolean connective
         };
         let result = exprs.into_iter().try_fold(false, |found_null, val| {
             match val {
@@ -324,8 +329,7 @@ impl ResolveColumnAsScalar for UnimplementedColumnResolver {
     }
 }
 
-// In testing, it is convenient to just build a hashmap of scalar values.
-#[cfg(test)]
+// Resolves the columns which are keys of the map, e.g. the (parsed) partition values of a file.
 impl ResolveColumnAsScalar for std::collections::HashMap<ColumnName, Scalar> {
     fn resolve_column(&self, col: &ColumnName) -> Option<Scalar> {
         self.get(col).cloned()
@@ -317,6 +317,7 @@ fn test_eval_variadic() {
                 Some(v) => Expression::literal(v),
                 None => Expression::null_literal(DataType::BOOLEAN),
             })
+            .map(ExpressionRef::new)
             .collect();
         for inverted in [true, false] {
             let invert_if_needed = |v: &Option<_>| v.map(|v| v != inverted);
@@ -1,22 +1,31 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, LazyLock};
 
+use itertools::Itertools;
 use tracing::debug;
 
+use super::parse_partition_value;
 use crate::actions::get_log_add_schema;
 use crate::actions::visitors::SelectionVectorVisitor;
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
 use crate::error::DeltaResult;
 use crate::expressions::{
-    column_expr, joined_column_expr, BinaryOperator, ColumnName, Expression as Expr, ExpressionRef,
-    Scalar, VariadicOperator,
+    column_expr, column_name, joined_column_expr, BinaryOperator, ColumnName, Expression as Expr,
+    ExpressionRef, Scalar, VariadicOperator,
 };
 use crate::predicates::{
-    DataSkippingPredicateEvaluator, PredicateEvaluator, PredicateEvaluatorDefaults,
+    DataSkippingPredicateEvaluator, DefaultPredicateEvaluator, PredicateEvaluator,
+    PredicateEvaluatorDefaults,
 };
-use crate::schema::{DataType, PrimitiveType, SchemaRef, SchemaTransform, StructField, StructType};
-use crate::{Engine, EngineData, ExpressionEvaluator, JsonHandler, RowVisitor as _};
+use crate::schema::{
+    ColumnNamesAndTypes, DataType, MapType, PrimitiveType, SchemaRef, SchemaTransform, StructField,
+    StructType,
+};
+use crate::table_features::non_binary_collated_columns;
+use crate::utils::require;
+use crate::{Engine, EngineData, Error, ExpressionEvaluator, JsonHandler};
 
 #[cfg(test)]
 mod tests;
@@ -37,28 +46,196 @@ mod tests;
 ///         are not eligible for data skipping.
 /// - `OR` is rewritten only if all operands are eligible for data skipping. Otherwise, the whole OR
 ///        expression is dropped.
-fn as_data_skipping_predicate(expr: &Expr, inverted: bool) -> Option<Expr> {
-    DataSkippingPredicateCreator.eval_expr(expr, inverted)
+///
+/// Stats of string columns are ordered by UTF-8 bytes, so comparisons on the given
+/// `collated_columns` (which have a non-binary collation) are not eligible for data skipping.
+/// Files have no stats for the given `partition_columns`, so they are not eligible either.
+fn as_data_skipping_predicate(
+    expr: &Expr,
+    inverted: bool,
+    collated_columns: &HashSet<ColumnName>,
+    partition_columns: &HashSet<ColumnName>,
+) -> Option<Expr> {
+    DataSkippingPredicateCreator {
+        collated_columns,
+        partition_columns,
+    }
+    .eval_expr(expr, inverted)
 }
 
+/// Skips the files of a scan whose partition values or stats prove that none of their rows can
+/// satisfy the predicate of the scan.
 pub(crate) struct DataSkippingFilter {
-    stats_schema: SchemaRef,
-    select_stats_evaluator: Arc<dyn ExpressionEvaluator>,
-    skipping_evaluator: Arc<dyn ExpressionEvaluator>,
-    filter_evaluator: Arc<dyn ExpressionEvaluator>,
-    json_handler: Arc<dyn JsonHandler>,
+    partition_filter: Option<PartitionFilter>,
+    stats_filter: Option<StatsFilter>,
 }
 
 impl DataSkippingFilter {
     /// Creates a new data skipping filter. Returns None if there is no predicate, or the predicate
-    /// is ineligible for data skipping.
+    /// is ineligible for both partition pruning and stats-based data skipping.
+    ///
+    /// The fields of `partition_schema` are the partition columns of the table, named like the
+    /// keys of the `partitionValues` of its add actions. Partition columns have no stats, so they
+    /// are ignored when building the stats schema from `table_schema`.
     ///
     /// NOTE: None is equivalent to a trivial filter that always returns TRUE (= keeps all files),
     /// but using an Option lets the engine easily avoid the overhead of applying trivial filters.
     pub(crate) fn new(
         engine: &dyn Engine,
         table_schema: &SchemaRef,
+        partition_schema: &StructType,
         predicate: Option<ExpressionRef>,
+    ) -> Option<Self> {
+        let predicate = predicate?;
+        debug!("Creating a data skipping filter for {}", &predicate);
+        let partition_filter = PartitionFilter::new(partition_schema, &predicate);
+        let stats_filter = StatsFilter::new(engine, table_schema, partition_schema, &predicate);
+        if partition_filter.is_none() && stats_filter.is_none() {
+            return None;
+        }
+        Some(Self {
+            partition_filter,
+            stats_filter,
+        })
+    }
+
+    /// Apply the DataSkippingFilter to an EngineData batch of actions. Returns a selection vector
+    /// which can be applied to the actions to find those that passed data skipping.
+    ///
+    /// Partition pruning is much cheaper than parsing the JSON stats of every file, so it runs
+    /// first, and only the stats of the files which survive it are parsed.
+    pub(crate) fn apply(&self, actions: &dyn EngineData) -> DeltaResult<Vec<bool>> {
+        let mut selection_vector = match &self.partition_filter {
+            Some(filter) => filter.apply(actions)?,
+            None => vec![true; actions.len()],
+        };
+        assert_eq!(selection_vector.len(), actions.len());
+        if let Some(filter) = &self.stats_filter {
+            if selection_vector.contains(&true) {
+                filter.apply(actions, &mut selection_vector)?;
+            }
+        }
+        Ok(selection_vector)
+
+        // TODO(zach): add some debug info about data skipping that occurred
+        // let before_count = actions.length();
+        // debug!(
+        //     "number of actions before/after data skipping: {before_count} / {}",
+        //     filtered_actions.num_rows()
+        // );
+    }
+}
+
+/// Skips files by evaluating the predicate over their partition values. Columns which aren't
+/// partition columns resolve to unknown (null) values, so only a predicate which is provably false
+/// whatever their values skips a file.
+struct PartitionFilter {
+    partition_schema: StructType,
+    predicate: ExpressionRef,
+}
+
+impl PartitionFilter {
+    /// Returns None if the predicate doesn't reference any partition column.
+    fn new(partition_schema: &StructType, predicate: &ExpressionRef) -> Option<Self> {
+        let field_names = predicate.references();
+        let partition_fields: Vec<_> = partition_schema
+            .fields()
+            .filter(|field| field_names.contains([field.name.clone()].as_slice()))
+            .cloned()
+            .collect();
+        if partition_fields.is_empty() {
+            return None;
+        }
+        Some(Self {
+            partition_schema: StructType::new(partition_fields),
+            predicate: predicate.clone(),
+        })
+    }
+
+    fn apply(&self, actions: &dyn EngineData) -> DeltaResult<Vec<bool>> {
+        let mut visitor = PartitionVisitor {
+            filter: self,
+            selection_vector: Vec::with_capacity(actions.len()),
+        };
+        visitor.visit_rows_of(actions)?;
+        Ok(visitor.selection_vector)
+    }
+
+    /// Whether the file with the given partition values may contain rows satisfying the predicate.
+    fn keep(&self, partition_values: &HashMap<String, String>) -> DeltaResult<bool> {
+        let resolver: HashMap<_, _> = self
+            .partition_schema
+            .fields()
+            .map(|field| -> DeltaResult<_> {
+                let value =
+                    parse_partition_value(partition_values.get(field.name()), field.data_type())?;
+                Ok((ColumnName::new([field.name()]), value))
+            })
+            .try_collect()?;
+        let evaluator = DefaultPredicateEvaluator::from(resolver);
+        Ok(evaluator.eval_expr(&self.predicate, false) != Some(false))
+    }
+}
+
+/// Evaluates the [`PartitionFilter`] over the partition values of each add action. Rows which
+/// aren't add actions are kept.
+struct PartitionVisitor<'a> {
+    filter: &'a PartitionFilter,
+    selection_vector: Vec<bool>,
+}
+
+impl RowVisitor for PartitionVisitor<'_> {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            let partition_values = MapType::new(DataType::STRING, DataType::STRING, true);
+            (
+                vec![column_name!("add.partitionValues")],
+                vec![partition_values.into()],
+            )
+                .into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 1,
+            Error::InternalError(format!(
+                "Wrong number of PartitionVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            let partition_values: Option<HashMap<_, _>> =
+                getters[0].get_opt(i, "add.partitionValues")?;
+            let keep = match partition_values {
+                Some(partition_values) => self.filter.keep(&partition_values)?,
+                None => true,
+            };
+            self.selection_vector.push(keep);
+        }
+        Ok(())
+    }
+}
+
+/// Skips files by evaluating a rewrite of the predicate over the min/max/nullcount stats of their
+/// (data) columns.
+struct StatsFilter {
+    stats_schema: SchemaRef,
+    select_stats_evaluator: Arc<dyn ExpressionEvaluator>,
+    skipping_evaluator: Arc<dyn ExpressionEvaluator>,
+    filter_evaluator: Arc<dyn ExpressionEvaluator>,
+    json_handler: Arc<dyn JsonHandler>,
+}
+
+impl StatsFilter {
+    /// Returns None if the predicate doesn't reference any data column, or is ineligible for data
+    /// skipping.
+    fn new(
+        engine: &dyn Engine,
+        table_schema: &SchemaRef,
+        partition_schema: &StructType,
+        predicate: &Expr,
     ) -> Option<Self> {
         static PREDICATE_SCHEMA: LazyLock<DataType> = LazyLock::new(|| {
             DataType::struct_type([StructField::new("predicate", DataType::BOOLEAN, true)])
@@ -67,8 +244,6 @@ impl DataSkippingFilter {
         static FILTER_EXPR: LazyLock<Expr> =
             LazyLock::new(|| column_expr!("predicate").distinct(false));
 
-        let predicate = predicate.as_deref()?;
-        debug!("Creating a data skipping filter for {}", &predicate);
         let field_names: HashSet<_> = predicate.references();
 
         // Build the stats read schema by extracting the column names referenced by the predicate,
@@ -78,12 +253,18 @@ impl DataSkippingFilter {
         let data_fields: Vec<_> = table_schema
             .fields()
             .filter(|field| field_names.contains([field.name.clone()].as_slice()))
+            .filter(|field| partition_schema.field(field.name()).is_none())
             .cloned()
             .collect();
         if data_fields.is_empty() {
             // The predicate didn't reference any eligible stats columns, so skip it.
             return None;
         }
+        let collated_columns = non_binary_collated_columns(table_schema);
+        let partition_columns: HashSet<_> = partition_schema
+            .fields()
+            .map(|field| ColumnName::new([field.name()]))
+            .collect();
         let minmax_schema = StructType::new(data_fields);
 
         // Convert a min/max stats schema into a nullcount schema (all leaf fields are LONG)
@@ -126,7 +307,12 @@ impl DataSkippingFilter {
 
         let skipping_evaluator = engine.get_expression_handler().get_evaluator(
             stats_schema.clone(),
-            Expr::struct_from([as_data_skipping_predicate(predicate, false)?]),
+            Expr::struct_from([as_data_skipping_predicate(
+                predicate,
+                false,
+                &collated_columns,
+                &partition_columns,
+            )?]),
             PREDICATE_SCHEMA.clone(),
         );
 
@@ -145,58 +331,69 @@ impl DataSkippingFilter {
         })
     }
 
-    /// Apply the DataSkippingFilter to an EngineData batch of actions. Returns a selection vector
-    /// which can be applied to the actions to find those that passed data skipping.
-    pub(crate) fn apply(&self, actions: &dyn EngineData) -> DeltaResult<Vec<bool>> {
+    /// Apply the stats filter to the actions selected by `selection_vector`, deselecting those
+    /// whose stats prove they can be skipped. Engines need not parse the stats of the actions
+    /// which are already deselected.
+    fn apply(&self, actions: &dyn EngineData, selection_vector: &mut [bool]) -> DeltaResult<()> {
         // retrieve and parse stats from actions data
         let stats = self.select_stats_evaluator.evaluate(actions)?;
         assert_eq!(stats.len(), actions.len());
-        let parsed_stats = self
-            .json_handler
-            .parse_json(stats, self.stats_schema.clone())?;
+        let parsed_stats = self.json_handler.parse_json_selected(
+            stats,
+            self.stats_schema.clone(),
+            selection_vector,
+        )?;
         assert_eq!(parsed_stats.len(), actions.len());
 
         // evaluate the predicate on the parsed stats, then convert to selection vector
         let skipping_predicate = self.skipping_evaluator.evaluate(&*parsed_stats)?;
         assert_eq!(skipping_predicate.len(), actions.len());
-        let selection_vector = self
+        let stats_selection_vector = self
             .filter_evaluator
             .evaluate(skipping_predicate.as_ref())?;
-        assert_eq!(selection_vector.len(), actions.len());
+        assert_eq!(stats_selection_vector.len(), actions.len());
 
         // visit the engine's selection vector to produce a Vec<bool>
         let mut visitor = SelectionVectorVisitor::default();
-        visitor.visit_rows_of(selection_vector.as_ref())?;
-        Ok(visitor.selection_vector)
-
-        // TODO(zach): add some debug info about data skipping that occurred
-        // let before_count = actions.length();
-        // debug!(
-        //     "number of actions before/after data skipping: {before_count} / {}",
-        //     filtered_actions.num_rows()
-        // );
+        visitor.visit_rows_of(stats_selection_vector.as_ref())?;
+        for (selected, keep) in selection_vector.iter_mut().zip(visitor.selection_vector) {
+            *selected &= keep;
+        }
+        Ok(())
     }
 }
 
-struct DataSkippingPredicateCreator;
+struct DataSkippingPredicateCreator<'a> {
+    collated_columns: &'a HashSet<ColumnName>,
+    partition_columns: &'a HashSet<ColumnName>,
+}
 
-impl DataSkippingPredicateEvaluator for DataSkippingPredicateCreator {
+impl DataSkippingPredicateEvaluator for DataSkippingPredicateCreator<'_> {
     type Output = Expr;
     type TypedStat = Expr;
     type IntStat = Expr;
 
     /// Retrieves the minimum value of a column, if it exists and has the requested type.
     fn get_min_stat(&self, col: &ColumnName, _data_type: &DataType) -> Option<Expr> {
+        if self.collated_columns.contains(col) || self.partition_columns.contains(col) {
+            return None;
+        }
         Some(joined_column_expr!("minValues", col))
     }
 
     /// Retrieves the maximum value of a column, if it exists and has the requested type.
     fn get_max_stat(&self, col: &ColumnName, _data_type: &DataType) -> Option<Expr> {
+        if self.collated_columns.contains(col) || self.partition_columns.contains(col) {
+            return None;
+        }
         Some(joined_column_expr!("maxValues", col))
     }
 
     /// Retrieves the null count of a column, if it exists.
     fn get_nullcount_stat(&self, col: &ColumnName) -> Option<Expr> {
+        if self.partition_columns.contains(col) {
+            return None;
+        }
         Some(joined_column_expr!("nullCount", col))
     }
 
@@ -34,7 +34,8 @@ fn test_eval_is_null() {
         ]);
         let filter = DefaultPredicateEvaluator::from(resolver);
         for (expr, expect) in expressions.iter().zip(expected) {
-            let pred = as_data_skipping_predicate(expr, false).unwrap();
+            let pred =
+                as_data_skipping_predicate(expr, false, &HashSet::new(), &HashSet::new()).unwrap();
             expect_eq!(
                 filter.eval_expr(&pred, false),
                 *expect,
@@ -77,7 +78,8 @@ fn test_eval_binary_comparisons() {
         ]);
         let filter = DefaultPredicateEvaluator::from(resolver);
         for (expr, expect) in expressions.iter().zip(expected.iter()) {
-            let pred = as_data_skipping_predicate(expr, false).unwrap();
+            let pred =
+                as_data_skipping_predicate(expr, false, &HashSet::new(), &HashSet::new()).unwrap();
             expect_eq!(
                 filter.eval_expr(&pred, false),
                 *expect,
@@ -160,7 +162,8 @@ fn test_eval_variadic() {
             .collect();
 
         let expr = Expr::and_from(inputs.clone());
-        let pred = as_data_skipping_predicate(&expr, false).unwrap();
+        let pred =
+            as_data_skipping_predicate(&expr, false, &HashSet::new(), &HashSet::new()).unwrap();
         expect_eq!(
             filter.eval_expr(&pred, false),
             *expect_and,
@@ -168,11 +171,13 @@ fn test_eval_variadic() {
         );
 
         let expr = Expr::or_from(inputs.clone());
-        let pred = as_data_skipping_predicate(&expr, false).unwrap();
+        let pred =
+            as_data_skipping_predicate(&expr, false, &HashSet::new(), &HashSet::new()).unwrap();
         expect_eq!(filter.eval_expr(&pred, false), *expect_or, "OR({inputs:?})");
 
         let expr = Expr::and_from(inputs.clone());
-        let pred = as_data_skipping_predicate(&expr, true).unwrap();
+        let pred =
+            as_data_skipping_predicate(&expr, true, &HashSet::new(), &HashSet::new()).unwrap();
         expect_eq!(
             filter.eval_expr(&pred, false),
             expect_and.map(|val| !val),
@@ -180,7 +185,8 @@ fn test_eval_variadic() {
         );
 
         let expr = Expr::or_from(inputs.clone());
-        let pred = as_data_skipping_predicate(&expr, true).unwrap();
+        let pred =
+            as_data_skipping_predicate(&expr, true, &HashSet::new(), &HashSet::new()).unwrap();
         expect_eq!(
             filter.eval_expr(&pred, false),
             expect_or.map(|val| !val),
@@ -216,7 +222,8 @@ fn test_eval_distinct() {
         ]);
         let filter = DefaultPredicateEvaluator::from(resolver);
         for (expr, expect) in expressions.iter().zip(expected) {
-            let pred = as_data_skipping_predicate(expr, false).unwrap();
+            let pred =
+                as_data_skipping_predicate(expr, false, &HashSet::new(), &HashSet::new()).unwrap();
             expect_eq!(
                 filter.eval_expr(&pred, false),
                 *expect,
@@ -252,3 +259,29 @@ fn test_eval_distinct() {
     // min < value < max, all nulls
     do_test(five, fifteen, 2, &[TRUE, FALSE, FALSE, TRUE]);
 }
+
+// Stats of strings are ordered by UTF-8 bytes, so they cannot skip files for comparisons on a
+// column with a non-binary collation, while null count checks remain eligible.
+#[test]
+fn test_collated_columns() {
+    let collated_columns = HashSet::from([column_name!("name")]);
+    let resolver = HashMap::from_iter([
+        (column_name!("numRecords"), Scalar::from(2i64)),
+        (column_name!("nullCount.name"), Scalar::from(0i64)),
+        (column_name!("minValues.name"), Scalar::from("b")),
+        (column_name!("maxValues.name"), Scalar::from("c")),
+    ]);
+    let filter = DefaultPredicateEvaluator::from(resolver);
+
+    let expr = column_expr!("name").eq(Expr::literal("A"));
+    let pred = as_data_skipping_predicate(&expr, false, &HashSet::new(), &HashSet::new()).unwrap();
+    expect_eq!(filter.eval_expr(&pred, false), FALSE, "{expr} (binary)");
+    let pred =
+        as_data_skipping_predicate(&expr, false, &collated_columns, &HashSet::new()).unwrap();
+    expect_eq!(filter.eval_expr(&pred, false), NULL, "{expr} (collated)");
+
+    let expr = column_expr!("name").is_null();
+    let pred =
+        as_data_skipping_predicate(&expr, false, &collated_columns, &HashSet::new()).unwrap();
+    expect_eq!(filter.eval_expr(&pred, false), FALSE, "{expr} (collated)");
+}
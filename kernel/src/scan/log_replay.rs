@@ -1,12 +1,14 @@
 use std::clone::Clone;
-use std::collections::HashSet;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
 use std::sync::{Arc, LazyLock};
 
 use tracing::debug;
 
 use super::data_skipping::DataSkippingFilter;
 use super::ScanData;
-use crate::actions::get_log_add_schema;
+use crate::actions::{get_log_add_schema, get_log_schema, ADD_NAME, REMOVE_NAME};
 use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
 use crate::expressions::{column_expr, column_name, ColumnName, Expression, ExpressionRef};
 use crate::scan::DeletionVectorDescriptor;
@@ -15,26 +17,168 @@ use crate::utils::require;
 use crate::{DeltaResult, Engine, EngineData, Error, ExpressionEvaluator};
 
 /// The subset of file action fields that uniquely identifies it in the log, used for deduplication
-/// of adds and removes during log replay.
-#[derive(Debug, Hash, Eq, PartialEq)]
-struct FileActionKey {
-    path: String,
-    dv_unique_id: Option<String>,
+/// of adds and removes during log replay: its (path, dv_unique_id) pair, along with a 128-bit hash
+/// of the pair. See [`SeenFileActions::key`].
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) struct FileActionKey<'a> {
+    hash: u128,
+    path: &'a str,
+    dv_unique_id: Option<&'a str>,
 }
-impl FileActionKey {
-    fn new(path: impl Into<String>, dv_unique_id: Option<String>) -> Self {
-        let path = path.into();
-        Self { path, dv_unique_id }
+
+/// Keys are already uniformly distributed (randomly seeded) hashes, so the set of seen keys uses
+/// their low 64 bits as their hash, rather than hashing them again.
+#[derive(Default)]
+struct FileActionKeyHasher(u64);
+
+impl Hasher for FileActionKeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        // Only reached if the seen files stop being keyed by a single u128
+        for byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+
+    fn write_u128(&mut self, key: u128) {
+        self.0 = key as u64;
+    }
+}
+
+/// The size of the chunks of a [`StringArena`]. Strings longer than this get a chunk of their own.
+const ARENA_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Append-only storage for many small strings, which are packed into large chunks rather than
+/// allocated one by one, so that each string only costs its bytes (and an [`ArenaStr`]).
+#[derive(Default)]
+struct StringArena {
+    chunks: Vec<String>,
+}
+
+/// The location of a string in a [`StringArena`].
+#[derive(Clone, Copy)]
+struct ArenaStr {
+    chunk: u32,
+    start: u32,
+    len: u32,
+}
+
+impl StringArena {
+    fn alloc(&mut self, s: &str) -> ArenaStr {
+        let has_room = self
+            .chunks
+            .last()
+            .is_some_and(|chunk| chunk.capacity() - chunk.len() >= s.len());
+        if !has_room {
+            // chunks never grow, so the strings already in them never move
+            let capacity = s.len().max(ARENA_CHUNK_SIZE);
+            self.chunks.push(String::with_capacity(capacity));
+        }
+        let chunk = self.chunks.len() - 1;
+        let start = self.chunks[chunk].len();
+        self.chunks[chunk].push_str(s);
+        // chunks are at most as large as the largest path, far below 4 GiB
+        ArenaStr {
+            chunk: chunk as u32,
+            start: start as u32,
+            len: s.len() as u32,
+        }
+    }
+
+    fn get(&self, s: ArenaStr) -> &str {
+        let start = s.start as usize;
+        &self.chunks[s.chunk as usize][start..start + s.len as usize]
+    }
+}
+
+/// The (path, dv_unique_id) pair of a seen file action, in the [`StringArena`] of the
+/// [`SeenFileActions`].
+struct SeenFile {
+    path: ArenaStr,
+    dv_unique_id: Option<ArenaStr>,
+}
+
+/// The set of file actions seen so far during log replay. Memory use is bounded by the number of
+/// file actions in commit files (checkpoint actions are never recorded). Seen files are looked up
+/// by the hashes of their keys, and their paths and deletion vector ids are kept in an arena, so
+/// that a hash collision between two distinct files never makes one of them look already seen.
+#[derive(Default)]
+pub(crate) struct SeenFileActions {
+    /// Two independently (and randomly) seeded hashers, which together produce a 128-bit hash.
+    hash_states: (RandomState, RandomState),
+    files: HashMap<u128, SeenFile, BuildHasherDefault<FileActionKeyHasher>>,
+    /// The seen files whose hashes collide with those of other seen files, which (with 128-bit
+    /// hashes) practically never happens.
+    collisions: HashSet<(String, Option<String>)>,
+    arena: StringArena,
+}
+
+impl SeenFileActions {
+    pub(crate) fn key<'a>(
+        &self,
+        path: &'a str,
+        dv_unique_id: Option<&'a str>,
+    ) -> FileActionKey<'a> {
+        let hash_with = |state: &RandomState| {
+            let mut hasher = state.build_hasher();
+            path.hash(&mut hasher);
+            dv_unique_id.hash(&mut hasher);
+            hasher.finish()
+        };
+        let hi = hash_with(&self.hash_states.0);
+        let lo = hash_with(&self.hash_states.1);
+        FileActionKey {
+            hash: (u128::from(hi) << 64) | u128::from(lo),
+            path,
+            dv_unique_id,
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &FileActionKey<'_>) -> bool {
+        match self.files.get(&key.hash) {
+            None => false,
+            Some(file) if self.is_seen_file(file, key) => true,
+            Some(_) => self.collisions.contains(&owned_key(key)),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: FileActionKey<'_>) {
+        match self.files.get(&key.hash) {
+            None => {
+                let file = SeenFile {
+                    path: self.arena.alloc(key.path),
+                    dv_unique_id: key.dv_unique_id.map(|id| self.arena.alloc(id)),
+                };
+                self.files.insert(key.hash, file);
+            }
+            Some(file) if self.is_seen_file(file, &key) => {}
+            Some(_) => {
+                debug!("File action key hash collision for {key:?}");
+                self.collisions.insert(owned_key(&key));
+            }
+        }
+    }
+
+    // Whether `file` is the file of `key` (rather than a distinct file with the same hash)
+    fn is_seen_file(&self, file: &SeenFile, key: &FileActionKey<'_>) -> bool {
+        self.arena.get(file.path) == key.path
+            && file.dv_unique_id.map(|id| self.arena.get(id)) == key.dv_unique_id
     }
 }
 
+fn owned_key(key: &FileActionKey<'_>) -> (String, Option<String>) {
+    (key.path.to_string(), key.dv_unique_id.map(str::to_string))
+}
+
 struct LogReplayScanner {
     filter: Option<DataSkippingFilter>,
 
-    /// A set of (data file path, dv_unique_id) pairs that have been seen thus
-    /// far in the log. This is used to filter out files with Remove actions as
-    /// well as duplicate entries in the log.
-    seen: HashSet<FileActionKey>,
+    /// The (data file path, dv_unique_id) pairs that have been seen thus far in the log. This is
+    /// used to filter out files with Remove actions as well as duplicate entries in the log.
+    seen: SeenFileActions,
 }
 
 /// A visitor that deduplicates a stream of add and remove actions into a stream of valid adds. Log
@@ -42,7 +186,7 @@ struct LogReplayScanner {
 /// pair, we should ignore all subsequent (older) actions for that same (path, dvId) pair. If the
 /// first action for a given file is a remove, then that file does not show up in the result at all.
 struct AddRemoveDedupVisitor<'seen> {
-    seen: &'seen mut HashSet<FileActionKey>,
+    seen: &'seen mut SeenFileActions,
     selection_vector: Vec<bool>,
     is_log_batch: bool,
 }
@@ -50,21 +194,22 @@ struct AddRemoveDedupVisitor<'seen> {
 impl AddRemoveDedupVisitor<'_> {
     /// Checks if log replay already processed this logical file (in which case the current action
     /// should be ignored). If not already seen, register it so we can recognize future duplicates.
-    fn check_and_record_seen(&mut self, key: FileActionKey) -> bool {
+    fn check_and_record_seen(&mut self, path: &str, dv_unique_id: Option<String>) -> bool {
         // Note: each (add.path + add.dv_unique_id()) pair has a
         // unique Add + Remove pair in the log. For example:
         // https://github.com/delta-io/delta/blob/master/spark/src/test/resources/delta/table-with-dv-large/_delta_log/00000000000000000001.json
 
+        let key = self.seen.key(path, dv_unique_id.as_deref());
         if self.seen.contains(&key) {
             debug!(
                 "Ignoring duplicate ({}, {:?}) in scan, is log {}",
-                key.path, key.dv_unique_id, self.is_log_batch
+                path, dv_unique_id, self.is_log_batch
             );
             true
         } else {
             debug!(
                 "Including ({}, {:?}) in scan, is log {}",
-                key.path, key.dv_unique_id, self.is_log_batch
+                path, dv_unique_id, self.is_log_batch
             );
             if self.is_log_batch {
                 // Remember file actions from this batch so we can ignore duplicates as we process
@@ -102,8 +247,7 @@ impl AddRemoveDedupVisitor<'_> {
         };
 
         // Process both adds and removes, but only return not already-seen adds
-        let file_key = FileActionKey::new(path, dv_unique_id);
-        Ok(!self.check_and_record_seen(file_key) && is_add)
+        Ok(!self.check_and_record_seen(path, dv_unique_id) && is_add)
     }
 }
 
@@ -180,6 +324,44 @@ pub(crate) static SCAN_ROW_SCHEMA: LazyLock<Arc<StructType>> = LazyLock::new(||
     ]))
 });
 
+/// The fields of add actions that scan log replay reads: to deduplicate files, skip them, and
+/// transform the surviving adds into scan rows (see [`get_add_transform_expr`]).
+const SCAN_ADD_FIELDS: &[&str] = &[
+    "path",
+    "partitionValues",
+    "size",
+    "modificationTime",
+    "stats",
+    "deletionVector",
+];
+
+/// The fields of remove actions that scan log replay reads, to deduplicate files.
+const SCAN_REMOVE_FIELDS: &[&str] = &["path", "deletionVector"];
+
+/// The schemas to read commit and checkpoint files with for scan log replay, as a pair. Only the
+/// action fields replay uses are read, so that reading wide checkpoints (e.g. with large tags)
+/// only fetches the parquet columns it needs. Removes in checkpoints are only tombstones, so
+/// checkpoints are read for their adds only.
+pub(crate) fn scan_read_schemas() -> DeltaResult<(SchemaRef, SchemaRef)> {
+    let project_action = |name: &str, fields: &[&str]| -> DeltaResult<StructField> {
+        let field = get_log_schema()
+            .field(name)
+            .ok_or_else(|| Error::missing_column(name))?;
+        let DataType::Struct(action_type) = field.data_type() else {
+            return Err(Error::internal_error(format!(
+                "Action {name} is not a struct"
+            )));
+        };
+        let action_type = action_type.project_as_struct(fields)?;
+        Ok(StructField::new(name, action_type, field.is_nullable()))
+    };
+    let add = project_action(ADD_NAME, SCAN_ADD_FIELDS)?;
+    let remove = project_action(REMOVE_NAME, SCAN_REMOVE_FIELDS)?;
+    let commit_read_schema = StructType::new([add.clone(), remove]);
+    let checkpoint_read_schema = StructType::new([add]);
+    Ok((commit_read_schema.into(), checkpoint_read_schema.into()))
+}
+
 pub(crate) static SCAN_ROW_DATATYPE: LazyLock<DataType> =
     LazyLock::new(|| SCAN_ROW_SCHEMA.clone().into());
 
@@ -199,20 +381,24 @@ impl LogReplayScanner {
     fn new(
         engine: &dyn Engine,
         table_schema: &SchemaRef,
+        partition_schema: &StructType,
         predicate: Option<ExpressionRef>,
     ) -> Self {
         Self {
-            filter: DataSkippingFilter::new(engine, table_schema, predicate),
+            filter: DataSkippingFilter::new(engine, table_schema, partition_schema, predicate),
             seen: Default::default(),
         }
     }
 
+    /// Process a batch of actions, returning `None` if none of its rows survive log replay. Batches
+    /// are processed (and scan files emitted) one at a time, so that only the deduplication state
+    /// is retained across batches.
     fn process_scan_batch(
         &mut self,
         add_transform: &dyn ExpressionEvaluator,
         actions: &dyn EngineData,
         is_log_batch: bool,
-    ) -> DeltaResult<ScanData> {
+    ) -> DeltaResult<Option<ScanData>> {
         // Apply data skipping to get back a selection vector for actions that passed skipping. We
         // will update the vector below as log replay identifies duplicates that should be ignored.
         let selection_vector = match &self.filter {
@@ -228,10 +414,15 @@ impl LogReplayScanner {
         };
         visitor.visit_rows_of(actions)?;
 
-        // TODO: Teach expression eval to respect the selection vector we just computed so carefully!
+        // Don't bother transforming batches with no surviving adds (e.g. batches of removes)
         let selection_vector = visitor.selection_vector;
+        if !selection_vector.contains(&true) {
+            return Ok(None);
+        }
+
+        // TODO: Teach expression eval to respect the selection vector we just computed so carefully!
         let result = add_transform.evaluate(actions)?;
-        Ok((result, selection_vector))
+        Ok(Some((result, selection_vector)))
     }
 }
 
@@ -239,34 +430,49 @@ impl LogReplayScanner {
 /// `(engine_data, selection_vec)`. Each row that is selected in the returned `engine_data` _must_
 /// be processed to complete the scan. Non-selected rows _must_ be ignored. The boolean flag
 /// indicates whether the record batch is a log or checkpoint batch.
+///
+/// Files are skipped by evaluating the predicate over the stats of the columns of `table_schema`,
+/// and over the partition values of the partition columns in `partition_schema`.
 pub fn scan_action_iter(
     engine: &dyn Engine,
     action_iter: impl Iterator<Item = DeltaResult<(Box<dyn EngineData>, bool)>>,
     table_schema: &SchemaRef,
+    partition_schema: &StructType,
     predicate: Option<ExpressionRef>,
 ) -> impl Iterator<Item = DeltaResult<ScanData>> {
-    let mut log_scanner = LogReplayScanner::new(engine, table_schema, predicate);
+    let mut log_scanner = LogReplayScanner::new(engine, table_schema, partition_schema, predicate);
     let add_transform = engine.get_expression_handler().get_evaluator(
         get_log_add_schema().clone(),
         get_add_transform_expr(),
         SCAN_ROW_DATATYPE.clone(),
     );
-    action_iter
-        .map(move |action_res| {
-            let (batch, is_log_batch) = action_res?;
-            log_scanner.process_scan_batch(add_transform.as_ref(), batch.as_ref(), is_log_batch)
-        })
-        .filter(|res| res.as_ref().map_or(true, |(_, sv)| sv.contains(&true)))
+    action_iter.filter_map(move |action_res| {
+        action_res
+            .and_then(|(batch, is_log_batch)| {
+                log_scanner.process_scan_batch(add_transform.as_ref(), batch.as_ref(), is_log_batch)
+            })
+            .transpose()
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::Arc;
 
+    use itertools::Itertools;
+
+    use super::{
+        scan_action_iter, scan_read_schemas, FileActionKey, SeenFileActions, StringArena,
+        ARENA_CHUNK_SIZE, SCAN_ADD_FIELDS, SCAN_REMOVE_FIELDS,
+    };
+    use crate::engine::sync::SyncEngine;
+    use crate::expressions::{column_expr, Expression};
     use crate::scan::{
         state::{DvInfo, Stats},
         test_utils::{add_batch_simple, add_batch_with_remove, run_with_validate_callback},
     };
+    use crate::schema::{DataType, PrimitiveType, StructField, StructType};
 
     // dv-info is more complex to validate, we validate that works in the test for visit_scan_files
     // in state.rs
@@ -308,4 +514,134 @@ mod tests {
             validate_simple,
         );
     }
+
+    #[test]
+    fn test_scan_read_schemas() {
+        let (commit_read_schema, checkpoint_read_schema) = scan_read_schemas().unwrap();
+        let field_names = |schema: &StructType, action: &str| -> Vec<String> {
+            let DataType::Struct(action_type) = schema.field(action).unwrap().data_type() else {
+                panic!("Expected {action} to be a struct");
+            };
+            action_type.fields().map(|f| f.name().clone()).collect()
+        };
+        assert_eq!(field_names(&checkpoint_read_schema, "add"), SCAN_ADD_FIELDS);
+        assert!(checkpoint_read_schema.field("remove").is_none());
+        assert_eq!(field_names(&commit_read_schema, "add"), SCAN_ADD_FIELDS);
+        assert_eq!(
+            field_names(&commit_read_schema, "remove"),
+            SCAN_REMOVE_FIELDS
+        );
+    }
+
+    #[test]
+    fn test_seen_file_action_keys() {
+        let mut seen = SeenFileActions::default();
+        let key = seen.key("a", None);
+        assert_eq!(key, seen.key("a", None));
+        assert_ne!(key, seen.key("a", Some("")));
+        assert_ne!(seen.key("ab", None), seen.key("a", Some("b")));
+        assert_ne!(seen.key("a", Some("b")), seen.key("ab", Some("")));
+
+        assert!(!seen.contains(&key));
+        seen.insert(key);
+        assert!(seen.contains(&key));
+        assert!(!seen.contains(&seen.key("a", Some("dv"))));
+    }
+
+    #[test]
+    fn test_seen_file_action_hash_collisions() {
+        let mut seen = SeenFileActions::default();
+        // distinct files whose hashes (artificially) collide
+        let key = |path, dv_unique_id| FileActionKey {
+            hash: 42,
+            path,
+            dv_unique_id,
+        };
+        seen.insert(key("a", None));
+        assert!(seen.contains(&key("a", None)));
+        assert!(!seen.contains(&key("b", None)));
+        assert!(!seen.contains(&key("a", Some("dv"))));
+
+        seen.insert(key("b", None));
+        seen.insert(key("b", None));
+        assert!(seen.contains(&key("a", None)));
+        assert!(seen.contains(&key("b", None)));
+        assert!(!seen.contains(&key("c", None)));
+        assert_eq!(seen.collisions.len(), 1);
+    }
+
+    #[test]
+    fn test_string_arena() {
+        let mut arena = StringArena::default();
+        let long = "x".repeat(ARENA_CHUNK_SIZE + 1);
+        let strings = ["a", "", long.as_str(), "b", &long[1..], "c"];
+        let allocated: Vec<_> = strings.iter().map(|s| arena.alloc(s)).collect();
+        let stored: Vec<_> = allocated.into_iter().map(|s| arena.get(s)).collect();
+        assert_eq!(stored, strings);
+    }
+
+    #[test]
+    fn test_scan_action_iter_skips_batches_without_adds() {
+        let engine = SyncEngine::new();
+        let table_schema = Arc::new(StructType::new([StructField::new(
+            "foo",
+            DataType::STRING,
+            false,
+        )]));
+        // the (newer) batch with removes already contains every file in the (older) simple batch
+        let batches = [add_batch_with_remove(), add_batch_simple()];
+        let scan_data: Vec<_> = scan_action_iter(
+            &engine,
+            batches.into_iter().map(|batch| Ok((batch as _, true))),
+            &table_schema,
+            &StructType::new([]),
+            None,
+        )
+        .try_collect()
+        .unwrap();
+        assert_eq!(scan_data.len(), 1);
+        assert_eq!(scan_data[0].1, &[false, false, true, false]);
+    }
+
+    #[test]
+    fn test_scan_action_iter_prunes_partitions() {
+        let engine = SyncEngine::new();
+        let table_schema = Arc::new(StructType::new([StructField::new(
+            "value",
+            DataType::INTEGER,
+            true,
+        )]));
+        let partition_schema = StructType::new([StructField::new("date", DataType::DATE, true)]);
+        let date = PrimitiveType::Date.parse_scalar("2017-12-10").unwrap();
+        let selection_vectors = |predicate: Expression| -> Vec<Vec<bool>> {
+            scan_action_iter(
+                &engine,
+                std::iter::once(Ok((add_batch_simple() as _, true))),
+                &table_schema,
+                &partition_schema,
+                Some(Arc::new(predicate)),
+            )
+            .map_ok(|(_, selection_vector)| selection_vector)
+            .try_collect()
+            .unwrap()
+        };
+
+        let expected = vec![vec![true, false]];
+        assert_eq!(
+            selection_vectors(column_expr!("date").eq(date.clone())),
+            expected
+        );
+        // the partition value proves nothing about the data columns
+        let predicate = column_expr!("date")
+            .gt(date.clone())
+            .or(column_expr!("value").lt(5));
+        assert_eq!(selection_vectors(predicate), expected);
+
+        // pruned by the partition value, and by the stats of a surviving partition
+        assert!(selection_vectors(column_expr!("date").gt(date.clone())).is_empty());
+        let predicate = column_expr!("date")
+            .eq(date)
+            .and(column_expr!("value").gt(100));
+        assert!(selection_vectors(predicate).is_empty());
+    }
 }
@@ -1,33 +1,53 @@
 //! Functionality to create and execute scans (reads) over data stored in a delta table
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 
 use itertools::Itertools;
-use tracing::debug;
+use tracing::{debug, info_span};
 use url::Url;
 
 use crate::actions::deletion_vector::{split_vector, treemap_to_bools, DeletionVectorDescriptor};
-use crate::actions::{get_log_add_schema, get_log_schema, ADD_NAME, REMOVE_NAME};
 use crate::expressions::{ColumnName, Expression, ExpressionRef, Scalar};
-use crate::scan::state::{DvInfo, Stats};
+use crate::path::resolve_file_path;
 use crate::schema::{DataType, Schema, SchemaRef, StructField, StructType};
 use crate::snapshot::Snapshot;
-use crate::table_features::ColumnMappingMode;
-use crate::{DeltaResult, Engine, EngineData, Error, FileMeta};
+use crate::table_features::{
+    make_physical_expression, non_binary_collated_columns, ColumnMappingMode,
+};
+use crate::utils::in_span;
+use crate::{DeltaResult, Engine, EngineData, Error, ErrorContext, FileMeta};
 
-use self::log_replay::scan_action_iter;
-use self::state::GlobalScanState;
+use self::log_replay::{scan_action_iter, scan_read_schemas};
+use self::state::{DvInfo, GlobalScanState};
 
 pub(crate) mod data_skipping;
 pub mod log_replay;
+pub mod serialization;
 pub mod state;
 
+/// Resolves the paths of data files and deletion vector files, as recorded in the log, to the URLs
+/// they are read from, e.g. to the pre-signed URLs handed out by a Delta Sharing server to engines
+/// which don't hold the credentials for the storage of the table. See
+/// [`ScanBuilder::with_file_url_resolver`].
+pub trait FileUrlResolver: Send + Sync {
+    /// The URL to read the file at `path` from, or `None` to resolve it against the table root.
+    fn resolve(&self, path: &str) -> DeltaResult<Option<Url>>;
+}
+
+/// Resolves the paths in the map to their URLs, e.g. to a fixed set of pre-signed URLs.
+impl FileUrlResolver for HashMap<String, Url> {
+    fn resolve(&self, path: &str) -> DeltaResult<Option<Url>> {
+        Ok(self.get(path).cloned())
+    }
+}
+
 /// Builder to scan a snapshot of a table.
 pub struct ScanBuilder {
     snapshot: Arc<Snapshot>,
     schema: Option<SchemaRef>,
     predicate: Option<ExpressionRef>,
+    file_url_resolver: Option<Arc<dyn FileUrlResolver>>,
 }
 
 impl std::fmt::Debug for ScanBuilder {
@@ -46,6 +66,7 @@ impl ScanBuilder {
             snapshot: snapshot.into(),
             schema: None,
             predicate: None,
+            file_url_resolver: None,
         }
     }
 
@@ -81,6 +102,14 @@ impl ScanBuilder {
         self
     }
 
+    /// Read the data files of the scan (and their deletion vectors) from the URLs given by
+    /// `resolver`, rather than from the table root, see [`Scan::file_url`]. The engine must be able
+    /// to read from the URLs, e.g. the default engine reads HTTP(S) URLs directly.
+    pub fn with_file_url_resolver(mut self, resolver: Arc<dyn FileUrlResolver>) -> Self {
+        self.file_url_resolver = Some(resolver);
+        self
+    }
+
     /// Build the [`Scan`].
     ///
     /// This does not scan the table at this point, but does do some work to ensure that the
@@ -95,16 +124,40 @@ impl ScanBuilder {
         let (all_fields, read_fields, have_partition_cols) = get_state_info(
             logical_schema.as_ref(),
             &self.snapshot.metadata().partition_columns,
+            self.snapshot.column_mapping_mode,
         )?;
         let physical_schema = Arc::new(StructType::new(read_fields));
 
+        // Data files and their stats use physical column names, so the predicate must too
+        let physical_predicate = match (&self.predicate, self.snapshot.column_mapping_mode) {
+            (Some(predicate), ColumnMappingMode::Name | ColumnMappingMode::Id) => Some(Arc::new(
+                make_physical_expression(self.snapshot.schema(), predicate),
+            )),
+            _ => self.predicate.clone(),
+        };
+
+        // Parquet footer stats order strings by UTF-8 bytes, so they cannot skip row groups for a
+        // predicate that references columns with a non-binary collation
+        let collated_columns = non_binary_collated_columns(self.snapshot.schema());
+        let parquet_predicate = physical_predicate.clone().filter(|_| {
+            self.predicate.as_ref().is_some_and(|predicate| {
+                predicate
+                    .references()
+                    .into_iter()
+                    .all(|column| !collated_columns.contains(column))
+            })
+        });
+
         Ok(Scan {
             snapshot: self.snapshot,
             logical_schema,
             physical_schema,
             predicate: self.predicate,
-            all_fields,
+            physical_predicate,
+            parquet_predicate,
+            all_fields: Arc::new(all_fields),
             have_partition_cols,
+            file_url_resolver: self.file_url_resolver,
         })
     }
 }
@@ -175,8 +228,11 @@ pub struct Scan {
     logical_schema: SchemaRef,
     physical_schema: SchemaRef,
     predicate: Option<ExpressionRef>,
-    all_fields: Vec<ColumnType>,
+    physical_predicate: Option<ExpressionRef>,
+    parquet_predicate: Option<ExpressionRef>,
+    all_fields: Arc<Vec<ColumnType>>,
     have_partition_cols: bool,
+    file_url_resolver: Option<Arc<dyn FileUrlResolver>>,
 }
 
 impl std::fmt::Debug for Scan {
@@ -184,6 +240,8 @@ impl std::fmt::Debug for Scan {
         f.debug_struct("Scan")
             .field("schema", &self.logical_schema)
             .field("predicate", &self.predicate)
+            .field("physical_predicate", &self.physical_predicate)
+            .field("parquet_predicate", &self.parquet_predicate)
             .finish()
     }
 }
@@ -201,6 +259,38 @@ impl Scan {
         self.predicate.clone()
     }
 
+    /// Get the predicate [`Expression`] of the scan, with column references translated to the
+    /// physical column names of the data files (see [column mapping]).
+    ///
+    /// [column mapping]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#column-mapping
+    pub fn physical_predicate(&self) -> Option<ExpressionRef> {
+        self.physical_predicate.clone()
+    }
+
+    /// Get the predicate to pass to [`crate::ParquetHandler::read_parquet_files`] when reading the
+    /// scan's data files. This is the [physical predicate], unless it references string columns
+    /// with a non-binary collation, which parquet statistics cannot be used to skip.
+    ///
+    /// Since the parquet handler may skip the rows of a file which don't satisfy the predicate,
+    /// it must not be passed when reading a file with a deletion vector, whose rows are selected by
+    /// their positions in the file.
+    ///
+    /// [physical predicate]: Self::physical_predicate
+    pub fn parquet_predicate(&self) -> Option<ExpressionRef> {
+        self.parquet_predicate.clone()
+    }
+
+    /// Get the URL to read the data file at `path` (as returned by [`Self::scan_data`]) from. This
+    /// is the URL given by the [`FileUrlResolver`] of the scan, if any, or else the path resolved
+    /// against the table root.
+    pub fn file_url(&self, path: &str) -> DeltaResult<Url> {
+        resolve_file_url(
+            self.file_url_resolver.as_deref(),
+            &self.snapshot.table_root,
+            path,
+        )
+    }
+
     /// Get an iterator of [`EngineData`]s that should be included in scan for a query. This handles
     /// log-replay, reconciling Add and Remove actions, and applying data skipping (if
     /// possible). Each item in the returned iterator is a tuple of:
@@ -217,12 +307,81 @@ impl Scan {
         &self,
         engine: &dyn Engine,
     ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanData>>> {
-        Ok(scan_action_iter(
-            engine,
-            self.replay_for_scan_data(engine)?,
-            &self.logical_schema,
-            self.predicate(),
-        ))
+        let log_segment = &self.snapshot.log_segment;
+        let span = info_span!(
+            "scan.skipping",
+            version = log_segment.end_version,
+            has_predicate = self.physical_predicate.is_some(),
+            commit_files = log_segment.ascending_commit_files.len(),
+            checkpoint_parts = log_segment.checkpoint_parts.len(),
+            bytes = log_segment.size_in_bytes(),
+            batches = 0,
+            selected_files = 0,
+        );
+        let partition_schema = physical_partition_schema(
+            self.snapshot.schema(),
+            &self.snapshot.metadata().partition_columns,
+            self.snapshot.column_mapping_mode,
+        );
+        let scan_data = span.in_scope(|| -> DeltaResult<_> {
+            Ok(scan_action_iter(
+                engine,
+                self.replay_for_scan_data(engine)?,
+                &self.physical_schema,
+                &partition_schema,
+                self.physical_predicate.clone(),
+            ))
+        })?;
+
+        // the log is replayed (and files skipped) lazily, so the span is entered for each batch
+        let (mut batches, mut selected_files) = (0, 0);
+        let counts = span.clone();
+        Ok(in_span(span, scan_data).inspect(move |res| {
+            if let Ok((_, selection_vector)) = res {
+                batches += 1;
+                selected_files += selection_vector
+                    .iter()
+                    .filter(|selected| **selected)
+                    .count();
+                counts.record("batches", batches);
+                counts.record("selected_files", selected_files);
+            }
+        }))
+    }
+
+    /// List the files of the scan (after data skipping) without reading them, e.g. to plan the
+    /// tasks of a distributed engine by the sizes and row counts of the files (see
+    /// [`ScanFile::exact_num_records`]). This is [`Scan::scan_data`] visited with
+    /// [`state::scan_files`].
+    ///
+    /// [`ScanFile::exact_num_records`]: state::ScanFile::exact_num_records
+    pub fn scan_files(
+        &self,
+        engine: &dyn Engine,
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<state::ScanFile>>> {
+        Ok(self
+            .scan_data(engine)?
+            .map(|res| {
+                let (data, vec) = res?;
+                state::scan_files(data.as_ref(), &vec)
+            })
+            // Iterator<DeltaResult<Vec<ScanFile>>> to Iterator<DeltaResult<ScanFile>>
+            .flatten_ok())
+    }
+
+    /// The roots of the storage outside of the table that the files of the scan are stored in
+    /// (e.g. `s3://source-bucket/` for a shallow clone of a table in that bucket), sorted. Engines
+    /// can set up access to each of them before reading the scan (e.g. the default engine with
+    /// `DefaultEngineOptions::with_external_store`). See [`ScanFile::is_external`].
+    ///
+    /// [`ScanFile::is_external`]: state::ScanFile::is_external
+    pub fn external_storage(&self, engine: &dyn Engine) -> DeltaResult<Vec<Url>> {
+        let table_root = self.snapshot.table_root();
+        let mut roots = BTreeSet::new();
+        for file in self.scan_files(engine)? {
+            roots.extend(file?.external_storage(table_root)?);
+        }
+        Ok(roots.into_iter().collect())
     }
 
     // Factored out to facilitate testing
@@ -230,8 +389,7 @@ impl Scan {
         &self,
         engine: &dyn Engine,
     ) -> DeltaResult<impl Iterator<Item = DeltaResult<(Box<dyn EngineData>, bool)>> + Send> {
-        let commit_read_schema = get_log_schema().project(&[ADD_NAME, REMOVE_NAME])?;
-        let checkpoint_read_schema = get_log_add_schema().clone();
+        let (commit_read_schema, checkpoint_read_schema) = scan_read_schemas()?;
 
         // NOTE: We don't pass any meta-predicate because we expect no meaningful row group skipping
         // when ~every checkpoint file will contain the adds and removes we are looking for.
@@ -263,78 +421,89 @@ impl Scan {
     pub fn execute(
         &self,
         engine: Arc<dyn Engine>,
-    ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanResult>> + '_> {
-        struct ScanFile {
-            path: String,
-            size: i64,
-            dv_info: DvInfo,
-            partition_values: HashMap<String, String>,
-        }
-        fn scan_data_callback(
-            batches: &mut Vec<ScanFile>,
-            path: &str,
-            size: i64,
-            _: Option<Stats>,
-            dv_info: DvInfo,
-            partition_values: HashMap<String, String>,
-        ) {
-            batches.push(ScanFile {
-                path: path.to_string(),
-                size,
-                dv_info,
-                partition_values,
-            });
-        }
-
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanResult>>> {
         debug!(
             "Executing scan with logical schema {:#?} and physical schema {:#?}",
             self.logical_schema, self.physical_schema
         );
 
         let global_state = Arc::new(self.global_scan_state());
-        let scan_data = self.scan_data(engine.as_ref())?;
-        let scan_files_iter = scan_data
-            .map(|res| {
-                let (data, vec) = res?;
-                let scan_files = vec![];
-                state::visit_scan_files(data.as_ref(), &vec, scan_files, scan_data_callback)
-            })
-            // Iterator<DeltaResult<Vec<ScanFile>>> to Iterator<DeltaResult<ScanFile>>
-            .flatten_ok();
+        let scan_files_iter = self.scan_files(engine.as_ref())?;
+        // The iterator owns (cheap clones of) the state of the scan it needs, rather than borrowing
+        // the scan, so that it may outlive the scan (e.g. when handed across the FFI boundary).
+        let table_root = self.snapshot.table_root.clone();
+        let version = self.snapshot.version();
+        let file_url_resolver = self.file_url_resolver.clone();
+        let parquet_predicate = self.parquet_predicate.clone();
+        let all_fields = self.all_fields.clone();
+        let have_partition_cols = self.have_partition_cols;
 
         let result = scan_files_iter
             .map(move |scan_file| -> DeltaResult<_> {
                 let scan_file = scan_file?;
-                let file_path = self.snapshot.table_root.join(&scan_file.path)?;
-                let mut selection_vector = scan_file
-                    .dv_info
-                    .get_selection_vector(engine.as_ref(), &self.snapshot.table_root)?;
-                let meta = FileMeta {
-                    last_modified: 0,
-                    size: scan_file.size as usize,
-                    location: file_path,
-                };
-                let read_result_iter = engine.get_parquet_handler().read_parquet_files(
-                    &[meta],
-                    global_state.read_schema.clone(),
-                    self.predicate(),
-                )?;
+                let file_path =
+                    resolve_file_url(file_url_resolver.as_deref(), &table_root, &scan_file.path)?;
+                // annotate the errors reading the file with the table, version and file
+                let context = ErrorContext::new()
+                    .with_table_root(table_root.clone())
+                    .with_version(version)
+                    .with_file(file_path.clone());
+                let span = info_span!(
+                    "scan.read_file",
+                    file = %file_path,
+                    bytes = scan_file.size,
+                    has_dv = scan_file.dv_info.has_vector(),
+                    rows = 0,
+                );
+                let (mut selection_vector, read_result_iter) = span
+                    .in_scope(|| -> DeltaResult<_> {
+                        let selection_vector = load_selection_vector(
+                            engine.as_ref(),
+                            file_url_resolver.as_deref(),
+                            &table_root,
+                            &scan_file.dv_info,
+                        )?;
+                        let meta = FileMeta {
+                            last_modified: 0,
+                            size: scan_file.size as usize,
+                            location: file_path,
+                        };
+                        // skipping rows would shift the rows selected by the deletion vector
+                        let predicate = match scan_file.dv_info.has_vector() {
+                            true => None,
+                            false => parquet_predicate.clone(),
+                        };
+                        let read_result_iter = engine.get_parquet_handler().read_parquet_files(
+                            &[meta],
+                            global_state.read_schema.clone(),
+                            predicate,
+                        )?;
+                        Ok((selection_vector, read_result_iter))
+                    })
+                    .map_err(|err| err.with_context(context.clone()))?;
 
                 // Arc clones
                 let engine = engine.clone();
                 let global_state = global_state.clone();
-                Ok(read_result_iter.map(move |read_result| -> DeltaResult<_> {
-                    let read_result = read_result?;
+                let all_fields = all_fields.clone();
+                let mut rows = 0;
+                let counts = span.clone();
+                let results = read_result_iter.map(move |read_result| -> DeltaResult<_> {
+                    let read_result =
+                        read_result.map_err(|err| err.with_context(context.clone()))?;
                     // to transform the physical data into the correct logical form
                     let logical = transform_to_logical_internal(
                         engine.as_ref(),
                         read_result,
                         &global_state,
                         &scan_file.partition_values,
-                        &self.all_fields,
-                        self.have_partition_cols,
-                    );
+                        &all_fields,
+                        have_partition_cols,
+                    )
+                    .map_err(|err| err.with_context(context.clone()));
                     let len = logical.as_ref().map_or(0, |res| res.len());
+                    rows += len;
+                    counts.record("rows", rows);
                     // need to split the dv_mask. what's left in dv_mask covers this result, and rest
                     // will cover the following results. we `take()` out of `selection_vector` to avoid
                     // trying to return a captured variable. We're going to reassign `selection_vector`
@@ -347,7 +516,9 @@ impl Scan {
                     };
                     selection_vector = rest;
                     Ok(result)
-                }))
+                });
+                // the file is read (and transformed) lazily, so the span is entered for each batch
+                Ok(in_span(span, results))
             })
             // Iterator<DeltaResult<Iterator<DeltaResult<ScanResult>>>> to Iterator<DeltaResult<DeltaResult<ScanResult>>>
             .flatten_ok()
@@ -357,6 +528,42 @@ impl Scan {
     }
 }
 
+// The URL of the data file at `path`, given by `resolver` (if any) or else resolved against the
+// `table_root`
+fn resolve_file_url(
+    resolver: Option<&dyn FileUrlResolver>,
+    table_root: &Url,
+    path: &str,
+) -> DeltaResult<Url> {
+    if let Some(url) = resolver
+        .map(|resolver| resolver.resolve(path))
+        .transpose()?
+        .flatten()
+    {
+        return Ok(url);
+    }
+    resolve_file_path(table_root, path)
+}
+
+// The selection vector of the deletion vector of a file (if any), whose file is read from the URL
+// given by `resolver` (if any) or else resolved against the `table_root`
+fn load_selection_vector(
+    engine: &dyn Engine,
+    resolver: Option<&dyn FileUrlResolver>,
+    table_root: &Url,
+    dv_info: &DvInfo,
+) -> DeltaResult<Option<Vec<bool>>> {
+    let Some(descriptor) = dv_info.deletion_vector_descriptor() else {
+        return Ok(None);
+    };
+    let location = descriptor
+        .path()?
+        .map(|path| resolve_file_url(resolver, table_root, &path))
+        .transpose()?;
+    let dv = descriptor.load_from(engine.get_file_system_client(), location)?;
+    Ok(Some(dv.into_selection_vector()))
+}
+
 /// Get the schema that scan rows (from [`Scan::scan_data`]) will be returned with.
 ///
 /// It is:
@@ -382,7 +589,18 @@ pub fn scan_row_schema() -> Schema {
     log_replay::SCAN_ROW_SCHEMA.as_ref().clone()
 }
 
-fn parse_partition_value(raw: Option<&String>, data_type: &DataType) -> DeltaResult<Scalar> {
+/// The partition value some writers use for nulls (as Hive does in partition directories), which
+/// is read as null whatever the type of the partition column.
+pub(crate) const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Parse a partition value of the given type as described by the
+/// [Delta protocol](https://github.com/delta-io/delta/blob/master/PROTOCOL.md#partition-value-serialization).
+/// Missing and empty values, and [`HIVE_DEFAULT_PARTITION`], are null.
+pub(crate) fn parse_partition_value(
+    raw: Option<&String>,
+    data_type: &DataType,
+) -> DeltaResult<Scalar> {
+    let raw = raw.filter(|raw| *raw != HIVE_DEFAULT_PARTITION);
     match (raw, data_type.as_primitive_opt()) {
         (Some(v), Some(primitive)) => primitive.parse_scalar(v),
         (Some(_), None) => Err(Error::generic(format!(
@@ -392,6 +610,21 @@ fn parse_partition_value(raw: Option<&String>, data_type: &DataType) -> DeltaRes
     }
 }
 
+/// The partition columns of a table, with the physical names which key the `partitionValues` of its
+/// add actions.
+pub(crate) fn physical_partition_schema(
+    logical_schema: &Schema,
+    partition_columns: &[String],
+    column_mapping_mode: ColumnMappingMode,
+) -> StructType {
+    StructType::new(
+        logical_schema
+            .fields()
+            .filter(|field| partition_columns.contains(field.name()))
+            .map(|field| field.make_physical(column_mapping_mode)),
+    )
+}
+
 /// Get the state needed to process a scan. In particular this returns a triple of
 /// (all_fields_in_query, fields_to_read_from_parquet, have_partition_cols) where:
 /// - all_fields_in_query - all fields in the query as [`ColumnType`] enums
@@ -401,6 +634,7 @@ fn parse_partition_value(raw: Option<&String>, data_type: &DataType) -> DeltaRes
 fn get_state_info(
     logical_schema: &Schema,
     partition_columns: &[String],
+    column_mapping_mode: ColumnMappingMode,
 ) -> DeltaResult<(Vec<ColumnType>, Vec<StructField>, bool)> {
     let mut have_partition_cols = false;
     let mut read_fields = Vec::with_capacity(logical_schema.fields.len());
@@ -420,7 +654,7 @@ fn get_state_info(
             } else {
                 // Add to read schema, store field so we can build a `Column` expression later
                 // if needed (i.e. if we have partition columns)
-                let physical_field = logical_field.make_physical();
+                let physical_field = logical_field.make_physical(column_mapping_mode);
                 debug!("\n\n{logical_field:#?}\nAfter mapping: {physical_field:#?}\n\n");
                 let physical_name = physical_field.name.clone();
                 read_fields.push(physical_field);
@@ -452,6 +686,7 @@ pub fn transform_to_logical(
     let (all_fields, _read_fields, have_partition_cols) = get_state_info(
         &global_state.logical_schema,
         &global_state.partition_columns,
+        global_state.column_mapping_mode,
     )?;
     transform_to_logical_internal(
         engine,
@@ -588,6 +823,7 @@ pub(crate) mod test_utils {
             &engine,
             batch.into_iter().map(|batch| Ok((batch as _, true))),
             &table_schema,
+            &StructType::new([]),
             None,
         );
         let mut batch_count = 0;
@@ -613,6 +849,7 @@ mod tests {
 
     use crate::engine::sync::SyncEngine;
     use crate::expressions::column_expr;
+    use crate::scan::state::{DvInfo, Stats};
     use crate::schema::PrimitiveType;
     use crate::Table;
 
@@ -657,6 +894,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_scan_files() {
+        let engine = SyncEngine::new();
+        let scan_files = |table: &str| {
+            let path = std::fs::canonicalize(PathBuf::from(table)).unwrap();
+            let url = url::Url::from_directory_path(path).unwrap();
+            let snapshot = Table::new(url).snapshot(&engine, None).unwrap();
+            let scan = snapshot.into_scan_builder().build().unwrap();
+            let files: Vec<_> = scan.scan_files(&engine).unwrap().try_collect().unwrap();
+            files
+        };
+
+        let files = scan_files("./tests/data/table-without-dv-small/");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size, 548);
+        assert_eq!(files[0].modification_time, Some(1678020185157));
+        assert_eq!(files[0].exact_num_records(), Some(10));
+
+        // the number of records of a file with a deletion vector includes the deleted rows
+        let files = scan_files("./tests/data/table-with-dv-small/");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].modification_time, Some(1677811178336));
+        assert_eq!(files[0].stats.as_ref().unwrap().num_records, 10);
+        assert_eq!(files[0].exact_num_records(), None);
+    }
+
     #[test_log::test]
     fn test_scan_data() {
         let path =
@@ -706,6 +969,43 @@ mod tests {
                 PrimitiveType::Timestamp,
                 Scalar::Timestamp(123456),
             ),
+            (
+                "1970-01-01T00:00:00.123456Z",
+                PrimitiveType::Timestamp,
+                Scalar::Timestamp(123456),
+            ),
+            (
+                "1970-01-01T01:00:00+01:00",
+                PrimitiveType::Timestamp,
+                Scalar::Timestamp(0),
+            ),
+            (
+                "1970-01-01 00:00:01",
+                PrimitiveType::TimestampNtz,
+                Scalar::TimestampNtz(1_000_000),
+            ),
+            (
+                "1970-01-01T00:00:01",
+                PrimitiveType::TimestampNtz,
+                Scalar::TimestampNtz(1_000_000),
+            ),
+            ("FALSE", PrimitiveType::Boolean, Scalar::Boolean(false)),
+            (
+                "12.3",
+                PrimitiveType::Decimal(5, 2),
+                Scalar::Decimal(1230, 5, 2),
+            ),
+            (
+                "\u{1}\u{2}",
+                PrimitiveType::Binary,
+                Scalar::Binary(vec![1, 2]),
+            ),
+            (
+                HIVE_DEFAULT_PARTITION,
+                PrimitiveType::Date,
+                Scalar::Null(DataType::DATE),
+            ),
+            ("", PrimitiveType::Integer, Scalar::Null(DataType::INTEGER)),
         ];
 
         for (raw, data_type, expected) in &cases {
@@ -716,6 +1016,17 @@ mod tests {
             .unwrap();
             assert_eq!(value, *expected);
         }
+
+        let invalid = [
+            ("yes", PrimitiveType::Boolean),
+            ("2024-13-01", PrimitiveType::Date),
+            ("1970-01-01T00:00:00Z", PrimitiveType::TimestampNtz),
+            ("1.234", PrimitiveType::Decimal(5, 2)),
+        ];
+        for (raw, data_type) in invalid {
+            let result = parse_partition_value(Some(&raw.to_string()), &DataType::from(data_type));
+            assert!(result.is_err(), "{raw} should not parse");
+        }
     }
 
     #[test]
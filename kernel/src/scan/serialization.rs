@@ -0,0 +1,514 @@
+//! A stable, versioned serialized format for [`ScanFile`]s and the [`GlobalScanState`] of a scan.
+//!
+//! Distributed engines plan a scan in one process and read its files in others, so they ship the
+//! scan files and the global scan state between processes (or persist them), possibly between
+//! processes running different versions of the kernel. Each serialized value carries the version
+//! of its format, and a kernel reads all the format versions up to the one it writes,
+//! [`FORMAT_VERSION`]. Fields added within a format version are optional, and are ignored by
+//! kernels which don't know them; any other change to the format bumps its version.
+//!
+//! Values are serialized as JSON, see [`ScanFile::to_json`] and [`GlobalScanState::to_json`]. The
+//! JSON of a scan file (version 1) is
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "path": "part-00000.parquet",
+//!   "size": 635,
+//!   "modificationTime": 1587968586000,
+//!   "stats": { "numRecords": 10 },
+//!   "deletionVector": {
+//!     "storageType": "u",
+//!     "pathOrInlineDv": "vBn[lx{q8@P<9BNH/isA",
+//!     "offset": 1,
+//!     "sizeInBytes": 36,
+//!     "cardinality": 2
+//!   },
+//!   "partitionValues": { "date": "2017-12-10" }
+//! }
+//! ```
+//!
+//! where `modificationTime` (which older kernels don't write), `stats`, `deletionVector` and
+//! `partitionValues` may be absent, and the deletion vector is described as in the `add` actions of
+//! the Delta log. The JSON of the global scan state has the `version`, `tableRoot`,
+//! `partitionColumns`, `logicalSchema` and `readSchema` (as Delta schemas) and `columnMappingMode`
+//! (as in the table properties) of the scan.
+//!
+//! With the `protobuf` feature, values can also be serialized as protobuf messages (see
+//! [`ScanFile::to_protobuf`] and [`GlobalScanState::to_protobuf`]), with the schema
+//!
+//! ```protobuf
+//! message ScanFile {
+//!   uint32 version = 1;
+//!   string path = 2;
+//!   int64 size = 3;
+//!   optional uint64 num_records = 4;
+//!   optional DeletionVector deletion_vector = 5;
+//!   map<string, string> partition_values = 6;
+//!   optional int64 modification_time = 7;
+//! }
+//!
+//! message DeletionVector {
+//!   string storage_type = 1;
+//!   string path_or_inline_dv = 2;
+//!   optional int32 offset = 3;
+//!   int32 size_in_bytes = 4;
+//!   int64 cardinality = 5;
+//! }
+//!
+//! message GlobalScanState {
+//!   uint32 version = 1;
+//!   string table_root = 2;
+//!   repeated string partition_columns = 3;
+//!   string logical_schema = 4; // the JSON of the Delta schema
+//!   string read_schema = 5; // the JSON of the Delta schema
+//!   string column_mapping_mode = 6;
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::scan::state::{DvInfo, GlobalScanState, ScanFile, Stats};
+use crate::schema::SchemaRef;
+use crate::table_features::ColumnMappingMode;
+use crate::{DeltaResult, Error};
+
+/// The version of the format the kernel serializes scan files and global scan states with, which
+/// is also the latest version it deserializes.
+pub const FORMAT_VERSION: u32 = 1;
+
+// The supported versions of the format
+fn check_version(version: u32) -> DeltaResult<()> {
+    if (1..=FORMAT_VERSION).contains(&version) {
+        Ok(())
+    } else {
+        Err(Error::unsupported(format!(
+            "Unsupported version {version} of the serialized scan format, this kernel supports \
+            versions 1 to {FORMAT_VERSION}"
+        )))
+    }
+}
+
+#[derive(Deserialize)]
+struct Versioned {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanFileV1 {
+    version: u32,
+    path: String,
+    size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modification_time: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stats: Option<StatsV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deletion_vector: Option<DeletionVectorV1>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    partition_values: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsV1 {
+    num_records: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeletionVectorV1 {
+    storage_type: String,
+    path_or_inline_dv: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    offset: Option<i32>,
+    size_in_bytes: i32,
+    cardinality: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalScanStateV1 {
+    version: u32,
+    table_root: String,
+    partition_columns: Vec<String>,
+    logical_schema: SchemaRef,
+    read_schema: SchemaRef,
+    column_mapping_mode: ColumnMappingMode,
+}
+
+impl From<&ScanFile> for ScanFileV1 {
+    fn from(file: &ScanFile) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            path: file.path.clone(),
+            size: file.size,
+            modification_time: file.modification_time,
+            stats: file.stats.as_ref().map(|stats| StatsV1 {
+                num_records: stats.num_records,
+            }),
+            deletion_vector: file
+                .dv_info
+                .deletion_vector
+                .as_ref()
+                .map(|dv| DeletionVectorV1 {
+                    storage_type: dv.storage_type.clone(),
+                    path_or_inline_dv: dv.path_or_inline_dv.clone(),
+                    offset: dv.offset,
+                    size_in_bytes: dv.size_in_bytes,
+                    cardinality: dv.cardinality,
+                }),
+            partition_values: file.partition_values.clone(),
+        }
+    }
+}
+
+impl From<ScanFileV1> for ScanFile {
+    fn from(file: ScanFileV1) -> Self {
+        let deletion_vector = file.deletion_vector.map(|dv| DeletionVectorDescriptor {
+            storage_type: dv.storage_type,
+            path_or_inline_dv: dv.path_or_inline_dv,
+            offset: dv.offset,
+            size_in_bytes: dv.size_in_bytes,
+            cardinality: dv.cardinality,
+        });
+        Self {
+            path: file.path,
+            size: file.size,
+            modification_time: file.modification_time,
+            stats: file.stats.map(|stats| Stats {
+                num_records: stats.num_records,
+            }),
+            dv_info: DvInfo { deletion_vector },
+            partition_values: file.partition_values,
+        }
+    }
+}
+
+impl From<&GlobalScanState> for GlobalScanStateV1 {
+    fn from(state: &GlobalScanState) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            table_root: state.table_root.clone(),
+            partition_columns: state.partition_columns.clone(),
+            logical_schema: state.logical_schema.clone(),
+            read_schema: state.read_schema.clone(),
+            column_mapping_mode: state.column_mapping_mode,
+        }
+    }
+}
+
+impl From<GlobalScanStateV1> for GlobalScanState {
+    fn from(state: GlobalScanStateV1) -> Self {
+        Self {
+            table_root: state.table_root,
+            partition_columns: state.partition_columns,
+            logical_schema: state.logical_schema,
+            read_schema: state.read_schema,
+            column_mapping_mode: state.column_mapping_mode,
+        }
+    }
+}
+
+impl ScanFile {
+    /// Serialize the scan file as JSON, in the latest version of the format (see the
+    /// [module](self) docs).
+    pub fn to_json(&self) -> DeltaResult<String> {
+        Ok(serde_json::to_string(&ScanFileV1::from(self))?)
+    }
+
+    /// Deserialize a scan file from JSON, in any supported version of the format (see the
+    /// [module](self) docs).
+    pub fn from_json(json: &str) -> DeltaResult<Self> {
+        check_version(serde_json::from_str::<Versioned>(json)?.version)?;
+        Ok(serde_json::from_str::<ScanFileV1>(json)?.into())
+    }
+}
+
+impl GlobalScanState {
+    /// Serialize the global scan state as JSON, in the latest version of the format (see the
+    /// [module](self) docs).
+    pub fn to_json(&self) -> DeltaResult<String> {
+        Ok(serde_json::to_string(&GlobalScanStateV1::from(self))?)
+    }
+
+    /// Deserialize a global scan state from JSON, in any supported version of the format (see
+    /// the [module](self) docs).
+    pub fn from_json(json: &str) -> DeltaResult<Self> {
+        check_version(serde_json::from_str::<Versioned>(json)?.version)?;
+        Ok(serde_json::from_str::<GlobalScanStateV1>(json)?.into())
+    }
+}
+
+#[cfg(feature = "protobuf")]
+mod protobuf {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use prost::Message;
+
+    use super::*;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct ScanFileProto {
+        #[prost(uint32, tag = "1")]
+        version: u32,
+        #[prost(string, tag = "2")]
+        path: String,
+        #[prost(int64, tag = "3")]
+        size: i64,
+        #[prost(uint64, optional, tag = "4")]
+        num_records: Option<u64>,
+        #[prost(message, optional, tag = "5")]
+        deletion_vector: Option<DeletionVectorProto>,
+        #[prost(map = "string, string", tag = "6")]
+        partition_values: HashMap<String, String>,
+        #[prost(int64, optional, tag = "7")]
+        modification_time: Option<i64>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct DeletionVectorProto {
+        #[prost(string, tag = "1")]
+        storage_type: String,
+        #[prost(string, tag = "2")]
+        path_or_inline_dv: String,
+        #[prost(int32, optional, tag = "3")]
+        offset: Option<i32>,
+        #[prost(int32, tag = "4")]
+        size_in_bytes: i32,
+        #[prost(int64, tag = "5")]
+        cardinality: i64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct GlobalScanStateProto {
+        #[prost(uint32, tag = "1")]
+        version: u32,
+        #[prost(string, tag = "2")]
+        table_root: String,
+        #[prost(string, repeated, tag = "3")]
+        partition_columns: Vec<String>,
+        #[prost(string, tag = "4")]
+        logical_schema: String,
+        #[prost(string, tag = "5")]
+        read_schema: String,
+        #[prost(string, tag = "6")]
+        column_mapping_mode: String,
+    }
+
+    fn decode_error(err: prost::DecodeError) -> Error {
+        Error::generic(format!(
+            "Invalid protobuf of the serialized scan format: {err}"
+        ))
+    }
+
+    impl ScanFile {
+        /// Serialize the scan file as a protobuf message, in the latest version of the format
+        /// (see the [module](super) docs).
+        pub fn to_protobuf(&self) -> Vec<u8> {
+            let file = ScanFileV1::from(self);
+            ScanFileProto {
+                version: file.version,
+                path: file.path,
+                size: file.size,
+                num_records: file.stats.map(|stats| stats.num_records),
+                deletion_vector: file.deletion_vector.map(|dv| DeletionVectorProto {
+                    storage_type: dv.storage_type,
+                    path_or_inline_dv: dv.path_or_inline_dv,
+                    offset: dv.offset,
+                    size_in_bytes: dv.size_in_bytes,
+                    cardinality: dv.cardinality,
+                }),
+                partition_values: file.partition_values,
+                modification_time: file.modification_time,
+            }
+            .encode_to_vec()
+        }
+
+        /// Deserialize a scan file from a protobuf message, in any supported version of the
+        /// format (see the [module](super) docs).
+        pub fn from_protobuf(bytes: &[u8]) -> DeltaResult<Self> {
+            let file = ScanFileProto::decode(bytes).map_err(decode_error)?;
+            check_version(file.version)?;
+            let file = ScanFileV1 {
+                version: file.version,
+                path: file.path,
+                size: file.size,
+                modification_time: file.modification_time,
+                stats: file.num_records.map(|num_records| StatsV1 { num_records }),
+                deletion_vector: file.deletion_vector.map(|dv| DeletionVectorV1 {
+                    storage_type: dv.storage_type,
+                    path_or_inline_dv: dv.path_or_inline_dv,
+                    offset: dv.offset,
+                    size_in_bytes: dv.size_in_bytes,
+                    cardinality: dv.cardinality,
+                }),
+                partition_values: file.partition_values,
+            };
+            Ok(file.into())
+        }
+    }
+
+    impl GlobalScanState {
+        /// Serialize the global scan state as a protobuf message, in the latest version of the
+        /// format (see the [module](super) docs).
+        pub fn to_protobuf(&self) -> DeltaResult<Vec<u8>> {
+            let state = GlobalScanStateV1::from(self);
+            let column_mapping_mode = serde_json::to_value(state.column_mapping_mode)?;
+            Ok(GlobalScanStateProto {
+                version: state.version,
+                table_root: state.table_root,
+                partition_columns: state.partition_columns,
+                logical_schema: serde_json::to_string(&state.logical_schema)?,
+                read_schema: serde_json::to_string(&state.read_schema)?,
+                column_mapping_mode: column_mapping_mode.as_str().unwrap_or_default().to_string(),
+            }
+            .encode_to_vec())
+        }
+
+        /// Deserialize a global scan state from a protobuf message, in any supported version of
+        /// the format (see the [module](super) docs).
+        pub fn from_protobuf(bytes: &[u8]) -> DeltaResult<Self> {
+            let state = GlobalScanStateProto::decode(bytes).map_err(decode_error)?;
+            check_version(state.version)?;
+            let state = GlobalScanStateV1 {
+                version: state.version,
+                table_root: state.table_root,
+                partition_columns: state.partition_columns,
+                logical_schema: serde_json::from_str(&state.logical_schema)?,
+                read_schema: serde_json::from_str(&state.read_schema)?,
+                column_mapping_mode: ColumnMappingMode::from_str(&state.column_mapping_mode)
+                    .map_err(|_| Error::invalid_column_mapping_mode(state.column_mapping_mode))?,
+            };
+            Ok(state.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::schema::{DataType, StructField, StructType};
+
+    use super::*;
+
+    fn scan_file() -> ScanFile {
+        ScanFile {
+            path: "part-00000.parquet".to_string(),
+            size: 635,
+            modification_time: Some(1587968586000),
+            stats: Some(Stats { num_records: 10 }),
+            dv_info: DvInfo {
+                deletion_vector: Some(DeletionVectorDescriptor {
+                    storage_type: "u".to_string(),
+                    path_or_inline_dv: "vBn[lx{q8@P<9BNH/isA".to_string(),
+                    offset: Some(1),
+                    size_in_bytes: 36,
+                    cardinality: 2,
+                }),
+            },
+            partition_values: HashMap::from([("date".to_string(), "2017-12-10".to_string())]),
+        }
+    }
+
+    fn global_scan_state() -> GlobalScanState {
+        let schema = Arc::new(StructType::new([
+            StructField::new("id", DataType::LONG, false),
+            StructField::new("date", DataType::DATE, true),
+        ]));
+        GlobalScanState {
+            table_root: "s3://bucket/table/".to_string(),
+            partition_columns: vec!["date".to_string()],
+            logical_schema: schema.clone(),
+            read_schema: Arc::new(schema.project_as_struct(&["id"]).unwrap()),
+            column_mapping_mode: ColumnMappingMode::Name,
+        }
+    }
+
+    #[test]
+    fn test_scan_file_json_round_trip() {
+        let file = scan_file();
+        assert_eq!(ScanFile::from_json(&file.to_json().unwrap()).unwrap(), file);
+
+        let file = ScanFile {
+            modification_time: None,
+            stats: None,
+            dv_info: DvInfo::default(),
+            partition_values: HashMap::new(),
+            ..file
+        };
+        let json = file.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"path":"part-00000.parquet","size":635}"#
+        );
+        assert_eq!(ScanFile::from_json(&json).unwrap(), file);
+    }
+
+    // The documented version 1 of the format, which all future kernels must be able to read
+    #[test]
+    fn test_scan_file_json_v1() {
+        let json = r#"{
+            "version": 1,
+            "path": "part-00000.parquet",
+            "size": 635,
+            "modificationTime": 1587968586000,
+            "stats": { "numRecords": 10 },
+            "deletionVector": {
+                "storageType": "u",
+                "pathOrInlineDv": "vBn[lx{q8@P<9BNH/isA",
+                "offset": 1,
+                "sizeInBytes": 36,
+                "cardinality": 2
+            },
+            "partitionValues": { "date": "2017-12-10" },
+            "someFutureField": true
+        }"#;
+        assert_eq!(ScanFile::from_json(json).unwrap(), scan_file());
+    }
+
+    #[test]
+    fn test_unsupported_versions() {
+        for version in [0, FORMAT_VERSION + 1] {
+            let json = format!(r#"{{"version":{version},"path":"a.parquet","size":1}}"#);
+            assert!(matches!(
+                ScanFile::from_json(&json),
+                Err(Error::Unsupported(_))
+            ));
+        }
+        assert!(ScanFile::from_json(r#"{"path":"a.parquet","size":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_global_scan_state_json_round_trip() {
+        let state = global_scan_state();
+        let json = state.to_json().unwrap();
+        assert!(json.starts_with(r#"{"version":1,"tableRoot":"s3://bucket/table/""#));
+        let deserialized = GlobalScanState::from_json(&json).unwrap();
+        assert_eq!(deserialized.table_root, state.table_root);
+        assert_eq!(deserialized.partition_columns, state.partition_columns);
+        assert_eq!(deserialized.logical_schema, state.logical_schema);
+        assert_eq!(deserialized.read_schema, state.read_schema);
+        assert_eq!(deserialized.column_mapping_mode, state.column_mapping_mode);
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn test_protobuf_round_trip() {
+        let file = scan_file();
+        assert_eq!(ScanFile::from_protobuf(&file.to_protobuf()).unwrap(), file);
+
+        let state = global_scan_state();
+        let deserialized = GlobalScanState::from_protobuf(&state.to_protobuf().unwrap()).unwrap();
+        assert_eq!(deserialized.logical_schema, state.logical_schema);
+        assert_eq!(deserialized.read_schema, state.read_schema);
+        assert_eq!(deserialized.column_mapping_mode, state.column_mapping_mode);
+        assert!(ScanFile::from_protobuf(b"not protobuf").is_err());
+    }
+}
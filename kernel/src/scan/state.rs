@@ -3,10 +3,13 @@
 use std::collections::HashMap;
 use std::sync::LazyLock;
 
+use url::Url;
+
+use crate::path::{resolve_file_path, storage_root};
 use crate::utils::require;
 use crate::{
     actions::{
-        deletion_vector::{treemap_to_bools, DeletionVectorDescriptor},
+        deletion_vector::{DeletionVector, DeletionVectorDescriptor},
         visitors::visit_deletion_vector_at,
     },
     engine_data::{GetData, RowVisitor, TypedGetData as _},
@@ -30,7 +33,7 @@ pub struct GlobalScanState {
 }
 
 /// this struct can be used by an engine to materialize a selection vector
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DvInfo {
     pub(crate) deletion_vector: Option<DeletionVectorDescriptor>,
 }
@@ -53,20 +56,31 @@ impl DvInfo {
         self.deletion_vector.is_some()
     }
 
+    /// Get the descriptor of the deletion vector, if any, as found in the log.
+    pub fn deletion_vector_descriptor(&self) -> Option<&DeletionVectorDescriptor> {
+        self.deletion_vector.as_ref()
+    }
+
+    /// Load the [`DeletionVector`] of the file, if it has one. Deletion vectors that are not
+    /// stored inline are read with the engine's [`crate::FileSystemClient`].
+    pub fn get_deletion_vector(
+        &self,
+        engine: &dyn Engine,
+        table_root: &url::Url,
+    ) -> DeltaResult<Option<DeletionVector>> {
+        self.deletion_vector
+            .as_ref()
+            .map(|dv| dv.load(engine.get_file_system_client(), table_root))
+            .transpose()
+    }
+
     pub fn get_selection_vector(
         &self,
         engine: &dyn Engine,
         table_root: &url::Url,
     ) -> DeltaResult<Option<Vec<bool>>> {
-        let dv_treemap = self
-            .deletion_vector
-            .as_ref()
-            .map(|dv_descriptor| {
-                let fs_client = engine.get_file_system_client();
-                dv_descriptor.read(fs_client, table_root)
-            })
-            .transpose()?;
-        Ok(dv_treemap.map(treemap_to_bools))
+        let dv = self.get_deletion_vector(engine, table_root)?;
+        Ok(dv.map(DeletionVector::into_selection_vector))
     }
 
     /// Returns a vector of row indexes that should be *removed* from the result set
@@ -75,16 +89,93 @@ impl DvInfo {
         engine: &dyn Engine,
         table_root: &url::Url,
     ) -> DeltaResult<Option<Vec<u64>>> {
-        self.deletion_vector
-            .as_ref()
-            .map(|dv| {
-                let fs_client = engine.get_file_system_client();
-                dv.row_indexes(fs_client, table_root)
-            })
-            .transpose()
+        let dv = self.get_deletion_vector(engine, table_root)?;
+        Ok(dv.map(|dv| dv.row_indexes().collect()))
     }
 }
 
+/// A file to read for a scan, with the arguments of a [`ScanCallback`]. See [`scan_files`].
+///
+/// Scan files can be shipped to other processes (e.g. the workers of a distributed engine) in a
+/// stable serialized format, see [`crate::scan::serialization`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ScanFile {
+    /// The path of the file, relative to the table root (or an absolute URL)
+    pub path: String,
+    /// The size of the file in bytes
+    pub size: i64,
+    /// The time the file was last modified, in milliseconds since the epoch. It's only missing
+    /// from scan files deserialized from the format of older kernels.
+    pub modification_time: Option<i64>,
+    /// The statistics of the file, if any
+    pub stats: Option<Stats>,
+    /// The deletion vector of the file, if any
+    pub dv_info: DvInfo,
+    /// The (serialized) values of the partition columns of the file
+    pub partition_values: HashMap<String, String>,
+}
+
+impl ScanFile {
+    /// The exact number of rows the file contributes to the scan, if it's known without reading
+    /// the file: the `numRecords` statistic of a file without a deletion vector. Engines can e.g.
+    /// answer `SELECT COUNT(*)` of a scan without a predicate from the scan files alone, if this is
+    /// known for all of them.
+    pub fn exact_num_records(&self) -> Option<u64> {
+        match self.dv_info.has_vector() {
+            true => None,
+            false => self.stats.as_ref().map(|stats| stats.num_records),
+        }
+    }
+
+    /// The roots of the storage outside of the table at `table_root` that the file or its
+    /// deletion vector is stored in (see [`ScanFile::is_external`]), e.g. `s3://other-bucket/`.
+    pub fn external_storage(&self, table_root: &Url) -> DeltaResult<Vec<Url>> {
+        let table_storage = storage_root(table_root);
+        let file = resolve_file_path(table_root, &self.path)?;
+        let deletion_vector = match self.dv_info.deletion_vector_descriptor() {
+            Some(dv) => dv.absolute_path(table_root)?,
+            None => None,
+        };
+        let mut roots: Vec<_> = std::iter::once(file)
+            .chain(deletion_vector)
+            .map(|url| storage_root(&url))
+            .filter(|root| *root != table_storage)
+            .collect();
+        roots.dedup();
+        Ok(roots)
+    }
+
+    /// True if the file or its deletion vector is stored outside of the storage of the table at
+    /// `table_root`, i.e. its path in the log is an absolute URL in another bucket or file system.
+    /// Shallow clones reference the files of their source table like this. Engines need access to
+    /// the storage of external files (possibly with other credentials) to read them.
+    pub fn is_external(&self, table_root: &Url) -> DeltaResult<bool> {
+        Ok(!self.external_storage(table_root)?.is_empty())
+    }
+}
+
+/// The selected files of a batch of scan data (see [`crate::scan::Scan::scan_data`]). This is
+/// like [`visit_scan_files`], with the files' modification times.
+pub fn scan_files(data: &dyn EngineData, selection_vector: &[bool]) -> DeltaResult<Vec<ScanFile>> {
+    let mut files = vec![];
+    visit_scan_file_rows(
+        data,
+        selection_vector,
+        |path, size, modification_time, stats, dv_info, partition_values| {
+            files.push(ScanFile {
+                path: path.to_string(),
+                size,
+                modification_time: Some(modification_time),
+                stats,
+                dv_info,
+                partition_values,
+            })
+        },
+    )?;
+    Ok(files)
+}
+
 pub type ScanCallback<T> = fn(
     context: &mut T,
     path: &str,
@@ -128,22 +219,40 @@ pub fn visit_scan_files<T>(
     context: T,
     callback: ScanCallback<T>,
 ) -> DeltaResult<T> {
+    let mut context = context;
+    visit_scan_file_rows(
+        data,
+        selection_vector,
+        |path, size, _, stats, dv_info, partition_values| {
+            callback(&mut context, path, size, stats, dv_info, partition_values)
+        },
+    )?;
+    Ok(context)
+}
+
+// Call `on_file` with the path, size, modification time, stats, deletion vector and partition
+// values of each selected file
+fn visit_scan_file_rows(
+    data: &dyn EngineData,
+    selection_vector: &[bool],
+    on_file: impl FnMut(&str, i64, i64, Option<Stats>, DvInfo, HashMap<String, String>),
+) -> DeltaResult<()> {
     let mut visitor = ScanFileVisitor {
-        callback,
+        on_file,
         selection_vector,
-        context,
     };
-    visitor.visit_rows_of(data)?;
-    Ok(visitor.context)
+    visitor.visit_rows_of(data)
 }
 
 // add some visitor magic for engines
-struct ScanFileVisitor<'a, T> {
-    callback: ScanCallback<T>,
+struct ScanFileVisitor<'a, F> {
+    on_file: F,
     selection_vector: &'a [bool],
-    context: T,
 }
-impl<T> RowVisitor for ScanFileVisitor<'_, T> {
+impl<F> RowVisitor for ScanFileVisitor<'_, F>
+where
+    F: FnMut(&str, i64, i64, Option<Stats>, DvInfo, HashMap<String, String>),
+{
     fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
         static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
             LazyLock::new(|| SCAN_ROW_SCHEMA.leaves(None));
@@ -165,6 +274,7 @@ impl<T> RowVisitor for ScanFileVisitor<'_, T> {
             // Since path column is required, use it to detect presence of an Add action
             if let Some(path) = getters[0].get_opt(row_index, "scanFile.path")? {
                 let size = getters[1].get(row_index, "scanFile.size")?;
+                let modification_time = getters[2].get(row_index, "scanFile.modificationTime")?;
                 let stats: Option<String> = getters[3].get_opt(row_index, "scanFile.stats")?;
                 let stats: Option<Stats> =
                     stats.and_then(|json| match serde_json::from_str(json.as_str()) {
@@ -182,10 +292,10 @@ impl<T> RowVisitor for ScanFileVisitor<'_, T> {
                 let dv_info = DvInfo { deletion_vector };
                 let partition_values =
                     getters[9].get(row_index, "scanFile.fileConstantValues.partitionValues")?;
-                (self.callback)(
-                    &mut self.context,
+                (self.on_file)(
                     path,
                     size,
+                    modification_time,
                     stats,
                     dv_info,
                     partition_values,
@@ -10,7 +10,13 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 // re-export because many call sites that use schemas do not necessarily use expressions
+use crate::expressions::Expression;
 pub(crate) use crate::expressions::{column_name, ColumnName};
+use crate::table_features::{
+    collation, generation_expression, generation_expression_sql, identity_column_info,
+    rename_collated_field, type_changes, Collation, ColumnMappingMode, IdentityColumnInfo,
+    TypeChange,
+};
 use crate::utils::require;
 use crate::{DeltaResult, Error};
 
@@ -67,6 +73,8 @@ impl From<bool> for MetadataValue {
 
 #[derive(Debug)]
 pub enum ColumnMetadataKey {
+    /// The collations of the strings in a column's type, see [`Collation`]
+    Collations,
     ColumnMappingId,
     ColumnMappingPhysicalName,
     GenerationExpression,
@@ -75,11 +83,17 @@ pub enum ColumnMetadataKey {
     IdentityHighWaterMark,
     IdentityAllowExplicitInsert,
     Invariants,
+    /// The field ID of a column in parquet files. Physical schemas of tables in column mapping
+    /// `id` mode carry this annotation, so that parquet readers can resolve columns by field ID.
+    ParquetFieldId,
+    /// The history of type changes of a column, see [`TypeChange`]
+    TypeChanges,
 }
 
 impl AsRef<str> for ColumnMetadataKey {
     fn as_ref(&self) -> &str {
         match self {
+            Self::Collations => "__COLLATIONS",
             Self::ColumnMappingId => "delta.columnMapping.id",
             Self::ColumnMappingPhysicalName => "delta.columnMapping.physicalName",
             Self::GenerationExpression => "delta.generationExpression",
@@ -88,6 +102,8 @@ impl AsRef<str> for ColumnMetadataKey {
             Self::IdentityStart => "delta.identity.start",
             Self::IdentityStep => "delta.identity.step",
             Self::Invariants => "delta.invariants",
+            Self::ParquetFieldId => "PARQUET:field_id",
+            Self::TypeChanges => "delta.typeChanges",
         }
     }
 }
@@ -131,6 +147,36 @@ impl StructField {
         self.metadata.get(key.as_ref())
     }
 
+    /// Get the history of type changes of this field, oldest change first, as recorded by the
+    /// type widening table feature. Returns an empty list if the field's type was never changed.
+    pub fn type_changes(&self) -> DeltaResult<Vec<TypeChange>> {
+        type_changes(self)
+    }
+
+    /// Get the parameters and state of this field, if it is an identity column.
+    pub fn identity_column_info(&self) -> DeltaResult<Option<IdentityColumnInfo>> {
+        identity_column_info(self)
+    }
+
+    /// Get the collation of this field, if it is a string field with a collation other than the
+    /// default `spark.UTF8_BINARY`. The collations of strings nested in array or map fields are not
+    /// returned.
+    pub fn collation(&self) -> DeltaResult<Option<Collation>> {
+        collation(self, self.name())
+    }
+
+    /// Get the SQL expression that computes this field, if it is a generated column.
+    pub fn generation_expression_sql(&self) -> DeltaResult<Option<&str>> {
+        generation_expression_sql(self)
+    }
+
+    /// Get the expression that computes this field, if it is a generated column. Fails with
+    /// [`Error::Unsupported`] if the SQL expression cannot be represented as a kernel expression,
+    /// e.g. because it calls a function.
+    pub fn generation_expression(&self) -> DeltaResult<Option<Expression>> {
+        generation_expression(self)
+    }
+
     /// Get the physical name for this field as it should be read from parquet.
     ///
     /// NOTE: Caller affirms that the schema was already validated by
@@ -186,24 +232,34 @@ impl StructField {
             .collect()
     }
 
-    /// Applies physical name mappings to this field
+    /// Applies physical name mappings to this field. In column mapping `id` mode, each field is
+    /// also annotated with its parquet field ID (see [`ColumnMetadataKey::ParquetFieldId`]).
     ///
     /// NOTE: Caller affirms that the schema was already validated by
     /// [`crate::table_features::validate_schema_column_mapping`], to ensure that annotations are
     /// always and only present when column mapping mode is enabled.
-    pub fn make_physical(&self) -> Self {
-        struct MakePhysical;
+    pub fn make_physical(&self, column_mapping_mode: ColumnMappingMode) -> Self {
+        struct MakePhysical(ColumnMappingMode);
         impl<'a> SchemaTransform<'a> for MakePhysical {
             fn transform_struct_field(
                 &mut self,
                 field: &'a StructField,
             ) -> Option<Cow<'a, StructField>> {
                 let field = self.recurse_into_struct_field(field)?;
-                Some(Cow::Owned(field.with_name(field.physical_name())))
+                let mut field = rename_collated_field(&field, field.physical_name());
+                if self.0 == ColumnMappingMode::Id {
+                    if let Some(id) = field.get_config_value(&ColumnMetadataKey::ColumnMappingId) {
+                        let id = id.clone();
+                        field
+                            .metadata
+                            .insert(ColumnMetadataKey::ParquetFieldId.as_ref().into(), id);
+                    }
+                }
+                Some(Cow::Owned(field))
             }
         }
         // NOTE: unwrap is safe because the transformer is incapable of returning None
-        MakePhysical
+        MakePhysical(column_mapping_mode)
             .transform_struct_field(self)
             .unwrap()
             .into_owned()
@@ -268,6 +324,19 @@ impl StructType {
         self.fields.values()
     }
 
+    /// Get the name and generation expression of all generated columns of this struct, in schema
+    /// order. See [`StructField::generation_expression`]. The expression of each column is parsed
+    /// on its own, so that callers can use the supported expressions even if others (e.g. ones
+    /// calling functions like `year(ts)`) fail with [`Error::Unsupported`].
+    pub fn generated_columns(&self) -> Vec<(&str, DeltaResult<Expression>)> {
+        self.fields()
+            .filter_map(|field| {
+                let expr = field.generation_expression().transpose()?;
+                Some((field.name().as_str(), expr))
+            })
+            .collect()
+    }
+
     /// Extracts the name and type of all leaf columns, in schema order. Caller should pass Some
     /// `own_name` if this schema is embedded in a larger struct (e.g. `add.*`) and None if the
     /// schema is a top-level result (e.g. `*`).
@@ -528,6 +597,36 @@ pub enum DataType {
     /// A map stores an arbitrary length collection of key-value pairs
     /// with a single keyType and a single valueType
     Map(Box<MapType>),
+    /// The variant type stores semi-structured data. Its values are physically represented as a
+    /// struct of binary `metadata` and `value` fields (see [`DataType::unshredded_variant`]), and
+    /// it is serialized in the table schema simply as `"variant"`.
+    #[serde(
+        serialize_with = "serialize_variant",
+        deserialize_with = "deserialize_variant"
+    )]
+    Variant(Box<StructType>),
+}
+
+fn serialize_variant<S: serde::Serializer>(
+    _: &StructType,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str("variant")
+}
+
+fn deserialize_variant<'de, D>(deserializer: D) -> Result<Box<StructType>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let str_value = String::deserialize(deserializer)?;
+    require!(
+        str_value == "variant",
+        serde::de::Error::custom(format!("Invalid variant: {}", str_value))
+    );
+    match DataType::unshredded_variant() {
+        DataType::Variant(stype) => Ok(stype),
+        _ => unreachable!(),
+    }
 }
 
 impl From<PrimitiveType> for DataType {
@@ -596,6 +695,15 @@ impl DataType {
         Ok(StructType::try_new(fields)?.into())
     }
 
+    /// The variant type, physically represented as a struct of non-null binary `metadata` and
+    /// `value` fields. See <https://github.com/apache/parquet-format/blob/master/VariantEncoding.md>
+    pub fn unshredded_variant() -> Self {
+        DataType::Variant(Box::new(StructType::new([
+            StructField::new("metadata", DataType::BINARY, false),
+            StructField::new("value", DataType::BINARY, false),
+        ])))
+    }
+
     pub fn as_primitive_opt(&self) -> Option<&PrimitiveType> {
         match self {
             DataType::Primitive(ptype) => Some(ptype),
@@ -620,6 +728,7 @@ impl Display for DataType {
                 write!(f, ">")
             }
             DataType::Map(m) => write!(f, "map<{}, {}>", m.key_type, m.value_type),
+            DataType::Variant(_) => write!(f, "variant"),
         }
     }
 }
@@ -690,6 +799,13 @@ pub trait SchemaTransform<'a> {
         self.transform(etype)
     }
 
+    /// Called for each variant encountered during the schema traversal. Implementations can call
+    /// [`Self::recurse_into_struct`] if they wish to recursively transform the fields of the
+    /// variant's physical struct representation.
+    fn transform_variant(&mut self, stype: &'a StructType) -> Option<Cow<'a, StructType>> {
+        self.recurse_into_struct(stype)
+    }
+
     /// General entry point for a recursive traversal over any data type. Also invoked internally to
     /// dispatch on nested data types encountered during the traversal.
     fn transform(&mut self, data_type: &'a DataType) -> Option<Cow<'a, DataType>> {
@@ -711,6 +827,11 @@ pub trait SchemaTransform<'a> {
             Array(atype) => apply_transform!(transform_array, atype),
             Struct(stype) => apply_transform!(transform_struct, stype),
             Map(mtype) => apply_transform!(transform_map, mtype),
+            Variant(stype) => match self.transform_variant(stype) {
+                Some(Borrowed(_)) => Some(Borrowed(data_type)),
+                Some(Owned(inner)) => Some(Owned(Variant(Box::new(inner)))),
+                None => None,
+            },
         }
     }
 
@@ -990,6 +1111,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_roundtrip_variant() {
+        let data = r#"
+        {
+            "name": "v",
+            "type": "variant",
+            "nullable": true,
+            "metadata": {}
+        }
+        "#;
+        let field: StructField = serde_json::from_str(data).unwrap();
+        assert_eq!(field.data_type, DataType::unshredded_variant());
+        assert_eq!(field.data_type.to_string(), "variant");
+
+        let json_str = serde_json::to_string(&field).unwrap();
+        assert_eq!(
+            json_str,
+            r#"{"name":"v","type":"variant","nullable":true,"metadata":{}}"#
+        );
+
+        // variants can be nested like any other type
+        let data = r#"{"type":"array","elementType":"variant","containsNull":true}"#;
+        let array_type: ArrayType = serde_json::from_str(data).unwrap();
+        assert_eq!(array_type.element_type, DataType::unshredded_variant());
+    }
+
     #[test]
     fn test_field_metadata() {
         let data = r#"
@@ -1030,11 +1177,14 @@ mod tests {
             field.physical_name(),
             "col-5f422f40-de70-45b2-88ab-1d5c90e94db1"
         );
-        let physical_field = field.make_physical();
+        let physical_field = field.make_physical(ColumnMappingMode::Name);
         assert_eq!(
             physical_field.name,
             "col-5f422f40-de70-45b2-88ab-1d5c90e94db1"
         );
+        assert!(physical_field
+            .get_config_value(&ColumnMetadataKey::ParquetFieldId)
+            .is_none());
         let DataType::Array(atype) = physical_field.data_type else {
             panic!("Expected an Array");
         };
@@ -1045,6 +1195,25 @@ mod tests {
             stype.fields.get_index(0).unwrap().1.name,
             "col-a7f4159c-53be-4cb0-b81a-f7e5240cfc49"
         );
+
+        // id mode additionally annotates every (nested) field with its parquet field id
+        let physical_field = field.make_physical(ColumnMappingMode::Id);
+        assert_eq!(
+            physical_field.get_config_value(&ColumnMetadataKey::ParquetFieldId),
+            Some(&MetadataValue::Number(4))
+        );
+        let DataType::Array(atype) = physical_field.data_type else {
+            panic!("Expected an Array");
+        };
+        let DataType::Struct(stype) = atype.element_type else {
+            panic!("Expected a Struct");
+        };
+        let (_, inner) = stype.fields.get_index(0).unwrap();
+        assert_eq!(inner.name, "col-a7f4159c-53be-4cb0-b81a-f7e5240cfc49");
+        assert_eq!(
+            inner.get_config_value(&ColumnMetadataKey::ParquetFieldId),
+            Some(&MetadataValue::Number(5))
+        );
     }
 
     #[test]
@@ -0,0 +1,235 @@
+//! Differences between the schemas of two versions of a table, see
+//! [`Snapshot::schema_changes_since`]. Streaming readers can use a [`SchemaDiff`] to detect that
+//! the schema of the table changed while they were reading it, and decide whether they can keep
+//! going (e.g. a column was added) or have to restart (e.g. a column was dropped).
+//!
+//! [`Snapshot::schema_changes_since`]: crate::snapshot::Snapshot::schema_changes_since
+
+use crate::expressions::ColumnName;
+use crate::schema::{ColumnMetadataKey, DataType, MetadataValue, Schema, StructField, StructType};
+
+/// A change to a (possibly nested) column of a table's schema. Columns are identified by their
+/// full path, in the old schema for removed columns and in the new schema otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    /// A column was added
+    Added(ColumnName),
+    /// A column was removed
+    Removed(ColumnName),
+    /// A column was renamed (or moved into a renamed struct). Renames can only be detected for
+    /// tables with column mapping enabled: otherwise, a rename looks like a removed column and an
+    /// added one.
+    Renamed { from: ColumnName, to: ColumnName },
+    /// The type of a column changed, e.g. by type widening
+    TypeChanged {
+        column: ColumnName,
+        from: DataType,
+        to: DataType,
+    },
+    /// A column became nullable (`nullable` is true) or non-nullable (`nullable` is false)
+    NullabilityChanged { column: ColumnName, nullable: bool },
+}
+
+/// The changes between an old and a new schema of a table. Changes to columns nested in structs
+/// are reported for the nested columns; changes to the element types of arrays and maps are
+/// reported as type changes of the array or map column.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    /// Compute the changes from `old` to `new`. Fields are matched by their column mapping IDs if
+    /// both have one, and by name otherwise.
+    pub fn new(old: &Schema, new: &Schema) -> Self {
+        let mut diff = Self::default();
+        diff.diff_structs(old, new, &[], &[]);
+        diff
+    }
+
+    /// All changes, with the changes to existing columns (in the order of the old schema) first,
+    /// followed by the added columns (in the order of the new schema).
+    pub fn changes(&self) -> &[SchemaChange] {
+        &self.changes
+    }
+
+    /// True if the schemas are the same (apart from field metadata and the order of fields).
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn added_columns(&self) -> impl Iterator<Item = &ColumnName> {
+        self.changes.iter().filter_map(|change| match change {
+            SchemaChange::Added(column) => Some(column),
+            _ => None,
+        })
+    }
+
+    pub fn removed_columns(&self) -> impl Iterator<Item = &ColumnName> {
+        self.changes.iter().filter_map(|change| match change {
+            SchemaChange::Removed(column) => Some(column),
+            _ => None,
+        })
+    }
+
+    pub fn renamed_columns(&self) -> impl Iterator<Item = (&ColumnName, &ColumnName)> {
+        self.changes.iter().filter_map(|change| match change {
+            SchemaChange::Renamed { from, to } => Some((from, to)),
+            _ => None,
+        })
+    }
+
+    fn diff_structs(
+        &mut self,
+        old: &StructType,
+        new: &StructType,
+        old_path: &[String],
+        new_path: &[String],
+    ) {
+        for old_field in old.fields() {
+            let old_column = column(old_path, old_field);
+            let Some(new_field) = new.fields().find(|field| same_field(old_field, field)) else {
+                self.changes.push(SchemaChange::Removed(old_column));
+                continue;
+            };
+            let new_column = column(new_path, new_field);
+            if old_column != new_column {
+                self.changes.push(SchemaChange::Renamed {
+                    from: old_column.clone(),
+                    to: new_column.clone(),
+                });
+            }
+            match (old_field.data_type(), new_field.data_type()) {
+                (DataType::Struct(old_struct), DataType::Struct(new_struct)) => {
+                    self.diff_structs(old_struct, new_struct, &old_column, &new_column)
+                }
+                (old_type, new_type) if old_type != new_type => {
+                    self.changes.push(SchemaChange::TypeChanged {
+                        column: new_column.clone(),
+                        from: old_type.clone(),
+                        to: new_type.clone(),
+                    })
+                }
+                _ => {}
+            }
+            if old_field.is_nullable() != new_field.is_nullable() {
+                self.changes.push(SchemaChange::NullabilityChanged {
+                    column: new_column,
+                    nullable: new_field.is_nullable(),
+                });
+            }
+        }
+        for new_field in new.fields() {
+            if !old.fields().any(|field| same_field(field, new_field)) {
+                self.changes
+                    .push(SchemaChange::Added(column(new_path, new_field)));
+            }
+        }
+    }
+}
+
+fn column(path: &[String], field: &StructField) -> ColumnName {
+    ColumnName::new(path.iter().chain([field.name()]))
+}
+
+fn column_mapping_id(field: &StructField) -> Option<&MetadataValue> {
+    field.get_config_value(&ColumnMetadataKey::ColumnMappingId)
+}
+
+// Two fields of a struct are the same field if they have the same column mapping ID, or the same
+// name if either has no ID
+fn same_field(old: &StructField, new: &StructField) -> bool {
+    match (column_mapping_id(old), column_mapping_id(new)) {
+        (Some(old_id), Some(new_id)) => old_id == new_id,
+        _ => old.name() == new.name(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, data_type: impl Into<DataType>, id: i32) -> StructField {
+        StructField::new(name, data_type, true)
+            .with_metadata([(ColumnMetadataKey::ColumnMappingId.as_ref(), id)])
+    }
+
+    #[test]
+    fn test_schema_diff_without_column_mapping() {
+        let old = StructType::new([
+            StructField::new("a", DataType::INTEGER, true),
+            StructField::new("b", DataType::STRING, false),
+            StructField::new("c", DataType::LONG, true),
+        ]);
+        let new = StructType::new([
+            StructField::new("a", DataType::LONG, true),
+            StructField::new("b", DataType::STRING, true),
+            StructField::new("d", DataType::LONG, true),
+        ]);
+        let diff = SchemaDiff::new(&old, &new);
+        assert_eq!(
+            diff.changes(),
+            &[
+                SchemaChange::TypeChanged {
+                    column: ColumnName::new(["a"]),
+                    from: DataType::INTEGER,
+                    to: DataType::LONG,
+                },
+                SchemaChange::NullabilityChanged {
+                    column: ColumnName::new(["b"]),
+                    nullable: true,
+                },
+                SchemaChange::Removed(ColumnName::new(["c"])),
+                SchemaChange::Added(ColumnName::new(["d"])),
+            ]
+        );
+        assert!(SchemaDiff::new(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_schema_diff_with_column_mapping() {
+        let old = StructType::new([
+            field("a", DataType::INTEGER, 1),
+            field(
+                "s",
+                DataType::struct_type([
+                    field("x", DataType::INTEGER, 3),
+                    field("y", DataType::STRING, 4),
+                ]),
+                2,
+            ),
+        ]);
+        let new = StructType::new([
+            field(
+                "t",
+                DataType::struct_type([
+                    field("z", DataType::INTEGER, 3),
+                    field("w", DataType::STRING, 5),
+                ]),
+                2,
+            ),
+            field("a", DataType::INTEGER, 6),
+        ]);
+        let diff = SchemaDiff::new(&old, &new);
+        assert_eq!(
+            diff.changes(),
+            &[
+                SchemaChange::Removed(ColumnName::new(["a"])),
+                SchemaChange::Renamed {
+                    from: ColumnName::new(["s"]),
+                    to: ColumnName::new(["t"]),
+                },
+                SchemaChange::Renamed {
+                    from: ColumnName::new(["s", "x"]),
+                    to: ColumnName::new(["t", "z"]),
+                },
+                SchemaChange::Removed(ColumnName::new(["s", "y"])),
+                SchemaChange::Added(ColumnName::new(["t", "w"])),
+                SchemaChange::Added(ColumnName::new(["a"])),
+            ]
+        );
+        assert_eq!(diff.added_columns().count(), 2);
+        assert_eq!(diff.removed_columns().count(), 2);
+        assert_eq!(diff.renamed_columns().count(), 2);
+    }
+}
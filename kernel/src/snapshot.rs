@@ -1,20 +1,38 @@
 //! In-memory representation of snapshots of tables (snapshot is a table at given point in time, it
 //! has schema etc.)
 
+use bytes::Bytes;
+use itertools::Itertools;
+use md5::{Digest, Md5};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tracing::{debug, warn};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tracing::{debug, field, info_span, warn};
 use url::Url;
 
+use crate::actions::domain_metadata::domain_metadata_configuration;
 use crate::actions::{Metadata, Protocol};
-use crate::log_segment::LogSegment;
+use crate::checkpoint::{write_checkpoint, CheckpointWriteResult};
+use crate::expressions::Scalar;
+use crate::log_segment::{complete_checkpoint_parts, list_commit_files, LogSegment};
+use crate::path::ParsedLogPath;
 use crate::scan::ScanBuilder;
-use crate::schema::Schema;
+use crate::schema::{ColumnName, DataType, Schema, SchemaRef, StructField, StructType};
+use crate::schema_diff::SchemaDiff;
 use crate::table_features::{
-    column_mapping_mode, validate_schema_column_mapping, ColumnMappingMode,
+    check_constraints, clustering_columns, column_mapping_mode, commit_timestamp,
+    iceberg_compat_version, in_commit_timestamps_enabled, validate_schema_column_mapping,
+    validate_timestamp_ntz_feature_support, validate_type_changes,
+    validate_variant_type_feature_support, CheckConstraint, ColumnMappingMode,
+    IcebergCompatVersion,
 };
 use crate::table_properties::TableProperties;
-use crate::{DeltaResult, Engine, Error, FileSystemClient, Version};
+use crate::transaction::Transaction;
+use crate::utils::{current_time, require};
+use crate::vacuum::{plan_vacuum, VacuumPlan};
+use crate::{
+    DeltaResult, Engine, Error, ErrorContext, ErrorKind, FileMeta, FileSystemClient, Version,
+};
 
 const LAST_CHECKPOINT_FILE_NAME: &str = "_last_checkpoint";
 // TODO expose methods for accessing the files of a table (with file pruning).
@@ -61,6 +79,33 @@ impl Snapshot {
         engine: &dyn Engine,
         version: Option<Version>,
     ) -> DeltaResult<Self> {
+        let mut context = ErrorContext::new().with_table_root(table_root.clone());
+        if let Some(version) = version {
+            context = context.with_version(version);
+        }
+        Self::log_segment_for_version(&table_root, engine, version)
+            // try_new_from_log_segment will ensure the protocol is supported
+            .and_then(|log_segment| Self::try_new_from_log_segment(table_root, log_segment, engine))
+            .map_err(|err| err.with_context(context))
+    }
+
+    /// List the [`LogSegment`] of the table at `table_root` for the given version (or the latest
+    /// version), using the `_last_checkpoint` hint if present.
+    pub(crate) fn log_segment_for_version(
+        table_root: &Url,
+        engine: &dyn Engine,
+        version: Option<Version>,
+    ) -> DeltaResult<LogSegment> {
+        let span = info_span!(
+            "snapshot.list",
+            table_root = %table_root,
+            version = ?version,
+            end_version = field::Empty,
+            commit_files = field::Empty,
+            checkpoint_parts = field::Empty,
+            bytes = field::Empty,
+        );
+        let _entered = span.enter();
         let fs_client = engine.get_file_system_client();
         let log_root = table_root.join("_delta_log/")?;
 
@@ -68,9 +113,11 @@ impl Snapshot {
 
         let log_segment =
             LogSegment::for_snapshot(fs_client.as_ref(), log_root, checkpoint_hint, version)?;
-
-        // try_new_from_log_segment will ensure the protocol is supported
-        Self::try_new_from_log_segment(table_root, log_segment, engine)
+        span.record("end_version", log_segment.end_version);
+        span.record("commit_files", log_segment.ascending_commit_files.len());
+        span.record("checkpoint_parts", log_segment.checkpoint_parts.len());
+        span.record("bytes", log_segment.size_in_bytes());
+        Ok(log_segment)
     }
 
     /// Create a new [`Snapshot`] instance.
@@ -78,9 +125,36 @@ impl Snapshot {
         location: Url,
         log_segment: LogSegment,
         engine: &dyn Engine,
+    ) -> DeltaResult<Self> {
+        // falls back to an earlier checkpoint (or none) if the checkpoint can't be read
+        let (log_segment, metadata, protocol) = info_span!(
+            "snapshot.replay",
+            version = log_segment.end_version,
+            commit_files = log_segment.ascending_commit_files.len(),
+            checkpoint_parts = log_segment.checkpoint_parts.len(),
+            bytes = log_segment.size_in_bytes(),
+        )
+        .in_scope(|| log_segment.read_metadata_with_fallback(engine))?;
+        Self::try_new_from_metadata(location, log_segment, metadata, protocol)
+    }
+
+    /// Create a new [`Snapshot`] instance like [`Self::try_new_from_log_segment`], but fail rather
+    /// than fall back to an earlier checkpoint if the checkpoint of `log_segment` can't be read.
+    pub(crate) fn try_new_from_log_segment_without_fallback(
+        location: Url,
+        log_segment: LogSegment,
+        engine: &dyn Engine,
     ) -> DeltaResult<Self> {
         let (metadata, protocol) = log_segment.read_metadata(engine)?;
+        Self::try_new_from_metadata(location, log_segment, metadata, protocol)
+    }
 
+    fn try_new_from_metadata(
+        location: Url,
+        log_segment: LogSegment,
+        metadata: Metadata,
+        protocol: Protocol,
+    ) -> DeltaResult<Self> {
         // important! before a read/write to the table we must check it is supported
         protocol.ensure_read_supported()?;
 
@@ -89,6 +163,9 @@ impl Snapshot {
         let table_properties = metadata.parse_table_properties();
         let column_mapping_mode = column_mapping_mode(&protocol, &table_properties);
         validate_schema_column_mapping(&schema, column_mapping_mode)?;
+        validate_timestamp_ntz_feature_support(&schema, &protocol)?;
+        validate_variant_type_feature_support(&schema, &protocol)?;
+        validate_type_changes(&schema, &protocol)?;
 
         Ok(Self {
             table_root: location,
@@ -121,6 +198,31 @@ impl Snapshot {
         &self.schema
     }
 
+    /// The changes to the table's [`Schema`] from the version of the `other` snapshot (usually an
+    /// earlier snapshot of the same table) to the version of this snapshot.
+    pub fn schema_changes_since(&self, other: &Snapshot) -> SchemaDiff {
+        SchemaDiff::new(other.schema(), self.schema())
+    }
+
+    /// Plan a VACUUM of the table: find the files in the directory of the table which are not
+    /// referenced by this snapshot, nor by any earlier version within `retention` (i.e. whose
+    /// files were removed less than `retention` ago), and which were last modified more than
+    /// `retention` ago. Hidden files and directories (e.g. the `_delta_log`) are never deleted,
+    /// but change data files are. If no `retention` is given, it's the table's
+    /// `delta.deletedFileRetentionDuration` (one week by default).
+    ///
+    /// A retention shorter than the table's retention is rejected, since it would break readers of
+    /// older versions, and concurrent writers whose files aren't committed yet. So is a snapshot
+    /// which isn't the latest snapshot of the table, since the files of later versions would be
+    /// deleted.
+    pub fn vacuum_plan(
+        &self,
+        engine: &dyn Engine,
+        retention: Option<Duration>,
+    ) -> DeltaResult<VacuumPlan> {
+        plan_vacuum(self, engine, retention, current_time())
+    }
+
     /// Table [`Metadata`] at this `Snapshot`s version.
     pub fn metadata(&self) -> &Metadata {
         &self.metadata
@@ -131,6 +233,40 @@ impl Snapshot {
         &self.protocol
     }
 
+    /// The minimum version of the Delta read protocol that a client must implement in order to
+    /// read this `Snapshot`. See [`Protocol::min_reader_version`].
+    pub fn min_reader_version(&self) -> i32 {
+        self.protocol.min_reader_version()
+    }
+
+    /// The minimum version of the Delta write protocol that a client must implement in order to
+    /// write to this `Snapshot`. See [`Protocol::min_writer_version`].
+    pub fn min_writer_version(&self) -> i32 {
+        self.protocol.min_writer_version()
+    }
+
+    /// The reader features enabled at this `Snapshot`s version, if the table uses reader version 3.
+    pub fn reader_features(&self) -> Option<&[String]> {
+        self.protocol.reader_features()
+    }
+
+    /// The writer features enabled at this `Snapshot`s version, if the table uses writer version 7.
+    pub fn writer_features(&self) -> Option<&[String]> {
+        self.protocol.writer_features()
+    }
+
+    /// Check that the kernel supports reading this `Snapshot`, i.e. its reader version and all
+    /// of its reader features. The error lists each feature the kernel does not support.
+    pub fn ensure_read_supported(&self) -> DeltaResult<()> {
+        self.protocol.ensure_read_supported()
+    }
+
+    /// Check that the kernel supports writing to this `Snapshot`, i.e. its writer version and all
+    /// of its writer features. The error lists each feature the kernel does not support.
+    pub fn ensure_write_supported(&self) -> DeltaResult<()> {
+        self.protocol.ensure_write_supported()
+    }
+
     /// Get the [`TableProperties`] for this [`Snapshot`].
     pub fn table_properties(&self) -> &TableProperties {
         &self.table_properties
@@ -143,6 +279,76 @@ impl Snapshot {
         self.column_mapping_mode
     }
 
+    /// The [CHECK constraints] of the table at this `Snapshot`s version, ordered by name. Writers
+    /// must ensure that all rows they write satisfy these constraints, e.g. using
+    /// [`CheckConstraint::find_violations`]. Fails with [`Error::Unsupported`] if a constraint
+    /// cannot be represented as a kernel expression.
+    ///
+    /// [CHECK constraints]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#check-constraints
+    pub fn check_constraints(&self) -> DeltaResult<Vec<CheckConstraint>> {
+        check_constraints(&self.metadata.configuration)
+    }
+
+    /// Get the configuration of the metadata domain `domain` at this `Snapshot`s version, or
+    /// `None` if the domain does not exist or was removed. See [`DomainMetadata`].
+    ///
+    /// [`DomainMetadata`]: crate::actions::DomainMetadata
+    pub fn domain_metadata(
+        &self,
+        domain: &str,
+        engine: &dyn Engine,
+    ) -> DeltaResult<Option<String>> {
+        domain_metadata_configuration(&self.log_segment, domain, engine)
+    }
+
+    /// The logical names of the [clustering columns] of the table at this `Snapshot`s version, in
+    /// clustering order, or `None` if the table is not clustered. A clustered table may have no
+    /// clustering columns.
+    ///
+    /// [clustering columns]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#clustered-table
+    pub fn clustering_columns(&self, engine: &dyn Engine) -> DeltaResult<Option<Vec<ColumnName>>> {
+        clustering_columns(&self.log_segment, &self.protocol, &self.schema, engine)
+    }
+
+    /// The version of [Iceberg compatibility] enabled at this `Snapshot`s version, if any.
+    ///
+    /// [Iceberg compatibility]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#iceberg-compatibility-v2
+    pub fn iceberg_compat_version(&self) -> Option<IcebergCompatVersion> {
+        iceberg_compat_version(&self.table_properties)
+    }
+
+    /// Whether [in-commit timestamps] are enabled at this `Snapshot`s version.
+    ///
+    /// [in-commit timestamps]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps
+    pub fn is_in_commit_timestamps_enabled(&self) -> bool {
+        in_commit_timestamps_enabled(&self.protocol, &self.table_properties)
+    }
+
+    /// The timestamp of the commit of this `Snapshot`s version, as milliseconds since the epoch.
+    /// This is the commit's in-commit timestamp if in-commit timestamps are enabled (see
+    /// [`Snapshot::is_in_commit_timestamps_enabled`]), and the modification time of the commit
+    /// file otherwise.
+    pub fn timestamp(&self, engine: &dyn Engine) -> DeltaResult<i64> {
+        let commit = match self.log_segment.ascending_commit_files.last() {
+            Some(commit) => commit.clone(),
+            // The snapshot is at a checkpoint, so its commit is not part of the log segment
+            None => list_commit_files(
+                engine.get_file_system_client().as_ref(),
+                &self.log_segment.log_root,
+                self.version(),
+                self.version(),
+            )?
+            .pop()
+            .ok_or_else(|| {
+                Error::generic(format!(
+                    "No commit file found for version {}",
+                    self.version()
+                ))
+            })?,
+        };
+        commit_timestamp(engine, &commit, &self.protocol, &self.table_properties)
+    }
+
     /// Create a [`ScanBuilder`] for an `Arc<Snapshot>`.
     pub fn scan_builder(self: Arc<Self>) -> ScanBuilder {
         ScanBuilder::new(self)
@@ -152,9 +358,91 @@ impl Snapshot {
     pub fn into_scan_builder(self) -> ScanBuilder {
         ScanBuilder::new(self)
     }
+
+    /// Create a new write [`Transaction`] for an `Arc<Snapshot>`. The transaction commits the
+    /// version after this snapshot's version, and fails with a conflict if another writer committed
+    /// that version first.
+    pub fn new_transaction(self: Arc<Self>) -> DeltaResult<Transaction> {
+        Transaction::try_new(self)
+    }
+
+    /// Write a checkpoint of this `Snapshot`'s version, followed by the `_last_checkpoint` hint
+    /// file pointing at it. The checkpoint is a single parquet file that replaces the table's log
+    /// up to this version for readers, so that they don't need to replay all of its commits. Any
+    /// existing checkpoint of this version is overwritten.
+    ///
+    /// Remove actions that are older than the table's `delta.deletedFileRetentionDuration` are not
+    /// kept in the checkpoint.
+    pub fn checkpoint(&self, engine: &dyn Engine) -> DeltaResult<()> {
+        let CheckpointWriteResult {
+            file,
+            num_actions,
+            num_add_files,
+        } = write_checkpoint(engine, self)?;
+        self.write_last_checkpoint(engine, &[file], num_actions, Some(num_add_files))
+    }
+
+    /// Write (or overwrite) the `_last_checkpoint` hint file to point at a checkpoint of this
+    /// `Snapshot`'s version. This should be called after all the checkpoint files have been
+    /// successfully written.
+    ///
+    /// # Parameters
+    ///
+    /// - `engine`: Implementation of [`Engine`] apis.
+    /// - `checkpoint_files`: All the files of the checkpoint (one for a single-part checkpoint,
+    ///   or all the parts of a multi-part checkpoint). Their sizes are recorded in the hint.
+    /// - `num_actions`: The number of actions stored in the checkpoint.
+    /// - `num_add_files`: The number of add actions stored in the checkpoint, if known.
+    pub fn write_last_checkpoint(
+        &self,
+        engine: &dyn Engine,
+        checkpoint_files: &[FileMeta],
+        num_actions: i64,
+        num_add_files: Option<i64>,
+    ) -> DeltaResult<()> {
+        let checkpoint_parts: Vec<_> = checkpoint_files
+            .iter()
+            .map(|file| ParsedLogPath::try_from(file.clone()))
+            .filter_map_ok(|path| path.filter(ParsedLogPath::is_checkpoint))
+            .try_collect()?;
+        require!(
+            checkpoint_parts.len() == checkpoint_files.len(),
+            Error::generic("Expected only checkpoint files when writing _last_checkpoint")
+        );
+        require!(
+            checkpoint_parts
+                .iter()
+                .all(|part| part.version == self.version()),
+            Error::generic(format!(
+                "Expected checkpoint files for version {} when writing _last_checkpoint",
+                self.version()
+            ))
+        );
+        require!(
+            complete_checkpoint_parts(checkpoint_parts.clone())
+                .is_some_and(|complete| complete.len() == checkpoint_parts.len()),
+            Error::generic("Expected the files of exactly one complete checkpoint")
+        );
+
+        let size_in_bytes: usize = checkpoint_files.iter().map(|file| file.size).sum();
+        let checkpoint_metadata = CheckpointMetadata {
+            version: self.version(),
+            size: num_actions,
+            parts: (checkpoint_parts.len() > 1).then_some(checkpoint_parts.len()),
+            size_in_bytes: Some(
+                size_in_bytes
+                    .try_into()
+                    .map_err(|_| Error::generic("checkpoint size in bytes exceeded i64 size"))?,
+            ),
+            num_of_add_files: num_add_files,
+            checkpoint_schema: None,
+            checksum: None,
+        };
+        write_last_checkpoint(engine, &self.log_segment.log_root, &checkpoint_metadata)
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 #[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
@@ -165,42 +453,173 @@ struct CheckpointMetadata {
     /// The number of actions that are stored in the checkpoint.
     pub(crate) size: i64,
     /// The number of fragments if the last checkpoint was written in multiple parts.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) parts: Option<usize>,
     /// The number of bytes of the checkpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) size_in_bytes: Option<i64>,
     /// The number of AddFile actions in the checkpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) num_of_add_files: Option<i64>,
     /// The schema of the checkpoint file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) checkpoint_schema: Option<Schema>,
     /// The checksum of the last checkpoint JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) checksum: Option<String>,
 }
 
+/// The schema kernel uses to write the `_last_checkpoint` file. Note that kernel never writes the
+/// (optional) `checkpointSchema` field.
+static LAST_CHECKPOINT_WRITE_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    Arc::new(StructType::new([
+        StructField::new("version", DataType::LONG, false),
+        StructField::new("size", DataType::LONG, false),
+        StructField::new("parts", DataType::LONG, true),
+        StructField::new("sizeInBytes", DataType::LONG, true),
+        StructField::new("numOfAddFiles", DataType::LONG, true),
+        StructField::new("checksum", DataType::STRING, true),
+    ]))
+});
+
+/// Compute the checksum of a `_last_checkpoint` JSON object, in the same canonical form used by
+/// Delta Spark: every leaf value is flattened into a `key=value` entry (where nested keys are
+/// joined with `+`, and keys and string values are URL-encoded and quoted), the entries are
+/// sorted and joined with `,`, and the result is MD5 hashed. The top-level `checksum` field
+/// itself is excluded.
+fn last_checkpoint_checksum(value: &serde_json::Value) -> String {
+    fn encode(s: &str) -> String {
+        format!(
+            "\"{}\"",
+            url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>()
+        )
+    }
+    fn flatten(value: &serde_json::Value, prefix: String, entries: &mut Vec<(String, String)>) {
+        use serde_json::Value;
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    if prefix.is_empty() && name == "checksum" {
+                        continue;
+                    }
+                    let key = match prefix.as_str() {
+                        "" => encode(name),
+                        prefix => format!("{prefix}+{}", encode(name)),
+                    };
+                    flatten(field, key, entries);
+                }
+            }
+            Value::Array(elements) => {
+                for (i, element) in elements.iter().enumerate() {
+                    flatten(element, format!("{prefix}+{i}"), entries);
+                }
+            }
+            Value::String(s) => entries.push((prefix, encode(s))),
+            other => entries.push((prefix, other.to_string())),
+        }
+    }
+
+    let mut entries = vec![];
+    flatten(value, String::new(), &mut entries);
+    entries.sort();
+    let canonical = entries
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .join(",");
+    format!("{:x}", Md5::digest(canonical.as_bytes()))
+}
+
+/// Parse the contents of a `_last_checkpoint` file. Returns `None` (and logs a warning) if the
+/// contents are not valid JSON, don't describe a checkpoint, or don't match their own checksum.
+pub(crate) fn parse_last_checkpoint(data: &[u8]) -> Option<CheckpointMetadata> {
+    let value: serde_json::Value = serde_json::from_slice(data)
+        .inspect_err(|e| warn!("invalid _last_checkpoint JSON: {e}"))
+        .ok()?;
+    if let Some(expected) = value.get("checksum").and_then(|checksum| checksum.as_str()) {
+        let actual = last_checkpoint_checksum(&value);
+        if actual != expected {
+            warn!("_last_checkpoint checksum mismatch: expected {expected}, computed {actual}");
+            return None;
+        }
+    }
+    serde_json::from_value(value)
+        .inspect_err(|e| warn!("invalid _last_checkpoint JSON: {e}"))
+        .ok()
+}
+
 /// Try reading the `_last_checkpoint` file.
 ///
 /// Note that we typically want to ignore a missing/invalid `_last_checkpoint` file without failing
-/// the read. Thus, the semantics of this function are to return `None` if the file is not found or
-/// is invalid JSON. Unexpected/unrecoverable errors are returned as `Err` case and are assumed to
-/// cause failure.
+/// the read. Thus, the semantics of this function are to return `None` if the file is not found, is
+/// invalid JSON, or fails checksum validation. Unexpected/unrecoverable errors are returned as
+/// `Err` case and are assumed to cause failure.
 ///
 /// TODO: java kernel retries three times before failing, should we do the same?
 fn read_last_checkpoint(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
 ) -> DeltaResult<Option<CheckpointMetadata>> {
+    let data = read_last_checkpoint_file(fs_client, log_root)?;
+    Ok(data.and_then(|data| parse_last_checkpoint(&data)))
+}
+
+/// Read the contents of the `_last_checkpoint` file, or `None` if there's none.
+pub(crate) fn read_last_checkpoint_file(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+) -> DeltaResult<Option<Bytes>> {
     let file_path = log_root.join(LAST_CHECKPOINT_FILE_NAME)?;
     match fs_client
         .read_files(vec![(file_path, None)])
         .and_then(|mut data| data.next().expect("read_files should return one file"))
     {
-        Ok(data) => Ok(serde_json::from_slice(&data)
-            .inspect_err(|e| warn!("invalid _last_checkpoint JSON: {e}"))
-            .ok()),
-        Err(Error::FileNotFound(_)) => Ok(None),
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == ErrorKind::FileNotFound => Ok(None),
         Err(err) => Err(err),
     }
 }
 
+/// Write the `_last_checkpoint` file for the given checkpoint, overwriting any existing one. The
+/// checksum is always (re)computed from the other fields of `checkpoint_metadata`.
+fn write_last_checkpoint(
+    engine: &dyn Engine,
+    log_root: &Url,
+    checkpoint_metadata: &CheckpointMetadata,
+) -> DeltaResult<()> {
+    let checksum = last_checkpoint_checksum(&serde_json::to_value(CheckpointMetadata {
+        checkpoint_schema: None,
+        checksum: None,
+        ..checkpoint_metadata.clone()
+    })?);
+    let optional_long =
+        |value: Option<i64>| value.map_or(Scalar::Null(DataType::LONG), Scalar::Long);
+    let parts = checkpoint_metadata
+        .parts
+        .map(i64::try_from)
+        .transpose()
+        .map_err(|_| Error::generic("checkpoint parts exceeded i64 size"))?;
+    let values = [
+        Scalar::Long(
+            checkpoint_metadata
+                .version
+                .try_into()
+                .map_err(|_| Error::generic("checkpoint version exceeded i64 size"))?,
+        ),
+        Scalar::Long(checkpoint_metadata.size),
+        optional_long(parts),
+        optional_long(checkpoint_metadata.size_in_bytes),
+        optional_long(checkpoint_metadata.num_of_add_files),
+        Scalar::String(checksum),
+    ];
+    let data = engine
+        .get_expression_handler()
+        .create_one(LAST_CHECKPOINT_WRITE_SCHEMA.clone(), &values)?;
+    let path = log_root.join(LAST_CHECKPOINT_FILE_NAME)?;
+    engine
+        .get_json_handler()
+        .write_json_file(&path, Box::new(std::iter::once(Ok(data))), true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,6 +637,30 @@ mod tests {
     use crate::engine::sync::SyncEngine;
     use crate::path::ParsedLogPath;
     use crate::schema::StructType;
+    use crate::schema_diff::SchemaChange;
+
+    #[test]
+    fn test_schema_changes_since() {
+        let path = std::fs::canonicalize(PathBuf::from("./tests/data/type-widening/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine = SyncEngine::new();
+        let old = Snapshot::try_new(url.clone(), &engine, Some(1)).unwrap();
+        let new = Snapshot::try_new(url, &engine, Some(2)).unwrap();
+
+        assert!(new.schema_changes_since(&new).is_empty());
+        let diff = new.schema_changes_since(&old);
+        let type_changes: Vec<_> = diff
+            .changes()
+            .iter()
+            .map(|change| match change {
+                SchemaChange::TypeChanged { column, from, to } => {
+                    (column.to_string(), from.clone(), to.clone())
+                }
+                _ => panic!("unexpected change {change:?}"),
+            })
+            .collect();
+        assert!(type_changes.contains(&("byte_long".to_string(), DataType::BYTE, DataType::LONG)));
+    }
 
     #[test]
     fn test_snapshot_read_metadata() {
@@ -255,6 +698,82 @@ mod tests {
         assert_eq!(snapshot.schema(), &expected);
     }
 
+    #[test]
+    fn test_snapshot_protocol_introspection() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+
+        let engine = SyncEngine::new();
+        let snapshot = Snapshot::try_new(url, &engine, None).unwrap();
+
+        assert_eq!(snapshot.min_reader_version(), 3);
+        assert_eq!(snapshot.min_writer_version(), 7);
+        let features = Some(&["deletionVectors".to_string()][..]);
+        assert_eq!(snapshot.reader_features(), features);
+        assert_eq!(snapshot.writer_features(), features);
+        snapshot.ensure_read_supported().unwrap();
+        snapshot.ensure_write_supported().unwrap();
+    }
+
+    #[test]
+    fn test_new_snapshot_error_context() {
+        // a table whose second commit is truncated
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir(&log_dir).unwrap();
+        std::fs::copy(
+            "./tests/data/table-with-dv-small/_delta_log/00000000000000000000.json",
+            log_dir.join("00000000000000000000.json"),
+        )
+        .unwrap();
+        std::fs::write(log_dir.join("00000000000000000001.json"), "{\"add\":{").unwrap();
+        let url = url::Url::from_directory_path(dir.path()).unwrap();
+
+        let engine = SyncEngine::new();
+        let err = Snapshot::try_new(url.clone(), &engine, None).unwrap_err();
+        let context = err.context().unwrap();
+        assert_eq!(context.table_root(), Some(&url));
+        assert_eq!(context.version(), Some(1));
+        let file = url.join("_delta_log/00000000000000000001.json").unwrap();
+        assert_eq!(context.file(), Some(&file));
+        assert!(err.to_string().contains(file.as_str()), "{err}");
+    }
+
+    #[test]
+    fn test_snapshot_with_corrupt_checkpoint() {
+        let source = std::fs::canonicalize(PathBuf::from(
+            "./tests/data/with_checkpoint_no_last_checkpoint/_delta_log/",
+        ))
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir(&log_dir).unwrap();
+        for entry in std::fs::read_dir(source).unwrap() {
+            let path = entry.unwrap().path();
+            std::fs::copy(&path, log_dir.join(path.file_name().unwrap())).unwrap();
+        }
+        let checkpoint = log_dir.join("00000000000000000002.checkpoint.parquet");
+        std::fs::write(checkpoint, "not a parquet file").unwrap();
+        let url = Url::from_directory_path(dir.path()).unwrap();
+        let engine = SyncEngine::new();
+
+        // the snapshot is replayed from the commits instead
+        let snapshot = Snapshot::try_new(url.clone(), &engine, None).unwrap();
+        assert_eq!(snapshot.version(), 3);
+        assert!(snapshot.log_segment.checkpoint_parts.is_empty());
+        assert_eq!(snapshot.log_segment.ascending_commit_files.len(), 4);
+        assert_eq!(
+            snapshot.metadata().id,
+            "84b09beb-329c-4b5e-b493-f58c6c78b8fd"
+        );
+
+        // ... which isn't possible once the commits before the checkpoint are gone
+        std::fs::remove_file(log_dir.join("00000000000000000000.json")).unwrap();
+        let err = Snapshot::try_new(url, &engine, None).unwrap_err();
+        assert!(err.to_string().contains("checkpoint.parquet"), "{err}");
+    }
+
     #[test]
     fn test_read_table_with_last_checkpoint() {
         let path = std::fs::canonicalize(PathBuf::from(
@@ -317,6 +836,147 @@ mod tests {
         assert!(invalid.is_none())
     }
 
+    #[test]
+    fn test_read_last_checkpoint_with_checksum() {
+        let mut value = serde_json::json!({
+            "version": 2,
+            "size": 8,
+            "parts": 2,
+            "sizeInBytes": 21857,
+            "numOfAddFiles": 3,
+        });
+        let checksum = last_checkpoint_checksum(&value);
+        value["checksum"] = checksum.clone().into();
+        let data = serde_json::to_vec(&value).unwrap();
+        let cp = parse_last_checkpoint(&data).expect("valid checksum");
+        assert_eq!(cp.version, 2);
+        assert_eq!(cp.parts, Some(2));
+        assert_eq!(cp.size_in_bytes, Some(21857));
+
+        // the checksum is independent of field order and ignores the checksum field itself
+        let reordered = serde_json::json!({
+            "checksum": "ignored",
+            "numOfAddFiles": 3,
+            "sizeInBytes": 21857,
+            "parts": 2,
+            "size": 8,
+            "version": 2,
+        });
+        assert_eq!(last_checkpoint_checksum(&reordered), checksum);
+
+        // a hint whose contents don't match its checksum is ignored
+        value["sizeInBytes"] = 12345.into();
+        let data = serde_json::to_vec(&value).unwrap();
+        assert!(parse_last_checkpoint(&data).is_none());
+
+        // so is a hint that doesn't describe a checkpoint
+        assert!(parse_last_checkpoint(br#"{"size":8}"#).is_none());
+    }
+
+    #[test]
+    fn test_read_last_checkpoint_with_checksum_mismatch() {
+        let store = Arc::new(InMemory::new());
+        let mut value = serde_json::json!({"version": 1, "size": 8, "sizeInBytes": 21857});
+        value["checksum"] = last_checkpoint_checksum(&value).into();
+        let valid = serde_json::to_vec(&value).unwrap();
+        value["version"] = 2.into();
+        let mismatched = serde_json::to_vec(&value).unwrap();
+        tokio::runtime::Runtime::new()
+            .expect("create tokio runtime")
+            .block_on(async {
+                let path = Path::from("valid/_last_checkpoint");
+                store.put(&path, valid.into()).await.unwrap();
+                let path = Path::from("mismatched/_last_checkpoint");
+                store.put(&path, mismatched.into()).await.unwrap();
+            });
+        let client = ObjectStoreFileSystemClient::new(
+            store,
+            false, // don't have ordered listing
+            Path::from("/"),
+            Arc::new(TokioBackgroundExecutor::new()),
+        );
+
+        let url = Url::parse("memory:///valid/").unwrap();
+        let cp = read_last_checkpoint(&client, &url).unwrap();
+        assert_eq!(cp.map(|cp| cp.version), Some(1));
+        // a hint whose checksum doesn't match is rejected, as if there were none
+        let url = Url::parse("memory:///mismatched/").unwrap();
+        assert!(read_last_checkpoint(&client, &url).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_write_last_checkpoint() {
+        let source = std::fs::canonicalize(PathBuf::from(
+            "./tests/data/with_checkpoint_no_last_checkpoint/",
+        ))
+        .unwrap();
+        let table_dir = tempfile::tempdir().unwrap();
+        let log_dir = table_dir.path().join("_delta_log");
+        std::fs::create_dir(&log_dir).unwrap();
+        for entry in std::fs::read_dir(source.join("_delta_log")).unwrap() {
+            let entry = entry.unwrap();
+            std::fs::copy(entry.path(), log_dir.join(entry.file_name())).unwrap();
+        }
+        let table_root = Url::from_directory_path(table_dir.path()).unwrap();
+        let log_root = table_root.join("_delta_log/").unwrap();
+        let engine = SyncEngine::new();
+        let fs_client = engine.get_file_system_client();
+
+        let checkpoint_file = |version: Version| {
+            let location = log_root
+                .join(&format!("{version:020}.checkpoint.parquet"))
+                .unwrap();
+            let size = std::fs::metadata(location.to_file_path().unwrap())
+                .unwrap()
+                .len();
+            FileMeta::new(location, 0, size as usize)
+        };
+
+        // only checkpoint files of the snapshot's version are accepted
+        let snapshot = Snapshot::try_new(table_root.clone(), &engine, Some(3)).unwrap();
+        assert!(snapshot
+            .write_last_checkpoint(&engine, &[checkpoint_file(2)], 10, None)
+            .is_err());
+        let commit = FileMeta::new(log_root.join("00000000000000000003.json").unwrap(), 0, 1);
+        assert!(snapshot
+            .write_last_checkpoint(&engine, &[commit], 10, None)
+            .is_err());
+        assert!(read_last_checkpoint(fs_client.as_ref(), &log_root)
+            .unwrap()
+            .is_none());
+
+        let snapshot = Snapshot::try_new(table_root.clone(), &engine, Some(2)).unwrap();
+        let file = checkpoint_file(2);
+        let size = file.size;
+        snapshot
+            .write_last_checkpoint(&engine, &[file], 10, Some(2))
+            .unwrap();
+        let cp = read_last_checkpoint(fs_client.as_ref(), &log_root)
+            .unwrap()
+            .expect("_last_checkpoint should be written with a valid checksum");
+        assert_eq!(cp.version, 2);
+        assert_eq!(cp.size, 10);
+        assert_eq!(cp.parts, None);
+        assert_eq!(cp.size_in_bytes, Some(size as i64));
+        assert_eq!(cp.num_of_add_files, Some(2));
+        assert!(cp.checksum.is_some());
+
+        // writing again overwrites the existing hint
+        snapshot
+            .write_last_checkpoint(&engine, &[checkpoint_file(2)], 11, None)
+            .unwrap();
+        let cp = read_last_checkpoint(fs_client.as_ref(), &log_root)
+            .unwrap()
+            .unwrap();
+        assert_eq!(cp.size, 11);
+        assert_eq!(cp.num_of_add_files, None);
+
+        // and the hint is used to build new snapshots
+        let snapshot = Snapshot::try_new(table_root, &engine, None).unwrap();
+        assert_eq!(snapshot.version(), 3);
+        assert_eq!(snapshot.log_segment.checkpoint_parts[0].version, 2);
+    }
+
     #[test_log::test]
     fn test_read_table_with_checkpoint() {
         let path = std::fs::canonicalize(PathBuf::from(
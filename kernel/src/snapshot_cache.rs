@@ -0,0 +1,190 @@
+//! An opt-in cache of [`Snapshot`]s, keyed by table root and version. Interactive engines which
+//! issue many queries against the same table versions can use a [`SnapshotCache`] (see
+//! [`Table::with_snapshot_cache`]) to avoid repeatedly listing the log, reading checkpoints and
+//! replaying the log for protocol and metadata.
+//!
+//! [`Table::with_snapshot_cache`]: crate::Table::with_snapshot_cache
+
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZero;
+use std::sync::{Arc, Mutex};
+
+use tracing::debug;
+use url::Url;
+
+use crate::snapshot::Snapshot;
+use crate::{DeltaResult, Engine, Version};
+
+type CacheKey = (Url, Version);
+
+/// A thread-safe, bounded cache of [`Snapshot`]s keyed by (table root, version). When the cache
+/// is full, the least recently used snapshot is evicted. A single cache may be shared by several
+/// [`Table`]s.
+///
+/// Requesting the latest version of a table still lists the `_delta_log` to find out what the
+/// latest version is, but the snapshot itself is reused if that version is already cached.
+///
+/// [`Table`]: crate::Table
+pub struct SnapshotCache {
+    capacity: NonZero<usize>,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    snapshots: HashMap<CacheKey, Arc<Snapshot>>,
+    /// Keys of `snapshots`, least recently used first
+    recency: VecDeque<CacheKey>,
+}
+
+impl CacheInner {
+    fn get(&mut self, key: &CacheKey) -> Option<Arc<Snapshot>> {
+        let snapshot = self.snapshots.get(key)?.clone();
+        self.touch(key);
+        Some(snapshot)
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(pos) = self.recency.iter().position(|k| k == key) {
+            if let Some(key) = self.recency.remove(pos) {
+                self.recency.push_back(key);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for SnapshotCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnapshotCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl SnapshotCache {
+    /// Create a new, empty cache which holds at most `capacity` snapshots.
+    pub fn new(capacity: NonZero<usize>) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// The maximum number of snapshots this cache holds.
+    pub fn capacity(&self) -> NonZero<usize> {
+        self.capacity
+    }
+
+    /// The number of snapshots currently cached.
+    pub fn len(&self) -> usize {
+        self.lock().snapshots.len()
+    }
+
+    /// True if no snapshots are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached snapshots.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.snapshots.clear();
+        inner.recency.clear();
+    }
+
+    /// Get the cached snapshot of the table at `table_root` for `version`, if any.
+    pub fn get(&self, table_root: &Url, version: Version) -> Option<Arc<Snapshot>> {
+        self.lock().get(&(table_root.clone(), version))
+    }
+
+    /// Add a snapshot to the cache, evicting the least recently used snapshot if the cache is full.
+    /// Returns the cached snapshot for the same table root and version, which is the given snapshot
+    /// unless one was already cached.
+    pub fn insert(&self, snapshot: Arc<Snapshot>) -> Arc<Snapshot> {
+        let key = (snapshot.table_root().clone(), snapshot.version());
+        let mut inner = self.lock();
+        if let Some(existing) = inner.get(&key) {
+            return existing;
+        }
+        while inner.snapshots.len() >= self.capacity.get() {
+            let Some(evicted) = inner.recency.pop_front() else {
+                break;
+            };
+            debug!("Evicting snapshot {evicted:?} from snapshot cache");
+            inner.snapshots.remove(&evicted);
+        }
+        inner.snapshots.insert(key.clone(), snapshot.clone());
+        inner.recency.push_back(key);
+        snapshot
+    }
+
+    /// Get the snapshot of the table at `table_root` for `version` (or the latest version) from
+    /// the cache, creating and caching it if needed.
+    pub fn get_or_create(
+        &self,
+        table_root: &Url,
+        engine: &dyn Engine,
+        version: Option<Version>,
+    ) -> DeltaResult<Arc<Snapshot>> {
+        if let Some(snapshot) = version.and_then(|version| self.get(table_root, version)) {
+            return Ok(snapshot);
+        }
+        let log_segment = Snapshot::log_segment_for_version(table_root, engine, version)?;
+        if let Some(snapshot) = self.get(table_root, log_segment.end_version) {
+            return Ok(snapshot);
+        }
+        let snapshot = Snapshot::try_new_from_log_segment(table_root.clone(), log_segment, engine)?;
+        Ok(self.insert(Arc::new(snapshot)))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheInner> {
+        // The cache is always left in a consistent state, so it's fine to ignore poisoning
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+
+    fn table_root(path: &str) -> Url {
+        let path = std::fs::canonicalize(PathBuf::from(path)).unwrap();
+        Url::from_directory_path(path).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_cache() {
+        let engine = SyncEngine::new();
+        let cache = SnapshotCache::new(NonZero::new(2).unwrap());
+        let url = table_root("./tests/data/table-with-dv-small/");
+
+        let latest = cache.get_or_create(&url, &engine, None).unwrap();
+        assert_eq!(latest.version(), 1);
+        assert_eq!(cache.len(), 1);
+
+        // the latest version resolves to the cached snapshot, as does asking for it explicitly
+        let again = cache.get_or_create(&url, &engine, None).unwrap();
+        assert!(Arc::ptr_eq(&latest, &again));
+        let again = cache.get_or_create(&url, &engine, Some(1)).unwrap();
+        assert!(Arc::ptr_eq(&latest, &again));
+
+        let v0 = cache.get_or_create(&url, &engine, Some(0)).unwrap();
+        assert_eq!(v0.version(), 0);
+        assert_eq!(cache.len(), 2);
+
+        // touch v1 so that v0 is the least recently used snapshot, then overflow the cache
+        assert!(cache.get(&url, 1).is_some());
+        let other_url = table_root("./tests/data/basic_partitioned/");
+        cache.get_or_create(&other_url, &engine, None).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&url, 0).is_none());
+        assert!(cache.get(&url, 1).is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
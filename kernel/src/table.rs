@@ -2,14 +2,29 @@
 //! the different versions
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use url::Url;
 
+#[cfg(feature = "default-engine")]
+use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+#[cfg(feature = "default-engine")]
+use crate::engine::default::storage::{parse_url_opts, validate_storage_options};
+#[cfg(feature = "default-engine")]
+use crate::engine::default::{DefaultEngine, DefaultEngineOptions};
+use crate::log_segment::{earliest_version, list_commit_files};
+use crate::path::{storage_root, ParsedLogPath};
+use crate::schema::SchemaRef;
 use crate::snapshot::Snapshot;
+use crate::snapshot_cache::SnapshotCache;
 use crate::table_changes::TableChanges;
-use crate::transaction::Transaction;
+use crate::table_features::{commit_timestamp, has_in_commit_timestamp};
+use crate::transaction::{create_table, Transaction};
+use crate::utils::require;
+use crate::verify::{verify_table, VerificationReport};
 use crate::{DeltaResult, Engine, Error, Version};
 
 /// In-memory representation of a Delta table, which acts as an immutable root entity for reading
@@ -17,12 +32,14 @@ use crate::{DeltaResult, Engine, Error, Version};
 #[derive(Clone)]
 pub struct Table {
     location: Url,
+    snapshot_cache: Option<Arc<SnapshotCache>>,
 }
 
 impl std::fmt::Debug for Table {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         f.debug_struct("Table")
             .field("location", &self.location)
+            .field("snapshot_cache", &self.snapshot_cache)
             .finish()
     }
 }
@@ -30,7 +47,23 @@ impl std::fmt::Debug for Table {
 impl Table {
     /// Create a new Delta table with the given parameters
     pub fn new(location: Url) -> Self {
-        Self { location }
+        Self {
+            location,
+            snapshot_cache: None,
+        }
+    }
+
+    /// Create a [`TableBuilder`], which builds the table at `location` along with the engine to
+    /// access it.
+    pub fn builder(location: Url) -> TableBuilder {
+        TableBuilder::new(location)
+    }
+
+    /// Use the given [`SnapshotCache`] for [`Table::cached_snapshot`]. The cache may be shared
+    /// with other tables.
+    pub fn with_snapshot_cache(mut self, cache: Arc<SnapshotCache>) -> Self {
+        self.snapshot_cache = Some(cache);
+        self
     }
 
     /// Try to create a new table from a string uri. This will do it's best to handle things like
@@ -81,6 +114,131 @@ impl Table {
         Snapshot::try_new(self.location.clone(), engine, version)
     }
 
+    /// Get the latest version of the table, by listing its `_delta_log`.
+    pub fn get_latest_version(&self, engine: &dyn Engine) -> DeltaResult<Version> {
+        Snapshot::log_segment_for_version(&self.location, engine, None)
+            .map(|log_segment| log_segment.end_version)
+    }
+
+    /// Get the earliest version of the table that a [`Snapshot`] can be created for. This is
+    /// version 0 unless the log files of older versions were cleaned up, in which case it's the
+    /// version of the earliest checkpoint in the `_delta_log`.
+    pub fn get_earliest_version(&self, engine: &dyn Engine) -> DeltaResult<Version> {
+        let log_root = self.location.join("_delta_log/")?;
+        earliest_version(engine.get_file_system_client().as_ref(), &log_root)
+    }
+
+    /// Check the consistency of the latest version of the table: that the data files and deletion
+    /// vectors it references exist in storage, that its `_last_checkpoint` file is valid, and that
+    /// its checkpoint (if any) can be read and matches a replay of its commits. Engines can surface
+    /// the [`VerificationReport`] as an FSCK command. This reads the whole log of the table, and
+    /// the metadata of each of its live files.
+    pub fn verify(&self, engine: &dyn Engine) -> DeltaResult<VerificationReport> {
+        verify_table(&self.location, engine)
+    }
+
+    /// Get a shared [`Snapshot`] of the table corresponding to `version`, reusing a previously
+    /// created snapshot from the table's [`SnapshotCache`] if possible. If the table has no
+    /// snapshot cache, this always creates a new snapshot.
+    ///
+    /// If no version is supplied, the `_delta_log` is listed to find the latest version, whose
+    /// snapshot may then be found in the cache.
+    pub fn cached_snapshot(
+        &self,
+        engine: &dyn Engine,
+        version: Option<Version>,
+    ) -> DeltaResult<Arc<Snapshot>> {
+        match &self.snapshot_cache {
+            Some(cache) => cache.get_or_create(&self.location, engine, version),
+            None => self.snapshot(engine, version).map(Arc::new),
+        }
+    }
+
+    /// Get the latest version of the table whose commit timestamp is at or before `timestamp`
+    /// (milliseconds since the epoch), i.e. the version of the table as of that time.
+    ///
+    /// Commit timestamps are [in-commit timestamps] for versions where they are enabled, and the
+    /// modification times of the commit files otherwise. Since modification times need not
+    /// increase monotonically, each is adjusted to be at least one millisecond after the previous
+    /// commit's timestamp. Fails if `timestamp` is before the earliest available commit.
+    ///
+    /// [in-commit timestamps]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps
+    pub fn version_at_timestamp(
+        &self,
+        engine: &dyn Engine,
+        timestamp: i64,
+    ) -> DeltaResult<Version> {
+        let latest = self.snapshot(engine, None)?;
+        let (protocol, table_properties) = (latest.protocol(), latest.table_properties());
+        let commits = list_commit_files(
+            engine.get_file_system_client().as_ref(),
+            &latest.log_segment.log_root,
+            None,
+            latest.version(),
+        )?;
+        // Commits with in-commit timestamps are always the most recent ones
+        let first_with_ict = commits.partition_point(|commit| {
+            !has_in_commit_timestamp(protocol, table_properties, commit.version)
+        });
+        let (without_ict, with_ict) = commits.split_at(first_with_ict);
+        let commit_timestamp =
+            |commit: &ParsedLogPath| commit_timestamp(engine, commit, protocol, table_properties);
+
+        if let Some(first) = with_ict.first() {
+            let enablement_timestamp =
+                match table_properties.in_commit_timestamp_enablement_timestamp {
+                    Some(enablement_timestamp) => enablement_timestamp,
+                    None => commit_timestamp(first)?,
+                };
+            if timestamp >= enablement_timestamp {
+                // In-commit timestamps are monotonic, so binary search for the first commit after
+                // the timestamp. Its predecessor is the version we're looking for.
+                let (mut low, mut high) = (0, with_ict.len());
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    if commit_timestamp(&with_ict[mid])? <= timestamp {
+                        low = mid + 1;
+                    } else {
+                        high = mid;
+                    }
+                }
+                if let Some(commit) = low.checked_sub(1).map(|i| &with_ict[i]) {
+                    return Ok(commit.version);
+                }
+            }
+        }
+
+        let mut found = None;
+        let mut previous_timestamp = None;
+        for commit in without_ict {
+            let adjusted_timestamp = match previous_timestamp {
+                Some(previous) => commit.location.last_modified.max(previous + 1),
+                None => commit.location.last_modified,
+            };
+            if adjusted_timestamp > timestamp {
+                break;
+            }
+            found = Some(commit.version);
+            previous_timestamp = Some(adjusted_timestamp);
+        }
+        found.ok_or_else(|| {
+            Error::generic(format!(
+                "Timestamp {timestamp} is before the earliest available commit of the table"
+            ))
+        })
+    }
+
+    /// Create a [`Snapshot`] of the table as of `timestamp` (milliseconds since the epoch). See
+    /// [`Table::version_at_timestamp`] for how the version is found.
+    pub fn snapshot_at_timestamp(
+        &self,
+        engine: &dyn Engine,
+        timestamp: i64,
+    ) -> DeltaResult<Snapshot> {
+        let version = self.version_at_timestamp(engine, timestamp)?;
+        self.snapshot(engine, Some(version))
+    }
+
     /// Create a [`TableChanges`] to get a change data feed for the table between `start_version`,
     /// and `end_version`. If no `end_version` is supplied, the latest version will be used as the
     /// `end_version`.
@@ -102,6 +260,189 @@ impl Table {
     pub fn new_transaction(&self, engine: &dyn Engine) -> DeltaResult<Transaction> {
         Transaction::try_new(self.snapshot(engine, None)?)
     }
+
+    /// Create a new, empty table at this table's location with the given schema, partition columns
+    /// and table properties (`delta.*` properties, along with any custom ones), and return the
+    /// snapshot of its first version. Fails if a table already exists at the location.
+    ///
+    /// Table features are added to the protocol when the properties require them, e.g.
+    /// `delta.enableInCommitTimestamps = true`, and features may be requested explicitly with the
+    /// `delta.feature.<featureName> = supported` property. The kernel must be able to write to
+    /// the new table, so only features supported for writes may be enabled.
+    ///
+    /// Fails if a partition column is not a top-level column of the schema with a primitive type,
+    /// or if a `delta.*` property is unknown or has an invalid value.
+    pub fn create(
+        &self,
+        engine: &dyn Engine,
+        schema: SchemaRef,
+        partition_columns: impl IntoIterator<Item = impl Into<String>>,
+        properties: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> DeltaResult<Snapshot> {
+        create_table(
+            engine,
+            &self.location,
+            schema,
+            partition_columns.into_iter().map(Into::into).collect(),
+            properties
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+/// A builder of a [`Table`] along with the [`Engine`] to access it, see [`Table::builder`].
+///
+/// Unless an engine is given with [`TableBuilder::with_engine`], the table is accessed with a
+/// [`DefaultEngine`] (which requires the `default-engine` feature). Its object store is created for
+/// the URL of the table from the storage options of the builder (see [`parse_url_opts`]), and its
+/// handlers are configured by the engine options of the builder.
+///
+/// [`DefaultEngine`]: crate::engine::default::DefaultEngine
+/// [`parse_url_opts`]: crate::engine::default::storage::parse_url_opts
+pub struct TableBuilder {
+    location: Url,
+    storage_options: HashMap<String, String>,
+    external_storage_options: HashMap<Url, HashMap<String, String>>,
+    engine: Option<Arc<dyn Engine>>,
+    #[cfg(feature = "default-engine")]
+    engine_options: DefaultEngineOptions,
+    snapshot_cache: Option<Arc<SnapshotCache>>,
+}
+
+impl std::fmt::Debug for TableBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        // the values of storage options may be secrets (e.g. access keys)
+        let storage_options: Vec<_> = self.storage_options.keys().collect();
+        let external_storage: Vec<_> = self.external_storage_options.keys().collect();
+        f.debug_struct("TableBuilder")
+            .field("location", &self.location)
+            .field("storage_options", &storage_options)
+            .field("external_storage", &external_storage)
+            .field("has_engine", &self.engine.is_some())
+            .field("snapshot_cache", &self.snapshot_cache)
+            .finish()
+    }
+}
+
+impl TableBuilder {
+    fn new(location: Url) -> Self {
+        Self {
+            location,
+            storage_options: HashMap::new(),
+            external_storage_options: HashMap::new(),
+            engine: None,
+            #[cfg(feature = "default-engine")]
+            engine_options: DefaultEngineOptions::default(),
+            snapshot_cache: None,
+        }
+    }
+
+    /// Add options which configure the object store of the default engine, e.g. `aws_region` or
+    /// `aws_access_key_id` for `s3://` tables. [`TableBuilder::build`] fails if an option is
+    /// unknown to the store for the URL of the table (see [`validate_storage_options`]).
+    ///
+    /// [`validate_storage_options`]: crate::engine::default::storage::validate_storage_options
+    pub fn with_storage_options<K, V>(mut self, options: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let options = options.into_iter().map(|(k, v)| (k.into(), v.into()));
+        self.storage_options.extend(options);
+        self
+    }
+
+    /// Add options which configure the object store of the default engine for the files of the
+    /// table stored outside of its storage, in the storage of `url` (e.g. `s3://source-bucket/`
+    /// for a shallow clone of a table in that bucket). The options are validated like
+    /// [`TableBuilder::with_storage_options`]. See [`Scan::external_storage`] for the storage the
+    /// files of a scan are in.
+    ///
+    /// [`Scan::external_storage`]: crate::scan::Scan::external_storage
+    pub fn with_external_storage_options<K, V>(
+        mut self,
+        url: &Url,
+        options: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let options = options.into_iter().map(|(k, v)| (k.into(), v.into()));
+        self.external_storage_options
+            .entry(storage_root(url))
+            .or_default()
+            .extend(options);
+        self
+    }
+
+    /// Access the table with `engine`, rather than with a default engine. Storage options can't
+    /// be given along with an engine, since they only configure the store of the default engine.
+    pub fn with_engine(mut self, engine: Arc<dyn Engine>) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    /// Configure the handlers of the default engine (e.g. retries, caching and concurrency) with
+    /// `options`, see [`DefaultEngine::new_with_options`].
+    #[cfg(feature = "default-engine")]
+    pub fn with_engine_options(mut self, options: DefaultEngineOptions) -> Self {
+        self.engine_options = options;
+        self
+    }
+
+    /// Use the given [`SnapshotCache`] for [`Table::cached_snapshot`].
+    pub fn with_snapshot_cache(mut self, cache: Arc<SnapshotCache>) -> Self {
+        self.snapshot_cache = Some(cache);
+        self
+    }
+
+    /// Build the table, and the engine to access it with.
+    pub fn build(self) -> DeltaResult<(Table, Arc<dyn Engine>)> {
+        let engine = match &self.engine {
+            Some(engine) => {
+                require!(
+                    self.storage_options.is_empty() && self.external_storage_options.is_empty(),
+                    Error::generic(
+                        "Storage options only configure the default engine, not a given engine"
+                    )
+                );
+                engine.clone()
+            }
+            None => self.default_engine()?,
+        };
+        let mut table = Table::new(self.location);
+        table.snapshot_cache = self.snapshot_cache;
+        Ok((table, engine))
+    }
+
+    #[cfg(feature = "default-engine")]
+    fn default_engine(&self) -> DeltaResult<Arc<dyn Engine>> {
+        let keys = self.storage_options.keys().map(String::as_str);
+        validate_storage_options(&self.location, keys)?;
+        let (store, table_root) = parse_url_opts(&self.location, &self.storage_options)?;
+        let mut engine_options = self.engine_options.clone().with_table_root(&self.location);
+        for (storage_root, options) in &self.external_storage_options {
+            validate_storage_options(storage_root, options.keys().map(String::as_str))?;
+            let (store, _) = parse_url_opts(storage_root, options)?;
+            engine_options = engine_options.with_external_store(storage_root, store.into());
+        }
+        Ok(Arc::new(DefaultEngine::new_with_options(
+            store.into(),
+            table_root,
+            Arc::new(TokioBackgroundExecutor::new()),
+            engine_options,
+        )))
+    }
+
+    #[cfg(not(feature = "default-engine"))]
+    fn default_engine(&self) -> DeltaResult<Arc<dyn Engine>> {
+        Err(Error::unsupported(
+            "Tables without an engine require the `default-engine` feature",
+        ))
+    }
 }
 
 #[derive(Debug)]
@@ -158,6 +499,97 @@ mod tests {
         assert_eq!(snapshot.version(), 1)
     }
 
+    #[test]
+    fn test_table_with_snapshot_cache() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine = SyncEngine::new();
+
+        let table = Table::new(url.clone());
+        let snapshot = table.cached_snapshot(&engine, Some(0)).unwrap();
+        let again = table.cached_snapshot(&engine, Some(0)).unwrap();
+        assert!(!Arc::ptr_eq(&snapshot, &again));
+
+        let cache = Arc::new(SnapshotCache::new(std::num::NonZero::new(4).unwrap()));
+        let table = Table::new(url.clone()).with_snapshot_cache(cache.clone());
+        let snapshot = table.cached_snapshot(&engine, Some(0)).unwrap();
+        let other_table = Table::new(url).with_snapshot_cache(cache.clone());
+        let again = other_table.cached_snapshot(&engine, Some(0)).unwrap();
+        assert!(Arc::ptr_eq(&snapshot, &again));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_table_builder_with_engine() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+
+        let (table, engine) = Table::builder(url.clone())
+            .with_engine(Arc::new(SyncEngine::new()))
+            .build()
+            .unwrap();
+        assert_eq!(table.location(), &url);
+        assert_eq!(table.snapshot(engine.as_ref(), None).unwrap().version(), 1);
+
+        // storage options don't configure a given engine
+        let result = Table::builder(url)
+            .with_engine(Arc::new(SyncEngine::new()))
+            .with_storage_options([("aws_region", "us-east-1")])
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "default-engine")]
+    #[test]
+    fn test_table_builder_with_default_engine() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+
+        let (table, engine) = Table::builder(url.clone())
+            .with_engine_options(DefaultEngineOptions::default().with_view_types(true))
+            .build()
+            .unwrap();
+        assert_eq!(table.snapshot(engine.as_ref(), None).unwrap().version(), 1);
+
+        // local stores take no options
+        let result = Table::builder(url)
+            .with_storage_options([("aws_region", "us-east-1")])
+            .build();
+        assert!(matches!(result, Err(err) if err.kind() == crate::ErrorKind::Unsupported));
+    }
+
+    #[cfg(feature = "cloud")]
+    #[test]
+    fn test_table_builder_validates_storage_options() {
+        let url = Url::parse("s3://bucket/table/").unwrap();
+        let builder = || {
+            Table::builder(url.clone()).with_storage_options([
+                ("aws_region", "us-east-1"),
+                ("aws_access_key_id", "key"),
+                ("aws_secret_access_key", "secret"),
+            ])
+        };
+        assert!(builder().build().is_ok());
+        let result = builder()
+            .with_storage_options([("aws_acess_key_id", "key")])
+            .build();
+        assert!(matches!(result, Err(err) if err.to_string().contains("aws_acess_key_id")));
+
+        // the options of external storage are validated for its URL
+        let source = Url::parse("s3://source-bucket/table/part.parquet").unwrap();
+        let result = builder()
+            .with_external_storage_options(&source, [("aws_region", "eu-west-1")])
+            .build();
+        assert!(result.is_ok());
+        let result = builder()
+            .with_external_storage_options(&source, [("azure_storage_account_name", "a")])
+            .build();
+        assert!(matches!(result, Err(err) if err.kind() == crate::ErrorKind::Unsupported));
+    }
+
     #[test]
     fn test_path_parsing() {
         for x in [
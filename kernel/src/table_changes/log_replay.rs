@@ -7,8 +7,8 @@ use std::sync::{Arc, LazyLock};
 use crate::actions::schemas::GetStructField;
 use crate::actions::visitors::{visit_deletion_vector_at, ProtocolVisitor};
 use crate::actions::{
-    get_log_add_schema, Add, Cdc, Metadata, Protocol, Remove, ADD_NAME, CDC_NAME, METADATA_NAME,
-    PROTOCOL_NAME, REMOVE_NAME,
+    get_log_add_schema, Add, Cdc, CommitInfo, Metadata, Protocol, Remove, ADD_NAME, CDC_NAME,
+    COMMIT_INFO_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
 };
 use crate::engine_data::{GetData, TypedGetData};
 use crate::expressions::{column_expr, column_name, ColumnName, Expression};
@@ -51,9 +51,12 @@ pub(crate) fn table_changes_action_iter(
     engine: Arc<dyn Engine>,
     commit_files: impl IntoIterator<Item = ParsedLogPath>,
     table_schema: SchemaRef,
+    partition_schema: &StructType,
     predicate: Option<ExpressionRef>,
 ) -> DeltaResult<impl Iterator<Item = DeltaResult<TableChangesScanData>>> {
-    let filter = DataSkippingFilter::new(engine.as_ref(), &table_schema, predicate).map(Arc::new);
+    let filter =
+        DataSkippingFilter::new(engine.as_ref(), &table_schema, partition_schema, predicate)
+            .map(Arc::new);
     let result = commit_files
         .into_iter()
         .map(move |commit_file| -> DeltaResult<_> {
@@ -92,6 +95,9 @@ fn add_transform_expr() -> Expression {
 ///       exists in another `add` action _within the same commit_. We store the result in `remove_dvs`.
 ///       Deletion vector resolution affects whether a remove action is selected in the second
 ///       phase, so we must perform it ahead of time in phase 1.
+///     - Find the in-commit timestamp of the commit, if it has one. This must be done in the first
+///       phase because the second phase lazily transforms engine data with an extra timestamp
+///       column. Thus, the timestamp must be known ahead of time.
 ///     - Ensure that reading is supported on any protocol updates.
 ///     - Ensure that Change Data Feed is enabled for any metadata update. See  [`TableProperties`]
 ///     - Ensure that any schema update is compatible with the provided `schema`. Currently, schema
@@ -108,12 +114,6 @@ fn add_transform_expr() -> Expression {
 ///
 /// See https://github.com/delta-io/delta/blob/master/PROTOCOL.md#deletion-vectors
 ///
-/// TODO: When the kernel supports in-commit timestamps, we will also have to inspect CommitInfo
-/// actions to find the timestamp. These are generated when incommit timestamps is enabled.
-/// This must be done in the first phase because the second phase lazily transforms engine data with
-/// an extra timestamp column. Thus, the timestamp must be known ahead of time.
-/// See https://github.com/delta-io/delta-kernel-rs/issues/559
-///
 /// 2. Scan file generation phase [`LogReplayScanner::into_scan_batches`]: This iterates over every
 ///    action in the commit, and generates [`TableChangesScanData`]. It does so by transforming the
 ///    actions using [`add_transform_expr`], and generating selection vectors with the following rules:
@@ -132,12 +132,9 @@ struct LogReplayScanner {
     remove_dvs: HashMap<String, DvInfo>,
     // The commit file that this replay scanner will operate on.
     commit_file: ParsedLogPath,
-    // The timestamp associated with this commit. This is the file modification time
-    // from the commit's [`FileMeta`].
-    //
-    //
-    // TODO when incommit timestamps are supported: If there is a [`CommitInfo`] with a timestamp
-    // generated by in-commit timestamps, that timestamp will be used instead.
+    // The timestamp associated with this commit. This is the in-commit timestamp from the
+    // commit's [`CommitInfo`] if it has one, and the file modification time from the commit's
+    // [`FileMeta`] otherwise.
     //
     // Note: This will be used once an expression is introduced to transform the engine data in
     // [`TableChangesScanData`]
@@ -179,6 +176,7 @@ impl LogReplayScanner {
         let mut remove_dvs = HashMap::default();
         let mut add_paths = HashSet::default();
         let mut has_cdc_action = false;
+        let mut in_commit_timestamp = None;
         for actions in action_iter {
             let actions = actions?;
 
@@ -186,6 +184,7 @@ impl LogReplayScanner {
                 add_paths: &mut add_paths,
                 remove_dvs: &mut remove_dvs,
                 has_cdc_action: &mut has_cdc_action,
+                in_commit_timestamp: &mut in_commit_timestamp,
                 protocol: None,
                 metadata_info: None,
             };
@@ -218,7 +217,7 @@ impl LogReplayScanner {
             remove_dvs.retain(|rm_path, _| add_paths.contains(rm_path));
         }
         Ok(LogReplayScanner {
-            timestamp: commit_file.location.last_modified,
+            timestamp: in_commit_timestamp.unwrap_or(commit_file.location.last_modified),
             commit_file,
             has_cdc_action,
             remove_dvs,
@@ -284,6 +283,7 @@ struct PreparePhaseVisitor<'a> {
     protocol: Option<Protocol>,
     metadata_info: Option<(String, HashMap<String, String>)>,
     has_cdc_action: &'a mut bool,
+    in_commit_timestamp: &'a mut Option<i64>,
     add_paths: &'a mut HashSet<String>,
     remove_dvs: &'a mut HashMap<String, DvInfo>,
 }
@@ -295,6 +295,7 @@ impl PreparePhaseVisitor<'_> {
             Option::<Cdc>::get_struct_field(CDC_NAME),
             Option::<Metadata>::get_struct_field(METADATA_NAME),
             Option::<Protocol>::get_struct_field(PROTOCOL_NAME),
+            Option::<CommitInfo>::get_struct_field(COMMIT_INFO_NAME),
         ]))
     }
 }
@@ -326,6 +327,7 @@ impl RowVisitor for PreparePhaseVisitor<'_> {
                 (INTEGER, column_name!("protocol.minWriterVersion")),
                 (string_list.clone(), column_name!("protocol.readerFeatures")),
                 (string_list, column_name!("protocol.writerFeatures")),
+                (LONG, column_name!("commitInfo.inCommitTimestamp")),
             ];
             let (types, names) = types_and_names.into_iter().unzip();
             (names, types).into()
@@ -335,7 +337,7 @@ impl RowVisitor for PreparePhaseVisitor<'_> {
 
     fn visit<'b>(&mut self, row_count: usize, getters: &[&'b dyn GetData<'b>]) -> DeltaResult<()> {
         require!(
-            getters.len() == 16,
+            getters.len() == 17,
             Error::InternalError(format!(
                 "Wrong number of PreparePhaseVisitor getters: {}",
                 getters.len()
@@ -366,6 +368,10 @@ impl RowVisitor for PreparePhaseVisitor<'_> {
                 let protocol =
                     ProtocolVisitor::visit_protocol(i, min_reader_version, &getters[12..=15])?;
                 self.protocol = Some(protocol);
+            } else if let Some(timestamp) =
+                getters[16].get_opt(i, "commitInfo.inCommitTimestamp")?
+            {
+                *self.in_commit_timestamp = Some(timestamp);
             }
         }
         Ok(())
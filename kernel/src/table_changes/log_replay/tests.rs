@@ -1,7 +1,7 @@
 use super::table_changes_action_iter;
 use super::TableChangesScanData;
 use crate::actions::deletion_vector::DeletionVectorDescriptor;
-use crate::actions::{Add, Cdc, Metadata, Protocol, Remove};
+use crate::actions::{Add, Cdc, CommitInfo, Metadata, Protocol, Remove};
 use crate::engine::sync::SyncEngine;
 use crate::expressions::Scalar;
 use crate::expressions::{column_expr, BinaryOperator};
@@ -86,8 +86,14 @@ async fn metadata_protocol() {
         .unwrap()
         .into_iter();
 
-    let scan_batches =
-        table_changes_action_iter(engine, commits, get_schema().into(), None).unwrap();
+    let scan_batches = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap();
     let sv = result_to_sv(scan_batches);
     assert_eq!(sv, &[false, false]);
 }
@@ -111,10 +117,15 @@ async fn cdf_not_enabled() {
         .unwrap()
         .into_iter();
 
-    let res: DeltaResult<Vec<_>> =
-        table_changes_action_iter(engine, commits, get_schema().into(), None)
-            .unwrap()
-            .try_collect();
+    let res: DeltaResult<Vec<_>> = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .try_collect();
 
     assert!(matches!(res, Err(Error::ChangeDataFeedUnsupported(_))));
 }
@@ -142,10 +153,15 @@ async fn unsupported_reader_feature() {
         .unwrap()
         .into_iter();
 
-    let res: DeltaResult<Vec<_>> =
-        table_changes_action_iter(engine, commits, get_schema().into(), None)
-            .unwrap()
-            .try_collect();
+    let res: DeltaResult<Vec<_>> = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .try_collect();
 
     assert!(matches!(res, Err(Error::ChangeDataFeedUnsupported(_))));
 }
@@ -173,10 +189,15 @@ async fn column_mapping_should_fail() {
         .unwrap()
         .into_iter();
 
-    let res: DeltaResult<Vec<_>> =
-        table_changes_action_iter(engine, commits, get_schema().into(), None)
-            .unwrap()
-            .try_collect();
+    let res: DeltaResult<Vec<_>> = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .try_collect();
 
     assert!(matches!(res, Err(Error::ChangeDataFeedUnsupported(_))));
 }
@@ -204,10 +225,15 @@ async fn incompatible_schemas_fail() {
             .unwrap()
             .into_iter();
 
-        let res: DeltaResult<Vec<_>> =
-            table_changes_action_iter(engine, commits, cdf_schema.into(), None)
-                .unwrap()
-                .try_collect();
+        let res: DeltaResult<Vec<_>> = table_changes_action_iter(
+            engine,
+            commits,
+            cdf_schema.into(),
+            &StructType::new([]),
+            None,
+        )
+        .unwrap()
+        .try_collect();
 
         assert!(matches!(
             res,
@@ -294,14 +320,20 @@ async fn add_remove() {
         .unwrap()
         .into_iter();
 
-    let sv = table_changes_action_iter(engine, commits, get_schema().into(), None)
-        .unwrap()
-        .flat_map(|scan_data| {
-            let scan_data = scan_data.unwrap();
-            assert_eq!(scan_data.remove_dvs, HashMap::new().into());
-            scan_data.selection_vector
-        })
-        .collect_vec();
+    let sv = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .flat_map(|scan_data| {
+        let scan_data = scan_data.unwrap();
+        assert_eq!(scan_data.remove_dvs, HashMap::new().into());
+        scan_data.selection_vector
+    })
+    .collect_vec();
 
     assert_eq!(sv, &[true, true]);
 }
@@ -344,14 +376,20 @@ async fn filter_data_change() {
         .unwrap()
         .into_iter();
 
-    let sv = table_changes_action_iter(engine, commits, get_schema().into(), None)
-        .unwrap()
-        .flat_map(|scan_data| {
-            let scan_data = scan_data.unwrap();
-            assert_eq!(scan_data.remove_dvs, HashMap::new().into());
-            scan_data.selection_vector
-        })
-        .collect_vec();
+    let sv = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .flat_map(|scan_data| {
+        let scan_data = scan_data.unwrap();
+        assert_eq!(scan_data.remove_dvs, HashMap::new().into());
+        scan_data.selection_vector
+    })
+    .collect_vec();
 
     assert_eq!(sv, &[false; 5]);
 }
@@ -390,14 +428,20 @@ async fn cdc_selection() {
         .unwrap()
         .into_iter();
 
-    let sv = table_changes_action_iter(engine, commits, get_schema().into(), None)
-        .unwrap()
-        .flat_map(|scan_data| {
-            let scan_data = scan_data.unwrap();
-            assert_eq!(scan_data.remove_dvs, HashMap::new().into());
-            scan_data.selection_vector
-        })
-        .collect_vec();
+    let sv = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .flat_map(|scan_data| {
+        let scan_data = scan_data.unwrap();
+        assert_eq!(scan_data.remove_dvs, HashMap::new().into());
+        scan_data.selection_vector
+    })
+    .collect_vec();
 
     assert_eq!(sv, &[true, false, true, true]);
 }
@@ -456,14 +500,20 @@ async fn dv() {
         },
     )])
     .into();
-    let sv = table_changes_action_iter(engine, commits, get_schema().into(), None)
-        .unwrap()
-        .flat_map(|scan_data| {
-            let scan_data = scan_data.unwrap();
-            assert_eq!(scan_data.remove_dvs, expected_remove_dvs);
-            scan_data.selection_vector
-        })
-        .collect_vec();
+    let sv = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .flat_map(|scan_data| {
+        let scan_data = scan_data.unwrap();
+        assert_eq!(scan_data.remove_dvs, expected_remove_dvs);
+        scan_data.selection_vector
+    })
+    .collect_vec();
 
     assert_eq!(sv, &[false, true, true]);
 }
@@ -527,14 +577,19 @@ async fn data_skipping_filter() {
         .unwrap()
         .into_iter();
 
-    let sv =
-        table_changes_action_iter(engine, commits, get_schema().into(), Some(predicate.into()))
-            .unwrap()
-            .flat_map(|scan_data| {
-                let scan_data = scan_data.unwrap();
-                scan_data.selection_vector
-            })
-            .collect_vec();
+    let sv = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        Some(predicate.into()),
+    )
+    .unwrap()
+    .flat_map(|scan_data| {
+        let scan_data = scan_data.unwrap();
+        scan_data.selection_vector
+    })
+    .collect_vec();
 
     // Note: since the first pair is a dv operation, remove action will always be filtered
     assert_eq!(sv, &[false, true, false, false, true]);
@@ -573,10 +628,15 @@ async fn failing_protocol() {
         .unwrap()
         .into_iter();
 
-    let res: DeltaResult<Vec<_>> =
-        table_changes_action_iter(engine, commits, get_schema().into(), None)
-            .unwrap()
-            .try_collect();
+    let res: DeltaResult<Vec<_>> = table_changes_action_iter(
+        engine,
+        commits,
+        get_schema().into(),
+        &StructType::new([]),
+        None,
+    )
+    .unwrap()
+    .try_collect();
 
     assert!(res.is_err());
 }
@@ -603,3 +663,32 @@ async fn file_meta_timestamp() {
     let scanner = LogReplayScanner::try_new(engine.as_ref(), commit, &get_schema().into()).unwrap();
     assert_eq!(scanner.timestamp, file_meta_ts);
 }
+
+#[tokio::test]
+async fn in_commit_timestamp() {
+    let engine = Arc::new(SyncEngine::new());
+    let mut mock_table = LocalMockTable::new();
+
+    mock_table
+        .commit([
+            Action::CommitInfo(CommitInfo {
+                in_commit_timestamp: Some(1234),
+                timestamp: Some(1234),
+                ..Default::default()
+            }),
+            Action::Add(Add {
+                path: "fake_path_1".into(),
+                data_change: true,
+                ..Default::default()
+            }),
+        ])
+        .await;
+
+    let mut commits = get_segment(engine.as_ref(), mock_table.table_root(), 0, None)
+        .unwrap()
+        .into_iter();
+
+    let commit = commits.next().unwrap();
+    let scanner = LogReplayScanner::try_new(engine.as_ref(), commit, &get_schema().into()).unwrap();
+    assert_eq!(scanner.timestamp, 1234);
+}
@@ -193,7 +193,7 @@ impl TableChanges {
 /// check that column mapping is disabled, or the column mapping mode is `None`.
 fn check_cdf_table_properties(table_properties: &TableProperties) -> DeltaResult<()> {
     require!(
-        table_properties.enable_change_data_feed.unwrap_or(false),
+        table_properties.is_change_data_feed_enabled(),
         Error::unsupported("Change data feed is not enabled")
     );
     require!(
@@ -219,7 +219,9 @@ fn ensure_cdf_read_supported(protocol: &Protocol) -> DeltaResult<()> {
     match &protocol.reader_features() {
         // if min_reader_version = 3 and all reader features are subset of supported => OK
         Some(reader_features) if protocol.min_reader_version() == 3 => {
-            ensure_supported_features(reader_features, &CDF_SUPPORTED_READER_FEATURES)
+            ensure_supported_features(reader_features, &CDF_SUPPORTED_READER_FEATURES).map_err(
+                |features| Error::unsupported_table_features(protocol.with_versions(features)),
+            )
         }
         // if min_reader_version = 1 and there are no reader features => OK
         None if protocol.min_reader_version() == 1 => Ok(()),
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use itertools::Itertools;
 use tracing::debug;
 
-use crate::scan::ColumnType;
+use crate::scan::{physical_partition_schema, ColumnType};
 use crate::schema::{SchemaRef, StructType};
 use crate::{DeltaResult, Engine, ExpressionRef};
 
@@ -150,7 +150,8 @@ impl TableChangesScanBuilder {
                 } else {
                     // Add to read schema, store field so we can build a `Column` expression later
                     // if needed (i.e. if we have partition columns)
-                    let physical_field = logical_field.make_physical();
+                    let physical_field = logical_field
+                        .make_physical(self.table_changes.end_snapshot.column_mapping_mode);
                     debug!("\n\n{logical_field:#?}\nAfter mapping: {physical_field:#?}\n\n");
                     let physical_name = physical_field.name.clone();
                     read_fields.push(physical_field);
@@ -185,8 +186,20 @@ impl TableChangesScan {
             .log_segment
             .ascending_commit_files
             .clone();
-        let schema = self.table_changes.end_snapshot.schema().clone().into();
-        table_changes_action_iter(engine, commits, schema, self.predicate.clone())
+        let end_snapshot = &self.table_changes.end_snapshot;
+        let schema = end_snapshot.schema().clone().into();
+        let partition_schema = physical_partition_schema(
+            end_snapshot.schema(),
+            self.table_changes.partition_columns(),
+            end_snapshot.column_mapping_mode,
+        );
+        table_changes_action_iter(
+            engine,
+            commits,
+            schema,
+            &partition_schema,
+            self.predicate.clone(),
+        )
     }
 }
 
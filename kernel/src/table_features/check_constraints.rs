@@ -0,0 +1,211 @@
+//! Code to handle CHECK constraints, which are SQL predicates that every row of a table must
+//! satisfy. Each constraint is recorded in a `delta.constraints.<name>` table property. See
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#check-constraints>
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::expressions::sql::parse_sql_expression;
+use crate::expressions::Expression;
+use crate::schema::{column_name, ColumnName, ColumnNamesAndTypes, DataType, SchemaRef};
+use crate::utils::require;
+use crate::{DeltaResult, Engine, EngineData, Error};
+
+const CONSTRAINT_PREFIX: &str = "delta.constraints.";
+
+/// A CHECK constraint of a table: a named predicate that every row of the table must satisfy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckConstraint {
+    name: String,
+    sql: String,
+    expression: Expression,
+}
+
+impl CheckConstraint {
+    /// The name of the constraint
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The SQL text of the constraint's predicate, as recorded in the table properties
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The constraint's predicate
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Evaluate the constraint against a batch of rows to be written to the table, and return the
+    /// indexes of the rows that violate it. `schema` is the logical schema of `data`. As in Delta
+    /// Spark, a row for which the predicate evaluates to NULL also violates the constraint.
+    pub fn find_violations(
+        &self,
+        engine: &dyn Engine,
+        schema: SchemaRef,
+        data: &dyn EngineData,
+    ) -> DeltaResult<Vec<usize>> {
+        let evaluator = engine.get_expression_handler().get_evaluator(
+            schema,
+            self.expression.clone(),
+            DataType::BOOLEAN,
+        );
+        let result = evaluator.evaluate(data)?;
+        let mut visitor = ConstraintViolationVisitor::default();
+        visitor.visit_rows_of(result.as_ref())?;
+        Ok(visitor.violations)
+    }
+}
+
+/// Evaluate `constraints` against a batch of rows to be written to a table, and return each
+/// violated constraint with the indexes of the rows that violate it. `schema` is the logical schema
+/// of `data`. See [`CheckConstraint::find_violations`].
+pub fn find_check_constraint_violations<'a>(
+    engine: &dyn Engine,
+    constraints: &'a [CheckConstraint],
+    schema: SchemaRef,
+    data: &dyn EngineData,
+) -> DeltaResult<Vec<(&'a CheckConstraint, Vec<usize>)>> {
+    let mut violations = vec![];
+    for constraint in constraints {
+        let rows = constraint.find_violations(engine, schema.clone(), data)?;
+        if !rows.is_empty() {
+            violations.push((constraint, rows));
+        }
+    }
+    Ok(violations)
+}
+
+/// Parse the CHECK constraints recorded in the given table properties, ordered by name. Fails with
+/// [`Error::Unsupported`] if a constraint cannot be represented as a kernel expression.
+pub(crate) fn check_constraints(
+    configuration: &HashMap<String, String>,
+) -> DeltaResult<Vec<CheckConstraint>> {
+    let mut constraints: Vec<_> = configuration
+        .iter()
+        .filter_map(|(key, sql)| Some((key.strip_prefix(CONSTRAINT_PREFIX)?, sql)))
+        .map(|(name, sql)| {
+            Ok(CheckConstraint {
+                name: name.to_string(),
+                sql: sql.clone(),
+                expression: parse_sql_expression(sql)?,
+            })
+        })
+        .collect::<DeltaResult<_>>()?;
+    constraints.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(constraints)
+}
+
+/// Collects the indexes of the rows for which a predicate did not evaluate to true
+#[derive(Default)]
+struct ConstraintViolationVisitor {
+    violations: Vec<usize>,
+    rows_seen: usize,
+}
+
+impl RowVisitor for ConstraintViolationVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("output")], vec![DataType::BOOLEAN]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 1,
+            Error::InternalError(format!(
+                "Wrong number of ConstraintViolationVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            let satisfied: Option<bool> = getters[0].get_opt(i, "constraint.output")?;
+            if satisfied != Some(true) {
+                self.violations.push(self.rows_seen + i);
+            }
+        }
+        self.rows_seen += row_count;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{Int32Array, RecordBatch};
+    use arrow_schema::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+
+    use super::*;
+    use crate::engine::arrow_data::ArrowEngineData;
+    use crate::engine::sync::SyncEngine;
+    use crate::expressions::column_expr;
+    use crate::schema::{StructField, StructType};
+
+    #[test]
+    fn test_check_constraints() {
+        let configuration = HashMap::from([
+            (
+                "delta.constraints.positive".to_string(),
+                "a > 0".to_string(),
+            ),
+            (
+                "delta.constraints.bounded".to_string(),
+                "b <= 10".to_string(),
+            ),
+            ("delta.appendOnly".to_string(), "true".to_string()),
+        ]);
+        let constraints = check_constraints(&configuration).unwrap();
+        let names: Vec<_> = constraints.iter().map(|c| c.name()).collect();
+        assert_eq!(names, ["bounded", "positive"]);
+        assert_eq!(constraints[1].sql(), "a > 0");
+        assert_eq!(
+            constraints[1].expression(),
+            &column_expr!("a").gt(Expression::literal(0))
+        );
+
+        let configuration = HashMap::from([(
+            "delta.constraints.recent".to_string(),
+            "year(ts) > 2000".to_string(),
+        )]);
+        let err = check_constraints(&configuration).unwrap_err();
+        assert!(matches!(err, Error::Unsupported(_)));
+    }
+
+    #[test]
+    fn test_find_violations() {
+        let schema = Arc::new(StructType::new([StructField::new(
+            "a",
+            DataType::INTEGER,
+            true,
+        )]));
+        let arrow_schema = ArrowSchema::new(vec![Field::new("a", ArrowDataType::Int32, true)]);
+        let values = Int32Array::from(vec![Some(1), Some(-1), None, Some(5), Some(0)]);
+        let batch = RecordBatch::try_new(Arc::new(arrow_schema), vec![Arc::new(values)]).unwrap();
+
+        let data = ArrowEngineData::new(batch);
+        let engine = SyncEngine::new();
+
+        let configuration = HashMap::from([
+            (
+                "delta.constraints.positive".to_string(),
+                "a > 0".to_string(),
+            ),
+            (
+                "delta.constraints.small".to_string(),
+                "a IS NULL OR a < 100".to_string(),
+            ),
+        ]);
+        let constraints = check_constraints(&configuration).unwrap();
+        let violations = constraints[0]
+            .find_violations(&engine, schema.clone(), &data)
+            .unwrap();
+        assert_eq!(violations, [1, 2, 4]);
+
+        // only violated constraints are reported
+        let violations =
+            find_check_constraint_violations(&engine, &constraints, schema, &data).unwrap();
+        assert_eq!(violations, [(&constraints[0], vec![1, 2, 4])]);
+    }
+}
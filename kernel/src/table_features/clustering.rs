@@ -0,0 +1,163 @@
+//! Code to handle clustered tables, whose data files are clustered by a set of clustering columns.
+//! The clustering columns are recorded as physical column names in the configuration of the
+//! `delta.clustering` metadata domain. See
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#clustered-table>
+use serde::Deserialize;
+
+use super::WriterFeatures;
+use crate::actions::domain_metadata::domain_metadata_configuration;
+use crate::actions::Protocol;
+use crate::log_segment::LogSegment;
+use crate::schema::{ColumnName, DataType, Schema, StructType};
+use crate::{DeltaResult, Engine, Error};
+
+pub(crate) const CLUSTERING_DOMAIN_NAME: &str = "delta.clustering";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClusteringDomainConfiguration {
+    /// Physical names of the clustering columns, each given as a path of nested field names
+    clustering_columns: Vec<Vec<String>>,
+}
+
+/// Get the logical names of the clustering columns of a table, in clustering order, or `None` if
+/// the table is not clustered.
+pub(crate) fn clustering_columns(
+    log_segment: &LogSegment,
+    protocol: &Protocol,
+    schema: &Schema,
+    engine: &dyn Engine,
+) -> DeltaResult<Option<Vec<ColumnName>>> {
+    if !protocol.has_writer_feature(&WriterFeatures::Clustering) {
+        return Ok(None);
+    }
+    // a clustered table without clustering columns has not recorded its domain yet
+    let configuration = domain_metadata_configuration(log_segment, CLUSTERING_DOMAIN_NAME, engine)?;
+    let Some(configuration) = configuration else {
+        return Ok(Some(vec![]));
+    };
+    parse_clustering_columns(&configuration, schema).map(Some)
+}
+
+fn parse_clustering_columns(configuration: &str, schema: &Schema) -> DeltaResult<Vec<ColumnName>> {
+    let configuration: ClusteringDomainConfiguration = serde_json::from_str(configuration)
+        .map_err(|e| {
+            Error::generic(format!(
+                "Invalid {CLUSTERING_DOMAIN_NAME} domain configuration: {e}"
+            ))
+        })?;
+    configuration
+        .clustering_columns
+        .iter()
+        .map(|physical_path| to_logical_column(physical_path, schema))
+        .collect()
+}
+
+// Resolve a path of physical field names to the corresponding logical column name
+fn to_logical_column(physical_path: &[String], schema: &Schema) -> DeltaResult<ColumnName> {
+    let mut logical_path = vec![];
+    let mut current: Option<&StructType> = Some(schema);
+    for physical_name in physical_path {
+        let field = current
+            .and_then(|s| s.fields().find(|f| f.physical_name() == physical_name))
+            .ok_or_else(|| {
+                Error::generic(format!(
+                    "Clustering column {} not found in table schema",
+                    ColumnName::new(physical_path)
+                ))
+            })?;
+        logical_path.push(field.name().clone());
+        current = match field.data_type() {
+            DataType::Struct(s) => Some(s),
+            _ => None,
+        };
+    }
+    Ok(ColumnName::new(logical_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{DomainMetadata, Metadata};
+    use crate::engine::sync::SyncEngine;
+    use crate::schema::{ColumnMetadataKey, MetadataValue, StructField};
+    use crate::utils::test_utils::{Action, LocalMockTable};
+    use crate::Table;
+
+    fn physically_named(field: StructField, physical_name: &str) -> StructField {
+        field.with_metadata([(
+            ColumnMetadataKey::ColumnMappingPhysicalName.as_ref(),
+            MetadataValue::String(physical_name.to_string()),
+        )])
+    }
+
+    #[test]
+    fn test_parse_clustering_columns() {
+        let schema = StructType::new([
+            physically_named(StructField::new("id", DataType::LONG, true), "col-1"),
+            physically_named(
+                StructField::new(
+                    "s",
+                    StructType::new([physically_named(
+                        StructField::new("a", DataType::STRING, true),
+                        "col-3",
+                    )]),
+                    true,
+                ),
+                "col-2",
+            ),
+        ]);
+        let configuration = r#"{"clusteringColumns": [["col-2", "col-3"], ["col-1"]]}"#;
+        assert_eq!(
+            parse_clustering_columns(configuration, &schema).unwrap(),
+            [ColumnName::new(["s", "a"]), ColumnName::new(["id"])]
+        );
+
+        let configuration = r#"{"clusteringColumns": [["id"]]}"#;
+        assert!(parse_clustering_columns(configuration, &schema).is_err());
+        assert!(parse_clustering_columns("{}", &schema).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clustering_columns() {
+        let schema = StructType::new([
+            StructField::new("id", DataType::LONG, true),
+            StructField::new("region", DataType::STRING, true),
+        ]);
+        let mut mock_table = LocalMockTable::new();
+        mock_table
+            .commit([
+                Action::Protocol(
+                    Protocol::try_new(
+                        1,
+                        7,
+                        None::<Vec<String>>,
+                        Some([WriterFeatures::DomainMetadata, WriterFeatures::Clustering]),
+                    )
+                    .unwrap(),
+                ),
+                Action::Metadata(Metadata {
+                    schema_string: serde_json::to_string(&schema).unwrap(),
+                    ..Default::default()
+                }),
+            ])
+            .await;
+        mock_table
+            .commit([Action::DomainMetadata(DomainMetadata {
+                domain: CLUSTERING_DOMAIN_NAME.to_string(),
+                configuration: r#"{"clusteringColumns": [["region"], ["id"]]}"#.to_string(),
+                removed: false,
+            })])
+            .await;
+
+        let engine = SyncEngine::new();
+        let table = Table::new(url::Url::from_directory_path(mock_table.table_root()).unwrap());
+        let snapshot = table.snapshot(&engine, Some(0)).unwrap();
+        assert_eq!(snapshot.clustering_columns(&engine).unwrap(), Some(vec![]));
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        assert_eq!(
+            snapshot.clustering_columns(&engine).unwrap(),
+            Some(vec![ColumnName::new(["region"]), ColumnName::new(["id"])])
+        );
+    }
+}
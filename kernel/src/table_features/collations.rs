@@ -0,0 +1,278 @@
+//! Code to handle collated string columns, whose values are compared according to a collation
+//! instead of by their UTF-8 bytes. The collation of each string in a struct field's type is
+//! recorded in the field's `__COLLATIONS` metadata, keyed by the path of the string within the
+//! field, e.g. `name` for a string field `name`, or `name.element` for an array of strings. See
+//! <https://github.com/delta-io/delta/blob/master/protocol_rfcs/collated-string-type.md>
+//!
+//! File statistics and parquet footer statistics of strings are always ordered by UTF-8 bytes, so
+//! they cannot be used to skip data for comparisons on columns with any other collation.
+//!
+//! Expressions are not evaluated collation-aware yet, so the `collations-preview` reader feature
+//! is not supported and tables using it cannot be read.
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::schema::{
+    ColumnMetadataKey, ColumnName, MetadataValue, SchemaTransform, StructField, StructType,
+};
+use crate::{DeltaResult, Error};
+
+const SPARK_PROVIDER: &str = "spark";
+const UTF8_BINARY: &str = "UTF8_BINARY";
+
+/// The collation of a string type, identified as `provider.name[.version]`, e.g.
+/// `spark.UTF8_LCASE` or `ICU.en_US.74`. Strings without a collation use `spark.UTF8_BINARY`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Collation {
+    provider: String,
+    name: String,
+    version: Option<String>,
+}
+
+impl Collation {
+    /// The default collation, which compares strings by their UTF-8 bytes
+    pub fn utf8_binary() -> Self {
+        Self {
+            provider: SPARK_PROVIDER.to_string(),
+            name: UTF8_BINARY.to_string(),
+            version: None,
+        }
+    }
+
+    /// The provider of the collation, e.g. `spark` or `ICU`
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// The name of the collation within its provider, e.g. `UTF8_LCASE` or `en_US`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The version of the collation, if the provider versions its collations
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Whether this collation compares strings by their UTF-8 bytes, which is the only ordering
+    /// of strings that the kernel and file statistics know about.
+    pub fn is_binary(&self) -> bool {
+        self.provider.eq_ignore_ascii_case(SPARK_PROVIDER) && self.name == UTF8_BINARY
+    }
+}
+
+impl FromStr for Collation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> DeltaResult<Self> {
+        let mut parts = s.splitn(3, '.');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(provider), Some(name), version) if !provider.is_empty() && !name.is_empty() => {
+                Ok(Self {
+                    provider: provider.to_string(),
+                    name: name.to_string(),
+                    version: version.map(str::to_string),
+                })
+            }
+            _ => Err(Error::generic(format!("Invalid collation identifier: {s}"))),
+        }
+    }
+}
+
+impl Display for Collation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.provider, self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, ".{version}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the collation of the string at `path` within the type of `field`, e.g. the field name for a
+/// string field, or `None` if the string has the default collation.
+pub(crate) fn collation(field: &StructField, path: &str) -> DeltaResult<Option<Collation>> {
+    let invalid = || {
+        Error::generic(format!(
+            "Invalid {} metadata on field '{}'",
+            ColumnMetadataKey::Collations.as_ref(),
+            field.name()
+        ))
+    };
+    let collations = match field.get_config_value(&ColumnMetadataKey::Collations) {
+        None => return Ok(None),
+        Some(MetadataValue::Other(serde_json::Value::Object(collations))) => collations,
+        Some(_) => return Err(invalid()),
+    };
+    match collations.get(path) {
+        None => Ok(None),
+        Some(serde_json::Value::String(collation)) => collation.parse().map(Some),
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// Rename `field` to `name`, along with the paths of the collations in its metadata, which start
+/// with the field name. Physical schemas use this to stay consistent under column mapping.
+pub(crate) fn rename_collated_field(field: &StructField, name: &str) -> StructField {
+    let mut renamed = field.with_name(name);
+    if let Some(MetadataValue::Other(serde_json::Value::Object(collations))) = renamed
+        .metadata
+        .get_mut(ColumnMetadataKey::Collations.as_ref())
+    {
+        *collations = std::mem::take(collations)
+            .into_iter()
+            .map(|(path, collation)| match path.strip_prefix(field.name()) {
+                Some(rest) if rest.is_empty() || rest.starts_with('.') => {
+                    (format!("{name}{rest}"), collation)
+                }
+                _ => (path, collation),
+            })
+            .collect();
+    }
+    renamed
+}
+
+/// Get the names of all (possibly nested) columns of a schema whose strings have a non-binary
+/// collation. A column with invalid collation metadata is conservatively included.
+pub(crate) fn non_binary_collated_columns(schema: &StructType) -> HashSet<ColumnName> {
+    let mut finder = NonBinaryCollatedColumns::default();
+    let _ = finder.transform_struct(schema);
+    finder.columns
+}
+
+#[derive(Default)]
+struct NonBinaryCollatedColumns<'a> {
+    path: Vec<&'a str>,
+    columns: HashSet<ColumnName>,
+}
+
+impl<'a> SchemaTransform<'a> for NonBinaryCollatedColumns<'a> {
+    fn transform_struct_field(&mut self, field: &'a StructField) -> Option<Cow<'a, StructField>> {
+        self.path.push(field.name());
+        let non_binary = match collation(field, field.name()) {
+            Ok(collation) => collation.is_some_and(|c| !c.is_binary()),
+            Err(_) => true,
+        };
+        if non_binary {
+            self.columns
+                .insert(ColumnName::new(self.path.iter().copied()));
+        }
+        let _ = self.recurse_into_struct_field(field);
+        self.path.pop();
+        Some(Cow::Borrowed(field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Protocol;
+    use crate::schema::{ArrayType, DataType};
+    use crate::table_features::{ReaderFeatures, WriterFeatures};
+
+    fn collated(field: StructField, collations: serde_json::Value) -> StructField {
+        field.with_metadata([(
+            ColumnMetadataKey::Collations.as_ref(),
+            MetadataValue::Other(collations),
+        )])
+    }
+
+    #[test]
+    fn test_parse_collation() {
+        let collation: Collation = "ICU.en_US.74.1".parse().unwrap();
+        assert_eq!(collation.provider(), "ICU");
+        assert_eq!(collation.name(), "en_US");
+        assert_eq!(collation.version(), Some("74.1"));
+        assert_eq!(collation.to_string(), "ICU.en_US.74.1");
+        assert!(!collation.is_binary());
+
+        let collation: Collation = "spark.UTF8_LCASE".parse().unwrap();
+        assert_eq!(collation.version(), None);
+        assert!(!collation.is_binary());
+        assert_eq!(
+            "spark.UTF8_BINARY".parse::<Collation>().unwrap(),
+            Collation::utf8_binary()
+        );
+        assert!(Collation::utf8_binary().is_binary());
+
+        assert!("UTF8_LCASE".parse::<Collation>().is_err());
+        assert!(".en_US".parse::<Collation>().is_err());
+    }
+
+    #[test]
+    fn test_collations() {
+        let schema = StructType::new([
+            collated(
+                StructField::new("name", DataType::STRING, true),
+                serde_json::json!({"name": "spark.UTF8_LCASE"}),
+            ),
+            collated(
+                StructField::new("id", DataType::STRING, true),
+                serde_json::json!({"id": "spark.UTF8_BINARY"}),
+            ),
+            StructField::new(
+                "s",
+                StructType::new([
+                    collated(
+                        StructField::new("tags", ArrayType::new(DataType::STRING, true), true),
+                        serde_json::json!({"tags.element": "ICU.en_US"}),
+                    ),
+                    collated(
+                        StructField::new("city", DataType::STRING, true),
+                        serde_json::json!({"city": "ICU.de_DE.74"}),
+                    ),
+                ]),
+                true,
+            ),
+            collated(
+                StructField::new("bad", DataType::STRING, true),
+                serde_json::json!({"bad": 1}),
+            ),
+        ]);
+        let name = schema.field("name").unwrap();
+        assert_eq!(
+            name.collation().unwrap(),
+            Some("spark.UTF8_LCASE".parse().unwrap())
+        );
+        assert!(schema.field("bad").unwrap().collation().is_err());
+        let DataType::Struct(s) = schema.field("s").unwrap().data_type() else {
+            panic!("expected a struct");
+        };
+        assert_eq!(s.field("tags").unwrap().collation().unwrap(), None);
+        assert_eq!(
+            collation(s.field("tags").unwrap(), "tags.element").unwrap(),
+            Some("ICU.en_US".parse().unwrap())
+        );
+
+        let renamed = rename_collated_field(s.field("tags").unwrap(), "col-1");
+        assert_eq!(
+            collation(&renamed, "col-1.element").unwrap(),
+            Some("ICU.en_US".parse().unwrap())
+        );
+
+        assert_eq!(
+            non_binary_collated_columns(&schema),
+            HashSet::from([
+                ColumnName::new(["name"]),
+                ColumnName::new(["s", "city"]),
+                ColumnName::new(["bad"]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_collations_not_supported() {
+        // expressions are always evaluated by comparing UTF-8 bytes, which gives wrong results for
+        // non-binary collations, so tables using collations can't be read yet
+        let protocol = Protocol::try_new(
+            3,
+            7,
+            Some([ReaderFeatures::CollationsPreview]),
+            Some([WriterFeatures::CollationsPreview]),
+        )
+        .unwrap();
+        assert!(protocol.ensure_read_supported().is_err());
+    }
+}
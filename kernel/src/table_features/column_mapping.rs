@@ -1,6 +1,7 @@
 //! Code to handle column mapping, including modes and schema transforms
 use super::ReaderFeatures;
 use crate::actions::Protocol;
+use crate::expressions::{Expression, ExpressionTransform};
 use crate::schema::{ColumnName, DataType, MetadataValue, Schema, SchemaTransform, StructField};
 use crate::table_properties::TableProperties;
 use crate::{DeltaResult, Error};
@@ -44,11 +45,6 @@ pub(crate) fn column_mapping_mode(
 /// When column mapping mode is enabled, verify that each field in the schema is annotated with a
 /// physical name and field_id; when not enabled, verify that no fields are annotated.
 pub fn validate_schema_column_mapping(schema: &Schema, mode: ColumnMappingMode) -> DeltaResult<()> {
-    if mode == ColumnMappingMode::Id {
-        // TODO: Support column mapping ID mode
-        return Err(Error::unsupported("Column mapping ID mode not supported"));
-    }
-
     let mut validator = ValidateColumnMappings {
         mode,
         path: vec![],
@@ -157,9 +153,50 @@ impl<'a> SchemaTransform<'a> for ValidateColumnMappings<'a> {
     }
 }
 
+/// Translate the column references of an expression over the logical `schema` (e.g. a scan
+/// predicate) into references to the corresponding physical columns, following the physical name
+/// annotations of each (possibly nested) field. References to columns which are not in the
+/// schema are left unchanged.
+///
+/// NOTE: Caller affirms that the schema was already validated by
+/// [`validate_schema_column_mapping`], to ensure that annotations are always and only present when
+/// column mapping mode is enabled.
+pub(crate) fn make_physical_expression(schema: &Schema, expr: &Expression) -> Expression {
+    struct MakePhysical<'s>(&'s Schema);
+    impl<'a> ExpressionTransform<'a> for MakePhysical<'_> {
+        fn transform_column(&mut self, name: &'a ColumnName) -> Option<Cow<'a, ColumnName>> {
+            let mut physical_path = Vec::with_capacity(name.path().len());
+            let mut fields = Some(self.0);
+            for part in name.path() {
+                match fields.and_then(|schema| schema.field(part)) {
+                    Some(field) => {
+                        physical_path.push(field.physical_name().to_string());
+                        fields = match field.data_type() {
+                            DataType::Struct(inner) => Some(inner),
+                            _ => None,
+                        };
+                    }
+                    None => {
+                        physical_path.push(part.clone());
+                        fields = None;
+                    }
+                }
+            }
+            if physical_path == name.path() {
+                Some(Cow::Borrowed(name))
+            } else {
+                Some(Cow::Owned(ColumnName::new(physical_path)))
+            }
+        }
+    }
+    // NOTE: unwrap is safe because the transformer is incapable of returning None
+    MakePhysical(schema).transform(expr).unwrap().into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::expressions::column_expr;
     use crate::schema::StructType;
     use std::collections::HashMap;
 
@@ -312,12 +349,14 @@ mod tests {
     fn test_column_mapping_enabled() {
         let schema = create_schema("5", "\"col-a7f4159c\"", "4", "\"col-5f422f40\"");
         validate_schema_column_mapping(&schema, ColumnMappingMode::Name).unwrap();
-        validate_schema_column_mapping(&schema, ColumnMappingMode::Id).expect_err("not supported");
+        validate_schema_column_mapping(&schema, ColumnMappingMode::Id).unwrap();
 
         // missing annotation
         let schema = create_schema(None, "\"col-a7f4159c\"", "4", "\"col-5f422f40\"");
         validate_schema_column_mapping(&schema, ColumnMappingMode::Name)
             .expect_err("missing field id");
+        validate_schema_column_mapping(&schema, ColumnMappingMode::Id)
+            .expect_err("missing field id");
         let schema = create_schema("5", None, "4", "\"col-5f422f40\"");
         validate_schema_column_mapping(&schema, ColumnMappingMode::Name)
             .expect_err("missing field name");
@@ -359,4 +398,64 @@ mod tests {
         let schema = create_schema(None, None, None, "\"col-5f422f40\"");
         validate_schema_column_mapping(&schema, ColumnMappingMode::None).expect_err("field name");
     }
+
+    #[test]
+    fn test_make_physical_expression() {
+        let schema: StructType = serde_json::from_str(
+            r#"
+        {
+            "type": "struct",
+            "fields": [
+                {
+                    "name": "a",
+                    "type": {
+                        "type": "struct",
+                        "fields": [
+                            {
+                                "name": "b",
+                                "type": "integer",
+                                "nullable": true,
+                                "metadata": {
+                                    "delta.columnMapping.id": 2,
+                                    "delta.columnMapping.physicalName": "col_b"
+                                }
+                            }
+                        ]
+                    },
+                    "nullable": true,
+                    "metadata": {
+                        "delta.columnMapping.id": 1,
+                        "delta.columnMapping.physicalName": "col_a"
+                    }
+                },
+                {
+                    "name": "c",
+                    "type": "integer",
+                    "nullable": true,
+                    "metadata": {
+                        "delta.columnMapping.id": 3,
+                        "delta.columnMapping.physicalName": "col_c"
+                    }
+                }
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let expr = Expression::and(
+            column_expr!("a.b").gt(Expression::literal(1)),
+            column_expr!("c").lt(column_expr!("missing.x")),
+        );
+        let expected = Expression::and(
+            column_expr!("col_a.col_b").gt(Expression::literal(1)),
+            column_expr!("col_c").lt(column_expr!("missing.x")),
+        );
+        assert_eq!(make_physical_expression(&schema, &expr), expected);
+
+        // no annotations => no change
+        let schema = StructType::new([StructField::new("c", DataType::INTEGER, true)]);
+        let expr = column_expr!("c").gt(Expression::literal(1));
+        assert_eq!(make_physical_expression(&schema, &expr), expr);
+    }
 }
@@ -0,0 +1,82 @@
+//! Code to handle generated columns, whose values are computed from other columns of the same row.
+//! The SQL expression computing a generated column is recorded in its `delta.generationExpression`
+//! metadata. See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#generated-columns>
+use crate::expressions::sql::parse_sql_expression;
+use crate::expressions::Expression;
+use crate::schema::{ColumnMetadataKey, MetadataValue, StructField};
+use crate::{DeltaResult, Error};
+
+/// Get the SQL text of the `delta.generationExpression` metadata of a struct field, if any.
+pub(crate) fn generation_expression_sql(field: &StructField) -> DeltaResult<Option<&str>> {
+    match field.get_config_value(&ColumnMetadataKey::GenerationExpression) {
+        None => Ok(None),
+        Some(MetadataValue::String(sql)) => Ok(Some(sql)),
+        Some(value) => Err(Error::generic(format!(
+            "Invalid {} metadata on field '{}': {value}",
+            ColumnMetadataKey::GenerationExpression.as_ref(),
+            field.name()
+        ))),
+    }
+}
+
+/// Parse the `delta.generationExpression` metadata of a struct field into a kernel expression.
+/// Returns `None` if the field is not a generated column.
+pub(crate) fn generation_expression(field: &StructField) -> DeltaResult<Option<Expression>> {
+    generation_expression_sql(field)?
+        .map(parse_sql_expression)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::{column_expr, BinaryOperator};
+    use crate::schema::{DataType, StructType};
+
+    fn generated_field(name: &str, sql: &str) -> StructField {
+        StructField::new(name, DataType::LONG, true).with_metadata([(
+            ColumnMetadataKey::GenerationExpression.as_ref(),
+            sql.to_string(),
+        )])
+    }
+
+    #[test]
+    fn test_generation_expression() {
+        let field = generated_field("b", "a * 2");
+        assert_eq!(generation_expression_sql(&field).unwrap(), Some("a * 2"));
+        assert_eq!(
+            generation_expression(&field).unwrap(),
+            Some(Expression::binary(
+                BinaryOperator::Multiply,
+                column_expr!("a"),
+                2
+            ))
+        );
+
+        let field = StructField::new("a", DataType::LONG, true);
+        assert_eq!(generation_expression(&field).unwrap(), None);
+
+        let field = StructField::new("a", DataType::LONG, true)
+            .with_metadata([(ColumnMetadataKey::GenerationExpression.as_ref(), 1)]);
+        assert!(generation_expression(&field).is_err());
+    }
+
+    #[test]
+    fn test_generated_columns() {
+        let schema = StructType::new([
+            StructField::new("a", DataType::LONG, true),
+            generated_field("b", "a + 1"),
+            generated_field("c", "CAST(a AS STRING)"),
+        ]);
+        let generated = schema.generated_columns();
+        assert_eq!(generated.len(), 2);
+        assert_eq!(generated[0].0, "b");
+        assert_eq!(
+            generated[0].1.as_ref().unwrap(),
+            &Expression::binary(BinaryOperator::Plus, column_expr!("a"), 1)
+        );
+        // an unsupported expression doesn't keep the others from being used
+        assert_eq!(generated[1].0, "c");
+        assert!(matches!(generated[1].1, Err(Error::Unsupported(_))));
+    }
+}
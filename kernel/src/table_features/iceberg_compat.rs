@@ -0,0 +1,217 @@
+//! Code to validate the requirements of the Iceberg compatibility table features, which keep a
+//! table's data files and metadata convertible to Iceberg. See
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#iceberg-compatibility-v1> and
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#iceberg-compatibility-v2>
+use super::{ColumnMappingMode, WriterFeatures};
+use crate::actions::Protocol;
+use crate::schema::{
+    ArrayType, ColumnName, MapType, Schema, SchemaTransform, StructField, StructType,
+};
+use crate::table_properties::TableProperties;
+use crate::{DeltaResult, Error};
+
+use std::borrow::Cow;
+
+/// The version of Iceberg compatibility enabled for a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcebergCompatVersion {
+    /// `icebergCompatV1`: no map or array columns are allowed
+    V1,
+    /// `icebergCompatV2`
+    V2,
+}
+
+impl IcebergCompatVersion {
+    fn writer_feature(&self) -> WriterFeatures {
+        match self {
+            Self::V1 => WriterFeatures::IcebergCompatV1,
+            Self::V2 => WriterFeatures::IcebergCompatV2,
+        }
+    }
+}
+
+/// The version of Iceberg compatibility enabled by the table properties, if any.
+pub(crate) fn iceberg_compat_version(
+    table_properties: &TableProperties,
+) -> Option<IcebergCompatVersion> {
+    if table_properties.enable_iceberg_compat_v2 == Some(true) {
+        Some(IcebergCompatVersion::V2)
+    } else if table_properties.enable_iceberg_compat_v1 == Some(true) {
+        Some(IcebergCompatVersion::V1)
+    } else {
+        None
+    }
+}
+
+/// Ensure that a table with Iceberg compatibility enabled meets its requirements before writing
+/// to it: the corresponding writer feature is supported, column mapping is enabled, deletion
+/// vectors are disabled, and the schema only contains types that can be converted to Iceberg.
+pub(crate) fn validate_iceberg_compat(
+    protocol: &Protocol,
+    table_properties: &TableProperties,
+    schema: &Schema,
+) -> DeltaResult<()> {
+    let Some(version) = iceberg_compat_version(table_properties) else {
+        return Ok(());
+    };
+    let unsupported = |msg: String| {
+        Err(Error::unsupported(format!(
+            "IcebergCompat{version:?}: {msg}"
+        )))
+    };
+    if table_properties.enable_iceberg_compat_v1 == Some(true)
+        && table_properties.enable_iceberg_compat_v2 == Some(true)
+    {
+        return unsupported("only one version of Iceberg compatibility may be enabled".into());
+    }
+    if !protocol.has_writer_feature(&version.writer_feature()) {
+        return unsupported(format!(
+            "the table does not support the '{}' writer feature",
+            version.writer_feature()
+        ));
+    }
+    match table_properties.column_mapping_mode {
+        Some(ColumnMappingMode::Name | ColumnMappingMode::Id) => {}
+        _ => return unsupported("column mapping must be enabled".into()),
+    }
+    if table_properties.enable_deletion_vectors == Some(true) {
+        return unsupported("deletion vectors must be disabled".into());
+    }
+    let mut checker = UnsupportedIcebergTypes {
+        version,
+        path: vec![],
+        unsupported: None,
+    };
+    let _ = checker.transform_struct(schema);
+    if let Some((column, data_type)) = checker.unsupported {
+        return unsupported(format!("unsupported {data_type} type of column '{column}'"));
+    }
+    Ok(())
+}
+
+/// Schema visitor which records the first column whose type cannot be converted to Iceberg
+struct UnsupportedIcebergTypes<'a> {
+    version: IcebergCompatVersion,
+    path: Vec<&'a str>,
+    unsupported: Option<(ColumnName, &'static str)>,
+}
+
+impl UnsupportedIcebergTypes<'_> {
+    fn record(&mut self, data_type: &'static str) {
+        if self.unsupported.is_none() {
+            self.unsupported = Some((ColumnName::new(self.path.iter().copied()), data_type));
+        }
+    }
+}
+
+impl<'a> SchemaTransform<'a> for UnsupportedIcebergTypes<'a> {
+    fn transform_struct_field(&mut self, field: &'a StructField) -> Option<Cow<'a, StructField>> {
+        self.path.push(field.name());
+        let _ = self.recurse_into_struct_field(field);
+        self.path.pop();
+        Some(Cow::Borrowed(field))
+    }
+
+    fn transform_array(&mut self, atype: &'a ArrayType) -> Option<Cow<'a, ArrayType>> {
+        if self.version == IcebergCompatVersion::V1 {
+            self.record("array");
+        }
+        self.recurse_into_array(atype)
+    }
+
+    fn transform_map(&mut self, mtype: &'a MapType) -> Option<Cow<'a, MapType>> {
+        if self.version == IcebergCompatVersion::V1 {
+            self.record("map");
+        }
+        self.recurse_into_map(mtype)
+    }
+
+    fn transform_variant(&mut self, stype: &'a StructType) -> Option<Cow<'a, StructType>> {
+        self.record("variant");
+        Some(Cow::Borrowed(stype))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::DataType;
+
+    fn properties(version: &str, extra: &[(&str, &str)]) -> TableProperties {
+        let mut properties = vec![
+            (
+                format!("delta.enableIcebergCompat{version}"),
+                "true".to_string(),
+            ),
+            ("delta.columnMapping.mode".to_string(), "name".to_string()),
+        ];
+        properties.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        TableProperties::from(properties)
+    }
+
+    fn protocol(features: &[WriterFeatures]) -> Protocol {
+        let mut features = features.to_vec();
+        features.push(WriterFeatures::ColumnMapping);
+        Protocol::try_new(2, 7, None::<Vec<String>>, Some(features)).unwrap()
+    }
+
+    #[test]
+    fn test_iceberg_compat_version() {
+        assert_eq!(iceberg_compat_version(&TableProperties::default()), None);
+        assert_eq!(
+            iceberg_compat_version(&properties("V1", &[])),
+            Some(IcebergCompatVersion::V1)
+        );
+        assert_eq!(
+            iceberg_compat_version(&properties("V2", &[])),
+            Some(IcebergCompatVersion::V2)
+        );
+    }
+
+    #[test]
+    fn test_validate_iceberg_compat() {
+        let schema = StructType::new([
+            StructField::new("id", DataType::LONG, true),
+            StructField::new(
+                "s",
+                StructType::new([StructField::new(
+                    "tags",
+                    ArrayType::new(DataType::STRING, true),
+                    true,
+                )]),
+                true,
+            ),
+        ]);
+        let v1 = protocol(&[WriterFeatures::IcebergCompatV1]);
+        let v2 = protocol(&[WriterFeatures::IcebergCompatV2]);
+
+        // not enabled
+        let no_compat = TableProperties::default();
+        assert!(validate_iceberg_compat(&v1, &no_compat, &schema).is_ok());
+
+        // V2 allows arrays but V1 does not
+        assert!(validate_iceberg_compat(&v2, &properties("V2", &[]), &schema).is_ok());
+        let err = validate_iceberg_compat(&v1, &properties("V1", &[]), &schema).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported: IcebergCompatV1: unsupported array type of column 's.tags'"
+        );
+
+        // missing writer feature
+        let err = validate_iceberg_compat(&v1, &properties("V2", &[]), &schema).unwrap_err();
+        assert!(matches!(err, Error::Unsupported(_)));
+
+        // column mapping required
+        let props = properties("V2", &[("delta.columnMapping.mode", "none")]);
+        assert!(validate_iceberg_compat(&v2, &props, &schema).is_err());
+
+        // deletion vectors not allowed
+        let props = properties("V2", &[("delta.enableDeletionVectors", "true")]);
+        assert!(validate_iceberg_compat(&v2, &props, &schema).is_err());
+
+        // variants not allowed
+        let variant_schema =
+            StructType::new([StructField::new("v", DataType::unshredded_variant(), true)]);
+        assert!(validate_iceberg_compat(&v2, &properties("V2", &[]), &variant_schema).is_err());
+    }
+}
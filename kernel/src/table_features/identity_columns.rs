@@ -0,0 +1,172 @@
+//! Code to handle identity columns, whose values are generated by writers from a start value and a
+//! step. The parameters of an identity column and the highest value generated so far are recorded
+//! in its `delta.identity.*` metadata. See
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#identity-columns>
+use crate::schema::{ColumnMetadataKey, MetadataValue, StructField};
+use crate::{DeltaResult, Error};
+
+/// The parameters and state of an identity column, parsed from its `delta.identity.*` metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdentityColumnInfo {
+    /// The first value generated for the column
+    pub start: i64,
+    /// The difference between consecutive generated values. Never zero.
+    pub step: i64,
+    /// The highest (or, for a negative step, lowest) value generated so far, if any
+    pub high_water_mark: Option<i64>,
+    /// Whether writers may insert explicit values into the column instead of generated ones
+    pub allow_explicit_insert: bool,
+}
+
+impl IdentityColumnInfo {
+    /// The next value to generate for the column, given the values generated so far.
+    pub fn next_value(&self) -> DeltaResult<i64> {
+        match self.high_water_mark {
+            Some(high_water_mark) => high_water_mark
+                .checked_add(self.step)
+                .ok_or_else(|| Error::generic("Identity column values exhausted")),
+            None => Ok(self.start),
+        }
+    }
+
+    /// Whether `high_water_mark` is further along in the step direction than the current high
+    /// water mark, i.e. whether it would advance the high water mark.
+    pub fn advances_high_water_mark(&self, high_water_mark: i64) -> bool {
+        match self.high_water_mark {
+            None => true,
+            Some(current) if self.step > 0 => high_water_mark > current,
+            Some(current) => high_water_mark < current,
+        }
+    }
+}
+
+fn get_long(field: &StructField, key: ColumnMetadataKey) -> DeltaResult<Option<i64>> {
+    match field.get_config_value(&key) {
+        None => Ok(None),
+        Some(MetadataValue::Number(n)) => Ok(Some(*n as i64)),
+        Some(MetadataValue::Other(value)) if value.is_i64() => Ok(value.as_i64()),
+        Some(value) => Err(Error::generic(format!(
+            "Invalid {} metadata on field '{}': {value}",
+            key.as_ref(),
+            field.name()
+        ))),
+    }
+}
+
+/// Parse the `delta.identity.*` metadata of a struct field. Returns `None` if the field is not an
+/// identity column.
+pub(crate) fn identity_column_info(field: &StructField) -> DeltaResult<Option<IdentityColumnInfo>> {
+    let Some(start) = get_long(field, ColumnMetadataKey::IdentityStart)? else {
+        return Ok(None);
+    };
+    let step = get_long(field, ColumnMetadataKey::IdentityStep)?.unwrap_or(1);
+    if step == 0 {
+        return Err(Error::generic(format!(
+            "Identity column '{}' has a step of zero",
+            field.name()
+        )));
+    }
+    let high_water_mark = get_long(field, ColumnMetadataKey::IdentityHighWaterMark)?;
+    let allow_explicit_insert =
+        match field.get_config_value(&ColumnMetadataKey::IdentityAllowExplicitInsert) {
+            None => false,
+            Some(MetadataValue::Boolean(allow)) => *allow,
+            Some(value) => {
+                return Err(Error::generic(format!(
+                    "Invalid {} metadata on field '{}': {value}",
+                    ColumnMetadataKey::IdentityAllowExplicitInsert.as_ref(),
+                    field.name()
+                )))
+            }
+        };
+    Ok(Some(IdentityColumnInfo {
+        start,
+        step,
+        high_water_mark,
+        allow_explicit_insert,
+    }))
+}
+
+/// Set the `delta.identity.highWaterMark` metadata of an identity column.
+pub(crate) fn set_identity_high_water_mark(field: &mut StructField, high_water_mark: i64) {
+    let value = match i32::try_from(high_water_mark) {
+        Ok(n) => MetadataValue::Number(n),
+        Err(_) => MetadataValue::Other(high_water_mark.into()),
+    };
+    field.metadata.insert(
+        ColumnMetadataKey::IdentityHighWaterMark
+            .as_ref()
+            .to_string(),
+        value,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::DataType;
+
+    fn identity_field(metadata: serde_json::Value) -> StructField {
+        let metadata: std::collections::HashMap<String, MetadataValue> =
+            serde_json::from_value(metadata).unwrap();
+        StructField::new("id", DataType::LONG, false).with_metadata(metadata)
+    }
+
+    #[test]
+    fn test_identity_column_info() {
+        let field = identity_field(serde_json::json!({
+            "delta.identity.start": 10,
+            "delta.identity.step": -2,
+            "delta.identity.highWaterMark": 4,
+            "delta.identity.allowExplicitInsert": true,
+        }));
+        let info = identity_column_info(&field).unwrap().unwrap();
+        assert_eq!(
+            info,
+            IdentityColumnInfo {
+                start: 10,
+                step: -2,
+                high_water_mark: Some(4),
+                allow_explicit_insert: true,
+            }
+        );
+        assert_eq!(info.next_value().unwrap(), 2);
+        assert!(info.advances_high_water_mark(2));
+        assert!(!info.advances_high_water_mark(6));
+
+        let field = identity_field(serde_json::json!({
+            "delta.identity.start": 1,
+            "delta.identity.step": 1,
+        }));
+        let info = identity_column_info(&field).unwrap().unwrap();
+        assert_eq!(info.high_water_mark, None);
+        assert!(!info.allow_explicit_insert);
+        assert_eq!(info.next_value().unwrap(), 1);
+
+        let field = StructField::new("id", DataType::LONG, false);
+        assert_eq!(identity_column_info(&field).unwrap(), None);
+
+        let field = identity_field(serde_json::json!({
+            "delta.identity.start": 1,
+            "delta.identity.step": 0,
+        }));
+        assert!(identity_column_info(&field).is_err());
+    }
+
+    #[test]
+    fn test_set_identity_high_water_mark() {
+        let mut field = identity_field(serde_json::json!({
+            "delta.identity.start": 1,
+            "delta.identity.step": 1,
+        }));
+        set_identity_high_water_mark(&mut field, 3_000_000_000);
+        let info = identity_column_info(&field).unwrap().unwrap();
+        assert_eq!(info.high_water_mark, Some(3_000_000_000));
+
+        // the metadata must survive a round trip through the schema string
+        let json = serde_json::to_string(&field).unwrap();
+        let field: StructField = serde_json::from_str(&json).unwrap();
+        let info = identity_column_info(&field).unwrap().unwrap();
+        assert_eq!(info.high_water_mark, Some(3_000_000_000));
+    }
+}
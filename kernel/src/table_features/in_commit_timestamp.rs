@@ -0,0 +1,257 @@
+//! Code to handle in-commit timestamps, which record a monotonically increasing timestamp in the
+//! `commitInfo` of each commit. See
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps>
+use std::sync::LazyLock;
+
+use super::WriterFeatures;
+use crate::actions::{get_log_commit_info_schema, Protocol};
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::path::ParsedLogPath;
+use crate::schema::{column_name, ColumnName, ColumnNamesAndTypes, DataType};
+use crate::table_properties::TableProperties;
+use crate::utils::require;
+use crate::{DeltaResult, Engine, Error, Version};
+
+/// Whether in-commit timestamps are enabled for a table with the given [`Protocol`] and
+/// [`TableProperties`].
+pub(crate) fn in_commit_timestamps_enabled(
+    protocol: &Protocol,
+    table_properties: &TableProperties,
+) -> bool {
+    protocol.has_writer_feature(&WriterFeatures::InCommitTimestamp)
+        && table_properties.is_in_commit_timestamps_enabled()
+}
+
+/// Whether the commit at `version` must have an in-commit timestamp, for a table with the given
+/// [`Protocol`] and [`TableProperties`]. Commits from before in-commit timestamps were enabled
+/// (see [`TableProperties::in_commit_timestamp_enablement_version`]) don't have one.
+pub(crate) fn has_in_commit_timestamp(
+    protocol: &Protocol,
+    table_properties: &TableProperties,
+    version: Version,
+) -> bool {
+    in_commit_timestamps_enabled(protocol, table_properties)
+        && table_properties
+            .in_commit_timestamp_enablement_version
+            .map_or(true, |enablement_version| version >= enablement_version)
+}
+
+/// Get the timestamp of a commit, as milliseconds since the epoch. This is the in-commit timestamp
+/// of the commit if it must have one (see [`has_in_commit_timestamp`]), and the modification time
+/// of the commit file otherwise.
+pub(crate) fn commit_timestamp(
+    engine: &dyn Engine,
+    commit: &ParsedLogPath,
+    protocol: &Protocol,
+    table_properties: &TableProperties,
+) -> DeltaResult<i64> {
+    if !has_in_commit_timestamp(protocol, table_properties, commit.version) {
+        return Ok(commit.location.last_modified);
+    }
+    read_in_commit_timestamp(engine, commit)?.ok_or_else(|| {
+        Error::generic(format!(
+            "In-commit timestamps are enabled, but commit {} has no inCommitTimestamp",
+            commit.version
+        ))
+    })
+}
+
+/// Read the `inCommitTimestamp` of the `commitInfo` action of a commit, if it has one.
+pub(crate) fn read_in_commit_timestamp(
+    engine: &dyn Engine,
+    commit: &ParsedLogPath,
+) -> DeltaResult<Option<i64>> {
+    let batches = engine.get_json_handler().read_json_files(
+        std::slice::from_ref(&commit.location),
+        get_log_commit_info_schema().clone(),
+        None,
+    )?;
+    let mut visitor = InCommitTimestampVisitor::default();
+    for batch in batches {
+        visitor.visit_rows_of(batch?.as_ref())?;
+        if visitor.in_commit_timestamp.is_some() {
+            break;
+        }
+    }
+    Ok(visitor.in_commit_timestamp)
+}
+
+#[derive(Default)]
+struct InCommitTimestampVisitor {
+    in_commit_timestamp: Option<i64>,
+}
+
+impl RowVisitor for InCommitTimestampVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            (
+                vec![column_name!("commitInfo.inCommitTimestamp")],
+                vec![DataType::LONG],
+            )
+                .into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 1,
+            Error::InternalError(format!(
+                "Wrong number of InCommitTimestampVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            if let Some(timestamp) = getters[0].get_opt(i, "commitInfo.inCommitTimestamp")? {
+                self.in_commit_timestamp = Some(timestamp);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::actions::{CommitInfo, Metadata};
+    use crate::engine::sync::SyncEngine;
+    use crate::schema::{StructField, StructType};
+    use crate::utils::test_utils::{Action, LocalMockTable};
+    use crate::Table;
+
+    fn commit_info(in_commit_timestamp: i64) -> Action {
+        Action::CommitInfo(CommitInfo {
+            in_commit_timestamp: Some(in_commit_timestamp),
+            timestamp: Some(in_commit_timestamp),
+            ..Default::default()
+        })
+    }
+
+    // Creates a table with three commits, with in-commit timestamps `base`, `base + 1000` and
+    // `base + 2000`.
+    async fn mock_table(
+        base: i64,
+        configuration: HashMap<String, String>,
+    ) -> (LocalMockTable, Table) {
+        let schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+        let mut mock_table = LocalMockTable::new();
+        mock_table
+            .commit([
+                commit_info(base),
+                Action::Protocol(
+                    Protocol::try_new(
+                        1,
+                        7,
+                        None::<Vec<String>>,
+                        Some([WriterFeatures::InCommitTimestamp]),
+                    )
+                    .unwrap(),
+                ),
+                Action::Metadata(Metadata {
+                    schema_string: serde_json::to_string(&schema).unwrap(),
+                    configuration,
+                    ..Default::default()
+                }),
+            ])
+            .await;
+        mock_table.commit([commit_info(base + 1000)]).await;
+        mock_table.commit([commit_info(base + 2000)]).await;
+        let table = Table::new(url::Url::from_directory_path(mock_table.table_root()).unwrap());
+        (mock_table, table)
+    }
+
+    #[tokio::test]
+    async fn test_in_commit_timestamps() {
+        let engine = SyncEngine::new();
+        let configuration = HashMap::from([(
+            "delta.enableInCommitTimestamps".to_string(),
+            "true".to_string(),
+        )]);
+        let (_mock_table, table) = mock_table(1000, configuration).await;
+
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        assert!(snapshot.is_in_commit_timestamps_enabled());
+        assert_eq!(snapshot.timestamp(&engine).unwrap(), 3000);
+        let snapshot = table.snapshot(&engine, Some(1)).unwrap();
+        assert_eq!(snapshot.timestamp(&engine).unwrap(), 2000);
+
+        assert!(table.version_at_timestamp(&engine, 999).is_err());
+        for (timestamp, expected) in [(1000, 0), (1999, 0), (2000, 1), (2999, 1), (3000, 2)] {
+            assert_eq!(
+                table.version_at_timestamp(&engine, timestamp).unwrap(),
+                expected
+            );
+        }
+        let snapshot = table.snapshot_at_timestamp(&engine, i64::MAX).unwrap();
+        assert_eq!(snapshot.version(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_in_commit_timestamps_not_enabled() {
+        let engine = SyncEngine::new();
+        let (_mock_table, table) = mock_table(1000, HashMap::new()).await;
+
+        // without in-commit timestamps, the file modification time is the commit timestamp
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        assert!(!snapshot.is_in_commit_timestamps_enabled());
+        let timestamp = snapshot.timestamp(&engine).unwrap();
+        assert_ne!(timestamp, 3000);
+        // the commits may have been written within the same millisecond, in which case their
+        // timestamps are adjusted to be increasing
+        assert_eq!(
+            table.version_at_timestamp(&engine, timestamp + 2).unwrap(),
+            2
+        );
+        assert!(table.version_at_timestamp(&engine, 1000).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_commit_timestamps_enabled_later() {
+        let engine = SyncEngine::new();
+        // in-commit timestamps must be greater than the modification times of earlier commits
+        let base = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+            + 3_600_000;
+        let configuration = HashMap::from([
+            (
+                "delta.enableInCommitTimestamps".to_string(),
+                "true".to_string(),
+            ),
+            (
+                "delta.inCommitTimestampEnablementVersion".to_string(),
+                "1".to_string(),
+            ),
+            (
+                "delta.inCommitTimestampEnablementTimestamp".to_string(),
+                (base + 1000).to_string(),
+            ),
+        ]);
+        let (_mock_table, table) = mock_table(base, configuration).await;
+
+        // version 0 predates in-commit timestamps, so its file modification time is used
+        let snapshot = table.snapshot(&engine, Some(0)).unwrap();
+        let modification_time = snapshot.timestamp(&engine).unwrap();
+        assert!(modification_time < base);
+        assert!(table
+            .version_at_timestamp(&engine, modification_time - 1)
+            .is_err());
+        for (timestamp, expected) in [
+            (modification_time, 0),
+            (base + 999, 0),
+            (base + 1000, 1),
+            (base + 1999, 1),
+            (base + 2000, 2),
+        ] {
+            assert_eq!(
+                table.version_at_timestamp(&engine, timestamp).unwrap(),
+                expected
+            );
+        }
+    }
+}
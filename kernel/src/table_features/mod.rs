@@ -1,12 +1,44 @@
 use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::str::FromStr;
 use std::sync::LazyLock;
 
 use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, Display as StrumDisplay, EnumString, VariantNames};
 
-pub(crate) use column_mapping::column_mapping_mode;
+pub(crate) use check_constraints::check_constraints;
+pub use check_constraints::{find_check_constraint_violations, CheckConstraint};
+pub(crate) use clustering::clustering_columns;
+pub use collations::Collation;
+pub(crate) use collations::{collation, non_binary_collated_columns, rename_collated_field};
+pub(crate) use column_mapping::{column_mapping_mode, make_physical_expression};
 pub use column_mapping::{validate_schema_column_mapping, ColumnMappingMode};
+pub(crate) use generated_columns::{generation_expression, generation_expression_sql};
+pub use iceberg_compat::IcebergCompatVersion;
+pub(crate) use iceberg_compat::{iceberg_compat_version, validate_iceberg_compat};
+pub use identity_columns::IdentityColumnInfo;
+pub(crate) use identity_columns::{identity_column_info, set_identity_high_water_mark};
+pub(crate) use in_commit_timestamp::{
+    commit_timestamp, has_in_commit_timestamp, in_commit_timestamps_enabled,
+};
+pub(crate) use timestamp_ntz::{
+    schema_contains_timestamp_ntz, validate_timestamp_ntz_feature_support,
+};
+pub use type_widening::TypeChange;
+pub(crate) use type_widening::{type_changes, validate_type_changes};
+pub(crate) use variant::{schema_contains_variant, validate_variant_type_feature_support};
+mod check_constraints;
+mod clustering;
+mod collations;
 mod column_mapping;
+mod generated_columns;
+mod iceberg_compat;
+mod identity_columns;
+mod in_commit_timestamp;
+mod timestamp_ntz;
+mod type_widening;
+mod variant;
 
 /// Reader features communicate capabilities that must be implemented in order to correctly read a
 /// given table. That is, readers must implement and respect all features listed in a table's
@@ -48,6 +80,15 @@ pub enum ReaderFeatures {
     /// vacuumProtocolCheck ReaderWriter feature ensures consistent application of reader and writer
     /// protocol checks during VACUUM operations
     VacuumProtocolCheck,
+    /// variant type support
+    VariantType,
+    #[strum(serialize = "variantType-preview")]
+    #[serde(rename = "variantType-preview")]
+    VariantTypePreview,
+    /// Strings compared according to a collation instead of by their UTF-8 bytes
+    #[strum(serialize = "collations-preview")]
+    #[serde(rename = "collations-preview")]
+    CollationsPreview,
 }
 
 /// Similar to reader features, writer features communicate capabilities that must be implemented
@@ -109,6 +150,19 @@ pub enum WriterFeatures {
     /// vacuumProtocolCheck ReaderWriter feature ensures consistent application of reader and writer
     /// protocol checks during VACUUM operations
     VacuumProtocolCheck,
+    /// Monotonically increasing timestamps recorded in each commit's `commitInfo`
+    InCommitTimestamp,
+    /// variant type support
+    VariantType,
+    #[strum(serialize = "variantType-preview")]
+    #[serde(rename = "variantType-preview")]
+    VariantTypePreview,
+    /// Clustering of data files by a set of clustering columns
+    Clustering,
+    /// Strings compared according to a collation instead of by their UTF-8 bytes
+    #[strum(serialize = "collations-preview")]
+    #[serde(rename = "collations-preview")]
+    CollationsPreview,
 }
 
 impl From<ReaderFeatures> for String {
@@ -123,6 +177,34 @@ impl From<WriterFeatures> for String {
     }
 }
 
+/// The reader or writer features of a protocol
+pub(crate) trait TableFeature: Debug + FromStr + Hash + Eq {
+    /// Whether these are writer features, rather than reader features
+    const WRITER: bool;
+}
+
+impl TableFeature for ReaderFeatures {
+    const WRITER: bool = false;
+}
+
+impl TableFeature for WriterFeatures {
+    const WRITER: bool = true;
+}
+
+impl ReaderFeatures {
+    /// True if the kernel can read tables which require this reader feature
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED_READER_FEATURES.contains(self)
+    }
+}
+
+impl WriterFeatures {
+    /// True if the kernel can write to tables which require this writer feature
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED_WRITER_FEATURES.contains(self)
+    }
+}
+
 // we support everything except V2 checkpoints
 pub(crate) static SUPPORTED_READER_FEATURES: LazyLock<HashSet<ReaderFeatures>> =
     LazyLock::new(|| {
@@ -133,12 +215,22 @@ pub(crate) static SUPPORTED_READER_FEATURES: LazyLock<HashSet<ReaderFeatures>> =
             ReaderFeatures::TypeWidening,
             ReaderFeatures::TypeWideningPreview,
             ReaderFeatures::VacuumProtocolCheck,
+            ReaderFeatures::VariantType,
+            ReaderFeatures::VariantTypePreview,
         ])
     });
 
-// write support wip: no table features are supported yet
+// write support wip: only the features below are supported so far
 pub(crate) static SUPPORTED_WRITER_FEATURES: LazyLock<HashSet<WriterFeatures>> =
-    LazyLock::new(|| HashSet::from([]));
+    LazyLock::new(|| {
+        HashSet::from([
+            WriterFeatures::AppendOnly,
+            WriterFeatures::Clustering,
+            WriterFeatures::DeletionVectors,
+            WriterFeatures::DomainMetadata,
+            WriterFeatures::InCommitTimestamp,
+        ])
+    });
 
 #[cfg(test)]
 mod tests {
@@ -154,6 +246,9 @@ mod tests {
             (ReaderFeatures::TypeWideningPreview, "typeWidening-preview"),
             (ReaderFeatures::V2Checkpoint, "v2Checkpoint"),
             (ReaderFeatures::VacuumProtocolCheck, "vacuumProtocolCheck"),
+            (ReaderFeatures::VariantType, "variantType"),
+            (ReaderFeatures::VariantTypePreview, "variantType-preview"),
+            (ReaderFeatures::CollationsPreview, "collations-preview"),
         ];
 
         assert_eq!(ReaderFeatures::VARIANTS.len(), cases.len());
@@ -192,6 +287,11 @@ mod tests {
             (WriterFeatures::IcebergCompatV1, "icebergCompatV1"),
             (WriterFeatures::IcebergCompatV2, "icebergCompatV2"),
             (WriterFeatures::VacuumProtocolCheck, "vacuumProtocolCheck"),
+            (WriterFeatures::InCommitTimestamp, "inCommitTimestamp"),
+            (WriterFeatures::VariantType, "variantType"),
+            (WriterFeatures::VariantTypePreview, "variantType-preview"),
+            (WriterFeatures::Clustering, "clustering"),
+            (WriterFeatures::CollationsPreview, "collations-preview"),
         ];
 
         assert_eq!(WriterFeatures::VARIANTS.len(), cases.len());
@@ -0,0 +1,96 @@
+//! Code to validate that tables with `timestamp_ntz` columns support the `timestampNtz` feature
+use super::ReaderFeatures;
+use crate::actions::Protocol;
+use crate::schema::{PrimitiveType, Schema, SchemaTransform};
+use crate::{DeltaResult, Error};
+
+use std::borrow::Cow;
+
+/// Ensure that a table whose (possibly nested) schema contains a `timestamp_ntz` column enables
+/// the `timestampNtz` reader feature. See
+/// <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#timestamp-without-timezone-timestampntz>
+pub(crate) fn validate_timestamp_ntz_feature_support(
+    schema: &Schema,
+    protocol: &Protocol,
+) -> DeltaResult<()> {
+    if !protocol.has_reader_feature(&ReaderFeatures::TimestampWithoutTimezone)
+        && schema_contains_timestamp_ntz(schema)
+    {
+        return Err(Error::unsupported(
+            "Table contains TIMESTAMP_NTZ columns but does not have the required 'timestampNtz' \
+             reader feature",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the (possibly nested) schema contains a `timestamp_ntz` column
+pub(crate) fn schema_contains_timestamp_ntz(schema: &Schema) -> bool {
+    let mut uses_timestamp_ntz = UsesTimestampNtz(false);
+    let _ = uses_timestamp_ntz.transform_struct(schema);
+    uses_timestamp_ntz.0
+}
+
+/// Schema visitor which records whether any `timestamp_ntz` column was seen
+struct UsesTimestampNtz(bool);
+
+impl<'a> SchemaTransform<'a> for UsesTimestampNtz {
+    fn transform_primitive(&mut self, ptype: &'a PrimitiveType) -> Option<Cow<'a, PrimitiveType>> {
+        if *ptype == PrimitiveType::TimestampNtz {
+            self.0 = true;
+        }
+        Some(Cow::Borrowed(ptype))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ArrayType, DataType, MapType, StructField, StructType};
+    use crate::table_features::WriterFeatures;
+
+    #[test]
+    fn test_timestamp_ntz_feature_support() {
+        let plain_schema = StructType::new([
+            StructField::new("id", DataType::INTEGER, true),
+            StructField::new("ts", DataType::TIMESTAMP, true),
+        ]);
+        let ntz_schemas = [
+            StructType::new([StructField::new("ts", DataType::TIMESTAMP_NTZ, true)]),
+            StructType::new([StructField::new(
+                "nested",
+                StructType::new([StructField::new(
+                    "ts",
+                    ArrayType::new(DataType::TIMESTAMP_NTZ, true),
+                    true,
+                )]),
+                true,
+            )]),
+            StructType::new([StructField::new(
+                "map",
+                MapType::new(DataType::STRING, DataType::TIMESTAMP_NTZ, true),
+                true,
+            )]),
+        ];
+
+        let legacy_protocol =
+            Protocol::try_new(1, 2, None::<Vec<String>>, None::<Vec<String>>).unwrap();
+        let ntz_protocol = Protocol::try_new(
+            3,
+            7,
+            Some([ReaderFeatures::TimestampWithoutTimezone]),
+            Some([WriterFeatures::TimestampWithoutTimezone]),
+        )
+        .unwrap();
+
+        assert!(validate_timestamp_ntz_feature_support(&plain_schema, &legacy_protocol).is_ok());
+        assert!(validate_timestamp_ntz_feature_support(&plain_schema, &ntz_protocol).is_ok());
+        for schema in &ntz_schemas {
+            assert!(matches!(
+                validate_timestamp_ntz_feature_support(schema, &legacy_protocol),
+                Err(Error::Unsupported(_))
+            ));
+            assert!(validate_timestamp_ntz_feature_support(schema, &ntz_protocol).is_ok());
+        }
+    }
+}
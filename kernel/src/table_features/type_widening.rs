@@ -0,0 +1,304 @@
+//! Code to handle type widening, which allows the type of columns to be changed to a wider type
+//! without rewriting existing data files. The history of type changes of each field is recorded in
+//! its `delta.typeChanges` metadata. See
+//! <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#type-widening>
+use super::ReaderFeatures;
+use crate::actions::Protocol;
+use crate::schema::{
+    ColumnMetadataKey, ColumnName, MetadataValue, PrimitiveType, Schema, SchemaTransform,
+    StructField,
+};
+use crate::{DeltaResult, Error, Version};
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+/// A type change recorded in the `delta.typeChanges` metadata of a struct field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeChange {
+    /// The type of the field (or of the nested element identified by `field_path`) before
+    /// the change
+    pub from_type: PrimitiveType,
+    /// The type of the field (or of the nested element identified by `field_path`) after the
+    /// change
+    pub to_type: PrimitiveType,
+    /// The version of the table in which the type change was applied, if recorded
+    pub table_version: Option<Version>,
+    /// For fields of map or array type, the path to the nested map key, map value or array
+    /// element whose type was changed, e.g. `key`, `value` or `element.element`
+    pub field_path: Option<String>,
+}
+
+/// Parse the `delta.typeChanges` metadata of a struct field, oldest change first. Returns an empty
+/// list if the field's type was never changed.
+pub(crate) fn type_changes(field: &StructField) -> DeltaResult<Vec<TypeChange>> {
+    match field.get_config_value(&ColumnMetadataKey::TypeChanges) {
+        None => Ok(vec![]),
+        Some(MetadataValue::Other(value)) => serde_json::from_value(value.clone()).map_err(|e| {
+            Error::generic(format!(
+                "Invalid {} metadata on field '{}': {e}",
+                ColumnMetadataKey::TypeChanges.as_ref(),
+                field.name()
+            ))
+        }),
+        Some(value) => Err(Error::generic(format!(
+            "Invalid {} metadata on field '{}': {value}",
+            ColumnMetadataKey::TypeChanges.as_ref(),
+            field.name()
+        ))),
+    }
+}
+
+/// Whether changing the type of a column from `from_type` to `to_type` is a widening supported
+/// by the type widening table feature, i.e. whether data files written with `from_type` can be
+/// read as `to_type`.
+pub(crate) fn is_widening_supported(from_type: &PrimitiveType, to_type: &PrimitiveType) -> bool {
+    use PrimitiveType::*;
+    match (from_type, to_type) {
+        (Byte, Short | Integer | Long | Double) => true,
+        (Short, Integer | Long | Double) => true,
+        (Integer, Long | Double) => true,
+        (Float, Double) => true,
+        (Date, TimestampNtz) => true,
+        (Decimal(from_precision, from_scale), Decimal(to_precision, to_scale)) => {
+            decimal_widening_supported(*from_precision, *from_scale, *to_precision, *to_scale)
+        }
+        // integers can be widened to decimals that can hold all of their values
+        (Byte, Decimal(precision, scale)) => decimal_widening_supported(3, 0, *precision, *scale),
+        (Short, Decimal(precision, scale)) => decimal_widening_supported(5, 0, *precision, *scale),
+        (Integer, Decimal(precision, scale)) => {
+            decimal_widening_supported(10, 0, *precision, *scale)
+        }
+        (Long, Decimal(precision, scale)) => decimal_widening_supported(20, 0, *precision, *scale),
+        _ => false,
+    }
+}
+
+// Decimal(p, s) can be widened to Decimal(p + k1, s + k2) where k1 >= k2 >= 0
+fn decimal_widening_supported(
+    from_precision: u8,
+    from_scale: u8,
+    to_precision: u8,
+    to_scale: u8,
+) -> bool {
+    to_scale >= from_scale
+        && to_precision >= from_precision
+        && to_precision - from_precision >= to_scale - from_scale
+}
+
+/// Ensure that all type changes recorded in the (possibly nested) fields of `schema` are
+/// widenings supported by the kernel (see [`is_widening_supported`]), so that data files written
+/// before a type change can be read. Type changes are only meaningful if the table supports the
+/// `typeWidening` (or `typeWidening-preview`) reader feature, so nothing is checked otherwise.
+pub(crate) fn validate_type_changes(schema: &Schema, protocol: &Protocol) -> DeltaResult<()> {
+    if !protocol.has_reader_feature(&ReaderFeatures::TypeWidening)
+        && !protocol.has_reader_feature(&ReaderFeatures::TypeWideningPreview)
+    {
+        return Ok(());
+    }
+    let mut validator = ValidateTypeChanges {
+        path: vec![],
+        err: None,
+    };
+    let _ = validator.transform_struct(schema);
+    match validator.err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+struct ValidateTypeChanges<'a> {
+    path: Vec<&'a str>,
+    err: Option<Error>,
+}
+
+impl ValidateTypeChanges<'_> {
+    fn check_type_changes(&self, field: &StructField) -> DeltaResult<()> {
+        for change in type_changes(field)? {
+            if !is_widening_supported(&change.from_type, &change.to_type) {
+                // The field path identifies nested map keys, map values and array elements
+                let field_path = change.field_path.iter().flat_map(|path| path.split('.'));
+                let column_name = ColumnName::new(self.path.iter().copied().chain(field_path));
+                return Err(Error::unsupported(format!(
+                    "Unsupported type change of column '{column_name}' from {} to {}",
+                    change.from_type, change.to_type
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> SchemaTransform<'a> for ValidateTypeChanges<'a> {
+    fn transform_struct_field(&mut self, field: &'a StructField) -> Option<Cow<'a, StructField>> {
+        if self.err.is_none() {
+            self.path.push(&field.name);
+            if let Err(err) = self.check_type_changes(field) {
+                self.err = Some(err);
+            }
+            let _ = self.recurse_into_struct_field(field);
+            self.path.pop();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::schema::{ArrayType, DataType, StructType};
+    use crate::table_features::WriterFeatures;
+    use crate::Table;
+
+    fn field_with_type_changes(data_type: impl Into<DataType>, type_changes: &str) -> StructField {
+        StructField::new("a", data_type, true).with_metadata([(
+            ColumnMetadataKey::TypeChanges.as_ref(),
+            MetadataValue::Other(serde_json::from_str(type_changes).unwrap()),
+        )])
+    }
+
+    #[test]
+    fn test_parse_type_changes() {
+        let field = StructField::new("a", DataType::LONG, true);
+        assert_eq!(field.type_changes().unwrap(), vec![]);
+
+        let field = field_with_type_changes(
+            DataType::LONG,
+            r#"[{"fromType":"byte","toType":"short","tableVersion":1},
+                {"fromType":"short","toType":"long","tableVersion":3}]"#,
+        );
+        let expected = vec![
+            TypeChange {
+                from_type: PrimitiveType::Byte,
+                to_type: PrimitiveType::Short,
+                table_version: Some(1),
+                field_path: None,
+            },
+            TypeChange {
+                from_type: PrimitiveType::Short,
+                to_type: PrimitiveType::Long,
+                table_version: Some(3),
+                field_path: None,
+            },
+        ];
+        assert_eq!(field.type_changes().unwrap(), expected);
+
+        let field = field_with_type_changes(
+            ArrayType::new(DataType::decimal(12, 2).unwrap(), true),
+            r#"[{"fromType":"decimal(10,2)","toType":"decimal(12,2)","fieldPath":"element"}]"#,
+        );
+        let expected = vec![TypeChange {
+            from_type: PrimitiveType::Decimal(10, 2),
+            to_type: PrimitiveType::Decimal(12, 2),
+            table_version: None,
+            field_path: Some("element".to_string()),
+        }];
+        assert_eq!(field.type_changes().unwrap(), expected);
+
+        let field = field_with_type_changes(DataType::LONG, r#"{"fromType":"byte"}"#);
+        assert!(field.type_changes().is_err());
+    }
+
+    #[test]
+    fn test_is_widening_supported() {
+        use PrimitiveType::*;
+        let supported = [
+            (Byte, Short),
+            (Byte, Long),
+            (Short, Integer),
+            (Integer, Long),
+            (Integer, Double),
+            (Float, Double),
+            (Date, TimestampNtz),
+            (Decimal(10, 2), Decimal(20, 2)),
+            (Decimal(10, 2), Decimal(20, 5)),
+            (Byte, Decimal(4, 1)),
+            (Integer, Decimal(11, 1)),
+            (Long, Decimal(21, 1)),
+        ];
+        for (from_type, to_type) in supported {
+            assert!(
+                is_widening_supported(&from_type, &to_type),
+                "{from_type} -> {to_type}"
+            );
+        }
+        let unsupported = [
+            (Long, Integer),
+            (Long, Double),
+            (Double, Float),
+            (String, Long),
+            (TimestampNtz, Date),
+            (Date, Timestamp),
+            (Decimal(10, 2), Decimal(11, 4)),
+            (Decimal(10, 2), Decimal(20, 1)),
+            (Integer, Decimal(10, 1)),
+            (Long, Decimal(20, 1)),
+        ];
+        for (from_type, to_type) in unsupported {
+            assert!(
+                !is_widening_supported(&from_type, &to_type),
+                "{from_type} -> {to_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_type_changes() {
+        let protocol = Protocol::try_new(
+            3,
+            7,
+            Some([ReaderFeatures::TypeWidening]),
+            Some([WriterFeatures::TypeWidening]),
+        )
+        .unwrap();
+        let schema = StructType::new([StructField::new(
+            "s",
+            StructType::new([field_with_type_changes(
+                DataType::LONG,
+                r#"[{"fromType":"integer","toType":"long"}]"#,
+            )]),
+            true,
+        )]);
+        assert!(validate_type_changes(&schema, &protocol).is_ok());
+
+        let schema = StructType::new([StructField::new(
+            "s",
+            StructType::new([field_with_type_changes(
+                ArrayType::new(DataType::INTEGER, true),
+                r#"[{"fromType":"long","toType":"integer","fieldPath":"element"}]"#,
+            )]),
+            true,
+        )]);
+        let err = validate_type_changes(&schema, &protocol).unwrap_err();
+        assert!(matches!(err, Error::Unsupported(_)));
+        assert_eq!(
+            err.to_string(),
+            "Unsupported: Unsupported type change of column 's.a.element' from long to integer"
+        );
+
+        // without type widening, the type change metadata is ignored
+        let protocol = Protocol::try_new(1, 2, None::<Vec<String>>, None::<Vec<String>>).unwrap();
+        assert!(validate_type_changes(&schema, &protocol).is_ok());
+    }
+
+    #[test]
+    fn test_type_widening_table() {
+        let path = std::fs::canonicalize(PathBuf::from("./tests/data/type-widening/")).unwrap();
+        let url = url::Url::from_directory_path(path).unwrap();
+        let engine = SyncEngine::new();
+        let snapshot = Table::new(url).snapshot(&engine, None).unwrap();
+        let field = snapshot.schema().field("int_decimal").unwrap();
+        let expected = vec![TypeChange {
+            from_type: PrimitiveType::Integer,
+            to_type: PrimitiveType::Decimal(11, 1),
+            table_version: Some(2),
+            field_path: None,
+        }];
+        assert_eq!(field.type_changes().unwrap(), expected);
+    }
+}
@@ -0,0 +1,91 @@
+//! Code to validate that tables with `variant` columns support the `variantType` feature
+use super::ReaderFeatures;
+use crate::actions::Protocol;
+use crate::schema::{Schema, SchemaTransform, StructType};
+use crate::{DeltaResult, Error};
+
+use std::borrow::Cow;
+
+/// Ensure that a table whose (possibly nested) schema contains a `variant` column enables the
+/// `variantType` (or `variantType-preview`) reader feature. See
+/// <https://github.com/delta-io/delta/blob/master/protocol_rfcs/variant-type.md>
+pub(crate) fn validate_variant_type_feature_support(
+    schema: &Schema,
+    protocol: &Protocol,
+) -> DeltaResult<()> {
+    if !protocol.has_reader_feature(&ReaderFeatures::VariantType)
+        && !protocol.has_reader_feature(&ReaderFeatures::VariantTypePreview)
+        && schema_contains_variant(schema)
+    {
+        return Err(Error::unsupported(
+            "Table contains VARIANT columns but does not have the required 'variantType' reader \
+             feature",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the (possibly nested) schema contains a `variant` column
+pub(crate) fn schema_contains_variant(schema: &Schema) -> bool {
+    let mut uses_variant = UsesVariant(false);
+    let _ = uses_variant.transform_struct(schema);
+    uses_variant.0
+}
+
+/// Schema visitor which records whether any `variant` column was seen
+struct UsesVariant(bool);
+
+impl<'a> SchemaTransform<'a> for UsesVariant {
+    fn transform_variant(&mut self, stype: &'a StructType) -> Option<Cow<'a, StructType>> {
+        self.0 = true;
+        Some(Cow::Borrowed(stype))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ArrayType, DataType, StructField};
+    use crate::table_features::WriterFeatures;
+
+    #[test]
+    fn test_variant_type_feature_support() {
+        let plain_schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+        let variant_schemas = [
+            StructType::new([StructField::new("v", DataType::unshredded_variant(), true)]),
+            StructType::new([StructField::new(
+                "nested",
+                StructType::new([StructField::new(
+                    "v",
+                    ArrayType::new(DataType::unshredded_variant(), true),
+                    true,
+                )]),
+                true,
+            )]),
+        ];
+
+        let legacy_protocol =
+            Protocol::try_new(1, 2, None::<Vec<String>>, None::<Vec<String>>).unwrap();
+        let variant_protocols = [
+            (ReaderFeatures::VariantType, WriterFeatures::VariantType),
+            (
+                ReaderFeatures::VariantTypePreview,
+                WriterFeatures::VariantTypePreview,
+            ),
+        ]
+        .map(|(reader_feature, writer_feature)| {
+            Protocol::try_new(3, 7, Some([reader_feature]), Some([writer_feature])).unwrap()
+        });
+
+        assert!(validate_variant_type_feature_support(&plain_schema, &legacy_protocol).is_ok());
+        for schema in &variant_schemas {
+            assert!(matches!(
+                validate_variant_type_feature_support(schema, &legacy_protocol),
+                Err(Error::Unsupported(_))
+            ));
+            for protocol in &variant_protocols {
+                assert!(validate_variant_type_feature_support(schema, protocol).is_ok());
+            }
+        }
+    }
+}
@@ -16,13 +16,26 @@ use std::time::Duration;
 
 use crate::expressions::ColumnName;
 use crate::table_features::ColumnMappingMode;
-use crate::Error;
+use crate::{Error, Version};
 
 use strum::EnumString;
 
 mod deserialize;
 pub use deserialize::ParseIntervalError;
 
+/// The default value of `delta.checkpointInterval`: a checkpoint every 10 commits.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 10;
+
+/// The default value of `delta.deletedFileRetentionDuration`: one week.
+pub const DEFAULT_DELETED_FILE_RETENTION_DURATION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// The default value of `delta.logRetentionDuration`: 30 days.
+pub const DEFAULT_LOG_RETENTION_DURATION: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// The default value of `delta.dataSkippingNumIndexedCols`: statistics on the first 32 columns.
+pub const DEFAULT_NUM_INDEXED_COLS: DataSkippingNumIndexedCols =
+    DataSkippingNumIndexedCols::NumColumns(32);
+
 /// Delta table properties. These are parsed from the 'configuration' map in the most recent
 /// 'Metadata' action of a table.
 ///
@@ -137,10 +150,111 @@ pub struct TableProperties {
     /// whether to enable row tracking during writes.
     pub enable_row_tracking: Option<bool>,
 
+    /// whether to record a monotonically increasing `inCommitTimestamp` in the `commitInfo` of
+    /// each commit, which is then used as the commit's timestamp rather than the modification
+    /// time of the commit file.
+    pub enable_in_commit_timestamps: Option<bool>,
+
+    /// The version of the table at which in-commit timestamps were enabled, if they were enabled
+    /// after the table was created.
+    pub in_commit_timestamp_enablement_version: Option<Version>,
+
+    /// The in-commit timestamp (milliseconds since the epoch) of the commit at which in-commit
+    /// timestamps were enabled, if they were enabled after the table was created.
+    pub in_commit_timestamp_enablement_timestamp: Option<i64>,
+
+    /// whether the table must remain convertible to Iceberg, as required by the `icebergCompatV1`
+    /// table feature.
+    pub enable_iceberg_compat_v1: Option<bool>,
+
+    /// whether the table must remain convertible to Iceberg, as required by the `icebergCompatV2`
+    /// table feature.
+    pub enable_iceberg_compat_v2: Option<bool>,
+
     /// any unrecognized properties are passed through and ignored by the parser
     pub unknown_properties: HashMap<String, String>,
 }
 
+impl TableProperties {
+    /// Whether the table is append-only (`delta.appendOnly`). Defaults to false.
+    pub fn is_append_only(&self) -> bool {
+        self.append_only.unwrap_or(false)
+    }
+
+    /// Whether change data feed is enabled (`delta.enableChangeDataFeed`). Defaults to false.
+    pub fn is_change_data_feed_enabled(&self) -> bool {
+        self.enable_change_data_feed.unwrap_or(false)
+    }
+
+    /// Whether deletion vectors are enabled (`delta.enableDeletionVectors`). Defaults to false.
+    pub fn is_deletion_vectors_enabled(&self) -> bool {
+        self.enable_deletion_vectors.unwrap_or(false)
+    }
+
+    /// Whether row tracking is enabled (`delta.enableRowTracking`). Defaults to false.
+    pub fn is_row_tracking_enabled(&self) -> bool {
+        self.enable_row_tracking.unwrap_or(false)
+    }
+
+    /// Whether in-commit timestamps are enabled (`delta.enableInCommitTimestamps`). Defaults to
+    /// false.
+    pub fn is_in_commit_timestamps_enabled(&self) -> bool {
+        self.enable_in_commit_timestamps.unwrap_or(false)
+    }
+
+    /// Whether expired log files should be cleaned up (`delta.enableExpiredLogCleanup`). Defaults
+    /// to true.
+    pub fn is_expired_log_cleanup_enabled(&self) -> bool {
+        self.enable_expired_log_cleanup.unwrap_or(true)
+    }
+
+    /// The number of commits between checkpoints (`delta.checkpointInterval`), or
+    /// [`DEFAULT_CHECKPOINT_INTERVAL`] if unset.
+    pub fn checkpoint_interval_or_default(&self) -> u64 {
+        self.checkpoint_interval
+            .map_or(DEFAULT_CHECKPOINT_INTERVAL, NonZero::get)
+    }
+
+    /// The retention duration of removed data files (`delta.deletedFileRetentionDuration`), or
+    /// [`DEFAULT_DELETED_FILE_RETENTION_DURATION`] if unset.
+    pub fn deleted_file_retention_duration_or_default(&self) -> Duration {
+        self.deleted_file_retention_duration
+            .unwrap_or(DEFAULT_DELETED_FILE_RETENTION_DURATION)
+    }
+
+    /// The retention duration of the table history (`delta.logRetentionDuration`), or
+    /// [`DEFAULT_LOG_RETENTION_DURATION`] if unset.
+    pub fn log_retention_duration_or_default(&self) -> Duration {
+        self.log_retention_duration
+            .unwrap_or(DEFAULT_LOG_RETENTION_DURATION)
+    }
+
+    /// The number of leading columns to collect statistics on (`delta.dataSkippingNumIndexedCols`),
+    /// or [`DEFAULT_NUM_INDEXED_COLS`] if unset.
+    pub fn data_skipping_num_indexed_cols_or_default(&self) -> DataSkippingNumIndexedCols {
+        self.data_skipping_num_indexed_cols
+            .unwrap_or(DEFAULT_NUM_INDEXED_COLS)
+    }
+
+    /// The column mapping mode set by `delta.columnMapping.mode`, or [`ColumnMappingMode::None`]
+    /// if unset. Note that the mode only takes effect if the table protocol supports column
+    /// mapping; see [`crate::snapshot::Snapshot::column_mapping_mode`].
+    pub fn column_mapping_mode_or_default(&self) -> ColumnMappingMode {
+        self.column_mapping_mode.unwrap_or(ColumnMappingMode::None)
+    }
+
+    /// The isolation level set by `delta.isolationLevel`, or [`IsolationLevel::default`] if unset.
+    pub fn isolation_level_or_default(&self) -> IsolationLevel {
+        self.isolation_level.unwrap_or_default()
+    }
+
+    /// The checkpoint policy set by `delta.checkpointPolicy`, or [`CheckpointPolicy::default`] if
+    /// unset.
+    pub fn checkpoint_policy_or_default(&self) -> CheckpointPolicy {
+        self.checkpoint_policy.clone().unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DataSkippingNumIndexedCols {
     AllColumns,
@@ -221,6 +335,82 @@ mod tests {
         assert_eq!(table_properties, expected);
     }
 
+    #[test]
+    fn known_key_invalid_enum_val() {
+        let properties = HashMap::from([
+            ("delta.columnMapping.mode".to_string(), "wack".to_string()),
+            (
+                "delta.dataSkippingNumIndexedCols".to_string(),
+                "-2".to_string(),
+            ),
+            ("delta.isolationLevel".to_string(), "wack".to_string()),
+            ("delta.checkpointPolicy".to_string(), "wack".to_string()),
+        ]);
+        let table_properties = TableProperties::from(properties.iter());
+        let expected = TableProperties {
+            unknown_properties: properties,
+            ..Default::default()
+        };
+        assert_eq!(table_properties, expected);
+    }
+
+    #[test]
+    fn test_table_properties_defaults() {
+        let props = TableProperties::default();
+        assert!(!props.is_append_only());
+        assert!(!props.is_change_data_feed_enabled());
+        assert!(!props.is_deletion_vectors_enabled());
+        assert!(!props.is_row_tracking_enabled());
+        assert!(props.is_expired_log_cleanup_enabled());
+        assert_eq!(props.checkpoint_interval_or_default(), 10);
+        assert_eq!(
+            props.deleted_file_retention_duration_or_default(),
+            Duration::from_secs(7 * 24 * 3600)
+        );
+        assert_eq!(
+            props.log_retention_duration_or_default(),
+            Duration::from_secs(30 * 24 * 3600)
+        );
+        assert_eq!(
+            props.data_skipping_num_indexed_cols_or_default(),
+            DataSkippingNumIndexedCols::NumColumns(32)
+        );
+        assert_eq!(
+            props.column_mapping_mode_or_default(),
+            ColumnMappingMode::None
+        );
+        assert_eq!(
+            props.isolation_level_or_default(),
+            IsolationLevel::Serializable
+        );
+        assert_eq!(
+            props.checkpoint_policy_or_default(),
+            CheckpointPolicy::Classic
+        );
+
+        let props = TableProperties::from([
+            ("delta.appendOnly", "true"),
+            ("delta.checkpointInterval", "3"),
+            ("delta.deletedFileRetentionDuration", "interval 1 day"),
+            ("delta.dataSkippingNumIndexedCols", "-1"),
+            ("delta.columnMapping.mode", "name"),
+        ]);
+        assert!(props.is_append_only());
+        assert_eq!(props.checkpoint_interval_or_default(), 3);
+        assert_eq!(
+            props.deleted_file_retention_duration_or_default(),
+            Duration::from_secs(24 * 3600)
+        );
+        assert_eq!(
+            props.data_skipping_num_indexed_cols_or_default(),
+            DataSkippingNumIndexedCols::AllColumns
+        );
+        assert_eq!(
+            props.column_mapping_mode_or_default(),
+            ColumnMappingMode::Name
+        );
+    }
+
     #[test]
     fn allow_unknown_keys() {
         let properties = [("unknown_properties".to_string(), "two words".to_string())];
@@ -268,6 +458,11 @@ mod tests {
             ("delta.tuneFileSizesForRewrites", "true"),
             ("delta.checkpointPolicy", "v2"),
             ("delta.enableRowTracking", "true"),
+            ("delta.enableInCommitTimestamps", "true"),
+            ("delta.inCommitTimestampEnablementVersion", "15"),
+            ("delta.inCommitTimestampEnablementTimestamp", "1612345678"),
+            ("delta.enableIcebergCompatV1", "false"),
+            ("delta.enableIcebergCompatV2", "true"),
         ];
         let actual = TableProperties::from(properties.into_iter());
         let expected = TableProperties {
@@ -293,6 +488,11 @@ mod tests {
             tune_file_sizes_for_rewrites: Some(true),
             checkpoint_policy: Some(CheckpointPolicy::V2),
             enable_row_tracking: Some(true),
+            enable_in_commit_timestamps: Some(true),
+            in_commit_timestamp_enablement_version: Some(15),
+            in_commit_timestamp_enablement_timestamp: Some(1612345678),
+            enable_iceberg_compat_v1: Some(false),
+            enable_iceberg_compat_v2: Some(true),
             unknown_properties: HashMap::new(),
         };
         assert_eq!(actual, expected);
@@ -49,10 +49,11 @@ fn try_parse(props: &mut TableProperties, k: &str, v: &str) -> Option<()> {
             props.checkpoint_write_stats_as_struct = Some(parse_bool(v)?)
         }
         "delta.columnMapping.mode" => {
-            props.column_mapping_mode = ColumnMappingMode::try_from(v).ok()
+            props.column_mapping_mode = Some(ColumnMappingMode::try_from(v).ok()?)
         }
         "delta.dataSkippingNumIndexedCols" => {
-            props.data_skipping_num_indexed_cols = DataSkippingNumIndexedCols::try_from(v).ok()
+            props.data_skipping_num_indexed_cols =
+                Some(DataSkippingNumIndexedCols::try_from(v).ok()?)
         }
         "delta.dataSkippingStatsColumns" => {
             props.data_skipping_stats_columns = Some(parse_column_names(v)?)
@@ -62,7 +63,7 @@ fn try_parse(props: &mut TableProperties, k: &str, v: &str) -> Option<()> {
         }
         "delta.enableChangeDataFeed" => props.enable_change_data_feed = Some(parse_bool(v)?),
         "delta.enableDeletionVectors" => props.enable_deletion_vectors = Some(parse_bool(v)?),
-        "delta.isolationLevel" => props.isolation_level = IsolationLevel::try_from(v).ok(),
+        "delta.isolationLevel" => props.isolation_level = Some(IsolationLevel::try_from(v).ok()?),
         "delta.logRetentionDuration" => props.log_retention_duration = Some(parse_interval(v)?),
         "delta.enableExpiredLogCleanup" => props.enable_expired_log_cleanup = Some(parse_bool(v)?),
         "delta.randomizeFilePrefixes" => props.randomize_file_prefixes = Some(parse_bool(v)?),
@@ -74,8 +75,21 @@ fn try_parse(props: &mut TableProperties, k: &str, v: &str) -> Option<()> {
         "delta.tuneFileSizesForRewrites" => {
             props.tune_file_sizes_for_rewrites = Some(parse_bool(v)?)
         }
-        "delta.checkpointPolicy" => props.checkpoint_policy = CheckpointPolicy::try_from(v).ok(),
+        "delta.checkpointPolicy" => {
+            props.checkpoint_policy = Some(CheckpointPolicy::try_from(v).ok()?)
+        }
         "delta.enableRowTracking" => props.enable_row_tracking = Some(parse_bool(v)?),
+        "delta.enableInCommitTimestamps" => {
+            props.enable_in_commit_timestamps = Some(parse_bool(v)?)
+        }
+        "delta.inCommitTimestampEnablementVersion" => {
+            props.in_commit_timestamp_enablement_version = Some(parse_non_negative_int(v)?)
+        }
+        "delta.inCommitTimestampEnablementTimestamp" => {
+            props.in_commit_timestamp_enablement_timestamp = Some(v.parse().ok()?)
+        }
+        "delta.enableIcebergCompatV1" => props.enable_iceberg_compat_v1 = Some(parse_bool(v)?),
+        "delta.enableIcebergCompatV2" => props.enable_iceberg_compat_v2 = Some(parse_bool(v)?),
         _ => return None,
     }
     Some(())
@@ -89,6 +103,14 @@ pub(crate) fn parse_positive_int(s: &str) -> Option<NonZero<u64>> {
     NonZero::new(n.try_into().ok()?)
 }
 
+/// Deserialize a string representing a non-negative integer into an `Option<u64>`. Returns `Some`
+/// if successfully parses, and `None` otherwise.
+pub(crate) fn parse_non_negative_int(s: &str) -> Option<u64> {
+    // parse to i64 (then check n >= 0) since java doesn't even allow u64
+    let n: i64 = s.parse().ok()?;
+    n.try_into().ok()
+}
+
 /// Deserialize a string representing a boolean into an `Option<bool>`. Returns `Some` if
 /// successfully parses, and `None` otherwise.
 pub(crate) fn parse_bool(s: &str) -> Option<bool> {
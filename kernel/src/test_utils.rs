@@ -0,0 +1,412 @@
+//! Utilities for engines to test their integration with the kernel against tables which are built
+//! programmatically in memory, rather than golden tables checked into their repositories. Requires
+//! the `test-utils` feature.
+//!
+//! An [`InMemoryTable`] is a table in an in-memory object store, read and written by a
+//! [`DefaultEngine`] (or by the engine under test, through the [`FileSystemClient`] of the table).
+//! Its versions are committed as the JSON actions of the log, which are built with [`protocol`],
+//! [`metadata`], [`add`], [`remove`] and [`with_deletion_vector`], so that tests control exactly
+//! what the log of the table contains:
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use arrow_array::{Int32Array, RecordBatch};
+//! # use delta_kernel::schema::{DataType, StructField, StructType};
+//! # use delta_kernel::test_utils::{metadata, protocol, with_deletion_vector, InMemoryTable};
+//! # fn main() -> delta_kernel::DeltaResult<()> {
+//! let schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+//! let mut table = InMemoryTable::new();
+//! let deletion_vectors = Some(&["deletionVectors"][..]);
+//! table.commit([
+//!     protocol(3, 7, deletion_vectors, deletion_vectors),
+//!     metadata(&schema, &[], [("delta.enableDeletionVectors", "true")]),
+//! ])?;
+//!
+//! let batch = RecordBatch::try_from_iter([("id", Arc::new(Int32Array::from(vec![1, 2, 3])) as _)])?;
+//! let add = table.write_data("part-0.parquet", &batch)?;
+//! let deletion_vector = table.write_deletion_vector([1])?;
+//! table.commit([with_deletion_vector(add, &deletion_vector)])?;
+//! table.checkpoint()?;
+//!
+//! assert_eq!(table.snapshot()?.version(), 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DefaultEngine`]: crate::engine::default::DefaultEngine
+
+use std::sync::Arc;
+
+use arrow_array::RecordBatch;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+use object_store::path::Path;
+use parquet::arrow::ArrowWriter;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::actions::deletion_vector::{DeletionVector, DeletionVectorDescriptor};
+use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+use crate::engine::default::DefaultEngine;
+use crate::engine::stats::StatsCollector;
+use crate::expressions::ColumnName;
+use crate::path::resolve_file_path;
+use crate::schema::{ColumnMetadataKey, DataType, StructField, StructType};
+use crate::snapshot::Snapshot;
+use crate::{DeltaResult, Engine, FileSystemClient, Table, Version};
+
+/// A table in an in-memory object store, see the [module docs](self).
+pub struct InMemoryTable {
+    store: Arc<InMemory>,
+    engine: Arc<DefaultEngine<TokioBackgroundExecutor>>,
+    table_root: Url,
+    next_version: Version,
+}
+
+impl InMemoryTable {
+    /// Create an empty table (without any commits) at `memory:///`, in a new in-memory store.
+    pub fn new() -> Self {
+        let store = Arc::new(InMemory::new());
+        let engine = Arc::new(DefaultEngine::new(
+            store.clone(),
+            Path::from("/"),
+            Arc::new(TokioBackgroundExecutor::new()),
+        ));
+        Self {
+            store,
+            engine,
+            // a valid URL, so parsing it can't fail
+            table_root: Url::parse("memory:///").unwrap(),
+            next_version: 0,
+        }
+    }
+
+    /// The URL of the root of the table.
+    pub fn table_root(&self) -> &Url {
+        &self.table_root
+    }
+
+    /// The in-memory object store of the table, e.g. to create the engine under test with.
+    pub fn store(&self) -> Arc<InMemory> {
+        self.store.clone()
+    }
+
+    /// A default engine which reads and writes the in-memory store of the table.
+    pub fn engine(&self) -> Arc<DefaultEngine<TokioBackgroundExecutor>> {
+        self.engine.clone()
+    }
+
+    /// A [`FileSystemClient`] of the in-memory store of the table.
+    pub fn file_system_client(&self) -> Arc<dyn FileSystemClient> {
+        self.engine.get_file_system_client()
+    }
+
+    /// The table, e.g. to create snapshots of it with the engine under test.
+    pub fn table(&self) -> Table {
+        Table::new(self.table_root.clone())
+    }
+
+    /// The snapshot of the latest version of the table.
+    pub fn snapshot(&self) -> DeltaResult<Snapshot> {
+        self.table().snapshot(self.engine.as_ref(), None)
+    }
+
+    /// Commit `actions` (JSON objects such as `{"add": {...}}`) as the next version of the table,
+    /// and return the version.
+    pub fn commit(&mut self, actions: impl IntoIterator<Item = Value>) -> DeltaResult<Version> {
+        let version = self.next_version;
+        let mut commit = String::new();
+        for action in actions {
+            commit.push_str(&serde_json::to_string(&action)?);
+            commit.push('\n');
+        }
+        let path = self
+            .table_root
+            .join(&format!("_delta_log/{version:020}.json"))?;
+        self.file_system_client()
+            .write_file(&path, commit.into(), false)?;
+        self.next_version += 1;
+        Ok(version)
+    }
+
+    /// Write a classic checkpoint of the latest version of the table, see [`Snapshot::checkpoint`].
+    pub fn checkpoint(&self) -> DeltaResult<()> {
+        self.snapshot()?.checkpoint(self.engine.as_ref())
+    }
+
+    /// Write `batch` as a parquet data file at `path` (relative to the table root), and return an
+    /// [`add`] action for it, with the stats of its top-level columns. The columns of the batch
+    /// must have the physical names of the columns of the table.
+    pub fn write_data(&self, path: &str, batch: &RecordBatch) -> DeltaResult<Value> {
+        let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), None)?;
+        writer.write(batch)?;
+        let data = writer.into_inner()?;
+        let size = data.len();
+        self.write_file(path, data.into())?;
+
+        let columns: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| ColumnName::new([field.name()]))
+            .collect();
+        let mut stats = StatsCollector::new(&columns);
+        stats.update(batch)?;
+        let mut action = add(path, size as u64);
+        action["add"]["stats"] = stats.finish()?.into();
+        Ok(action)
+    }
+
+    /// Write a deletion vector file which deletes the rows at `row_indexes`, and return its
+    /// descriptor, e.g. for [`with_deletion_vector`].
+    pub fn write_deletion_vector(
+        &self,
+        row_indexes: impl IntoIterator<Item = u64>,
+    ) -> DeltaResult<DeletionVectorDescriptor> {
+        let deletion_vector = DeletionVector::from_iter(row_indexes);
+        DeletionVectorDescriptor::write(
+            &deletion_vector,
+            self.file_system_client().as_ref(),
+            &self.table_root,
+        )
+    }
+
+    /// Write `data` to the file at `path` (relative to the table root), e.g. a corrupt file.
+    pub fn write_file(&self, path: &str, data: Bytes) -> DeltaResult<()> {
+        let url = resolve_file_path(&self.table_root, path)?;
+        self.file_system_client().write_file(&url, data, true)
+    }
+}
+
+impl Default for InMemoryTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `protocol` action. Reader (writer) features must be given for reader (writer) version 3 (7).
+pub fn protocol(
+    min_reader_version: i32,
+    min_writer_version: i32,
+    reader_features: Option<&[&str]>,
+    writer_features: Option<&[&str]>,
+) -> Value {
+    let mut protocol = json!({
+        "minReaderVersion": min_reader_version,
+        "minWriterVersion": min_writer_version,
+    });
+    if let Some(reader_features) = reader_features {
+        protocol["readerFeatures"] = json!(reader_features);
+    }
+    if let Some(writer_features) = writer_features {
+        protocol["writerFeatures"] = json!(writer_features);
+    }
+    json!({ "protocol": protocol })
+}
+
+/// A `metaData` action for a table with `schema`, partitioned by `partition_columns` and with the
+/// table properties `configuration`. The ID of the table is the nil UUID, so that the action is
+/// deterministic.
+pub fn metadata<'a>(
+    schema: &StructType,
+    partition_columns: &[&str],
+    configuration: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Value {
+    let configuration: serde_json::Map<_, _> = configuration
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.into()))
+        .collect();
+    json!({
+        "metaData": {
+            "id": uuid::Uuid::nil().to_string(),
+            "format": { "provider": "parquet", "options": {} },
+            // serializing a schema can't fail
+            "schemaString": serde_json::to_string(schema).unwrap(),
+            "partitionColumns": partition_columns,
+            "configuration": configuration,
+            "createdTime": 0,
+        }
+    })
+}
+
+/// An `add` action for the data file at `path` with `size` bytes, without stats or partition
+/// values. See [`InMemoryTable::write_data`] to write a data file.
+pub fn add(path: &str, size: u64) -> Value {
+    json!({
+        "add": {
+            "path": path,
+            "partitionValues": {},
+            "size": size,
+            "modificationTime": 0,
+            "dataChange": true,
+        }
+    })
+}
+
+/// A `remove` action for the data file at `path`.
+pub fn remove(path: &str) -> Value {
+    json!({
+        "remove": {
+            "path": path,
+            "deletionTimestamp": 0,
+            "dataChange": true,
+        }
+    })
+}
+
+/// The `add` (or `remove`) action `action`, with `deletion_vector`.
+pub fn with_deletion_vector(
+    mut action: Value,
+    deletion_vector: &DeletionVectorDescriptor,
+) -> Value {
+    if let Some(file_action) = action
+        .as_object_mut()
+        .and_then(|action| action.values_mut().next())
+    {
+        // serializing a descriptor can't fail
+        file_action["deletionVector"] = serde_json::to_value(deletion_vector).unwrap();
+    }
+    action
+}
+
+/// Annotate the (nested) fields of `schema` with column mapping IDs (1, 2, ... in depth-first
+/// order) and physical names (`col-1`, `col-2`, ...), for a table with column mapping. The table
+/// also needs the `delta.columnMapping.mode` and `delta.columnMapping.maxColumnId` properties
+/// (the latter is the number of fields), and a protocol which supports column mapping.
+pub fn with_column_mapping(schema: &StructType) -> DeltaResult<StructType> {
+    let mut next_id = 0;
+    annotate_struct(schema, &mut next_id)
+}
+
+fn annotate_struct(struct_type: &StructType, next_id: &mut i32) -> DeltaResult<StructType> {
+    StructType::try_new(struct_type.fields().map(|field| {
+        *next_id += 1;
+        let id = *next_id;
+        let data_type = match field.data_type() {
+            DataType::Struct(nested) => annotate_struct(nested, next_id)?.into(),
+            data_type => data_type.clone(),
+        };
+        let mut field = StructField {
+            data_type,
+            ..field.clone()
+        };
+        field.metadata.insert(
+            ColumnMetadataKey::ColumnMappingId.as_ref().to_string(),
+            id.into(),
+        );
+        field.metadata.insert(
+            ColumnMetadataKey::ColumnMappingPhysicalName
+                .as_ref()
+                .to_string(),
+            format!("col-{id}").into(),
+        );
+        Ok(field)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int32Type;
+    use arrow_array::{ArrayRef, Int32Array};
+
+    use super::*;
+    use crate::engine::arrow_data::ArrowEngineData;
+
+    fn batch(columns: Vec<(&str, Vec<i32>)>) -> RecordBatch {
+        RecordBatch::try_from_iter(
+            columns
+                .into_iter()
+                .map(|(name, values)| (name, Arc::new(Int32Array::from(values)) as ArrayRef)),
+        )
+        .unwrap()
+    }
+
+    // The values of the first column of the selected rows of the latest version of the table
+    fn read_values(table: &InMemoryTable) -> Vec<i32> {
+        let scan = table
+            .snapshot()
+            .unwrap()
+            .into_scan_builder()
+            .build()
+            .unwrap();
+        let mut values = vec![];
+        for result in scan.execute(table.engine()).unwrap() {
+            let result = result.unwrap();
+            let mask = result.full_mask();
+            let data = ArrowEngineData::try_from_engine_data(result.raw_data.unwrap()).unwrap();
+            let column = data.record_batch().column(0).as_primitive::<Int32Type>();
+            for (row, value) in column.values().iter().enumerate() {
+                if mask.as_ref().map_or(true, |mask| mask[row]) {
+                    values.push(*value);
+                }
+            }
+        }
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn test_in_memory_table() {
+        let schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+        let features = Some(&["deletionVectors"][..]);
+        let mut table = InMemoryTable::new();
+        table
+            .commit([
+                protocol(3, 7, features, features),
+                metadata(&schema, &[], []),
+            ])
+            .unwrap();
+        let first = table.write_data("a.parquet", &batch(vec![("id", vec![1, 2, 3])]));
+        let second = table.write_data("b.parquet", &batch(vec![("id", vec![4, 5])]));
+        table.commit([first.unwrap(), second.unwrap()]).unwrap();
+        assert_eq!(read_values(&table), [1, 2, 3, 4, 5]);
+
+        let deletion_vector = table.write_deletion_vector([0, 2]).unwrap();
+        let first = table.write_data("a.parquet", &batch(vec![("id", vec![1, 2, 3])]));
+        let version = table
+            .commit([
+                remove("a.parquet"),
+                with_deletion_vector(first.unwrap(), &deletion_vector),
+                remove("b.parquet"),
+            ])
+            .unwrap();
+        assert_eq!(version, 2);
+        table.checkpoint().unwrap();
+        let snapshot = table.snapshot().unwrap();
+        assert_eq!(snapshot.log_segment.checkpoint_parts.len(), 1);
+        assert_eq!(read_values(&table), [2]);
+    }
+
+    #[test]
+    fn test_in_memory_table_with_column_mapping() {
+        let schema = StructType::new([
+            StructField::new("id", DataType::INTEGER, true),
+            StructField::new(
+                "s",
+                DataType::struct_type([StructField::new("x", DataType::INTEGER, true)]),
+                true,
+            ),
+        ]);
+        let schema = with_column_mapping(&schema).unwrap();
+        let physical_names: Vec<_> = schema.fields().map(|f| f.physical_name()).collect();
+        assert_eq!(physical_names, ["col-1", "col-2"]);
+
+        let mut table = InMemoryTable::new();
+        let configuration = [
+            ("delta.columnMapping.mode", "name"),
+            ("delta.columnMapping.maxColumnId", "3"),
+        ];
+        let data = table
+            .write_data("part.parquet", &batch(vec![("col-1", vec![7, 8])]))
+            .unwrap();
+        table
+            .commit([
+                protocol(2, 5, None, None),
+                metadata(&schema, &[], configuration),
+                data,
+            ])
+            .unwrap();
+        assert_eq!(read_values(&table), [7, 8]);
+    }
+}
@@ -0,0 +1,329 @@
+//! Conflict detection for transactions that lost the race to commit a version. Following the
+//! optimistic concurrency control of the Delta protocol, the actions of each winning commit are
+//! classified, and the transaction is retried at the next version unless they conflict with it. See
+//! <https://docs.delta.io/latest/concurrency-control.html#write-conflicts>
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use tracing::debug;
+use url::Url;
+
+use super::Transaction;
+use crate::actions::{
+    get_log_schema, ADD_NAME, COMMIT_INFO_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME,
+    REMOVE_NAME, SET_TRANSACTION_NAME,
+};
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::path::ParsedLogPath;
+use crate::schema::{column_name, ColumnName, ColumnNamesAndTypes, DataType};
+use crate::utils::require;
+use crate::{DeltaResult, Engine, Error, FileMeta, Version};
+
+/// The reason a transaction could not be committed after losing the race to commit a version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictType {
+    /// A winning commit changed the protocol of the table
+    ProtocolChanged,
+    /// A winning commit changed the metadata (e.g. the schema) of the table
+    MetadataChanged,
+    /// A winning commit changed a metadata domain that the transaction also changes
+    ConcurrentDomainMetadata(String),
+    /// A winning commit removed a file (e.g. deleted rows from it) that the transaction also
+    /// removes
+    ConcurrentDeleteDelete(String),
+    /// A winning commit committed a version of an application transaction (e.g. a batch of a
+    /// streaming query) with the same app ID as the transaction
+    ConcurrentTransaction(String),
+}
+
+/// The changes made by a commit that won the race against a transaction, classified by kind.
+#[derive(Debug, Default)]
+pub(crate) struct WinningCommitSummary {
+    /// The number of files added by the commit (concurrent appends)
+    num_added_files: usize,
+    /// The paths of the files removed by the commit (concurrent deletes)
+    removed_files: HashSet<String>,
+    metadata_changed: bool,
+    protocol_changed: bool,
+    /// The metadata domains changed (or removed) by the commit
+    domains: HashSet<String>,
+    /// The app IDs of the application transactions committed by the commit
+    app_ids: HashSet<String>,
+    pub(crate) in_commit_timestamp: Option<i64>,
+}
+
+impl WinningCommitSummary {
+    /// Read and classify the actions of the commit at `version`.
+    pub(crate) fn try_new(
+        engine: &dyn Engine,
+        table_root: &Url,
+        version: Version,
+    ) -> DeltaResult<Self> {
+        let commit = ParsedLogPath::new_commit(table_root, version)?;
+        let schema = get_log_schema().project(&[
+            ADD_NAME,
+            REMOVE_NAME,
+            METADATA_NAME,
+            PROTOCOL_NAME,
+            DOMAIN_METADATA_NAME,
+            SET_TRANSACTION_NAME,
+            COMMIT_INFO_NAME,
+        ])?;
+        let batches = engine.get_json_handler().read_json_files(
+            &[FileMeta::new(commit.location, 0, 0)],
+            schema,
+            None,
+        )?;
+        let mut visitor = WinningCommitVisitor::default();
+        for batch in batches {
+            visitor.visit_rows_of(batch?.as_ref())?;
+        }
+        Ok(visitor.summary)
+    }
+}
+
+/// Find the first conflict between `transaction` and the changes of a winning commit, if any.
+///
+/// Appends of the transaction do not read the table, i.e. they are blind appends, so concurrent
+/// appends and deletes never conflict with them. Files removed by the transaction (e.g. to delete
+/// rows from them) conflict with concurrent removes of the same files, but not with concurrent
+/// appends. Metadata changes of the transaction keep the schema compatible with existing data, so
+/// they do not conflict with concurrent appends either. Changes to the protocol or metadata always
+/// conflict, as do changes to any metadata domain that the transaction also changes, and commits
+/// of application transactions with an app ID that the transaction also commits.
+pub(crate) fn find_conflict(
+    transaction: &Transaction,
+    winning_commit: &WinningCommitSummary,
+) -> Option<ConflictType> {
+    if winning_commit.protocol_changed {
+        return Some(ConflictType::ProtocolChanged);
+    }
+    if winning_commit.metadata_changed {
+        return Some(ConflictType::MetadataChanged);
+    }
+    if let Some((domain, _)) = transaction
+        .domain_metadata
+        .iter()
+        .find(|(domain, _)| winning_commit.domains.contains(domain))
+    {
+        return Some(ConflictType::ConcurrentDomainMetadata(domain.clone()));
+    }
+    if let Some(app_id) = transaction
+        .set_transactions
+        .keys()
+        .find(|app_id| winning_commit.app_ids.contains(*app_id))
+    {
+        return Some(ConflictType::ConcurrentTransaction(app_id.clone()));
+    }
+    if let Some(path) = transaction
+        .removed_files
+        .keys()
+        .find(|path| winning_commit.removed_files.contains(*path))
+    {
+        return Some(ConflictType::ConcurrentDeleteDelete(path.clone()));
+    }
+    debug!(
+        "Winning commit added {} and removed {} files, which does not conflict with the transaction",
+        winning_commit.num_added_files,
+        winning_commit.removed_files.len()
+    );
+    None
+}
+
+#[derive(Default)]
+struct WinningCommitVisitor {
+    summary: WinningCommitSummary,
+}
+
+impl RowVisitor for WinningCommitVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            (
+                vec![
+                    column_name!("add.path"),
+                    column_name!("remove.path"),
+                    column_name!("metaData.id"),
+                    column_name!("protocol.minReaderVersion"),
+                    column_name!("domainMetadata.domain"),
+                    column_name!("txn.appId"),
+                    column_name!("commitInfo.inCommitTimestamp"),
+                ],
+                vec![
+                    DataType::STRING,
+                    DataType::STRING,
+                    DataType::STRING,
+                    DataType::INTEGER,
+                    DataType::STRING,
+                    DataType::STRING,
+                    DataType::LONG,
+                ],
+            )
+                .into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 7,
+            Error::InternalError(format!(
+                "Wrong number of WinningCommitVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        let summary = &mut self.summary;
+        for i in 0..row_count {
+            let add_path: Option<String> = getters[0].get_opt(i, "add.path")?;
+            if add_path.is_some() {
+                summary.num_added_files += 1;
+            }
+            if let Some(path) = getters[1].get_opt(i, "remove.path")? {
+                summary.removed_files.insert(path);
+            }
+            let metadata_id: Option<String> = getters[2].get_opt(i, "metaData.id")?;
+            summary.metadata_changed |= metadata_id.is_some();
+            let min_reader_version: Option<i32> =
+                getters[3].get_opt(i, "protocol.minReaderVersion")?;
+            summary.protocol_changed |= min_reader_version.is_some();
+            if let Some(domain) = getters[4].get_opt(i, "domainMetadata.domain")? {
+                summary.domains.insert(domain);
+            }
+            if let Some(app_id) = getters[5].get_opt(i, "txn.appId")? {
+                summary.app_ids.insert(app_id);
+            }
+            if let Some(timestamp) = getters[6].get_opt(i, "commitInfo.inCommitTimestamp")? {
+                summary.in_commit_timestamp = Some(timestamp);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{
+        Add, CommitInfo, DomainMetadata, Metadata, Protocol, Remove, SetTransaction,
+    };
+    use crate::engine::sync::SyncEngine;
+    use crate::schema::{StructField, StructType};
+    use crate::table_features::WriterFeatures;
+    use crate::utils::test_utils::{Action, LocalMockTable};
+    use crate::Table;
+
+    #[tokio::test]
+    async fn test_winning_commit_summary() {
+        let schema = StructType::new([StructField::new("id", DataType::INTEGER, true)]);
+        let metadata = Metadata {
+            schema_string: serde_json::to_string(&schema).unwrap(),
+            ..Default::default()
+        };
+        let protocol = Protocol::try_new(
+            3,
+            7,
+            Some(Vec::<String>::new()),
+            Some([WriterFeatures::DomainMetadata]),
+        )
+        .unwrap();
+        let mut mock_table = LocalMockTable::new();
+        mock_table
+            .commit([
+                Action::Protocol(protocol.clone()),
+                Action::Metadata(metadata.clone()),
+            ])
+            .await;
+        mock_table
+            .commit([
+                Action::CommitInfo(CommitInfo {
+                    in_commit_timestamp: Some(1000),
+                    ..Default::default()
+                }),
+                Action::Add(Add {
+                    path: "a.parquet".into(),
+                    data_change: true,
+                    ..Default::default()
+                }),
+                Action::Add(Add {
+                    path: "b.parquet".into(),
+                    data_change: true,
+                    ..Default::default()
+                }),
+                Action::Remove(Remove {
+                    path: "c.parquet".into(),
+                    data_change: true,
+                    ..Default::default()
+                }),
+                Action::DomainMetadata(DomainMetadata {
+                    domain: "app1".into(),
+                    configuration: "{}".into(),
+                    removed: false,
+                }),
+                Action::SetTransaction(SetTransaction {
+                    app_id: "stream1".into(),
+                    version: 7,
+                    last_updated: None,
+                }),
+            ])
+            .await;
+        mock_table.commit([Action::Metadata(metadata)]).await;
+        mock_table.commit([Action::Protocol(protocol)]).await;
+
+        let engine = SyncEngine::new();
+        let table_root = url::Url::from_directory_path(mock_table.table_root()).unwrap();
+        let table = Table::new(table_root.clone());
+
+        let summary = WinningCommitSummary::try_new(&engine, &table_root, 1).unwrap();
+        assert_eq!(summary.num_added_files, 2);
+        assert_eq!(summary.removed_files, HashSet::from(["c.parquet".into()]));
+        assert_eq!(summary.domains, HashSet::from(["app1".into()]));
+        assert_eq!(summary.app_ids, HashSet::from(["stream1".into()]));
+        assert_eq!(summary.in_commit_timestamp, Some(1000));
+        assert!(!summary.metadata_changed && !summary.protocol_changed);
+
+        // a blind append only conflicts with changes to the domains it changes
+        let snapshot = table.snapshot(&engine, Some(0)).unwrap();
+        let mut txn = Transaction::try_new(snapshot).unwrap();
+        assert_eq!(find_conflict(&txn, &summary), None);
+        txn.set_domain_metadata("app1", "{}").unwrap();
+        assert_eq!(
+            find_conflict(&txn, &summary),
+            Some(ConflictType::ConcurrentDomainMetadata("app1".into()))
+        );
+
+        // committing an application transaction conflicts with concurrent commits of the same app
+        let txn = Transaction::try_new(table.snapshot(&engine, Some(0)).unwrap()).unwrap();
+        let txn = txn.with_transaction_id("stream2", 8);
+        assert_eq!(find_conflict(&txn, &summary), None);
+        let txn = txn.with_transaction_id("stream1", 8);
+        assert_eq!(
+            find_conflict(&txn, &summary),
+            Some(ConflictType::ConcurrentTransaction("stream1".into()))
+        );
+
+        // removing a file conflicts with concurrent removes of the same file
+        let mut txn = Transaction::try_new(table.snapshot(&engine, Some(0)).unwrap()).unwrap();
+        for path in ["a.parquet", "c.parquet"] {
+            let remove = Remove {
+                path: path.into(),
+                data_change: true,
+                ..Default::default()
+            };
+            txn.removed_files.insert(path.into(), (remove, None));
+        }
+        assert_eq!(
+            find_conflict(&txn, &summary),
+            Some(ConflictType::ConcurrentDeleteDelete("c.parquet".into()))
+        );
+
+        let summary = WinningCommitSummary::try_new(&engine, &table_root, 2).unwrap();
+        assert_eq!(
+            find_conflict(&txn, &summary),
+            Some(ConflictType::MetadataChanged)
+        );
+        let summary = WinningCommitSummary::try_new(&engine, &table_root, 3).unwrap();
+        assert_eq!(
+            find_conflict(&txn, &summary),
+            Some(ConflictType::ProtocolChanged)
+        );
+    }
+}
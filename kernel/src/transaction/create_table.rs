@@ -0,0 +1,246 @@
+//! Creation of new tables, by committing version 0 of the table with the protocol and metadata
+//! derived from the table's schema, partition columns and properties.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use url::Url;
+
+use super::metadata_update::{
+    split_feature_properties, upgrade_protocol, validate_table_properties,
+};
+use super::{current_time_ms, json_action, KERNEL_VERSION};
+use crate::actions::{
+    CommitInfo, Format, Metadata, COMMIT_INFO_NAME, METADATA_NAME, PROTOCOL_NAME,
+};
+use crate::path::ParsedLogPath;
+use crate::schema::{DataType, SchemaRef, StructField, StructType};
+use crate::snapshot::Snapshot;
+use crate::table_features::{
+    validate_iceberg_compat, validate_schema_column_mapping,
+    validate_timestamp_ntz_feature_support, validate_type_changes,
+    validate_variant_type_feature_support,
+};
+use crate::table_properties::TableProperties;
+use crate::{DeltaResult, Engine, Error};
+
+const CREATE_TABLE_OPERATION: &str = "CREATE TABLE";
+
+/// Create a table at `table_root` by committing version 0 with a protocol and metadata, and
+/// return the snapshot of the new table. See [`Table::create`].
+///
+/// [`Table::create`]: crate::Table::create
+pub(crate) fn create_table(
+    engine: &dyn Engine,
+    table_root: &Url,
+    schema: SchemaRef,
+    partition_columns: Vec<String>,
+    properties: HashMap<String, String>,
+) -> DeltaResult<Snapshot> {
+    validate_partition_columns(&schema, &partition_columns)?;
+    let (configuration, features) = split_feature_properties(properties)?;
+    validate_table_properties(&configuration)?;
+    let table_properties = TableProperties::from(configuration.iter());
+    let protocol = upgrade_protocol(None, features, &table_properties, &schema)?;
+
+    // the new table must be readable and writable by the kernel, just like existing tables
+    protocol.ensure_read_supported()?;
+    protocol.ensure_write_supported()?;
+    validate_schema_column_mapping(&schema, table_properties.column_mapping_mode_or_default())?;
+    validate_timestamp_ntz_feature_support(&schema, &protocol)?;
+    validate_variant_type_feature_support(&schema, &protocol)?;
+    validate_type_changes(&schema, &protocol)?;
+    validate_iceberg_compat(&protocol, &table_properties, &schema)?;
+
+    let timestamp = current_time_ms()?;
+    let commit_info = CommitInfo {
+        // in-commit timestamps enabled at creation are not recorded with enablement properties
+        in_commit_timestamp: table_properties
+            .is_in_commit_timestamps_enabled()
+            .then_some(timestamp),
+        timestamp: Some(timestamp),
+        operation: Some(CREATE_TABLE_OPERATION.to_string()),
+        operation_parameters: Some(HashMap::from([
+            (
+                "partitionBy".to_string(),
+                serde_json::to_string(&partition_columns)?,
+            ),
+            (
+                "properties".to_string(),
+                serde_json::to_string(&configuration)?,
+            ),
+        ])),
+        kernel_version: Some(format!("v{KERNEL_VERSION}")),
+        engine_info: None,
+        engine_commit_info: None,
+    };
+    let metadata = Metadata {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: None,
+        description: None,
+        format: Format::default(),
+        schema_string: serde_json::to_string(&schema)?,
+        partition_columns,
+        created_time: Some(timestamp),
+        configuration,
+    };
+    // the actions are evaluated as JSON literals against a batch with exactly one row
+    let one_row = engine.get_expression_handler().create_one(
+        Arc::new(StructType::new([StructField::new(
+            "version",
+            DataType::LONG,
+            false,
+        )])),
+        &[0i64.into()],
+    )?;
+    let actions = [
+        json_action(engine, COMMIT_INFO_NAME, commit_info, one_row.as_ref()),
+        json_action(engine, PROTOCOL_NAME, protocol, one_row.as_ref()),
+        json_action(engine, METADATA_NAME, metadata, one_row.as_ref()),
+    ];
+
+    let commit_path = ParsedLogPath::new_commit(table_root, 0)?;
+    let json_handler = engine.get_json_handler();
+    match json_handler.write_json_file(&commit_path.location, Box::new(actions.into_iter()), false)
+    {
+        Ok(()) => Snapshot::try_new(table_root.clone(), engine, Some(0)),
+        Err(Error::FileAlreadyExists(_)) => Err(Error::generic(format!(
+            "Cannot create table: a table already exists at {table_root}"
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+// Partition columns must be distinct, non-nested columns of the schema with a primitive type, and
+// at least one column of the schema must not be a partition column.
+fn validate_partition_columns(
+    schema: &StructType,
+    partition_columns: &[String],
+) -> DeltaResult<()> {
+    let mut seen = HashSet::new();
+    for column in partition_columns {
+        let field = schema
+            .field(column)
+            .ok_or_else(|| Error::generic(format!("Partition column '{column}' not in schema")))?;
+        if !matches!(field.data_type(), DataType::Primitive(_)) {
+            return Err(Error::generic(format!(
+                "Partition column '{column}' must have a primitive type, not {}",
+                field.data_type()
+            )));
+        }
+        if !seen.insert(column) {
+            return Err(Error::generic(format!(
+                "Partition column '{column}' given more than once"
+            )));
+        }
+    }
+    if schema.fields().count() == seen.len() {
+        return Err(Error::generic(
+            "Cannot partition a table by all of its columns",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::table_features::WriterFeatures;
+
+    fn schema() -> SchemaRef {
+        Arc::new(StructType::new([
+            StructField::new("id", DataType::LONG, true),
+            StructField::new("date", DataType::DATE, true),
+            StructField::new(
+                "s",
+                StructType::new([StructField::new("a", DataType::STRING, true)]),
+                true,
+            ),
+        ]))
+    }
+
+    fn properties<const N: usize>(properties: [(&str, &str); N]) -> HashMap<String, String> {
+        properties
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_create_table() {
+        let engine = SyncEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = Url::from_directory_path(dir.path()).unwrap();
+        let snapshot = create_table(
+            &engine,
+            &table_root,
+            schema(),
+            vec!["date".to_string()],
+            properties([
+                ("delta.enableInCommitTimestamps", "true"),
+                ("delta.feature.domainMetadata", "supported"),
+                ("myapp.owner", "me"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(snapshot.version(), 0);
+        assert_eq!(snapshot.schema(), schema().as_ref());
+        assert_eq!(snapshot.metadata().partition_columns, ["date"]);
+        assert_eq!(
+            snapshot.metadata().configuration,
+            properties([
+                ("delta.enableInCommitTimestamps", "true"),
+                ("myapp.owner", "me"),
+            ])
+        );
+        let protocol = snapshot.protocol();
+        assert_eq!(protocol.reader_features(), Some(&[][..]));
+        assert!(protocol.has_writer_feature(&WriterFeatures::DomainMetadata));
+        assert!(protocol.has_writer_feature(&WriterFeatures::InCommitTimestamp));
+        assert!(snapshot.is_in_commit_timestamps_enabled());
+        assert!(snapshot.timestamp(&engine).is_ok());
+
+        let err = create_table(&engine, &table_root, schema(), vec![], HashMap::new());
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("a table already exists"));
+    }
+
+    #[test]
+    fn test_create_table_validation() {
+        let engine = SyncEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = Url::from_directory_path(dir.path()).unwrap();
+        let create = |partition_columns: &[&str], properties: HashMap<String, String>| {
+            let partition_columns = partition_columns.iter().map(|c| c.to_string()).collect();
+            create_table(
+                &engine,
+                &table_root,
+                schema(),
+                partition_columns,
+                properties,
+            )
+            .unwrap_err()
+            .to_string()
+        };
+
+        assert!(create(&["missing"], HashMap::new()).contains("not in schema"));
+        assert!(create(&["s"], HashMap::new()).contains("must have a primitive type"));
+        assert!(create(&["id", "id"], HashMap::new()).contains("more than once"));
+        assert!(create(&["id", "date", "s.a"], HashMap::new()).contains("not in schema"));
+
+        let props = properties([("delta.checkpointInterval", "-1")]);
+        assert!(create(&[], props).contains("'delta.checkpointInterval'"));
+        let props = properties([("delta.feature.notAFeature", "supported")]);
+        assert!(create(&[], props).contains("Unknown table feature"));
+        let props = properties([("delta.feature.domainMetadata", "disabled")]);
+        assert!(create(&[], props).contains("expected 'supported' or 'enabled'"));
+        // the kernel cannot write tables with change data feed yet
+        let props = properties([("delta.enableChangeDataFeed", "true")]);
+        assert!(create(&[], props).contains("changeDataFeed"));
+
+        // nothing was written by the failed attempts
+        assert!(!dir.path().join("_delta_log").exists());
+    }
+}
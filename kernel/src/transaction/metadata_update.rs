@@ -0,0 +1,584 @@
+//! Validation of the metadata changes made by transactions: schema changes must stay compatible
+//! with the existing data of the table, table properties must be valid, and the protocol must
+//! support every table feature required by the new schema and properties.
+use std::collections::{HashMap, HashSet};
+
+use crate::actions::Protocol;
+use crate::schema::{
+    ArrayType, ColumnMetadataKey, ColumnName, DataType, MapType, MetadataValue, StructField,
+    StructType,
+};
+use crate::table_features::{
+    schema_contains_timestamp_ntz, schema_contains_variant, ColumnMappingMode, ReaderFeatures,
+    WriterFeatures,
+};
+use crate::table_properties::TableProperties;
+use crate::{DeltaResult, Error};
+
+// properties of the form `delta.feature.<name> = supported` add a feature to the table's protocol
+pub(super) const FEATURE_PROPERTY_PREFIX: &str = "delta.feature.";
+pub(super) const MAX_COLUMN_ID_PROPERTY: &str = "delta.columnMapping.maxColumnId";
+
+/// Ensure that every `delta.*` property is known and has a valid value. Unlike when reading a
+/// table, invalid properties are not ignored when writing them.
+pub(super) fn validate_table_properties(properties: &HashMap<String, String>) -> DeltaResult<()> {
+    let table_properties = TableProperties::from(properties.iter());
+    match table_properties
+        .unknown_properties
+        .keys()
+        .find(|key| key.starts_with("delta."))
+    {
+        Some(key) => Err(Error::generic(format!(
+            "Invalid value '{}' of table property '{key}'",
+            properties[key]
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Separate the `delta.feature.<name>` properties from the table configuration, returning the
+/// named features along with the remaining configuration.
+pub(super) fn split_feature_properties(
+    properties: HashMap<String, String>,
+) -> DeltaResult<(HashMap<String, String>, Vec<WriterFeatures>)> {
+    let mut features = vec![];
+    let mut configuration = HashMap::new();
+    for (key, value) in properties {
+        let Some(name) = key.strip_prefix(FEATURE_PROPERTY_PREFIX) else {
+            configuration.insert(key, value);
+            continue;
+        };
+        if value != "supported" && value != "enabled" {
+            return Err(Error::generic(format!(
+                "Invalid value '{value}' of table property '{key}': expected 'supported' or \
+                 'enabled'"
+            )));
+        }
+        let feature = name
+            .parse()
+            .map_err(|_| Error::unsupported(format!("Unknown table feature '{name}'")))?;
+        features.push(feature);
+    }
+    Ok((configuration, features))
+}
+
+/// Upgrade `protocol` (or, for a new table, an empty protocol) to support the requested
+/// `features`, along with the features required by the table properties and the schema. Writer
+/// features that are also reader features (i.e. reader-writer features) are added to the reader
+/// features too.
+pub(super) fn upgrade_protocol(
+    protocol: Option<&Protocol>,
+    mut features: Vec<WriterFeatures>,
+    table_properties: &TableProperties,
+    schema: &StructType,
+) -> DeltaResult<Protocol> {
+    let enabled = |property: Option<bool>| property == Some(true);
+    let has_variant_preview =
+        protocol.is_some_and(|p| p.has_writer_feature(&WriterFeatures::VariantTypePreview));
+    let implied_features = [
+        (
+            enabled(table_properties.append_only),
+            WriterFeatures::AppendOnly,
+        ),
+        (
+            enabled(table_properties.enable_change_data_feed),
+            WriterFeatures::ChangeDataFeed,
+        ),
+        (
+            enabled(table_properties.enable_deletion_vectors),
+            WriterFeatures::DeletionVectors,
+        ),
+        (
+            enabled(table_properties.enable_in_commit_timestamps),
+            WriterFeatures::InCommitTimestamp,
+        ),
+        (
+            enabled(table_properties.enable_iceberg_compat_v1),
+            WriterFeatures::IcebergCompatV1,
+        ),
+        (
+            enabled(table_properties.enable_iceberg_compat_v2),
+            WriterFeatures::IcebergCompatV2,
+        ),
+        (
+            enabled(table_properties.enable_row_tracking),
+            WriterFeatures::RowTracking,
+        ),
+        (
+            enabled(table_properties.enable_row_tracking),
+            WriterFeatures::DomainMetadata,
+        ),
+        (
+            matches!(
+                table_properties.column_mapping_mode,
+                Some(ColumnMappingMode::Name | ColumnMappingMode::Id)
+            ),
+            WriterFeatures::ColumnMapping,
+        ),
+        (
+            schema_contains_timestamp_ntz(schema),
+            WriterFeatures::TimestampWithoutTimezone,
+        ),
+        (
+            schema_contains_variant(schema) && !has_variant_preview,
+            WriterFeatures::VariantType,
+        ),
+    ];
+    features.extend(
+        implied_features
+            .into_iter()
+            .filter_map(|(implied, feature)| implied.then_some(feature)),
+    );
+
+    let mut writer_features: Vec<String> = protocol
+        .and_then(|p| p.writer_features())
+        .unwrap_or_default()
+        .to_vec();
+    let mut reader_features: Vec<String> = protocol
+        .and_then(|p| p.reader_features())
+        .unwrap_or_default()
+        .to_vec();
+    for feature in features {
+        let name = feature.to_string();
+        if name.parse::<ReaderFeatures>().is_ok() && !reader_features.contains(&name) {
+            reader_features.push(name.clone());
+        }
+        if !writer_features.contains(&name) {
+            writer_features.push(name);
+        }
+    }
+    Protocol::try_new(3, 7, Some(reader_features), Some(writer_features))
+}
+
+/// Evolve the table schema `current` into `new`. The new schema must be compatible with the
+/// existing data of the table, so columns may be added (if nullable) and made nullable, but not
+/// dropped, made non-nullable, or changed to a different type. With column mapping enabled,
+/// columns of `new` are matched to those of `current` by their physical names, so columns may
+/// also be renamed; added columns are assigned a physical name and a column ID, the largest of
+/// which is tracked in `max_column_id`.
+pub(super) fn evolve_schema(
+    current: &StructType,
+    new: &StructType,
+    column_mapping_mode: ColumnMappingMode,
+    max_column_id: &mut i64,
+) -> DeltaResult<StructType> {
+    let mut evolution = SchemaEvolution {
+        column_mapping_mode,
+        max_column_id: *max_column_id,
+        path: vec![],
+    };
+    let schema = evolution.evolve_struct(current, new)?;
+    *max_column_id = evolution.max_column_id;
+    Ok(schema)
+}
+
+/// The largest column ID assigned to any (possibly nested) field of `schema`, or 0 if none is.
+pub(super) fn max_column_id(schema: &StructType) -> i64 {
+    fn max_in_type(data_type: &DataType) -> i64 {
+        match data_type {
+            DataType::Struct(s) => max_in_struct(s),
+            DataType::Array(a) => max_in_type(a.element_type()),
+            DataType::Map(m) => max_in_type(m.key_type()).max(max_in_type(m.value_type())),
+            _ => 0,
+        }
+    }
+    fn max_in_struct(schema: &StructType) -> i64 {
+        schema
+            .fields()
+            .map(|field| {
+                let id = match field.get_config_value(&ColumnMetadataKey::ColumnMappingId) {
+                    Some(MetadataValue::Number(id)) => (*id).into(),
+                    _ => 0,
+                };
+                id.max(max_in_type(field.data_type()))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+    max_in_struct(schema)
+}
+
+struct SchemaEvolution<'a> {
+    column_mapping_mode: ColumnMappingMode,
+    max_column_id: i64,
+    path: Vec<&'a str>,
+}
+
+impl<'a> SchemaEvolution<'a> {
+    fn column(&self, name: &str) -> ColumnName {
+        ColumnName::new(self.path.iter().copied().chain([name]))
+    }
+
+    fn evolve_struct(
+        &mut self,
+        current: &'a StructType,
+        new: &'a StructType,
+    ) -> DeltaResult<StructType> {
+        let mut matched = HashSet::new();
+        let mut fields = vec![];
+        for field in new.fields() {
+            let existing = match self.column_mapping_mode {
+                ColumnMappingMode::None => current.field(field.name()),
+                ColumnMappingMode::Name | ColumnMappingMode::Id => {
+                    match field.get_config_value(&ColumnMetadataKey::ColumnMappingPhysicalName) {
+                        Some(_) => Some(
+                            current
+                                .fields()
+                                .find(|f| f.physical_name() == field.physical_name())
+                                .ok_or_else(|| {
+                                    Error::generic(format!(
+                                        "Column '{}' has an unknown physical name",
+                                        self.column(field.name())
+                                    ))
+                                })?,
+                        ),
+                        None => None,
+                    }
+                }
+            };
+            let field = match existing {
+                Some(existing) => {
+                    matched.insert(existing.name());
+                    self.evolve_field(existing, field)?
+                }
+                // existing rows have no values for an added column
+                None if field.is_nullable() => self.assign_column_mapping(field)?,
+                None => {
+                    return Err(Error::generic(format!(
+                        "Added column '{}' must be nullable",
+                        self.column(field.name())
+                    )))
+                }
+            };
+            fields.push(field);
+        }
+        if let Some(dropped) = current.fields().find(|f| !matched.contains(f.name())) {
+            return Err(Error::generic(format!(
+                "Column '{}' cannot be dropped",
+                self.column(dropped.name())
+            )));
+        }
+        Ok(StructType::new(fields))
+    }
+
+    fn evolve_field(
+        &mut self,
+        current: &'a StructField,
+        new: &'a StructField,
+    ) -> DeltaResult<StructField> {
+        if current.is_nullable() && !new.is_nullable() {
+            return Err(Error::generic(format!(
+                "Column '{}' cannot be made non-nullable",
+                self.column(new.name())
+            )));
+        }
+        self.path.push(new.name());
+        let data_type = self.evolve_type(current.data_type(), new.data_type());
+        self.path.pop();
+        let mut field = StructField {
+            data_type: data_type?,
+            ..new.clone()
+        };
+        // the column mapping annotations of a column never change
+        for key in [
+            ColumnMetadataKey::ColumnMappingId,
+            ColumnMetadataKey::ColumnMappingPhysicalName,
+        ] {
+            if let Some(value) = current.get_config_value(&key) {
+                field
+                    .metadata
+                    .insert(key.as_ref().to_string(), value.clone());
+            }
+        }
+        Ok(field)
+    }
+
+    fn evolve_type(&mut self, current: &'a DataType, new: &'a DataType) -> DeltaResult<DataType> {
+        let contains_null_error = |path: &[&str], what: &str| {
+            Error::generic(format!(
+                "The {what} of column '{}' cannot be made non-nullable",
+                ColumnName::new(path.iter().copied())
+            ))
+        };
+        match (current, new) {
+            (DataType::Struct(current), DataType::Struct(new)) => Ok(DataType::Struct(Box::new(
+                self.evolve_struct(current, new)?,
+            ))),
+            (DataType::Array(current), DataType::Array(new)) => {
+                if current.contains_null() && !new.contains_null() {
+                    return Err(contains_null_error(&self.path, "elements"));
+                }
+                let element_type = self.evolve_type(current.element_type(), new.element_type())?;
+                Ok(ArrayType::new(element_type, new.contains_null()).into())
+            }
+            (DataType::Map(current), DataType::Map(new)) => {
+                if current.value_contains_null() && !new.value_contains_null() {
+                    return Err(contains_null_error(&self.path, "values"));
+                }
+                let key_type = self.evolve_type(current.key_type(), new.key_type())?;
+                let value_type = self.evolve_type(current.value_type(), new.value_type())?;
+                Ok(MapType::new(key_type, value_type, new.value_contains_null()).into())
+            }
+            (current, new) if current == new => Ok(new.clone()),
+            (current, new) => Err(Error::generic(format!(
+                "Cannot change the type of column '{}' from {current} to {new}",
+                ColumnName::new(self.path.iter().copied())
+            ))),
+        }
+    }
+
+    // Assign a physical name and column ID to an added field and its nested fields, if column
+    // mapping is enabled
+    fn assign_column_mapping(&mut self, field: &StructField) -> DeltaResult<StructField> {
+        if self.column_mapping_mode == ColumnMappingMode::None {
+            return Ok(field.clone());
+        }
+        let mut field = StructField {
+            data_type: self.assign_column_mapping_in_type(field.data_type())?,
+            ..field.clone()
+        };
+        self.max_column_id += 1;
+        let id = i32::try_from(self.max_column_id)
+            .map_err(|_| Error::generic("Too many columns to assign a column ID"))?;
+        field.metadata.insert(
+            ColumnMetadataKey::ColumnMappingId.as_ref().to_string(),
+            id.into(),
+        );
+        field.metadata.insert(
+            ColumnMetadataKey::ColumnMappingPhysicalName
+                .as_ref()
+                .to_string(),
+            format!("col-{}", uuid::Uuid::new_v4()).into(),
+        );
+        Ok(field)
+    }
+
+    fn assign_column_mapping_in_type(&mut self, data_type: &DataType) -> DeltaResult<DataType> {
+        Ok(match data_type {
+            DataType::Struct(s) => {
+                let fields = s.fields().map(|f| self.assign_column_mapping(f));
+                DataType::Struct(Box::new(StructType::try_new(fields)?))
+            }
+            DataType::Array(a) => ArrayType::new(
+                self.assign_column_mapping_in_type(a.element_type())?,
+                a.contains_null(),
+            )
+            .into(),
+            DataType::Map(m) => MapType::new(
+                self.assign_column_mapping_in_type(m.key_type())?,
+                self.assign_column_mapping_in_type(m.value_type())?,
+                m.value_contains_null(),
+            )
+            .into(),
+            other => other.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapped(field: StructField, id: i32) -> StructField {
+        field.with_metadata([
+            (
+                ColumnMetadataKey::ColumnMappingId.as_ref(),
+                MetadataValue::Number(id),
+            ),
+            (
+                ColumnMetadataKey::ColumnMappingPhysicalName.as_ref(),
+                MetadataValue::String(format!("col-{id}")),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_evolve_schema() {
+        let current = StructType::new([
+            StructField::new("id", DataType::LONG, false),
+            StructField::new(
+                "s",
+                StructType::new([StructField::new("a", DataType::STRING, false)]),
+                true,
+            ),
+            StructField::new("tags", ArrayType::new(DataType::STRING, true), true),
+        ]);
+        let evolve =
+            |new: StructType| evolve_schema(&current, &new, ColumnMappingMode::None, &mut 0);
+
+        // add nullable columns (including nested ones) and relax nullability
+        let new = StructType::new([
+            StructField::new("id", DataType::LONG, true),
+            StructField::new(
+                "s",
+                StructType::new([
+                    StructField::new("a", DataType::STRING, true),
+                    StructField::new("b", DataType::INTEGER, true),
+                ]),
+                true,
+            ),
+            StructField::new("tags", ArrayType::new(DataType::STRING, true), true),
+            StructField::new("c", DataType::DATE, true),
+        ]);
+        assert_eq!(evolve(new.clone()).unwrap(), new);
+
+        let err = |new: StructType| evolve(new).unwrap_err().to_string();
+        let mut fields: Vec<_> = current.fields().cloned().collect();
+        fields.push(StructField::new("c", DataType::DATE, false));
+        assert!(err(StructType::new(fields)).contains("Added column 'c' must be nullable"));
+        let fields = current.fields().skip(1).cloned();
+        assert!(err(StructType::new(fields)).contains("Column 'id' cannot be dropped"));
+        let mut fields: Vec<_> = current.fields().cloned().collect();
+        fields[1] = StructField::new(
+            "s",
+            StructType::new([StructField::new("a", DataType::INTEGER, false)]),
+            true,
+        );
+        assert!(err(StructType::new(fields)).contains("type of column 's.a' from string"));
+        let mut fields: Vec<_> = current.fields().cloned().collect();
+        fields[1] = StructField::new("s", StructType::new([]), false);
+        assert!(err(StructType::new(fields)).contains("Column 's' cannot be made non-nullable"));
+        let mut fields: Vec<_> = current.fields().cloned().collect();
+        fields[2] = StructField::new("tags", ArrayType::new(DataType::STRING, false), true);
+        assert!(err(StructType::new(fields)).contains("elements of column 'tags'"));
+        // renames look like a drop and an add without column mapping
+        let mut fields: Vec<_> = current.fields().cloned().collect();
+        fields[2] = fields[2].with_name("labels");
+        assert!(err(StructType::new(fields)).contains("Column 'tags' cannot be dropped"));
+    }
+
+    #[test]
+    fn test_evolve_schema_with_column_mapping() {
+        let current = StructType::new([
+            mapped(StructField::new("id", DataType::LONG, true), 1),
+            mapped(
+                StructField::new(
+                    "s",
+                    StructType::new([mapped(StructField::new("a", DataType::STRING, true), 3)]),
+                    true,
+                ),
+                2,
+            ),
+        ]);
+        assert_eq!(max_column_id(&current), 3);
+
+        // rename `id` and `s.a`, and add a struct column
+        let new = StructType::new([
+            current.field("id").unwrap().with_name("key"),
+            mapped(
+                StructField::new(
+                    "s",
+                    StructType::new([mapped(StructField::new("b", DataType::STRING, true), 3)]),
+                    true,
+                ),
+                2,
+            ),
+            StructField::new(
+                "added",
+                StructType::new([StructField::new("x", DataType::INTEGER, false)]),
+                true,
+            ),
+        ]);
+        let mut max_id = 3;
+        let evolved = evolve_schema(&current, &new, ColumnMappingMode::Name, &mut max_id).unwrap();
+        assert_eq!(max_id, 5);
+        assert_eq!(evolved.field("key").unwrap().physical_name(), "col-1");
+        let added = evolved.field("added").unwrap();
+        assert!(added.physical_name().starts_with("col-"));
+        assert_eq!(
+            added.get_config_value(&ColumnMetadataKey::ColumnMappingId),
+            Some(&MetadataValue::Number(5))
+        );
+        let DataType::Struct(added) = added.data_type() else {
+            panic!("expected a struct");
+        };
+        assert_eq!(
+            added
+                .field("x")
+                .unwrap()
+                .get_config_value(&ColumnMetadataKey::ColumnMappingId),
+            Some(&MetadataValue::Number(4))
+        );
+        assert_eq!(max_column_id(&evolved), 5);
+
+        // physical names must refer to existing columns
+        let new = StructType::new([mapped(StructField::new("id", DataType::LONG, true), 9)]);
+        let result = evolve_schema(&current, &new, ColumnMappingMode::Name, &mut max_id);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("unknown physical name"));
+    }
+
+    #[test]
+    fn test_table_properties() {
+        let properties = |props: &[(&str, &str)]| -> HashMap<String, String> {
+            props
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(validate_table_properties(&properties(&[
+            ("delta.appendOnly", "true"),
+            ("myapp.owner", "me")
+        ]))
+        .is_ok());
+        assert!(validate_table_properties(&properties(&[("delta.appendOnly", "yes")])).is_err());
+        assert!(validate_table_properties(&properties(&[("delta.unknown", "1")])).is_err());
+
+        let (configuration, features) = split_feature_properties(properties(&[
+            ("delta.feature.domainMetadata", "supported"),
+            ("delta.appendOnly", "true"),
+        ]))
+        .unwrap();
+        assert_eq!(configuration, properties(&[("delta.appendOnly", "true")]));
+        assert_eq!(features, [WriterFeatures::DomainMetadata]);
+        let result = split_feature_properties(properties(&[("delta.feature.foo", "supported")]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_upgrade_protocol() {
+        let schema = StructType::new([StructField::new("ts", DataType::TIMESTAMP_NTZ, true)]);
+        let table_properties = TableProperties::from([
+            ("delta.enableDeletionVectors", "true"),
+            ("delta.enableRowTracking", "true"),
+        ]);
+        let protocol = upgrade_protocol(
+            None,
+            vec![WriterFeatures::DomainMetadata],
+            &table_properties,
+            &schema,
+        )
+        .unwrap();
+        assert_eq!(
+            protocol.reader_features(),
+            Some(&["deletionVectors".into(), "timestampNtz".into()][..])
+        );
+        assert_eq!(
+            protocol.writer_features(),
+            Some(
+                &[
+                    "domainMetadata".into(),
+                    "deletionVectors".into(),
+                    "rowTracking".into(),
+                    "timestampNtz".into()
+                ][..]
+            )
+        );
+
+        // upgrading keeps the existing features
+        let upgraded = upgrade_protocol(
+            Some(&protocol),
+            vec![WriterFeatures::InCommitTimestamp],
+            &TableProperties::default(),
+            &StructType::new([]),
+        )
+        .unwrap();
+        assert_eq!(upgraded.reader_features(), protocol.reader_features());
+        assert_eq!(
+            upgraded.writer_features().unwrap().last().unwrap(),
+            "inCommitTimestamp"
+        );
+    }
+}
@@ -0,0 +1,1613 @@
+use std::collections::HashMap;
+use std::iter;
+use std::sync::{Arc, LazyLock};
+use std::time::UNIX_EPOCH;
+
+use crate::actions::deletion_vector::DeletionVector;
+use crate::actions::schemas::{GetNullableContainerStructField, GetStructField};
+use crate::actions::{
+    get_log_add_schema, get_log_schema, Add, CommitInfo, DomainMetadata, Metadata, Protocol,
+    Remove, SetTransaction,
+};
+use crate::actions::{
+    ADD_NAME, COMMIT_INFO_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME,
+};
+use crate::engine_data::{GetData, MapItem, RowVisitor, TypedGetData as _};
+use crate::error::Error;
+use crate::path::ParsedLogPath;
+use crate::schema::{column_name, ColumnName, ColumnNamesAndTypes, MapType, SchemaRef, StructType};
+use crate::snapshot::Snapshot;
+use crate::table_features::{
+    set_identity_high_water_mark, validate_iceberg_compat, validate_schema_column_mapping,
+    validate_timestamp_ntz_feature_support, validate_type_changes,
+    validate_variant_type_feature_support, ColumnMappingMode, IdentityColumnInfo, WriterFeatures,
+};
+use crate::utils::{current_time, require};
+use crate::{DataType, DeltaResult, Engine, EngineData, Expression, Version};
+
+use indexmap::IndexMap;
+use itertools::chain;
+use serde::Serialize;
+use tracing::{debug, warn};
+use url::Url;
+
+pub use self::conflict_checker::ConflictType;
+use self::conflict_checker::{find_conflict, WinningCommitSummary};
+pub(crate) use self::create_table::create_table;
+use self::metadata_update::{
+    evolve_schema, max_column_id, split_feature_properties, upgrade_protocol,
+    validate_table_properties, FEATURE_PROPERTY_PREFIX, MAX_COLUMN_ID_PROPERTY,
+};
+use self::row_deletes::{delete_rows_from_file, find_live_adds, remove_action};
+use self::stats_columns::stats_columns;
+pub use self::streaming::{StreamingCommitResult, StreamingWriter};
+
+mod conflict_checker;
+mod create_table;
+mod metadata_update;
+mod row_deletes;
+mod stats_columns;
+mod streaming;
+
+const KERNEL_VERSION: &str = env!("CARGO_PKG_VERSION");
+const UNKNOWN_OPERATION: &str = "UNKNOWN";
+const DEFAULT_MAX_COMMIT_RETRIES: usize = 10;
+// metadata domains whose names start with this prefix are controlled by table features
+const SYSTEM_DOMAIN_PREFIX: &str = "delta.";
+
+pub(crate) static WRITE_METADATA_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    Arc::new(StructType::new(vec![
+        <String>::get_struct_field("path"),
+        <HashMap<String, String>>::get_nullable_container_struct_field("partitionValues"),
+        <i64>::get_struct_field("size"),
+        <i64>::get_struct_field("modificationTime"),
+        <bool>::get_struct_field("dataChange"),
+        <Option<String>>::get_struct_field("stats"),
+    ]))
+});
+
+/// Get the expected schema for engine data passed to [`add_write_metadata`].
+///
+/// [`add_write_metadata`]: crate::transaction::Transaction::add_write_metadata
+pub fn get_write_metadata_schema() -> &'static SchemaRef {
+    &WRITE_METADATA_SCHEMA
+}
+
+/// A transaction represents an in-progress write to a table. After creating a transaction, changes
+/// to the table may be staged via the transaction methods before calling `commit` to commit the
+/// changes to the table.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // create a transaction (or use `snapshot.new_transaction()` to write on top of a snapshot)
+/// let mut txn = table.new_transaction(&engine)?;
+/// // stage table changes (right now only commit info)
+/// txn.commit_info(Box::new(ArrowEngineData::new(engine_commit_info)));
+/// // commit! (consume the transaction)
+/// txn.commit(&engine)?;
+/// ```
+pub struct Transaction {
+    read_snapshot: Arc<Snapshot>,
+    operation: Option<String>,
+    operation_parameters: HashMap<String, String>,
+    engine_info: Option<String>,
+    commit_info: Option<Arc<dyn EngineData>>,
+    write_metadata: Vec<Box<dyn EngineData>>,
+    identity_high_water_marks: HashMap<String, i64>,
+    // domain metadata changes, with `None` configurations for removed domains
+    domain_metadata: Vec<(String, Option<String>)>,
+    // the versions of the application transactions committed by this transaction, by app ID
+    set_transactions: IndexMap<String, i64>,
+    // the updated metadata of the table, along with its parsed schema
+    metadata_update: Option<(Metadata, SchemaRef)>,
+    protocol_update: Option<Protocol>,
+    // the files removed by this transaction, keyed by path, along with the add which replaces
+    // each one (e.g. with a new deletion vector), if any
+    removed_files: IndexMap<String, (Remove, Option<Add>)>,
+    automatic_checkpointing: bool,
+    max_commit_retries: usize,
+    // the conflict with a winning commit which made this transaction fail to commit, if any
+    conflict: Option<ConflictType>,
+}
+
+impl std::fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!(
+            "Transaction {{ read_snapshot version: {}, commit_info: {} }}",
+            self.read_snapshot.version(),
+            self.commit_info.is_some()
+        ))
+    }
+}
+
+impl Transaction {
+    /// Create a new transaction from a snapshot. The snapshot will be used to read the current
+    /// state of the table (e.g. to read the current version).
+    ///
+    /// Instead of using this API, the more typical (user-facing) API is
+    /// [Table::new_transaction](crate::table::Table::new_transaction) to create a transaction from
+    /// a table automatically backed by the latest snapshot.
+    pub(crate) fn try_new(snapshot: impl Into<Arc<Snapshot>>) -> DeltaResult<Self> {
+        let read_snapshot = snapshot.into();
+
+        // important! before a read/write to the table we must check it is supported
+        read_snapshot.protocol().ensure_write_supported()?;
+
+        Ok(Transaction {
+            read_snapshot,
+            operation: None,
+            operation_parameters: HashMap::new(),
+            engine_info: None,
+            commit_info: None,
+            write_metadata: vec![],
+            identity_high_water_marks: HashMap::new(),
+            domain_metadata: vec![],
+            set_transactions: IndexMap::new(),
+            metadata_update: None,
+            protocol_update: None,
+            removed_files: IndexMap::new(),
+            automatic_checkpointing: false,
+            max_commit_retries: DEFAULT_MAX_COMMIT_RETRIES,
+            conflict: None,
+        })
+    }
+
+    /// Consume the transaction and commit it to the table. The result is a [CommitResult] which
+    /// will include the failed transaction in case of a conflict so the user can retry.
+    ///
+    /// If another writer commits the version this transaction attempts to commit first, the
+    /// winning commit is checked for conflicts with this transaction (see [`ConflictType`]). If
+    /// there are none, the commit is automatically retried at the next version, at most
+    /// [`Self::with_max_commit_retries`] times. Otherwise [`CommitResult::Conflict`] is returned,
+    /// and [`Self::conflict`] of the returned transaction tells the kind of conflict.
+    ///
+    /// If automatic checkpointing is enabled (see [`Self::with_automatic_checkpointing`]), a
+    /// checkpoint is written after a successful commit whose version is a multiple of the table's
+    /// `delta.checkpointInterval`.
+    pub fn commit(mut self, engine: &dyn Engine) -> DeltaResult<CommitResult> {
+        // writes must not break the convertibility of Iceberg compatible tables
+        validate_iceberg_compat(
+            self.read_snapshot.protocol(),
+            self.read_snapshot.table_properties(),
+            self.read_snapshot.schema(),
+        )?;
+        let mut commit_version = self.read_snapshot.version() + 1;
+        // in-commit timestamps must be strictly greater than that of the previous commit
+        let mut previous_timestamp = match self.read_snapshot.is_in_commit_timestamps_enabled() {
+            true => Some(self.read_snapshot.timestamp(engine)?),
+            false => None,
+        };
+        let mut retries = 0;
+        loop {
+            if self.try_commit(engine, commit_version, previous_timestamp)? {
+                if self.automatic_checkpointing {
+                    self.checkpoint_if_due(engine, commit_version);
+                }
+                return Ok(CommitResult::Committed(commit_version));
+            }
+            let winning_commit = WinningCommitSummary::try_new(
+                engine,
+                self.read_snapshot.table_root(),
+                commit_version,
+            )?;
+            if let Some(conflict) = find_conflict(&self, &winning_commit) {
+                self.conflict = Some(conflict);
+                return Ok(CommitResult::Conflict(self, commit_version));
+            }
+            if retries == self.max_commit_retries {
+                debug!("Giving up on committing after {retries} retries");
+                return Ok(CommitResult::Conflict(self, commit_version));
+            }
+            retries += 1;
+            debug!("Retrying commit after losing version {commit_version} to a concurrent writer");
+            previous_timestamp = previous_timestamp.map(|previous| {
+                winning_commit
+                    .in_commit_timestamp
+                    .map_or(previous, |winning| winning.max(previous))
+            });
+            commit_version += 1;
+        }
+    }
+
+    // Write a checkpoint of the committed version if it is a multiple of the checkpoint interval.
+    // The commit has already succeeded, so failures to checkpoint are logged instead of returned: a
+    // missing checkpoint only makes reads slower, and the next due checkpoint will cover it.
+    fn checkpoint_if_due(&self, engine: &dyn Engine, version: Version) {
+        // the interval may have been changed by this transaction
+        let interval = self
+            .metadata()
+            .parse_table_properties()
+            .checkpoint_interval_or_default();
+        if version % interval != 0 {
+            return;
+        }
+        let table_root = self.read_snapshot.table_root().clone();
+        let result = Snapshot::try_new(table_root, engine, Some(version))
+            .and_then(|snapshot| snapshot.checkpoint(engine));
+        if let Err(e) = result {
+            warn!("Failed to write checkpoint for version {version}: {e}");
+        }
+    }
+
+    // Attempt to write the commit for `commit_version`. Returns false if that version already
+    // exists, i.e. another writer won the race to commit it.
+    fn try_commit(
+        &self,
+        engine: &dyn Engine,
+        commit_version: Version,
+        previous_timestamp: Option<i64>,
+    ) -> DeltaResult<bool> {
+        // step one: construct the iterator of actions we want to commit
+        let engine_commit_info = self
+            .commit_info
+            .as_ref()
+            .ok_or_else(|| Error::MissingCommitInfo)?;
+        let in_commit_timestamp = match previous_timestamp {
+            Some(previous) => Some(current_time_ms()?.max(previous + 1)),
+            None => None,
+        };
+        let commit_info = generate_commit_info(
+            engine,
+            self.kernel_commit_info(in_commit_timestamp)?,
+            engine_commit_info.as_ref(),
+        )?;
+        let protocol = match &self.protocol_update {
+            Some(protocol) => Some(json_action(
+                engine,
+                PROTOCOL_NAME,
+                protocol,
+                commit_info.as_ref(),
+            )?),
+            None => None,
+        };
+        let metadata = self.generate_metadata_update(engine, commit_info.as_ref())?;
+        let domain_metadata = self.generate_domain_metadata(engine, commit_info.as_ref())?;
+        let set_transactions = self.generate_set_transactions(engine, commit_info.as_ref())?;
+        let adds = generate_adds(engine, self.write_metadata.iter().map(|a| a.as_ref()));
+        let file_actions = self.generate_file_actions(engine, commit_info.as_ref())?;
+        let actions = chain(iter::once(Ok(commit_info)), protocol.map(Ok))
+            .chain(metadata.map(Ok))
+            .chain(domain_metadata.into_iter().map(Ok))
+            .chain(set_transactions.into_iter().map(Ok))
+            .chain(adds)
+            .chain(file_actions.into_iter().map(Ok));
+
+        // step two: the path to write for the commit version
+        let commit_path =
+            ParsedLogPath::new_commit(self.read_snapshot.table_root(), commit_version)?;
+
+        // step three: commit the actions as a json file in the log
+        let json_handler = engine.get_json_handler();
+        match json_handler.write_json_file(&commit_path.location, Box::new(actions), false) {
+            Ok(()) => Ok(true),
+            Err(Error::FileAlreadyExists(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The conflict with a concurrent commit which made [`Self::commit`] return
+    /// [`CommitResult::Conflict`] for this transaction. `None` if the transaction did not conflict,
+    /// or if it gave up retrying after [`Self::with_max_commit_retries`] concurrent commits.
+    pub fn conflict(&self) -> Option<&ConflictType> {
+        self.conflict.as_ref()
+    }
+
+    /// Set the operation that this transaction is performing. This string will be persisted in the
+    /// commit and visible to anyone who describes the table history.
+    pub fn with_operation(mut self, operation: String) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Set how many times a commit which lost the race for a version to a non-conflicting
+    /// concurrent commit is retried at the next version before giving up. Defaults to 10.
+    pub fn with_max_commit_retries(mut self, max_commit_retries: usize) -> Self {
+        self.max_commit_retries = max_commit_retries;
+        self
+    }
+
+    /// Set the parameters of the operation that this transaction is performing, which will be
+    /// persisted as the `operationParameters` of the commit. Following Spark, the values are
+    /// strings that are often JSON-encoded, e.g. `"partitionBy": "[\"date\"]"`.
+    pub fn with_operation_parameters(
+        mut self,
+        operation_parameters: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.operation_parameters = operation_parameters
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self
+    }
+
+    /// Enable (or disable) automatic checkpointing: after a successful commit whose version is a
+    /// multiple of the table's `delta.checkpointInterval` (10 by default), write a checkpoint of
+    /// that version (see [`Snapshot::checkpoint`]). Checkpointing is disabled by default, for
+    /// engines that write checkpoints on their own.
+    pub fn with_automatic_checkpointing(mut self, automatic_checkpointing: bool) -> Self {
+        self.automatic_checkpointing = automatic_checkpointing;
+        self
+    }
+
+    /// Set a string that identifies the engine performing this transaction, which will be
+    /// persisted as the `engineInfo` of the commit, e.g. `Apache-Spark/3.5.0 Delta-Lake/3.2.0`.
+    pub fn with_engine_info(mut self, engine_info: impl Into<String>) -> Self {
+        self.engine_info = Some(engine_info.into());
+        self
+    }
+
+    // The commit info of this transaction, except for the engine's commit info
+    fn kernel_commit_info(&self, in_commit_timestamp: Option<i64>) -> DeltaResult<CommitInfo> {
+        // when in-commit timestamps are enabled, the commit timestamp is the in-commit timestamp
+        let timestamp = match in_commit_timestamp {
+            Some(in_commit_timestamp) => in_commit_timestamp,
+            None => current_time_ms()?,
+        };
+        Ok(CommitInfo {
+            in_commit_timestamp,
+            timestamp: Some(timestamp),
+            operation: Some(
+                self.operation
+                    .clone()
+                    .unwrap_or_else(|| UNKNOWN_OPERATION.to_string()),
+            ),
+            operation_parameters: Some(self.operation_parameters.clone()),
+            kernel_version: Some(format!("v{KERNEL_VERSION}")),
+            engine_info: self.engine_info.clone(),
+            engine_commit_info: None,
+        })
+    }
+
+    /// WARNING: This is an unstable API and will likely change in the future.
+    ///
+    /// Add commit info to the transaction. This is commit-wide metadata that is written as the
+    /// first action in the commit. The engine data passed here must have exactly one row, and we
+    /// only read one column: `engineCommitInfo` which must be a map<string, string> encoding the
+    /// metadata.
+    ///
+    /// The engine is required to provide commit info before committing the transaction. If the
+    /// engine would like to omit engine-specific commit info, it can do so by passing pass a
+    /// commit_info engine data chunk with one row and one column of type `Map<string, string>`
+    /// that can either be `null` or contain an empty map.
+    ///
+    /// Any other columns in the data chunk are ignored.
+    pub fn with_commit_info(mut self, commit_info: Box<dyn EngineData>) -> Self {
+        self.commit_info = Some(commit_info.into());
+        self
+    }
+
+    // Generate the logical-to-physical transform expression which must be evaluated on every data
+    // chunk before writing. At the moment, this is a transaction-wide expression.
+    fn generate_logical_to_physical(&self) -> Expression {
+        // for now, we just pass through all the columns except partition columns.
+        // note this is _incorrect_ if table config deems we need partition columns.
+        let partition_columns = &self.metadata().partition_columns;
+        let fields = self.schema().fields();
+        let fields = fields
+            .filter(|f| !partition_columns.contains(f.name()))
+            .map(|f| Expression::column([f.name()]));
+        Expression::struct_from(fields)
+    }
+
+    /// Get the write context for this transaction, which describes how engines must write data
+    /// files for the table so that they can be committed with [`add_write_metadata`]. It reflects
+    /// the metadata changes staged in the transaction so far (e.g. by [`update_schema`] and
+    /// [`set_table_properties`]), so engines should get it again after changing the metadata.
+    ///
+    /// [`add_write_metadata`]: Transaction::add_write_metadata
+    /// [`update_schema`]: Transaction::update_schema
+    /// [`set_table_properties`]: Transaction::set_table_properties
+    pub fn write_context(&self) -> WriteContext {
+        let target_dir = self.read_snapshot.table_root();
+        let snapshot_schema = self.schema();
+        let partition_columns = self.metadata().partition_columns.clone();
+        // partition values are stored in the log instead of the data files
+        let physical_schema = StructType::new(
+            snapshot_schema
+                .fields()
+                .filter(|f| !partition_columns.contains(f.name()))
+                .map(|f| f.make_physical(self.read_snapshot.column_mapping_mode())),
+        );
+        let stats_columns = stats_columns(
+            snapshot_schema,
+            &partition_columns,
+            &self.metadata().parse_table_properties(),
+        );
+        let logical_to_physical = self.generate_logical_to_physical();
+        WriteContext::new(
+            target_dir.clone(),
+            Arc::new(snapshot_schema.clone()),
+            Arc::new(physical_schema),
+            partition_columns,
+            stats_columns,
+            logical_to_physical,
+        )
+    }
+
+    /// Change the schema of the table to `schema`. The new schema must be compatible with the
+    /// existing data of the table: columns may be added (if nullable, including nested columns),
+    /// and existing columns may be made nullable, but columns may not be dropped, made
+    /// non-nullable or change their type.
+    ///
+    /// With column mapping enabled, the columns of `schema` are matched to the existing columns
+    /// by their physical name annotations, so that existing columns may also be renamed by
+    /// changing the names of the fields of the table's schema. Added columns (those without
+    /// annotations) are assigned a physical name and column ID. Without column mapping, columns
+    /// are matched by name.
+    ///
+    /// The protocol of the table is upgraded if the new schema requires a table feature, e.g. for
+    /// `timestamp_ntz` columns.
+    pub fn update_schema(&mut self, schema: StructType) -> DeltaResult<()> {
+        let column_mapping_mode = self.read_snapshot.column_mapping_mode();
+        let mut metadata = self.metadata().clone();
+        let mut max_id = match metadata.configuration.get(MAX_COLUMN_ID_PROPERTY) {
+            Some(max_id) => max_id.parse().map_err(|_| {
+                Error::generic(format!(
+                    "Invalid value '{max_id}' of table property '{MAX_COLUMN_ID_PROPERTY}'"
+                ))
+            })?,
+            None => max_column_id(self.schema()),
+        };
+        let schema = evolve_schema(self.schema(), &schema, column_mapping_mode, &mut max_id)?;
+        if column_mapping_mode != ColumnMappingMode::None {
+            metadata
+                .configuration
+                .insert(MAX_COLUMN_ID_PROPERTY.to_string(), max_id.to_string());
+        }
+        // partition columns are recorded by name, so follow any renames
+        for partition_column in &mut metadata.partition_columns {
+            let physical_name = self
+                .schema()
+                .field(partition_column.as_str())
+                .ok_or_else(|| Error::missing_column(partition_column.as_str()))?
+                .physical_name();
+            if let Some(field) = schema.fields().find(|f| f.physical_name() == physical_name) {
+                *partition_column = field.name().clone();
+            }
+        }
+        metadata.schema_string = serde_json::to_string(&schema)?;
+        self.update_metadata(metadata, schema, vec![])
+    }
+
+    /// Set the given properties in the configuration of the table, and upgrade the protocol of the
+    /// table if the properties require a table feature (e.g. `delta.enableDeletionVectors`).
+    /// Features may also be requested explicitly with `delta.feature.<featureName> = supported`
+    /// properties, which are not added to the configuration.
+    ///
+    /// Fails if a `delta.*` property is unknown or has an invalid value, or if the protocol would
+    /// require a table feature that the kernel cannot write. Changing the column mapping mode or
+    /// enabling in-commit timestamps on an existing table is not supported.
+    pub fn set_table_properties(
+        &mut self,
+        properties: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> DeltaResult<()> {
+        let properties = properties
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        let (properties, features) = split_feature_properties(properties)?;
+        validate_table_properties(&properties)?;
+        let mut metadata = self.metadata().clone();
+        metadata.configuration.extend(properties);
+        self.update_configuration(metadata, features)
+    }
+
+    /// Remove the given properties from the configuration of the table. Properties that are not
+    /// set are ignored if `if_exists` is true, and are an error otherwise.
+    ///
+    /// Fails if a property cannot be removed: table features (`delta.feature.<featureName>`) cannot
+    /// be removed from the protocol, and the column mapping properties cannot be changed.
+    pub fn unset_table_properties(
+        &mut self,
+        keys: impl IntoIterator<Item = impl Into<String>>,
+        if_exists: bool,
+    ) -> DeltaResult<()> {
+        let mut metadata = self.metadata().clone();
+        for key in keys {
+            let key = key.into();
+            if key.starts_with(FEATURE_PROPERTY_PREFIX) {
+                return Err(Error::unsupported(format!(
+                    "Removing table features is not supported: '{key}'"
+                )));
+            }
+            if key == MAX_COLUMN_ID_PROPERTY {
+                return Err(Error::unsupported(format!(
+                    "Table property '{key}' cannot be removed"
+                )));
+            }
+            if metadata.configuration.remove(&key).is_none() && !if_exists {
+                return Err(Error::generic(format!(
+                    "Cannot unset table property '{key}', which is not set"
+                )));
+            }
+        }
+        self.update_configuration(metadata, vec![])
+    }
+
+    // Stage the updated configuration of `metadata`, ensuring the changes are supported.
+    fn update_configuration(
+        &mut self,
+        metadata: Metadata,
+        features: Vec<WriterFeatures>,
+    ) -> DeltaResult<()> {
+        let table_properties = metadata.parse_table_properties();
+        if table_properties.column_mapping_mode_or_default()
+            != self
+                .read_snapshot
+                .table_properties()
+                .column_mapping_mode_or_default()
+        {
+            return Err(Error::unsupported(
+                "Changing the column mapping mode of a table is not supported",
+            ));
+        }
+        if table_properties.is_in_commit_timestamps_enabled()
+            && !self.read_snapshot.is_in_commit_timestamps_enabled()
+        {
+            return Err(Error::unsupported(
+                "Enabling in-commit timestamps on an existing table is not supported",
+            ));
+        }
+        let schema = self.schema().clone();
+        self.update_metadata(metadata, schema, features)
+    }
+
+    // Stage the updated metadata of the table, upgrading the protocol to support the requested
+    // features and any features required by the new metadata.
+    fn update_metadata(
+        &mut self,
+        metadata: Metadata,
+        schema: StructType,
+        features: Vec<WriterFeatures>,
+    ) -> DeltaResult<()> {
+        let table_properties = metadata.parse_table_properties();
+        let protocol =
+            upgrade_protocol(Some(self.protocol()), features, &table_properties, &schema)?;
+        protocol.ensure_write_supported()?;
+        validate_schema_column_mapping(&schema, self.read_snapshot.column_mapping_mode())?;
+        validate_timestamp_ntz_feature_support(&schema, &protocol)?;
+        validate_variant_type_feature_support(&schema, &protocol)?;
+        validate_type_changes(&schema, &protocol)?;
+        validate_iceberg_compat(&protocol, &table_properties, &schema)?;
+
+        if protocol != *self.read_snapshot.protocol() {
+            self.protocol_update = Some(protocol);
+        }
+        self.metadata_update = Some((metadata, Arc::new(schema)));
+        Ok(())
+    }
+
+    // The metadata of the table, including any changes made by this transaction
+    fn metadata(&self) -> &Metadata {
+        match &self.metadata_update {
+            Some((metadata, _)) => metadata,
+            None => self.read_snapshot.metadata(),
+        }
+    }
+
+    // The schema of the table, including any changes made by this transaction
+    fn schema(&self) -> &StructType {
+        match &self.metadata_update {
+            Some((_, schema)) => schema,
+            None => self.read_snapshot.schema(),
+        }
+    }
+
+    // The protocol of the table, including any upgrades made by this transaction
+    fn protocol(&self) -> &Protocol {
+        self.protocol_update
+            .as_ref()
+            .unwrap_or_else(|| self.read_snapshot.protocol())
+    }
+
+    /// Record the highest (or, for a negative step, lowest) value generated for the identity
+    /// column `column` by the data written in this transaction. On commit, the column's high water
+    /// mark is advanced to this value, unless it is already further along in the step direction,
+    /// so that later writers do not generate the same values again. See
+    /// [`IdentityColumnInfo::next_value`] for the first value a writer should generate.
+    ///
+    /// Fails if `column` is not a top-level identity column of the table.
+    ///
+    /// [`IdentityColumnInfo::next_value`]: crate::table_features::IdentityColumnInfo::next_value
+    pub fn update_identity_high_water_mark(
+        &mut self,
+        column: &str,
+        high_water_mark: i64,
+    ) -> DeltaResult<()> {
+        let field = self
+            .schema()
+            .field(column)
+            .ok_or_else(|| Error::missing_column(column))?;
+        let info = field.identity_column_info()?.ok_or_else(|| {
+            Error::generic(format!("Column '{column}' is not an identity column"))
+        })?;
+        let info = IdentityColumnInfo {
+            high_water_mark: self
+                .identity_high_water_marks
+                .get(column)
+                .copied()
+                .or(info.high_water_mark),
+            ..info
+        };
+        if info.advances_high_water_mark(high_water_mark) {
+            self.identity_high_water_marks
+                .insert(column.to_string(), high_water_mark);
+        }
+        Ok(())
+    }
+
+    // Generate a metadata action recording the metadata changes of this transaction, including
+    // the identity column high water marks it advanced, if any. `commit_info` must have exactly
+    // one row.
+    fn generate_metadata_update(
+        &self,
+        engine: &dyn Engine,
+        commit_info: &dyn EngineData,
+    ) -> DeltaResult<Option<Box<dyn EngineData>>> {
+        if self.metadata_update.is_none() && self.identity_high_water_marks.is_empty() {
+            return Ok(None);
+        }
+        let mut schema = self.schema().clone();
+        for (column, high_water_mark) in &self.identity_high_water_marks {
+            let field = schema
+                .fields
+                .get_mut(column)
+                .ok_or_else(|| Error::missing_column(column))?;
+            set_identity_high_water_mark(field, *high_water_mark);
+        }
+        let metadata = Metadata {
+            schema_string: serde_json::to_string(&schema)?,
+            ..self.metadata().clone()
+        };
+        let action = json_action(engine, METADATA_NAME, metadata, commit_info)?;
+        Ok(Some(action))
+    }
+
+    /// Set the configuration of the user-controlled metadata domain `domain`, creating the domain
+    /// if it does not exist. A domain may only be changed once per transaction.
+    ///
+    /// Fails if the table does not support the `domainMetadata` writer feature, or if `domain` is
+    /// a system-controlled domain (whose name starts with `delta.`).
+    pub fn set_domain_metadata(
+        &mut self,
+        domain: impl Into<String>,
+        configuration: impl Into<String>,
+    ) -> DeltaResult<()> {
+        self.add_domain_metadata_change(domain.into(), Some(configuration.into()))
+    }
+
+    /// Remove the user-controlled metadata domain `domain`. Removing a domain that does not exist
+    /// has no effect. A domain may only be changed once per transaction.
+    ///
+    /// Fails if the table does not support the `domainMetadata` writer feature, or if `domain` is
+    /// a system-controlled domain (whose name starts with `delta.`).
+    pub fn remove_domain_metadata(&mut self, domain: impl Into<String>) -> DeltaResult<()> {
+        self.add_domain_metadata_change(domain.into(), None)
+    }
+
+    fn add_domain_metadata_change(
+        &mut self,
+        domain: String,
+        configuration: Option<String>,
+    ) -> DeltaResult<()> {
+        if !self
+            .protocol()
+            .has_writer_feature(&WriterFeatures::DomainMetadata)
+        {
+            return Err(Error::unsupported(
+                "Domain metadata requires the domainMetadata writer feature",
+            ));
+        }
+        if domain.starts_with(SYSTEM_DOMAIN_PREFIX) {
+            return Err(Error::generic(format!(
+                "Cannot change system-controlled metadata domain '{domain}'"
+            )));
+        }
+        if self.domain_metadata.iter().any(|(d, _)| *d == domain) {
+            return Err(Error::generic(format!(
+                "Metadata domain '{domain}' was already changed in this transaction"
+            )));
+        }
+        self.domain_metadata.push((domain, configuration));
+        Ok(())
+    }
+
+    // Generate the domain metadata actions of this transaction. Removing a domain requires its
+    // current configuration, so removals of domains that do not exist are dropped.
+    // `commit_info` must have exactly one row.
+    fn generate_domain_metadata(
+        &self,
+        engine: &dyn Engine,
+        commit_info: &dyn EngineData,
+    ) -> DeltaResult<Vec<Box<dyn EngineData>>> {
+        let mut actions = vec![];
+        for (domain, configuration) in &self.domain_metadata {
+            let (configuration, removed) = match configuration {
+                Some(configuration) => (configuration.clone(), false),
+                None => match self.read_snapshot.domain_metadata(domain, engine)? {
+                    Some(configuration) => (configuration, true),
+                    None => continue,
+                },
+            };
+            let domain_metadata = DomainMetadata {
+                domain: domain.clone(),
+                configuration,
+                removed,
+            };
+            actions.push(json_action(
+                engine,
+                DOMAIN_METADATA_NAME,
+                domain_metadata,
+                commit_info,
+            )?);
+        }
+        Ok(actions)
+    }
+
+    /// Record that this transaction commits `version` of the application transaction `app_id`,
+    /// e.g. the batch `version` of a streaming query. Readers find the latest committed version of
+    /// an application with [`SetTransactionScanner`], so that applications can make their writes
+    /// idempotent by skipping versions which were already committed. If another writer commits a
+    /// version of the same application first, the commit fails with a
+    /// [`ConflictType::ConcurrentTransaction`].
+    ///
+    /// Note that this does not check that `version` is newer than the latest committed version of
+    /// the application; see [`StreamingWriter`] for a writer which does.
+    ///
+    /// [`SetTransactionScanner`]: crate::actions::set_transaction::SetTransactionScanner
+    pub fn with_transaction_id(mut self, app_id: impl Into<String>, version: i64) -> Self {
+        self.set_transactions.insert(app_id.into(), version);
+        self
+    }
+
+    fn generate_set_transactions(
+        &self,
+        engine: &dyn Engine,
+        commit_info: &dyn EngineData,
+    ) -> DeltaResult<Vec<Box<dyn EngineData>>> {
+        let last_updated = current_time_ms()?;
+        self.set_transactions
+            .iter()
+            .map(|(app_id, &version)| {
+                let set_transaction = SetTransaction {
+                    app_id: app_id.clone(),
+                    version,
+                    last_updated: Some(last_updated),
+                };
+                json_action(engine, SET_TRANSACTION_NAME, set_transaction, commit_info)
+            })
+            .collect()
+    }
+
+    /// Add write metadata about files to include in the transaction. This API can be called
+    /// multiple times to add multiple batches.
+    ///
+    /// The expected schema for `write_metadata` is given by [`get_write_metadata_schema`]. Each row
+    /// describes one data file written by the engine and becomes an `add` action of the commit.
+    /// The optional `stats` column holds the file's statistics as a JSON string, as described in
+    /// the [protocol](https://github.com/delta-io/delta/blob/master/PROTOCOL.md#per-file-statistics).
+    pub fn add_write_metadata(&mut self, write_metadata: Box<dyn EngineData>) {
+        self.write_metadata.push(write_metadata);
+    }
+
+    /// Delete rows from data files of the table, by marking them as deleted in the deletion
+    /// vector of each file. `deletes` pairs the path of a data file, as given by its `add` action
+    /// (e.g. the `path` passed to a [`ScanCallback`]), with the indexes of the rows to delete,
+    /// i.e. their positions in the data file.
+    ///
+    /// The rows are merged with those already deleted from the file, and the new deletion vector
+    /// is stored inline in the log if it is small, or written to a new file in the table
+    /// otherwise. On commit, the `add` of each file is replaced by a `remove` of the old logical
+    /// file and an `add` with the new deletion vector. Since the statistics of a file then include
+    /// deleted rows, they are marked as not being tight bounds (`tightBounds: false`). Files whose
+    /// rows are all deleted are removed instead.
+    ///
+    /// Fails if deletion vectors are not enabled on the table (see `delta.enableDeletionVectors`),
+    /// or if a file is not part of the table.
+    ///
+    /// [`ScanCallback`]: crate::scan::state::ScanCallback
+    pub fn delete_rows(
+        &mut self,
+        engine: &dyn Engine,
+        deletes: impl IntoIterator<Item = (String, DeletionVector)>,
+    ) -> DeltaResult<()> {
+        require!(
+            self.protocol()
+                .has_writer_feature(&WriterFeatures::DeletionVectors)
+                && self
+                    .metadata()
+                    .parse_table_properties()
+                    .is_deletion_vectors_enabled(),
+            Error::unsupported(
+                "Deleting rows requires deletion vectors to be enabled on the table"
+            )
+        );
+        self.ensure_data_removal_allowed()?;
+        let mut deletes_by_path: IndexMap<String, DeletionVector> = IndexMap::new();
+        for (path, deleted) in deletes {
+            deletes_by_path
+                .entry(path)
+                .or_default()
+                .union_with(&deleted);
+        }
+
+        // files which already had rows deleted by this transaction are updated in place
+        let new_paths = deletes_by_path
+            .keys()
+            .filter(|path| !self.removed_files.contains_key(*path))
+            .cloned()
+            .collect();
+        let mut live_adds = find_live_adds(engine, &self.read_snapshot, &new_paths)?;
+        let fs_client = engine.get_file_system_client();
+        let table_root = self.read_snapshot.table_root();
+        let deletion_timestamp = current_time_ms()?;
+        for (path, deleted) in deletes_by_path {
+            let staged = self.removed_files.get(&path);
+            let add = match staged {
+                Some((_, Some(add))) => add,
+                // every row of the file is already deleted
+                Some((_, None)) => continue,
+                None => live_adds.get(&path).ok_or_else(|| {
+                    Error::generic(format!("File {path} is not part of the table"))
+                })?,
+            };
+            let Some((remove, add)) = delete_rows_from_file(
+                fs_client.clone(),
+                table_root,
+                add,
+                deleted,
+                deletion_timestamp,
+            )?
+            else {
+                continue;
+            };
+            match self.removed_files.get_mut(&path) {
+                // keep the remove of the file as it is in the read snapshot
+                Some((_, staged_add)) => *staged_add = add,
+                None => {
+                    live_adds.remove(&path);
+                    self.removed_files.insert(path, (remove, add));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove data files from the table, e.g. to implement DELETE or overwrite operations. Each
+    /// path is the path of a data file as given by its `add` action (e.g. the `path` passed to a
+    /// [`ScanCallback`]). On commit, a `remove` action is written for each file, with the given
+    /// `dataChange` flag and the time of this call as its `deletionTimestamp`.
+    ///
+    /// `data_change` should only be false if the data of the removed files is added back by this
+    /// transaction, e.g. when compacting small files. Files which had rows deleted by this
+    /// transaction (see [`Self::delete_rows`]) are removed entirely.
+    ///
+    /// Fails if a file is not part of the table, or if `data_change` is true and the table is
+    /// append-only (see `delta.appendOnly`). No file is removed if the call fails.
+    ///
+    /// [`ScanCallback`]: crate::scan::state::ScanCallback
+    pub fn remove_files(
+        &mut self,
+        engine: &dyn Engine,
+        paths: impl IntoIterator<Item = impl Into<String>>,
+        data_change: bool,
+    ) -> DeltaResult<()> {
+        if data_change {
+            self.ensure_data_removal_allowed()?;
+        }
+        let paths: Vec<String> = paths.into_iter().map(Into::into).collect();
+        let new_paths = paths
+            .iter()
+            .filter(|path| !self.removed_files.contains_key(*path))
+            .cloned()
+            .collect();
+        let live_adds = find_live_adds(engine, &self.read_snapshot, &new_paths)?;
+        if let Some(path) = new_paths.iter().find(|path| !live_adds.contains_key(*path)) {
+            return Err(Error::generic(format!(
+                "File {path} is not part of the table"
+            )));
+        }
+        let deletion_timestamp = current_time_ms()?;
+        for path in paths {
+            match self.removed_files.get_mut(&path) {
+                // the replacement of a file with deleted rows is dropped along with the file
+                Some((remove, add)) => {
+                    remove.data_change |= data_change;
+                    *add = None;
+                }
+                None => {
+                    let remove = remove_action(&live_adds[&path], deletion_timestamp, data_change);
+                    self.removed_files.insert(path, (remove, None));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Atomically replace the data files at `paths` with the compacted files described by
+    /// `write_metadata` (see [`Self::add_write_metadata`]), e.g. to implement OPTIMIZE. The files
+    /// are removed with `dataChange = false`, and the compacted files must have been written with
+    /// `dataChange = false` too, so that readers of the changes of the table (e.g. streaming
+    /// queries) do not read the rearranged data again. Concurrent readers of the table are not
+    /// affected, since the removed files stay in place until they are vacuumed.
+    ///
+    /// Fails if a file is not part of the table, or if a compacted file was written with
+    /// `dataChange = true`, in which case nothing is staged. If another writer removes one of the
+    /// files before this transaction commits (e.g. to delete rows from it), the commit fails with a
+    /// [`ConflictType::ConcurrentDeleteDelete`].
+    pub fn compact_files(
+        &mut self,
+        engine: &dyn Engine,
+        paths: impl IntoIterator<Item = impl Into<String>>,
+        write_metadata: impl IntoIterator<Item = Box<dyn EngineData>>,
+    ) -> DeltaResult<()> {
+        let write_metadata: Vec<_> = write_metadata.into_iter().collect();
+        let mut visitor = DataChangeVisitor::default();
+        for write_metadata in &write_metadata {
+            visitor.visit_rows_of(write_metadata.as_ref())?;
+        }
+        require!(
+            !visitor.data_change,
+            Error::generic("Compacted files must be written with dataChange = false")
+        );
+        self.remove_files(engine, paths, false)?;
+        self.write_metadata.extend(write_metadata);
+        Ok(())
+    }
+
+    // Append-only tables must not have data removed, so files may only be removed without
+    // changing the data of the table (e.g. when compacting files)
+    fn ensure_data_removal_allowed(&self) -> DeltaResult<()> {
+        require!(
+            !self.metadata().parse_table_properties().is_append_only(),
+            Error::generic("Cannot remove data from an append-only table")
+        );
+        Ok(())
+    }
+
+    // Convert the removed files (and their replacements) into remove and add actions
+    fn generate_file_actions(
+        &self,
+        engine: &dyn Engine,
+        one_row: &dyn EngineData,
+    ) -> DeltaResult<Vec<Box<dyn EngineData>>> {
+        let mut actions = vec![];
+        for (remove, add) in self.removed_files.values() {
+            actions.push(json_action(engine, REMOVE_NAME, remove, one_row)?);
+            if let Some(add) = add {
+                actions.push(json_action(engine, ADD_NAME, add, one_row)?);
+            }
+        }
+        Ok(actions)
+    }
+}
+
+// Convert a single action into engine data with the log schema of `action_name`. `one_row` must
+// have exactly one row.
+fn json_action(
+    engine: &dyn Engine,
+    action_name: &str,
+    action: impl Serialize,
+    one_row: &dyn EngineData,
+) -> DeltaResult<Box<dyn EngineData>> {
+    let json = serde_json::json!({ action_name: action }).to_string();
+
+    // HACK: kernel cannot create engine data itself, so we evaluate the JSON-encoded action as a
+    // string literal against a batch with exactly one row and let the engine parse it.
+    let evaluator = engine.get_expression_handler().get_evaluator(
+        Arc::new(StructType::new(vec![])),
+        Expression::literal(json),
+        DataType::STRING,
+    );
+    let json = evaluator.evaluate(one_row)?;
+    let schema = get_log_schema().project(&[action_name])?;
+    engine.get_json_handler().parse_json(json, schema)
+}
+
+// convert write_metadata into add actions using an expression to transform the data in a single
+// pass
+fn generate_adds<'a>(
+    engine: &dyn Engine,
+    write_metadata: impl Iterator<Item = &'a dyn EngineData> + Send + 'a,
+) -> impl Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + 'a {
+    let expression_handler = engine.get_expression_handler();
+    let write_metadata_schema = get_write_metadata_schema();
+    let log_schema = get_log_add_schema();
+
+    write_metadata.map(move |write_metadata_batch| {
+        let adds_expr = Expression::struct_from([Expression::struct_from(
+            write_metadata_schema
+                .fields()
+                .map(|f| Expression::column([f.name()])),
+        )]);
+        let adds_evaluator = expression_handler.get_evaluator(
+            write_metadata_schema.clone(),
+            adds_expr,
+            log_schema.clone().into(),
+        );
+        adds_evaluator.evaluate(write_metadata_batch)
+    })
+}
+
+/// WriteContext is data derived from a [`Transaction`] that can be provided to writers in order to
+/// write table data.
+///
+/// [`Transaction`]: struct.Transaction.html
+pub struct WriteContext {
+    target_dir: Url,
+    schema: SchemaRef,
+    physical_schema: SchemaRef,
+    partition_columns: Vec<String>,
+    stats_columns: Vec<ColumnName>,
+    logical_to_physical: Expression,
+}
+
+impl WriteContext {
+    fn new(
+        target_dir: Url,
+        schema: SchemaRef,
+        physical_schema: SchemaRef,
+        partition_columns: Vec<String>,
+        stats_columns: Vec<ColumnName>,
+        logical_to_physical: Expression,
+    ) -> Self {
+        WriteContext {
+            target_dir,
+            schema,
+            physical_schema,
+            partition_columns,
+            stats_columns,
+            logical_to_physical,
+        }
+    }
+
+    /// The directory to write data files to
+    pub fn target_dir(&self) -> &Url {
+        &self.target_dir
+    }
+
+    /// The logical schema of the table, i.e. the schema of the data to write
+    pub fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    /// The schema of the data files to write. This is the logical schema without the partition
+    /// columns, whose values are recorded in the `partitionValues` of the write metadata instead,
+    /// and with the physical column names of [column mapping]. In column mapping `id` mode, each
+    /// field also carries the parquet field ID to write in its `PARQUET:field_id` metadata.
+    ///
+    /// [column mapping]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#column-mapping
+    pub fn physical_schema(&self) -> &SchemaRef {
+        &self.physical_schema
+    }
+
+    /// The logical names of the partition columns of the table
+    pub fn partition_columns(&self) -> &[String] {
+        &self.partition_columns
+    }
+
+    /// The leaf columns of the [`physical_schema`] whose statistics (`minValues`, `maxValues` and
+    /// `nullCount`) must be recorded in the `stats` of the write metadata of each data file, as
+    /// determined by the `delta.dataSkippingNumIndexedCols` and `delta.dataSkippingStatsColumns`
+    /// table properties.
+    ///
+    /// [`physical_schema`]: Self::physical_schema
+    pub fn stats_columns(&self) -> &[ColumnName] {
+        &self.stats_columns
+    }
+
+    /// The expression which transforms data with the logical [`schema`] into data with the
+    /// [`physical_schema`], to be evaluated on every chunk of data before writing it.
+    ///
+    /// [`schema`]: Self::schema
+    /// [`physical_schema`]: Self::physical_schema
+    pub fn logical_to_physical(&self) -> &Expression {
+        &self.logical_to_physical
+    }
+}
+
+/// Result after committing a transaction. If 'committed', the version is the new version written
+/// to the log. If 'conflict', the transaction is returned so the caller can resolve the conflict
+/// (along with the version which conflicted). See [`Transaction::conflict`] for the kind of
+/// conflict.
+// TODO(zach): in order to make the returning of a transcation useful, we need to add APIs to
+// update the transaction to a new version etc.
+// the transaction is returned unboxed so that callers can resolve the conflict and retry it
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum CommitResult {
+    /// The transaction was successfully committed at the version.
+    Committed(Version),
+    /// The transaction conflicted with an existing version (at the version given).
+    Conflict(Transaction, Version),
+}
+
+fn current_time_ms() -> DeltaResult<i64> {
+    current_time()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| Error::generic("time went backwards"))?
+        .as_millis()
+        .try_into()
+        .map_err(|_| Error::generic("milliseconds since unix_epoch exceeded i64 size"))
+}
+
+// given the engine's commit info we want to create commitInfo action to commit (and append more
+// actions to). The engine's commit info is added to the kernel's `commit_info`.
+fn generate_commit_info(
+    engine: &dyn Engine,
+    mut commit_info: CommitInfo,
+    engine_commit_info: &dyn EngineData,
+) -> DeltaResult<Box<dyn EngineData>> {
+    if engine_commit_info.len() != 1 {
+        return Err(Error::InvalidCommitInfo(format!(
+            "Engine commit info should have exactly one row, found {}",
+            engine_commit_info.len()
+        )));
+    }
+    let mut visitor = EngineCommitInfoVisitor::default();
+    visitor.visit_rows_of(engine_commit_info)?;
+    commit_info.engine_commit_info = visitor.engine_commit_info;
+    json_action(engine, COMMIT_INFO_NAME, commit_info, engine_commit_info)
+}
+
+/// Checks whether any file of the write metadata changes the data of the table
+#[derive(Default)]
+struct DataChangeVisitor {
+    data_change: bool,
+}
+
+impl RowVisitor for DataChangeVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("dataChange")], vec![DataType::BOOLEAN]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 1,
+            Error::InternalError(format!(
+                "Wrong number of DataChangeVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            let data_change: bool = getters[0].get(i, "dataChange")?;
+            self.data_change |= data_change;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the `engineCommitInfo` map of the engine's commit info
+#[derive(Default)]
+struct EngineCommitInfoVisitor {
+    engine_commit_info: Option<HashMap<String, String>>,
+}
+
+impl RowVisitor for EngineCommitInfoVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            let map_type = MapType::new(DataType::STRING, DataType::STRING, true);
+            (
+                vec![column_name!("engineCommitInfo")],
+                vec![map_type.into()],
+            )
+                .into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 1,
+            Error::InternalError(format!(
+                "Wrong number of EngineCommitInfoVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            let engine_commit_info: Option<MapItem<'_>> =
+                getters[0].get_opt(i, "engineCommitInfo")?;
+            self.engine_commit_info = engine_commit_info.map(|map| map.materialize());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::engine::arrow_data::ArrowEngineData;
+    use crate::engine::sync::SyncEngine;
+    use crate::schema::StructField;
+
+    use arrow::json::writer::LineDelimitedWriter;
+    use arrow::record_batch::RecordBatch;
+    use arrow_array::builder::StringBuilder;
+    use arrow_schema::Schema as ArrowSchema;
+    use arrow_schema::{DataType as ArrowDataType, Field};
+
+    // The kernel's commit info for a "test operation", as generated by a transaction
+    fn test_commit_info(in_commit_timestamp: Option<i64>) -> CommitInfo {
+        CommitInfo {
+            in_commit_timestamp,
+            timestamp: Some(in_commit_timestamp.unwrap_or(0)),
+            operation: Some("test operation".to_string()),
+            operation_parameters: Some(HashMap::new()),
+            kernel_version: Some(format!("v{KERNEL_VERSION}")),
+            engine_info: None,
+            engine_commit_info: None,
+        }
+    }
+
+    fn build_map(entries: Vec<(&str, &str)>) -> arrow_array::MapArray {
+        let key_builder = StringBuilder::new();
+        let val_builder = StringBuilder::new();
+        let names = arrow_array::builder::MapFieldNames {
+            entry: "entries".to_string(),
+            key: "key".to_string(),
+            value: "value".to_string(),
+        };
+        let mut builder =
+            arrow_array::builder::MapBuilder::new(Some(names), key_builder, val_builder);
+        for (key, val) in entries {
+            builder.keys().append_value(key);
+            builder.values().append_value(val);
+            builder.append(true).unwrap();
+        }
+        builder.finish()
+    }
+
+    // convert it to JSON just for ease of comparison (and since we ultimately persist as JSON)
+    fn as_json(data: Box<dyn EngineData>) -> serde_json::Value {
+        let record_batch: RecordBatch = data
+            .into_any()
+            .downcast::<ArrowEngineData>()
+            .unwrap()
+            .into();
+
+        let buf = Vec::new();
+        let mut writer = LineDelimitedWriter::new(buf);
+        writer.write_batches(&[&record_batch]).unwrap();
+        writer.finish().unwrap();
+        let buf = writer.into_inner();
+
+        serde_json::from_slice(&buf).unwrap()
+    }
+
+    fn as_json_and_scrub_timestamp(data: Box<dyn EngineData>) -> serde_json::Value {
+        let mut result = as_json(data);
+        *result
+            .get_mut("commitInfo")
+            .unwrap()
+            .get_mut("timestamp")
+            .unwrap() = serde_json::Value::Number(0.into());
+        result
+    }
+
+    #[test]
+    fn test_generate_commit_info() -> DeltaResult<()> {
+        let engine = SyncEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "engineCommitInfo",
+            ArrowDataType::Map(
+                Arc::new(Field::new(
+                    "entries",
+                    ArrowDataType::Struct(
+                        vec![
+                            Field::new("key", ArrowDataType::Utf8, false),
+                            Field::new("value", ArrowDataType::Utf8, true),
+                        ]
+                        .into(),
+                    ),
+                    false,
+                )),
+                false,
+            ),
+            false,
+        )]));
+
+        let map_array = build_map(vec![("engineInfo", "default engine")]);
+        let commit_info_batch =
+            RecordBatch::try_new(engine_commit_info_schema, vec![Arc::new(map_array)])?;
+
+        let actions = generate_commit_info(
+            &engine,
+            test_commit_info(None),
+            &ArrowEngineData::new(commit_info_batch),
+        )?;
+
+        let expected = serde_json::json!({
+            "commitInfo": {
+                "timestamp": 0,
+                "operation": "test operation",
+                "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                "operationParameters": {},
+                "engineCommitInfo": {
+                    "engineInfo": "default engine"
+                }
+            }
+        });
+
+        assert_eq!(actions.len(), 1);
+        let result = as_json_and_scrub_timestamp(actions);
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_commit_info_with_in_commit_timestamp() -> DeltaResult<()> {
+        let engine = SyncEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "engineCommitInfo",
+            ArrowDataType::Map(
+                Arc::new(Field::new(
+                    "entries",
+                    ArrowDataType::Struct(
+                        vec![
+                            Field::new("key", ArrowDataType::Utf8, false),
+                            Field::new("value", ArrowDataType::Utf8, true),
+                        ]
+                        .into(),
+                    ),
+                    false,
+                )),
+                false,
+            ),
+            false,
+        )]));
+
+        let map_array = build_map(vec![("engineInfo", "default engine")]);
+        let commit_info_batch =
+            RecordBatch::try_new(engine_commit_info_schema, vec![Arc::new(map_array)])?;
+
+        let actions = generate_commit_info(
+            &engine,
+            test_commit_info(Some(1234)),
+            &ArrowEngineData::new(commit_info_batch),
+        )?;
+
+        // the commit timestamp is the in-commit timestamp
+        let expected = serde_json::json!({
+            "commitInfo": {
+                "inCommitTimestamp": 1234,
+                "timestamp": 1234,
+                "operation": "test operation",
+                "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                "operationParameters": {},
+                "engineCommitInfo": {
+                    "engineInfo": "default engine"
+                }
+            }
+        });
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(as_json(actions), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_info_with_multiple_columns() -> DeltaResult<()> {
+        let engine = SyncEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![
+            Field::new(
+                "engineCommitInfo",
+                ArrowDataType::Map(
+                    Arc::new(Field::new(
+                        "entries",
+                        ArrowDataType::Struct(
+                            vec![
+                                Field::new("key", ArrowDataType::Utf8, false),
+                                Field::new("value", ArrowDataType::Utf8, true),
+                            ]
+                            .into(),
+                        ),
+                        false,
+                    )),
+                    false,
+                ),
+                false,
+            ),
+            Field::new("operation", ArrowDataType::Utf8, true),
+        ]));
+
+        let map_array = build_map(vec![("engineInfo", "default engine")]);
+
+        let commit_info_batch = RecordBatch::try_new(
+            engine_commit_info_schema,
+            vec![
+                Arc::new(map_array),
+                Arc::new(arrow_array::StringArray::from(vec!["some_string"])),
+            ],
+        )?;
+
+        let actions = generate_commit_info(
+            &engine,
+            test_commit_info(None),
+            &ArrowEngineData::new(commit_info_batch),
+        )?;
+
+        let expected = serde_json::json!({
+            "commitInfo": {
+                "timestamp": 0,
+                "operation": "test operation",
+                "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                "operationParameters": {},
+                "engineCommitInfo": {
+                    "engineInfo": "default engine"
+                }
+            }
+        });
+
+        assert_eq!(actions.len(), 1);
+        let result = as_json_and_scrub_timestamp(actions);
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_commit_info_missing_column() -> DeltaResult<()> {
+        let engine = SyncEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "some_column_name",
+            ArrowDataType::Utf8,
+            true,
+        )]));
+        let commit_info_batch = RecordBatch::try_new(
+            engine_commit_info_schema,
+            vec![Arc::new(arrow_array::StringArray::new_null(1))],
+        )?;
+
+        let _ = generate_commit_info(
+            &engine,
+            test_commit_info(None),
+            &ArrowEngineData::new(commit_info_batch),
+        )
+        .map_err(|e| match e {
+            Error::MissingColumn(_) => (),
+            Error::Backtraced { source, .. } if matches!(&*source, Error::MissingColumn(_)) => {}
+            _ => panic!("expected missing column error, got {:?}", e),
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_commit_info_invalid_column_type() -> DeltaResult<()> {
+        let engine = SyncEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "engineCommitInfo",
+            ArrowDataType::Utf8,
+            true,
+        )]));
+        let commit_info_batch = RecordBatch::try_new(
+            engine_commit_info_schema,
+            vec![Arc::new(arrow_array::StringArray::new_null(1))],
+        )?;
+
+        let _ = generate_commit_info(
+            &engine,
+            test_commit_info(None),
+            &ArrowEngineData::new(commit_info_batch),
+        )
+        .map_err(|e| match e {
+            Error::UnexpectedColumnType(_) => (),
+            Error::Backtraced { source, .. }
+                if matches!(&*source, Error::UnexpectedColumnType(_)) => {}
+            _ => panic!("expected unexpected column type error, got {:?}", e),
+        });
+
+        Ok(())
+    }
+
+    fn assert_empty_commit_info(
+        data: Box<dyn EngineData>,
+        write_engine_commit_info: bool,
+    ) -> DeltaResult<()> {
+        assert_eq!(data.len(), 1);
+        let expected = if write_engine_commit_info {
+            serde_json::json!({
+                "commitInfo": {
+                    "timestamp": 0,
+                    "operation": "test operation",
+                    "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                    "operationParameters": {},
+                    "engineCommitInfo": {}
+                }
+            })
+        } else {
+            serde_json::json!({
+                "commitInfo": {
+                    "timestamp": 0,
+                    "operation": "test operation",
+                    "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                    "operationParameters": {},
+                }
+            })
+        };
+        let result = as_json_and_scrub_timestamp(data);
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    // Three cases for empty commit info:
+    // 1. `engineCommitInfo` column with an empty Map<string, string>
+    // 2. `engineCommitInfo` null column of type Map<string, string>
+    // 3. a column that has a name other than `engineCommitInfo`; Delta can detect that the column
+    //    is missing and substitute a null literal in its place. The type of that column doesn't
+    //    matter, Delta will ignore it.
+    #[test]
+    fn test_empty_commit_info() -> DeltaResult<()> {
+        // test with null map and empty map
+        for is_null in [true, false] {
+            let engine = SyncEngine::new();
+            let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+                "engineCommitInfo",
+                ArrowDataType::Map(
+                    Arc::new(Field::new(
+                        "entries",
+                        ArrowDataType::Struct(
+                            vec![
+                                Field::new("key", ArrowDataType::Utf8, false),
+                                Field::new("value", ArrowDataType::Utf8, true),
+                            ]
+                            .into(),
+                        ),
+                        false,
+                    )),
+                    false,
+                ),
+                true,
+            )]));
+            use arrow_array::builder::StringBuilder;
+            let key_builder = StringBuilder::new();
+            let val_builder = StringBuilder::new();
+            let names = arrow_array::builder::MapFieldNames {
+                entry: "entries".to_string(),
+                key: "key".to_string(),
+                value: "value".to_string(),
+            };
+            let mut builder =
+                arrow_array::builder::MapBuilder::new(Some(names), key_builder, val_builder);
+            builder.append(is_null).unwrap();
+            let array = builder.finish();
+
+            let commit_info_batch =
+                RecordBatch::try_new(engine_commit_info_schema, vec![Arc::new(array)])?;
+
+            let actions = generate_commit_info(
+                &engine,
+                test_commit_info(None),
+                &ArrowEngineData::new(commit_info_batch),
+            )?;
+
+            assert_empty_commit_info(actions, is_null)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_metadata_schema() {
+        let schema = get_write_metadata_schema();
+        let expected = StructType::new(vec![
+            StructField::new("path", DataType::STRING, false),
+            StructField::new(
+                "partitionValues",
+                MapType::new(DataType::STRING, DataType::STRING, true),
+                false,
+            ),
+            StructField::new("size", DataType::LONG, false),
+            StructField::new("modificationTime", DataType::LONG, false),
+            StructField::new("dataChange", DataType::BOOLEAN, false),
+            StructField::new("stats", DataType::STRING, true),
+        ]);
+        assert_eq!(*schema, expected.into());
+    }
+}
@@ -0,0 +1,337 @@
+//! Support for removing data files from a table, and for deleting rows from data files with
+//! deletion vectors. Deleting rows from a file replaces its `add` action with a `remove` of the old
+//! logical file and an `add` of the same data file with a deletion vector that marks the deleted
+//! rows.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+
+use url::Url;
+
+use crate::actions::deletion_vector::{DeletionVector, DeletionVectorDescriptor};
+use crate::actions::visitors::{visit_deletion_vector_at, AddVisitor};
+use crate::actions::{get_log_schema, Add, Remove, ADD_NAME, REMOVE_NAME};
+use crate::engine_data::{GetData, RowVisitor};
+use crate::expressions::{ColumnName, Expression as Expr, ExpressionRef};
+use crate::scan::log_replay::SeenFileActions;
+use crate::schema::{column_name, ColumnNamesAndTypes, DataType};
+use crate::snapshot::Snapshot;
+use crate::{DeltaResult, Engine, Error, FileSystemClient};
+
+/// Deletion vectors up to this size (in serialized bytes) are stored inline in the log instead of
+/// in a separate file. Small DVs are common for targeted deletes, and inlining them saves a file
+/// read per data file when scanning, at the cost of a slightly larger log.
+pub(super) const MAX_INLINE_DV_SIZE: usize = 128;
+
+/// Find the live `add` actions of the data files at `paths` in the snapshot, keyed by path. Paths
+/// which are not part of the snapshot are absent from the result.
+pub(super) fn find_live_adds(
+    engine: &dyn Engine,
+    snapshot: &Snapshot,
+    paths: &HashSet<String>,
+) -> DeltaResult<HashMap<String, Add>> {
+    static META_PREDICATE: LazyLock<Option<ExpressionRef>> = LazyLock::new(|| {
+        Some(Arc::new(Expr::or(
+            Expr::column([ADD_NAME, "path"]).is_not_null(),
+            Expr::column([REMOVE_NAME, "path"]).is_not_null(),
+        )))
+    });
+    let schema = get_log_schema().project(&[ADD_NAME, REMOVE_NAME])?;
+    let mut visitor = LiveAddVisitor {
+        paths,
+        seen: SeenFileActions::default(),
+        adds: HashMap::new(),
+        is_log_batch: false,
+    };
+    for maybe_data in
+        snapshot
+            .log_segment
+            .replay(engine, schema.clone(), schema, META_PREDICATE.clone())?
+    {
+        let (actions, is_log_batch) = maybe_data?;
+        visitor.is_log_batch = is_log_batch;
+        visitor.visit_rows_of(actions.as_ref())?;
+    }
+    Ok(visitor.adds)
+}
+
+/// Replays add and remove actions newest-first, keeping the first action seen for each (path,
+/// dvId) pair of the requested paths. Each data file has at most one live add.
+struct LiveAddVisitor<'p> {
+    paths: &'p HashSet<String>,
+    seen: SeenFileActions,
+    adds: HashMap<String, Add>,
+    is_log_batch: bool,
+}
+
+impl LiveAddVisitor<'_> {
+    /// Returns true if this is the first (i.e. most recent) action for the given logical file.
+    fn record_seen(&mut self, path: &str, dv_unique_id: Option<&str>) -> bool {
+        let key = self.seen.key(path, dv_unique_id);
+        if self.seen.contains(&key) {
+            return false;
+        }
+        // Checkpoint actions are already reconciled and never replace each other, so there is no
+        // need to remember them.
+        if self.is_log_batch {
+            self.seen.insert(key);
+        }
+        true
+    }
+}
+
+impl RowVisitor for LiveAddVisitor<'_> {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            let (names, types) = AddVisitor::names_and_types();
+            let mut names = names.to_vec();
+            let mut types = types.to_vec();
+            names.extend([
+                column_name!("remove.path"),
+                column_name!("remove.deletionVector.storageType"),
+                column_name!("remove.deletionVector.pathOrInlineDv"),
+                column_name!("remove.deletionVector.offset"),
+                column_name!("remove.deletionVector.sizeInBytes"),
+                column_name!("remove.deletionVector.cardinality"),
+            ]);
+            types.extend([
+                DataType::STRING,
+                DataType::STRING,
+                DataType::STRING,
+                DataType::INTEGER,
+                DataType::INTEGER,
+                DataType::LONG,
+            ]);
+            (names, types).into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        let num_add_getters = AddVisitor::names_and_types().0.len();
+        let (add_getters, remove_getters) = getters.split_at(num_add_getters);
+        for i in 0..row_count {
+            if let Some(path) = add_getters[0].get_str(i, "add.path")? {
+                if !self.paths.contains(path) {
+                    continue;
+                }
+                let add = AddVisitor::visit_add(i, path.to_string(), add_getters)?;
+                if self.record_seen(path, add.dv_unique_id().as_deref()) {
+                    self.adds.insert(add.path.clone(), add);
+                }
+            } else if let Some(path) = remove_getters[0].get_str(i, "remove.path")? {
+                if !self.paths.contains(path) {
+                    continue;
+                }
+                let deletion_vector = visit_deletion_vector_at(i, &remove_getters[1..])?;
+                let dv_unique_id = deletion_vector.map(|dv| dv.unique_id());
+                self.record_seen(path, dv_unique_id.as_deref());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compute the actions which delete the rows in `deleted` from the logical file of `add`, merging
+/// them with the rows already deleted by its current deletion vector. The result is a `remove` of
+/// the current logical file, and an `add` of the data file with the new deletion vector, unless
+/// every row of the file is deleted. Returns `None` if none of the rows are newly deleted.
+pub(super) fn delete_rows_from_file(
+    fs_client: Arc<dyn FileSystemClient>,
+    table_root: &Url,
+    add: &Add,
+    mut deleted: DeletionVector,
+    deletion_timestamp: i64,
+) -> DeltaResult<Option<(Remove, Option<Add>)>> {
+    let num_records = num_records(add)?;
+    if let Some(num_records) = num_records {
+        if let Some(row_index) = deleted.row_indexes().find(|&row| row >= num_records) {
+            return Err(Error::generic(format!(
+                "Cannot delete row {row_index} of file {}, which has {num_records} rows",
+                add.path
+            )));
+        }
+    }
+    if let Some(descriptor) = &add.deletion_vector {
+        let existing = descriptor.load(fs_client.clone(), table_root)?;
+        let num_deleted = existing.cardinality();
+        deleted.union_with(&existing);
+        if deleted.cardinality() == num_deleted {
+            return Ok(None);
+        }
+    } else if deleted.is_empty() {
+        return Ok(None);
+    }
+
+    let remove = remove_action(add, deletion_timestamp, true);
+    if num_records.is_some_and(|num_records| deleted.cardinality() >= num_records) {
+        return Ok(Some((remove, None)));
+    }
+
+    let descriptor = if deleted.serialize()?.len() <= MAX_INLINE_DV_SIZE {
+        DeletionVectorDescriptor::inline(&deleted)?
+    } else {
+        DeletionVectorDescriptor::write(&deleted, fs_client.as_ref(), table_root)?
+    };
+    let add = Add {
+        data_change: true,
+        stats: loose_bounds_stats(add.stats.as_deref())?,
+        deletion_vector: Some(descriptor),
+        ..add.clone()
+    };
+    Ok(Some((remove, Some(add))))
+}
+
+/// The `remove` action for the logical file of `add`, which keeps its metadata (i.e. it has
+/// `extendedFileMetadata`).
+pub(super) fn remove_action(add: &Add, deletion_timestamp: i64, data_change: bool) -> Remove {
+    Remove {
+        path: add.path.clone(),
+        deletion_timestamp: Some(deletion_timestamp),
+        data_change,
+        extended_file_metadata: Some(true),
+        partition_values: Some(add.partition_values.clone()),
+        size: Some(add.size),
+        tags: add.tags.clone(),
+        deletion_vector: add.deletion_vector.clone(),
+        base_row_id: add.base_row_id,
+        default_row_commit_version: add.default_row_commit_version,
+    }
+}
+
+// The number of (physical) rows in the data file of `add`, if recorded in its statistics.
+fn num_records(add: &Add) -> DeltaResult<Option<u64>> {
+    let Some(stats) = &add.stats else {
+        return Ok(None);
+    };
+    let stats: serde_json::Value = serde_json::from_str(stats)?;
+    Ok(stats.get("numRecords").and_then(|n| n.as_u64()))
+}
+
+// The min/max statistics of a file with a deletion vector may include deleted rows, so they are
+// no longer tight bounds of its live rows. Statistics are kept, but marked as such.
+fn loose_bounds_stats(stats: Option<&str>) -> DeltaResult<Option<String>> {
+    let Some(stats) = stats else {
+        return Ok(None);
+    };
+    let mut stats: serde_json::Value = serde_json::from_str(stats)?;
+    if let Some(stats) = stats.as_object_mut() {
+        stats.insert("tightBounds".to_string(), false.into());
+    }
+    Ok(Some(stats.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+
+    fn add(stats: Option<&str>) -> Add {
+        Add {
+            path: "part-00000.parquet".to_string(),
+            partition_values: HashMap::from([("p".to_string(), "1".to_string())]),
+            size: 100,
+            data_change: true,
+            stats: stats.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_delete_rows_from_file() {
+        let engine = SyncEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = Url::from_directory_path(dir.path()).unwrap();
+        let fs_client = engine.get_file_system_client();
+        let stats = r#"{"numRecords":10,"minValues":{"x":1},"maxValues":{"x":10}}"#;
+        let original = add(Some(stats));
+
+        let deleted = [1, 3].into_iter().collect();
+        let (remove, new_add) =
+            delete_rows_from_file(fs_client.clone(), &table_root, &original, deleted, 1000)
+                .unwrap()
+                .unwrap();
+        assert_eq!(remove.path, original.path);
+        assert_eq!(remove.deletion_timestamp, Some(1000));
+        assert_eq!(
+            remove.partition_values,
+            Some(original.partition_values.clone())
+        );
+        assert_eq!(remove.size, Some(100));
+        assert_eq!(remove.deletion_vector, None);
+        let new_add = new_add.unwrap();
+        let descriptor = new_add.deletion_vector.clone().unwrap();
+        assert_eq!(descriptor.storage_type, "i");
+        assert_eq!(descriptor.cardinality, 2);
+        let stats: serde_json::Value =
+            serde_json::from_str(new_add.stats.as_ref().unwrap()).unwrap();
+        assert_eq!(stats["tightBounds"], false);
+        assert_eq!(stats["numRecords"], 10);
+
+        // deleting more rows merges with the existing DV, and deleting the same rows does nothing
+        let deleted = [3, 5].into_iter().collect();
+        let (remove, newer_add) =
+            delete_rows_from_file(fs_client.clone(), &table_root, &new_add, deleted, 2000)
+                .unwrap()
+                .unwrap();
+        assert_eq!(remove.deletion_vector, Some(descriptor));
+        let dv = newer_add
+            .unwrap()
+            .deletion_vector
+            .unwrap()
+            .load(fs_client.clone(), &table_root)
+            .unwrap();
+        assert_eq!(dv.row_indexes().collect::<Vec<_>>(), [1, 3, 5]);
+        let deleted = [1].into_iter().collect();
+        let result =
+            delete_rows_from_file(fs_client.clone(), &table_root, &new_add, deleted, 2000).unwrap();
+        assert!(result.is_none());
+
+        // deleting every row removes the file
+        let (_, new_add) = delete_rows_from_file(
+            fs_client.clone(),
+            &table_root,
+            &original,
+            (0..10).collect(),
+            1000,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(new_add.is_none());
+
+        // rows must be in the file
+        let err = delete_rows_from_file(
+            fs_client.clone(),
+            &table_root,
+            &original,
+            [10].into_iter().collect(),
+            1000,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("which has 10 rows"));
+    }
+
+    #[test]
+    fn test_large_deletion_vector_is_written_to_file() {
+        let engine = SyncEngine::new();
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = Url::from_directory_path(dir.path()).unwrap();
+        let fs_client = engine.get_file_system_client();
+
+        // without stats, the number of rows is unknown
+        let deleted: DeletionVector = (0..10_000).step_by(7).collect();
+        let (_, new_add) = delete_rows_from_file(
+            fs_client.clone(),
+            &table_root,
+            &add(None),
+            deleted.clone(),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+        let new_add = new_add.unwrap();
+        assert_eq!(new_add.stats, None);
+        let descriptor = new_add.deletion_vector.unwrap();
+        assert_eq!(descriptor.storage_type, "u");
+        assert_eq!(descriptor.load(fs_client, &table_root).unwrap(), deleted);
+    }
+}
@@ -0,0 +1,181 @@
+//! Selection of the columns whose statistics are collected for the data files written to a table.
+//! See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#per-file-statistics>
+use crate::expressions::ColumnName;
+use crate::schema::{DataType, StructField, StructType};
+use crate::table_properties::{DataSkippingNumIndexedCols, TableProperties};
+
+/// The physical names of the leaf columns of the data files whose statistics must be collected.
+///
+/// If the table sets `delta.dataSkippingStatsColumns`, these are the (logical) columns it lists,
+/// with struct columns expanded to all of their leaves. Otherwise, these are the first
+/// `delta.dataSkippingNumIndexedCols` leaf columns of the schema, in depth-first order. Partition
+/// columns are never included, since their values are recorded in the log instead.
+pub(super) fn stats_columns(
+    logical_schema: &StructType,
+    partition_columns: &[String],
+    table_properties: &TableProperties,
+) -> Vec<ColumnName> {
+    let mut columns = vec![];
+    if let Some(stats_columns) = &table_properties.data_skipping_stats_columns {
+        for column in stats_columns {
+            if column
+                .first()
+                .is_some_and(|name| partition_columns.contains(name))
+            {
+                continue;
+            }
+            // columns that do not (or no longer) exist are ignored
+            if let Some((field, physical_path)) = resolve_physical(logical_schema, column) {
+                let mut unlimited = usize::MAX;
+                push_leaves(field, physical_path, &mut columns, &mut unlimited);
+            }
+        }
+        return columns;
+    }
+    let mut remaining = match table_properties.data_skipping_num_indexed_cols_or_default() {
+        DataSkippingNumIndexedCols::AllColumns => usize::MAX,
+        DataSkippingNumIndexedCols::NumColumns(n) => n.try_into().unwrap_or(usize::MAX),
+    };
+    for field in logical_schema
+        .fields()
+        .filter(|f| !partition_columns.contains(f.name()))
+    {
+        push_leaves(
+            field,
+            vec![field.physical_name().to_string()],
+            &mut columns,
+            &mut remaining,
+        );
+    }
+    columns
+}
+
+// Find the field of `column` in `schema`, along with its physical path
+fn resolve_physical<'a>(
+    schema: &'a StructType,
+    column: &ColumnName,
+) -> Option<(&'a StructField, Vec<String>)> {
+    let (first, rest) = column.split_first()?;
+    let mut field = schema.field(first)?;
+    let mut physical_path = vec![field.physical_name().to_string()];
+    for name in rest {
+        let DataType::Struct(inner) = field.data_type() else {
+            return None;
+        };
+        field = inner.field(name)?;
+        physical_path.push(field.physical_name().to_string());
+    }
+    Some((field, physical_path))
+}
+
+// Add the leaf columns of `field` (at `physical_path`) to `columns` in depth-first order, until
+// `remaining` columns have been added. Arrays, maps and variants count as leaves.
+fn push_leaves(
+    field: &StructField,
+    physical_path: Vec<String>,
+    columns: &mut Vec<ColumnName>,
+    remaining: &mut usize,
+) {
+    match field.data_type() {
+        DataType::Struct(inner) => {
+            for child in inner.fields() {
+                let mut path = physical_path.clone();
+                path.push(child.physical_name().to_string());
+                push_leaves(child, path, columns, remaining);
+            }
+        }
+        _ if *remaining > 0 => {
+            *remaining -= 1;
+            columns.push(ColumnName::new(physical_path));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::column_name;
+    use crate::schema::{ArrayType, ColumnMetadataKey, MetadataValue};
+
+    fn table_properties(properties: &[(&str, &str)]) -> TableProperties {
+        properties.iter().copied().into()
+    }
+
+    #[test]
+    fn test_stats_columns() {
+        let nested = StructType::new([
+            StructField::new("x", DataType::LONG, true),
+            StructField::new("y", DataType::STRING, true),
+        ]);
+        let schema = StructType::new([
+            StructField::new("a", DataType::INTEGER, true),
+            StructField::new("part", DataType::STRING, true),
+            StructField::new("b", nested, true),
+            StructField::new("c", ArrayType::new(DataType::LONG, true), true),
+            StructField::new("d", DataType::DATE, true),
+        ]);
+        let partition_columns = ["part".to_string()];
+
+        let columns = stats_columns(&schema, &partition_columns, &table_properties(&[]));
+        assert_eq!(
+            columns,
+            [
+                column_name!("a"),
+                column_name!("b.x"),
+                column_name!("b.y"),
+                column_name!("c"),
+                column_name!("d"),
+            ]
+        );
+
+        let properties = table_properties(&[("delta.dataSkippingNumIndexedCols", "2")]);
+        let columns = stats_columns(&schema, &partition_columns, &properties);
+        assert_eq!(columns, [column_name!("a"), column_name!("b.x")]);
+
+        let properties = table_properties(&[("delta.dataSkippingNumIndexedCols", "0")]);
+        assert!(stats_columns(&schema, &partition_columns, &properties).is_empty());
+
+        // explicit stats columns take precedence, and ignore partition and missing columns
+        let properties = table_properties(&[
+            ("delta.dataSkippingNumIndexedCols", "1"),
+            ("delta.dataSkippingStatsColumns", "d,b,part,missing,a.x"),
+        ]);
+        let columns = stats_columns(&schema, &partition_columns, &properties);
+        assert_eq!(
+            columns,
+            [column_name!("d"), column_name!("b.x"), column_name!("b.y")]
+        );
+    }
+
+    #[test]
+    fn test_stats_columns_use_physical_names() {
+        let physical = |name: &str, field: StructField| {
+            field.with_metadata([(
+                ColumnMetadataKey::ColumnMappingPhysicalName.as_ref(),
+                MetadataValue::String(name.into()),
+            )])
+        };
+        let nested = StructType::new([physical(
+            "col-2",
+            StructField::new("x", DataType::LONG, true),
+        )]);
+        let schema = StructType::new([
+            physical("col-1", StructField::new("a", nested, true)),
+            physical("col-3", StructField::new("b", DataType::LONG, true)),
+        ]);
+
+        let columns = stats_columns(&schema, &[], &table_properties(&[]));
+        assert_eq!(
+            columns,
+            [
+                ColumnName::new(["col-1", "col-2"]),
+                ColumnName::new(["col-3"])
+            ]
+        );
+
+        let properties = table_properties(&[("delta.dataSkippingStatsColumns", "a.x")]);
+        let columns = stats_columns(&schema, &[], &properties);
+        assert_eq!(columns, [ColumnName::new(["col-1", "col-2"])]);
+    }
+}
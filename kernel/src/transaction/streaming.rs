@@ -0,0 +1,195 @@
+//! A writer for the micro-batches of streaming queries, which commits each batch to a table
+//! exactly once. Each batch is committed along with an application transaction (a `txn` action)
+//! recording its version, so that batches which were already committed (e.g. by an earlier attempt
+//! of a restarted query) are not committed again.
+use std::sync::Arc;
+
+use tracing::debug;
+
+use super::{CommitResult, ConflictType, Transaction, WriteContext};
+use crate::actions::set_transaction::SetTransactionScanner;
+use crate::snapshot::Snapshot;
+use crate::{DeltaResult, Engine, EngineData, Error, Table, Version};
+
+const STREAMING_UPDATE_OPERATION: &str = "STREAMING UPDATE";
+const DEFAULT_MAX_RETRIES: usize = 10;
+
+/// The result of committing a micro-batch with a [`StreamingWriter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamingCommitResult {
+    /// The batch was committed to the table at the version.
+    Committed(Version),
+    /// The batch (or a later one) was already committed to the table by the application, so it
+    /// was not committed again. The data files written for the batch are not part of the table,
+    /// and may be deleted.
+    AlreadyCommitted,
+}
+
+/// Appends the micro-batches of a streaming query (the application `app_id`) to a table with
+/// exactly-once semantics. Engines write the data files of each batch (see [`write_context`]),
+/// and then commit them with [`commit_batch`] along with the version of the batch, which must
+/// increase from one batch to the next.
+///
+/// Committing a batch is idempotent: a batch whose version is not greater than the version of the
+/// last batch committed by the application is not committed again. After a restart, queries resume
+/// after the [`latest_batch_version`]. Commits which conflict with concurrent commits of the same
+/// application are retried (up to a maximum number of times, see [`with_max_retries`]) on top of
+/// the latest version of the table.
+///
+/// [`write_context`]: Self::write_context
+/// [`commit_batch`]: Self::commit_batch
+/// [`latest_batch_version`]: Self::latest_batch_version
+/// [`with_max_retries`]: Self::with_max_retries
+#[derive(Debug)]
+pub struct StreamingWriter {
+    table: Table,
+    app_id: String,
+    max_retries: usize,
+}
+
+impl StreamingWriter {
+    /// Create a writer for the batches of the application `app_id` (which must be unique to the
+    /// streaming query, and stable across restarts) to `table`.
+    pub fn new(table: Table, app_id: impl Into<String>) -> Self {
+        Self {
+            table,
+            app_id: app_id.into(),
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// Set the maximum number of times a commit is retried after conflicting with a concurrent
+    /// commit of the application. Defaults to 10.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The ID of the application whose batches this writer commits
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    /// The version of the last batch committed by the application to the latest version of the
+    /// table, if any.
+    pub fn latest_batch_version(&self, engine: &dyn Engine) -> DeltaResult<Option<i64>> {
+        let snapshot = Arc::new(self.table.snapshot(engine, None)?);
+        self.batch_version_at(engine, snapshot)
+    }
+
+    /// The write context for the data files of the next batch, at the latest version of the
+    /// table.
+    pub fn write_context(&self, engine: &dyn Engine) -> DeltaResult<WriteContext> {
+        Ok(self.table.new_transaction(engine)?.write_context())
+    }
+
+    /// Commit the data files of batch `batch_version`, given by their `write_metadata` (see
+    /// [`Transaction::add_write_metadata`]), along with the engine's `commit_info` (see
+    /// [`Transaction::with_commit_info`]).
+    ///
+    /// Returns [`StreamingCommitResult::AlreadyCommitted`] without committing if the application
+    /// has already committed this batch or a later one. Fails if the commit conflicts with a
+    /// concurrent change to the protocol or metadata of the table, or if it still conflicts with
+    /// concurrent commits of the application after the maximum number of retries.
+    pub fn commit_batch(
+        &self,
+        engine: &dyn Engine,
+        batch_version: i64,
+        commit_info: Box<dyn EngineData>,
+        write_metadata: impl IntoIterator<Item = Box<dyn EngineData>>,
+    ) -> DeltaResult<StreamingCommitResult> {
+        let snapshot = Arc::new(self.table.snapshot(engine, None)?);
+        if self.is_committed(engine, snapshot.clone(), batch_version)? {
+            return Ok(StreamingCommitResult::AlreadyCommitted);
+        }
+        let mut txn = self
+            .batch_transaction(snapshot, batch_version)?
+            .with_commit_info(commit_info);
+        for write_metadata in write_metadata {
+            txn.add_write_metadata(write_metadata);
+        }
+        let mut retries = 0;
+        loop {
+            let (failed_txn, version) = match txn.commit(engine)? {
+                CommitResult::Committed(version) => {
+                    return Ok(StreamingCommitResult::Committed(version))
+                }
+                CommitResult::Conflict(txn, version) => (txn, version),
+            };
+            // only concurrent commits of the application (e.g. by a zombie attempt of the query)
+            // can be resolved by retrying on top of them
+            let conflict = failed_txn.conflict();
+            if !matches!(conflict, Some(ConflictType::ConcurrentTransaction(_))) {
+                return Err(Error::generic(format!(
+                    "Batch {batch_version} of {} conflicts with version {version} of the table: \
+                     {conflict:?}",
+                    self.app_id
+                )));
+            }
+            if retries == self.max_retries {
+                return Err(Error::generic(format!(
+                    "Failed to commit batch {batch_version} of {} after {retries} retries",
+                    self.app_id
+                )));
+            }
+            retries += 1;
+            let snapshot = Arc::new(self.table.snapshot(engine, None)?);
+            if self.is_committed(engine, snapshot.clone(), batch_version)? {
+                return Ok(StreamingCommitResult::AlreadyCommitted);
+            }
+            debug!(
+                "Retrying batch {batch_version} of {} after conflict with version {version}",
+                self.app_id
+            );
+            // carry the commit info and files of the batch over to the retried transaction
+            txn = self.batch_transaction(snapshot, batch_version)?;
+            txn.commit_info = failed_txn.commit_info;
+            txn.write_metadata = failed_txn.write_metadata;
+        }
+    }
+
+    fn batch_transaction(
+        &self,
+        snapshot: Arc<Snapshot>,
+        batch_version: i64,
+    ) -> DeltaResult<Transaction> {
+        Ok(snapshot
+            .new_transaction()?
+            .with_operation(STREAMING_UPDATE_OPERATION.to_string())
+            .with_operation_parameters([
+                ("outputMode", "Append".to_string()),
+                ("queryId", self.app_id.clone()),
+                ("epochId", batch_version.to_string()),
+            ])
+            .with_transaction_id(self.app_id.clone(), batch_version))
+    }
+
+    // Whether the application has committed batch `batch_version` (or a later one) in `snapshot`
+    fn is_committed(
+        &self,
+        engine: &dyn Engine,
+        snapshot: Arc<Snapshot>,
+        batch_version: i64,
+    ) -> DeltaResult<bool> {
+        let latest = self.batch_version_at(engine, snapshot)?;
+        if latest.is_some_and(|latest| latest >= batch_version) {
+            debug!(
+                "Batch {batch_version} of {} was already committed",
+                self.app_id
+            );
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // The version of the last batch committed by the application in `snapshot`
+    fn batch_version_at(
+        &self,
+        engine: &dyn Engine,
+        snapshot: Arc<Snapshot>,
+    ) -> DeltaResult<Option<i64>> {
+        let txn =
+            SetTransactionScanner::new(snapshot).application_transaction(engine, &self.app_id)?;
+        Ok(txn.map(|txn| txn.version))
+    }
+}
@@ -11,6 +11,36 @@ macro_rules! require {
 
 pub(crate) use require;
 
+/// The current time. Unlike [`std::time::SystemTime::now`], which panics on wasm32-unknown-unknown,
+/// this gets the time from the JS host there.
+pub(crate) fn current_time() -> std::time::SystemTime {
+    chrono::Utc::now().into()
+}
+
+/// The time (in milliseconds since the unix epoch) `retention` before `now`, before which actions
+/// with a retention duration (e.g. tombstones) have expired.
+pub(crate) fn retention_cutoff_millis(
+    now: std::time::SystemTime,
+    retention: std::time::Duration,
+) -> crate::DeltaResult<i64> {
+    let cutoff = now.checked_sub(retention).unwrap_or(std::time::UNIX_EPOCH);
+    cutoff
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| crate::Error::generic("retention cutoff is before the unix epoch"))?
+        .as_millis()
+        .try_into()
+        .map_err(|_| crate::Error::generic("milliseconds since unix_epoch exceeded i64 size"))
+}
+
+/// Enter `span` whenever the next item of `iter` is computed, so that the (lazy) work of the
+/// iterator is attributed to the span rather than to whatever span its consumer is in.
+pub(crate) fn in_span<I: Iterator>(
+    span: tracing::Span,
+    mut iter: I,
+) -> impl Iterator<Item = I::Item> {
+    std::iter::from_fn(move || span.in_scope(|| iter.next()))
+}
+
 #[cfg(test)]
 pub(crate) mod test_utils {
     use itertools::Itertools;
@@ -20,8 +50,11 @@ pub(crate) mod test_utils {
     use std::{path::Path, sync::Arc};
     use tempfile::TempDir;
     use test_utils::delta_path_for_version;
+    use url::Url;
 
-    use crate::actions::{Add, Cdc, CommitInfo, Metadata, Protocol, Remove};
+    use crate::actions::{
+        Add, Cdc, CommitInfo, DomainMetadata, Metadata, Protocol, Remove, SetTransaction,
+    };
 
     #[derive(Serialize)]
     pub(crate) enum Action {
@@ -35,9 +68,12 @@ pub(crate) mod test_utils {
         Metadata(Metadata),
         #[serde(rename = "protocol")]
         Protocol(Protocol),
-        #[allow(unused)]
         #[serde(rename = "commitInfo")]
         CommitInfo(CommitInfo),
+        #[serde(rename = "domainMetadata")]
+        DomainMetadata(DomainMetadata),
+        #[serde(rename = "txn")]
+        SetTransaction(SetTransaction),
     }
 
     /// A mock table that writes commits to a local temporary delta log. This can be used to
@@ -78,4 +114,21 @@ pub(crate) mod test_utils {
             self.dir.path()
         }
     }
+
+    /// Copy a test table (of `tests/data`) to `dir`, e.g. so that files can be added to it, and
+    /// get the url of the copy.
+    pub(crate) fn copy_table(name: &str, dir: &Path) -> Url {
+        let source = std::fs::canonicalize(format!("./tests/data/{name}/")).unwrap();
+        let mut stack = vec![source.clone()];
+        while let Some(path) = stack.pop() {
+            let target = dir.join(path.strip_prefix(&source).unwrap());
+            if path.is_dir() {
+                std::fs::create_dir_all(&target).unwrap();
+                stack.extend(std::fs::read_dir(&path).unwrap().map(|e| e.unwrap().path()));
+            } else {
+                std::fs::copy(&path, &target).unwrap();
+            }
+        }
+        Url::from_directory_path(dir).unwrap()
+    }
 }
@@ -0,0 +1,294 @@
+//! Planning of VACUUM, i.e. finding the files in the directory of a table which are no longer
+//! needed by any version of the table within the retention duration, see
+//! [`Snapshot::vacuum_plan`]. The kernel only plans a vacuum: it's up to the engine to delete the
+//! files of the plan.
+//!
+//! [`Snapshot::vacuum_plan`]: crate::snapshot::Snapshot::vacuum_plan
+
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+use tracing::debug;
+use url::Url;
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::tombstones::visit_file_actions;
+use crate::log_segment::list_commit_files;
+use crate::path::resolve_file_path;
+use crate::snapshot::Snapshot;
+use crate::utils::{require, retention_cutoff_millis};
+use crate::{DeltaResult, Engine, Error, FileMeta, FileSystemClient, ListOptions};
+
+/// The name of the directory with the change data files of a table, see [`is_hidden`].
+const CHANGE_DATA_DIR_NAME: &str = "_change_data";
+
+/// The files a VACUUM of a table would delete, and statistics about the files of the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VacuumPlan {
+    /// The files which are safe to delete, sorted by location.
+    pub files_to_delete: Vec<FileMeta>,
+    /// The total size of the files to delete, in bytes.
+    pub bytes_to_delete: u64,
+    /// The number of files found in the directory of the table, excluding the `_delta_log` and
+    /// other hidden files.
+    pub files_listed: usize,
+    /// The number of listed files which are referenced by a version of the table within the
+    /// retention duration, or were modified too recently to be deleted.
+    pub files_retained: usize,
+    /// The retention duration the plan was made with.
+    pub retention: Duration,
+    /// The time `retention` before the plan was made, in milliseconds since the epoch. Files which
+    /// were removed from the table (or modified) at or after this time are retained.
+    pub cutoff_timestamp: i64,
+}
+
+/// Plan a VACUUM of the table of `snapshot` at the time `now`. See [`Snapshot::vacuum_plan`].
+pub(crate) fn plan_vacuum(
+    snapshot: &Snapshot,
+    engine: &dyn Engine,
+    retention: Option<Duration>,
+    now: SystemTime,
+) -> DeltaResult<VacuumPlan> {
+    let table_retention = snapshot
+        .table_properties()
+        .deleted_file_retention_duration_or_default();
+    let retention = retention.unwrap_or(table_retention);
+    require!(
+        retention >= table_retention,
+        Error::generic(format!(
+            "Cannot vacuum with a retention of {retention:?}, which is shorter than the \
+            delta.deletedFileRetentionDuration of the table ({table_retention:?})"
+        ))
+    );
+    let cutoff_timestamp = retention_cutoff_millis(now, retention)?;
+    let table_root = snapshot.table_root();
+    let fs_client = engine.get_file_system_client();
+
+    // Files added by later versions aren't referenced by this snapshot
+    let version = snapshot.version();
+    let log_root = &snapshot.log_segment.log_root;
+    let later_commits = list_commit_files(fs_client.as_ref(), log_root, version + 1, None)?;
+    if let Some(latest) = later_commits.last() {
+        return Err(Error::generic(format!(
+            "Cannot vacuum with version {version} of the table, which isn't the latest version ({})",
+            latest.version
+        )));
+    }
+
+    // The files referenced by the snapshot, and by the earlier versions within the retention
+    // duration (whose files were removed at or after the cutoff). Even the deletion vectors of
+    // tombstones whose data file is still live must be retained, for reading those versions.
+    let actions = visit_file_actions(snapshot, engine)?;
+    let mut referenced = HashSet::new();
+    let retained_tombstones = actions
+        .tombstones
+        .iter()
+        .filter(|tombstone| tombstone.deletion_timestamp.unwrap_or(0) >= cutoff_timestamp)
+        .map(|tombstone| (&tombstone.path, &tombstone.deletion_vector));
+    let live_files = actions.live_files.iter().map(|(path, dv)| (path, dv));
+    for (path, deletion_vector) in live_files.chain(retained_tombstones) {
+        referenced.insert(resolve_file_path(table_root, path)?);
+        if let Some(dv_path) = deletion_vector_path(deletion_vector.as_ref(), table_root)? {
+            referenced.insert(dv_path);
+        }
+    }
+
+    let partition_columns = &snapshot.metadata().partition_columns;
+    let files = list_table_files(fs_client.as_ref(), table_root, partition_columns)?;
+    let files_listed = files.len();
+    let mut files_to_delete: Vec<_> = files
+        .into_iter()
+        .filter(|file| {
+            file.last_modified < cutoff_timestamp && !referenced.contains(&file.location)
+        })
+        .collect();
+    files_to_delete.sort_unstable();
+    let bytes_to_delete = files_to_delete.iter().map(|file| file.size as u64).sum();
+    debug!(
+        "Planned vacuum of {table_root}: {} of {files_listed} files to delete",
+        files_to_delete.len()
+    );
+    Ok(VacuumPlan {
+        files_retained: files_listed - files_to_delete.len(),
+        files_to_delete,
+        bytes_to_delete,
+        files_listed,
+        retention,
+        cutoff_timestamp,
+    })
+}
+
+fn deletion_vector_path(
+    deletion_vector: Option<&DeletionVectorDescriptor>,
+    table_root: &Url,
+) -> DeltaResult<Option<Url>> {
+    match deletion_vector {
+        Some(deletion_vector) => deletion_vector.absolute_path(table_root),
+        None => Ok(None),
+    }
+}
+
+// List all files under the table root, walking its directories (with delimited listings) except
+// for the hidden ones. Clients which don't support delimited listings list all files under the
+// table root at once, so the hidden ones are skipped by their (relative) path either way.
+fn list_table_files(
+    fs_client: &dyn FileSystemClient,
+    table_root: &Url,
+    partition_columns: &[String],
+) -> DeltaResult<Vec<FileMeta>> {
+    let options = ListOptions::default().with_delimiter();
+    let mut directories = vec![table_root.clone()];
+    let mut files = vec![];
+    let mut seen = HashSet::new();
+    while let Some(directory) = directories.pop() {
+        for file in fs_client.list_with_options(&directory, &options)? {
+            let file = file?;
+            let Some(relative_path) = file.location.path().strip_prefix(table_root.path()) else {
+                continue;
+            };
+            let hidden = relative_path
+                .split('/')
+                .any(|name| is_hidden(name, partition_columns));
+            if hidden || !seen.insert(file.location.clone()) {
+                continue;
+            }
+            if file.is_directory() {
+                directories.push(file.location);
+            } else {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+// Files and directories whose names start with `_` or `.` (e.g. `_delta_log`) aren't data files
+// of the table, except for the change data directory and the directories of partition columns
+// whose names start with `_`
+fn is_hidden(name: &str, partition_columns: &[String]) -> bool {
+    if !(name.starts_with('_') || name.starts_with('.')) || name == CHANGE_DATA_DIR_NAME {
+        return false;
+    }
+    !name
+        .split_once('=')
+        .is_some_and(|(column, _)| partition_columns.iter().any(|c| c == column))
+}
+
+#[cfg(all(test, feature = "sync-engine"))]
+mod tests {
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::utils::test_utils::copy_table;
+    use crate::Table;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn files_to_delete(plan: &VacuumPlan, table_root: &Url) -> Vec<String> {
+        plan.files_to_delete
+            .iter()
+            .map(|file| file.location.path()[table_root.path().len()..].to_string())
+            .collect()
+    }
+
+    fn millis(time: SystemTime) -> i64 {
+        retention_cutoff_millis(time, Duration::ZERO).unwrap()
+    }
+
+    #[test]
+    fn test_is_hidden() {
+        let partition_columns = ["_p".to_string()];
+        assert!(is_hidden("_delta_log", &partition_columns));
+        assert!(is_hidden(".part-0.parquet.crc", &partition_columns));
+        assert!(is_hidden("_q=1", &partition_columns));
+        assert!(!is_hidden("_p=1", &partition_columns));
+        assert!(!is_hidden("_change_data", &partition_columns));
+        assert!(!is_hidden("part-0.parquet", &partition_columns));
+    }
+
+    #[test]
+    fn test_vacuum_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = copy_table("basic_partitioned", dir.path());
+        let write = |path: &str, data: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        };
+        write("orphan.parquet", "orphan");
+        write("_change_data/cdc.parquet", "cdc");
+        write("_hidden/file.parquet", "hidden");
+        write("letter=a/.part.parquet.crc", "crc");
+        // remove a data file of the table 29 days from now
+        let now = SystemTime::now();
+        let removed =
+            "letter=a/part-00000-a08d296a-d2c5-4a99-bea9-afcea42ba2e9.c000.snappy.parquet";
+        let remove = serde_json::json!({
+            "remove": {
+                "path": removed,
+                "deletionTimestamp": millis(now + 29 * DAY),
+                "dataChange": true,
+            }
+        });
+        write("_delta_log/00000000000000000002.json", &remove.to_string());
+
+        let engine = SyncEngine::new();
+        let snapshot = Table::new(table_root.clone())
+            .snapshot(&engine, None)
+            .unwrap();
+
+        // Nothing is old enough to delete right after the files were written
+        let plan = snapshot.vacuum_plan(&engine, None).unwrap();
+        assert_eq!(plan.retention, 7 * DAY);
+        assert!(plan.files_to_delete.is_empty());
+        assert_eq!(plan.files_listed, 8);
+        assert_eq!(plan.files_retained, 8);
+
+        // A month from now, the removed file is still needed by versions within the retention
+        let plan = plan_vacuum(&snapshot, &engine, None, now + 30 * DAY).unwrap();
+        assert_eq!(
+            files_to_delete(&plan, &table_root),
+            ["_change_data/cdc.parquet", "orphan.parquet"]
+        );
+        assert_eq!(plan.bytes_to_delete, 9);
+        assert_eq!(plan.files_retained, 6);
+
+        // ... but no longer once its tombstone expired
+        let plan = plan_vacuum(&snapshot, &engine, None, now + 40 * DAY).unwrap();
+        assert_eq!(
+            files_to_delete(&plan, &table_root),
+            ["_change_data/cdc.parquet", removed, "orphan.parquet"]
+        );
+
+        // ... unless the retention is longer
+        let plan = plan_vacuum(&snapshot, &engine, Some(20 * DAY), now + 40 * DAY).unwrap();
+        assert_eq!(plan.files_to_delete.len(), 2);
+
+        // A retention shorter than the table's is rejected
+        let result = plan_vacuum(&snapshot, &engine, Some(DAY), now + 40 * DAY);
+        assert!(result.unwrap_err().to_string().contains("shorter"));
+
+        // ... and so is a snapshot which isn't the latest
+        write("_delta_log/00000000000000000003.json", &remove.to_string());
+        let result = plan_vacuum(&snapshot, &engine, None, now + 40 * DAY);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("latest version (3)"));
+    }
+
+    #[test]
+    fn test_vacuum_plan_with_deletion_vectors() {
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = copy_table("table-with-dv-small", dir.path());
+        let orphan_dv = "deletion_vector_00000000-0000-0000-0000-000000000000.bin";
+        std::fs::write(dir.path().join(orphan_dv), "dv").unwrap();
+
+        let engine = SyncEngine::new();
+        let snapshot = Table::new(table_root.clone())
+            .snapshot(&engine, None)
+            .unwrap();
+        let plan = plan_vacuum(&snapshot, &engine, None, SystemTime::now() + 30 * DAY).unwrap();
+        assert_eq!(files_to_delete(&plan, &table_root), [orphan_dv]);
+        assert_eq!(plan.files_listed, 3);
+    }
+}
@@ -0,0 +1,380 @@
+//! Consistency checks of a table (an FSCK), see [`Table::verify`]. A [`VerificationReport`] lists
+//! the files the table references but which are missing from storage, and whether the checkpoint
+//! of the table (and its `_last_checkpoint` file) can be read and matches a replay of its commits. The kernel only reports problems: it's up to the
+//! engine to surface them, and to repair the table.
+//!
+//! The kernel doesn't support V2 checkpoints, so there are no sidecar files to check.
+//!
+//! [`Table::verify`]: crate::Table::verify
+
+use std::collections::HashSet;
+
+use tracing::debug;
+use url::Url;
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::tombstones::{
+    visit_file_actions, visit_log_segment_file_actions, TombstoneVisitor,
+};
+use crate::log_segment::{is_checkpoint_error, LogSegment};
+use crate::path::resolve_file_path;
+use crate::snapshot::{parse_last_checkpoint, read_last_checkpoint_file, Snapshot};
+use crate::{DeltaResult, Engine, ErrorKind, FileSystemClient, Version};
+
+/// The result of a consistency check of a version of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// The version of the table which was checked.
+    pub version: Version,
+    /// The number of live data files of the version.
+    pub files_checked: usize,
+    /// The live data files which don't exist in storage, sorted by location.
+    pub missing_files: Vec<Url>,
+    /// The deletion vector files of live data files which don't exist in storage, sorted by
+    /// location. Inline deletion vectors have no file.
+    pub missing_deletion_vectors: Vec<Url>,
+    /// True if the `_last_checkpoint` file exists but is invalid (e.g. not JSON, or with a wrong
+    /// checksum). Readers then ignore it, and find the latest checkpoint by listing the log.
+    pub invalid_last_checkpoint: bool,
+    /// The result of validating the checkpoint the version was read from.
+    pub checkpoint: CheckpointVerification,
+}
+
+impl VerificationReport {
+    /// True if no files are missing, and the `_last_checkpoint` file and the checkpoint (if
+    /// verified) are valid.
+    pub fn is_consistent(&self) -> bool {
+        let checkpoint_consistent = match &self.checkpoint {
+            CheckpointVerification::Verified { mismatches, .. } => mismatches.is_empty(),
+            CheckpointVerification::Unreadable { .. } => false,
+            CheckpointVerification::NoCheckpoint | CheckpointVerification::Unverifiable { .. } => {
+                true
+            }
+        };
+        self.missing_files.is_empty()
+            && self.missing_deletion_vectors.is_empty()
+            && !self.invalid_last_checkpoint
+            && checkpoint_consistent
+    }
+}
+
+/// The result of validating the checkpoint of a version against a replay of its commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointVerification {
+    /// The version was read from commits only.
+    NoCheckpoint,
+    /// The commits before the checkpoint at `version` were cleaned up, so it can't be validated.
+    Unverifiable { version: Version },
+    /// The checkpoint at `version` can't be read (e.g. it's corrupt), with the error reading it.
+    /// The files of the version are then checked as read from its commits.
+    Unreadable { version: Version, error: String },
+    /// The checkpoint at `version` was compared with a replay of all commits up to the checked
+    /// version, and differs by `mismatches` (which are empty if the checkpoint is consistent).
+    Verified {
+        version: Version,
+        mismatches: Vec<CheckpointMismatch>,
+    },
+}
+
+/// A difference between the state of a table read with its checkpoint, and read from its commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointMismatch {
+    /// A data file (by its path in the log) which is live with the checkpoint, but not with the
+    /// commits. Files whose deletion vectors differ are both extra and missing.
+    ExtraFile(String),
+    /// A data file (by its path in the log) which is live with the commits, but not with the
+    /// checkpoint.
+    MissingFile(String),
+    /// The protocol of the table differs.
+    Protocol,
+    /// The metadata of the table differs.
+    Metadata,
+}
+
+/// Check the files and the checkpoint of the latest version of the table at `table_root`. See
+/// [`Table::verify`].
+///
+/// [`Table::verify`]: crate::Table::verify
+pub(crate) fn verify_table(
+    table_root: &Url,
+    engine: &dyn Engine,
+) -> DeltaResult<VerificationReport> {
+    let fs_client = engine.get_file_system_client();
+    let log_root = table_root.join("_delta_log/")?;
+    let invalid_last_checkpoint = read_last_checkpoint_file(fs_client.as_ref(), &log_root)?
+        .is_some_and(|data| parse_last_checkpoint(&data).is_none());
+
+    // Snapshots fall back to an earlier checkpoint (or to the commits) if their checkpoint can't
+    // be read, but here an unreadable checkpoint is reported instead
+    let log_segment = Snapshot::log_segment_for_version(table_root, engine, None)?;
+    let checkpoint_version = log_segment
+        .checkpoint_parts
+        .first()
+        .map(|part| part.version);
+    let commits = match checkpoint_version {
+        Some(_) => log_segment.commits_only(fs_client.as_ref())?,
+        None => None,
+    };
+    let (snapshot, actions, checkpoint) = match read_file_actions(table_root, log_segment, engine) {
+        Ok((snapshot, actions)) => {
+            let live_files = live_file_ids(&actions.live_files);
+            let checkpoint = verify_checkpoint(&snapshot, commits, engine, &live_files)?;
+            (snapshot, actions, checkpoint)
+        }
+        Err(err) => {
+            let Some(version) = checkpoint_version.filter(|_| is_checkpoint_error(&err)) else {
+                return Err(err);
+            };
+            // check the files as read from the commits, unless they were cleaned up
+            let Some(commits) = commits else {
+                return Err(err);
+            };
+            let (snapshot, actions) = read_file_actions(table_root, commits, engine)?;
+            let error = err.to_string();
+            (
+                snapshot,
+                actions,
+                CheckpointVerification::Unreadable { version, error },
+            )
+        }
+    };
+
+    let mut missing_files = vec![];
+    let mut missing_deletion_vectors = vec![];
+    let mut checked_dvs = HashSet::new();
+    for (path, deletion_vector) in &actions.live_files {
+        let location = resolve_file_path(table_root, path)?;
+        if !exists(fs_client.as_ref(), &location)? {
+            missing_files.push(location);
+        }
+        let Some(deletion_vector) = deletion_vector else {
+            continue;
+        };
+        // several files may share a deletion vector file
+        if let Some(dv_location) = deletion_vector.absolute_path(table_root)? {
+            if checked_dvs.insert(dv_location.clone()) && !exists(fs_client.as_ref(), &dv_location)?
+            {
+                missing_deletion_vectors.push(dv_location);
+            }
+        }
+    }
+    missing_files.sort_unstable();
+    missing_deletion_vectors.sort_unstable();
+
+    debug!(
+        "Verified version {} of {table_root}: {} missing files, {} missing deletion vectors, {checkpoint:?}",
+        snapshot.version(),
+        missing_files.len(),
+        missing_deletion_vectors.len(),
+    );
+    Ok(VerificationReport {
+        version: snapshot.version(),
+        files_checked: actions.live_files.len(),
+        missing_files,
+        missing_deletion_vectors,
+        invalid_last_checkpoint,
+        checkpoint,
+    })
+}
+
+// Read the snapshot of a log segment (without falling back to an earlier checkpoint), and its
+// file actions
+fn read_file_actions(
+    table_root: &Url,
+    log_segment: LogSegment,
+    engine: &dyn Engine,
+) -> DeltaResult<(Snapshot, TombstoneVisitor)> {
+    let snapshot = Snapshot::try_new_from_log_segment_without_fallback(
+        table_root.clone(),
+        log_segment,
+        engine,
+    )?;
+    let actions = visit_file_actions(&snapshot, engine)?;
+    Ok((snapshot, actions))
+}
+
+// Replay the commits from commit 0 (if they weren't cleaned up), and compare the state with the one
+// read with the checkpoint
+fn verify_checkpoint(
+    snapshot: &Snapshot,
+    commits: Option<LogSegment>,
+    engine: &dyn Engine,
+    live_files: &HashSet<(String, Option<String>)>,
+) -> DeltaResult<CheckpointVerification> {
+    let Some(checkpoint) = snapshot.log_segment.checkpoint_parts.first() else {
+        return Ok(CheckpointVerification::NoCheckpoint);
+    };
+    let version = checkpoint.version;
+    let Some(commits) = commits else {
+        return Ok(CheckpointVerification::Unverifiable { version });
+    };
+
+    let mut mismatches = vec![];
+    let (metadata, protocol) = commits.read_metadata(engine)?;
+    if &protocol != snapshot.protocol() {
+        mismatches.push(CheckpointMismatch::Protocol);
+    }
+    if &metadata != snapshot.metadata() {
+        mismatches.push(CheckpointMismatch::Metadata);
+    }
+    let replayed = visit_log_segment_file_actions(&commits, engine)?;
+    let replayed_files = live_file_ids(&replayed.live_files);
+    let mut extra: Vec<_> = live_files.difference(&replayed_files).collect();
+    let mut missing: Vec<_> = replayed_files.difference(live_files).collect();
+    extra.sort_unstable();
+    missing.sort_unstable();
+    mismatches.extend(
+        extra
+            .into_iter()
+            .map(|(path, _)| CheckpointMismatch::ExtraFile(path.clone())),
+    );
+    mismatches.extend(
+        missing
+            .into_iter()
+            .map(|(path, _)| CheckpointMismatch::MissingFile(path.clone())),
+    );
+    Ok(CheckpointVerification::Verified {
+        version,
+        mismatches,
+    })
+}
+
+// Live files are identified by their path and the unique id of their deletion vector
+fn live_file_ids(
+    live_files: &[(String, Option<DeletionVectorDescriptor>)],
+) -> HashSet<(String, Option<String>)> {
+    live_files
+        .iter()
+        .map(|(path, dv)| (path.clone(), dv.as_ref().map(|dv| dv.unique_id())))
+        .collect()
+}
+
+fn exists(fs_client: &dyn FileSystemClient, location: &Url) -> DeltaResult<bool> {
+    match fs_client.head(location) {
+        Ok(_) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::FileNotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(all(test, feature = "sync-engine"))]
+mod tests {
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::utils::test_utils::copy_table;
+    use crate::Table;
+
+    #[test]
+    fn test_verify_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = copy_table("table-with-dv-small", dir.path());
+        let engine = SyncEngine::new();
+        let table = Table::new(table_root.clone());
+
+        let report = table.verify(&engine).unwrap();
+        assert_eq!(report.version, 1);
+        assert_eq!(report.files_checked, 1);
+        assert_eq!(report.checkpoint, CheckpointVerification::NoCheckpoint);
+        assert!(report.is_consistent());
+
+        let data_file = "part-00000-fae5310a-a37d-4e51-827b-c3d5516560ca-c000.snappy.parquet";
+        let dv_file = "deletion_vector_61d16c75-6994-46b7-a15b-8b538852e50e.bin";
+        std::fs::remove_file(dir.path().join(data_file)).unwrap();
+        std::fs::remove_file(dir.path().join(dv_file)).unwrap();
+        let report = table.verify(&engine).unwrap();
+        assert_eq!(report.missing_files, [table_root.join(data_file).unwrap()]);
+        assert_eq!(
+            report.missing_deletion_vectors,
+            [table_root.join(dv_file).unwrap()]
+        );
+        assert!(!report.is_consistent());
+    }
+
+    #[test]
+    fn test_verify_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = copy_table("with_checkpoint_no_last_checkpoint", dir.path());
+        let engine = SyncEngine::new();
+        let table = Table::new(table_root);
+
+        // The table has no data files, but its checkpoint is consistent with its commits
+        let report = table.verify(&engine).unwrap();
+        assert_eq!(report.version, 3);
+        assert_eq!(report.missing_files.len(), 1);
+        assert_eq!(
+            report.checkpoint,
+            CheckpointVerification::Verified {
+                version: 2,
+                mismatches: vec![],
+            }
+        );
+
+        // A file added by a commit before the checkpoint, which the checkpoint lacks
+        let commit = dir.path().join("_delta_log/00000000000000000001.json");
+        let mut contents = std::fs::read_to_string(&commit).unwrap();
+        let add = serde_json::json!({
+            "add": {
+                "path": "lost.parquet",
+                "partitionValues": {},
+                "size": 1,
+                "modificationTime": 0,
+                "dataChange": true,
+            }
+        });
+        contents.push_str(&format!("{add}\n"));
+        std::fs::write(&commit, contents).unwrap();
+        let report = table.verify(&engine).unwrap();
+        assert_eq!(
+            report.checkpoint,
+            CheckpointVerification::Verified {
+                version: 2,
+                mismatches: vec![CheckpointMismatch::MissingFile("lost.parquet".to_string())],
+            }
+        );
+
+        // Without the commits before the checkpoint, it can't be verified
+        std::fs::remove_file(dir.path().join("_delta_log/00000000000000000000.json")).unwrap();
+        let report = table.verify(&engine).unwrap();
+        assert_eq!(
+            report.checkpoint,
+            CheckpointVerification::Unverifiable { version: 2 }
+        );
+    }
+
+    #[test]
+    fn test_verify_corrupt_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let table_root = copy_table("with_checkpoint_no_last_checkpoint", dir.path());
+        let engine = SyncEngine::new();
+        let table = Table::new(table_root);
+        let log_dir = dir.path().join("_delta_log");
+
+        // An invalid _last_checkpoint file is ignored by readers, but is an inconsistency
+        std::fs::write(log_dir.join("_last_checkpoint"), "{").unwrap();
+        let report = table.verify(&engine).unwrap();
+        assert!(report.invalid_last_checkpoint);
+        assert!(matches!(
+            report.checkpoint,
+            CheckpointVerification::Verified { .. }
+        ));
+        std::fs::remove_file(log_dir.join("_last_checkpoint")).unwrap();
+
+        // The snapshot falls back to the commits, but the corrupt checkpoint is reported
+        let checkpoint = log_dir.join("00000000000000000002.checkpoint.parquet");
+        std::fs::write(checkpoint, "not a parquet file").unwrap();
+        assert_eq!(table.snapshot(&engine, None).unwrap().version(), 3);
+        let report = table.verify(&engine).unwrap();
+        assert_eq!(report.version, 3);
+        assert!(!report.invalid_last_checkpoint);
+        assert!(matches!(
+            report.checkpoint,
+            CheckpointVerification::Unreadable { version: 2, .. }
+        ));
+        assert!(!report.is_consistent());
+        assert_eq!(report.missing_files.len(), 1);
+
+        // ... and is an error once the commits before it are gone
+        std::fs::remove_file(log_dir.join("00000000000000000000.json")).unwrap();
+        assert!(table.verify(&engine).is_err());
+    }
+}
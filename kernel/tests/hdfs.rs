@@ -4,16 +4,16 @@
 // tools available and on your path. Any Java version between 8 and 17 should work.
 //
 // Run these integration tests with:
-//   cargo test --features integration-test,cloud --test hdfs
+//   cargo test --features integration-test,hdfs --test hdfs
 #![cfg(all(
     feature = "integration-test",
-    feature = "cloud",
+    feature = "hdfs",
     not(target_os = "windows")
 ))]
 
 use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
 use delta_kernel::engine::default::DefaultEngine;
-use delta_kernel::Table;
+use delta_kernel::{Engine, Error, Table};
 use hdfs_native::{Client, WriteOptions};
 use hdfs_native_object_store::minidfs::MiniDfs;
 use std::collections::HashSet;
@@ -83,3 +83,25 @@ async fn read_table_version_hdfs() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn write_file_hdfs() -> Result<(), Box<dyn std::error::Error>> {
+    let minidfs = MiniDfs::with_features(&HashSet::new());
+    let url = url::Url::parse(&format!("{}/my-delta-table/", minidfs.url))?;
+    let engine = DefaultEngine::try_new(
+        &url,
+        std::iter::empty::<(&str, &str)>(),
+        Arc::new(TokioBackgroundExecutor::new()),
+    )?;
+    let client = engine.get_file_system_client();
+
+    // commits are written atomically, and never overwrite each other
+    let commit = url.join("_delta_log/00000000000000000000.json")?;
+    client.write_file(&commit, "{}".into(), false)?;
+    let result = client.write_file(&commit, "{}".into(), false);
+    assert!(matches!(result, Err(Error::FileAlreadyExists(_))));
+    client.write_file(&commit, "{}".into(), true)?;
+    assert_eq!(client.head(&commit)?.size, 2);
+
+    Ok(())
+}
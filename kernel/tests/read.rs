@@ -3,19 +3,24 @@ use std::ops::Not;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use arrow::array::{AsArray, BooleanArray};
 use arrow::compute::filter_record_batch;
-use arrow_schema::SchemaRef as ArrowSchemaRef;
+use arrow::datatypes::Int32Type;
+use arrow::record_batch::RecordBatch;
+use arrow_schema::{Schema as ArrowSchema, SchemaRef as ArrowSchemaRef};
 use arrow_select::concat::concat_batches;
 use delta_kernel::actions::deletion_vector::split_vector;
 use delta_kernel::engine::arrow_data::ArrowEngineData;
 use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
-use delta_kernel::engine::default::DefaultEngine;
+use delta_kernel::engine::default::{DefaultEngine, DefaultEngineOptions};
+use delta_kernel::engine::memory::MemoryPool;
 use delta_kernel::expressions::{column_expr, BinaryOperator, Expression};
 use delta_kernel::scan::state::{visit_scan_files, DvInfo, Stats};
-use delta_kernel::scan::{transform_to_logical, Scan};
+use delta_kernel::scan::{transform_to_logical, FileUrlResolver, Scan};
 use delta_kernel::schema::{DataType, Schema};
-use delta_kernel::{Engine, FileMeta, Table};
-use object_store::{memory::InMemory, path::Path, ObjectStore};
+use delta_kernel::{DeltaResult, Engine, ErrorKind, FileMeta, Table};
+use itertools::Itertools;
+use object_store::{local::LocalFileSystem, memory::InMemory, path::Path, ObjectStore, PutPayload};
 use test_utils::{
     actions_to_string, add_commit, generate_batch, generate_simple_batch, into_record_batch,
     record_batch_to_bytes, IntoArray, TestAction, METADATA,
@@ -195,6 +200,139 @@ async fn remove_action() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn percent_encoded_and_absolute_paths() -> Result<(), Box<dyn std::error::Error>> {
+    // (the path of the add action, the object path of the data file)
+    let files = [
+        // a space in a partition directory, percent-encoded
+        ("a=x%20y/part 1.parquet", "a=x y/part 1.parquet"),
+        // a Hive-escaped `:` in a partition directory, whose `%` is percent-encoded
+        ("a=x%253Ay/part.parquet", "a=x%3Ay/part.parquet"),
+        // non-ASCII characters, and a `#` which would otherwise start the fragment of the URL
+        ("a=%C3%BC/part#1.parquet", "a=ü/part#1.parquet"),
+        // an absolute path, e.g. of a shallow clone referencing the files of its source table
+        ("memory:///source/part.parquet", "source/part.parquet"),
+    ];
+    let batch = generate_simple_batch()?;
+    let storage = Arc::new(InMemory::new());
+    let adds = files
+        .iter()
+        .map(|(path, _)| TestAction::Add(path.to_string()));
+    add_commit(
+        storage.as_ref(),
+        0,
+        actions_to_string([TestAction::Metadata].into_iter().chain(adds).collect()),
+    )
+    .await?;
+    for (_, object_path) in files {
+        storage
+            .put(
+                &Path::parse(object_path)?,
+                record_batch_to_bytes(&batch).into(),
+            )
+            .await?;
+    }
+
+    let engine = Arc::new(DefaultEngine::new(
+        storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+    ));
+    let table = Table::new(Url::parse("memory:///")?);
+    let snapshot = table.snapshot(engine.as_ref(), None)?;
+    let scan = snapshot.into_scan_builder().build()?;
+    let mut batches = 0;
+    for data in scan.execute(engine)? {
+        assert_eq!(into_record_batch(data?.raw_data?), batch);
+        batches += 1;
+    }
+    assert_eq!(batches, files.len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn external_files_of_shallow_clone() -> Result<(), Box<dyn std::error::Error>> {
+    // a shallow clone in one store, referencing a file of its source table in another bucket
+    let batch = generate_simple_batch()?;
+    let clone_storage = Arc::new(InMemory::new());
+    let source_storage = Arc::new(InMemory::new());
+    let external_path = "s3://source-bucket/table/part.parquet";
+    add_commit(
+        clone_storage.as_ref(),
+        0,
+        actions_to_string(vec![
+            TestAction::Metadata,
+            TestAction::Add(external_path.to_string()),
+            TestAction::Add(PARQUET_FILE1.to_string()),
+        ]),
+    )
+    .await?;
+    let data = record_batch_to_bytes(&batch);
+    source_storage
+        .put(&Path::from("table/part.parquet"), data.clone().into())
+        .await?;
+    clone_storage
+        .put(&Path::from(PARQUET_FILE1), data.into())
+        .await?;
+
+    let table_root = Url::parse("memory:///")?;
+    let source_root = Url::parse("s3://source-bucket/")?;
+    let options = DefaultEngineOptions::default()
+        .with_table_root(&table_root)
+        .with_external_store(&source_root, source_storage.clone());
+    let engine = Arc::new(DefaultEngine::new_with_options(
+        clone_storage.clone(),
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+        options,
+    ));
+    let table = Table::new(table_root.clone());
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    assert_eq!(scan.external_storage(engine.as_ref())?, [source_root]);
+    let external: Vec<_> = scan
+        .scan_files(engine.as_ref())?
+        .map_ok(|file| (file.is_external(&table_root).unwrap(), file.path))
+        .try_collect()?;
+    assert_eq!(
+        external.into_iter().sorted().collect_vec(),
+        [
+            (false, PARQUET_FILE1.to_string()),
+            (true, external_path.to_string())
+        ]
+    );
+    let batches: Vec<_> = scan
+        .execute(engine)?
+        .map_ok(|data| into_record_batch(data.raw_data.unwrap()))
+        .try_collect()?;
+    assert_eq!(batches, [batch.clone(), batch]);
+
+    // without the store of the source table, reading its file fails rather than reading the file
+    // at the same path in the store of the clone
+    clone_storage
+        .put(&Path::from("table/part.parquet"), PutPayload::new())
+        .await?;
+    let engine = Arc::new(DefaultEngine::new_with_options(
+        clone_storage,
+        Path::from("/"),
+        Arc::new(TokioBackgroundExecutor::new()),
+        DefaultEngineOptions::default().with_table_root(&table_root),
+    ));
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    let result: DeltaResult<Vec<_>> = scan.execute(engine)?.try_collect();
+    let err = result.err().unwrap().to_string();
+    assert!(
+        err.contains("No store is configured for the storage s3://source-bucket/"),
+        "{err}"
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn stats() -> Result<(), Box<dyn std::error::Error>> {
     fn generate_commit2(actions: Vec<TestAction>) -> String {
@@ -296,7 +434,32 @@ async fn stats() -> Result<(), Box<dyn std::error::Error>> {
         (NotEqual, 7, vec![&batch2, &batch1]),
         (NotEqual, 8, vec![&batch2, &batch1]),
     ];
+    // The rows of the scanned files which don't match the predicate are skipped as well
+    let filter_rows = |batch: &RecordBatch, op, value| {
+        let ids = batch
+            .column_by_name("id")
+            .unwrap()
+            .as_primitive::<Int32Type>();
+        let matches: BooleanArray = ids
+            .values()
+            .iter()
+            .map(|id| match op {
+                Equal => Some(*id == value),
+                NotEqual => Some(*id != value),
+                LessThan => Some(*id < value),
+                LessThanOrEqual => Some(*id <= value),
+                GreaterThan => Some(*id > value),
+                GreaterThanOrEqual => Some(*id >= value),
+                _ => unreachable!(),
+            })
+            .collect();
+        filter_record_batch(batch, &matches).unwrap()
+    };
     for (op, value, expected_batches) in test_cases {
+        let expected_batches: Vec<_> = expected_batches
+            .into_iter()
+            .map(|batch| filter_rows(batch, op, value))
+            .collect();
         let predicate = Expression::binary(op, column_expr!("id"), value);
         let scan = snapshot
             .clone()
@@ -311,7 +474,7 @@ async fn stats() -> Result<(), Box<dyn std::error::Error>> {
         for (batch, expected) in stream {
             let raw_data = batch?.raw_data?;
             files_scanned += 1;
-            assert_eq!(into_record_batch(raw_data), expected.clone());
+            assert_eq!(into_record_batch(raw_data), expected);
         }
         assert_eq!(expected_files, files_scanned, "{predicate:?}");
     }
@@ -412,13 +575,14 @@ fn read_with_scan_data(
             size: scan_file.size as usize,
             location: file_path,
         };
+        // the rows of files with a deletion vector must not be skipped
+        let predicate = match scan_file.dv_info.has_vector() {
+            true => None,
+            false => scan.parquet_predicate(),
+        };
         let read_results = engine
             .get_parquet_handler()
-            .read_parquet_files(
-                &[meta],
-                global_state.read_schema.clone(),
-                scan.predicate().clone(),
-            )
+            .read_parquet_files(&[meta], global_state.read_schema.clone(), predicate)
             .unwrap();
 
         for read_result in read_results {
@@ -709,20 +873,18 @@ fn predicate_null() -> Result<(), Box<dyn std::error::Error>> {
 #[test]
 fn mixed_null() -> Result<(), Box<dyn std::error::Error>> {
     let expected = vec![
-        "+------+--------------+",
-        "| part | n            |",
-        "+------+--------------+",
-        "| 0    |              |",
-        "| 0    |              |",
-        "| 0    |              |",
-        "| 0    |              |",
-        "| 0    |              |",
-        "| 2    |              |",
-        "| 2    | non-null-mix |",
-        "| 2    |              |",
-        "| 2    | non-null-mix |",
-        "| 2    |              |",
-        "+------+--------------+",
+        "+------+---+",
+        "| part | n |",
+        "+------+---+",
+        "| 0    |   |",
+        "| 0    |   |",
+        "| 0    |   |",
+        "| 0    |   |",
+        "| 0    |   |",
+        "| 2    |   |",
+        "| 2    |   |",
+        "| 2    |   |",
+        "+------+---+",
     ];
     read_table_data_str(
         "./tests/data/mixed-nulls",
@@ -744,9 +906,6 @@ fn mixed_not_null() -> Result<(), Box<dyn std::error::Error>> {
         "| 1    | non-null     |",
         "| 1    | non-null     |",
         "| 1    | non-null     |",
-        "| 2    |              |",
-        "| 2    |              |",
-        "| 2    |              |",
         "| 2    | non-null-mix |",
         "| 2    | non-null-mix |",
         "+------+--------------+",
@@ -983,6 +1142,138 @@ fn timestamp_ntz() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn timestamp_ntz_requires_feature() -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = |protocol: &str| {
+        format!(
+            r#"{{"protocol":{protocol}}}
+{{"metaData":{{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{{"provider":"parquet","options":{{}}}},"schemaString":"{{\"type\":\"struct\",\"fields\":[{{\"name\":\"ts\",\"type\":\"timestamp_ntz\",\"nullable\":true,\"metadata\":{{}}}}]}}","partitionColumns":[],"configuration":{{}},"createdTime":1587968585495}}}}"#
+        )
+    };
+    let engine = |storage: Arc<InMemory>| {
+        DefaultEngine::new(
+            storage,
+            Path::from(""),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+    };
+    let location = Url::parse("memory:///")?;
+
+    // the table has a timestamp_ntz column, but doesn't enable the timestampNtz feature
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        metadata(r#"{"minReaderVersion":1,"minWriterVersion":2}"#),
+    )
+    .await?;
+    let err = Table::new(location.clone())
+        .snapshot(&engine(storage), None)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert_eq!(err.context().unwrap().table_root(), Some(&location));
+
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        metadata(
+            r#"{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":["timestampNtz"],"writerFeatures":["timestampNtz"]}"#,
+        ),
+    )
+    .await?;
+    let snapshot = Table::new(location).snapshot(&engine(storage), None)?;
+    assert_eq!(
+        snapshot.schema().field("ts").unwrap().data_type(),
+        &DataType::TIMESTAMP_NTZ
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn variant() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow::array::{ArrayRef, BinaryArray, Int8Array, StructArray};
+    use arrow_schema::{DataType as ArrowDataType, Field};
+
+    let metadata = r#"{"protocol":{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":["variantType"],"writerFeatures":["variantType"]}}
+{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{}},{\"name\":\"v\",\"type\":\"variant\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{},"createdTime":1587968585495}}"#;
+    let add = format!(
+        r#"{{"add":{{"path":"{PARQUET_FILE1}","partitionValues":{{}},"size":262,"modificationTime":1587968586000,"dataChange":true}}}}"#
+    );
+    // A variant with an empty metadata dictionary, holding the int8 value 42 and null
+    let binary = |values: [&[u8]; 2]| Arc::new(BinaryArray::from_vec(values.to_vec())) as ArrayRef;
+    let variant_fields = vec![
+        (
+            Arc::new(Field::new("metadata", ArrowDataType::Binary, false)),
+            binary([&[0x01, 0x00, 0x00], &[0x01, 0x00, 0x00]]),
+        ),
+        (
+            Arc::new(Field::new("value", ArrowDataType::Binary, false)),
+            binary([&[0x0c, 0x2a], &[0x00]]),
+        ),
+    ];
+    let commit = &[metadata, &add].join("\n");
+    let make_storage = |variant: StructArray| async move {
+        let batch = generate_batch(vec![
+            ("id", vec![1, 2].into_array()),
+            ("v", Arc::new(variant) as ArrayRef),
+        ])?;
+        let storage = Arc::new(InMemory::new());
+        add_commit(storage.as_ref(), 0, commit.clone()).await?;
+        storage
+            .put(
+                &Path::from(PARQUET_FILE1),
+                record_batch_to_bytes(&batch).into(),
+            )
+            .await?;
+        Ok::<_, Box<dyn std::error::Error>>(storage)
+    };
+    let location = Url::parse("memory:///")?;
+    let make_engine = |storage| -> Arc<dyn Engine> {
+        Arc::new(DefaultEngine::new(
+            storage,
+            Path::from(""),
+            Arc::new(TokioBackgroundExecutor::new()),
+        ))
+    };
+
+    let storage = make_storage(StructArray::from(variant_fields.clone())).await?;
+    let engine = make_engine(storage);
+    let snapshot = Arc::new(Table::new(location.clone()).snapshot(engine.as_ref(), None)?);
+    assert_eq!(
+        snapshot.schema().field("v").unwrap().data_type(),
+        &DataType::unshredded_variant()
+    );
+    let scan = snapshot.scan_builder().build()?;
+    let expected = vec![
+        "+----+---------------------------------+",
+        "| id | v                               |",
+        "+----+---------------------------------+",
+        "| 1  | {metadata: 010000, value: 0c2a} |",
+        "| 2  | {metadata: 010000, value: 00}   |",
+        "+----+---------------------------------+",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+    read_with_execute(engine.clone(), &scan, &expected)?;
+    read_with_scan_data(&location, engine.as_ref(), &scan, &expected)?;
+
+    // shredded variants can't be read yet
+    let mut shredded_fields = variant_fields;
+    shredded_fields.push((
+        Arc::new(Field::new("typed_value", ArrowDataType::Int8, true)),
+        Arc::new(Int8Array::from(vec![Some(42), None])),
+    ));
+    let storage = make_storage(StructArray::from(shredded_fields)).await?;
+    let engine = make_engine(storage);
+    let snapshot = Arc::new(Table::new(location).snapshot(engine.as_ref(), None)?);
+    let scan = snapshot.scan_builder().build()?;
+    let err = read_scan(&scan, engine).unwrap_err();
+    assert!(err.to_string().contains("shredded variant"), "{err}");
+    Ok(())
+}
+
 #[test]
 fn type_widening_basic() -> Result<(), Box<dyn std::error::Error>> {
     let expected = vec![
@@ -1083,3 +1374,457 @@ fn predicate_references_invalid_missing_column() -> Result<(), Box<dyn std::erro
     )?;
     Ok(())
 }
+
+#[tokio::test]
+async fn column_mapping_name_mode_predicate() -> Result<(), Box<dyn std::error::Error>> {
+    // Stats and parquet files of a name-mapped table use the physical column names
+    fn add(path: &str, min: i32, max: i32) -> String {
+        format!(
+            r#"{{"add":{{"path":"{path}","partitionValues":{{}},"size":262,"modificationTime":1587968586000,"dataChange":true,"stats":"{{\"numRecords\":2,\"nullCount\":{{\"col-id\":0}},\"minValues\":{{\"col-id\":{min}}},\"maxValues\":{{\"col-id\":{max}}}}}"}}}}"#
+        )
+    }
+    let metadata = r#"{"protocol":{"minReaderVersion":2,"minWriterVersion":5}}
+{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":1,\"delta.columnMapping.physicalName\":\"col-id\"}},{\"name\":\"val\",\"type\":\"string\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":2,\"delta.columnMapping.physicalName\":\"col-val\"}}]}","partitionColumns":[],"configuration":{"delta.columnMapping.mode":"name","delta.columnMapping.maxColumnId":"2"},"createdTime":1587968585495}}"#;
+
+    let batch1 = generate_batch(vec![
+        ("col-id", vec![1, 3].into_array()),
+        ("col-val", vec!["a", "c"].into_array()),
+    ])?;
+    let batch2 = generate_batch(vec![
+        ("col-id", vec![5, 7].into_array()),
+        ("col-val", vec!["e", "g"].into_array()),
+    ])?;
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [
+            metadata.to_string(),
+            add(PARQUET_FILE1, 1, 3),
+            add(PARQUET_FILE2, 5, 7),
+        ]
+        .join("\n"),
+    )
+    .await?;
+    for (path, batch) in [(PARQUET_FILE1, &batch1), (PARQUET_FILE2, &batch2)] {
+        storage
+            .put(&Path::from(path), record_batch_to_bytes(batch).into())
+            .await?;
+    }
+
+    let location = Url::parse("memory:///")?;
+    let engine = Arc::new(DefaultEngine::new(
+        storage.clone(),
+        Path::from(""),
+        Arc::new(TokioBackgroundExecutor::new()),
+    ));
+    let snapshot = Arc::new(Table::new(location.clone()).snapshot(engine.as_ref(), None)?);
+
+    // only the second file can match, and the data is returned with logical column names
+    let predicate = column_expr!("id").gt(4);
+    let scan = snapshot
+        .scan_builder()
+        .with_predicate(Arc::new(predicate))
+        .build()?;
+    let expected = vec![
+        "+----+-----+",
+        "| id | val |",
+        "+----+-----+",
+        "| 5  | e   |",
+        "| 7  | g   |",
+        "+----+-----+",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+    read_with_execute(engine.clone(), &scan, &expected)?;
+    read_with_scan_data(&location, engine.as_ref(), &scan, &expected)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn column_mapping_id_mode() -> Result<(), Box<dyn std::error::Error>> {
+    // Parquet files of id-mapped tables (e.g. converted from Iceberg) need not use the physical
+    // column names, so columns must be resolved by their parquet field ids
+    fn add(path: &str, min: i32, max: i32) -> String {
+        format!(
+            r#"{{"add":{{"path":"{path}","partitionValues":{{}},"size":262,"modificationTime":1587968586000,"dataChange":true,"stats":"{{\"numRecords\":2,\"nullCount\":{{\"col-id\":0}},\"minValues\":{{\"col-id\":{min}}},\"maxValues\":{{\"col-id\":{max}}}}}"}}}}"#
+        )
+    }
+    fn with_field_ids(batch: RecordBatch) -> RecordBatch {
+        let fields: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let field_id =
+                    HashMap::from([("PARQUET:field_id".to_string(), (i + 1).to_string())]);
+                field.as_ref().clone().with_metadata(field_id)
+            })
+            .collect();
+        RecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), batch.columns().to_vec()).unwrap()
+    }
+    let metadata = r#"{"protocol":{"minReaderVersion":2,"minWriterVersion":5}}
+{"metaData":{"id":"5fba94ed-9794-4965-ba6e-6ee3c0d22af9","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"integer\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":1,\"delta.columnMapping.physicalName\":\"col-id\"}},{\"name\":\"val\",\"type\":\"string\",\"nullable\":true,\"metadata\":{\"delta.columnMapping.id\":2,\"delta.columnMapping.physicalName\":\"col-val\"}}]}","partitionColumns":[],"configuration":{"delta.columnMapping.mode":"id","delta.columnMapping.maxColumnId":"2"},"createdTime":1587968585495}}"#;
+
+    // the second file stores its columns in a different order, under yet other names
+    let batch1 = with_field_ids(generate_batch(vec![
+        ("id", vec![1, 3].into_array()),
+        ("val", vec!["a", "c"].into_array()),
+    ])?);
+    let batch2 = generate_batch(vec![
+        ("renamed_val", vec!["e", "g"].into_array()),
+        ("renamed_id", vec![5, 7].into_array()),
+    ])?;
+    let batch2 = with_field_ids(RecordBatch::try_new(
+        Arc::new(batch2.schema().project(&[1, 0])?),
+        vec![batch2.column(1).clone(), batch2.column(0).clone()],
+    )?);
+    let storage = Arc::new(InMemory::new());
+    add_commit(
+        storage.as_ref(),
+        0,
+        [
+            metadata.to_string(),
+            add(PARQUET_FILE1, 1, 3),
+            add(PARQUET_FILE2, 5, 7),
+        ]
+        .join("\n"),
+    )
+    .await?;
+    for (path, batch) in [(PARQUET_FILE1, &batch1), (PARQUET_FILE2, &batch2)] {
+        storage
+            .put(&Path::from(path), record_batch_to_bytes(batch).into())
+            .await?;
+    }
+
+    let location = Url::parse("memory:///")?;
+    let engine = Arc::new(DefaultEngine::new(
+        storage.clone(),
+        Path::from(""),
+        Arc::new(TokioBackgroundExecutor::new()),
+    ));
+    let snapshot = Arc::new(Table::new(location.clone()).snapshot(engine.as_ref(), None)?);
+
+    let scan = snapshot.clone().scan_builder().build()?;
+    let expected = vec![
+        "+----+-----+",
+        "| id | val |",
+        "+----+-----+",
+        "| 1  | a   |",
+        "| 3  | c   |",
+        "| 5  | e   |",
+        "| 7  | g   |",
+        "+----+-----+",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+    read_with_execute(engine.clone(), &scan, &expected)?;
+    read_with_scan_data(&location, engine.as_ref(), &scan, &expected)?;
+
+    let scan = snapshot
+        .scan_builder()
+        .with_predicate(Arc::new(column_expr!("id").gt(4)))
+        .build()?;
+    let expected = vec![
+        "+----+-----+",
+        "| id | val |",
+        "+----+-----+",
+        "| 5  | e   |",
+        "| 7  | g   |",
+        "+----+-----+",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+    read_with_execute(engine.clone(), &scan, &expected)?;
+    read_with_scan_data(&location, engine.as_ref(), &scan, &expected)?;
+    Ok(())
+}
+
+#[test]
+fn memory_limit() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::fs::canonicalize(PathBuf::from("./tests/data/table-without-dv-small/"))?;
+    let url = Url::from_directory_path(path).unwrap();
+    let table_root = Path::from_url_path(url.path())?;
+    let table = Table::new(url);
+    let store = Arc::new(LocalFileSystem::new());
+    let engine = |memory_pool: &MemoryPool| {
+        let options = DefaultEngineOptions::default().with_memory_pool(memory_pool.clone());
+        Arc::new(DefaultEngine::new_with_options(
+            store.clone(),
+            table_root.clone(),
+            Arc::new(TokioBackgroundExecutor::new()),
+            options,
+        ))
+    };
+
+    // the log of the table doesn't fit into 100 bytes
+    let memory_pool = MemoryPool::new(100);
+    let result = table.snapshot(engine(&memory_pool).as_ref(), None);
+    assert!(matches!(result, Err(err) if err.kind() == ErrorKind::MemoryLimitExceeded));
+    assert_eq!(memory_pool.used(), 0);
+
+    let memory_pool = MemoryPool::new(100 * 1024 * 1024);
+    let engine = engine(&memory_pool);
+    let snapshot = table.snapshot(engine.as_ref(), None)?;
+    let scan = snapshot.into_scan_builder().build()?;
+    let results: Vec<_> = scan.execute(engine)?.try_collect()?;
+    assert!(memory_pool.used() > 0);
+    let rows: usize = results
+        .iter()
+        .map(|result| result.raw_data.as_ref().unwrap().len())
+        .sum();
+    assert_eq!(rows, 10);
+    // the memory of the data is released once it's dropped
+    drop(results);
+    assert_eq!(memory_pool.used(), 0);
+    Ok(())
+}
+
+#[test]
+fn view_types() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = |table_root: &Url, view_types: bool| -> DeltaResult<_> {
+        let options = DefaultEngineOptions::default().with_view_types(view_types);
+        Ok(Arc::new(DefaultEngine::new_with_options(
+            Arc::new(LocalFileSystem::new()),
+            Path::from_url_path(table_root.path())?,
+            Arc::new(TokioBackgroundExecutor::new()),
+            options,
+        )))
+    };
+
+    // the strings of the data and the partition values are read and computed as views
+    let path = std::fs::canonicalize(PathBuf::from("./tests/data/basic_partitioned/"))?;
+    let url = Url::from_directory_path(path).unwrap();
+    let read = |view_types: bool| -> DeltaResult<Vec<RecordBatch>> {
+        let engine = engine(&url, view_types)?;
+        let snapshot = Table::new(url.clone()).snapshot(engine.as_ref(), None)?;
+        let predicate = column_expr!("number").gt(Expression::literal(2i64));
+        let scan = snapshot
+            .into_scan_builder()
+            .with_predicate(Arc::new(predicate))
+            .build()?;
+        read_scan(&scan, engine)
+    };
+    let batches = read(true)?;
+    assert!(!batches.is_empty());
+    for batch in &batches {
+        let letter = batch.column_by_name("letter").unwrap();
+        assert_eq!(letter.data_type(), &arrow_schema::DataType::Utf8View);
+    }
+    let expected = arrow::util::pretty::pretty_format_batches(&read(false)?)?;
+    let actual = arrow::util::pretty::pretty_format_batches(&batches)?;
+    assert_eq!(actual.to_string(), expected.to_string());
+
+    // the actions of a checkpoint read as views are replayed like any others
+    let path = std::fs::canonicalize(PathBuf::from(
+        "./tests/data/with_checkpoint_no_last_checkpoint/",
+    ))?;
+    let url = Url::from_directory_path(path).unwrap();
+    let scan_files = |view_types: bool| -> DeltaResult<Vec<String>> {
+        let engine = engine(&url, view_types)?;
+        let snapshot = Table::new(url.clone()).snapshot(engine.as_ref(), None)?;
+        let scan = snapshot.into_scan_builder().build()?;
+        let mut scan_files = vec![];
+        for data in scan.scan_data(engine.as_ref())? {
+            let (data, vec) = data?;
+            scan_files = visit_scan_files(data.as_ref(), &vec, scan_files, scan_data_callback)?;
+        }
+        Ok(scan_files.into_iter().map(|file| file.path).collect())
+    };
+    let paths = scan_files(true)?;
+    assert!(!paths.is_empty());
+    assert_eq!(paths, scan_files(false)?);
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = |table_root: &Url, mmap: bool| -> DeltaResult<_> {
+        let options = DefaultEngineOptions::default().with_mmap(mmap);
+        Ok(Arc::new(DefaultEngine::new_with_options(
+            Arc::new(LocalFileSystem::new()),
+            Path::from_url_path(table_root.path())?,
+            Arc::new(TokioBackgroundExecutor::new()),
+            options,
+        )))
+    };
+
+    // the checkpoint is read by the parquet handler
+    let path = std::fs::canonicalize(PathBuf::from(
+        "./tests/data/with_checkpoint_no_last_checkpoint/",
+    ))?;
+    let url = Url::from_directory_path(path).unwrap();
+    let scan_files = |mmap: bool| -> DeltaResult<Vec<String>> {
+        let engine = engine(&url, mmap)?;
+        let snapshot = Table::new(url.clone()).snapshot(engine.as_ref(), None)?;
+        let scan = snapshot.into_scan_builder().build()?;
+        let mut scan_files = vec![];
+        for data in scan.scan_data(engine.as_ref())? {
+            let (data, vec) = data?;
+            scan_files = visit_scan_files(data.as_ref(), &vec, scan_files, scan_data_callback)?;
+        }
+        Ok(scan_files.into_iter().map(|file| file.path).collect())
+    };
+    let paths = scan_files(true)?;
+    assert!(!paths.is_empty());
+    assert_eq!(paths, scan_files(false)?);
+
+    // the data files are read by the parquet handler, and the deletion vector by the file system
+    // client
+    let path = std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/"))?;
+    let url = Url::from_directory_path(path).unwrap();
+    let read = |mmap: bool| -> DeltaResult<String> {
+        let engine = engine(&url, mmap)?;
+        let snapshot = Table::new(url.clone()).snapshot(engine.as_ref(), None)?;
+        let scan = snapshot.into_scan_builder().build()?;
+        let batches = read_scan(&scan, engine)?;
+        Ok(arrow::util::pretty::pretty_format_batches(&batches)?.to_string())
+    };
+    assert_eq!(read(true)?, read(false)?);
+    Ok(())
+}
+
+// Records the fields of the spans created while it's the default subscriber, by span name
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    spans: Arc<std::sync::Mutex<Vec<RecordedSpan>>>,
+}
+
+struct RecordedSpan {
+    id: tracing::span::Id,
+    name: &'static str,
+    fields: HashMap<String, String>,
+}
+
+impl SpanRecorder {
+    fn fields(&self, name: &str) -> Vec<HashMap<String, String>> {
+        let spans = self.spans.lock().unwrap();
+        spans
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| span.fields.clone())
+            .collect()
+    }
+}
+
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl tracing::field::Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanRecorder {
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut fields = HashMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        self.spans.lock().unwrap().push(RecordedSpan {
+            id: id.clone(),
+            name: attrs.metadata().name(),
+            fields,
+        });
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        // the ids of closed spans are reused, so the span is the latest one with the id
+        let mut spans = self.spans.lock().unwrap();
+        if let Some(span) = spans.iter_mut().rev().find(|span| span.id == *id) {
+            values.record(&mut FieldVisitor(&mut span.fields));
+        }
+    }
+}
+
+#[test]
+fn tracing_spans() -> Result<(), Box<dyn std::error::Error>> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let recorder = SpanRecorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    tracing::subscriber::with_default(subscriber, || -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/"))?;
+        let url = Url::from_directory_path(path).unwrap();
+        let engine = Arc::new(delta_kernel::engine::sync::SyncEngine::new());
+        let snapshot = Table::new(url).snapshot(engine.as_ref(), None)?;
+        let scan = snapshot.into_scan_builder().build()?;
+        read_scan(&scan, engine)?;
+        Ok(())
+    })?;
+
+    let field = |name: &str, field: &str| -> Vec<String> {
+        let fields = recorder.fields(name);
+        fields.iter().map(|fields| fields[field].clone()).collect()
+    };
+    assert_eq!(field("snapshot.list", "end_version"), ["1"]);
+    assert_eq!(field("snapshot.list", "commit_files"), ["2"]);
+    assert_eq!(field("snapshot.list", "checkpoint_parts"), ["0"]);
+    assert_eq!(field("snapshot.replay", "version"), ["1"]);
+    assert_eq!(field("scan.skipping", "has_predicate"), ["false"]);
+    assert_eq!(field("scan.skipping", "selected_files"), ["1"]);
+    assert_eq!(field("scan.read_file", "has_dv"), ["true"]);
+    assert_eq!(field("scan.read_file", "rows"), ["10"]);
+    Ok(())
+}
+
+// Resolves the data files of a table to the URLs of an HTTP server which serves them
+struct HttpUrls {
+    base_url: Url,
+    resolved: std::sync::atomic::AtomicUsize,
+}
+
+impl FileUrlResolver for HttpUrls {
+    fn resolve(&self, path: &str) -> DeltaResult<Option<Url>> {
+        self.resolved
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Ok(Some(self.base_url.join(path)?))
+    }
+}
+
+#[test]
+fn read_from_presigned_urls() -> Result<(), Box<dyn std::error::Error>> {
+    // the deletion vector of a file is read from a resolved URL too
+    for (table, resolved_files) in [("table-without-dv-small", 1), ("table-with-dv-small", 2)] {
+        let path = std::fs::canonicalize(PathBuf::from(format!("./tests/data/{table}/")))?;
+        let base_url = Url::parse(&test_utils::serve_files(path.clone()))?;
+        let url = Url::from_directory_path(path).unwrap();
+        let engine = Arc::new(DefaultEngine::try_new(
+            &url,
+            std::iter::empty::<(&str, &str)>(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )?);
+        let snapshot = Arc::new(Table::new(url).snapshot(engine.as_ref(), None)?);
+        let expected = read_scan(&snapshot.clone().scan_builder().build()?, engine.clone())?;
+
+        let resolver = Arc::new(HttpUrls {
+            base_url,
+            resolved: Default::default(),
+        });
+        let scan = snapshot
+            .scan_builder()
+            .with_file_url_resolver(resolver.clone())
+            .build()?;
+        let batches = read_scan(&scan, engine)?;
+        assert_eq!(batches, expected);
+        assert_eq!(
+            resolver.resolved.load(std::sync::atomic::Ordering::Relaxed),
+            resolved_files
+        );
+    }
+    Ok(())
+}
@@ -18,8 +18,9 @@ use delta_kernel::engine::arrow_data::ArrowEngineData;
 use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
 use delta_kernel::engine::default::DefaultEngine;
 use delta_kernel::schema::{DataType, SchemaRef, StructField, StructType};
+use delta_kernel::transaction::WriteContext;
 use delta_kernel::Error as KernelError;
-use delta_kernel::{DeltaResult, Table};
+use delta_kernel::{DeltaResult, EngineData, Table};
 
 mod common;
 use common::test_read;
@@ -58,6 +59,17 @@ async fn create_table(
     table_path: Url,
     schema: SchemaRef,
     partition_columns: &[&str],
+) -> Result<Table, Box<dyn std::error::Error>> {
+    create_table_with_writer_features(store, table_path, schema, partition_columns, &[]).await
+}
+
+// like `create_table`, but with the given writer features enabled
+async fn create_table_with_writer_features(
+    store: Arc<dyn ObjectStore>,
+    table_path: Url,
+    schema: SchemaRef,
+    partition_columns: &[&str],
+    writer_features: &[&str],
 ) -> Result<Table, Box<dyn std::error::Error>> {
     let table_id = "test_id";
     let schema = serde_json::to_string(&schema)?;
@@ -67,7 +79,7 @@ async fn create_table(
             "minReaderVersion": 3,
             "minWriterVersion": 7,
             "readerFeatures": [],
-            "writerFeatures": []
+            "writerFeatures": writer_features
         }
     });
     let metadata = json!({
@@ -139,6 +151,29 @@ fn new_commit_info() -> DeltaResult<Box<ArrowEngineData>> {
     Ok(Box::new(ArrowEngineData::new(commit_info_batch)))
 }
 
+// write the given numbers as a parquet file of a table with a single integer column, returning the
+// write metadata of the file
+async fn write_numbers(
+    engine: &DefaultEngine<TokioBackgroundExecutor>,
+    write_context: &WriteContext,
+    numbers: Vec<i32>,
+    data_change: bool,
+) -> Result<Box<dyn EngineData>, Box<dyn std::error::Error>> {
+    let data = RecordBatch::try_new(
+        Arc::new(write_context.schema().as_ref().try_into()?),
+        vec![Arc::new(Int32Array::from(numbers))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            write_context,
+            HashMap::new(),
+            data_change,
+        )
+        .await?;
+    Ok(write_metadata)
+}
+
 #[tokio::test]
 async fn test_commit_info() -> Result<(), Box<dyn std::error::Error>> {
     // setup tracing
@@ -193,6 +228,60 @@ async fn test_commit_info() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_commit_info_with_operation() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // setup in-memory object store and default engine
+    let (store, engine, table_location) = setup("test_table", true);
+
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = create_table(store.clone(), table_location, schema, &[]).await?;
+
+    // describe the operation the way Spark does for DESCRIBE HISTORY
+    let txn = table
+        .new_transaction(&engine)?
+        .with_operation("WRITE".to_string())
+        .with_operation_parameters([("mode", "Append"), ("partitionBy", "[]")])
+        .with_engine_info("test-engine/1.0")
+        .with_commit_info(new_commit_info()?);
+    txn.commit(&engine)?;
+
+    let commit1 = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let mut parsed_commit: serde_json::Value = serde_json::from_slice(&commit1.bytes().await?)?;
+    *parsed_commit
+        .get_mut("commitInfo")
+        .unwrap()
+        .get_mut("timestamp")
+        .unwrap() = serde_json::Value::Number(0.into());
+
+    let expected_commit = json!({
+        "commitInfo": {
+            "timestamp": 0,
+            "operation": "WRITE",
+            "operationParameters": {
+                "mode": "Append",
+                "partitionBy": "[]"
+            },
+            "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+            "engineInfo": "test-engine/1.0",
+            "engineCommitInfo": {
+                "engineInfo": "default engine"
+            }
+        }
+    });
+    assert_eq!(parsed_commit, expected_commit);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_empty_commit() -> Result<(), Box<dyn std::error::Error>> {
     // setup tracing
@@ -360,7 +449,7 @@ async fn test_append() -> Result<(), Box<dyn std::error::Error>> {
 
     // write data out by spawning async tasks to simulate executors
     let engine = Arc::new(engine);
-    let write_context = Arc::new(txn.get_write_context());
+    let write_context = Arc::new(txn.write_context());
     let tasks = append_data.into_iter().map(|data| {
         // arc clones
         let engine = engine.clone();
@@ -426,7 +515,8 @@ async fn test_append() -> Result<(), Box<dyn std::error::Error>> {
                 "partitionValues": {},
                 "size": size,
                 "modificationTime": 0,
-                "dataChange": true
+                "dataChange": true,
+                "stats": "{\"numRecords\":3,\"minValues\":{\"number\":1},\"maxValues\":{\"number\":3},\"nullCount\":{\"number\":0}}"
             }
         }),
         json!({
@@ -435,7 +525,8 @@ async fn test_append() -> Result<(), Box<dyn std::error::Error>> {
                 "partitionValues": {},
                 "size": size,
                 "modificationTime": 0,
-                "dataChange": true
+                "dataChange": true,
+                "stats": "{\"numRecords\":3,\"minValues\":{\"number\":4},\"maxValues\":{\"number\":6},\"nullCount\":{\"number\":0}}"
             }
         }),
     ];
@@ -500,7 +591,11 @@ async fn test_append_partitioned() -> Result<(), Box<dyn std::error::Error>> {
 
     // write data out by spawning async tasks to simulate executors
     let engine = Arc::new(engine);
-    let write_context = Arc::new(txn.get_write_context());
+    let write_context = Arc::new(txn.write_context());
+    // partition values are not written to the data files
+    assert_eq!(write_context.schema(), &table_schema);
+    assert_eq!(write_context.physical_schema(), &data_schema);
+    assert_eq!(write_context.partition_columns(), [partition_col]);
     let tasks = append_data
         .into_iter()
         .zip(partition_vals)
@@ -571,7 +666,8 @@ async fn test_append_partitioned() -> Result<(), Box<dyn std::error::Error>> {
                 },
                 "size": size,
                 "modificationTime": 0,
-                "dataChange": true
+                "dataChange": true,
+                "stats": "{\"numRecords\":3,\"minValues\":{\"number\":1},\"maxValues\":{\"number\":3},\"nullCount\":{\"number\":0}}"
             }
         }),
         json!({
@@ -582,7 +678,8 @@ async fn test_append_partitioned() -> Result<(), Box<dyn std::error::Error>> {
                 },
                 "size": size,
                 "modificationTime": 0,
-                "dataChange": true
+                "dataChange": true,
+                "stats": "{\"numRecords\":3,\"minValues\":{\"number\":4},\"maxValues\":{\"number\":6},\"nullCount\":{\"number\":0}}"
             }
         }),
     ];
@@ -641,7 +738,7 @@ async fn test_append_invalid_schema() -> Result<(), Box<dyn std::error::Error>>
 
     // write data out by spawning async tasks to simulate executors
     let engine = Arc::new(engine);
-    let write_context = Arc::new(txn.get_write_context());
+    let write_context = Arc::new(txn.write_context());
     let tasks = append_data.into_iter().map(|data| {
         // arc clones
         let engine = engine.clone();
@@ -671,3 +768,1397 @@ async fn test_append_invalid_schema() -> Result<(), Box<dyn std::error::Error>>
     }));
     Ok(())
 }
+
+#[tokio::test]
+async fn test_identity_high_water_mark() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // setup in-memory object store and default engine
+    let (store, engine, table_location) = setup("test_table", true);
+
+    // create a table with an identity column 'id' (starting at 1, step 1) and an int column
+    let schema = Arc::new(StructType::new(vec![
+        StructField::new("id", DataType::LONG, false)
+            .with_metadata([("delta.identity.start", 1), ("delta.identity.step", 1)]),
+        StructField::new("number", DataType::INTEGER, true),
+    ]));
+    let table = create_table(store.clone(), table_location, schema, &[]).await?;
+
+    let snapshot = table.snapshot(&engine, None)?;
+    let info = snapshot
+        .schema()
+        .field("id")
+        .unwrap()
+        .identity_column_info()?;
+    assert_eq!(info.unwrap().next_value()?, 1);
+
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    assert!(txn.update_identity_high_water_mark("number", 10).is_err());
+    txn.update_identity_high_water_mark("id", 10)?;
+    // high water marks only move in the step direction
+    txn.update_identity_high_water_mark("id", 5)?;
+    txn.commit(&engine)?;
+
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.version(), 1);
+    let info = snapshot
+        .schema()
+        .field("id")
+        .unwrap()
+        .identity_column_info()?;
+    let info = info.unwrap();
+    assert_eq!(info.high_water_mark, Some(10));
+    assert_eq!(info.next_value()?, 11);
+    assert_eq!(snapshot.metadata().id, "test_id");
+
+    // a commit without identity column updates does not write a metadata action
+    let txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.commit(&engine)?;
+    let commit2 = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit2.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert!(actions
+        .iter()
+        .all(|action| action.get("metaData").is_none()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commit_validates_iceberg_compat() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // setup in-memory object store and default engine
+    let (store, engine, table_location) = setup("test_table", true);
+
+    // Iceberg compatibility is enabled, but the protocol lacks the 'icebergCompatV2' feature
+    let schema = StructType::new(vec![StructField::new("number", DataType::INTEGER, true)]);
+    let protocol = json!({
+        "protocol": {
+            "minReaderVersion": 3,
+            "minWriterVersion": 7,
+            "readerFeatures": [],
+            "writerFeatures": []
+        }
+    });
+    let metadata = json!({
+        "metaData": {
+            "id": "test_id",
+            "format": {
+                "provider": "parquet",
+                "options": {}
+            },
+            "schemaString": serde_json::to_string(&schema)?,
+            "partitionColumns": [],
+            "configuration": {
+                "delta.enableIcebergCompatV2": "true"
+            },
+            "createdTime": 1677811175819u64
+        }
+    });
+    let data = [to_vec(&protocol)?, b"\n".to_vec(), to_vec(&metadata)?].concat();
+    store
+        .put(
+            &Path::from("/test_table/_delta_log/00000000000000000000.json"),
+            data.into(),
+        )
+        .await?;
+    let table = Table::new(table_location);
+
+    let txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let err = txn.commit(&engine).unwrap_err();
+    assert!(matches!(err, KernelError::Unsupported(_)));
+    assert!(err.to_string().contains("icebergCompatV2"), "{err}");
+    // nothing was committed
+    assert_eq!(table.snapshot(&engine, None)?.version(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_domain_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // setup in-memory object store and default engine
+    let (store, engine, table_location) = setup("test_table", true);
+
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = create_table_with_writer_features(
+        store.clone(),
+        table_location.clone(),
+        schema.clone(),
+        &[],
+        &["domainMetadata"],
+    )
+    .await?;
+
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_domain_metadata("app1", r#"{"a":1}"#)?;
+    txn.set_domain_metadata("app2", "{}")?;
+    // removing a domain that does not exist has no effect
+    txn.remove_domain_metadata("app3")?;
+    assert!(txn.set_domain_metadata("app1", "{}").is_err());
+    assert!(txn.set_domain_metadata("delta.rowTracking", "{}").is_err());
+    txn.commit(&engine)?;
+
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.remove_domain_metadata("app2")?;
+    txn.commit(&engine)?;
+
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.version(), 2);
+    assert_eq!(
+        snapshot.domain_metadata("app1", &engine)?.as_deref(),
+        Some(r#"{"a":1}"#)
+    );
+    assert_eq!(snapshot.domain_metadata("app2", &engine)?, None);
+    assert_eq!(snapshot.domain_metadata("app3", &engine)?, None);
+
+    // the removal is a tombstone carrying the domain's last configuration
+    let commit2 = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit2.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1],
+        json!({
+            "domainMetadata": {
+                "domain": "app2",
+                "configuration": "{}",
+                "removed": true,
+            }
+        })
+    );
+
+    // domain metadata can only be set on tables with the domainMetadata writer feature
+    let (store, engine, table_location) = setup("test_table_2", true);
+    let table = create_table(store, table_location, schema, &[]).await?;
+    let mut txn = table.new_transaction(&engine)?;
+    assert!(matches!(
+        txn.set_domain_metadata("app1", "{}"),
+        Err(KernelError::Unsupported(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commit_engine_written_files() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow_array::builder::{MapBuilder, MapFieldNames, StringBuilder};
+    use arrow_array::{BooleanArray, Int64Array};
+    use delta_kernel::transaction::{get_write_metadata_schema, CommitResult};
+
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // setup in-memory object store and default engine
+    let (store, engine, table_location) = setup("test_table", true);
+
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = create_table(store.clone(), table_location, schema, &[]).await?;
+    let snapshot = Arc::new(table.snapshot(&engine, None)?);
+
+    // metadata of a data file written by the engine, including its stats
+    let stats = r#"{"numRecords":3,"minValues":{"number":1},"maxValues":{"number":3},"nullCount":{"number":0}}"#;
+    let mut partition_values = MapBuilder::new(
+        Some(MapFieldNames {
+            entry: "key_value".to_string(),
+            key: "key".to_string(),
+            value: "value".to_string(),
+        }),
+        StringBuilder::new(),
+        StringBuilder::new(),
+    );
+    partition_values.append(true)?;
+    let write_metadata = RecordBatch::try_new(
+        Arc::new(get_write_metadata_schema().as_ref().try_into()?),
+        vec![
+            Arc::new(StringArray::from(vec!["part-00000.parquet"])),
+            Arc::new(partition_values.finish()),
+            Arc::new(Int64Array::from(vec![1024])),
+            Arc::new(Int64Array::from(vec![1677811178336])),
+            Arc::new(BooleanArray::from(vec![true])),
+            Arc::new(StringArray::from(vec![stats])),
+        ],
+    )?;
+
+    let mut txn = snapshot
+        .clone()
+        .new_transaction()?
+        .with_commit_info(new_commit_info()?);
+    txn.add_write_metadata(Box::new(ArrowEngineData::new(write_metadata)));
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+    let commit1 = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit1.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1],
+        json!({
+            "add": {
+                "path": "part-00000.parquet",
+                "partitionValues": {},
+                "size": 1024,
+                "modificationTime": 1677811178336i64,
+                "dataChange": true,
+                "stats": stats,
+            }
+        })
+    );
+
+    // another transaction on the same snapshot loses the race for version 1, but a concurrent
+    // append does not conflict with it, so it is retried at version 2
+    let txn = snapshot
+        .new_transaction()?
+        .with_commit_info(new_commit_info()?);
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(2)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commit_conflict() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::{CommitResult, ConflictType};
+
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // setup in-memory object store and default engine
+    let (store, engine, table_location) = setup("test_table", true);
+
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table =
+        create_table_with_writer_features(store, table_location, schema, &[], &["domainMetadata"])
+            .await?;
+    let snapshot = Arc::new(table.snapshot(&engine, None)?);
+
+    // two concurrent transactions which change the same metadata domain
+    let mut txn1 = snapshot
+        .clone()
+        .new_transaction()?
+        .with_commit_info(new_commit_info()?);
+    txn1.set_domain_metadata("app1", "{}")?;
+    let mut txn2 = snapshot
+        .clone()
+        .new_transaction()?
+        .with_commit_info(new_commit_info()?);
+    txn2.set_domain_metadata("app1", r#"{"a":1}"#)?;
+    // a third one which changes a different domain
+    let mut txn3 = snapshot
+        .clone()
+        .new_transaction()?
+        .with_commit_info(new_commit_info()?);
+    txn3.set_domain_metadata("app2", "{}")?;
+    // and a fourth one which doesn't retry
+    let mut txn4 = snapshot
+        .new_transaction()?
+        .with_commit_info(new_commit_info()?)
+        .with_max_commit_retries(0);
+    txn4.set_domain_metadata("app3", "{}")?;
+
+    assert!(matches!(txn1.commit(&engine)?, CommitResult::Committed(1)));
+    match txn2.commit(&engine)? {
+        CommitResult::Conflict(txn, version) => {
+            assert_eq!(version, 1);
+            assert_eq!(
+                txn.conflict(),
+                Some(&ConflictType::ConcurrentDomainMetadata("app1".to_string()))
+            );
+        }
+        CommitResult::Committed(_) => panic!("expected a conflict"),
+    }
+    assert!(matches!(txn3.commit(&engine)?, CommitResult::Committed(2)));
+    match txn4.commit(&engine)? {
+        CommitResult::Conflict(txn, version) => {
+            assert_eq!(version, 1);
+            assert_eq!(txn.conflict(), None);
+        }
+        CommitResult::Committed(_) => panic!("expected to give up retrying"),
+    }
+
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot.domain_metadata("app1", &engine)?.as_deref(),
+        Some("{}")
+    );
+    assert_eq!(
+        snapshot.domain_metadata("app2", &engine)?.as_deref(),
+        Some("{}")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_table() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::CommitResult;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, true),
+        StructField::new("partition", DataType::STRING, true),
+    ]));
+    let table = Table::new(table_location);
+    let snapshot = table.create(
+        &engine,
+        schema.clone(),
+        ["partition"],
+        [
+            ("delta.feature.domainMetadata", "supported"),
+            ("delta.checkpointInterval", "5"),
+        ],
+    )?;
+    assert_eq!(snapshot.version(), 0);
+    assert_eq!(snapshot.schema(), schema.as_ref());
+    assert_eq!(
+        snapshot.table_properties().checkpoint_interval,
+        Some(5.try_into()?)
+    );
+
+    let commit0 = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000000.json",
+        ))
+        .await?;
+    let parsed_commits: Vec<serde_json::Value> = Deserializer::from_slice(&commit0.bytes().await?)
+        .into_iter::<serde_json::Value>()
+        .try_collect()?;
+    assert_eq!(parsed_commits.len(), 3);
+    assert_eq!(parsed_commits[0]["commitInfo"]["operation"], "CREATE TABLE");
+    assert_eq!(
+        parsed_commits[0]["commitInfo"]["operationParameters"]["partitionBy"],
+        "[\"partition\"]"
+    );
+    assert_eq!(
+        parsed_commits[1],
+        json!({
+            "protocol": {
+                "minReaderVersion": 3,
+                "minWriterVersion": 7,
+                "readerFeatures": [],
+                "writerFeatures": ["domainMetadata"]
+            }
+        })
+    );
+    assert_eq!(
+        parsed_commits[2]["metaData"]["configuration"],
+        json!({"delta.checkpointInterval": "5"})
+    );
+    assert_eq!(
+        parsed_commits[2]["metaData"]["schemaString"],
+        serde_json::to_string(&schema)?
+    );
+
+    // the new table can be written to
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_domain_metadata("app1", "{}")?;
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+    // but not created again
+    let result = table.create(
+        &engine,
+        schema,
+        None::<String>,
+        HashMap::<String, String>::new(),
+    );
+    assert!(matches!(result, Err(KernelError::Generic(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_schema_and_properties() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::CommitResult;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, false),
+        StructField::new("partition", DataType::STRING, true),
+    ]));
+    let table = create_table(store.clone(), table_location, schema, &["partition"]).await?;
+
+    // add a column, make `number` nullable, and enable domain metadata
+    let new_schema = StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, true),
+        StructField::new("partition", DataType::STRING, true),
+        StructField::new("name", DataType::STRING, true),
+    ]);
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.update_schema(new_schema.clone())?;
+    txn.set_table_properties([
+        ("delta.checkpointInterval", "5"),
+        ("delta.feature.domainMetadata", "supported"),
+    ])?;
+    txn.set_domain_metadata("app1", "{}")?;
+    assert_eq!(txn.write_context().schema().as_ref(), &new_schema);
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+    let commit1 = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let parsed_commits: Vec<serde_json::Value> = Deserializer::from_slice(&commit1.bytes().await?)
+        .into_iter::<serde_json::Value>()
+        .try_collect()?;
+    assert_eq!(
+        parsed_commits[1],
+        json!({
+            "protocol": {
+                "minReaderVersion": 3,
+                "minWriterVersion": 7,
+                "readerFeatures": [],
+                "writerFeatures": ["domainMetadata"]
+            }
+        })
+    );
+    assert_eq!(parsed_commits[2]["metaData"]["id"], "test_id");
+
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.schema(), &new_schema);
+    assert_eq!(snapshot.metadata().partition_columns, ["partition"]);
+    assert_eq!(
+        snapshot.table_properties().checkpoint_interval,
+        Some(5.try_into()?)
+    );
+    assert_eq!(
+        snapshot.domain_metadata("app1", &engine)?.as_deref(),
+        Some("{}")
+    );
+
+    // incompatible changes are rejected
+    let mut txn = table.new_transaction(&engine)?;
+    let dropped = StructType::new(vec![StructField::new("number", DataType::INTEGER, true)]);
+    assert!(txn.update_schema(dropped).is_err());
+    let non_nullable = StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, false),
+        StructField::new("partition", DataType::STRING, true),
+        StructField::new("name", DataType::STRING, true),
+    ]);
+    assert!(txn.update_schema(non_nullable).is_err());
+    assert!(txn
+        .set_table_properties([("delta.appendOnly", "maybe")])
+        .is_err());
+    // the kernel cannot write to tables with change data feed yet
+    assert!(matches!(
+        txn.set_table_properties([("delta.enableChangeDataFeed", "true")]),
+        Err(KernelError::UnsupportedTableFeature(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unset_table_properties() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::CommitResult;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (_store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema,
+        Vec::<String>::new(),
+        HashMap::from([
+            ("delta.appendOnly", "true"),
+            ("delta.checkpointInterval", "5"),
+            ("custom.owner", "me"),
+        ]),
+    )?;
+
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    // properties which are not set are an error, unless they may not exist
+    assert!(txn
+        .unset_table_properties(["delta.logRetentionDuration"], false)
+        .is_err());
+    assert!(matches!(
+        txn.unset_table_properties(["delta.feature.appendOnly"], true),
+        Err(KernelError::Unsupported(_))
+    ));
+    txn.unset_table_properties(["delta.appendOnly", "custom.owner", "missing"], true)?;
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot.metadata().configuration,
+        HashMap::from([("delta.checkpointInterval".to_string(), "5".to_string())])
+    );
+    assert!(!snapshot.table_properties().is_append_only());
+    // the table feature remains supported by the protocol
+    assert!(snapshot
+        .protocol()
+        .writer_features()
+        .is_some_and(|features| features.contains(&"appendOnly".to_string())));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_automatic_checkpointing() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::CommitResult;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema.clone(),
+        None::<String>,
+        [
+            ("delta.feature.domainMetadata", "supported"),
+            ("delta.checkpointInterval", "2"),
+        ],
+    )?;
+
+    // append one file per commit, checkpointing all but the last commit automatically
+    let engine = Arc::new(engine);
+    for (version, checkpointing) in [(1, true), (2, true), (3, true), (4, false)] {
+        let mut txn = table
+            .new_transaction(engine.as_ref())?
+            .with_commit_info(new_commit_info()?)
+            .with_automatic_checkpointing(checkpointing);
+        txn.set_domain_metadata(format!("app{version}"), "{}")?;
+        if version == 3 {
+            txn.remove_domain_metadata("app1")?;
+        }
+        let data = RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![version]))],
+        )?;
+        let write_metadata = engine
+            .write_parquet(
+                &ArrowEngineData::new(data),
+                &txn.write_context(),
+                HashMap::new(),
+                true,
+            )
+            .await?;
+        txn.add_write_metadata(write_metadata);
+        assert!(matches!(
+            txn.commit(engine.as_ref())?,
+            CommitResult::Committed(v) if v == version as u64
+        ));
+    }
+
+    // only version 2 is a multiple of the interval and was committed with checkpointing enabled
+    let log_files: Vec<_> = futures::TryStreamExt::try_collect(
+        store.list(Some(&Path::from("/test_table/_delta_log/"))),
+    )
+    .await?;
+    let checkpoints: Vec<_> = log_files
+        .into_iter()
+        .map(|meta| meta.location.to_string())
+        .filter(|location| location.contains(".checkpoint."))
+        .collect();
+    assert_eq!(
+        checkpoints,
+        ["test_table/_delta_log/00000000000000000002.checkpoint.parquet"]
+    );
+    let last_checkpoint = store
+        .get(&Path::from("/test_table/_delta_log/_last_checkpoint"))
+        .await?;
+    let last_checkpoint: serde_json::Value =
+        serde_json::from_slice(&last_checkpoint.bytes().await?)?;
+    assert_eq!(last_checkpoint["version"], 2);
+    // protocol, metadata, two adds, and two domain metadata
+    assert_eq!(last_checkpoint["size"], 6);
+    assert_eq!(last_checkpoint["numOfAddFiles"], 2);
+
+    // the latest snapshot is read from the checkpoint and the commits after it
+    let snapshot = table.snapshot(engine.as_ref(), None)?;
+    assert_eq!(snapshot.version(), 4);
+    assert_eq!(
+        snapshot.table_properties().checkpoint_interval,
+        Some(2.try_into()?)
+    );
+    assert_eq!(snapshot.domain_metadata("app1", engine.as_ref())?, None);
+    assert_eq!(
+        snapshot
+            .domain_metadata("app2", engine.as_ref())?
+            .as_deref(),
+        Some("{}")
+    );
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            // log replay returns the newest files first
+            vec![Arc::new(Int32Array::from(vec![4, 3, 2, 1]))],
+        )?),
+        &table,
+        engine,
+    )?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_rows() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::actions::deletion_vector::DeletionVector;
+    use delta_kernel::transaction::{CommitResult, ConflictType};
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema.clone(),
+        None::<String>,
+        [("delta.enableDeletionVectors", "true")],
+    )?;
+
+    let engine = Arc::new(engine);
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5]))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.write_context(),
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    txn.commit(engine.as_ref())?;
+
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let path = actions[1]["add"]["path"].as_str().unwrap().to_string();
+
+    // delete the second and fourth rows
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let deleted: DeletionVector = [1, 3].into_iter().collect();
+    txn.delete_rows(engine.as_ref(), [(path.clone(), deleted)])?;
+    assert!(matches!(
+        txn.commit(engine.as_ref())?,
+        CommitResult::Committed(2)
+    ));
+
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(actions.len(), 3);
+    assert_eq!(actions[1]["remove"]["path"], path.as_str());
+    assert_eq!(actions[1]["remove"]["dataChange"], true);
+    assert!(actions[1]["remove"]["deletionVector"].is_null());
+    assert_eq!(actions[2]["add"]["path"], path.as_str());
+    assert_eq!(actions[2]["add"]["deletionVector"]["storageType"], "i");
+    assert_eq!(actions[2]["add"]["deletionVector"]["cardinality"], 2);
+
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![1, 3, 5]))],
+        )?),
+        &table,
+        engine.clone(),
+    )?;
+
+    // concurrent deletes from the same file conflict
+    let mut txn1 = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let mut txn2 = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    txn1.delete_rows(engine.as_ref(), [(path.clone(), [0].into_iter().collect())])?;
+    txn2.delete_rows(engine.as_ref(), [(path.clone(), [4].into_iter().collect())])?;
+    assert!(matches!(
+        txn1.commit(engine.as_ref())?,
+        CommitResult::Committed(3)
+    ));
+    assert!(matches!(
+        txn2.commit(engine.as_ref())?,
+        CommitResult::Conflict(txn, 3)
+            if txn.conflict() == Some(&ConflictType::ConcurrentDeleteDelete(path.clone()))
+    ));
+
+    // the second round of deletes removes the file with the first deletion vector, and merges
+    // both into the new one
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000003.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(actions.len(), 3);
+    assert_eq!(actions[1]["remove"]["path"], path.as_str());
+    assert_eq!(actions[1]["remove"]["deletionVector"]["cardinality"], 2);
+    assert_eq!(actions[2]["add"]["path"], path.as_str());
+    assert_eq!(actions[2]["add"]["deletionVector"]["cardinality"], 3);
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![3, 5]))],
+        )?),
+        &table,
+        engine.clone(),
+    )?;
+
+    // only files of the table can have rows deleted
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    assert!(txn
+        .delete_rows(
+            engine.as_ref(),
+            [("missing.parquet".to_string(), [0].into_iter().collect())]
+        )
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_rows_requires_deletion_vectors() -> Result<(), Box<dyn std::error::Error>> {
+    let (_store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema,
+        None::<String>,
+        HashMap::<String, String>::new(),
+    )?;
+
+    let mut txn = table.new_transaction(&engine)?;
+    assert!(matches!(
+        txn.delete_rows(
+            &engine,
+            [("a.parquet".to_string(), [0].into_iter().collect())]
+        ),
+        Err(KernelError::Unsupported(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remove_files() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::CommitResult;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema.clone(),
+        None::<String>,
+        HashMap::<String, String>::new(),
+    )?;
+
+    // append two files
+    let engine = Arc::new(engine);
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    for numbers in [vec![1, 2], vec![3]] {
+        let data = RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(numbers))],
+        )?;
+        let write_metadata = engine
+            .write_parquet(
+                &ArrowEngineData::new(data),
+                &txn.write_context(),
+                HashMap::new(),
+                true,
+            )
+            .await?;
+        txn.add_write_metadata(write_metadata);
+    }
+    txn.commit(engine.as_ref())?;
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let add = &actions[1]["add"];
+    let path = add["path"].as_str().unwrap().to_string();
+    let other_path = actions[2]["add"]["path"].as_str().unwrap().to_string();
+
+    // files must be part of the table, and nothing is removed if one is not
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let err = txn
+        .remove_files(engine.as_ref(), [path.as_str(), "missing.parquet"], true)
+        .unwrap_err();
+    assert!(err.to_string().contains("missing.parquet"));
+    txn.remove_files(engine.as_ref(), [path.as_str()], true)?;
+    assert!(matches!(
+        txn.commit(engine.as_ref())?,
+        CommitResult::Committed(2)
+    ));
+
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(actions.len(), 2);
+    let remove = &actions[1]["remove"];
+    assert_eq!(remove["path"], path.as_str());
+    assert_eq!(remove["dataChange"], true);
+    assert_eq!(remove["extendedFileMetadata"], true);
+    assert_eq!(remove["size"], add["size"]);
+    assert!(remove["deletionTimestamp"].as_i64().unwrap() > 0);
+
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![3]))],
+        )?),
+        &table,
+        engine.clone(),
+    )?;
+
+    // the removed file is no longer part of the table
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    assert!(txn
+        .remove_files(engine.as_ref(), [path.as_str()], true)
+        .is_err());
+
+    // append-only tables only allow removing files without changing data
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    txn.set_table_properties([("delta.appendOnly", "true")])?;
+    assert!(txn
+        .remove_files(engine.as_ref(), [other_path.as_str()], true)
+        .is_err());
+    txn.remove_files(engine.as_ref(), [other_path.as_str()], false)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_partitioned() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow::array::{Array, AsArray, Date32Array};
+    use arrow::datatypes::{Date32Type, Int32Type};
+    use common::read_scan;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, true),
+        StructField::new("region", DataType::STRING, true),
+        StructField::new("date", DataType::DATE, true),
+    ]));
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema.clone(),
+        ["region", "date"],
+        HashMap::<String, String>::new(),
+    )?;
+
+    let engine = Arc::new(engine);
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec![
+                Some("US/East"),
+                Some("a b%c"),
+                Some("US/East"),
+                None,
+            ])),
+            Arc::new(Date32Array::from(vec![19723, 19723, 19723, 0])),
+        ],
+    )?;
+    let write_metadata = engine
+        .write_partitioned_parquet(&ArrowEngineData::new(data), &txn.write_context(), true)
+        .await?;
+    assert_eq!(write_metadata.len(), 3);
+    for meta in write_metadata {
+        txn.add_write_metadata(meta);
+    }
+    txn.commit(engine.as_ref())?;
+
+    // the data files are in (escaped) partition directories
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let adds: Vec<_> = actions[1..].iter().map(|action| &action["add"]).collect();
+    assert_eq!(
+        adds[0]["partitionValues"],
+        json!({"region": "US/East", "date": "2024-01-01"})
+    );
+    assert!(adds[0]["path"]
+        .as_str()
+        .unwrap()
+        .contains("/test_table/region=US%252FEast/date=2024-01-01/"));
+    assert_eq!(
+        adds[1]["partitionValues"],
+        json!({"region": "a b%c", "date": "2024-01-01"})
+    );
+    assert_eq!(adds[2]["partitionValues"], json!({"date": "1970-01-01"}));
+    assert!(adds[2]["path"]
+        .as_str()
+        .unwrap()
+        .contains("/region=__HIVE_DEFAULT_PARTITION__/date=1970-01-01/"));
+
+    // the partition values are read back from the log
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    let mut rows = vec![];
+    for batch in read_scan(&scan, engine)? {
+        let numbers = batch.column(0).as_primitive::<Int32Type>();
+        let regions = batch.column(1).as_string::<i32>();
+        let dates = batch.column(2).as_primitive::<Date32Type>();
+        for i in 0..batch.num_rows() {
+            rows.push((
+                numbers.value(i),
+                regions.is_valid(i).then(|| regions.value(i).to_string()),
+                dates.value(i),
+            ));
+        }
+    }
+    rows.sort();
+    assert_eq!(
+        rows,
+        [
+            (1, Some("US/East".to_string()), 19723),
+            (2, Some("a b%c".to_string()), 19723),
+            (3, Some("US/East".to_string()), 19723),
+            (4, None, 0),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_stats() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int32Type;
+    use common::read_scan;
+    use delta_kernel::expressions::{column_expr, Expression};
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, true),
+        StructField::new("name", DataType::STRING, true),
+    ]));
+    // only the first column is indexed
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema.clone(),
+        Vec::<String>::new(),
+        HashMap::from([("delta.dataSkippingNumIndexedCols", "1")]),
+    )?;
+
+    let engine = Arc::new(engine);
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    for (numbers, names) in [([1, 2, 3], ["a", "b", "c"]), ([4, 5, 6], ["d", "e", "f"])] {
+        let data = RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![
+                Arc::new(Int32Array::from(numbers.to_vec())),
+                Arc::new(StringArray::from(names.to_vec())),
+            ],
+        )?;
+        let write_metadata = engine
+            .write_parquet(
+                &ArrowEngineData::new(data),
+                &txn.write_context(),
+                HashMap::new(),
+                true,
+            )
+            .await?;
+        txn.add_write_metadata(write_metadata);
+    }
+    txn.commit(engine.as_ref())?;
+
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let stats: Vec<serde_json::Value> = actions[1..]
+        .iter()
+        .map(|action| serde_json::from_str(action["add"]["stats"].as_str().unwrap()))
+        .try_collect()?;
+    assert_eq!(
+        stats[0],
+        json!({
+            "numRecords": 3,
+            "minValues": {"number": 1},
+            "maxValues": {"number": 3},
+            "nullCount": {"number": 0},
+        })
+    );
+
+    // the stats of the written files are used to skip the first one, and the rows of the second
+    // one which don't match are skipped as well
+    let predicate = Arc::new(Expression::gt(column_expr!("number"), 4));
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .with_predicate(predicate)
+        .build()?;
+    let numbers: Vec<i32> = read_scan(&scan, engine)?
+        .iter()
+        .flat_map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    assert_eq!(numbers, vec![5, 6]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streaming_writer() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::{
+        CommitResult, ConflictType, StreamingCommitResult, StreamingWriter,
+    };
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    let table = create_table(store.clone(), table_location, schema.clone(), &[]).await?;
+    let engine = Arc::new(engine);
+    let writer = StreamingWriter::new(table.clone(), "query-1");
+    assert_eq!(writer.latest_batch_version(engine.as_ref())?, None);
+
+    let write_context = writer.write_context(engine.as_ref())?;
+    let files = vec![write_numbers(&engine, &write_context, vec![1, 2], true).await?];
+    let result = writer.commit_batch(engine.as_ref(), 0, new_commit_info()?, files)?;
+    assert_eq!(result, StreamingCommitResult::Committed(1));
+    assert_eq!(writer.latest_batch_version(engine.as_ref())?, Some(0));
+
+    // the batch is recorded as an application transaction
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(actions[0]["commitInfo"]["operation"], "STREAMING UPDATE");
+    let txn = actions.iter().find(|action| action.get("txn").is_some());
+    assert_eq!(txn.unwrap()["txn"]["appId"], "query-1");
+    assert_eq!(txn.unwrap()["txn"]["version"], 0);
+
+    // replaying a committed batch (e.g. after a restart) does not commit it again
+    let files = vec![write_numbers(&engine, &write_context, vec![1, 2], true).await?];
+    let result = writer.commit_batch(engine.as_ref(), 0, new_commit_info()?, files)?;
+    assert_eq!(result, StreamingCommitResult::AlreadyCommitted);
+
+    // a zombie attempt of a batch conflicts with the committed batch
+    let zombie = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?)
+        .with_transaction_id("query-1", 1);
+    let files = vec![write_numbers(&engine, &write_context, vec![3], true).await?];
+    let result = writer.commit_batch(engine.as_ref(), 1, new_commit_info()?, files)?;
+    assert_eq!(result, StreamingCommitResult::Committed(2));
+    match zombie.commit(engine.as_ref())? {
+        CommitResult::Conflict(txn, 2) => assert_eq!(
+            txn.conflict(),
+            Some(&ConflictType::ConcurrentTransaction("query-1".to_string()))
+        ),
+        result => panic!("Expected a conflict, got {result:?}"),
+    }
+
+    // other applications are independent
+    let other = StreamingWriter::new(table.clone(), "query-2");
+    let files = vec![write_numbers(&engine, &write_context, vec![4], true).await?];
+    let result = other.commit_batch(engine.as_ref(), 0, new_commit_info()?, files)?;
+    assert_eq!(result, StreamingCommitResult::Committed(3));
+
+    // the files of the table are read newest first
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![4, 3, 1, 2]))],
+        )?),
+        &table,
+        engine,
+    )?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_compact_files() -> Result<(), Box<dyn std::error::Error>> {
+    use delta_kernel::transaction::{CommitResult, ConflictType};
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let (store, engine, table_location) = setup("test_table", true);
+    let schema = Arc::new(StructType::new(vec![StructField::new(
+        "number",
+        DataType::INTEGER,
+        true,
+    )]));
+    // compaction does not change the data, so it is allowed on append-only tables
+    let table = Table::new(table_location);
+    table.create(
+        &engine,
+        schema.clone(),
+        None::<String>,
+        HashMap::from([("delta.appendOnly", "true")]),
+    )?;
+    let engine = Arc::new(engine);
+    let write_context = table.new_transaction(engine.as_ref())?.write_context();
+
+    // append three small files
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    for numbers in [vec![1, 2], vec![3], vec![4]] {
+        txn.add_write_metadata(write_numbers(&engine, &write_context, numbers, true).await?);
+    }
+    txn.commit(engine.as_ref())?;
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let paths: Vec<String> = actions[1..]
+        .iter()
+        .map(|action| action["add"]["path"].as_str().unwrap().to_string())
+        .collect();
+
+    // compacted files must not change the data of the table, and the replaced files must exist
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let compacted = write_numbers(&engine, &write_context, vec![1, 2, 3], true).await?;
+    assert!(txn
+        .compact_files(engine.as_ref(), &paths[..2], [compacted])
+        .is_err());
+    let compacted = write_numbers(&engine, &write_context, vec![1, 2, 3], false).await?;
+    assert!(txn
+        .compact_files(engine.as_ref(), ["missing.parquet"], [compacted])
+        .is_err());
+
+    // a concurrent compaction of the same files conflicts
+    let mut concurrent_txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let compacted = write_numbers(&engine, &write_context, vec![1, 2, 3], false).await?;
+    concurrent_txn.compact_files(engine.as_ref(), &paths[..2], [compacted])?;
+
+    let compacted = write_numbers(&engine, &write_context, vec![1, 2, 3], false).await?;
+    txn.compact_files(engine.as_ref(), &paths[..2], [compacted])?;
+    assert!(matches!(
+        txn.commit(engine.as_ref())?,
+        CommitResult::Committed(2)
+    ));
+    match concurrent_txn.commit(engine.as_ref())? {
+        CommitResult::Conflict(txn, 2) => match txn.conflict() {
+            Some(ConflictType::ConcurrentDeleteDelete(path)) => assert!(paths[..2].contains(path)),
+            conflict => panic!("Expected a delete conflict, got {conflict:?}"),
+        },
+        result => panic!("Expected a conflict, got {result:?}"),
+    }
+
+    // the files are replaced without changing the data of the table
+    let commit = store
+        .get(&Path::from(
+            "/test_table/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let adds: Vec<_> = actions.iter().filter_map(|a| a.get("add")).collect();
+    let removes: Vec<_> = actions.iter().filter_map(|a| a.get("remove")).collect();
+    assert_eq!(adds.len(), 1);
+    assert_eq!(adds[0]["dataChange"], false);
+    assert_eq!(removes.len(), 2);
+    assert!(removes.iter().all(|remove| remove["dataChange"] == false));
+
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3, 4]))],
+        )?),
+        &table,
+        engine,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_sync_engine_write() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int32Type;
+    use common::read_scan;
+    use delta_kernel::engine::sync::SyncEngine;
+
+    let _ = tracing_subscriber::fmt::try_init();
+    let tmp = tempfile::tempdir()?;
+    let table = Table::new(Url::from_directory_path(tmp.path()).unwrap());
+    let engine = Arc::new(SyncEngine::new());
+    let schema = Arc::new(StructType::new(vec![
+        StructField::new("number", DataType::INTEGER, true),
+        StructField::new("partition", DataType::STRING, true),
+    ]));
+    table.create(
+        engine.as_ref(),
+        schema.clone(),
+        vec!["partition"],
+        HashMap::<String, String>::new(),
+    )?;
+
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["a", "b", "a"])),
+        ],
+    )?;
+    let write_metadata = engine.write_partitioned_parquet(
+        &ArrowEngineData::new(data),
+        &txn.write_context(),
+        true,
+    )?;
+    assert_eq!(write_metadata.len(), 2);
+    for write_metadata in write_metadata {
+        txn.add_write_metadata(write_metadata);
+    }
+    txn.commit(engine.as_ref())?;
+    assert!(tmp.path().join("partition=a").is_dir());
+    assert!(tmp.path().join("partition=b").is_dir());
+
+    // the commit records the partition values and statistics of the files
+    let commit = std::fs::read(tmp.path().join("_delta_log/00000000000000000001.json"))?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit)
+        .into_iter()
+        .try_collect()?;
+    let adds: Vec<_> = actions
+        .iter()
+        .filter_map(|action| action.get("add"))
+        .collect();
+    assert_eq!(adds.len(), 2);
+    assert_eq!(adds[0]["partitionValues"], json!({"partition": "a"}));
+    let stats: serde_json::Value = serde_json::from_str(adds[0]["stats"].as_str().unwrap())?;
+    assert_eq!(stats["numRecords"], 2);
+
+    // the files can be read back
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    let mut numbers: Vec<i32> = read_scan(&scan, engine)?
+        .iter()
+        .flat_map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec()
+        })
+        .collect();
+    numbers.sort();
+    assert_eq!(numbers, [1, 2, 3]);
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Translation of Polars predicates into kernel predicates.
+
+use delta_kernel::expressions::{BinaryOperator, Expression, Scalar, UnaryOperator};
+use polars::prelude::{BooleanFunction, Expr, FunctionExpr, LiteralValue, Operator};
+
+/// Translate `expr` into a kernel expression, or `None` if it (or any of its children) has no
+/// kernel equivalent.
+pub fn to_kernel_expression(expr: &Expr) -> Option<Expression> {
+    let expr = match expr {
+        Expr::Column(name) => Expression::column([name.as_str()]),
+        Expr::Literal(value) => Expression::literal(to_kernel_scalar(value)?),
+        Expr::BinaryExpr { left, op, right } => {
+            let left = to_kernel_expression(left)?;
+            let right = to_kernel_expression(right)?;
+            match op {
+                Operator::And | Operator::LogicalAnd => left.and(right),
+                Operator::Or | Operator::LogicalOr => left.or(right),
+                Operator::EqValidity => Expression::unary(UnaryOperator::Not, left.distinct(right)),
+                op => Expression::binary(to_kernel_operator(op)?, left, right),
+            }
+        }
+        Expr::Function {
+            input,
+            function: FunctionExpr::Boolean(function),
+            ..
+        } => {
+            let [input] = input.as_slice() else {
+                return None;
+            };
+            let input = to_kernel_expression(input)?;
+            match function {
+                BooleanFunction::Not => Expression::unary(UnaryOperator::Not, input),
+                BooleanFunction::IsNull => input.is_null(),
+                BooleanFunction::IsNotNull => input.is_not_null(),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(expr)
+}
+
+fn to_kernel_operator(op: &Operator) -> Option<BinaryOperator> {
+    let op = match op {
+        Operator::Eq => BinaryOperator::Equal,
+        Operator::NotEq => BinaryOperator::NotEqual,
+        Operator::Lt => BinaryOperator::LessThan,
+        Operator::LtEq => BinaryOperator::LessThanOrEqual,
+        Operator::Gt => BinaryOperator::GreaterThan,
+        Operator::GtEq => BinaryOperator::GreaterThanOrEqual,
+        Operator::NotEqValidity => BinaryOperator::Distinct,
+        Operator::Plus => BinaryOperator::Plus,
+        Operator::Minus => BinaryOperator::Minus,
+        Operator::Multiply => BinaryOperator::Multiply,
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn to_kernel_scalar(value: &LiteralValue) -> Option<Scalar> {
+    let scalar = match value {
+        LiteralValue::Boolean(v) => Scalar::Boolean(*v),
+        LiteralValue::Int32(v) => Scalar::Integer(*v),
+        LiteralValue::Int64(v) => Scalar::Long(*v),
+        LiteralValue::Float32(v) => Scalar::Float(*v),
+        LiteralValue::Float64(v) => Scalar::Double(*v),
+        LiteralValue::String(v) => Scalar::String(v.to_string()),
+        LiteralValue::Binary(v) => Scalar::Binary(v.clone()),
+        // dynamically typed literals take the type of the column they're compared with, which
+        // isn't known here
+        // nulls compare as unknown, which can't be used to skip data
+        _ => return None,
+    };
+    Some(scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use delta_kernel::expressions::column_expr;
+    use polars::prelude::{col, lit, Null};
+
+    use super::*;
+
+    #[test]
+    fn test_to_kernel_expression() {
+        let expr = col("a")
+            .gt(lit(1i64))
+            .and(col("b").is_null().or(col("c").eq(lit("x"))));
+        let expected = column_expr!("a")
+            .gt(Scalar::Long(1))
+            .and(column_expr!("b").is_null().or(column_expr!("c").eq("x")));
+        assert_eq!(to_kernel_expression(&expr), Some(expected));
+
+        let expr = col("a").is_not_null().not();
+        let expected = Expression::unary(UnaryOperator::Not, column_expr!("a").is_not_null());
+        assert_eq!(to_kernel_expression(&expr), Some(expected));
+    }
+
+    #[test]
+    fn test_untranslatable_expressions() {
+        // modulo has no kernel equivalent, so neither has the conjunction
+        let expr = (col("a") % lit(2i32))
+            .eq(lit(0i32))
+            .and(col("b").eq(lit(1i32)));
+        assert_eq!(to_kernel_expression(&expr), None);
+        assert_eq!(to_kernel_expression(&col("a").eq(lit(Null {}))), None);
+    }
+}
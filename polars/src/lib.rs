@@ -0,0 +1,117 @@
+//! Read Delta tables into [Polars](https://pola.rs) `DataFrame`s with delta kernel.
+//!
+//! [`scan_to_data_frame`] executes a kernel [`Scan`] and collects its data (without the rows
+//! deleted by deletion vectors) into a [`DataFrame`], and [`scan_to_lazy_frame`] makes a
+//! [`LazyFrame`] of it, to be queried further with Polars. The arrow record batches read by the
+//! kernel are handed to Polars through the arrow C Data Interface, without copying their data.
+//!
+//! Polars predicates which can be translated into kernel expressions (see
+//! [`to_kernel_expression`]) can be used as the predicate of the scan, to skip the files which
+//! can't contain matching rows. Skipping is inexact, so the predicate must still be applied to the
+//! data frame.
+
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::datatypes::{Field, Schema as ArrowSchema};
+use arrow::record_batch::RecordBatch;
+use delta_kernel::scan::Scan;
+use delta_kernel::{DeltaResult, Engine, Error};
+use polars::prelude::{DataFrame, IntoLazy, LazyFrame, PolarsError, Series};
+use polars_arrow::ffi;
+
+mod expr;
+
+pub use expr::to_kernel_expression;
+
+/// Execute the scan, and collect its data into a [`DataFrame`] with the schema of the scan.
+pub fn scan_to_data_frame(scan: &Scan, engine: Arc<dyn Engine>) -> DeltaResult<DataFrame> {
+    let mut df = to_data_frame(empty_batch(scan)?)?;
+    for result in scan.execute(engine)? {
+        df.vstack_mut(&to_data_frame(RecordBatch::try_from(result?)?)?)
+            .map_err(polars_err)?;
+    }
+    df.align_chunks();
+    Ok(df)
+}
+
+/// Execute the scan, and make a [`LazyFrame`] of its data.
+pub fn scan_to_lazy_frame(scan: &Scan, engine: Arc<dyn Engine>) -> DeltaResult<LazyFrame> {
+    Ok(scan_to_data_frame(scan, engine)?.lazy())
+}
+
+/// Convert an arrow record batch into a [`DataFrame`], without copying its data.
+pub fn to_data_frame(batch: RecordBatch) -> DeltaResult<DataFrame> {
+    let schema = batch.schema();
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| to_series(field, array))
+        .collect::<DeltaResult<_>>()?;
+    DataFrame::new(columns).map_err(polars_err)
+}
+
+fn to_series(field: &Field, array: &ArrayRef) -> DeltaResult<Series> {
+    let (array, schema) = arrow::ffi::to_ffi(&array.to_data())?;
+    // SAFETY: arrow and polars-arrow both define the structs of the arrow C Data Interface, which
+    // are `repr(C)`, so the (owned) structs exported by arrow can be imported by polars, which
+    // releases them when it's done with them.
+    let (array, schema): (ffi::ArrowArray, ffi::ArrowSchema) =
+        unsafe { (std::mem::transmute(array), std::mem::transmute(schema)) };
+    let imported = unsafe { ffi::import_field_from_c(&schema) }.map_err(polars_err)?;
+    let array = unsafe { ffi::import_array_from_c(array, imported.dtype) }.map_err(polars_err)?;
+    Series::from_arrow(field.name().as_str().into(), array).map_err(polars_err)
+}
+
+// An empty batch with the schema of the scan, so that the data frame of a scan which reads no
+// data has the columns of the scan
+fn empty_batch(scan: &Scan) -> DeltaResult<RecordBatch> {
+    let schema = ArrowSchema::try_from(scan.schema().as_ref())?;
+    Ok(RecordBatch::new_empty(Arc::new(schema)))
+}
+
+fn polars_err(err: PolarsError) -> Error {
+    Error::generic_err(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use delta_kernel::engine::default::DefaultEngine;
+    use delta_kernel::Table;
+    use polars::prelude::{col, lit};
+
+    use super::*;
+
+    #[test]
+    fn test_scan_to_data_frame() -> DeltaResult<()> {
+        let path =
+            std::fs::canonicalize(PathBuf::from("../kernel/tests/data/table-with-dv-small/"))
+                .map_err(Error::generic_err)?;
+        let table = Table::try_from_uri(path.to_string_lossy())?;
+        let engine: Arc<dyn Engine> = Arc::new(DefaultEngine::try_new(
+            table.location(),
+            std::iter::empty::<(&str, &str)>(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )?);
+        let snapshot = Arc::new(table.snapshot(engine.as_ref(), None)?);
+        let scan = snapshot.scan_builder().build()?;
+
+        // rows 0 and 9 are deleted by the deletion vector
+        let df = scan_to_data_frame(&scan, engine.clone())?;
+        assert_eq!(df.width(), 1);
+        let values = df.column("value").unwrap().i32().unwrap();
+        let values: Vec<_> = values.into_no_null_iter().collect();
+        assert_eq!(values, (1..=8).collect::<Vec<_>>());
+
+        let df = scan_to_lazy_frame(&scan, engine)?
+            .filter(col("value").gt(lit(5)))
+            .collect()
+            .unwrap();
+        assert_eq!(df.height(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Python bindings for delta kernel.
+//!
+//! The `delta_kernel` Python module opens a [`Table`], gets a [`Snapshot`] of it (at its latest or
+//! a given version), and scans the snapshot into pyarrow record batches, using the default engine.
+//!
+//! ```python
+//! import delta_kernel
+//!
+//! table = delta_kernel.Table("s3://bucket/path/to/table", {"aws_region": "us-west-2"})
+//! snapshot = table.snapshot(version=3)
+//! data = snapshot.scan(columns=["id", "value"]).to_table()
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::datatypes::Schema as ArrowSchema;
+use arrow::pyarrow::ToPyArrow;
+use arrow::record_batch::RecordBatch;
+use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
+use delta_kernel::engine::default::DefaultEngine;
+use delta_kernel::schema::Schema;
+use delta_kernel::{DeltaResult, Error, Version};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+create_exception!(
+    delta_kernel,
+    DeltaKernelError,
+    PyException,
+    "An error raised by delta kernel, e.g. if a table doesn't exist or can't be read."
+);
+
+type PyEngine = Arc<DefaultEngine<TokioBackgroundExecutor>>;
+
+fn kernel_err(err: Error) -> PyErr {
+    DeltaKernelError::new_err(err.to_string())
+}
+
+// The arrow schema of a kernel schema, as a `pyarrow.Schema`
+fn to_pyarrow_schema(py: Python<'_>, schema: &Schema) -> PyResult<PyObject> {
+    let schema = ArrowSchema::try_from(schema).map_err(|err| kernel_err(err.into()))?;
+    schema.to_pyarrow(py)
+}
+
+/// A Delta table, at the given location in storage. The storage options (e.g. credentials) are
+/// passed to the object store of the engine which reads the table.
+#[pyclass(module = "delta_kernel", frozen)]
+struct Table {
+    table: delta_kernel::Table,
+    engine: PyEngine,
+}
+
+#[pymethods]
+impl Table {
+    #[new]
+    #[pyo3(signature = (location, storage_options = None))]
+    fn new(location: &str, storage_options: Option<HashMap<String, String>>) -> PyResult<Self> {
+        let table = delta_kernel::Table::try_from_uri(location).map_err(kernel_err)?;
+        let engine = DefaultEngine::try_new(
+            table.location(),
+            storage_options.unwrap_or_default(),
+            Arc::new(TokioBackgroundExecutor::new()),
+        )
+        .map_err(kernel_err)?;
+        Ok(Self {
+            table,
+            engine: Arc::new(engine),
+        })
+    }
+
+    /// The URL of the root of the table.
+    #[getter]
+    fn location(&self) -> String {
+        self.table.location().to_string()
+    }
+
+    /// A snapshot of the table at `version`, or at its latest version if `version` is `None`.
+    #[pyo3(signature = (version = None))]
+    fn snapshot(&self, py: Python<'_>, version: Option<Version>) -> PyResult<Snapshot> {
+        let snapshot = py
+            .allow_threads(|| self.table.snapshot(self.engine.as_ref(), version))
+            .map_err(kernel_err)?;
+        Ok(Snapshot {
+            snapshot: Arc::new(snapshot),
+            engine: self.engine.clone(),
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Table({})", self.table.location())
+    }
+}
+
+/// The state of a table at a version.
+#[pyclass(module = "delta_kernel", frozen)]
+struct Snapshot {
+    snapshot: Arc<delta_kernel::snapshot::Snapshot>,
+    engine: PyEngine,
+}
+
+#[pymethods]
+impl Snapshot {
+    /// The version of the table.
+    #[getter]
+    fn version(&self) -> Version {
+        self.snapshot.version()
+    }
+
+    /// The (logical) schema of the table, as a `pyarrow.Schema`.
+    fn schema(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_pyarrow_schema(py, self.snapshot.schema())
+    }
+
+    /// The names of the partition columns of the table.
+    #[getter]
+    fn partition_columns(&self) -> Vec<String> {
+        self.snapshot.metadata().partition_columns.clone()
+    }
+
+    /// The configuration (i.e. table properties) of the table.
+    #[getter]
+    fn configuration(&self) -> HashMap<String, String> {
+        self.snapshot.metadata().configuration.clone()
+    }
+
+    /// A scan of the given `columns` of the table, or of all of its columns if `columns` is
+    /// `None`.
+    #[pyo3(signature = (columns = None))]
+    fn scan(&self, columns: Option<Vec<String>>) -> PyResult<Scan> {
+        let schema = columns
+            .map(|columns| self.snapshot.schema().project(&columns))
+            .transpose()
+            .map_err(kernel_err)?;
+        let scan = self
+            .snapshot
+            .clone()
+            .scan_builder()
+            .with_schema_opt(schema)
+            .build()
+            .map_err(kernel_err)?;
+        Ok(Scan {
+            scan,
+            engine: self.engine.clone(),
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Snapshot({}, version={})",
+            self.snapshot.table_root(),
+            self.snapshot.version()
+        )
+    }
+}
+
+/// A scan of (some of the columns of) a snapshot.
+#[pyclass(module = "delta_kernel", frozen)]
+struct Scan {
+    scan: delta_kernel::scan::Scan,
+    engine: PyEngine,
+}
+
+#[pymethods]
+impl Scan {
+    /// The schema of the data of the scan, as a `pyarrow.Schema`.
+    fn schema(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_pyarrow_schema(py, self.scan.schema())
+    }
+
+    /// Read the data of the scan, as a list of `pyarrow.RecordBatch`es. The rows deleted by
+    /// deletion vectors are filtered out.
+    fn to_batches(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let batches = py
+            .allow_threads(|| self.read_batches())
+            .map_err(kernel_err)?;
+        batches.iter().map(|batch| batch.to_pyarrow(py)).collect()
+    }
+
+    /// Read the data of the scan, as a `pyarrow.Table`.
+    fn to_table(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let batches = self.to_batches(py)?;
+        let schema = self.schema(py)?;
+        let table = py.import_bound("pyarrow")?.getattr("Table")?;
+        Ok(table
+            .call_method1("from_batches", (batches, schema))?
+            .unbind())
+    }
+}
+
+impl Scan {
+    fn read_batches(&self) -> DeltaResult<Vec<RecordBatch>> {
+        self.scan
+            .execute(self.engine.clone())?
+            .map(|result| RecordBatch::try_from(result?))
+            .collect()
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "delta_kernel")]
+fn delta_kernel_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Table>()?;
+    m.add_class::<Snapshot>()?;
+    m.add_class::<Scan>()?;
+    m.add(
+        "DeltaKernelError",
+        m.py().get_type_bound::<DeltaKernelError>(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,40 @@
+from pathlib import Path
+
+import pyarrow as pa
+import pytest
+
+import delta_kernel
+
+DATA = Path(__file__).parents[2] / "kernel" / "tests" / "data"
+
+
+def table(name):
+    return delta_kernel.Table(str(DATA / name) + "/")
+
+
+def test_snapshot():
+    snapshot = table("basic_partitioned").snapshot()
+    assert snapshot.version == 1
+    assert snapshot.partition_columns == ["letter"]
+    assert snapshot.schema().names == ["letter", "number", "a_float"]
+
+
+def test_snapshot_at_version():
+    assert table("basic_partitioned").snapshot(version=0).version == 0
+
+
+def test_scan():
+    data = table("table-with-dv-small").snapshot().scan().to_table()
+    # the deletion vector deletes the first and last rows
+    assert data.column("value").to_pylist() == list(range(1, 9))
+
+
+def test_scan_columns():
+    scan = table("basic_partitioned").snapshot().scan(columns=["number"])
+    assert scan.schema() == pa.schema([pa.field("number", pa.int64())])
+    assert sorted(scan.to_table().column("number").to_pylist()) == [1, 2, 3, 4, 5, 6]
+
+
+def test_errors():
+    with pytest.raises(delta_kernel.DeltaKernelError):
+        table("does-not-exist").snapshot()
@@ -0,0 +1,311 @@
+//! Translation of Substrait expressions into kernel expressions.
+
+use std::collections::HashMap;
+
+use delta_kernel::expressions::{ColumnName, Expression, Scalar, UnaryOperator};
+use delta_kernel::schema::DataType;
+use delta_kernel::{DeltaResult, Error};
+use substrait::proto::expression::field_reference::{ReferenceType, RootType};
+use substrait::proto::expression::literal::LiteralType;
+use substrait::proto::expression::reference_segment;
+use substrait::proto::expression::{
+    FieldReference, Literal, ReferenceSegment, RexType, ScalarFunction, SingularOrList,
+};
+use substrait::proto::expression_reference::ExprType;
+use substrait::proto::extensions::simple_extension_declaration::MappingType;
+use substrait::proto::function_argument::ArgType;
+use substrait::proto::r#type::Kind;
+use substrait::proto::{Expression as SubstraitExpression, ExtendedExpression, NamedStruct, Type};
+
+use crate::types::{to_kernel_type, TIMESTAMP_PRECISION};
+use crate::Function;
+
+/// A (possibly nested) field of the base schema, with the fields nested in it if it's a struct.
+struct Field {
+    name: String,
+    children: Vec<Field>,
+}
+
+/// Rebuild the tree of field names of the schema, from its depth-first list of names.
+fn fields_of(schema: &NamedStruct) -> DeltaResult<Vec<Field>> {
+    fn fields_of_type<'a>(
+        substrait_type: &Type,
+        names: &mut impl Iterator<Item = &'a String>,
+    ) -> DeltaResult<Vec<Field>> {
+        match &substrait_type.kind {
+            Some(Kind::Struct(struct_type)) => struct_type
+                .types
+                .iter()
+                .map(|field_type| {
+                    let name = names.next().ok_or_else(|| {
+                        Error::invalid_expression("Too few names for the Substrait base schema")
+                    })?;
+                    let children = fields_of_type(field_type, names)?;
+                    Ok(Field {
+                        name: name.clone(),
+                        children,
+                    })
+                })
+                .collect(),
+            // Fields nested in lists and maps are named too, but can't be referenced by columns
+            Some(Kind::List(list)) => {
+                if let Some(element) = &list.r#type {
+                    fields_of_type(element, names)?;
+                }
+                Ok(vec![])
+            }
+            Some(Kind::Map(map)) => {
+                for nested in [&map.key, &map.value].into_iter().flatten() {
+                    fields_of_type(nested, names)?;
+                }
+                Ok(vec![])
+            }
+            _ => Ok(vec![]),
+        }
+    }
+    let root = schema
+        .r#struct
+        .clone()
+        .ok_or_else(|| Error::invalid_expression("Substrait base schema without a struct"))?;
+    let root = Type {
+        kind: Some(Kind::Struct(root)),
+    };
+    let mut names = schema.names.iter();
+    let fields = fields_of_type(&root, &mut names)?;
+    match names.next() {
+        Some(_) => Err(Error::invalid_expression(
+            "Too many names for the Substrait base schema",
+        )),
+        None => Ok(fields),
+    }
+}
+
+/// The state needed to translate the expressions of an [`ExtendedExpression`]: the fields of
+/// its base schema, and the functions declared by its extensions (by anchor).
+struct Consumer {
+    fields: Vec<Field>,
+    functions: HashMap<u32, Function>,
+}
+
+/// Translate the expressions of a Substrait [`ExtendedExpression`] into kernel expressions, in
+/// order. Field references are resolved against the base schema of the extended expression, into
+/// (possibly nested) columns.
+///
+/// Returns an error if an expression (or any of its children) has no kernel equivalent, for
+/// example a call to a function which isn't one of the standard Substrait functions with a kernel
+/// equivalent.
+pub fn from_substrait(extended: &ExtendedExpression) -> DeltaResult<Vec<Expression>> {
+    let schema = extended
+        .base_schema
+        .as_ref()
+        .ok_or_else(|| Error::invalid_expression("Substrait expression without a base schema"))?;
+    let uris: HashMap<_, _> = extended
+        .extension_uris
+        .iter()
+        .map(|uri| (uri.extension_uri_anchor, uri.uri.as_str()))
+        .collect();
+    let functions = extended
+        .extensions
+        .iter()
+        .filter_map(|extension| match &extension.mapping_type {
+            Some(MappingType::ExtensionFunction(function)) => Some(function),
+            _ => None,
+        })
+        .filter_map(|function| {
+            let uri = uris.get(&function.extension_uri_reference)?;
+            let resolved = Function::resolve(uri, &function.name)?;
+            Some((function.function_anchor, resolved))
+        })
+        .collect();
+    let consumer = Consumer {
+        fields: fields_of(schema)?,
+        functions,
+    };
+    extended
+        .referred_expr
+        .iter()
+        .map(|referred| match &referred.expr_type {
+            Some(ExprType::Expression(expr)) => consumer.to_kernel_expression(expr),
+            _ => Err(Error::unsupported(
+                "Only Substrait scalar expressions can be translated",
+            )),
+        })
+        .collect()
+}
+
+impl Consumer {
+    fn to_kernel_expression(&self, expr: &SubstraitExpression) -> DeltaResult<Expression> {
+        let rex_type = expr
+            .rex_type
+            .as_ref()
+            .ok_or_else(|| Error::invalid_expression("Substrait expression without a type"))?;
+        match rex_type {
+            RexType::Literal(literal) => Ok(Expression::literal(to_kernel_scalar(literal)?)),
+            RexType::Selection(reference) => Ok(Expression::Column(self.to_column(reference)?)),
+            RexType::ScalarFunction(function) => self.to_kernel_call(function),
+            RexType::SingularOrList(list) => self.to_kernel_in_list(list),
+            _ => Err(Error::unsupported(format!(
+                "Substrait expression {rex_type:?} has no kernel equivalent"
+            ))),
+        }
+    }
+
+    /// Resolve a reference to a (possibly nested) field of the base schema into a column.
+    fn to_column(&self, reference: &FieldReference) -> DeltaResult<ColumnName> {
+        if !matches!(reference.root_type, None | Some(RootType::RootReference(_))) {
+            return Err(Error::unsupported(
+                "Only Substrait references to fields of the base schema can be translated",
+            ));
+        }
+        let Some(ReferenceType::DirectReference(segment)) = &reference.reference_type else {
+            return Err(Error::unsupported(
+                "Only direct Substrait field references can be translated",
+            ));
+        };
+        let mut path = vec![];
+        let mut fields = &self.fields;
+        let mut segment: Option<&ReferenceSegment> = Some(segment);
+        while let Some(ReferenceSegment { reference_type }) = segment {
+            let Some(reference_segment::ReferenceType::StructField(struct_field)) = reference_type
+            else {
+                return Err(Error::unsupported(
+                    "Only Substrait references to struct fields can be translated",
+                ));
+            };
+            let field = usize::try_from(struct_field.field)
+                .ok()
+                .and_then(|index| fields.get(index))
+                .ok_or_else(|| {
+                    Error::invalid_expression(format!(
+                        "Substrait field reference {} out of bounds",
+                        struct_field.field
+                    ))
+                })?;
+            path.push(field.name.clone());
+            fields = &field.children;
+            segment = struct_field.child.as_deref();
+        }
+        Ok(ColumnName::new(path))
+    }
+
+    fn to_kernel_call(&self, function: &ScalarFunction) -> DeltaResult<Expression> {
+        let resolved = self
+            .functions
+            .get(&function.function_reference)
+            .ok_or_else(|| {
+                Error::unsupported(format!(
+                    "Substrait function with anchor {} has no kernel equivalent",
+                    function.function_reference
+                ))
+            })?;
+        let args = function
+            .arguments
+            .iter()
+            .map(|arg| match &arg.arg_type {
+                Some(ArgType::Value(value)) => self.to_kernel_expression(value),
+                _ => Err(Error::unsupported(
+                    "Only value arguments of Substrait functions can be translated",
+                )),
+            })
+            .collect::<DeltaResult<Vec<_>>>()?;
+        resolved.to_kernel_expression(args)
+    }
+
+    /// `value IN (options...)` is translated into a disjunction of equalities, which has the same
+    /// semantics (including for nulls).
+    fn to_kernel_in_list(&self, list: &SingularOrList) -> DeltaResult<Expression> {
+        let value = list
+            .value
+            .as_deref()
+            .ok_or_else(|| Error::invalid_expression("Substrait IN list without a value"))?;
+        let value = self.to_kernel_expression(value)?;
+        let equalities = list
+            .options
+            .iter()
+            .map(|option| Ok(value.clone().eq(self.to_kernel_expression(option)?)))
+            .collect::<DeltaResult<Vec<_>>>()?;
+        Ok(Expression::or_from(equalities))
+    }
+}
+
+impl Function {
+    /// Translate a call of the function with the given arguments into a kernel expression.
+    fn to_kernel_expression(self, args: Vec<Expression>) -> DeltaResult<Expression> {
+        let wrong_args = |_| {
+            Error::invalid_expression(format!(
+                "Wrong number of arguments for Substrait function {}",
+                self.name()
+            ))
+        };
+        let expr = match self {
+            Function::And => Expression::and_from(args),
+            Function::Or => Expression::or_from(args),
+            Function::Coalesce => Expression::coalesce(args),
+            Function::Not => {
+                let [arg]: [_; 1] = args.try_into().map_err(wrong_args)?;
+                Expression::unary(UnaryOperator::Not, arg)
+            }
+            Function::IsNull => {
+                let [arg]: [_; 1] = args.try_into().map_err(wrong_args)?;
+                arg.is_null()
+            }
+            Function::IsNotNull => {
+                let [arg]: [_; 1] = args.try_into().map_err(wrong_args)?;
+                arg.is_not_null()
+            }
+            Function::IsNotDistinctFrom => {
+                let [left, right]: [_; 2] = args.try_into().map_err(wrong_args)?;
+                Expression::unary(UnaryOperator::Not, left.distinct(right))
+            }
+            Function::Binary(op) => {
+                let [left, right]: [_; 2] = args.try_into().map_err(wrong_args)?;
+                Expression::binary(op, left, right)
+            }
+        };
+        Ok(expr)
+    }
+}
+
+fn to_kernel_scalar(literal: &Literal) -> DeltaResult<Scalar> {
+    let literal_type = literal
+        .literal_type
+        .as_ref()
+        .ok_or_else(|| Error::invalid_expression("Substrait literal without a type"))?;
+    let scalar = match literal_type {
+        LiteralType::Boolean(value) => Scalar::Boolean(*value),
+        LiteralType::I8(value) => Scalar::Byte(i8::try_from(*value).map_err(Error::generic_err)?),
+        LiteralType::I16(value) => {
+            Scalar::Short(i16::try_from(*value).map_err(Error::generic_err)?)
+        }
+        LiteralType::I32(value) => Scalar::Integer(*value),
+        LiteralType::I64(value) => Scalar::Long(*value),
+        LiteralType::Fp32(value) => Scalar::Float(*value),
+        LiteralType::Fp64(value) => Scalar::Double(*value),
+        LiteralType::String(value) => Scalar::String(value.clone()),
+        LiteralType::Binary(value) => Scalar::Binary(value.clone()),
+        LiteralType::Date(value) => Scalar::Date(*value),
+        LiteralType::PrecisionTimestamp(ts) if ts.precision == TIMESTAMP_PRECISION => {
+            Scalar::TimestampNtz(ts.value)
+        }
+        LiteralType::PrecisionTimestampTz(ts) if ts.precision == TIMESTAMP_PRECISION => {
+            Scalar::Timestamp(ts.value)
+        }
+        LiteralType::Decimal(decimal) => {
+            let bytes: [u8; 16] = decimal.value.as_slice().try_into().map_err(|_| {
+                Error::invalid_decimal("Substrait decimal literals must have 16 bytes")
+            })?;
+            let precision = u8::try_from(decimal.precision).map_err(Error::generic_err)?;
+            let scale = u8::try_from(decimal.scale).map_err(Error::generic_err)?;
+            // validates the precision and scale
+            DataType::decimal(precision, scale)?;
+            Scalar::Decimal(i128::from_le_bytes(bytes), precision, scale)
+        }
+        LiteralType::Null(null_type) => Scalar::Null(to_kernel_type(null_type)?),
+        _ => {
+            return Err(Error::unsupported(format!(
+                "Substrait literal {literal_type:?} has no kernel equivalent"
+            )))
+        }
+    };
+    Ok(scalar)
+}
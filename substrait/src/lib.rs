@@ -0,0 +1,196 @@
+//! Convert between [Substrait](https://substrait.io) expressions and delta kernel expressions.
+//!
+//! Engines which already represent their predicates as Substrait expressions can push them into
+//! kernel scans without translating them by hand: [`from_substrait`] translates the expressions of
+//! a Substrait [`ExtendedExpression`] into kernel [`Expression`]s, resolving its field references
+//! against its base schema, and [`to_substrait`] does the reverse, for the expressions over a
+//! kernel schema.
+//!
+//! Only a subset of Substrait has a kernel equivalent: literals of the Delta primitive types,
+//! references to (possibly nested) struct fields, `IN` lists, and calls of these standard
+//! Substrait functions:
+//!
+//! | Extension                   | Functions                                                  |
+//! |-----------------------------|------------------------------------------------------------|
+//! | `functions_boolean.yaml`    | `and`, `or`, `not`                                         |
+//! | `functions_comparison.yaml` | `equal`, `not_equal`, `lt`, `lte`, `gt`, `gte`,            |
+//! |                             | `is_null`, `is_not_null`, `nullif`, `coalesce`,            |
+//! |                             | `is_distinct_from`, `is_not_distinct_from`                 |
+//! | `functions_arithmetic.yaml` | `add`, `subtract`, `multiply`, `divide`                    |
+//!
+//! Anything else is an error, so that a predicate is never silently changed by the translation.
+//!
+//! [`ExtendedExpression`]: substrait::proto::ExtendedExpression
+//! [`Expression`]: delta_kernel::expressions::Expression
+
+use delta_kernel::expressions::{BinaryOperator, BinaryOperator as Op};
+
+mod consumer;
+mod producer;
+mod types;
+
+pub use consumer::from_substrait;
+pub use producer::to_substrait;
+
+const BOOLEAN: &str =
+    "https://github.com/substrait-io/substrait/blob/main/extensions/functions_boolean.yaml";
+const COMPARISON: &str =
+    "https://github.com/substrait-io/substrait/blob/main/extensions/functions_comparison.yaml";
+const ARITHMETIC: &str =
+    "https://github.com/substrait-io/substrait/blob/main/extensions/functions_arithmetic.yaml";
+
+/// A Substrait function with a kernel equivalent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Binary(BinaryOperator),
+    IsNotDistinctFrom,
+    Not,
+    IsNull,
+    IsNotNull,
+    And,
+    Or,
+    Coalesce,
+}
+
+/// The extension URI and name of each Substrait function with a kernel equivalent.
+const FUNCTIONS: &[(&str, &str, Function)] = &[
+    (BOOLEAN, "and", Function::And),
+    (BOOLEAN, "or", Function::Or),
+    (BOOLEAN, "not", Function::Not),
+    (COMPARISON, "equal", Function::Binary(Op::Equal)),
+    (COMPARISON, "not_equal", Function::Binary(Op::NotEqual)),
+    (COMPARISON, "lt", Function::Binary(Op::LessThan)),
+    (COMPARISON, "lte", Function::Binary(Op::LessThanOrEqual)),
+    (COMPARISON, "gt", Function::Binary(Op::GreaterThan)),
+    (COMPARISON, "gte", Function::Binary(Op::GreaterThanOrEqual)),
+    (
+        COMPARISON,
+        "is_distinct_from",
+        Function::Binary(Op::Distinct),
+    ),
+    (
+        COMPARISON,
+        "is_not_distinct_from",
+        Function::IsNotDistinctFrom,
+    ),
+    (COMPARISON, "is_null", Function::IsNull),
+    (COMPARISON, "is_not_null", Function::IsNotNull),
+    (COMPARISON, "nullif", Function::Binary(Op::NullIf)),
+    (COMPARISON, "coalesce", Function::Coalesce),
+    (ARITHMETIC, "add", Function::Binary(Op::Plus)),
+    (ARITHMETIC, "subtract", Function::Binary(Op::Minus)),
+    (ARITHMETIC, "multiply", Function::Binary(Op::Multiply)),
+    (ARITHMETIC, "divide", Function::Binary(Op::Divide)),
+];
+
+impl Function {
+    /// The function declared with the given extension URI and (possibly compound) name, if it has
+    /// a kernel equivalent. Producers don't agree on the location of the standard extensions, so
+    /// only the file names of the URIs are compared.
+    fn resolve(uri: &str, name: &str) -> Option<Self> {
+        let file_name = |uri: &str| uri.rsplit('/').next().unwrap_or(uri).to_string();
+        // a compound name has the signature of the function after its name: `equal:any_any`
+        let name = name.split(':').next().unwrap_or(name);
+        FUNCTIONS
+            .iter()
+            .find(|(u, n, _)| *n == name && file_name(u) == file_name(uri))
+            .map(|(_, _, function)| *function)
+    }
+
+    /// The function which a call of `op` translates into, if any.
+    fn from_binary(op: BinaryOperator) -> Option<Self> {
+        let function = Function::Binary(op);
+        FUNCTIONS
+            .iter()
+            .any(|(_, _, f)| *f == function)
+            .then_some(function)
+    }
+
+    fn uri(self) -> &'static str {
+        self.entry().0
+    }
+
+    fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    fn entry(self) -> &'static (&'static str, &'static str, Function) {
+        // Functions are only ever made from the entries of `FUNCTIONS`
+        FUNCTIONS.iter().find(|(_, _, f)| *f == self).unwrap()
+    }
+
+    /// Whether calls of the function are predicates.
+    fn returns_boolean(self) -> bool {
+        use BinaryOperator::*;
+        !matches!(
+            self,
+            Function::Coalesce | Function::Binary(Plus | Minus | Multiply | Divide | NullIf)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use delta_kernel::expressions::{column_expr, Expression, Scalar};
+    use delta_kernel::schema::{DataType, StructField, StructType};
+
+    use super::*;
+
+    fn schema() -> StructType {
+        let nested = StructType::new([
+            StructField::new("x", DataType::LONG, true),
+            StructField::new("y", DataType::STRING, true),
+        ]);
+        StructType::new([
+            StructField::new("a", DataType::INTEGER, false),
+            StructField::new("s", nested, true),
+            StructField::new("d", DataType::decimal(10, 2).unwrap(), true),
+        ])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let exprs = [
+            column_expr!("a").gt(Expression::literal(1)).and(
+                column_expr!("s.y")
+                    .is_null()
+                    .or(column_expr!("s.x").eq(Scalar::Long(3))),
+            ),
+            Expression::coalesce([
+                column_expr!("s.x"),
+                Expression::null_literal(DataType::LONG),
+            ])
+            .distinct(column_expr!("s.x")),
+            column_expr!("d").le(Scalar::Decimal(-12345, 10, 2)),
+        ];
+        let extended = to_substrait(&exprs, &schema()).unwrap();
+        assert_eq!(extended.referred_expr.len(), 3);
+        assert_eq!(
+            extended.base_schema.as_ref().unwrap().names,
+            ["a", "s", "x", "y", "d"]
+        );
+        assert_eq!(from_substrait(&extended).unwrap(), exprs);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let expr = Expression::struct_from([column_expr!("a")]);
+        assert!(to_substrait(&[expr], &schema()).is_err());
+        assert!(to_substrait(&[column_expr!("s.z")], &schema()).is_err());
+
+        // calls of functions declared with unknown extensions can't be translated
+        let mut extended = to_substrait(&[column_expr!("a").is_null()], &schema()).unwrap();
+        extended.extension_uris[0].uri = "https://example.com/functions.yaml".to_string();
+        assert!(from_substrait(&extended).is_err());
+    }
+
+    #[test]
+    fn test_resolve_function() {
+        let uri = "/extensions/functions_comparison.yaml";
+        let lt = Function::Binary(BinaryOperator::LessThan);
+        assert_eq!(Function::resolve(uri, "lt:any_any"), Some(lt));
+        assert_eq!(Function::resolve(uri, "lt"), Some(lt));
+        assert_eq!(Function::resolve(uri, "add"), None);
+        assert_eq!(Function::from_binary(BinaryOperator::In), None);
+    }
+}
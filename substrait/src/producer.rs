@@ -0,0 +1,243 @@
+//! Translation of kernel expressions into Substrait expressions.
+
+use delta_kernel::expressions::{
+    BinaryExpression, ColumnName, Expression, Scalar, UnaryExpression, UnaryOperator,
+    VariadicExpression, VariadicOperator,
+};
+use delta_kernel::schema::{DataType, StructType};
+use delta_kernel::{DeltaResult, Error};
+use substrait::proto::expression::field_reference::{ReferenceType, RootReference, RootType};
+use substrait::proto::expression::literal::{self, LiteralType, PrecisionTimestamp};
+use substrait::proto::expression::reference_segment::{self, StructField};
+use substrait::proto::expression::{
+    FieldReference, Literal, ReferenceSegment, RexType, ScalarFunction,
+};
+use substrait::proto::expression_reference::ExprType;
+use substrait::proto::extensions::simple_extension_declaration::{ExtensionFunction, MappingType};
+use substrait::proto::extensions::{SimpleExtensionDeclaration, SimpleExtensionUri};
+use substrait::proto::function_argument::ArgType;
+use substrait::proto::{
+    Expression as SubstraitExpression, ExpressionReference, ExtendedExpression, FunctionArgument,
+};
+
+use crate::types::{to_named_struct, to_substrait_type, TIMESTAMP_PRECISION};
+use crate::Function;
+
+/// Translate kernel expressions over the columns of `schema` into the expressions of a Substrait
+/// [`ExtendedExpression`], in order, with `schema` as its base schema. The expressions are named
+/// `expr_0`, `expr_1`, etc.
+///
+/// Returns an error if an expression (or any of its children) has no Substrait equivalent in the
+/// supported subset, or references a column which isn't in `schema`.
+pub fn to_substrait(exprs: &[Expression], schema: &StructType) -> DeltaResult<ExtendedExpression> {
+    let mut producer = Producer {
+        schema,
+        functions: vec![],
+    };
+    let referred_expr = exprs
+        .iter()
+        .enumerate()
+        .map(|(index, expr)| {
+            Ok(ExpressionReference {
+                output_names: vec![format!("expr_{index}")],
+                expr_type: Some(ExprType::Expression(
+                    producer.to_substrait_expression(expr)?,
+                )),
+            })
+        })
+        .collect::<DeltaResult<_>>()?;
+
+    // Anchors are 1-based: 0 is the default value of a protobuf field, so it looks unset
+    let mut uris: Vec<&str> = vec![];
+    let mut extensions = vec![];
+    for (index, function) in producer.functions.iter().enumerate() {
+        let uri_index = match uris.iter().position(|uri| *uri == function.uri()) {
+            Some(uri_index) => uri_index,
+            None => {
+                uris.push(function.uri());
+                uris.len() - 1
+            }
+        };
+        let function = ExtensionFunction {
+            extension_uri_reference: uri_index as u32 + 1,
+            function_anchor: index as u32 + 1,
+            name: function.name().to_string(),
+        };
+        extensions.push(SimpleExtensionDeclaration {
+            mapping_type: Some(MappingType::ExtensionFunction(function)),
+        });
+    }
+    let extension_uris = uris
+        .into_iter()
+        .enumerate()
+        .map(|(index, uri)| SimpleExtensionUri {
+            extension_uri_anchor: index as u32 + 1,
+            uri: uri.to_string(),
+        })
+        .collect();
+
+    Ok(ExtendedExpression {
+        extension_uris,
+        extensions,
+        referred_expr,
+        base_schema: Some(to_named_struct(schema)?),
+        ..Default::default()
+    })
+}
+
+/// The state needed to translate kernel expressions: the schema their columns are resolved
+/// against, and the functions called so far (whose anchors are their 1-based positions).
+struct Producer<'a> {
+    schema: &'a StructType,
+    functions: Vec<Function>,
+}
+
+impl Producer<'_> {
+    fn to_substrait_expression(&mut self, expr: &Expression) -> DeltaResult<SubstraitExpression> {
+        let rex_type = match expr {
+            Expression::Literal(scalar) => RexType::Literal(to_substrait_literal(scalar)?),
+            Expression::Column(name) => {
+                RexType::Selection(Box::new(self.to_field_reference(name)?))
+            }
+            Expression::Unary(UnaryExpression { op, expr }) => {
+                let function = match op {
+                    UnaryOperator::Not => Function::Not,
+                    UnaryOperator::IsNull => Function::IsNull,
+                };
+                self.to_substrait_call(function, [expr.as_ref()])?
+            }
+            Expression::Binary(BinaryExpression { op, left, right }) => {
+                let function = Function::from_binary(*op).ok_or_else(|| {
+                    Error::unsupported(format!("Binary operator {op} has no Substrait equivalent"))
+                })?;
+                self.to_substrait_call(function, [left.as_ref(), right.as_ref()])?
+            }
+            Expression::Variadic(VariadicExpression { op, exprs }) => {
+                let function = match op {
+                    VariadicOperator::And => Function::And,
+                    VariadicOperator::Or => Function::Or,
+                    VariadicOperator::Coalesce => Function::Coalesce,
+                };
+                self.to_substrait_call(function, exprs.iter().map(AsRef::as_ref))?
+            }
+            Expression::Struct(_) => {
+                return Err(Error::unsupported(
+                    "Struct expressions have no Substrait equivalent",
+                ))
+            }
+        };
+        Ok(SubstraitExpression {
+            rex_type: Some(rex_type),
+        })
+    }
+
+    fn to_substrait_call<'e>(
+        &mut self,
+        function: Function,
+        args: impl IntoIterator<Item = &'e Expression>,
+    ) -> DeltaResult<RexType> {
+        let arguments = args
+            .into_iter()
+            .map(|arg| {
+                Ok(FunctionArgument {
+                    arg_type: Some(ArgType::Value(self.to_substrait_expression(arg)?)),
+                })
+            })
+            .collect::<DeltaResult<_>>()?;
+        let output_type = function
+            .returns_boolean()
+            .then(|| to_substrait_type(&DataType::BOOLEAN, true))
+            .transpose()?;
+        Ok(RexType::ScalarFunction(ScalarFunction {
+            function_reference: self.function_anchor(function),
+            arguments,
+            output_type,
+            ..Default::default()
+        }))
+    }
+
+    fn function_anchor(&mut self, function: Function) -> u32 {
+        let index = match self.functions.iter().position(|f| *f == function) {
+            Some(index) => index,
+            None => {
+                self.functions.push(function);
+                self.functions.len() - 1
+            }
+        };
+        index as u32 + 1
+    }
+
+    /// Resolve a (possibly nested) column into a reference to a field of the schema, by the
+    /// positions of the fields on its path.
+    fn to_field_reference(&self, name: &ColumnName) -> DeltaResult<FieldReference> {
+        let mut positions = vec![];
+        let mut schema = Some(self.schema);
+        for field_name in name.iter() {
+            let (index, field) = schema
+                .and_then(|schema| Some((schema.index_of(field_name)?, schema.field(field_name)?)))
+                .ok_or_else(|| Error::missing_column(name))?;
+            positions.push(index as i32);
+            schema = match field.data_type() {
+                DataType::Struct(struct_type) => Some(struct_type.as_ref()),
+                _ => None,
+            };
+        }
+        let segment = positions
+            .into_iter()
+            .rev()
+            .fold(None, |child, field| {
+                Some(ReferenceSegment {
+                    reference_type: Some(reference_segment::ReferenceType::StructField(Box::new(
+                        StructField {
+                            field,
+                            child: child.map(Box::new),
+                        },
+                    ))),
+                })
+            })
+            .ok_or_else(|| Error::invalid_expression("Empty column name"))?;
+        Ok(FieldReference {
+            reference_type: Some(ReferenceType::DirectReference(segment)),
+            root_type: Some(RootType::RootReference(RootReference {})),
+        })
+    }
+}
+
+fn to_substrait_literal(scalar: &Scalar) -> DeltaResult<Literal> {
+    let literal_type = match scalar {
+        Scalar::Integer(value) => LiteralType::I32(*value),
+        Scalar::Long(value) => LiteralType::I64(*value),
+        Scalar::Short(value) => LiteralType::I16((*value).into()),
+        Scalar::Byte(value) => LiteralType::I8((*value).into()),
+        Scalar::Float(value) => LiteralType::Fp32(*value),
+        Scalar::Double(value) => LiteralType::Fp64(*value),
+        Scalar::String(value) => LiteralType::String(value.clone()),
+        Scalar::Boolean(value) => LiteralType::Boolean(*value),
+        Scalar::Timestamp(value) => LiteralType::PrecisionTimestampTz(PrecisionTimestamp {
+            precision: TIMESTAMP_PRECISION,
+            value: *value,
+        }),
+        Scalar::TimestampNtz(value) => LiteralType::PrecisionTimestamp(PrecisionTimestamp {
+            precision: TIMESTAMP_PRECISION,
+            value: *value,
+        }),
+        Scalar::Date(value) => LiteralType::Date(*value),
+        Scalar::Binary(value) => LiteralType::Binary(value.clone()),
+        Scalar::Decimal(value, precision, scale) => LiteralType::Decimal(literal::Decimal {
+            value: value.to_le_bytes().to_vec(),
+            precision: *precision as i32,
+            scale: *scale as i32,
+        }),
+        Scalar::Null(data_type) => LiteralType::Null(to_substrait_type(data_type, true)?),
+        Scalar::Struct(_) | Scalar::Array(_) => {
+            return Err(Error::unsupported(format!(
+                "Literal {scalar} has no Substrait equivalent"
+            )))
+        }
+    };
+    Ok(Literal {
+        nullable: scalar.is_null(),
+        literal_type: Some(literal_type),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,223 @@
+//! Translation between Substrait types and kernel data types.
+
+use delta_kernel::schema::{ArrayType, DataType, MapType, PrimitiveType, StructType};
+use delta_kernel::{DeltaResult, Error};
+use substrait::proto::r#type::{self, Kind, Nullability};
+use substrait::proto::{NamedStruct, Type};
+
+/// The precision of Delta timestamps: microseconds.
+pub(crate) const TIMESTAMP_PRECISION: i32 = 6;
+
+fn nullability(nullable: bool) -> i32 {
+    match nullable {
+        true => Nullability::Nullable as i32,
+        false => Nullability::Required as i32,
+    }
+}
+
+/// Translate a kernel data type into a Substrait type.
+pub(crate) fn to_substrait_type(data_type: &DataType, nullable: bool) -> DeltaResult<Type> {
+    let nullability = nullability(nullable);
+    let kind = match data_type {
+        DataType::Primitive(primitive) => match primitive {
+            PrimitiveType::String => Kind::String(r#type::String {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Long => Kind::I64(r#type::I64 {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Integer => Kind::I32(r#type::I32 {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Short => Kind::I16(r#type::I16 {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Byte => Kind::I8(r#type::I8 {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Float => Kind::Fp32(r#type::Fp32 {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Double => Kind::Fp64(r#type::Fp64 {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Boolean => Kind::Bool(r#type::Boolean {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Binary => Kind::Binary(r#type::Binary {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Date => Kind::Date(r#type::Date {
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Timestamp => Kind::PrecisionTimestampTz(r#type::PrecisionTimestampTz {
+                precision: TIMESTAMP_PRECISION,
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::TimestampNtz => Kind::PrecisionTimestamp(r#type::PrecisionTimestamp {
+                precision: TIMESTAMP_PRECISION,
+                nullability,
+                ..Default::default()
+            }),
+            PrimitiveType::Decimal(precision, scale) => Kind::Decimal(r#type::Decimal {
+                precision: *precision as i32,
+                scale: *scale as i32,
+                nullability,
+                ..Default::default()
+            }),
+        },
+        DataType::Array(array) => Kind::List(Box::new(r#type::List {
+            r#type: Some(Box::new(to_substrait_type(
+                array.element_type(),
+                array.contains_null(),
+            )?)),
+            nullability,
+            ..Default::default()
+        })),
+        DataType::Map(map) => Kind::Map(Box::new(r#type::Map {
+            key: Some(Box::new(to_substrait_type(map.key_type(), false)?)),
+            value: Some(Box::new(to_substrait_type(
+                map.value_type(),
+                map.value_contains_null(),
+            )?)),
+            nullability,
+            ..Default::default()
+        })),
+        DataType::Struct(struct_type) | DataType::Variant(struct_type) => {
+            Kind::Struct(to_substrait_struct(struct_type, nullability)?)
+        }
+    };
+    Ok(Type { kind: Some(kind) })
+}
+
+fn to_substrait_struct(struct_type: &StructType, nullability: i32) -> DeltaResult<r#type::Struct> {
+    let types = struct_type
+        .fields()
+        .map(|field| to_substrait_type(field.data_type(), field.is_nullable()))
+        .collect::<DeltaResult<_>>()?;
+    Ok(r#type::Struct {
+        types,
+        nullability,
+        ..Default::default()
+    })
+}
+
+/// Translate a kernel schema into a Substrait [`NamedStruct`], whose names are the names of all
+/// the (possibly nested) fields of the schema, in depth-first order.
+pub(crate) fn to_named_struct(schema: &StructType) -> DeltaResult<NamedStruct> {
+    fn collect_names(data_type: &DataType, names: &mut Vec<String>) {
+        match data_type {
+            DataType::Struct(struct_type) | DataType::Variant(struct_type) => {
+                for field in struct_type.fields() {
+                    names.push(field.name().clone());
+                    collect_names(field.data_type(), names);
+                }
+            }
+            DataType::Array(array) => collect_names(array.element_type(), names),
+            DataType::Map(map) => {
+                collect_names(map.key_type(), names);
+                collect_names(map.value_type(), names);
+            }
+            DataType::Primitive(_) => {}
+        }
+    }
+    let mut names = vec![];
+    collect_names(&DataType::Struct(Box::new(schema.clone())), &mut names);
+    Ok(NamedStruct {
+        names,
+        r#struct: Some(to_substrait_struct(schema, Nullability::Required as i32)?),
+    })
+}
+
+/// Translate a Substrait type into a kernel data type. Struct types have no field names in
+/// Substrait, so they can't be translated.
+pub(crate) fn to_kernel_type(substrait_type: &Type) -> DeltaResult<DataType> {
+    let kind = substrait_type
+        .kind
+        .as_ref()
+        .ok_or_else(|| Error::invalid_expression("Substrait type without a kind"))?;
+    let data_type = match kind {
+        Kind::Bool(_) => DataType::BOOLEAN,
+        Kind::I8(_) => DataType::BYTE,
+        Kind::I16(_) => DataType::SHORT,
+        Kind::I32(_) => DataType::INTEGER,
+        Kind::I64(_) => DataType::LONG,
+        Kind::Fp32(_) => DataType::FLOAT,
+        Kind::Fp64(_) => DataType::DOUBLE,
+        Kind::String(_) => DataType::STRING,
+        Kind::Binary(_) => DataType::BINARY,
+        Kind::Date(_) => DataType::DATE,
+        Kind::PrecisionTimestamp(t) if t.precision == TIMESTAMP_PRECISION => {
+            DataType::TIMESTAMP_NTZ
+        }
+        Kind::PrecisionTimestampTz(t) if t.precision == TIMESTAMP_PRECISION => DataType::TIMESTAMP,
+        Kind::Decimal(decimal) => {
+            let precision = u8::try_from(decimal.precision).map_err(Error::generic_err)?;
+            let scale = u8::try_from(decimal.scale).map_err(Error::generic_err)?;
+            DataType::decimal(precision, scale)?
+        }
+        Kind::List(list) => {
+            let element = list
+                .r#type
+                .as_deref()
+                .ok_or_else(|| Error::invalid_expression("Substrait list without a type"))?;
+            let contains_null = element_nullable(element);
+            ArrayType::new(to_kernel_type(element)?, contains_null).into()
+        }
+        Kind::Map(map) => {
+            let (Some(key), Some(value)) = (map.key.as_deref(), map.value.as_deref()) else {
+                return Err(Error::invalid_expression(
+                    "Substrait map without a key or value type",
+                ));
+            };
+            let value_contains_null = element_nullable(value);
+            MapType::new(
+                to_kernel_type(key)?,
+                to_kernel_type(value)?,
+                value_contains_null,
+            )
+            .into()
+        }
+        _ => {
+            return Err(Error::unsupported(format!(
+                "Substrait type {kind:?} has no kernel equivalent"
+            )))
+        }
+    };
+    Ok(data_type)
+}
+
+/// Whether values of the (element) type may be null.
+fn element_nullable(substrait_type: &Type) -> bool {
+    let nullability = match &substrait_type.kind {
+        Some(Kind::Bool(t)) => t.nullability,
+        Some(Kind::I8(t)) => t.nullability,
+        Some(Kind::I16(t)) => t.nullability,
+        Some(Kind::I32(t)) => t.nullability,
+        Some(Kind::I64(t)) => t.nullability,
+        Some(Kind::Fp32(t)) => t.nullability,
+        Some(Kind::Fp64(t)) => t.nullability,
+        Some(Kind::String(t)) => t.nullability,
+        Some(Kind::Binary(t)) => t.nullability,
+        Some(Kind::Date(t)) => t.nullability,
+        Some(Kind::PrecisionTimestamp(t)) => t.nullability,
+        Some(Kind::PrecisionTimestampTz(t)) => t.nullability,
+        Some(Kind::Decimal(t)) => t.nullability,
+        Some(Kind::List(t)) => t.nullability,
+        Some(Kind::Map(t)) => t.nullability,
+        Some(Kind::Struct(t)) => t.nullability,
+        _ => Nullability::Nullable as i32,
+    };
+    nullability != Nullability::Required as i32
+}
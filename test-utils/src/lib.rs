@@ -106,3 +106,65 @@ pub fn into_record_batch(engine_data: Box<dyn EngineData>) -> RecordBatch {
         .unwrap()
         .into()
 }
+
+/// Serve the files under `root` over plain HTTP on a local port, returning the base URL of the
+/// server (with a trailing `/`). The server supports `GET` (including single byte ranges) and
+/// `HEAD` requests, and runs on background threads until the process exits.
+pub fn serve_files(root: std::path::PathBuf) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let root = root.clone();
+            std::thread::spawn(move || serve_file(&root, stream));
+        }
+    });
+    base_url
+}
+
+// Respond to a single request for a file under `root`, then close the connection
+fn serve_file(root: &std::path::Path, mut stream: std::net::TcpStream) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+    let request = lines.next().unwrap().unwrap();
+    let mut parts = request.split(' ');
+    let (method, path) = (parts.next().unwrap(), parts.next().unwrap());
+    let mut range = None;
+    for line in lines
+        .map_while(Result::ok)
+        .take_while(|line| !line.is_empty())
+    {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("range") {
+                let (start, end) = value
+                    .trim()
+                    .trim_start_matches("bytes=")
+                    .split_once('-')
+                    .unwrap();
+                range = Some((
+                    start.parse::<usize>().unwrap(),
+                    end.parse::<usize>().unwrap() + 1,
+                ));
+            }
+        }
+    }
+    let (status, body) = match std::fs::read(root.join(path.trim_start_matches('/'))) {
+        Ok(data) => match range {
+            Some((start, end)) => (
+                "206 Partial Content",
+                data[start..end.min(data.len())].to_vec(),
+            ),
+            None => ("200 OK", data),
+        },
+        Err(_) => ("404 Not Found", vec![]),
+    };
+    let header = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nLast-Modified: Wed, 21 Oct 2015 07:28:00 GMT\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).unwrap();
+    if method != "HEAD" {
+        stream.write_all(&body).unwrap();
+    }
+}